@@ -1,4 +1,5 @@
 // src/lib.rs - Library interface for testing
+pub mod app;
 pub mod core;
 pub mod github;
 pub mod nexus;
@@ -7,6 +8,7 @@ pub mod orchestration;
 pub mod utils;
 pub mod ui;
 
+pub use app::AppContext;
 pub use core::{AccountManager, BillingMonitor, ProxyManager, StateManager};
-pub use github::{GitHubClient, ForkManager, SecretsManager, WorkflowController};
+pub use github::{GitHubApi, GitHubClient, ForkManager, SecretsManager, WorkflowController};
 pub use nexus::{NexusConfig, NexusValidator};
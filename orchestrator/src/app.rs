@@ -0,0 +1,197 @@
+// src/app.rs - Per-process application context
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use crate::core::{settings, AccountManager, Paths, ProxyManager, Settings, StateManager};
+use crate::github::SecretVault;
+use crate::monitor::{AlertManager, HealthMonitor};
+use crate::orchestration::forks;
+
+/// What every command works against, built once in main: the config
+/// directory, settings and state manager, plus accounts, proxies, the billing
+/// monitor, alerts and the secret vault, each loaded from disk on first use and then shared.
+/// Accounts and proxies are read again after `reload_accounts`.
+pub struct AppContext {
+    paths: Paths,
+    settings: &'static Settings,
+    state: Arc<StateManager>,
+    accounts: RwLock<Option<Arc<AccountManager>>>,
+    proxies: RwLock<Option<Arc<ProxyManager>>>,
+    health: OnceLock<HealthMonitor>,
+    alerts: OnceLock<AlertManager>,
+    vault: OnceLock<Arc<Mutex<SecretVault>>>,
+}
+
+impl AppContext {
+    pub fn new(config_dir: &Path) -> Result<Self> {
+        let paths = Paths::new(config_dir);
+        
+        Ok(Self {
+            settings: settings::get(),
+            state: Arc::new(StateManager::new(&paths)?),
+            paths,
+            accounts: RwLock::new(None),
+            proxies: RwLock::new(None),
+            health: OnceLock::new(),
+            alerts: OnceLock::new(),
+            vault: OnceLock::new(),
+        })
+    }
+    
+    pub fn paths(&self) -> &Paths {
+        &self.paths
+    }
+    
+    pub fn config_dir(&self) -> &Path {
+        self.paths.config_dir()
+    }
+    
+    pub fn cache_dir(&self) -> &Path {
+        self.paths.cache_dir()
+    }
+    
+    pub fn settings(&self) -> &'static Settings {
+        self.settings
+    }
+    
+    pub fn state(&self) -> Arc<StateManager> {
+        self.state.clone()
+    }
+    
+    /// Accounts from tokens.txt.
+    pub fn accounts(&self) -> Result<Arc<AccountManager>> {
+        load_once(&self.accounts, || {
+            let mut accounts = AccountManager::new(&self.paths);
+            accounts.load_tokens(&self.paths.tokens_file())?;
+            accounts.set_fork_organization(forks::fork_organization(&self.paths)?.as_deref());
+            Ok(accounts)
+        })
+    }
+    
+    /// Token-proxy mappings from proxies.txt, or from the mapping cache when
+    /// there is no proxies.txt.
+    pub fn proxies(&self) -> Result<Arc<ProxyManager>> {
+        load_once(&self.proxies, || {
+            let mut proxies = ProxyManager::new(&self.paths);
+            let proxies_file = self.paths.proxies_file();
+            
+            if proxies_file.exists() {
+                let tokens: Vec<String> = self.accounts()?
+                    .get_all_accounts()
+                    .iter()
+                    .map(|a| a.token.clone())
+                    .collect();
+                proxies.load_from_file(&proxies_file, &tokens)?;
+            } else {
+                proxies.load_cache().ok();
+            }
+            Ok(proxies)
+        })
+    }
+    
+    /// Drops the loaded accounts and proxy mappings, so the next use reads
+    /// tokens.txt again. Call after tokens.txt or the token cache changed
+    /// underneath this context.
+    pub fn reload_accounts(&self) {
+        *self.accounts.write().unwrap() = None;
+        *self.proxies.write().unwrap() = None;
+    }
+    
+    /// Proxy URL mapped to `token`, if any.
+    pub fn proxy_url(&self, token: &str) -> Result<Option<String>> {
+        Ok(self.proxies()?.get_proxy(token).map(|p| p.to_curl_format()))
+    }
+    
+    /// Billing checks and their cache.
+    pub fn health(&self) -> Result<&HealthMonitor> {
+        if let Some(health) = self.health.get() {
+            return Ok(health);
+        }
+        
+        let health = HealthMonitor::new(&self.paths)?;
+        Ok(self.health.get_or_init(|| health))
+    }
+    
+    pub fn alerts(&self) -> Result<&AlertManager> {
+        if let Some(alerts) = self.alerts.get() {
+            return Ok(alerts);
+        }
+        
+        let alerts = AlertManager::new(&self.alerts_file())?;
+        Ok(self.alerts.get_or_init(|| alerts))
+    }
+    
+    /// The secret vault, shared so concurrent writers save each other's
+    /// entries too.
+    pub fn vault(&self) -> Result<Arc<Mutex<SecretVault>>> {
+        if let Some(vault) = self.vault.get() {
+            return Ok(vault.clone());
+        }
+        
+        let vault = Arc::new(Mutex::new(SecretVault::load(&self.paths.vault())?));
+        Ok(self.vault.get_or_init(|| vault).clone())
+    }
+    
+    pub fn alerts_file(&self) -> PathBuf {
+        self.settings.alerts_file(self.paths.config_dir())
+    }
+}
+
+/// The value in `slot`, loading it first when it is empty.
+fn load_once<T>(slot: &RwLock<Option<Arc<T>>>, load: impl FnOnce() -> Result<T>) -> Result<Arc<T>> {
+    if let Some(value) = slot.read().unwrap().as_ref() {
+        return Ok(value.clone());
+    }
+    
+    let value = Arc::new(load()?);
+    Ok(slot.write().unwrap().get_or_insert(value).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::state::OrchestratorState;
+    use std::fs;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_operations_share_one_state_and_account_load() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("tokens.txt"), "ghp_aaaa\nghp_bbbb\n").unwrap();
+        let ctx = AppContext::new(temp_dir.path()).unwrap();
+        
+        // Two operations in one process see the same state manager
+        assert!(Arc::ptr_eq(&ctx.state(), &ctx.state()));
+        
+        let state = OrchestratorState { total_accounts: 2, ..OrchestratorState::default() };
+        ctx.state().save_state(&state).unwrap();
+        assert_eq!(ctx.state().load_state().unwrap().total_accounts, 2);
+        
+        // tokens.txt is read once; later edits do not change this command's view
+        let first = ctx.accounts().unwrap();
+        fs::write(temp_dir.path().join("tokens.txt"), "ghp_cccc\n").unwrap();
+        assert!(Arc::ptr_eq(&ctx.accounts().unwrap(), &first));
+        assert_eq!(ctx.accounts().unwrap().total_accounts(), 2);
+        
+        // ...until the context is told to reload
+        ctx.reload_accounts();
+        assert_eq!(ctx.accounts().unwrap().total_accounts(), 1);
+        assert_eq!(first.total_accounts(), 2);
+    }
+    
+    #[test]
+    fn test_missing_files_load_lazily() {
+        let temp_dir = tempdir().unwrap();
+        let ctx = AppContext::new(temp_dir.path()).unwrap();
+        
+        // No proxies.txt: the (empty) cache is used and tokens.txt is not needed
+        assert_eq!(ctx.proxy_url("ghp_aaaa").unwrap(), None);
+        assert!(!ctx.alerts().unwrap().is_enabled());
+        
+        match ctx.accounts() {
+            Err(e) => assert!(e.to_string().contains("tokens.txt"), "{}", e),
+            Ok(_) => panic!("loaded accounts without tokens.txt"),
+        }
+    }
+}
@@ -0,0 +1,1112 @@
+// src/cli.rs - Command line interface (clap derive)
+
+use anyhow::{Result, Context};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use crate::orchestration::secrets::SecretSource;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "nexus-orchestrator",
+    about = "Multi-account GitHub Actions orchestrator for Nexus Network",
+    after_help = "Exit codes: 0 success, 1 failure, 2 partial failure, 3 config/usage error, \
+                  4 all accounts exhausted, 5 locked by another instance, 6 timed out",
+    disable_version_flag = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    
+    /// Configuration directory (relative paths resolve against the working directory)
+    #[arg(long, global = true, env = "NEXUS_CONFIG_DIR", default_value = "config", value_name = "PATH")]
+    pub config_dir: PathBuf,
+    
+    /// Log level filter (error, warn, info, debug, trace); overrides RUST_LOG
+    #[arg(long, global = true, value_name = "LEVEL")]
+    pub log_level: Option<String>,
+    
+    /// Disable colored output
+    #[arg(long, global = true)]
+    pub no_color: bool,
+    
+    /// Answer "yes" to every confirmation prompt
+    #[arg(short = 'y', long, global = true)]
+    pub yes: bool,
+    
+    /// Show version
+    #[arg(short = 'v', long = "version")]
+    pub version: bool,
+}
+
+impl Cli {
+    pub fn resolved_config_dir(&self) -> Result<PathBuf> {
+        if self.config_dir.is_absolute() {
+            return Ok(self.config_dir.clone());
+        }
+        
+        let cwd = std::env::current_dir()
+            .context("Failed to determine current directory")?;
+        
+        Ok(cwd.join(&self.config_dir))
+    }
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum Command {
+    /// Show orchestrator status
+    Status {
+        /// Print the raw state as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Also compare the chain with the forks GitHub lists
+        #[arg(long, conflicts_with = "json")]
+        reconcile: bool,
+        
+        /// Rebuild the state file from each account's fork on GitHub, backing up the old one
+        #[arg(long, conflicts_with = "reconcile")]
+        rebuild: bool,
+        
+        /// Parent repo for --rebuild (defaults to the main repo in setup.json)
+        #[arg(long, value_name = "OWNER/NAME", requires = "rebuild")]
+        parent: Option<String>,
+        
+        /// Also list the forks deleted so far, from cache/fork_archive.jsonl
+        #[arg(long, conflicts_with = "json")]
+        history: bool,
+    },
+    
+    /// Show billing for all accounts (exit 2 if some are exhausted, 4 if all are)
+    Billing {
+        /// Print per-account billing as a JSON array
+        #[arg(long, conflicts_with = "summary_json")]
+        json: bool,
+        
+        /// Print only the ok/warning/exhausted counts as JSON
+        #[arg(long)]
+        summary_json: bool,
+        
+        /// Only check this account (username or token index; repeatable)
+        #[arg(long = "account", value_name = "USERNAME|INDEX")]
+        accounts: Vec<String>,
+        
+        /// Bypass the billing cache and query GitHub
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// Delete or archive exhausted forks (or those matched by the filters)
+    Cleanup {
+        /// Fork status to clean up: exhausted, disabled or deleted (repeatable; default: exhausted)
+        #[arg(long = "status", value_name = "STATUS")]
+        statuses: Vec<String>,
+        
+        /// Only forks created longer ago than this, e.g. 36h, 7d
+        #[arg(long, value_name = "DURATION")]
+        older_than: Option<String>,
+        
+        /// Keep the N most recently created exhausted forks, which new chains fork from
+        #[arg(long, value_name = "N")]
+        keep_last: Option<usize>,
+        
+        /// Only forks owned by this account
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+        
+        /// Show what would be cleaned up, and which deletions the tokens would
+        /// fail, without writing to GitHub or state
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// delete removes the repos, archive makes them read-only
+        #[arg(long, value_name = "MODE", default_value = "delete")]
+        mode: String,
+        
+        /// Delete forks of the parent on managed accounts that the chain does not track
+        #[arg(long, conflicts_with_all = ["statuses", "older_than", "keep_last", "mode"])]
+        orphans: bool,
+        
+        /// Parent repo for --orphans (defaults to the main repo in setup.json)
+        #[arg(long, value_name = "OWNER/NAME", requires = "orphans")]
+        parent: Option<String>,
+    },
+    
+    /// Force account rotation
+    Rotate,
+    
+    /// Deploy the main workflow and set secrets on active forks
+    Deploy {
+        /// Only deploy the workflow file
+        #[arg(long, conflicts_with = "secrets_only")]
+        workflow_only: bool,
+        
+        /// Only set secrets on active forks
+        #[arg(long)]
+        secrets_only: bool,
+        
+        /// Rewrite every secret, even those the local manifest says are unchanged
+        #[arg(long, conflicts_with = "workflow_only")]
+        force: bool,
+        
+        /// Print the workflow with its placeholders filled in, without deploying anything
+        #[arg(long, conflicts_with_all = ["secrets_only", "force"])]
+        render_only: bool,
+        
+        /// Deploy only this managed workflow, by file name (repeatable; default: all)
+        #[arg(long = "workflow", value_name = "FILE", conflicts_with = "secrets_only")]
+        workflows: Vec<String>,
+        
+        /// Only deploy the workflows to chain forks whose copy is missing or differs from the local one
+        #[arg(long, conflicts_with_all = ["secrets_only", "force", "render_only"])]
+        only_drifted: bool,
+    },
+    
+    /// Delete the Nexus secrets from every chain fork still on GitHub, whatever its status
+    /// (asks first unless --yes; exit 2 if some forks fail)
+    ScrubSecrets,
+    
+    /// Dispatch the workflow on a fork (defaults to the active fork)
+    Trigger {
+        /// Target repository (owner/name)
+        #[arg(long, value_name = "OWNER/NAME")]
+        repo: Option<String>,
+        
+        /// Account whose token dispatches the run
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+        
+        /// Workflow file to dispatch (default: github.workflow_file)
+        #[arg(long, value_name = "FILE")]
+        workflow: Option<String>,
+        
+        /// workflow_dispatch input (repeatable)
+        #[arg(long = "input", value_name = "KEY=VALUE")]
+        inputs: Vec<String>,
+        
+        /// Wait for the run and print status changes (exit 0 success, 1 failure, 6 timeout)
+        #[arg(long)]
+        watch: bool,
+    },
+    
+    /// Download and unpack a run's artifacts into logs/artifacts/OWNER/NAME/RUN_ID
+    Artifacts {
+        /// Repository (owner/name; defaults to the active fork)
+        repo: Option<String>,
+        
+        /// Run to collect from (defaults to the newest completed run)
+        #[arg(long, value_name = "ID")]
+        run: Option<u64>,
+    },
+    
+    /// Show each job of a run and how many succeeded
+    /// (exit 0 when the run is healthy, see rotation.healthy_job_percent)
+    Jobs {
+        /// Repository (owner/name; defaults to the active fork)
+        repo: Option<String>,
+        
+        /// Run to break down (defaults to the newest completed run)
+        #[arg(long, value_name = "ID")]
+        run: Option<u64>,
+    },
+    
+    /// Re-run a failed workflow run, again while it keeps failing
+    /// (exit 0 once it succeeds, 1 if it still fails, 6 on timeout)
+    Rerun {
+        /// Repository (owner/name; defaults to the active fork)
+        repo: Option<String>,
+        
+        /// Run to re-run (defaults to the newest failed run)
+        #[arg(long, value_name = "ID")]
+        run: Option<u64>,
+        
+        /// Re-runs to try before giving up
+        #[arg(long, default_value_t = 1, value_name = "N")]
+        max_attempts: u32,
+        
+        /// Re-run every job, not only the failed ones
+        #[arg(long)]
+        all_jobs: bool,
+    },
+    
+    /// Fork chain operations
+    Fork {
+        #[command(subcommand)]
+        action: ForkAction,
+    },
+    
+    /// List, tail and search orchestrator logs, or fetch a failed run's logs from GitHub
+    #[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
+    Logs {
+        #[command(subcommand)]
+        action: Option<LogsAction>,
+        
+        /// Download and unpack the logs of the newest failed run in this repo into logs/runs
+        #[arg(long, value_name = "OWNER/NAME")]
+        run: Option<String>,
+    },
+    
+    /// Manage repository secrets (values come from a file, stdin or an env var, never from arguments)
+    Secrets {
+        #[command(subcommand)]
+        action: SecretsAction,
+    },
+    
+    /// Manage repository Actions variables (plain text, visible to workflows as vars.NAME)
+    Variables {
+        #[command(subcommand)]
+        action: VariablesAction,
+    },
+    
+    /// Add or remove tokens, keeping the fork chain's token indexes and caches in step
+    Token {
+        #[command(subcommand)]
+        action: TokenAction,
+    },
+    
+    /// Secrets kept encrypted in cache/vault.bin, written again to each fork that takes over
+    Vault {
+        #[command(subcommand)]
+        action: VaultAction,
+    },
+    
+    /// Validate tokens, proxies, nodes and wallets (exit 3 on errors; all categories if none given)
+    Validate {
+        /// Check tokens.txt
+        #[arg(long)]
+        tokens: bool,
+        
+        /// Check proxies.txt
+        #[arg(long)]
+        proxies: bool,
+        
+        /// Check nodes.txt and wallets.txt
+        #[arg(long)]
+        nodes: bool,
+        
+        /// Also contact GitHub and test proxy connectivity
+        #[arg(long)]
+        online: bool,
+        
+        /// Revalidate only the tokens whose last validation is older than
+        /// monitor.token_revalidate_hours (implies --tokens --online)
+        #[arg(long)]
+        stale_only: bool,
+        
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Write a per-token report to this file (CSV when it ends in .csv,
+        /// JSON otherwise), or print it with `json`/`csv`; implies --tokens --online
+        #[arg(long, value_name = "FILE|json|csv")]
+        output: Option<String>,
+    },
+    
+    /// Periodically check billing and rotate when exhausted
+    Watch {
+        /// Minutes between checks (defaults to monitor.watch_interval_minutes)
+        #[arg(long)]
+        interval: Option<u64>,
+    },
+    
+    /// Print a combined status and billing report
+    Report,
+    
+    /// Diagnose binaries, crypto, config files, state, clock and proxy (exit 1 on any failure)
+    Doctor,
+    
+    /// Show or check orchestrator.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    
+    /// Alert channel operations
+    Alert {
+        #[command(subcommand)]
+        action: AlertAction,
+    },
+    
+    /// Live status dashboard
+    Dashboard {
+        /// Refresh interval in seconds
+        refresh: Option<u64>,
+    },
+    
+    /// Refresh billing and run status of every fork in the chain from GitHub
+    Sync,
+    
+    /// Check each fork's token, repo, workflow and secrets on GitHub (exit 1 if the active fork fails a check)
+    Verify {
+        /// Instead, compare the workflows on every chain fork with the local ones (exit 1 if some differ)
+        #[arg(long)]
+        workflows: bool,
+    },
+    
+    /// GitHub API call latency recorded in logs/api_metrics.jsonl
+    Metrics {
+        /// Only calls made within this window, e.g. 30m or 2h
+        #[arg(long, default_value = "1h")]
+        since: String,
+        
+        /// Print the Prometheus text format instead of the per-account table
+        #[arg(long)]
+        prometheus: bool,
+    },
+    
+    /// Download and install a newer release of this binary
+    SelfUpdate {
+        /// Only report whether an update exists (exit 0 if one is available, 1 if up to date)
+        #[arg(long)]
+        check: bool,
+        
+        /// Install this release instead of the latest (downgrades allowed)
+        #[arg(long, value_name = "vX.Y.Z")]
+        version: Option<String>,
+    },
+    
+    /// Show version
+    Version,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ForkAction {
+    /// List forks in the chain
+    List {
+        /// Only these statuses, comma-separated (active, exhausted, disabled, source, deleted)
+        #[arg(long, value_name = "STATUS,...")]
+        status: Option<String>,
+        
+        /// Print the chain nodes as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Fork the parent repo into an account and add it to the chain
+    Create {
+        /// Account to fork into (defaults to the first account without a fork)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+        
+        /// Repo to fork (defaults to the newest chain fork, else the main repo)
+        #[arg(long, value_name = "OWNER/NAME")]
+        parent: Option<String>,
+    },
+    
+    /// Disable, cancel runs, delete and verify a fork
+    Delete {
+        /// Fork to delete (owner/name)
+        repo: String,
+        
+        /// Leave the chain node untouched
+        #[arg(long)]
+        keep_state: bool,
+        
+        /// Account that owns the fork (defaults to the chain node's account)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+    },
+    
+    /// Merge upstream into the default branch of forks
+    Sync {
+        /// Fork to sync (defaults to the active fork)
+        #[arg(conflicts_with = "all_active")]
+        repo: Option<String>,
+        
+        /// Sync every active fork
+        #[arg(long)]
+        all_active: bool,
+        
+        /// Account that owns the fork (defaults to the chain node's account)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum LogsAction {
+    /// List log files with sizes and ages
+    List,
+    
+    /// Print the last lines of a log file
+    Tail {
+        /// Log file name inside logs/ (".log" may be omitted)
+        #[arg(long, default_value = "orchestrator")]
+        file: String,
+        
+        /// Number of lines to print
+        #[arg(long, default_value_t = 100)]
+        lines: usize,
+        
+        /// Keep printing new lines (follows rotation)
+        #[arg(short = 'f', long)]
+        follow: bool,
+    },
+    
+    /// Print the log files of one workflow run
+    Show {
+        /// Workflow run id
+        run_id: u64,
+    },
+    
+    /// Search all log files for a substring
+    Grep {
+        /// Text to search for (case-sensitive)
+        pattern: String,
+        
+        /// Only lines logged within this window, e.g. 30m, 2h, 7d
+        #[arg(long, value_name = "DURATION")]
+        since: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum SecretsAction {
+    /// Create or update a secret
+    Set {
+        /// Repository (owner/name)
+        repo: String,
+        
+        /// Secret name
+        name: String,
+        
+        #[command(flatten)]
+        value: SecretValueArgs,
+        
+        /// Account whose token writes the secret (defaults to the fork's owner)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+        
+        /// Write to this GitHub Environment of the repo instead of the repo itself
+        #[arg(long, value_name = "NAME")]
+        environment: Option<String>,
+    },
+    
+    /// List secret names
+    List {
+        /// Repository (owner/name)
+        repo: String,
+        
+        /// Account whose token reads the secrets (defaults to the fork's owner)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+    },
+    
+    /// Delete a secret
+    Delete {
+        /// Repository (owner/name)
+        repo: String,
+        
+        /// Secret name
+        name: String,
+        
+        /// Account whose token deletes the secret (defaults to the fork's owner)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+    },
+    
+    /// Write every secret from a manifest (exit 2 if some writes fail)
+    Apply {
+        /// Manifest path (defaults to secrets.toml in the config directory)
+        #[arg(long, value_name = "PATH")]
+        manifest: Option<PathBuf>,
+        
+        /// Apply to every active fork instead of the current one
+        #[arg(long)]
+        all_active: bool,
+        
+        /// Account whose token writes the secrets (defaults to each fork's owner)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+    },
+    
+    /// Push config/secrets.env (secrets and `VAR_*`/`#@variable` variables) to a fork
+    ImportEnv {
+        /// Repository (owner/name); defaults to the active fork
+        repo: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum VariablesAction {
+    /// Create or update a variable
+    Set {
+        /// Repository (owner/name)
+        repo: String,
+        
+        /// Variable name
+        name: String,
+        
+        /// Variable value
+        value: String,
+        
+        /// Account whose token writes the variable (defaults to the fork's owner)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+    },
+    
+    /// Print a variable's value (exit 1 if it does not exist)
+    Get {
+        /// Repository (owner/name)
+        repo: String,
+        
+        /// Variable name
+        name: String,
+        
+        /// Account whose token reads the variable (defaults to the fork's owner)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+    },
+    
+    /// List variables with their values
+    List {
+        /// Repository (owner/name)
+        repo: String,
+        
+        /// Account whose token reads the variables (defaults to the fork's owner)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+    },
+    
+    /// Delete a variable
+    Delete {
+        /// Repository (owner/name)
+        repo: String,
+        
+        /// Variable name
+        name: String,
+        
+        /// Account whose token deletes the variable (defaults to the fork's owner)
+        #[arg(long, value_name = "USERNAME")]
+        account: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum TokenAction {
+    /// Check a token through the proxy it will be paired with, then append it to tokens.txt (exit 3 if the check fails)
+    Add {
+        /// The token (ghp_... or github_pat_...)
+        token: String,
+        
+        /// Organization the account's forks and billing belong to, as after a token in tokens.txt
+        #[arg(long, value_name = "LOGIN")]
+        owner: Option<String>,
+    },
+    
+    /// Remove a token; its forks leave the chain and later accounts move down one index
+    Remove {
+        /// Token index or username
+        account: String,
+        
+        /// Remove it even while it owns the active fork
+        #[arg(long)]
+        force: bool,
+    },
+    
+    /// Set an account aside: rotation, billing checks and deployments skip it, its index stays
+    Disable {
+        /// Token index or username
+        account: String,
+    },
+    
+    /// Put a disabled account back into use
+    Enable {
+        /// Token index or username
+        account: String,
+    },
+    
+    /// Set how early rotation takes an account: highest priority first, ties in index order
+    Priority {
+        /// Token index or username
+        account: String,
+        
+        /// 0 (the default) to 255
+        priority: u8,
+    },
+    
+    /// Name an account, e.g. after the email or provider behind it; shown in status, billing and validation
+    Label {
+        /// Token index or username
+        account: String,
+        
+        /// The label; empty to clear it
+        label: String,
+        
+        /// Longer notes kept with the label; empty to clear them
+        #[arg(long)]
+        notes: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum VaultAction {
+    /// List vaulted secret names, with the repo and time each was last set (never the values)
+    List,
+    
+    /// Stop carrying a secret over to new forks (exit 1 if it is not vaulted)
+    Forget {
+        /// Secret name
+        name: String,
+    },
+}
+
+#[derive(Args, Debug, PartialEq)]
+#[group(required = true, multiple = false)]
+pub struct SecretValueArgs {
+    /// Read the value from a file
+    #[arg(long, value_name = "PATH")]
+    value_file: Option<PathBuf>,
+    
+    /// Read the value from standard input
+    #[arg(long)]
+    stdin: bool,
+    
+    /// Read the value from an environment variable
+    #[arg(long, value_name = "VAR")]
+    value_env: Option<String>,
+}
+
+impl SecretValueArgs {
+    pub fn source(&self) -> SecretSource {
+        match (&self.value_file, &self.value_env) {
+            (Some(path), _) => SecretSource::File(path.clone()),
+            (None, Some(var)) => SecretSource::Env(var.clone()),
+            (None, None) => SecretSource::Stdin,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum ConfigAction {
+    /// Print the effective settings (file merged with defaults)
+    Show {
+        /// Print as JSON instead of TOML
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Check orchestrator.toml for unknown fields, bad types and out-of-range values (exit 3 on errors)
+    Validate,
+}
+
+#[derive(Subcommand, Debug, PartialEq)]
+pub enum AlertAction {
+    /// Send a test message through every configured channel
+    Test {
+        /// Message to send
+        #[arg(default_value = "Nexus orchestrator test alert")]
+        message: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+    use clap::CommandFactory;
+    
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("nexus-orchestrator").chain(args.iter().copied()))
+    }
+    
+    #[test]
+    fn test_no_arguments_starts_menu() {
+        let cli = parse(&[]).unwrap();
+        assert!(cli.command.is_none());
+        assert!(!cli.version);
+    }
+    
+    #[test]
+    fn test_existing_commands_parse() {
+        assert_eq!(parse(&["status"]).unwrap().command, Some(Command::Status { json: false, reconcile: false, rebuild: false, parent: None, history: false }));
+        assert_eq!(
+            parse(&["status", "--reconcile"]).unwrap().command,
+            Some(Command::Status { json: false, reconcile: true, rebuild: false, parent: None, history: false })
+        );
+        assert!(parse(&["status", "--json", "--reconcile"]).is_err());
+        assert_eq!(
+            parse(&["status", "--rebuild", "--parent", "origin/nexus-runner"]).unwrap().command,
+            Some(Command::Status { json: false, reconcile: false, rebuild: true, parent: Some("origin/nexus-runner".to_string()), history: false })
+        );
+        assert!(parse(&["status", "--rebuild", "--reconcile"]).is_err());
+        assert!(parse(&["status", "--parent", "origin/nexus-runner"]).is_err());
+        assert!(matches!(parse(&["status", "--history"]).unwrap().command, Some(Command::Status { history: true, .. })));
+        assert!(parse(&["status", "--json", "--history"]).is_err());
+        assert_eq!(
+            parse(&["billing"]).unwrap().command,
+            Some(Command::Billing { json: false, summary_json: false, accounts: vec![], force: false })
+        );
+        assert_eq!(
+            parse(&["cleanup"]).unwrap().command,
+            Some(Command::Cleanup {
+                statuses: vec![],
+                older_than: None,
+                keep_last: None,
+                account: None,
+                dry_run: false,
+                mode: "delete".to_string(),
+                orphans: false,
+                parent: None,
+            })
+        );
+        assert_eq!(parse(&["rotate"]).unwrap().command, Some(Command::Rotate));
+        assert_eq!(parse(&["version"]).unwrap().command, Some(Command::Version));
+        assert_eq!(
+            parse(&["dashboard", "10"]).unwrap().command,
+            Some(Command::Dashboard { refresh: Some(10) })
+        );
+        
+        assert!(parse(&["-v"]).unwrap().version);
+        assert!(parse(&["--version"]).unwrap().version);
+    }
+    
+    #[test]
+    fn test_global_flags() {
+        let cli = parse(&["status", "--json", "--no-color", "--log-level", "debug", "-y"]).unwrap();
+        
+        assert_eq!(cli.command, Some(Command::Status { json: true, reconcile: false, rebuild: false, parent: None, history: false }));
+        assert!(cli.no_color);
+        assert!(cli.yes);
+        assert_eq!(cli.log_level.as_deref(), Some("debug"));
+    }
+    
+    #[test]
+    fn test_config_dir_flag_and_env() {
+        let cli = parse(&["status"]).unwrap();
+        assert_eq!(cli.config_dir, PathBuf::from("config"));
+        
+        let cli = parse(&["status", "--config-dir", "/srv/nexus-prod"]).unwrap();
+        assert_eq!(cli.resolved_config_dir().unwrap(), PathBuf::from("/srv/nexus-prod"));
+        
+        let cli = parse(&["--config-dir", "envs/test", "billing"]).unwrap();
+        let resolved = cli.resolved_config_dir().unwrap();
+        assert!(resolved.is_absolute());
+        assert!(resolved.ends_with("envs/test"));
+        
+        // Checked on the parser rather than by setting the variable, which
+        // would leak into tests running alongside
+        let command = Cli::command();
+        let arg = command.get_arguments().find(|arg| arg.get_id() == "config_dir").unwrap();
+        assert_eq!(arg.get_env(), Some(std::ffi::OsStr::new("NEXUS_CONFIG_DIR")));
+    }
+    
+    #[test]
+    fn test_unknown_command_suggests() {
+        let err = parse(&["stauts"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidSubcommand);
+        assert!(err.to_string().contains("status"));
+    }
+    
+    #[test]
+    fn test_billing_output_flags() {
+        assert_eq!(
+            parse(&["billing", "--json"]).unwrap().command,
+            Some(Command::Billing { json: true, summary_json: false, accounts: vec![], force: false })
+        );
+        assert!(parse(&["billing", "--json", "--summary-json"]).is_err());
+        
+        assert_eq!(
+            parse(&["billing", "--account", "alice", "--account", "3", "--force", "--json"]).unwrap().command,
+            Some(Command::Billing {
+                json: true,
+                summary_json: false,
+                accounts: vec!["alice".to_string(), "3".to_string()],
+                force: true,
+            })
+        );
+    }
+    
+    #[test]
+    fn test_trigger_arguments() {
+        assert_eq!(
+            parse(&["trigger"]).unwrap().command,
+            Some(Command::Trigger { repo: None, account: None, workflow: None, inputs: vec![], watch: false })
+        );
+        
+        let cli = parse(&[
+            "trigger", "--repo", "alice/nexus-runner", "--account", "alice", "--workflow", "health-check.yml",
+            "--input", "batch=1", "--input", "mode=full", "--watch",
+        ]).unwrap();
+        
+        assert_eq!(cli.command, Some(Command::Trigger {
+            repo: Some("alice/nexus-runner".to_string()),
+            account: Some("alice".to_string()),
+            workflow: Some("health-check.yml".to_string()),
+            inputs: vec!["batch=1".to_string(), "mode=full".to_string()],
+            watch: true,
+        }));
+    }
+    
+    #[test]
+    fn test_validate_flags() {
+        assert_eq!(
+            parse(&["validate"]).unwrap().command,
+            Some(Command::Validate { tokens: false, proxies: false, nodes: false, online: false, stale_only: false, json: false, output: None })
+        );
+        assert_eq!(
+            parse(&["validate", "--proxies", "--online", "--json"]).unwrap().command,
+            Some(Command::Validate { tokens: false, proxies: true, nodes: false, online: true, stale_only: false, json: true, output: None })
+        );
+        assert_eq!(
+            parse(&["validate", "--stale-only"]).unwrap().command,
+            Some(Command::Validate { tokens: false, proxies: false, nodes: false, online: false, stale_only: true, json: false, output: None })
+        );
+        assert_eq!(
+            parse(&["validate", "--output", "report.csv"]).unwrap().command,
+            Some(Command::Validate {
+                tokens: false,
+                proxies: false,
+                nodes: false,
+                online: false,
+                stale_only: false,
+                json: false,
+                output: Some("report.csv".to_string()),
+            })
+        );
+    }
+    
+    #[test]
+    fn test_secrets_value_never_from_argument() {
+        assert!(parse(&["secrets", "set", "alice/runner", "TOKEN"]).is_err());
+        assert!(parse(&["secrets", "set", "alice/runner", "TOKEN", "hunter2"]).is_err());
+        assert!(parse(&["secrets", "set", "alice/runner", "TOKEN", "--stdin", "--value-env", "X"]).is_err());
+        
+        let cli = parse(&["secrets", "set", "alice/runner", "TOKEN", "--value-env", "MY_TOKEN"]).unwrap();
+        match cli.command {
+            Some(Command::Secrets { action: SecretsAction::Set { value, .. } }) => {
+                assert_eq!(value.source(), SecretSource::Env("MY_TOKEN".to_string()));
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        
+        assert_eq!(
+            parse(&["secrets", "apply", "--all-active"]).unwrap().command,
+            Some(Command::Secrets {
+                action: SecretsAction::Apply { manifest: None, all_active: true, account: None },
+            })
+        );
+    }
+    
+    #[test]
+    fn test_variables_arguments() {
+        assert_eq!(
+            parse(&["variables", "set", "alice/runner", "MAX_PARALLEL", "20", "--account", "bob"]).unwrap().command,
+            Some(Command::Variables {
+                action: VariablesAction::Set {
+                    repo: "alice/runner".to_string(),
+                    name: "MAX_PARALLEL".to_string(),
+                    value: "20".to_string(),
+                    account: Some("bob".to_string()),
+                },
+            })
+        );
+        assert!(parse(&["variables", "set", "alice/runner", "MAX_PARALLEL"]).is_err());
+    }
+    
+    #[test]
+    fn test_artifacts_arguments() {
+        assert_eq!(parse(&["artifacts"]).unwrap().command, Some(Command::Artifacts { repo: None, run: None }));
+        assert_eq!(
+            parse(&["artifacts", "alice/runner", "--run", "77"]).unwrap().command,
+            Some(Command::Artifacts { repo: Some("alice/runner".to_string()), run: Some(77) })
+        );
+    }
+    
+    #[test]
+    fn test_jobs_arguments() {
+        assert_eq!(parse(&["jobs"]).unwrap().command, Some(Command::Jobs { repo: None, run: None }));
+        assert_eq!(
+            parse(&["jobs", "alice/runner", "--run", "77"]).unwrap().command,
+            Some(Command::Jobs { repo: Some("alice/runner".to_string()), run: Some(77) })
+        );
+    }
+    
+    #[test]
+    fn test_rerun_arguments() {
+        assert_eq!(
+            parse(&["rerun"]).unwrap().command,
+            Some(Command::Rerun { repo: None, run: None, max_attempts: 1, all_jobs: false })
+        );
+        assert_eq!(
+            parse(&["rerun", "alice/runner", "--run", "77", "--max-attempts", "3", "--all-jobs"]).unwrap().command,
+            Some(Command::Rerun { repo: Some("alice/runner".to_string()), run: Some(77), max_attempts: 3, all_jobs: true })
+        );
+    }
+    
+    #[test]
+    fn test_token_arguments() {
+        assert_eq!(
+            parse(&["token", "add", "ghp_abc", "--owner", "my-org"]).unwrap().command,
+            Some(Command::Token { action: TokenAction::Add { token: "ghp_abc".to_string(), owner: Some("my-org".to_string()) } })
+        );
+        assert!(parse(&["token", "add"]).is_err());
+        assert_eq!(
+            parse(&["token", "remove", "alice", "--force"]).unwrap().command,
+            Some(Command::Token { action: TokenAction::Remove { account: "alice".to_string(), force: true } })
+        );
+        assert_eq!(
+            parse(&["token", "disable", "alice"]).unwrap().command,
+            Some(Command::Token { action: TokenAction::Disable { account: "alice".to_string() } })
+        );
+        assert_eq!(
+            parse(&["token", "priority", "@alice", "5"]).unwrap().command,
+            Some(Command::Token { action: TokenAction::Priority { account: "@alice".to_string(), priority: 5 } })
+        );
+        assert!(parse(&["token", "priority", "alice", "256"]).is_err());
+        assert_eq!(
+            parse(&["token", "label", "alice", "gmail #3", "--notes", "created 2026-01"]).unwrap().command,
+            Some(Command::Token { action: TokenAction::Label {
+                account: "alice".to_string(),
+                label: "gmail #3".to_string(),
+                notes: Some("created 2026-01".to_string()),
+            } })
+        );
+    }
+    
+    #[test]
+    fn test_vault_arguments() {
+        assert_eq!(parse(&["vault", "list"]).unwrap().command, Some(Command::Vault { action: VaultAction::List }));
+        assert_eq!(
+            parse(&["vault", "forget", "API_KEY"]).unwrap().command,
+            Some(Command::Vault { action: VaultAction::Forget { name: "API_KEY".to_string() } })
+        );
+        assert!(parse(&["vault", "forget"]).is_err());
+    }
+    
+    #[test]
+    fn test_logs_arguments() {
+        assert_eq!(
+            parse(&["logs", "tail"]).unwrap().command,
+            Some(Command::Logs {
+                action: Some(LogsAction::Tail { file: "orchestrator".to_string(), lines: 100, follow: false }),
+                run: None,
+            })
+        );
+        assert_eq!(
+            parse(&["logs", "grep", "rotation", "--since", "2h"]).unwrap().command,
+            Some(Command::Logs {
+                action: Some(LogsAction::Grep { pattern: "rotation".to_string(), since: Some("2h".to_string()) }),
+                run: None,
+            })
+        );
+        assert!(parse(&["logs", "show", "abc"]).is_err());
+        
+        assert_eq!(
+            parse(&["logs", "--run", "alice/nexus-runner"]).unwrap().command,
+            Some(Command::Logs { action: None, run: Some("alice/nexus-runner".to_string()) })
+        );
+        assert!(parse(&["logs"]).is_err());
+        assert!(parse(&["logs", "--run"]).is_err());
+    }
+    
+    #[test]
+    fn test_sync_command() {
+        assert_eq!(parse(&["sync"]).unwrap().command, Some(Command::Sync));
+        assert!(parse(&["sync", "--all"]).is_err());
+        assert_eq!(parse(&["verify"]).unwrap().command, Some(Command::Verify { workflows: false }));
+        assert_eq!(parse(&["verify", "--workflows"]).unwrap().command, Some(Command::Verify { workflows: true }));
+    }
+    
+    #[test]
+    fn test_metrics_arguments() {
+        assert_eq!(
+            parse(&["metrics"]).unwrap().command,
+            Some(Command::Metrics { since: "1h".to_string(), prometheus: false })
+        );
+        assert_eq!(
+            parse(&["metrics", "--since", "24h", "--prometheus"]).unwrap().command,
+            Some(Command::Metrics { since: "24h".to_string(), prometheus: true })
+        );
+    }
+    
+    #[test]
+    fn test_cleanup_filters() {
+        let cli = parse(&[
+            "cleanup", "--status", "exhausted", "--status", "disabled",
+            "--older-than", "7d", "--keep-last", "2", "--account", "alice", "--dry-run", "--mode", "archive",
+        ]).unwrap();
+        
+        assert_eq!(
+            cli.command,
+            Some(Command::Cleanup {
+                statuses: vec!["exhausted".to_string(), "disabled".to_string()],
+                older_than: Some("7d".to_string()),
+                keep_last: Some(2),
+                account: Some("alice".to_string()),
+                dry_run: true,
+                mode: "archive".to_string(),
+                orphans: false,
+                parent: None,
+            })
+        );
+        
+        let cli = parse(&["cleanup", "--orphans", "--parent", "origin/nexus-runner", "--dry-run"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Cleanup { orphans: true, dry_run: true, parent: Some(ref p), .. }) if p == "origin/nexus-runner"
+        ));
+        assert!(parse(&["cleanup", "--orphans", "--status", "exhausted"]).is_err());
+        assert!(parse(&["cleanup", "--parent", "origin/nexus-runner"]).is_err());
+    }
+    
+    #[test]
+    fn test_self_update_arguments() {
+        assert_eq!(
+            parse(&["self-update", "--check", "--version", "v2.1.0"]).unwrap().command,
+            Some(Command::SelfUpdate { check: true, version: Some("v2.1.0".to_string()) })
+        );
+        assert!(!parse(&["self-update"]).unwrap().version);
+    }
+    
+    #[test]
+    fn test_fork_arguments() {
+        assert_eq!(
+            parse(&["fork", "list", "--status", "active,exhausted", "--json"]).unwrap().command,
+            Some(Command::Fork {
+                action: ForkAction::List { status: Some("active,exhausted".to_string()), json: true },
+            })
+        );
+        assert_eq!(
+            parse(&["fork", "delete", "alice/runner", "--keep-state", "-y"]).unwrap().command,
+            Some(Command::Fork {
+                action: ForkAction::Delete { repo: "alice/runner".to_string(), keep_state: true, account: None },
+            })
+        );
+        assert!(parse(&["fork", "sync", "alice/runner", "--all-active"]).is_err());
+    }
+    
+    #[test]
+    fn test_deploy_flags_conflict() {
+        assert!(parse(&["deploy", "--workflow-only", "--secrets-only"]).is_err());
+        assert_eq!(
+            parse(&["deploy", "--secrets-only"]).unwrap().command,
+            Some(Command::Deploy {
+                workflow_only: false,
+                secrets_only: true,
+                force: false,
+                render_only: false,
+                workflows: vec![],
+                only_drifted: false,
+            })
+        );
+        assert!(parse(&["deploy", "--workflow-only", "--force"]).is_err());
+        assert!(parse(&["deploy", "--render-only", "--secrets-only"]).is_err());
+        assert_eq!(
+            parse(&["deploy", "--workflow-only", "--workflow", "nexus.yml", "--workflow", "health-check.yml"]).unwrap().command,
+            Some(Command::Deploy {
+                workflow_only: true,
+                secrets_only: false,
+                force: false,
+                render_only: false,
+                workflows: vec!["nexus.yml".to_string(), "health-check.yml".to_string()],
+                only_drifted: false,
+            })
+        );
+        assert!(parse(&["deploy", "--only-drifted", "--secrets-only"]).is_err());
+        assert!(matches!(
+            parse(&["deploy", "--only-drifted"]).unwrap().command,
+            Some(Command::Deploy { only_drifted: true, .. })
+        ));
+    }
+}
@@ -1,115 +1,659 @@
 // src/core/account.rs - Account management and rotation
 
 use anyhow::{Result, Context, bail};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 use log::{info, warn};
+use crate::core::billing::BillingMonitor;
+use crate::core::paths::Paths;
+use crate::core::token_report::TokenReportEntry;
+use crate::core::proxy::{self, ProxyConfig};
+use crate::core::settings;
+use crate::core::state::OrchestratorState;
+use crate::github::api::{GitHubError, RateLimitHeaders};
+use crate::github::fork::ForkApi;
+use crate::utils::delay::{self, Sleeper};
+use crate::utils::exit_code::ConfigError;
+use crate::utils::{platform, redact};
+use crate::utils::pool::{JobOutcome, WorkerPool};
+
+/// Whether forks and billing belong to a user or an organization.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OwnerType {
+    #[default]
+    User,
+    Organization,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
     pub username: String,
     pub token: String,
     pub index: usize,
+    /// Owner named after the token in tokens.txt, usually an organization
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub owner_type: OwnerType,
+    /// `fork_organization` from setup.json; forks land there while
+    /// Actions minutes stay billed to the token's own owner
+    #[serde(default)]
+    pub fork_org: Option<String>,
+    /// When the token stops working, as GitHub reported at the last
+    /// validation; None for tokens that never expire
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// What the token was found able to do at the last validation; None
+    /// when unknown
+    #[serde(default)]
+    pub capabilities: Option<Capabilities>,
+    /// False while the account is set aside with `token disable`: rotation,
+    /// billing checks and deployments pass it by, its index stays
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// When GitHub last confirmed the token; None when it never has
+    #[serde(default)]
+    pub validated_at: Option<DateTime<Utc>>,
+    /// Set on loading when the last validation is older than
+    /// `monitor.token_revalidate_hours`, or missing
+    #[serde(default)]
+    pub stale: bool,
+    /// Rotation takes higher-priority accounts first; 0 unless set with
+    /// `token priority`
+    #[serde(default)]
+    pub priority: u8,
+    /// Short name shown next to the login, e.g. the email or provider
+    /// behind a throwaway account; set with `token label`
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// When GitHub started answering the token with 401 mid-run; the
+    /// account stays disabled until `token enable`
+    #[serde(default)]
+    pub quarantined_at: Option<DateTime<Utc>>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl AccountInfo {
+    pub fn token_kind(&self) -> TokenKind {
+        TokenKind::of(&self.token)
+    }
+    
+    /// `@login`, followed by the label in brackets when there is one.
+    pub fn display_name(&self) -> String {
+        match &self.label {
+            Some(label) => format!("@{} [{}]", self.username, label),
+            None => format!("@{}", self.username),
+        }
+    }
+    
+    /// Login that forks land under.
+    pub fn owner(&self) -> &str {
+        match &self.fork_org {
+            Some(org) => org,
+            None => self.billing_owner().0,
+        }
+    }
+    
+    /// Login, and its kind, that Actions minutes are billed to.
+    pub fn billing_owner(&self) -> (&str, OwnerType) {
+        match (self.owner_type, &self.owner) {
+            (OwnerType::Organization, Some(org)) => (org, OwnerType::Organization),
+            _ => (&self.username, OwnerType::User),
+        }
+    }
+    
+    /// Whether forks land under a user or an organization.
+    pub fn fork_owner_type(&self) -> OwnerType {
+        match self.fork_org {
+            Some(_) => OwnerType::Organization,
+            None => self.owner_type,
+        }
+    }
+    
+    /// Whether the token expires within `window` of `now`, or already has.
+    pub fn expires_within(&self, window: chrono::Duration, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at - now <= window)
+    }
+    
+    /// Whether the last validation is older than `ttl` at `now`, or the
+    /// token was never validated.
+    pub fn validation_expired(&self, ttl: chrono::Duration, now: DateTime<Utc>) -> bool {
+        self.validated_at.is_none_or(|validated_at| now - validated_at > ttl)
+    }
+    
+    /// Expiry date for display, `never` for tokens without one.
+    pub fn expiry_label(&self) -> String {
+        match self.expires_at {
+            Some(expires_at) => expires_at.format("%Y-%m-%d").to_string(),
+            None => "never".to_string(),
+        }
+    }
+    
+    /// Fails with a `ConfigError` naming the account and what it lacks when
+    /// the last validation found the token unable to do any of `needed`.
+    /// A token never validated is let through.
+    pub fn require(&self, needed: &[Capability]) -> Result<()> {
+        let Some(capabilities) = &self.capabilities else {
+            return Ok(());
+        };
+        
+        let missing: Vec<String> = needed
+            .iter()
+            .filter(|capability| !capabilities.has(**capability))
+            .map(|capability| capability.requirement())
+            .collect();
+        if !missing.is_empty() {
+            bail!(ConfigError::new(format!(
+                "Token #{} (@{}) lacks {}; update the token and validate the accounts again",
+                self.index,
+                self.username,
+                missing.join(", ")
+            )));
+        }
+        Ok(())
+    }
+    
+    /// The `organization` to fork into, if forks do not land under the user.
+    pub fn fork_organization(&self) -> Option<&str> {
+        match (&self.fork_org, self.owner_type) {
+            (Some(org), _) => Some(org),
+            (None, OwnerType::Organization) => self.owner.as_deref(),
+            (None, OwnerType::User) => None,
+        }
+    }
+}
+
+/// Splits a tokens.txt line into the token and the optional owner after it,
+/// e.g. `ghp_xxx my-org`. Lines that are not tokens give None.
+pub fn parse_token_line(line: &str) -> Option<(String, Option<String>)> {
+    let mut parts = line.split_whitespace();
+    let token = parts.next()?;
+    
+    if !token.starts_with("ghp_") && !token.starts_with("github_pat_") {
+        return None;
+    }
+    
+    Some((token.to_string(), parts.next().map(|owner| owner.to_string())))
 }
 
+/// Line number, token and owner of each token line of tokens.txt, in
+/// account index order. A token repeated on a later line is dropped with a
+/// warning, so one account is never counted twice.
+pub fn token_lines(content: &str) -> Vec<(usize, String, Option<String>)> {
+    let mut tokens: Vec<(usize, String, Option<String>)> = Vec::new();
+    
+    for (line_no, line) in platform::config_lines(content) {
+        let Some((token, owner)) = parse_token_line(line) else { continue };
+        match tokens.iter().find(|(_, seen, _)| *seen == token) {
+            Some((first, _, _)) => warn!("tokens.txt line {} repeats the token on line {}; ignoring it", line_no, first),
+            None => tokens.push((line_no, token, owner)),
+        }
+    }
+    
+    tokens
+}
+
+/// Classic (`ghp_`) or fine-grained (`github_pat_`) personal access token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Classic,
+    /// Lists no scopes; what it may do is probed, see `probe_capabilities`
+    FineGrained,
+}
+
+impl TokenKind {
+    pub fn of(token: &str) -> Self {
+        if token.starts_with("github_pat_") {
+            TokenKind::FineGrained
+        } else {
+            TokenKind::Classic
+        }
+    }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenKind::Classic => "classic",
+            TokenKind::FineGrained => "fine-grained",
+        }
+    }
+}
+
+/// Something the orchestrator needs a token to be allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Fork,
+    /// Write files under .github/workflows
+    Workflows,
+    Secrets,
+    Delete,
+    /// Read the owner's Actions usage
+    Billing,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 5] = [
+        Capability::Fork,
+        Capability::Workflows,
+        Capability::Secrets,
+        Capability::Delete,
+        Capability::Billing,
+    ];
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::Fork => "fork",
+            Capability::Workflows => "workflows",
+            Capability::Secrets => "secrets",
+            Capability::Delete => "delete",
+            Capability::Billing => "billing",
+        }
+    }
+    
+    /// What grants it, in an error message.
+    fn requirement(&self) -> String {
+        match self {
+            Capability::Fork => "the repo or public_repo scope (fine-grained: Administration write) to fork",
+            Capability::Workflows => "the workflow scope (fine-grained: Workflows write) to push workflows",
+            Capability::Secrets => "the repo scope (fine-grained: Secrets write) to set secrets",
+            Capability::Delete => "the delete_repo scope (fine-grained: Administration write) to delete repos",
+            Capability::Billing => "the user scope (fine-grained: Plan read) to read billing",
+        }
+        .to_string()
+    }
+}
+
+/// Per-token capability flags found by `validate_all`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub fork: bool,
+    pub workflows: bool,
+    pub secrets: bool,
+    pub delete: bool,
+    /// Only probed for fine-grained tokens; caches from before it was
+    /// recorded read as true
+    #[serde(default = "enabled_by_default")]
+    pub billing: bool,
+}
+
+impl Capabilities {
+    /// A classic token's, from its `x-oauth-scopes` header. Billing is not
+    /// told from the scopes and taken as allowed.
+    pub fn from_scopes(scopes: &str) -> Self {
+        let scopes: Vec<&str> = scopes.split(',').map(str::trim).collect();
+        let has = |scope: &str| scopes.contains(&scope);
+        
+        Self {
+            fork: has("repo") || has("public_repo"),
+            workflows: has("workflow"),
+            secrets: has("repo"),
+            delete: has("delete_repo"),
+            billing: true,
+        }
+    }
+    
+    /// A fine-grained token's, which GitHub does not list: from the
+    /// `permissions` it reports on one of the owner's repos, and whether
+    /// that repo's secrets public key and the owner's billing usage could
+    /// be read. Creating forks takes Administration write, which shows as
+    /// admin on the owner's repos. Push access is taken as leave to push
+    /// workflows, which the Workflows permission decides.
+    pub fn from_probe(repo: &serde_json::Value, secrets_readable: bool, billing_readable: bool) -> Self {
+        let admin = repo["permissions"]["admin"].as_bool().unwrap_or(false);
+        
+        Self {
+            fork: admin,
+            workflows: repo["permissions"]["push"].as_bool().unwrap_or(false),
+            secrets: secrets_readable,
+            delete: admin,
+            billing: billing_readable,
+        }
+    }
+    
+    pub fn has(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::Fork => self.fork,
+            Capability::Workflows => self.workflows,
+            Capability::Secrets => self.secrets,
+            Capability::Delete => self.delete,
+            Capability::Billing => self.billing,
+        }
+    }
+    
+    /// `fork ✓ workflows ✓ secrets ✓ delete ✗ billing ✓`
+    pub fn summary(&self) -> String {
+        Capability::ALL
+            .iter()
+            .map(|capability| format!("{} {}", capability.name(), if self.has(*capability) { "✓" } else { "✗" }))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Who a token belongs to, when it expires and what it may do, see
+/// `lookup_token`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenLookup {
+    pub username: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub capabilities: Option<Capabilities>,
+}
+
+/// A tokenmap.json entry; files written before expiry dates were kept
+/// hold the login alone, and are upgraded by `load_tokens`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum CachedToken {
+    Validated {
+        username: String,
+        #[serde(default)]
+        expires_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        capabilities: Option<Capabilities>,
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+        /// Account index when last written, for `load_cached`
+        #[serde(default)]
+        index: Option<usize>,
+        #[serde(default)]
+        validated_at: Option<DateTime<Utc>>,
+        #[serde(default)]
+        priority: u8,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        notes: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        quarantined_at: Option<DateTime<Utc>>,
+    },
+    Login(String),
+}
+
+impl CachedToken {
+    fn username(&self) -> &str {
+        match self {
+            CachedToken::Validated { username, .. } | CachedToken::Login(username) => username,
+        }
+    }
+    
+    fn expires_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            CachedToken::Validated { expires_at, .. } => *expires_at,
+            CachedToken::Login(_) => None,
+        }
+    }
+    
+    fn capabilities(&self) -> Option<Capabilities> {
+        match self {
+            CachedToken::Validated { capabilities, .. } => *capabilities,
+            CachedToken::Login(_) => None,
+        }
+    }
+    
+    fn enabled(&self) -> bool {
+        match self {
+            CachedToken::Validated { enabled, .. } => *enabled,
+            CachedToken::Login(_) => true,
+        }
+    }
+    
+    fn validated_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            CachedToken::Validated { validated_at, .. } => *validated_at,
+            CachedToken::Login(_) => None,
+        }
+    }
+    
+    fn priority(&self) -> u8 {
+        match self {
+            CachedToken::Validated { priority, .. } => *priority,
+            CachedToken::Login(_) => 0,
+        }
+    }
+    
+    /// (label, notes)
+    fn annotations(&self) -> (Option<String>, Option<String>) {
+        match self {
+            CachedToken::Validated { label, notes, .. } => (label.clone(), notes.clone()),
+            CachedToken::Login(_) => (None, None),
+        }
+    }
+    
+    fn quarantined_at(&self) -> Option<DateTime<Utc>> {
+        match self {
+            CachedToken::Validated { quarantined_at, .. } => *quarantined_at,
+            CachedToken::Login(_) => None,
+        }
+    }
+}
+
+/// How old a validation may get, `monitor.token_revalidate_hours`.
+fn validation_ttl() -> chrono::Duration {
+    chrono::Duration::hours(settings::get().monitor.token_revalidate_hours as i64)
+}
+
+#[derive(Clone)]
 pub struct AccountManager {
     accounts: Vec<AccountInfo>,
+    /// tokens.txt line of each account, by index
+    lines: Vec<usize>,
     cache_file: std::path::PathBuf,
+    owner_cache_file: std::path::PathBuf,
+    sleeper: Arc<dyn Sleeper>,
 }
 
 impl AccountManager {
-    pub fn new(cache_dir: &Path) -> Self {
-        let cache_file = cache_dir.join("tokenmap.json");
-        
+    pub fn new(paths: &Paths) -> Self {
         Self {
             accounts: Vec::new(),
-            cache_file,
+            lines: Vec::new(),
+            cache_file: paths.tokenmap(),
+            owner_cache_file: paths.ownermap(),
+            sleeper: delay::thread_sleeper(),
         }
     }
     
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+    
     pub fn load_tokens(&mut self, tokens_file: &Path) -> Result<()> {
         let content = fs::read_to_string(tokens_file)
-            .context("Failed to read tokens.txt")?;
+            .context(ConfigError::new("Failed to read tokens.txt"))?;
         
-        let tokens: Vec<String> = content
-            .lines()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty() && (s.starts_with("ghp_") || s.starts_with("github_pat_")))
-            .collect();
+        let tokens = token_lines(&content);
         
         if tokens.is_empty() {
-            bail!("No valid tokens found in tokens.txt");
+            bail!(ConfigError::new("No valid tokens found in tokens.txt"));
         }
         
         info!("Loaded {} tokens from file", tokens.len());
         
         // Try to load cached usernames first
-        let cached_usernames = self.load_cache().unwrap_or_default();
+        let cached_tokens = self.load_cache().unwrap_or_default();
+        let owner_types = self.load_owner_cache();
         
-        for (i, token) in tokens.iter().enumerate() {
-            let username = cached_usernames.get(token)
-                .cloned()
+        let now = Utc::now();
+        for (i, (line_no, token, owner)) in tokens.into_iter().enumerate() {
+            redact::register(&token);
+            self.lines.push(line_no);
+            let cached = cached_tokens.get(&token);
+            let username = cached
+                .map(|cached| cached.username().to_string())
                 .unwrap_or_else(|| format!("user_{}", i));
+            let owner_type = owner.as_ref()
+                .and_then(|o| owner_types.get(o).copied())
+                .unwrap_or_default();
             
-            self.accounts.push(AccountInfo {
+            let (label, notes) = cached.map(CachedToken::annotations).unwrap_or_default();
+            let mut account = AccountInfo {
                 username,
-                token: token.clone(),
+                token,
                 index: i,
-            });
+                owner,
+                owner_type,
+                fork_org: None,
+                expires_at: cached.and_then(CachedToken::expires_at),
+                capabilities: cached.and_then(CachedToken::capabilities),
+                enabled: cached.is_none_or(CachedToken::enabled),
+                validated_at: cached.and_then(CachedToken::validated_at),
+                stale: false,
+                priority: cached.map_or(0, CachedToken::priority),
+                label,
+                notes,
+                quarantined_at: cached.and_then(CachedToken::quarantined_at),
+            };
+            account.stale = account.validation_expired(validation_ttl(), now);
+            self.accounts.push(account);
+        }
+        
+        let stale = self.accounts.iter().filter(|account| account.stale).count();
+        if stale > 0 {
+            info!("{} of {} tokens need revalidation", stale, self.accounts.len());
+        }
+        
+        if cached_tokens.values().any(|cached| matches!(cached, CachedToken::Login(_))) {
+            if let Err(e) = self.upgrade_cache(cached_tokens) {
+                warn!("Could not upgrade {}: {}", self.cache_file.display(), e);
+            }
         }
         
         Ok(())
     }
     
-    pub fn validate_all(&mut self, proxy_manager: &crate::core::proxy::ProxyManager) -> Result<()> {
-        use std::process::Command;
+    /// Rewrites login-only tokenmap.json entries, from before anything else
+    /// was cached, as full entries; indexes come from the loaded accounts.
+    fn upgrade_cache(&self, cache: HashMap<String, CachedToken>) -> Result<()> {
+        let upgraded = cache
+            .into_iter()
+            .map(|(token, cached)| {
+                let cached = match cached {
+                    CachedToken::Login(username) => CachedToken::Validated {
+                        username,
+                        expires_at: None,
+                        capabilities: None,
+                        enabled: true,
+                        index: self.get_account_by_token(&token).map(|account| account.index),
+                        validated_at: None,
+                        priority: 0,
+                        label: None,
+                        notes: None,
+                        quarantined_at: None,
+                    },
+                    validated => validated,
+                };
+                (token, cached)
+            })
+            .collect();
         
+        self.save_cache(&upgraded)?;
+        info!("Upgraded {} to the current format", self.cache_file.display());
+        Ok(())
+    }
+    
+    /// Checks every token on the pool; same-token lookups never overlap.
+    /// Returns a report entry per account that was checked.
+    pub fn validate_all(
+        &mut self,
+        proxy_manager: &crate::core::proxy::ProxyManager,
+        pool: &WorkerPool,
+    ) -> Result<Vec<TokenReportEntry>> {
         info!("Validating {} accounts...", self.accounts.len());
         
         let mut valid_accounts = Vec::new();
+        let mut report = Vec::new();
         let mut cache_map = HashMap::new();
+        let mut owner_types = self.load_owner_cache();
         
-        for (i, account) in self.accounts.iter().enumerate() {
-            print!("  [{}/{}] Validating {}... ", i + 1, self.accounts.len(), account.username);
-            
+        let pause = Duration::from_millis(settings::get().delays.between_validations_ms);
+        let pool = pool.clone().with_pacing(self.sleeper.clone(), "between_validations", pause);
+        
+        let sweep = pool.run(&self.accounts, |account| account.token.clone(), |account| {
             let proxy = proxy_manager.get_proxy(&account.token);
+            let lookup = lookup_token(&account.token, proxy)?;
             
-            let mut cmd = Command::new("gh");
-            cmd.args(&["api", "user", "--jq", ".login"]);
-            cmd.env("GH_TOKEN", &account.token);
+            let owner_type = account.owner.as_ref()
+                .filter(|o| !o.eq_ignore_ascii_case(&lookup.username))
+                .map(|owner| lookup_owner_type(owner, &account.token, proxy));
             
-            if let Some(proxy_config) = proxy {
-                let proxy_url = proxy_config.to_curl_format();
-                cmd.env("https_proxy", &proxy_url);
-                cmd.env("http_proxy", &proxy_url);
-            }
+            Ok((lookup, owner_type))
+        });
+        
+        for (account, outcome) in self.accounts.iter().zip(sweep.outcomes) {
+            print!("  Validating {}... ", account.display_name());
             
-            match cmd.output() {
-                Ok(output) if output.status.success() => {
-                    let username = String::from_utf8_lossy(&output.stdout)
-                        .trim()
-                        .to_string();
-                    
-                    println!("✅ @{}", username);
-                    
-                    let mut validated_account = account.clone();
-                    validated_account.username = username.clone();
-                    
-                    cache_map.insert(account.token.clone(), username);
-                    valid_accounts.push(validated_account);
+            let proxy = proxy_manager.get_proxy(&account.token);
+            let (lookup, owner_type) = match outcome {
+                JobOutcome::Done(found) => found,
+                JobOutcome::Failed(e) => {
+                    println!("❌ Invalid: {}", e);
+                    report.push(TokenReportEntry::new(account, Err(format!("{:#}", e)), proxy));
+                    continue;
                 }
-                Ok(output) => {
-                    let error = String::from_utf8_lossy(&output.stderr);
-                    println!("❌ Invalid: {}", error.lines().next().unwrap_or("Unknown error"));
+                JobOutcome::Cancelled => {
+                    println!("⏭️  Skipped (cancelled)");
+                    continue;
                 }
-                Err(e) => {
-                    println!("❌ Error: {}", e);
+            };
+            report.push(TokenReportEntry::new(account, Ok(&lookup), proxy));
+            let TokenLookup { username, expires_at, capabilities } = lookup;
+            
+            let expiry = match expires_at {
+                Some(expires_at) => format!(" (token expires {})", expires_at.format("%Y-%m-%d")),
+                None => String::new(),
+            };
+            let capability_column = match &capabilities {
+                Some(capabilities) => format!("{} | {}", account.token_kind().name(), capabilities.summary()),
+                None => format!("{} | capabilities unknown", account.token_kind().name()),
+            };
+            println!("✅ {:<28} | {}", format!("@{}{}", username, expiry), capability_column);
+            
+            let validated_at = Utc::now();
+            let mut validated_account = account.clone();
+            validated_account.username = username.clone();
+            validated_account.expires_at = expires_at;
+            validated_account.capabilities = capabilities;
+            validated_account.validated_at = Some(validated_at);
+            validated_account.stale = false;
+            
+            if let (Some(owner), Some(owner_type)) = (account.owner.as_ref(), owner_type) {
+                match owner_type {
+                    Ok(owner_type) => {
+                        println!("     owner {} is a{}", owner, match owner_type {
+                            OwnerType::Organization => "n organization",
+                            OwnerType::User => " user, forking into the token's account",
+                        });
+                        validated_account.owner_type = owner_type;
+                        owner_types.insert(owner.clone(), owner_type);
+                    }
+                    Err(e) => warn!("Could not check owner {}: {}", owner, e),
                 }
             }
             
-            std::thread::sleep(std::time::Duration::from_secs(1));
+            cache_map.insert(account.token.clone(), CachedToken::Validated {
+                username,
+                expires_at,
+                capabilities,
+                enabled: account.enabled,
+                index: Some(account.index),
+                validated_at: Some(validated_at),
+                priority: account.priority,
+                label: account.label.clone(),
+                notes: account.notes.clone(),
+                quarantined_at: account.quarantined_at,
+            });
+            valid_accounts.push(validated_account);
         }
         
         if valid_accounts.is_empty() {
@@ -118,42 +662,452 @@ impl AccountManager {
         
         info!("Validation complete: {}/{} accounts valid", valid_accounts.len(), self.accounts.len());
         
-        self.accounts = valid_accounts;
         self.save_cache(&cache_map)?;
+        fs::write(&self.owner_cache_file, serde_json::to_string_pretty(&owner_types)?)?;
         
-        Ok(())
+        let found: Vec<(usize, String)> = valid_accounts.iter().map(|a| (a.index, a.username.clone())).collect();
+        self.check_unique_usernames(&found)?;
+        self.accounts = valid_accounts;
+        
+        Ok(report)
     }
     
-    fn load_cache(&self) -> Result<HashMap<String, String>> {
+    fn load_cache(&self) -> Result<HashMap<String, CachedToken>> {
         if !self.cache_file.exists() {
             return Ok(HashMap::new());
         }
         
         let content = fs::read_to_string(&self.cache_file)?;
-        let cache: HashMap<String, String> = serde_json::from_str(&content)?;
+        let cache: HashMap<String, CachedToken> = serde_json::from_str(&content)?;
         
         Ok(cache)
     }
     
-    fn save_cache(&self, cache: &HashMap<String, String>) -> Result<()> {
+    /// Owner types found by the last validation, keyed by owner login.
+    fn load_owner_cache(&self) -> HashMap<String, OwnerType> {
+        fs::read_to_string(&self.owner_cache_file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+    
+    fn save_cache(&self, cache: &HashMap<String, CachedToken>) -> Result<()> {
         let json = serde_json::to_string_pretty(cache)?;
         fs::write(&self.cache_file, json)?;
         Ok(())
     }
     
+    /// Records what `lookup_token` found for `token`, the account at
+    /// `index`, in the tokenmap cache, as `validate_all` does.
+    pub fn remember_token(&self, token: &str, index: usize, lookup: &TokenLookup) -> Result<()> {
+        let mut cache = self.load_cache().unwrap_or_default();
+        cache.insert(token.to_string(), CachedToken::Validated {
+            username: lookup.username.clone(),
+            expires_at: lookup.expires_at,
+            capabilities: lookup.capabilities,
+            enabled: true,
+            index: Some(index),
+            validated_at: Some(Utc::now()),
+            priority: 0,
+            label: None,
+            notes: None,
+            quarantined_at: None,
+        });
+        self.save_cache(&cache)
+    }
+    
+    /// Sets the account at `index` aside, or back into use, and records it
+    /// in the tokenmap cache. Returns the updated account.
+    /// Enabling also lifts a quarantine, see `quarantine`.
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) -> Result<&AccountInfo> {
+        self.update(index, |account| {
+            account.enabled = enabled;
+            if enabled {
+                account.quarantined_at = None;
+            }
+        })
+    }
+    
+    /// Disables the account at `index` after GitHub rejected its token
+    /// with 401, and records when in the tokenmap cache; rotation passes it
+    /// by like any disabled account until `token enable`.
+    pub fn quarantine(&mut self, index: usize) -> Result<&AccountInfo> {
+        self.update(index, |account| {
+            account.enabled = false;
+            account.quarantined_at = Some(Utc::now());
+        })
+    }
+    
+    /// Sets how early rotation takes the account at `index`, and records it
+    /// in the tokenmap cache. Returns the updated account.
+    pub fn set_priority(&mut self, index: usize, priority: u8) -> Result<&AccountInfo> {
+        self.update(index, |account| account.priority = priority)
+    }
+    
+    /// Sets the label and notes of the account at `index`, None clearing
+    /// them, and records them in the tokenmap cache.
+    pub fn set_annotations(&mut self, index: usize, label: Option<String>, notes: Option<String>) -> Result<&AccountInfo> {
+        self.update(index, |account| {
+            account.label = label;
+            account.notes = notes;
+        })
+    }
+    
+    /// Applies `change` to the account at `index` and writes the account,
+    /// as it then is, to the tokenmap cache.
+    fn update(&mut self, index: usize, change: impl FnOnce(&mut AccountInfo)) -> Result<&AccountInfo> {
+        let mut cache = self.load_cache().unwrap_or_default();
+        let position = self.accounts.iter()
+            .position(|account| account.index == index)
+            .with_context(|| ConfigError::new(format!("No token #{} in tokens.txt", index)))?;
+        let account = &mut self.accounts[position];
+        change(account);
+        
+        cache.insert(account.token.clone(), CachedToken::Validated {
+            username: account.username.clone(),
+            expires_at: account.expires_at,
+            capabilities: account.capabilities,
+            enabled: account.enabled,
+            index: Some(index),
+            validated_at: account.validated_at,
+            priority: account.priority,
+            label: account.label.clone(),
+            notes: account.notes.clone(),
+            quarantined_at: account.quarantined_at,
+        });
+        self.save_cache(&cache)?;
+        
+        Ok(&self.accounts[position])
+    }
+    
+    /// Drops `token` from the tokenmap cache; the tokens after it move
+    /// down one index, as they do in tokens.txt.
+    pub fn forget_token(&self, token: &str) -> Result<()> {
+        let mut cache = self.load_cache().unwrap_or_default();
+        let Some(removed) = cache.remove(token) else {
+            return Ok(());
+        };
+        
+        if let CachedToken::Validated { index: Some(removed), .. } = removed {
+            for cached in cache.values_mut() {
+                if let CachedToken::Validated { index: Some(index), .. } = cached {
+                    if *index > removed {
+                        *index -= 1;
+                    }
+                }
+            }
+        }
+        self.save_cache(&cache)
+    }
+    
+    /// Applies a fresh `lookup_token` result to the account at `index` and
+    /// records it in the tokenmap cache, keeping whether it is enabled.
+    pub fn record_lookup(&mut self, index: usize, lookup: &TokenLookup) -> Result<()> {
+        self.update(index, |account| {
+            account.username = lookup.username.clone();
+            account.expires_at = lookup.expires_at;
+            account.capabilities = lookup.capabilities;
+            account.validated_at = Some(Utc::now());
+            account.stale = false;
+        })?;
+        Ok(())
+    }
+    
+    /// Checks `account`'s token against GitHub right before destructive
+    /// calls, however recent the cached validation: it must still work and
+    /// still sign in as the same login. Records the fresh validation time.
+    pub fn revalidate(&self, account: &AccountInfo, client: &dyn ForkApi) -> Result<()> {
+        let login = client.username().with_context(|| format!(
+            "Token #{} (@{}) failed revalidation; nothing was changed",
+            account.index, account.username
+        ))?;
+        
+        // A never-validated account still has a placeholder name
+        if account.validated_at.is_some() && !login.eq_ignore_ascii_case(&account.username) {
+            bail!(ConfigError::new(format!(
+                "Token #{} now signs in as @{}, not @{}; validate the accounts again",
+                account.index, login, account.username
+            )));
+        }
+        
+        let mut cache = self.load_cache().unwrap_or_default();
+        if let Some(CachedToken::Validated { validated_at, .. }) = cache.get_mut(&account.token) {
+            *validated_at = Some(Utc::now());
+            self.save_cache(&cache)?;
+        }
+        Ok(())
+    }
+    
+    /// Loads the accounts from tokenmap.json alone, as the last validation
+    /// left them, for read-only commands like `status` that should work
+    /// without tokens.txt. Entries written before indexes were recorded are
+    /// left out; owners are not cached, so every account reads as a user.
+    pub fn load_cached(&mut self) -> Result<()> {
+        let now = Utc::now();
+        let mut accounts: Vec<AccountInfo> = self.load_cache()?
+            .into_iter()
+            .filter_map(|(token, cached)| match cached {
+                CachedToken::Validated {
+                    username,
+                    expires_at,
+                    capabilities,
+                    enabled,
+                    index: Some(index),
+                    validated_at,
+                    priority,
+                    label,
+                    notes,
+                    quarantined_at,
+                } => {
+                    redact::register(&token);
+                    let mut account = AccountInfo {
+                        username,
+                        token,
+                        index,
+                        owner: None,
+                        owner_type: OwnerType::User,
+                        fork_org: None,
+                        expires_at,
+                        capabilities,
+                        enabled,
+                        validated_at,
+                        stale: false,
+                        priority,
+                        label,
+                        notes,
+                        quarantined_at,
+                    };
+                    account.stale = account.validation_expired(validation_ttl(), now);
+                    Some(account)
+                }
+                _ => None,
+            })
+            .collect();
+        accounts.sort_by_key(|account| account.index);
+        
+        info!("Loaded {} accounts from the token cache", accounts.len());
+        self.accounts = accounts;
+        Ok(())
+    }
+    
+    /// tokens.txt line the account at `index` was read from.
+    pub fn line_of(&self, index: usize) -> Option<usize> {
+        self.lines.get(index).copied()
+    }
+    
+    /// Fails with a `ConfigError` when tokens among `found` (account index,
+    /// login) resolve to the same login: one account cannot own two forks
+    /// of the same parent. Lists every collision with its tokens.txt lines.
+    pub fn check_unique_usernames(&self, found: &[(usize, String)]) -> Result<()> {
+        let mut collisions = Vec::new();
+        
+        for (i, (_, username)) in found.iter().enumerate() {
+            let same: Vec<usize> = found.iter()
+                .filter(|(_, other)| other.eq_ignore_ascii_case(username))
+                .map(|(index, _)| *index)
+                .collect();
+            // Reported once, at the first token of the login
+            if same.len() < 2 || found[..i].iter().any(|(_, other)| other.eq_ignore_ascii_case(username)) {
+                continue;
+            }
+            
+            let tokens: Vec<String> = same.iter()
+                .map(|index| match self.line_of(*index) {
+                    Some(line) => format!("#{} (line {})", index, line),
+                    None => format!("#{}", index),
+                })
+                .collect();
+            collisions.push(format!("@{} is behind tokens {}", username, tokens.join(", ")));
+        }
+        
+        if !collisions.is_empty() {
+            bail!(ConfigError::new(format!(
+                "Duplicate accounts in tokens.txt: {}. One account cannot own two forks of the same parent; keep one token per account",
+                collisions.join("; ")
+            )));
+        }
+        Ok(())
+    }
+    
+    /// The account with token index `index`, wherever it sits in the list.
     pub fn get_account(&self, index: usize) -> Option<&AccountInfo> {
-        self.accounts.get(index)
+        self.accounts.iter().find(|a| a.index == index)
+    }
+    
+    pub fn get_account_by_username(&self, username: &str) -> Option<&AccountInfo> {
+        self.accounts
+            .iter()
+            .find(|a| a.username.eq_ignore_ascii_case(username))
+    }
+    
+    pub fn get_account_by_token(&self, token: &str) -> Option<&AccountInfo> {
+        self.accounts.iter().find(|a| a.token == token)
+    }
+    
+    /// (token index, account) pairs in tokens.txt order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &AccountInfo)> {
+        self.accounts.iter().map(|account| (account.index, account))
+    }
+    
+    /// The account that owns `repo`: `username` when given, otherwise the
+    /// fork chain node's token index.
+    pub fn resolve_for_repo(
+        &self,
+        state: &OrchestratorState,
+        repo: &str,
+        username: Option<&str>,
+    ) -> Result<&AccountInfo> {
+        if let Some(username) = username {
+            return self.get_account_by_username(username)
+                .with_context(|| {
+                    let known: Vec<&str> = self.accounts
+                        .iter()
+                        .map(|a| a.username.as_str())
+                        .collect();
+                    ConfigError::new(format!("Unknown account '{}'. Known accounts: {}", username, known.join(", ")))
+                });
+        }
+        
+        let node = state.fork_chain
+            .iter()
+            .find(|n| n.repo == repo)
+            .with_context(|| ConfigError::new(format!(
+                "{} is not in the fork chain; pass --account <username> to choose the token",
+                repo
+            )))?;
+        
+        self.get_account(node.pat_index)
+            .with_context(|| format!(
+                "Account index {} owning {} is missing from tokens.txt",
+                node.pat_index, repo
+            ))
+    }
+    
+    /// Points every account's forks at `org`, or back at its own owner.
+    pub fn set_fork_organization(&mut self, org: Option<&str>) {
+        for account in &mut self.accounts {
+            account.fork_org = org.map(|o| o.to_string());
+        }
     }
     
     pub fn get_all_accounts(&self) -> &[AccountInfo] {
         &self.accounts
     }
     
+    /// Whether the account at `index` is in use; indexes without a token
+    /// count as enabled, their lookups fail on their own.
+    pub fn is_enabled(&self, index: usize) -> bool {
+        self.get_account(index).is_none_or(|account| account.enabled)
+    }
+    
+    /// Rotation priority of the account at `index`; 0 without a token.
+    pub fn priority(&self, index: usize) -> u8 {
+        self.get_account(index).map_or(0, |account| account.priority)
+    }
+    
     pub fn total_accounts(&self) -> usize {
         self.accounts.len()
     }
 }
 
+/// Token indexes in the order rotation considers them after `current`:
+/// highest `priority` first, ties in index order from the one after
+/// `current`, wrapping around. Those `enabled` says no to are left out.
+pub fn rotation_order(
+    total: usize,
+    current: usize,
+    enabled: impl Fn(usize) -> bool,
+    priority: impl Fn(usize) -> u8,
+) -> Vec<usize> {
+    let total = total.max(1);
+    let mut order: Vec<usize> = (1..=total)
+        .map(|step| (current + step) % total)
+        .filter(|&index| enabled(index))
+        .collect();
+    order.sort_by_key(|&index| std::cmp::Reverse(priority(index)));
+    order
+}
+
+/// Resolves the GitHub login behind a token, when it expires and what it
+/// may do, going through its proxy when one is mapped. Classic tokens list
+/// their scopes; fine-grained ones are probed, see `probe_capabilities`.
+pub fn lookup_token(token: &str, proxy: Option<&ProxyConfig>) -> Result<TokenLookup> {
+    let output = gh_api(token, proxy, &["user", "--include"])?;
+    
+    if !output.status.success() {
+        let error = redact::output_text(&output.stderr);
+        bail!("{}", error.lines().next().unwrap_or("Unknown error"));
+    }
+    
+    let mut lookup = token_lookup_from_response(&String::from_utf8_lossy(&output.stdout))?;
+    if TokenKind::of(token) == TokenKind::FineGrained || lookup.capabilities.is_none() {
+        lookup.capabilities = probe_capabilities(token, proxy, &lookup.username);
+    }
+    Ok(lookup)
+}
+
+fn token_lookup_from_response(stdout: &str) -> Result<TokenLookup> {
+    let (headers, body) = RateLimitHeaders::split_gh_output(stdout);
+    let user: serde_json::Value = serde_json::from_str(body).context("Failed to parse user response")?;
+    let username = user["login"].as_str().context("Username not found in response")?;
+    
+    Ok(TokenLookup {
+        username: username.to_string(),
+        expires_at: headers.token_expires_at,
+        capabilities: headers.oauth_scopes.as_deref().map(Capabilities::from_scopes),
+    })
+}
+
+/// A fine-grained token's capabilities, from the first repo of its owner
+/// and `login`'s billing usage; None when it sees no repo, or the probe
+/// fails.
+fn probe_capabilities(token: &str, proxy: Option<&ProxyConfig>, login: &str) -> Option<Capabilities> {
+    let output = gh_api(token, proxy, &["user/repos?affiliation=owner&per_page=1"]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let repos: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    let repo = repos.get(0)?;
+    let name = repo["full_name"].as_str()?;
+    
+    let readable = |endpoint: String| gh_api(token, proxy, &[&endpoint, "--silent"])
+        .is_ok_and(|output| output.status.success());
+    let secrets_readable = readable(format!("repos/{}/actions/secrets/public-key", name));
+    let billing_readable = readable(BillingMonitor::billing_endpoint(login, OwnerType::User));
+    Some(Capabilities::from_probe(repo, secrets_readable, billing_readable))
+}
+
+/// `gh api` with `args`, as `token`, through `proxy` when given.
+fn gh_api(token: &str, proxy: Option<&ProxyConfig>, args: &[&str]) -> Result<std::process::Output> {
+    let mut cmd = platform::command("gh");
+    cmd.arg("api").args(args);
+    cmd.env("GH_TOKEN", token);
+    crate::core::settings::get().github.apply_host(&mut cmd);
+    
+    if let Some(proxy_config) = proxy {
+        proxy::apply_env(&mut cmd, &proxy_config.to_curl_format());
+    }
+    
+    cmd.output().context("Failed to execute gh command")
+}
+
+/// Tells organizations from users: `/orgs/{owner}` only exists for organizations.
+pub fn lookup_owner_type(owner: &str, token: &str, proxy: Option<&ProxyConfig>) -> Result<OwnerType> {
+    let output = gh_api(token, proxy, &[&format!("orgs/{}", owner), "--silent"])?;
+    
+    owner_type_from_response(output.status.success(), &redact::output_text(&output.stderr))
+}
+
+fn owner_type_from_response(success: bool, stderr: &str) -> Result<OwnerType> {
+    if success {
+        Ok(OwnerType::Organization)
+    } else if matches!(GitHubError::from_stderr(stderr), GitHubError::NotFound(_)) {
+        Ok(OwnerType::User)
+    } else {
+        bail!("{}", stderr.lines().next().unwrap_or("Unknown error"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,12 +1121,282 @@ mod tests {
         
         fs::write(&tokens_file, "ghp_test123\nghp_test456\n").unwrap();
         
-        let cache_dir = temp_dir.path().join("cache");
-        fs::create_dir(&cache_dir).unwrap();
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
         
-        let mut manager = AccountManager::new(&cache_dir);
+        let mut manager = AccountManager::new(&paths);
         manager.load_tokens(&tokens_file).unwrap();
         
         assert_eq!(manager.total_accounts(), 2);
     }
+    
+    #[test]
+    fn test_duplicate_tokens_and_usernames() {
+        let temp_dir = tempdir().unwrap();
+        let tokens_file = temp_dir.path().join("tokens.txt");
+        // The same token pasted twice counts once
+        fs::write(&tokens_file, "ghp_test123\n# spare\nghp_test456\nghp_test123 my-org\nghp_test789\n").unwrap();
+        
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
+        let mut manager = AccountManager::new(&paths);
+        manager.load_tokens(&tokens_file).unwrap();
+        
+        let tokens: Vec<&str> = manager.get_all_accounts().iter().map(|a| a.token.as_str()).collect();
+        assert_eq!(tokens, ["ghp_test123", "ghp_test456", "ghp_test789"]);
+        assert_eq!((manager.line_of(1), manager.line_of(2)), (Some(3), Some(5)));
+        
+        // Two different tokens of one login
+        manager.check_unique_usernames(&[(0, "alice".to_string()), (1, "bob".to_string())]).unwrap();
+        let err = manager
+            .check_unique_usernames(&[(0, "alice".to_string()), (1, "bob".to_string()), (2, "Alice".to_string())])
+            .unwrap_err();
+        assert_eq!(crate::utils::exit_code::for_error(&err), crate::utils::exit_code::CONFIG_ERROR);
+        assert!(err.to_string().contains("@alice is behind tokens #0 (line 1), #2 (line 5)"), "{}", err);
+        assert!(!err.to_string().contains("@Alice"), "{}", err);
+    }
+    
+    #[test]
+    fn test_load_cached_without_tokens_file() {
+        let temp_dir = tempdir().unwrap();
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
+        // A legacy entry holds no index and is left out
+        fs::write(paths.tokenmap(), r#"{"ghp_legacy999": "ghost"}"#).unwrap();
+        
+        let manager = AccountManager::new(&paths);
+        let lookup = |username: &str| TokenLookup {
+            username: username.to_string(),
+            expires_at: None,
+            capabilities: None,
+        };
+        manager.remember_token("ghp_test456", 1, &lookup("bob")).unwrap();
+        manager.remember_token("ghp_test123", 0, &lookup("alice")).unwrap();
+        manager.remember_token("ghp_test789", 2, &lookup("carol")).unwrap();
+        assert!(!paths.tokens_file().exists());
+        
+        let mut cached = AccountManager::new(&paths);
+        cached.load_cached().unwrap();
+        let usernames: Vec<(usize, &str)> = cached.iter().map(|(i, a)| (i, a.username.as_str())).collect();
+        assert_eq!(usernames, [(0, "alice"), (1, "bob"), (2, "carol")]);
+        assert_eq!(cached.get_account_by_token("ghp_test789").map(|a| a.index), Some(2));
+        assert!(cached.get_account_by_token("ghp_legacy999").is_none());
+        assert_eq!(cached.get_account_by_username("BOB").map(|a| a.index), Some(1));
+        
+        // Forgetting a token moves the ones after it down, as in tokens.txt
+        manager.forget_token("ghp_test456").unwrap();
+        cached.load_cached().unwrap();
+        let usernames: Vec<(usize, &str)> = cached.iter().map(|(i, a)| (i, a.username.as_str())).collect();
+        assert_eq!(usernames, [(0, "alice"), (1, "carol")]);
+        assert_eq!(cached.get_account(1).map(|a| a.token.as_str()), Some("ghp_test789"));
+    }
+    
+    #[test]
+    fn test_old_validations_are_stale() {
+        let temp_dir = tempdir().unwrap();
+        let tokens_file = temp_dir.path().join("tokens.txt");
+        fs::write(&tokens_file, "ghp_test123\nghp_test456\nghp_test789\n").unwrap();
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
+        
+        let hours_ago = |hours: i64| (Utc::now() - chrono::Duration::hours(hours)).to_rfc3339();
+        fs::write(paths.tokenmap(), format!(
+            r#"{{"ghp_test123": {{"username": "alice", "validated_at": "{}"}},
+                "ghp_test456": {{"username": "bob", "validated_at": "{}"}},
+                "ghp_test789": "carol"}}"#,
+            hours_ago(2),
+            hours_ago(30),
+        )).unwrap();
+        
+        let mut manager = AccountManager::new(&paths);
+        manager.load_tokens(&tokens_file).unwrap();
+        let stale: Vec<(&str, bool)> = manager.iter().map(|(_, a)| (a.username.as_str(), a.stale)).collect();
+        assert_eq!(stale, [("alice", false), ("bob", true), ("carol", true)]);
+        
+        manager.record_lookup(1, &TokenLookup { username: "bob".to_string(), expires_at: None, capabilities: None }).unwrap();
+        assert!(!manager.get_account(1).unwrap().stale);
+        
+        let mut reloaded = AccountManager::new(&paths);
+        reloaded.load_tokens(&tokens_file).unwrap();
+        let stale: Vec<bool> = reloaded.iter().map(|(_, a)| a.stale).collect();
+        assert_eq!(stale, [false, false, true]);
+    }
+    
+    #[test]
+    fn test_login_only_cache_is_upgraded_in_place() {
+        let temp_dir = tempdir().unwrap();
+        let tokens_file = temp_dir.path().join("tokens.txt");
+        fs::write(&tokens_file, "ghp_test123\nghp_test456\n").unwrap();
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
+        fs::write(paths.tokenmap(), r#"{"ghp_test123": "alice", "ghp_test456": "bob", "ghp_gone0000": "carol"}"#).unwrap();
+        
+        let mut manager = AccountManager::new(&paths);
+        manager.load_tokens(&tokens_file).unwrap();
+        assert_eq!(manager.get_account(1).unwrap().username, "bob");
+        
+        let upgraded: serde_json::Value = serde_json::from_str(&fs::read_to_string(paths.tokenmap()).unwrap()).unwrap();
+        assert_eq!(upgraded["ghp_test456"]["username"], "bob");
+        assert_eq!(upgraded["ghp_test456"]["index"], 1);
+        assert_eq!(upgraded["ghp_gone0000"]["index"], serde_json::Value::Null);
+        
+        // Labels and notes ride along in the upgraded entries
+        manager.set_annotations(0, Some("gmail #3".to_string()), Some("phone-verified".to_string())).unwrap();
+        let mut reloaded = AccountManager::new(&paths);
+        reloaded.load_tokens(&tokens_file).unwrap();
+        let alice = reloaded.get_account(0).unwrap();
+        assert_eq!(alice.display_name(), "@alice [gmail #3]");
+        assert_eq!(alice.notes.as_deref(), Some("phone-verified"));
+        assert_eq!(reloaded.get_account(1).unwrap().display_name(), "@bob");
+        
+        let mut cached = AccountManager::new(&paths);
+        cached.load_cached().unwrap();
+        assert_eq!(cached.iter().map(|(i, a)| (i, a.display_name())).collect::<Vec<_>>(), [
+            (0, "@alice [gmail #3]".to_string()),
+            (1, "@bob".to_string()),
+        ]);
+    }
+    
+    #[test]
+    fn test_org_owner_from_tokens_and_cache() {
+        let temp_dir = tempdir().unwrap();
+        let tokens_file = temp_dir.path().join("tokens.txt");
+        fs::write(&tokens_file, "ghp_user111
+ghp_org2222  acme-labs
+ghp_typo333 somebody
+").unwrap();
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
+        fs::write(
+            paths.ownermap(),
+            r#"{"acme-labs": "organization", "somebody": "user"}"#,
+        ).unwrap();
+        
+        let mut manager = AccountManager::new(&paths);
+        manager.load_tokens(&tokens_file).unwrap();
+        let accounts = manager.get_all_accounts();
+        
+        assert_eq!(accounts[0].owner_type, OwnerType::User);
+        assert_eq!(accounts[0].owner(), "user_0");
+        
+        assert_eq!(accounts[1].token, "ghp_org2222");
+        assert_eq!(accounts[1].owner_type, OwnerType::Organization);
+        assert_eq!(accounts[1].owner(), "acme-labs");
+        assert_eq!(accounts[1].fork_organization(), Some("acme-labs"));
+        
+        // A named owner that turned out to be a user changes nothing
+        assert_eq!(accounts[2].owner(), "user_2");
+        assert_eq!(accounts[2].fork_organization(), None);
+    }
+    
+    #[test]
+    fn test_fork_organization_keeps_billing_owner() {
+        let temp_dir = tempdir().unwrap();
+        let tokens_file = temp_dir.path().join("tokens.txt");
+        fs::write(&tokens_file, "ghp_user111\n").unwrap();
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
+        
+        let mut manager = AccountManager::new(&paths);
+        manager.load_tokens(&tokens_file).unwrap();
+        manager.set_fork_organization(Some("nexus-farm"));
+        let account = &manager.get_all_accounts()[0];
+        
+        assert_eq!(account.owner(), "nexus-farm");
+        assert_eq!(account.fork_organization(), Some("nexus-farm"));
+        assert_eq!(account.fork_owner_type(), OwnerType::Organization);
+        assert_eq!(account.billing_owner(), ("user_0", OwnerType::User));
+    }
+    
+    #[test]
+    fn test_owner_type_from_response() {
+        assert_eq!(owner_type_from_response(true, "").unwrap(), OwnerType::Organization);
+        assert_eq!(
+            owner_type_from_response(false, "gh: Not Found (HTTP 404)").unwrap(),
+            OwnerType::User
+        );
+        assert!(owner_type_from_response(false, "gh: Bad credentials (HTTP 401)").is_err());
+        
+        assert_eq!(parse_token_line("notatoken acme"), None);
+    }
+    
+    #[test]
+    fn test_token_expiry_from_lookup_and_cache() {
+        let fine_grained = "HTTP/2.0 200 OK\r\nGithub-Authentication-Token-Expiration: 2026-11-01 09:30:00 UTC\r\n\r\n{\"login\": \"alice\"}";
+        let lookup = token_lookup_from_response(fine_grained).unwrap();
+        assert_eq!(lookup.username, "alice");
+        assert_eq!(lookup.expires_at.unwrap().to_rfc3339(), "2026-11-01T09:30:00+00:00");
+        assert_eq!(token_lookup_from_response("HTTP/2.0 200 OK\n\n{\"login\": \"bob\"}").unwrap().expires_at, None);
+        
+        let temp_dir = tempdir().unwrap();
+        let tokens_file = temp_dir.path().join("tokens.txt");
+        fs::write(&tokens_file, "ghp_old1111\ngithub_pat_new2222\n").unwrap();
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
+        // The first entry is in the format written before expiry dates were kept
+        fs::write(
+            paths.tokenmap(),
+            r#"{"ghp_old1111": "olduser", "github_pat_new2222": {"username": "alice", "expires_at": "2026-11-01T09:30:00Z"}}"#,
+        ).unwrap();
+        
+        let mut manager = AccountManager::new(&paths);
+        manager.load_tokens(&tokens_file).unwrap();
+        let accounts = manager.get_all_accounts();
+        
+        assert_eq!(accounts[0].username, "olduser");
+        assert_eq!(accounts[0].expiry_label(), "never");
+        assert_eq!(accounts[1].username, "alice");
+        assert_eq!(accounts[1].expiry_label(), "2026-11-01");
+        
+        let now = lookup.expires_at.unwrap() - chrono::Duration::days(10);
+        assert!(accounts[1].expires_within(chrono::Duration::days(14), now));
+        assert!(!accounts[1].expires_within(chrono::Duration::days(7), now));
+        assert!(!accounts[0].expires_within(chrono::Duration::days(14), now));
+    }
+    
+    #[test]
+    fn test_capabilities_from_scopes_and_probe() {
+        let classic = "HTTP/2.0 200 OK\r\nX-OAuth-Scopes: repo, workflow\r\n\r\n{\"login\": \"alice\"}";
+        let capabilities = token_lookup_from_response(classic).unwrap().capabilities.unwrap();
+        assert_eq!(capabilities, Capabilities { fork: true, workflows: true, secrets: true, delete: false, billing: true });
+        assert_eq!(capabilities.summary(), "fork ✓ workflows ✓ secrets ✓ delete ✗ billing ✓");
+        assert_eq!(Capabilities::from_scopes("public_repo"), Capabilities { fork: true, billing: true, ..Capabilities::default() });
+        
+        let repo = serde_json::json!({ "full_name": "bob/site", "permissions": { "admin": false, "push": true } });
+        assert_eq!(
+            Capabilities::from_probe(&repo, true, false),
+            Capabilities { fork: false, workflows: true, secrets: true, delete: false, billing: false }
+        );
+        // Caches from before billing was probed
+        let cached: Capabilities = serde_json::from_str(r#"{"fork": true, "workflows": true, "secrets": true, "delete": true}"#).unwrap();
+        assert!(cached.billing);
+        assert_eq!(TokenKind::of("github_pat_11ABC"), TokenKind::FineGrained);
+        assert_eq!(TokenKind::of("ghp_test123"), TokenKind::Classic);
+        
+        let mut account = AccountInfo {
+            username: "alice".to_string(),
+            token: "ghp_test123".to_string(),
+            index: 2,
+            owner: None,
+            owner_type: OwnerType::User,
+            fork_org: None,
+            expires_at: None,
+            capabilities: None,
+            enabled: true,
+            validated_at: None,
+            stale: false,
+            priority: 0,
+            label: None,
+            notes: None,
+            quarantined_at: None,
+        };
+        // Never validated: let through
+        account.require(&[Capability::Delete]).unwrap();
+        
+        account.capabilities = Some(capabilities);
+        account.require(&[Capability::Secrets, Capability::Workflows]).unwrap();
+        let err = account.require(&[Capability::Secrets, Capability::Delete]).unwrap_err();
+        assert_eq!(crate::utils::exit_code::for_error(&err), crate::utils::exit_code::CONFIG_ERROR);
+        assert!(err.to_string().starts_with("Token #2 (@alice) lacks the delete_repo scope"), "{}", err);
+    }
 }
@@ -1,9 +1,15 @@
 // src/core/billing.rs - Billing monitor (ported from Nexus Rust billing.rs)
 
-use anyhow::{Result, Context};
+use anyhow::{Result, Context, bail};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
 use log::{info, warn};
+use std::fs;
+use std::path::Path;
+use crate::core::account::{AccountInfo, OwnerType, TokenKind};
+use crate::core::settings;
+use crate::github::api::{GitHubApi, GitHubError};
+use crate::utils::exit_code::ConfigError;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BillingInfo {
@@ -14,6 +20,36 @@ pub struct BillingInfo {
     pub hours_remaining: f32,
     pub is_exhausted: bool,
     pub is_warning: bool,
+    /// True when the billing API failed and the values are the safe fallback
+    #[serde(default)]
+    pub is_estimated: bool,
+    /// True when a fine-grained token was refused billing (it lacks Plan:
+    /// read); usage is unknown rather than assumed exhausted
+    #[serde(default)]
+    pub permission_missing: bool,
+    /// The billing owner's GitHub plan, when GitHub shows it to the token
+    #[serde(default, rename = "plan")]
+    pub plan_name: Option<String>,
+    /// Core-hours in the plan's monthly quota
+    #[serde(default = "free_tier_core_hours")]
+    pub included_core_hours: f32,
+    #[serde(default = "Utc::now")]
+    pub checked_at: DateTime<Utc>,
+}
+
+fn free_tier_core_hours() -> f32 {
+    120.0
+}
+
+impl BillingInfo {
+    /// Share of the plan's quota used, in percent.
+    pub fn usage_percent(&self) -> f32 {
+        if self.included_core_hours > 0.0 {
+            self.total_core_hours_used / self.included_core_hours * 100.0
+        } else {
+            0.0
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -30,9 +66,126 @@ struct BillingResponse {
     usage_items: Vec<UsageItem>,
 }
 
+/// Actions minutes in a billing usage response.
+pub fn actions_minutes(response: &str) -> Result<f32> {
+    let response: BillingResponse = serde_json::from_str(response)
+        .context("Failed to parse billing response")?;
+    
+    Ok(response.usage_items
+        .iter()
+        .filter(|item| item.product == "actions" && item.unit_type == "Minutes")
+        .map(|item| item.quantity)
+        .sum())
+}
+
+/// Actions minutes a GitHub plan includes each month. None for the free
+/// plan and any plan not listed, which get the configured quota.
+pub fn plan_included_minutes(plan: &str) -> Option<f32> {
+    match plan {
+        "pro" | "team" => Some(3000.0),
+        "business_plus" | "enterprise" => Some(50000.0),
+        _ => None,
+    }
+}
+
+/// Billing limits from `thresholds.json` in the config directory. Fields
+/// left out of the file keep their defaults: the `[rotation]` thresholds
+/// and the free tier's 120 core-hours (2000 minutes). Accounts on a plan
+/// with more minutes get the same thresholds as a share of their quota.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BillingThresholds {
+    /// Core-hours used at which an account is flagged as low
+    pub warning: f32,
+    /// Core-hours used at which an account counts as exhausted
+    pub critical: f32,
+    /// Core-hours in the monthly quota
+    pub max_core_hours: f32,
+    /// Actions minutes included in the monthly quota
+    pub included_minutes: f32,
+}
+
+impl Default for BillingThresholds {
+    fn default() -> Self {
+        let rotation = &settings::get().rotation;
+        Self {
+            warning: rotation.warning_threshold,
+            critical: rotation.critical_threshold,
+            max_core_hours: 120.0,
+            included_minutes: 2000.0,
+        }
+    }
+}
+
+impl BillingThresholds {
+    /// Loads the thresholds, or the defaults when the file does not exist.
+    /// A file that does not parse or breaks the rules is a config error.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        
+        let content = fs::read_to_string(path)
+            .with_context(|| ConfigError::new(format!("Failed to read {}", path.display())))?;
+        let thresholds: Self = serde_json::from_str(&content)
+            .map_err(|e| ConfigError::new(format!("{}: {}", path.display(), e)))?;
+        
+        let errors = thresholds.validate();
+        if !errors.is_empty() {
+            bail!(ConfigError::new(format!("Invalid {}:\n  {}", path.display(), errors.join("\n  "))));
+        }
+        
+        Ok(thresholds)
+    }
+    
+    /// Writes the thresholds through a temp file, so a reader never sees
+    /// half a file. Refuses values that `validate` rejects.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let errors = self.validate();
+        if !errors.is_empty() {
+            bail!(ConfigError::new(format!("Invalid thresholds:\n  {}", errors.join("\n  "))));
+        }
+        
+        let temp_file = path.with_extension("tmp");
+        fs::write(&temp_file, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write {}", temp_file.display()))?;
+        fs::rename(&temp_file, path)
+            .with_context(|| format!("Failed to replace {}", path.display()))
+    }
+    
+    /// Every broken rule: all values positive, warning < critical <= max_core_hours.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        
+        for (key, value) in [
+            ("warning", self.warning),
+            ("critical", self.critical),
+            ("max_core_hours", self.max_core_hours),
+            ("included_minutes", self.included_minutes),
+        ] {
+            if !(value.is_finite() && value > 0.0) {
+                errors.push(format!("{} ({}) must be positive", key, value));
+            }
+        }
+        if self.warning >= self.critical {
+            errors.push(format!("critical ({}) must be above warning ({})", self.critical, self.warning));
+        }
+        if self.critical > self.max_core_hours {
+            errors.push(format!(
+                "critical ({}) must not exceed max_core_hours ({})",
+                self.critical, self.max_core_hours
+            ));
+        }
+        
+        errors
+    }
+}
+
 pub struct BillingMonitor {
     warning_threshold: f32,    // 118.0 for free tier (120 total)
     critical_threshold: f32,   // 119.5 for free tier
+    max_core_hours: f32,
+    included_minutes: f32,
 }
 
 impl Default for BillingMonitor {
@@ -40,81 +193,114 @@ impl Default for BillingMonitor {
         Self {
             warning_threshold: 118.0,
             critical_threshold: 119.5,
+            max_core_hours: 120.0,
+            included_minutes: 2000.0,
         }
     }
 }
 
 impl BillingMonitor {
-    pub fn new(warning_threshold: f32, critical_threshold: f32) -> Self {
+    pub fn from_thresholds(thresholds: &BillingThresholds) -> Self {
         Self {
-            warning_threshold,
-            critical_threshold,
+            warning_threshold: thresholds.warning,
+            critical_threshold: thresholds.critical,
+            max_core_hours: thresholds.max_core_hours,
+            included_minutes: thresholds.included_minutes,
         }
     }
     
-    pub fn check_billing(
-        &self,
-        username: &str,
-        token: &str,
-        proxy: Option<&str>,
-    ) -> Result<BillingInfo> {
-        let endpoint = format!("/users/{}/settings/billing/usage", username);
-        
-        let mut cmd = Command::new("gh");
-        cmd.args(&[
-            "api",
-            &endpoint,
-            "-H", "Accept: application/vnd.github+json",
-        ]);
-        
-        if let Some(proxy_url) = proxy {
-            cmd.env("https_proxy", proxy_url);
-            cmd.env("http_proxy", proxy_url);
-        }
-        
-        cmd.env("GH_TOKEN", token);
-        
-        let output = cmd.output()
-            .context("Failed to execute gh command")?;
-        
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            warn!("Billing API call failed for {}: {}", username, stderr);
-            
-            // Return safe default (assume exhausted)
-            return Ok(BillingInfo {
-                username: username.to_string(),
-                total_core_hours_used: 999.0,
-                total_minutes_used: 999.0,
-                included_minutes: 2000.0,
-                hours_remaining: 0.0,
-                is_exhausted: true,
-                is_warning: true,
-            });
+    /// Thresholds from `thresholds.json` at `path`; see `BillingThresholds::load`.
+    pub fn from_config(path: &Path) -> Result<Self> {
+        Ok(Self::from_thresholds(&BillingThresholds::load(path)?))
+    }
+    
+    pub fn warning_threshold(&self) -> f32 {
+        self.warning_threshold
+    }
+    
+    pub fn critical_threshold(&self) -> f32 {
+        self.critical_threshold
+    }
+    
+    pub fn max_core_hours(&self) -> f32 {
+        self.max_core_hours
+    }
+    
+    /// Billing usage endpoint for a user or organization owner.
+    pub fn billing_endpoint(owner: &str, owner_type: OwnerType) -> String {
+        match owner_type {
+            OwnerType::User => format!("/users/{}/settings/billing/usage", owner),
+            OwnerType::Organization => format!("/orgs/{}/settings/billing/usage", owner),
         }
-        
-        let response_text = String::from_utf8_lossy(&output.stdout);
-        let response: BillingResponse = serde_json::from_str(&response_text)
-            .context("Failed to parse billing response")?;
-        
-        let mut total_minutes = 0.0;
-        
-        for item in response.usage_items {
-            if item.product == "actions" && item.unit_type == "Minutes" {
-                total_minutes += item.quantity;
+    }
+    
+    /// Usage of the account's billing owner: its login, or its organization.
+    pub fn check_billing(&self, account: &AccountInfo, client: &dyn GitHubApi) -> Result<BillingInfo> {
+        let (username, owner_type) = account.billing_owner();
+        let total_minutes = match client.actions_minutes_used(username, owner_type) {
+            Ok(minutes) => minutes,
+            Err(e) if account.token_kind() == TokenKind::FineGrained
+                && (GitHubError::is_no_access(&e) || GitHubError::is_not_found(&e)) =>
+            {
+                warn!("Token for {} may not read billing for {}: {}", account.username, username, e);
+                
+                return Ok(BillingInfo {
+                    username: username.to_string(),
+                    total_core_hours_used: 0.0,
+                    total_minutes_used: 0.0,
+                    included_minutes: self.included_minutes,
+                    hours_remaining: 0.0,
+                    is_exhausted: false,
+                    is_warning: false,
+                    is_estimated: true,
+                    permission_missing: true,
+                    plan_name: None,
+                    included_core_hours: self.max_core_hours,
+                    checked_at: Utc::now(),
+                });
             }
-        }
+            Err(e) => {
+                warn!("Billing API call failed for {}: {}", username, e);
+                
+                // Return safe default (assume exhausted)
+                return Ok(BillingInfo {
+                    username: username.to_string(),
+                    total_core_hours_used: 999.0,
+                    total_minutes_used: 999.0,
+                    included_minutes: self.included_minutes,
+                    hours_remaining: 0.0,
+                    is_exhausted: true,
+                    is_warning: true,
+                    is_estimated: true,
+                    permission_missing: false,
+                    plan_name: None,
+                    included_core_hours: self.max_core_hours,
+                    checked_at: Utc::now(),
+                });
+            }
+        };
+        
+        let plan_name = client.plan_name(username, owner_type).unwrap_or_else(|e| {
+            warn!("Could not read the plan of {}, assuming the configured quota: {}", username, e);
+            None
+        });
         
         // Free tier: 2000 minutes = ~33.3 hours at 1x multiplier
         // But Actions use multipliers: 2x for Linux = 120 "core-hours"
-        let included_minutes = 2000.0;
+        let included_minutes = plan_name
+            .as_deref()
+            .and_then(plan_included_minutes)
+            .unwrap_or(self.included_minutes);
         let total_core_hours = total_minutes * 2.0 / 60.0; // 2x multiplier for Linux
-        let max_core_hours = 120.0;
         
-        let hours_remaining = (max_core_hours - total_core_hours).max(0.0);
+        // The thresholds are set against the configured quota; a bigger plan
+        // scales them so they stay the same share of its quota
+        let scale = included_minutes / self.included_minutes;
+        let included_core_hours = self.max_core_hours * scale;
+        let hours_remaining = (included_core_hours - total_core_hours).max(0.0);
         
-        let is_warning = total_core_hours >= self.warning_threshold;
-        let is_exhausted = total_core_hours >= self.critical_threshold;
+        let is_warning = total_core_hours >= self.warning_threshold * scale;
+        let is_exhausted = total_core_hours >= self.critical_threshold * scale;
         
         Ok(BillingInfo {
             username: username.to_string(),
@@ -124,10 +310,23 @@ impl BillingMonitor {
             hours_remaining,
             is_exhausted,
             is_warning,
+            is_estimated: false,
+            permission_missing: false,
+            plan_name,
+            included_core_hours,
+            checked_at: Utc::now(),
         })
     }
     
     pub fn display_billing(&self, info: &BillingInfo) {
+        if info.permission_missing {
+            println!(
+                "⚪ @{:<20} | billing permission missing (fine-grained token needs Plan: read)",
+                info.username
+            );
+            return;
+        }
+        
         let status_icon = if info.is_exhausted {
             "🔴"
         } else if info.is_warning {
@@ -137,11 +336,14 @@ impl BillingMonitor {
         };
         
         println!(
-            "{} @{:<20} | {:.1}/120.0 core-hours | {:.1}h remaining",
+            "{} @{:<20} | {:.1}/{:.1} core-hours ({:.0}%) | {:.1}h remaining{}",
             status_icon,
             info.username,
             info.total_core_hours_used,
-            info.hours_remaining
+            info.included_core_hours,
+            info.usage_percent(),
+            info.hours_remaining,
+            info.plan_name.as_deref().map(|plan| format!(" | {} plan", plan)).unwrap_or_default()
         );
         
         if info.is_exhausted {
@@ -162,4 +364,96 @@ mod tests {
         assert_eq!(monitor.warning_threshold, 118.0);
         assert_eq!(monitor.critical_threshold, 119.5);
     }
+    
+    #[test]
+    fn test_thresholds_file_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("thresholds.json");
+        
+        assert_eq!(BillingThresholds::load(&path).unwrap(), BillingThresholds::default());
+        
+        let thresholds = BillingThresholds {
+            warning: 80.0,
+            critical: 95.0,
+            max_core_hours: 100.0,
+            included_minutes: 3000.0,
+        };
+        thresholds.save(&path).unwrap();
+        assert_eq!(BillingThresholds::load(&path).unwrap(), thresholds);
+        assert!(!path.with_extension("tmp").exists());
+        
+        let monitor = BillingMonitor::from_config(&path).unwrap();
+        assert_eq!((monitor.warning_threshold(), monitor.max_core_hours()), (80.0, 100.0));
+        
+        std::fs::write(&path, r#"{"critical": 119.9}"#).unwrap();
+        let partial = BillingThresholds::load(&path).unwrap();
+        assert_eq!((partial.critical, partial.max_core_hours), (119.9, 120.0));
+        
+        std::fs::write(&path, r#"{"critcal": 119.9}"#).unwrap();
+        assert!(BillingThresholds::load(&path).is_err());
+    }
+    
+    #[test]
+    fn test_thresholds_validation_rules() {
+        let valid = BillingThresholds { warning: 100.0, critical: 120.0, max_core_hours: 120.0, included_minutes: 2000.0 };
+        assert!(valid.validate().is_empty());
+        
+        let equal = BillingThresholds { warning: 120.0, ..valid };
+        assert_eq!(equal.validate(), vec!["critical (120) must be above warning (120)"]);
+        
+        let over_max = BillingThresholds { critical: 121.0, ..valid };
+        assert_eq!(over_max.validate(), vec!["critical (121) must not exceed max_core_hours (120)"]);
+        
+        let negative = BillingThresholds { warning: -1.0, included_minutes: 0.0, ..valid };
+        assert_eq!(negative.validate().len(), 2);
+        
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("thresholds.json");
+        assert!(over_max.save(&path).is_err());
+        assert!(!path.exists());
+        std::fs::write(&path, serde_json::to_string(&over_max).unwrap()).unwrap();
+        let error = BillingThresholds::load(&path).unwrap_err();
+        assert_eq!(crate::utils::exit_code::for_error(&error), crate::utils::exit_code::CONFIG_ERROR, "{:#}", error);
+    }
+    
+    #[test]
+    fn test_actions_minutes_sums_actions_only() {
+        let response = r#"{"usageItems": [
+            {"product": "actions", "unitType": "Minutes", "quantity": 120.5},
+            {"product": "actions", "unitType": "GigabyteHours", "quantity": 3.0},
+            {"product": "packages", "unitType": "Minutes", "quantity": 50.0},
+            {"product": "actions", "unitType": "Minutes", "quantity": 9.5}
+        ]}"#;
+        
+        assert_eq!(actions_minutes(response).unwrap(), 130.0);
+        assert_eq!(actions_minutes(r#"{"usageItems": []}"#).unwrap(), 0.0);
+        assert!(actions_minutes("not json").is_err());
+    }
+    
+    #[test]
+    fn test_plan_included_minutes() {
+        assert_eq!(plan_included_minutes("pro"), Some(3000.0));
+        assert_eq!(plan_included_minutes("team"), Some(3000.0));
+        assert_eq!(plan_included_minutes("free"), None);
+        assert_eq!(plan_included_minutes("something_new"), None);
+        
+        // Cache entries written before plans were read count as free tier
+        let cached: BillingInfo = serde_json::from_str(
+            r#"{"username": "alice", "total_core_hours_used": 60.0, "total_minutes_used": 1800.0,
+                "included_minutes": 2000.0, "hours_remaining": 60.0, "is_exhausted": false, "is_warning": false}"#,
+        ).unwrap();
+        assert_eq!((cached.plan_name.as_deref(), cached.included_core_hours, cached.usage_percent()), (None, 120.0, 50.0));
+    }
+    
+    #[test]
+    fn test_billing_endpoint_per_owner_type() {
+        assert_eq!(
+            BillingMonitor::billing_endpoint("alice", OwnerType::User),
+            "/users/alice/settings/billing/usage"
+        );
+        assert_eq!(
+            BillingMonitor::billing_endpoint("acme-labs", OwnerType::Organization),
+            "/orgs/acme-labs/settings/billing/usage"
+        );
+    }
 }
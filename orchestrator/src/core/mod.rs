@@ -2,10 +2,15 @@
 
 pub mod account;
 pub mod billing;
+pub mod paths;
 pub mod proxy;
+pub mod settings;
 pub mod state;
+pub mod token_report;
 
 pub use account::AccountManager;
 pub use billing::{BillingMonitor, BillingInfo};
+pub use paths::Paths;
 pub use proxy::ProxyManager;
+pub use settings::Settings;
 pub use state::{StateManager, OrchestratorState};
@@ -0,0 +1,256 @@
+// src/core/paths.rs - Config and cache file locations
+
+use anyhow::{Result, Context};
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+
+/// Every file the orchestrator keeps under `cache/`.
+pub const CACHE_FILES: &[&str] = &[
+    "active.json",
+    "tokenmap.json",
+    "ownermap.json",
+    "proxymap.json",
+    "billing_cache.json",
+    "rate_limits.json",
+    "events.jsonl",
+    "deploy_checkpoint.json",
+];
+
+/// Where everything lives for one config directory. Build it from the
+/// config dir and ask it for paths rather than joining them by hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    config_dir: PathBuf,
+    cache_dir: PathBuf,
+}
+
+impl Paths {
+    pub fn new(config_dir: &Path) -> Self {
+        Self {
+            config_dir: config_dir.to_path_buf(),
+            cache_dir: config_dir.join("cache"),
+        }
+    }
+    
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+    
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+    
+    pub fn ensure_cache_dir(&self) -> Result<()> {
+        fs::create_dir_all(&self.cache_dir)
+            .with_context(|| format!("Failed to create {}", self.cache_dir.display()))
+    }
+    
+    pub fn tokens_file(&self) -> PathBuf {
+        self.config_dir.join("tokens.txt")
+    }
+    
+    pub fn proxies_file(&self) -> PathBuf {
+        self.config_dir.join("proxies.txt")
+    }
+    
+    pub fn nodes_file(&self) -> PathBuf {
+        self.config_dir.join("nodes.txt")
+    }
+    
+    pub fn wallets_file(&self) -> PathBuf {
+        self.config_dir.join("wallets.txt")
+    }
+    
+    pub fn setup_file(&self) -> PathBuf {
+        self.config_dir.join("setup.json")
+    }
+    
+    /// Billing thresholds edited from the Advanced menu
+    pub fn thresholds_file(&self) -> PathBuf {
+        self.config_dir.join("thresholds.json")
+    }
+    
+    pub fn secrets_manifest(&self) -> PathBuf {
+        self.config_dir.join("secrets.toml")
+    }
+    
+    pub fn secrets_env(&self) -> PathBuf {
+        self.config_dir.join("secrets.env")
+    }
+    
+    pub fn state_file(&self) -> PathBuf {
+        self.cache_dir.join("active.json")
+    }
+    
+    /// Held by the one instance allowed to change the fork chain
+    pub fn state_lock(&self) -> PathBuf {
+        self.cache_dir.join("active.lock")
+    }
+    
+    /// Token to username
+    pub fn tokenmap(&self) -> PathBuf {
+        self.cache_dir.join("tokenmap.json")
+    }
+    
+    /// Owner to user/organization
+    pub fn ownermap(&self) -> PathBuf {
+        self.cache_dir.join("ownermap.json")
+    }
+    
+    /// Token to proxy
+    pub fn proxymap(&self) -> PathBuf {
+        self.cache_dir.join("proxymap.json")
+    }
+    
+    pub fn billing_cache(&self) -> PathBuf {
+        self.cache_dir.join("billing_cache.json")
+    }
+    
+    /// Last REST quota reading per token
+    pub fn rate_limit_cache(&self) -> PathBuf {
+        self.cache_dir.join("rate_limits.json")
+    }
+    
+    pub fn events_file(&self) -> PathBuf {
+        self.cache_dir.join("events.jsonl")
+    }
+    
+    /// One line per fork the orchestrator deleted
+    pub fn fork_archive(&self) -> PathBuf {
+        self.cache_dir.join("fork_archive.jsonl")
+    }
+    
+    /// Repos an unfinished secrets deployment already covered
+    pub fn deploy_checkpoint(&self) -> PathBuf {
+        self.cache_dir.join("deploy_checkpoint.json")
+    }
+    
+    /// HMAC of each secret value last written, per repo
+    pub fn secret_hashes(&self) -> PathBuf {
+        self.cache_dir.join("secrets_manifest.json")
+    }
+    
+    /// Encrypted copy of the secrets set through the orchestrator
+    pub fn vault(&self) -> PathBuf {
+        self.cache_dir.join("vault.bin")
+    }
+    
+    pub fn backup_dir(&self) -> PathBuf {
+        self.cache_dir.join("backups")
+    }
+    
+    /// Places older builds wrote cache files to: the config dir itself, and
+    /// `config/cache` under the working directory, which some modules
+    /// hard-coded regardless of `--config-dir`. The latter is skipped when
+    /// `config/` holds a tokens.txt, since it is then a config dir in its own
+    /// right.
+    pub fn legacy_cache_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.config_dir.clone()];
+        
+        if let Ok(cwd) = std::env::current_dir() {
+            let hard_coded = Paths::new(&cwd.join("config"));
+            if !hard_coded.tokens_file().exists() && !same_dir(hard_coded.cache_dir(), &self.cache_dir) {
+                dirs.push(hard_coded.cache_dir);
+            }
+        }
+        
+        dirs
+    }
+    
+    /// Moves cache files found in legacy locations into the cache dir.
+    /// A file already present in the cache dir wins; the stray copy is left
+    /// in place with a warning. Returns the files that were moved.
+    pub fn migrate_legacy_cache(&self) -> Result<Vec<PathBuf>> {
+        self.migrate_from(&self.legacy_cache_dirs())
+    }
+    
+    pub fn migrate_from(&self, legacy_dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let mut moved = Vec::new();
+        
+        for dir in legacy_dirs {
+            for name in CACHE_FILES {
+                let stray = dir.join(name);
+                if !stray.is_file() {
+                    continue;
+                }
+                
+                let target = self.cache_dir.join(name);
+                if target.exists() {
+                    warn!("Ignoring {}: {} already exists", stray.display(), target.display());
+                    continue;
+                }
+                
+                self.ensure_cache_dir()?;
+                move_file(&stray, &target)?;
+                info!("Moved {} to {}", stray.display(), target.display());
+                moved.push(target);
+            }
+        }
+        
+        Ok(moved)
+    }
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Rename, falling back to copy and delete across filesystems.
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    
+    fs::copy(from, to)
+        .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+    fs::remove_file(from)
+        .with_context(|| format!("Failed to remove {}", from.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_cache_files_live_under_cache_dir() {
+        let paths = Paths::new(Path::new("/etc/nexus"));
+        
+        assert_eq!(paths.cache_dir(), Path::new("/etc/nexus/cache"));
+        assert_eq!(paths.tokens_file(), Path::new("/etc/nexus/tokens.txt"));
+        
+        for file in [paths.state_file(), paths.tokenmap(), paths.ownermap(), paths.proxymap(), paths.billing_cache(), paths.events_file(), paths.deploy_checkpoint()] {
+            assert_eq!(file.parent(), Some(paths.cache_dir()));
+            assert!(CACHE_FILES.contains(&file.file_name().unwrap().to_str().unwrap()));
+        }
+    }
+    
+    #[test]
+    fn test_migrate_moves_stray_files_once() {
+        let temp_dir = tempdir().unwrap();
+        let paths = Paths::new(&temp_dir.path().join("config"));
+        let legacy = temp_dir.path().join("old-cache");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::create_dir_all(paths.config_dir()).unwrap();
+        
+        fs::write(legacy.join("tokenmap.json"), "{\"ghp_a\": \"alice\"}").unwrap();
+        fs::write(paths.config_dir().join("proxymap.json"), "{}").unwrap();
+        fs::write(legacy.join("notes.txt"), "not a cache file").unwrap();
+        
+        let moved = paths.migrate_from(&[paths.config_dir().to_path_buf(), legacy.clone()]).unwrap();
+        assert_eq!(moved, vec![paths.proxymap(), paths.tokenmap()]);
+        assert_eq!(fs::read_to_string(paths.tokenmap()).unwrap(), "{\"ghp_a\": \"alice\"}");
+        assert!(!legacy.join("tokenmap.json").exists());
+        assert!(legacy.join("notes.txt").exists());
+        
+        // The canonical copy wins over a later stray one
+        fs::write(legacy.join("tokenmap.json"), "{}").unwrap();
+        assert!(paths.migrate_from(std::slice::from_ref(&legacy)).unwrap().is_empty());
+        assert!(legacy.join("tokenmap.json").exists());
+        assert_eq!(fs::read_to_string(paths.tokenmap()).unwrap(), "{\"ghp_a\": \"alice\"}");
+    }
+}
@@ -0,0 +1,674 @@
+// src/core/settings.rs - Central orchestrator.toml settings
+
+use anyhow::{Result, Context, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use crate::utils::delay::Delays;
+use crate::utils::exit_code::ConfigError;
+use crate::utils::retry::RetryConfig;
+
+pub const SETTINGS_FILE: &str = "orchestrator.toml";
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Installs the settings for this process. The first call wins, so load
+/// before any module reads them.
+pub fn init(settings: Settings) -> &'static Settings {
+    SETTINGS.get_or_init(|| settings)
+}
+
+/// The process-wide settings, or the defaults if `init` was never called.
+pub fn get() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub github: GitHubSettings,
+    pub rotation: RotationSettings,
+    pub monitor: MonitorSettings,
+    /// Named retry profiles; `default` and `github` always exist.
+    pub retries: BTreeMap<String, RetryConfig>,
+    pub delays: Delays,
+    pub proxy: ProxySettings,
+    pub ui: UiSettings,
+    pub alerts: AlertSettings,
+    pub pool: PoolSettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitHubSettings {
+    pub host: String,
+    pub workflow_file: String,
+    pub branch: String,
+    pub transport: GitHubTransport,
+    /// How long a new fork may take to get its git data before creation fails
+    pub fork_ready_timeout_secs: u64,
+    /// Name requested for new forks, `{index}` being the account's token
+    /// index; unset keeps the parent's name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fork_name: Option<String>,
+    /// GitHub Environment the Nexus secrets go to, created on deploy if
+    /// missing; unset stores them as repository secrets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secrets_environment: Option<String>,
+    /// Only warn when a written secret cannot be verified on GitHub,
+    /// instead of failing the write
+    pub lenient_secret_verification: bool,
+    /// Deploy workflow files with git clone, commit and push instead of the
+    /// contents API
+    pub deploy_with_git: bool,
+    /// Actions a new fork may run once Actions is turned on for it
+    pub allowed_actions: AllowedActions,
+}
+
+impl Default for GitHubSettings {
+    fn default() -> Self {
+        Self {
+            host: "github.com".to_string(),
+            workflow_file: "nexus.yml".to_string(),
+            branch: "main".to_string(),
+            transport: GitHubTransport::Auto,
+            fork_ready_timeout_secs: 120,
+            fork_name: None,
+            secrets_environment: None,
+            lenient_secret_verification: false,
+            deploy_with_git: false,
+            allowed_actions: AllowedActions::All,
+        }
+    }
+}
+
+impl GitHubSettings {
+    /// Points a `gh` invocation at the configured host (GitHub Enterprise).
+    pub fn apply_host(&self, cmd: &mut Command) {
+        if self.host != "github.com" {
+            cmd.env("GH_HOST", &self.host);
+        }
+    }
+    
+    /// `fork_name` for the account with token index `index`.
+    pub fn fork_name_for(&self, index: usize) -> Option<String> {
+        self.fork_name
+            .as_ref()
+            .map(|template| template.replace("{index}", &index.to_string()))
+    }
+    
+    /// REST API root for the configured host.
+    pub fn api_base(&self) -> String {
+        if self.host == "github.com" {
+            "https://api.github.com".to_string()
+        } else {
+            format!("https://{}/api/v3", self.host)
+        }
+    }
+}
+
+/// How GitHubClient talks to the REST API.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitHubTransport {
+    /// gh when it is on PATH, native HTTP otherwise
+    Auto,
+    /// Always shell out to gh
+    Gh,
+    /// Built-in HTTP client; gh is not needed
+    Native,
+}
+
+/// GitHub's `allowed_actions` repo permission.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AllowedActions {
+    /// Any action or reusable workflow
+    All,
+    /// Only those in the repo's owner
+    LocalOnly,
+    /// Those picked in the repo's settings
+    Selected,
+}
+
+impl AllowedActions {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AllowedActions::All => "all",
+            AllowedActions::LocalOnly => "local_only",
+            AllowedActions::Selected => "selected",
+        }
+    }
+}
+
+/// Accounts given different `token priority` values rotate by priority,
+/// passing by exhausted forks, under either strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationStrategy {
+    /// Next token index, wrapping around
+    Sequential,
+    /// Next token index whose fork is not already exhausted
+    SkipExhausted,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RotationSettings {
+    /// Core-hours at which an account is flagged as low
+    pub warning_threshold: f32,
+    /// Core-hours at which an account is treated as exhausted and rotated
+    pub critical_threshold: f32,
+    /// Pause between disabling the old fork's workflow and switching
+    pub cooldown_secs: u64,
+    pub strategy: RotationStrategy,
+    /// A missing active fork is re-forked at most once per this window
+    pub heal_cooldown_minutes: i64,
+    /// How long rotation waits for the exhausted fork's cancelled runs to stop
+    pub cancel_runs_timeout_secs: u64,
+    /// Disable every managed workflow of the exhausted fork, not only
+    /// `github.workflow_file`
+    pub disable_all_workflows: bool,
+    /// A completed run counts as healthy when at least this percentage of
+    /// its finished jobs succeeded, whatever its overall conclusion
+    pub healthy_job_percent: f64,
+}
+
+impl Default for RotationSettings {
+    fn default() -> Self {
+        Self {
+            warning_threshold: 118.0,
+            critical_threshold: 119.5,
+            cooldown_secs: 5,
+            strategy: RotationStrategy::Sequential,
+            heal_cooldown_minutes: 60,
+            cancel_runs_timeout_secs: 60,
+            disable_all_workflows: false,
+            healthy_job_percent: 90.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitorSettings {
+    pub watch_interval_minutes: u64,
+    pub dashboard_refresh_secs: u64,
+    /// Dashboard marks fork data older than this as stale
+    pub stale_after_minutes: i64,
+    pub billing_cache_ttl_minutes: i64,
+    /// Poll interval while watching a workflow run, after the first
+    /// `run_poll_fast_minutes`
+    pub run_poll_secs: u64,
+    /// Poll interval during the first minutes of a watch, when jobs start
+    pub run_poll_fast_secs: u64,
+    pub run_poll_fast_minutes: u64,
+    /// A watched run still going after this long is given up on
+    pub stuck_run_minutes: u64,
+    /// A run still queued after this long is reported as stuck: the account
+    /// is at its concurrency limit or Actions is disabled on the fork
+    pub stuck_queued_minutes: u64,
+    /// `watch` commits to a fork untouched for this many days, before
+    /// GitHub disables its scheduled workflows at 60
+    pub keep_alive_days: u64,
+    /// Tokens expiring within this many days are flagged by `status`,
+    /// `billing` and `watch`
+    pub token_expiry_warning_days: u64,
+    /// Validations older than this many hours need redoing; menus and
+    /// `status` flag such tokens, `validate --stale-only` refreshes them
+    pub token_revalidate_hours: u64,
+    /// A single GitHub API attempt slower than this is logged as a warning
+    pub slow_call_ms: u64,
+    /// How often API call timings are appended to logs/api_metrics.jsonl
+    pub metrics_flush_secs: u64,
+    /// Billing checks warn when a token has fewer REST requests left
+    pub rate_limit_floor: u32,
+}
+
+impl Default for MonitorSettings {
+    fn default() -> Self {
+        Self {
+            watch_interval_minutes: 30,
+            dashboard_refresh_secs: 5,
+            stale_after_minutes: 10,
+            billing_cache_ttl_minutes: 10,
+            run_poll_secs: 60,
+            run_poll_fast_secs: 10,
+            run_poll_fast_minutes: 2,
+            stuck_run_minutes: 360,
+            stuck_queued_minutes: 30,
+            keep_alive_days: 50,
+            token_expiry_warning_days: 14,
+            token_revalidate_hours: 24,
+            slow_call_ms: 10000,
+            metrics_flush_secs: 60,
+            rate_limit_floor: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMappingMode {
+    /// Token N uses proxy line N; needs at least one proxy per token
+    Index,
+    /// Tokens share proxies in turn when there are fewer proxies than tokens
+    RoundRobin,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProxySettings {
+    pub mapping_mode: ProxyMappingMode,
+    /// A proxy passes when any of these answers HTTP 200
+    pub test_urls: Vec<String>,
+    /// Once proxies are configured, skip GitHub calls for a token that has
+    /// none instead of sending them from this machine's own IP
+    pub require_proxy: bool,
+}
+
+impl Default for ProxySettings {
+    fn default() -> Self {
+        Self {
+            mapping_mode: ProxyMappingMode::Index,
+            test_urls: vec!["https://api.github.com/".to_string()],
+            require_proxy: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiSettings {
+    pub color: bool,
+    /// Plain ASCII markers instead of emoji and box drawing
+    pub ascii: bool,
+}
+
+impl Default for UiSettings {
+    fn default() -> Self {
+        Self {
+            color: true,
+            ascii: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertSettings {
+    /// Alert channel file, relative to the config directory
+    pub config: PathBuf,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            config: PathBuf::from("alerts.json"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PoolSettings {
+    /// Accounts checked at once by validation and billing sweeps
+    pub workers: usize,
+    /// Retries shared by all calls of one sweep
+    pub retry_budget: u32,
+    /// Forks given their secrets at once by `deploy`
+    pub deploy_workers: usize,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self {
+            workers: 4,
+            retry_budget: 20,
+            deploy_workers: 3,
+        }
+    }
+}
+
+fn default_retry_profiles() -> BTreeMap<String, RetryConfig> {
+    ["default", "github"]
+        .iter()
+        .map(|name| (name.to_string(), RetryConfig::default()))
+        .collect()
+}
+
+impl Settings {
+    /// Loads `orchestrator.toml` from the config directory. A missing file
+    /// means all defaults; parse and range errors are config errors.
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = config_dir.join(SETTINGS_FILE);
+        
+        if !path.exists() {
+            return Ok(Self::default().with_default_profiles());
+        }
+        
+        let content = fs::read_to_string(&path)
+            .with_context(|| ConfigError::new(format!("Failed to read {}", path.display())))?;
+        
+        let settings = Self::parse(&content)?;
+        let errors = settings.validate(&content);
+        
+        if !errors.is_empty() {
+            bail!(ConfigError::new(format!("Invalid {}:\n  {}", SETTINGS_FILE, errors.join("\n  "))));
+        }
+        
+        Ok(settings)
+    }
+    
+    /// Parses TOML text, filling every missing field with its default.
+    pub fn parse(content: &str) -> Result<Self> {
+        let settings: Self = toml::from_str(content)
+            .map_err(|e| ConfigError::new(format!("{}: {}", SETTINGS_FILE, e)))?;
+        
+        Ok(settings.with_default_profiles())
+    }
+    
+    fn with_default_profiles(mut self) -> Self {
+        for (name, profile) in default_retry_profiles() {
+            self.retries.entry(name).or_insert(profile);
+        }
+        self
+    }
+    
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self).context("Failed to serialize settings")
+    }
+    
+    /// The named retry profile, falling back to `default`.
+    pub fn retry_profile(&self, name: &str) -> RetryConfig {
+        self.retries
+            .get(name)
+            .or_else(|| self.retries.get("default"))
+            .cloned()
+            .unwrap_or_default()
+    }
+    
+    pub fn alerts_file(&self, config_dir: &Path) -> PathBuf {
+        config_dir.join(&self.alerts.config)
+    }
+    
+    /// Range and consistency checks that the schema cannot express. Each
+    /// error names the field and, when found in `content`, its line.
+    pub fn validate(&self, content: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, section: &str, key: &str, message: String| {
+            if !ok {
+                let location = match find_line(content, section, key) {
+                    Some(line) => format!("{}:{}: ", SETTINGS_FILE, line),
+                    None => String::new(),
+                };
+                errors.push(format!("{}{}.{} {}", location, section, key, message));
+            }
+        };
+        
+        let github = &self.github;
+        check(!github.host.trim().is_empty(), "github", "host", "must not be empty".to_string());
+        check(
+            github.workflow_file.ends_with(".yml") || github.workflow_file.ends_with(".yaml"),
+            "github",
+            "workflow_file",
+            format!("'{}' must be a .yml or .yaml file", github.workflow_file),
+        );
+        check(!github.branch.trim().is_empty(), "github", "branch", "must not be empty".to_string());
+        check(
+            github.fork_ready_timeout_secs > 0,
+            "github",
+            "fork_ready_timeout_secs",
+            format!("({}) must be at least 1", github.fork_ready_timeout_secs),
+        );
+        if let Some(name) = github.fork_name_for(0) {
+            check(
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)),
+                "github",
+                "fork_name",
+                format!("'{}' must be a repo name: letters, digits, '-', '_' or '.'", name),
+            );
+        }
+        
+        let rotation = &self.rotation;
+        check(
+            rotation.warning_threshold > 0.0,
+            "rotation",
+            "warning_threshold",
+            format!("({}) must be positive", rotation.warning_threshold),
+        );
+        check(
+            rotation.warning_threshold < rotation.critical_threshold,
+            "rotation",
+            "critical_threshold",
+            format!(
+                "({}) must be above rotation.warning_threshold ({})",
+                rotation.critical_threshold, rotation.warning_threshold
+            ),
+        );
+        check(
+            (0.0..=100.0).contains(&rotation.healthy_job_percent),
+            "rotation",
+            "healthy_job_percent",
+            format!("({}) must be between 0 and 100", rotation.healthy_job_percent),
+        );
+        
+        let monitor = &self.monitor;
+        for (key, value) in [
+            ("watch_interval_minutes", monitor.watch_interval_minutes as i64),
+            ("dashboard_refresh_secs", monitor.dashboard_refresh_secs as i64),
+            ("stale_after_minutes", monitor.stale_after_minutes),
+            ("billing_cache_ttl_minutes", monitor.billing_cache_ttl_minutes),
+            ("run_poll_secs", monitor.run_poll_secs as i64),
+            ("run_poll_fast_secs", monitor.run_poll_fast_secs as i64),
+            ("stuck_run_minutes", monitor.stuck_run_minutes as i64),
+            ("stuck_queued_minutes", monitor.stuck_queued_minutes as i64),
+            ("keep_alive_days", monitor.keep_alive_days as i64),
+            ("token_expiry_warning_days", monitor.token_expiry_warning_days as i64),
+            ("token_revalidate_hours", monitor.token_revalidate_hours as i64),
+            ("slow_call_ms", monitor.slow_call_ms as i64),
+            ("metrics_flush_secs", monitor.metrics_flush_secs as i64),
+        ] {
+            check(value > 0, "monitor", key, format!("({}) must be at least 1", value));
+        }
+        
+        for (name, profile) in &self.retries {
+            let section = format!("retries.{}", name);
+            check(profile.max_attempts >= 1, &section, "max_attempts", "must be at least 1".to_string());
+            check(
+                profile.multiplier >= 1.0,
+                &section,
+                "multiplier",
+                format!("({}) must be at least 1.0", profile.multiplier),
+            );
+            check(
+                profile.initial_delay_ms <= profile.max_delay_ms,
+                &section,
+                "max_delay_ms",
+                format!(
+                    "({}) must not be below initial_delay_ms ({})",
+                    profile.max_delay_ms, profile.initial_delay_ms
+                ),
+            );
+        }
+        
+        check(!self.proxy.test_urls.is_empty(), "proxy", "test_urls", "needs at least one URL".to_string());
+        for url in &self.proxy.test_urls {
+            check(
+                url.starts_with("http://") || url.starts_with("https://"),
+                "proxy",
+                "test_urls",
+                format!("'{}' must be an http(s) URL", url),
+            );
+        }
+        
+        check(
+            !self.alerts.config.as_os_str().is_empty(),
+            "alerts",
+            "config",
+            "must not be empty".to_string(),
+        );
+        
+        check(
+            self.pool.workers >= 1,
+            "pool",
+            "workers",
+            format!("({}) must be at least 1", self.pool.workers),
+        );
+        
+        check(
+            self.pool.deploy_workers >= 1,
+            "pool",
+            "deploy_workers",
+            format!("({}) must be at least 1", self.pool.deploy_workers),
+        );
+        
+        errors
+    }
+}
+
+/// 1-based line of `key = ...` inside `[section]`, if the file sets it.
+fn find_line(content: &str, section: &str, key: &str) -> Option<usize> {
+    let mut current = String::new();
+    
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = header.trim().to_string();
+        } else if current == section {
+            if let Some((name, _)) = line.split_once('=') {
+                if name.trim() == key {
+                    return Some(i + 1);
+                }
+            }
+        }
+    }
+    
+    None
+}
+
+/// `config show`: the effective settings after merging defaults, as TOML or JSON.
+pub fn show_settings(config_dir: &Path, json: bool) -> Result<()> {
+    let settings = Settings::load(config_dir)?;
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&settings)?);
+    } else {
+        println!("# Effective settings ({} + defaults)", config_dir.join(SETTINGS_FILE).display());
+        print!("{}", settings.to_toml()?);
+    }
+    
+    Ok(())
+}
+
+/// `config validate`: prints every problem and returns whether the file is valid.
+pub fn validate_settings(config_dir: &Path) -> Result<bool> {
+    let path = config_dir.join(SETTINGS_FILE);
+    
+    if !path.exists() {
+        println!("ℹ️  {} not found, all defaults apply", path.display());
+        return Ok(true);
+    }
+    
+    let content = fs::read_to_string(&path)
+        .with_context(|| ConfigError::new(format!("Failed to read {}", path.display())))?;
+    
+    let errors = match Settings::parse(&content) {
+        Ok(settings) => settings.validate(&content),
+        Err(e) => vec![e.to_string()],
+    };
+    
+    if errors.is_empty() {
+        println!("✅ {} is valid", path.display());
+        return Ok(true);
+    }
+    
+    for error in &errors {
+        println!("❌ {}", error);
+    }
+    
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_round_trip() {
+        let mut settings = Settings::default().with_default_profiles();
+        settings.github.host = "github.example.com".to_string();
+        settings.github.transport = GitHubTransport::Native;
+        settings.github.fork_name = Some("nexus-chain-{index}".to_string());
+        assert_eq!(settings.github.fork_name_for(3).as_deref(), Some("nexus-chain-3"));
+        assert_eq!(settings.github.api_base(), "https://github.example.com/api/v3");
+        settings.rotation.strategy = RotationStrategy::SkipExhausted;
+        settings.proxy.mapping_mode = ProxyMappingMode::RoundRobin;
+        settings.delays.fork_poll_ms = 0;
+        settings.pool.workers = 8;
+        settings.retries.insert("billing".to_string(), RetryConfig {
+            max_attempts: 5,
+            ..RetryConfig::default()
+        });
+        
+        let text = settings.to_toml().unwrap();
+        let parsed = Settings::parse(&text).unwrap();
+        
+        assert_eq!(parsed, settings);
+        assert!(parsed.validate(&text).is_empty());
+    }
+    
+    #[test]
+    fn test_empty_and_partial_files_merge_defaults() {
+        let empty = Settings::parse("").unwrap();
+        assert_eq!(empty, Settings::default().with_default_profiles());
+        
+        let partial = Settings::parse(
+            "[rotation]\nwarning_threshold = 100.0\n\n[retries.github]\nmax_attempts = 6\n"
+        ).unwrap();
+        
+        assert_eq!(partial.rotation.warning_threshold, 100.0);
+        assert_eq!(partial.rotation.critical_threshold, 119.5);
+        assert_eq!(partial.github, GitHubSettings::default());
+        assert_eq!(partial.retry_profile("github").max_attempts, 6);
+        assert_eq!(partial.retry_profile("github").initial_delay_ms, 1000);
+        assert_eq!(partial.retry_profile("default"), RetryConfig::default());
+        assert_eq!(partial.retry_profile("unknown"), RetryConfig::default());
+    }
+    
+    #[test]
+    fn test_schema_errors_name_the_field() {
+        let err = Settings::parse("[rotation]\nthreshold = 1.0\n").unwrap_err();
+        assert!(err.to_string().contains("threshold"), "{}", err);
+        assert!(err.to_string().contains("line 2"), "{}", err);
+        
+        let err = Settings::parse("[monitor]\nrun_poll_secs = \"fast\"\n").unwrap_err();
+        assert!(err.to_string().contains("run_poll_secs"), "{}", err);
+        
+        let content = "[github]\nbranch = \"main\"\n\n[rotation]\nwarning_threshold = 119.8\ncritical_threshold = 119.5\n";
+        let errors = Settings::parse(content).unwrap().validate(content);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("orchestrator.toml:6: rotation.critical_threshold"), "{}", errors[0]);
+        
+        let content = "[github]\nfork_name = \"nexus chain/{index}\"\n";
+        let errors = Settings::parse(content).unwrap().validate(content);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("orchestrator.toml:2: github.fork_name"), "{}", errors[0]);
+    }
+    
+    #[test]
+    fn test_load_missing_and_invalid_files() {
+        let temp_dir = tempdir().unwrap();
+        assert_eq!(Settings::load(temp_dir.path()).unwrap().monitor, MonitorSettings::default());
+        
+        fs::write(temp_dir.path().join(SETTINGS_FILE), "[monitor]\nrun_poll_secs = 0\n").unwrap();
+        let err = Settings::load(temp_dir.path()).unwrap_err();
+        assert_eq!(crate::utils::exit_code::for_error(&err), crate::utils::exit_code::CONFIG_ERROR);
+        assert!(!validate_settings(temp_dir.path()).unwrap());
+    }
+}
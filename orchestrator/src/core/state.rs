@@ -2,21 +2,53 @@
 
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::fs::{self, File, TryLockError};
 use std::path::{Path, PathBuf};
 use chrono::{DateTime, Utc};
 use log::{info, warn, error};
+use crate::app::AppContext;
+use crate::core::account::{self, AccountManager, OwnerType};
+use crate::core::paths::Paths;
+use crate::core::settings;
+use crate::monitor::{ArchivedFork, ForkArchive};
+use crate::monitor::health;
+use crate::utils::exit_code::LockError;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForkChainNode {
     pub pat_index: usize,
+    /// Owner of the fork: the account's login, or its organization
     pub username: String,
+    #[serde(default)]
+    pub owner_type: OwnerType,
     pub repo: String,
     pub parent: Option<String>,
     pub billing_used: f32,
     pub status: ForkStatus,
     pub created_at: DateTime<Utc>,
     pub last_updated: DateTime<Utc>,
+    #[serde(default)]
+    pub last_run: Option<RunSnapshot>,
+}
+
+impl ForkChainNode {
+    /// `@login` for users, `login [org]` for organization-owned forks.
+    pub fn owner_label(&self) -> String {
+        match self.owner_type {
+            OwnerType::User => format!("@{}", self.username),
+            OwnerType::Organization => format!("{} [org]", self.username),
+        }
+    }
+}
+
+/// Last known workflow run of a fork, as recorded by the orchestrator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSnapshot {
+    pub run_id: u64,
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub checked_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -25,10 +57,18 @@ pub enum ForkStatus {
     Exhausted,
     Disabled,
     Source,
+    /// The repository was deleted; the node is kept for chain history.
+    Deleted,
 }
 
+/// Bumped whenever the layout of active.json changes. Files written before
+/// versioning deserialize as 0.
+pub const STATE_SCHEMA_VERSION: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrchestratorState {
+    #[serde(default)]
+    pub schema_version: u32,
     pub fork_chain: Vec<ForkChainNode>,
     pub current_active_index: usize,
     pub total_accounts: usize,
@@ -38,6 +78,7 @@ pub struct OrchestratorState {
 impl Default for OrchestratorState {
     fn default() -> Self {
         Self {
+            schema_version: STATE_SCHEMA_VERSION,
             fork_chain: Vec::new(),
             current_active_index: 0,
             total_accounts: 0,
@@ -46,21 +87,33 @@ impl Default for OrchestratorState {
     }
 }
 
+impl OrchestratorState {
+    /// Whether the account at `pat_index` owns a fork marked exhausted.
+    pub fn is_exhausted(&self, pat_index: usize) -> bool {
+        self.fork_chain
+            .iter()
+            .any(|n| n.pat_index == pat_index && n.status == ForkStatus::Exhausted)
+    }
+}
+
+/// Exclusive hold on the state of one config directory, released on drop
+/// (or when the process dies, so a crash never leaves it stuck).
+pub struct StateLock {
+    _file: File,
+}
+
 pub struct StateManager {
-    cache_dir: PathBuf,
+    paths: Paths,
     state_file: PathBuf,
 }
 
 impl StateManager {
-    pub fn new(config_dir: &Path) -> Result<Self> {
-        let cache_dir = config_dir.join("cache");
-        fs::create_dir_all(&cache_dir)?;
-        
-        let state_file = cache_dir.join("active.json");
+    pub fn new(paths: &Paths) -> Result<Self> {
+        paths.ensure_cache_dir()?;
         
         Ok(Self {
-            cache_dir,
-            state_file,
+            paths: paths.clone(),
+            state_file: paths.state_file(),
         })
     }
     
@@ -71,10 +124,20 @@ impl StateManager {
         }
         
         let content = fs::read_to_string(&self.state_file)
-            .context("Failed to read state file")?;
+            .with_context(|| format!("Failed to read state file {}", self.state_file.display()))?;
         
-        let state: OrchestratorState = serde_json::from_str(&content)
-            .context("Failed to parse state JSON")?;
+        let mut state: OrchestratorState = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse state JSON in {}", self.state_file.display()))?;
+        
+        if state.schema_version > STATE_SCHEMA_VERSION {
+            warn!(
+                "State schema v{} is newer than this build (v{}); update the orchestrator",
+                state.schema_version, STATE_SCHEMA_VERSION
+            );
+        } else {
+            // v0 -> v1 only added the version field
+            state.schema_version = STATE_SCHEMA_VERSION;
+        }
         
         info!("Loaded state: {} accounts in chain", state.fork_chain.len());
         Ok(state)
@@ -87,16 +150,35 @@ impl StateManager {
         // Write to temp file first
         let temp_file = self.state_file.with_extension("tmp");
         fs::write(&temp_file, json)
-            .context("Failed to write temp state file")?;
+            .with_context(|| format!("Failed to write temp state file {}", temp_file.display()))?;
         
         // Atomic rename
         fs::rename(&temp_file, &self.state_file)
-            .context("Failed to rename state file")?;
+            .with_context(|| format!("Failed to rename state file to {}", self.state_file.display()))?;
         
         info!("State saved successfully");
         Ok(())
     }
     
+    /// Copies the state file as it is on disk, readable or not, into the
+    /// backup dir. Returns the copy, or None when there is no state file.
+    pub fn backup_state(&self) -> Result<Option<PathBuf>> {
+        if !self.state_file.exists() {
+            return Ok(None);
+        }
+        
+        let backup_dir = self.paths.backup_dir();
+        fs::create_dir_all(&backup_dir)
+            .with_context(|| format!("Failed to create {}", backup_dir.display()))?;
+        
+        let backup = backup_dir.join(format!("active-{}.json", Utc::now().format("%Y%m%d-%H%M%S-%3f")));
+        fs::copy(&self.state_file, &backup)
+            .with_context(|| format!("Failed to back up state file to {}", backup.display()))?;
+        
+        info!("State backed up to {}", backup.display());
+        Ok(Some(backup))
+    }
+    
     pub fn add_fork_node(&self, mut state: OrchestratorState, node: ForkChainNode) -> Result<OrchestratorState> {
         state.fork_chain.push(node);
         state.last_rotation = Some(Utc::now());
@@ -118,19 +200,65 @@ impl StateManager {
         Ok(state)
     }
     
+    /// Takes the state lock, failing with `LockError` while another
+    /// instance holds it.
+    pub fn lock(&self) -> Result<StateLock> {
+        let path = self.paths.state_lock();
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+        
+        match file.try_lock() {
+            Ok(()) => Ok(StateLock { _file: file }),
+            Err(TryLockError::WouldBlock) => Err(LockError(format!(
+                "Another orchestrator instance is using {} (lock: {})",
+                self.paths.config_dir().display(),
+                path.display()
+            ))
+            .into()),
+            Err(TryLockError::Error(e)) => Err(e).with_context(|| format!("Failed to lock {}", path.display())),
+        }
+    }
+    
     pub fn get_active_fork(&self, state: &OrchestratorState) -> Option<&ForkChainNode> {
         state.fork_chain.iter().find(|n| n.status == ForkStatus::Active)
     }
     
+    pub fn paths(&self) -> &Paths {
+        &self.paths
+    }
+    
+    pub fn cache_dir(&self) -> &Path {
+        self.paths.cache_dir()
+    }
+    
     pub fn get_cache_file(&self, filename: &str) -> PathBuf {
-        self.cache_dir.join(filename)
+        self.paths.cache_dir().join(filename)
     }
 }
 
-pub fn show_status() -> Result<()> {
-    let config_dir = PathBuf::from("config");
-    let state_mgr = StateManager::new(&config_dir)?;
-    let state = state_mgr.load_state()?;
+/// `status --json`: the state, with the chain's capacity from the billing
+/// cache next to its fields.
+#[derive(Serialize)]
+struct StatusJson<'a> {
+    #[serde(flatten)]
+    state: &'a OrchestratorState,
+    /// None until a billing check has been cached
+    capacity: Option<health::ChainCapacity>,
+}
+
+/// `history` adds the forks recorded in fork_archive.jsonl; JSON output
+/// is the state and the chain's capacity.
+pub fn show_status(ctx: &AppContext, json: bool, history: bool) -> Result<()> {
+    let state = ctx.state().load_state()?;
+    
+    if json {
+        let billing = ctx.health().map(|health| health.cached_billing_all()).unwrap_or_default();
+        let capacity = (!billing.is_empty()).then(|| {
+            health::ChainCapacity::from_infos(&billing, health::active_node_count(ctx.paths(), &state), Utc::now())
+        });
+        println!("{}", serde_json::to_string_pretty(&StatusJson { state: &state, capacity })?);
+        return Ok(());
+    }
     
     println!("\n╔═══════════════════════════════════════════════════════╗");
     println!("║          ORCHESTRATOR STATUS                          ║");
@@ -144,6 +272,12 @@ pub fn show_status() -> Result<()> {
         println!("Last Rotation: {}", last_rotation.format("%Y-%m-%d %H:%M:%S UTC"));
     }
     
+    // Usernames come from tokenmap.json, so status needs no tokens.txt
+    let mut cached = AccountManager::new(ctx.paths());
+    if let Err(e) = cached.load_cached() {
+        warn!("Token cache unreadable, showing usernames from the state file: {}", e);
+    }
+    
     println!("\nFork Chain:");
     println!("─────────────────────────────────────────────────────────");
     
@@ -153,13 +287,18 @@ pub fn show_status() -> Result<()> {
             ForkStatus::Exhausted => "🔴",
             ForkStatus::Disabled => "⚪",
             ForkStatus::Source => "🔵",
+            ForkStatus::Deleted => "⚫",
         };
         
         println!(
-            "{} [{:2}] @{:<20} | {} | Billing: {:.1}/120.0",
+            "{} [{:2}] {:<21} | {} | Billing: {:.1}/120.0",
             status_icon,
             i,
-            node.username,
+            match cached.get_account(node.pat_index) {
+                Some(account) if node.owner_type == OwnerType::User && node.status != ForkStatus::Source =>
+                    format!("@{}", account.username),
+                _ => node.owner_label(),
+            },
             node.repo,
             node.billing_used
         );
@@ -171,18 +310,142 @@ pub fn show_status() -> Result<()> {
     
     println!("─────────────────────────────────────────────────────────\n");
     
+    if history {
+        print_fork_history(&ForkArchive::new(ctx.paths()).read_all()?);
+    }
+    
+    // Expiry dates come from tokenmap.json, written by the last validation
+    let loaded = ctx.accounts().ok();
+    let accounts = loaded.as_deref().unwrap_or(&cached);
+    if !accounts.get_all_accounts().is_empty() {
+        let now = Utc::now();
+        println!("Tokens:");
+        for account in accounts.get_all_accounts() {
+            match health::ExpiringToken::flag(account, now) {
+                _ if account.quarantined_at.is_some() => println!(
+                    "⛔ [{:2}] {:<21} | quarantined {} (token rejected with 401; `token enable` once fixed)",
+                    account.index,
+                    account.display_name(),
+                    account.quarantined_at.unwrap_or(now).format("%Y-%m-%d %H:%M UTC")
+                ),
+                _ if !account.enabled => println!("⏸️  [{:2}] {:<21} | disabled", account.index, account.display_name()),
+                Some(expiring) => println!("⚠️  [{:2}] {}", account.index, expiring.message(now)),
+                None => println!("🔑 [{:2}] {:<21} | expires {}", account.index, account.display_name(), account.expiry_label()),
+            }
+            if let Some(notes) = &account.notes {
+                println!("       └─ {}", notes);
+            }
+            if account.stale {
+                println!("       └─ needs revalidation (`validate --stale-only`)");
+            }
+        }
+        println!();
+        
+        let order: Vec<String> = account::rotation_order(
+            accounts.total_accounts(),
+            state.current_active_index,
+            |index| accounts.is_enabled(index),
+            |index| accounts.priority(index),
+        )
+        .into_iter()
+        .filter_map(|index| accounts.get_account(index))
+        .map(|account| {
+            let mut label = format!("[{}] {}", account.index, account.display_name());
+            if account.priority > 0 {
+                label.push_str(&format!(" (priority {})", account.priority));
+            }
+            if state.is_exhausted(account.index) {
+                label.push_str(" (exhausted)");
+            }
+            label
+        })
+        .collect();
+        println!("Rotation Order: {}", order.join(" → "));
+        println!();
+    }
+    
+    let mut readings: Vec<_> = health::load_rate_limits(&ctx.paths().rate_limit_cache())
+        .into_iter()
+        .collect();
+    if !readings.is_empty() {
+        readings.sort_by(|a, b| a.1.username.cmp(&b.1.username));
+        let floor = settings::get().monitor.rate_limit_floor;
+        let now = Utc::now();
+        
+        println!("API Quota (last reading):");
+        for (token, reading) in &readings {
+            println!(
+                "{} {:<12} @{:<20} | {}/{} left | resets {} | checked {}m ago",
+                if reading.is_low(floor, now) { "⚠️ " } else { "🔑" },
+                token,
+                reading.username,
+                reading.rate.remaining,
+                reading.rate.limit,
+                reading.rate.reset_at.format("%H:%M UTC"),
+                (now - reading.checked_at).num_minutes().max(0)
+            );
+        }
+        println!();
+    }
+    
     Ok(())
 }
 
+fn print_fork_history(archived: &[ArchivedFork]) {
+    if archived.is_empty() {
+        println!("No deleted forks archived yet\n");
+        return;
+    }
+    
+    println!("Deleted Forks:");
+    println!("─────────────────────────────────────────────────────────");
+    
+    for fork in archived {
+        let runs = match fork.run_count {
+            Some(count) => format!("{} runs", count),
+            None => "runs unknown".to_string(),
+        };
+        println!(
+            "⚫ {} | {:?} | Billing: {:.1}/120.0 | {}, last {}",
+            fork.repo,
+            fork.status,
+            fork.billing_used,
+            runs,
+            fork.last_run_conclusion.as_deref().unwrap_or("unknown")
+        );
+        println!(
+            "       └─ {} → {}, deleted {}",
+            fork.created_at.format("%Y-%m-%d"),
+            fork.last_updated.format("%Y-%m-%d"),
+            fork.deleted_at.format("%Y-%m-%d %H:%M UTC")
+        );
+    }
+    
+    println!("─────────────────────────────────────────────────────────\n");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::tempdir;
     
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let temp_dir = tempdir().unwrap();
+        let state_mgr = StateManager::new(&Paths::new(temp_dir.path())).unwrap();
+        
+        let lock = state_mgr.lock().unwrap();
+        let err = StateManager::new(&Paths::new(temp_dir.path())).unwrap().lock().err().unwrap();
+        assert_eq!(crate::utils::exit_code::for_error(&err), crate::utils::exit_code::LOCKED);
+        
+        drop(lock);
+        state_mgr.lock().unwrap();
+    }
+    
     #[test]
     fn test_state_persistence() {
         let temp_dir = tempdir().unwrap();
-        let state_mgr = StateManager::new(temp_dir.path()).unwrap();
+        let state_mgr = StateManager::new(&Paths::new(temp_dir.path())).unwrap();
         
         let mut state = OrchestratorState::default();
         state.total_accounts = 5;
@@ -192,4 +455,14 @@ mod tests {
         let loaded = state_mgr.load_state().unwrap();
         assert_eq!(loaded.total_accounts, 5);
     }
+    
+    #[test]
+    fn test_show_status_uses_given_config_dir() {
+        let temp_dir = tempdir().unwrap();
+        let config_dir = temp_dir.path().join("prod");
+        
+        show_status(&AppContext::new(&config_dir).unwrap(), true, false).unwrap();
+        
+        assert!(config_dir.join("cache").is_dir());
+    }
 }
@@ -0,0 +1,224 @@
+// src/core/token_report.rs - Per-account token validation report (JSON or CSV)
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use crate::core::account::{AccountInfo, Capability, TokenLookup};
+use crate::core::proxy::ProxyConfig;
+
+/// Characters of the token kept in a report; never the whole token.
+pub const TOKEN_PREFIX_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenReportEntry {
+    pub index: usize,
+    pub username: String,
+    pub token_prefix: String,
+    pub valid: bool,
+    /// Capabilities the token has; None when GitHub did not tell
+    pub capabilities: Option<Vec<&'static str>>,
+    pub proxy_host: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+impl TokenReportEntry {
+    /// `lookup` is the validation result: the token's lookup, or why it failed.
+    pub fn new(account: &AccountInfo, lookup: Result<&TokenLookup, String>, proxy: Option<&ProxyConfig>) -> Self {
+        let (username, valid, capabilities, expires_at, error) = match lookup {
+            Ok(lookup) => (
+                lookup.username.clone(),
+                true,
+                lookup.capabilities.map(|capabilities| {
+                    Capability::ALL
+                        .iter()
+                        .filter(|capability| capabilities.has(**capability))
+                        .map(|capability| capability.name())
+                        .collect()
+                }),
+                lookup.expires_at,
+                None,
+            ),
+            Err(error) => (account.username.clone(), false, None, account.expires_at, Some(error)),
+        };
+        
+        Self {
+            index: account.index,
+            username,
+            token_prefix: account.token.chars().take(TOKEN_PREFIX_LEN).collect(),
+            valid,
+            capabilities,
+            proxy_host: proxy.map(|proxy| proxy.host.clone()),
+            expires_at,
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+impl ReportFormat {
+    /// `json` or `csv` alone print the report in that format; anything else
+    /// is a file path, written as CSV when it ends in `.csv`.
+    pub fn for_output(output: &str) -> (Self, Option<&Path>) {
+        match output.to_ascii_lowercase().as_str() {
+            "json" => (ReportFormat::Json, None),
+            "csv" => (ReportFormat::Csv, None),
+            _ => {
+                let path = Path::new(output);
+                let csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+                (if csv { ReportFormat::Csv } else { ReportFormat::Json }, Some(path))
+            }
+        }
+    }
+}
+
+const CSV_HEADER: &str = "index,username,token_prefix,valid,capabilities,proxy_host,expires_at,error";
+
+/// Quotes a CSV field when it holds a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn write_token_report(entries: &[TokenReportEntry], format: ReportFormat, out: &mut dyn Write) -> Result<()> {
+    match format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, entries)?;
+            writeln!(out)?;
+        }
+        ReportFormat::Csv => {
+            writeln!(out, "{}", CSV_HEADER)?;
+            for entry in entries {
+                let fields = [
+                    entry.index.to_string(),
+                    entry.username.clone(),
+                    entry.token_prefix.clone(),
+                    entry.valid.to_string(),
+                    entry.capabilities.as_ref().map(|c| c.join(" ")).unwrap_or_default(),
+                    entry.proxy_host.clone().unwrap_or_default(),
+                    entry.expires_at.map(|at| at.to_rfc3339()).unwrap_or_default(),
+                    entry.error.clone().unwrap_or_default(),
+                ];
+                let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                writeln!(out, "{}", fields.join(","))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes the report where `output` says, see `ReportFormat::for_output`;
+/// the file path when it went to one.
+pub fn export_token_report<'a>(entries: &[TokenReportEntry], output: &'a str) -> Result<Option<&'a Path>> {
+    let (format, path) = ReportFormat::for_output(output);
+    
+    match path {
+        Some(path) => {
+            let mut buffer = Vec::new();
+            write_token_report(entries, format, &mut buffer)?;
+            fs::write(path, buffer)
+                .with_context(|| format!("Failed to write validation report {}", path.display()))?;
+        }
+        None => write_token_report(entries, format, &mut std::io::stdout().lock())?,
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::account::{Capabilities, OwnerType};
+    
+    fn account(index: usize, token: &str) -> AccountInfo {
+        AccountInfo {
+            username: format!("user_{}", index),
+            token: token.to_string(),
+            index,
+            owner: None,
+            owner_type: OwnerType::User,
+            fork_org: None,
+            expires_at: None,
+            capabilities: None,
+            enabled: true,
+            validated_at: None,
+            stale: false,
+            priority: 0,
+            label: None,
+            notes: None,
+            quarantined_at: None,
+        }
+    }
+    
+    fn entries() -> Vec<TokenReportEntry> {
+        let proxy = ProxyConfig::from_url("http://u:p@1.2.3.4:8080").unwrap();
+        let lookup = TokenLookup {
+            username: "alice".to_string(),
+            expires_at: Some("2026-12-01T00:00:00Z".parse().unwrap()),
+            capabilities: Some(Capabilities { fork: true, workflows: true, ..Capabilities::default() }),
+        };
+        
+        vec![
+            TokenReportEntry::new(&account(0, "ghp_aaaaaaaaaaaaaaaa"), Ok(&lookup), Some(&proxy)),
+            TokenReportEntry::new(&account(1, "github_pat_bbbbbbbbbbbb"), Err("HTTP 401: Bad credentials, \"revoked\"".to_string()), None),
+        ]
+    }
+    
+    #[test]
+    fn test_entries_keep_only_the_token_prefix() {
+        let entries = entries();
+        
+        assert_eq!(entries[0].token_prefix, "ghp_aaaa");
+        assert_eq!(entries[0].username, "alice");
+        assert_eq!(entries[0].capabilities, Some(vec!["fork", "workflows"]));
+        assert_eq!(entries[0].proxy_host.as_deref(), Some("1.2.3.4"));
+        assert_eq!((entries[1].valid, entries[1].username.as_str()), (false, "user_1"));
+        
+        for format in [ReportFormat::Json, ReportFormat::Csv] {
+            let mut out = Vec::new();
+            write_token_report(&entries, format, &mut out).unwrap();
+            let out = String::from_utf8(out).unwrap();
+            assert!(!out.contains("ghp_aaaaaaaaaaaaaaaa") && !out.contains("github_pat_bbbbbbbbbbbb"), "{}", out);
+        }
+    }
+    
+    #[test]
+    fn test_json_and_csv_share_the_fields() {
+        let mut json = Vec::new();
+        write_token_report(&entries(), ReportFormat::Json, &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let mut keys: Vec<&String> = json[0].as_object().unwrap().keys().collect();
+        keys.sort();
+        let mut columns: Vec<&str> = CSV_HEADER.split(',').collect();
+        columns.sort();
+        assert_eq!(keys, columns);
+        assert_eq!(json[1]["error"], "HTTP 401: Bad credentials, \"revoked\"");
+        
+        let mut csv = Vec::new();
+        write_token_report(&entries(), ReportFormat::Csv, &mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), [
+            CSV_HEADER,
+            "0,alice,ghp_aaaa,true,fork workflows,1.2.3.4,2026-12-01T00:00:00+00:00,",
+            "1,user_1,github_p,false,,,,\"HTTP 401: Bad credentials, \"\"revoked\"\"\"",
+            "",
+        ].join("\n"));
+    }
+    
+    #[test]
+    fn test_output_picks_format_and_destination() {
+        assert_eq!(ReportFormat::for_output("csv"), (ReportFormat::Csv, None));
+        assert_eq!(ReportFormat::for_output("JSON"), (ReportFormat::Json, None));
+        assert_eq!(ReportFormat::for_output("report.json"), (ReportFormat::Json, Some(Path::new("report.json"))));
+        assert_eq!(ReportFormat::for_output("out/report.CSV"), (ReportFormat::Csv, Some(Path::new("out/report.CSV"))));
+    }
+}
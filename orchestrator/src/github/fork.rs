@@ -2,19 +2,277 @@
 
 use anyhow::{Result, Context, bail};
 use log::{info, warn, debug};
-use std::thread;
+use std::sync::Arc;
 use std::time::Duration;
+use chrono::{DateTime, Utc};
+use crate::app::AppContext;
 use crate::core::state::{StateManager, ForkChainNode, ForkStatus, OrchestratorState};
-use crate::core::account::AccountInfo;
-use crate::github::api::GitHubClient;
+use crate::core::account::{AccountInfo, AccountManager, Capability};
+use crate::core::billing::BillingMonitor;
+use crate::core::proxy::ProxyManager;
+use crate::core::settings;
+use crate::github::api::{client_for_account, ApiFactory, GitHubApi, GitHubClient, GitHubError, GraphQlError, MergeUpstream, RepoInfo, RunSummary};
+use crate::github::{secrets, workflow};
+use crate::monitor::archive::{ArchivedFork, ForkArchive};
+use crate::monitor::events::EventLog;
+use crate::utils::cancel::{self, CancelToken};
+use crate::utils::delay::{self, Sleeper};
+use crate::utils::exit_code::{self, ConfigError};
+use crate::utils::pool::WorkerPool;
+
+/// GitHub operations the fork chain needs, so ForkManager can run against a test double.
+pub trait ForkApi {
+    fn check_repo_exists(&self, repo: &str) -> Result<bool>;
+    fn get_repo_info(&self, repo: &str) -> Result<RepoInfo>;
+    fn branch_head(&self, repo: &str, branch: &str) -> Result<Option<String>>;
+    /// Returns the fork's full name as GitHub reports it.
+    fn create_fork(&self, source_repo: &str, organization: Option<&str>, name: Option<&str>) -> Result<String>;
+    fn delete_repo(&self, repo: &str) -> Result<()>;
+    fn archive_repo(&self, repo: &str) -> Result<()>;
+    fn transfer_repo(&self, repo: &str, new_owner: &str) -> Result<()>;
+    /// (owner, full name) of every fork of `repo`.
+    fn list_forks(&self, repo: &str) -> Result<Vec<(String, String)>>;
+    fn get_workflow_id(&self, repo: &str, workflow_file: &str) -> Result<Option<u64>>;
+    /// See `GitHubClient::workflow_state`.
+    fn workflow_state(&self, repo: &str, workflow_file: &str) -> Result<Option<String>>;
+    fn disable_workflow(&self, repo: &str, workflow_id: u64) -> Result<()>;
+    /// See `GitHubClient::enable_actions`.
+    fn enable_actions(&self, repo: &str) -> Result<()>;
+    /// Queued and in-progress runs, of `workflow_file` only when given.
+    fn list_active_runs(&self, repo: &str, workflow_file: Option<&str>) -> Result<Vec<u64>>;
+    fn run_summary(&self, repo: &str) -> Result<RunSummary>;
+    fn cancel_workflow_run(&self, repo: &str, run_id: u64) -> Result<()>;
+    fn merge_upstream(&self, repo: &str, branch: &str) -> Result<MergeUpstream>;
+    /// Runs a GraphQL query and returns its `data`; see `GitHubClient::api_graphql`.
+    fn api_graphql(&self, query: &str, variables: serde_json::Value) -> Result<serde_json::Value>;
+    /// Scopes of a classic token; None when GitHub does not list them.
+    fn token_scopes(&self) -> Result<Option<Vec<String>>>;
+    /// Login the token signs in as; fails once it is revoked or expired.
+    fn username(&self) -> Result<String>;
+}
+
+impl ForkApi for GitHubClient {
+    fn check_repo_exists(&self, repo: &str) -> Result<bool> {
+        GitHubClient::check_repo_exists(self, repo)
+    }
+    
+    fn get_repo_info(&self, repo: &str) -> Result<RepoInfo> {
+        GitHubClient::get_repo_info(self, repo)
+    }
+    
+    fn branch_head(&self, repo: &str, branch: &str) -> Result<Option<String>> {
+        GitHubClient::branch_head(self, repo, branch)
+    }
+    
+    fn create_fork(&self, source_repo: &str, organization: Option<&str>, name: Option<&str>) -> Result<String> {
+        GitHubClient::create_fork(self, source_repo, organization, name)
+    }
+    
+    fn delete_repo(&self, repo: &str) -> Result<()> {
+        GitHubClient::delete_repo(self, repo)
+    }
+    
+    fn archive_repo(&self, repo: &str) -> Result<()> {
+        GitHubClient::archive_repo(self, repo)
+    }
+    
+    fn transfer_repo(&self, repo: &str, new_owner: &str) -> Result<()> {
+        GitHubClient::transfer_repo(self, repo, new_owner)
+    }
+    
+    fn list_forks(&self, repo: &str) -> Result<Vec<(String, String)>> {
+        GitHubClient::list_forks(self, repo)
+    }
+    
+    fn get_workflow_id(&self, repo: &str, workflow_file: &str) -> Result<Option<u64>> {
+        GitHubClient::get_workflow_id(self, repo, workflow_file)
+    }
+    
+    fn workflow_state(&self, repo: &str, workflow_file: &str) -> Result<Option<String>> {
+        GitHubClient::workflow_state(self, repo, workflow_file)
+    }
+    
+    fn disable_workflow(&self, repo: &str, workflow_id: u64) -> Result<()> {
+        GitHubClient::disable_workflow(self, repo, workflow_id)
+    }
+    
+    fn enable_actions(&self, repo: &str) -> Result<()> {
+        GitHubClient::enable_actions(self, repo)
+    }
+    
+    fn list_active_runs(&self, repo: &str, workflow_file: Option<&str>) -> Result<Vec<u64>> {
+        GitHubClient::list_active_runs(self, repo, workflow_file)
+    }
+    
+    fn run_summary(&self, repo: &str) -> Result<RunSummary> {
+        GitHubClient::run_summary(self, repo)
+    }
+    
+    fn cancel_workflow_run(&self, repo: &str, run_id: u64) -> Result<()> {
+        GitHubClient::cancel_workflow_run(self, repo, run_id)
+    }
+    
+    fn merge_upstream(&self, repo: &str, branch: &str) -> Result<MergeUpstream> {
+        GitHubClient::merge_upstream(self, repo, branch)
+    }
+    
+    fn api_graphql(&self, query: &str, variables: serde_json::Value) -> Result<serde_json::Value> {
+        GitHubClient::api_graphql(self, query, variables)
+    }
+    
+    fn token_scopes(&self) -> Result<Option<Vec<String>>> {
+        GitHubClient::token_scopes(self)
+    }
+    
+    fn username(&self) -> Result<String> {
+        GitHubClient::get_username(self)
+    }
+}
+
+const DELETE_VERIFY_ATTEMPTS: u32 = 5;
+/// Checks for a transferred repo under its new owner before giving up.
+const TRANSFER_WAIT_ATTEMPTS: u32 = 24;
+
+/// Fields fetched per repo. Workflow runs hang off check suites; 15368 is
+/// the GitHub Actions app.
+const SNAPSHOT_FRAGMENT: &str = r#"
+fragment snapshot on Repository {
+  defaultBranchRef {
+    name
+    target {
+      ... on Commit {
+        checkSuites(last: 1, filterBy: { appId: 15368 }) {
+          nodes { status conclusion workflowRun { databaseId createdAt } }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// One chain repo as GitHub currently reports it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForkSnapshot {
+    pub repo: String,
+    pub exists: bool,
+    pub default_branch: Option<String>,
+    /// Latest Actions run on the head commit of the default branch
+    pub latest_run: Option<LatestRun>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatestRun {
+    pub run_id: u64,
+    /// Lowercase like the REST API: `queued`, `in_progress`, `completed`
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Where the chain and GitHub disagree, see `ForkManager::reconcile`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainDrift {
+    /// A chain node whose repo is gone from GitHub
+    MissingRepo { repo: String },
+    /// A fork owned by a managed account that the chain does not track
+    UntrackedFork { repo: String, parent: String },
+}
+
+impl std::fmt::Display for ChainDrift {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainDrift::MissingRepo { repo } => write!(f, "{} is in the chain but no longer exists on GitHub", repo),
+            ChainDrift::UntrackedFork { repo, parent } => {
+                write!(f, "{} (fork of {}) is on GitHub but not in the chain", repo, parent)
+            }
+        }
+    }
+}
+
+/// A fork of the configured parent on a managed account that the chain
+/// does not track, see `find_orphans`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanFork {
+    pub owner: String,
+    pub repo: String,
+}
+
+impl std::fmt::Display for OrphanFork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (@{})", self.repo, self.owner)
+    }
+}
+
+/// How `ForkManager::sync_with_upstream` left a fork's default branch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpstreamSync {
+    /// Upstream commits were merged in; `merge_type` is `fast-forward` or `merge`
+    Merged { branch: String, merge_type: String },
+    UpToDate { branch: String },
+    /// GitHub refused the merge (409); the branch needs resolving by hand
+    Conflict { branch: String, message: String },
+}
+
+impl UpstreamSync {
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, UpstreamSync::Conflict { .. })
+    }
+}
+
+impl std::fmt::Display for UpstreamSync {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpstreamSync::Merged { branch, merge_type } => write!(f, "{} synced with upstream ({})", branch, merge_type),
+            UpstreamSync::UpToDate { branch } => write!(f, "{} already up to date", branch),
+            UpstreamSync::Conflict { branch, message } => {
+                write!(f, "{} conflicts with upstream, resolve it by hand: {}", branch, message)
+            }
+        }
+    }
+}
+
+/// Builds the client for an account and its proxy URL; called from the
+/// worker threads of `ForkManager::create_chain_bulk`.
+pub type SyncClientFactory<'a> = dyn Fn(&AccountInfo, Option<String>) -> Box<dyn ForkApi> + Sync + 'a;
+
+/// What `ForkManager::create_chain_bulk` did, in account order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BulkCreateReport {
+    /// (username, fork) for every account that has its fork in the chain
+    pub created: Vec<(String, String)>,
+    /// (username, error) for every account that did not
+    pub failed: Vec<(String, String)>,
+}
 
 pub struct ForkManager {
-    state_manager: StateManager,
+    state_manager: Arc<StateManager>,
+    sleeper: Arc<dyn Sleeper>,
+    cancel: CancelToken,
 }
 
 impl ForkManager {
-    pub fn new(state_manager: StateManager) -> Self {
-        Self { state_manager }
+    pub fn new(state_manager: Arc<StateManager>) -> Self {
+        Self {
+            state_manager,
+            sleeper: delay::thread_sleeper(),
+            cancel: cancel::ctrl_c(),
+        }
+    }
+    
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+    
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+    
+    /// Waits `delays.fork_poll_ms` between readiness and deletion checks.
+    fn poll_pause(&self) {
+        let interval = Duration::from_millis(settings::get().delays.fork_poll_ms);
+        self.sleeper.sleep("fork_poll", interval);
     }
     
     pub fn create_fork_chain(
@@ -22,65 +280,194 @@ impl ForkManager {
         state: OrchestratorState,
         account: &AccountInfo,
         parent_repo: &str,
-        proxy: Option<String>,
+        client: &dyn ForkApi,
     ) -> Result<(OrchestratorState, String)> {
-        let client = GitHubClient::new(account.token.clone(), proxy);
+        info!("Creating fork for {} from {}", account.owner(), parent_repo);
         
-        info!("Creating fork for @{} from {}", account.username, parent_repo);
+        let (fork, created) = self.ensure_fork(account, parent_repo, client)?;
         
-        // Check if fork already exists
-        let expected_fork = format!("{}/{}", account.username, parent_repo.split('/').nth(1).unwrap());
+        // Check if it's in our chain
+        if !created && state.fork_chain.iter().any(|n| n.repo == fork) {
+            return Ok((state, fork));
+        }
         
-        if client.check_repo_exists(&expected_fork)? {
-            info!("Fork already exists: {}", expected_fork);
-            
-            // Check if it's in our chain
-            if state.fork_chain.iter().any(|n| n.repo == expected_fork) {
-                return Ok((state, expected_fork));
+        let new_state = self.state_manager.add_fork_node(state, fork_node(account, &fork, parent_repo))?;
+        
+        Ok((new_state, fork))
+    }
+    
+    /// Replaces a chain fork that is gone from GitHub: its node is marked
+    /// Disabled and `account` forks the node's parent again. Returns the new fork.
+    pub fn heal(
+        &self,
+        mut state: OrchestratorState,
+        repo: &str,
+        account: &AccountInfo,
+        client: &dyn ForkApi,
+    ) -> Result<(OrchestratorState, String)> {
+        let position = state.fork_chain
+            .iter()
+            .position(|n| n.repo.eq_ignore_ascii_case(repo) && n.status != ForkStatus::Deleted)
+            .with_context(|| format!("{} is not in the fork chain", repo))?;
+        let node = &mut state.fork_chain[position];
+        let parent = node.parent
+            .clone()
+            .with_context(|| format!("{} has no parent to fork again", repo))?;
+        
+        info!("{} is missing on GitHub, forking {} again for @{}", repo, parent, account.username);
+        node.status = ForkStatus::Disabled;
+        node.last_updated = Utc::now();
+        self.state_manager.save_state(&state)?;
+        
+        let (mut state, fork) = self.create_fork_chain(state, account, &parent, client)?;
+        
+        // The replacement takes the missing fork's place in the chain, so it
+        // is also the one that stays active
+        if state.fork_chain.len() > position + 2 && state.fork_chain.last().is_some_and(|n| n.repo == fork) {
+            let replacement = state.fork_chain.pop().expect("chain is not empty");
+            state.fork_chain.insert(position + 1, replacement);
+            self.state_manager.save_state(&state)?;
+        }
+        
+        Ok((state, fork))
+    }
+    
+    /// Forks `parent_repo` into every account, up to `concurrency` at a time,
+    /// each waiting for its own fork to be ready. Nodes are added afterwards
+    /// in account order, one save at a time, whatever order the forks finished
+    /// in. Accounts that failed or were cancelled are listed in the report;
+    /// the forks that succeeded stay in state.
+    pub fn create_chain_bulk(
+        &self,
+        mut state: OrchestratorState,
+        accounts: &[AccountInfo],
+        parent_repo: &str,
+        proxy_map: &ProxyManager,
+        concurrency: usize,
+        client_for: &SyncClientFactory<'_>,
+    ) -> Result<(OrchestratorState, BulkCreateReport)> {
+        info!("Creating forks of {} for {} accounts, {} at a time", parent_repo, accounts.len(), concurrency);
+        
+        let _interruptible = cancel::interruptible();
+        let sweep = WorkerPool::new(concurrency)
+            .with_cancel(self.cancel.clone())
+            .run(accounts, |account| account.owner().to_string(), |account| {
+                let proxy = proxy_map.get_proxy(&account.token).map(|p| p.to_curl_format());
+                let client = client_for(account, proxy);
+                self.ensure_fork(account, parent_repo, client.as_ref())
+            });
+        
+        let mut report = BulkCreateReport::default();
+        
+        for (account, outcome) in accounts.iter().zip(sweep.outcomes) {
+            match outcome.into_result() {
+                Ok((fork, created)) => {
+                    if created || !state.fork_chain.iter().any(|n| n.repo == fork) {
+                        state = self.state_manager.add_fork_node(state, fork_node(account, &fork, parent_repo))?;
+                    }
+                    report.created.push((account.username.clone(), fork));
+                }
+                Err(e) => {
+                    warn!("Fork for @{} failed: {:#}", account.username, e);
+                    report.failed.push((account.username.clone(), format!("{:#}", e)));
+                }
             }
-        } else {
-            // Create new fork
-            let fork_name = client.create_fork(parent_repo)?;
-            info!("Fork created: {}", fork_name);
-            
-            // Wait for fork to be ready
-            self.wait_for_fork_ready(&client, &fork_name)?;
         }
         
-        // Add to chain
-        let node = ForkChainNode {
-            pat_index: account.index,
-            username: account.username.clone(),
-            repo: expected_fork.clone(),
-            parent: Some(parent_repo.to_string()),
-            billing_used: 0.0,
-            status: ForkStatus::Active,
-            created_at: chrono::Utc::now(),
-            last_updated: chrono::Utc::now(),
+        info!("Bulk fork complete: {} forked, {} failed", report.created.len(), report.failed.len());
+        Ok((state, report))
+    }
+    
+    /// Makes sure `account` has a ready fork of `parent_repo`, creating it
+    /// if needed, with Actions enabled. Returns the fork and whether it was
+    /// created just now.
+    fn ensure_fork(&self, account: &AccountInfo, parent_repo: &str, client: &dyn ForkApi) -> Result<(String, bool)> {
+        let requested_name = settings::get().github.fork_name_for(account.index);
+        let expected_fork = expected_fork(account, parent_repo)?;
+        
+        let exists = match client.check_repo_exists(&expected_fork) {
+            Err(e) if GitHubError::is_no_access(&e) => {
+                warn!("Token for @{} cannot see repo {}, check its scopes", account.username, expected_fork);
+                return Err(e.context(format!(
+                    "Token for @{} cannot see repo {}; check the token's scopes",
+                    account.username, expected_fork
+                )));
+            }
+            result => result?,
         };
         
-        let new_state = self.state_manager.add_fork_node(state, node)?;
+        // An unrelated repo can hold the name; GitHub then picks another one
+        if exists {
+            let info = client.get_repo_info(&expected_fork)?;
+            match info.parent_name() {
+                Some(parent) if parent.eq_ignore_ascii_case(parent_repo) => {
+                    info!("Fork already exists: {}", expected_fork);
+                    return Ok((expected_fork, false));
+                }
+                other => warn!(
+                    "{} already exists and is not a fork of {} (parent: {})",
+                    expected_fork,
+                    parent_repo,
+                    other.unwrap_or("none")
+                ),
+            }
+        }
+        
+        // Create new fork; the name GitHub gave it is the one to track
+        let fork_name = client.create_fork(parent_repo, account.fork_organization(), requested_name.as_deref())?;
+        if !fork_name.eq_ignore_ascii_case(&expected_fork) {
+            warn!("Fork of {} was created as {}, not {}", parent_repo, fork_name, expected_fork);
+        }
+        info!("Fork created: {}", fork_name);
         
-        Ok((new_state, expected_fork))
+        // Wait for fork to be ready
+        self.wait_for_fork_ready(client, &fork_name)?;
+        
+        // GitHub turns Actions off on new forks; dispatches fail until it is on
+        client.enable_actions(&fork_name)?;
+        info!("Actions enabled on {}", fork_name);
+        
+        Ok((fork_name, true))
     }
     
-    fn wait_for_fork_ready(&self, client: &GitHubClient, fork_repo: &str) -> Result<()> {
+    /// Polls until the fork's default branch has a commit: GitHub creates the
+    /// repo right away but copies the git data later, and cloning before that
+    /// gets an empty repo. Gives up after `github.fork_ready_timeout_secs`.
+    fn wait_for_fork_ready(&self, client: &dyn ForkApi, fork_repo: &str) -> Result<()> {
         info!("Waiting for fork to be ready: {}", fork_repo);
         
-        let max_attempts = 24; // 2 minutes total (24 * 5s)
+        let settings = settings::get();
+        let timeout_secs = settings.github.fork_ready_timeout_secs;
+        let max_attempts = (timeout_secs * 1000 / settings.delays.fork_poll_ms.max(1)).max(1);
         let mut attempts = 0;
+        // Default branch of the repo once it has shown up
+        let mut empty_branch: Option<String> = None;
         
         while attempts < max_attempts {
-            thread::sleep(Duration::from_secs(5));
+            self.poll_pause();
             
-            match client.check_repo_exists(fork_repo) {
-                Ok(true) => {
+            let head = client
+                .get_repo_info(fork_repo)
+                .and_then(|info| {
+                    let head = client.branch_head(fork_repo, &info.default_branch)?;
+                    empty_branch = Some(info.default_branch);
+                    Ok(head)
+                });
+            
+            match head {
+                Ok(Some(_)) => {
                     info!("Fork is ready: {}", fork_repo);
                     return Ok(());
                 }
-                Ok(false) => {
+                Ok(None) => {
+                    debug!("Fork has no commits yet, attempt {}/{}", attempts + 1, max_attempts);
+                }
+                Err(e) if GitHubError::is_not_found(&e) => {
                     debug!("Fork not ready yet, attempt {}/{}", attempts + 1, max_attempts);
                 }
+                Err(e) if GitHubError::is_no_access(&e) => {
+                    return Err(e.context(format!("No access to fork {}", fork_repo)));
+                }
                 Err(e) => {
                     warn!("Error checking fork: {}", e);
                 }
@@ -89,16 +476,25 @@ impl ForkManager {
             attempts += 1;
         }
         
-        bail!("Timeout waiting for fork to be ready: {}", fork_repo)
+        match empty_branch {
+            Some(branch) => bail!(
+                "Timeout waiting for fork to be ready: {} exists but its {} branch still has no commits after {}s",
+                fork_repo, branch, timeout_secs
+            ),
+            None => bail!(
+                "Timeout waiting for fork to be ready: {} did not appear within {}s",
+                fork_repo, timeout_secs
+            ),
+        }
     }
     
     pub fn disable_fork_workflow(
         &self,
         repo: &str,
         workflow_file: &str,
-        client: &GitHubClient,
+        client: &dyn ForkApi,
     ) -> Result<()> {
-        info!("Disabling workflow in fork: {}", repo);
+        info!("Disabling workflow {} in fork: {}", workflow_file, repo);
         
         match client.get_workflow_id(repo, workflow_file)? {
             Some(workflow_id) => {
@@ -113,39 +509,460 @@ impl ForkManager {
         }
     }
     
+    /// Disables the workflows a retired fork should stop running:
+    /// `github.workflow_file`, or every managed workflow with
+    /// `rotation.disable_all_workflows`. Tries each, failing with the first error.
+    pub fn disable_fork_workflows(&self, repo: &str, client: &dyn ForkApi) -> Result<()> {
+        let settings = settings::get();
+        let workflows = if settings.rotation.disable_all_workflows {
+            workflow::managed_workflow_names()
+        } else {
+            vec![settings.github.workflow_file.clone()]
+        };
+        
+        let mut first_error = None;
+        for workflow in &workflows {
+            if let Err(e) = self.disable_fork_workflow(repo, workflow, client) {
+                warn!("Failed to disable {} in {}: {}", workflow, repo, e);
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+    
+    /// Best effort: disables the workflows and cancels queued/running runs
+    /// so nothing keeps billing against the fork.
+    fn stop_fork_workflow(&self, repo: &str, client: &dyn ForkApi) {
+        if let Err(e) = self.disable_fork_workflows(repo, client) {
+            warn!("Failed to disable workflow in {}: {}", repo, e);
+        }
+        
+        match client.list_active_runs(repo, None) {
+            Ok(run_ids) => {
+                for run_id in run_ids {
+                    if let Err(e) = client.cancel_workflow_run(repo, run_id) {
+                        warn!("Failed to cancel run {} in {}: {}", run_id, repo, e);
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to list active runs in {}: {}", repo, e),
+        }
+    }
+    
+    /// Disables the workflow, cancels queued/running runs, deletes the repo and
+    /// checks that it is really gone. Does not touch state.
+    pub fn delete_repo_verified(&self, repo: &str, client: &dyn ForkApi) -> Result<()> {
+        info!("Deleting fork: {}", repo);
+        
+        self.stop_fork_workflow(repo, client);
+        self.poll_pause();
+        
+        match client.delete_repo(repo) {
+            Err(e) if GitHubError::is_not_found(&e) => {
+                info!("Fork already gone: {}", repo);
+                return Ok(());
+            }
+            result => result?,
+        }
+        
+        for _ in 0..DELETE_VERIFY_ATTEMPTS {
+            if !client.check_repo_exists(repo)? {
+                info!("Fork deleted: {}", repo);
+                return Ok(());
+            }
+            self.poll_pause();
+        }
+        
+        bail!("{} still exists after deletion", repo)
+    }
+    
+    /// Like `delete_repo_verified`, but archives the repo instead so its
+    /// history stays readable on GitHub. Does not touch state.
+    pub fn archive_fork_repo(&self, repo: &str, client: &dyn ForkApi) -> Result<()> {
+        info!("Archiving fork: {}", repo);
+        
+        self.stop_fork_workflow(repo, client);
+        client.archive_repo(repo)?;
+        
+        info!("Fork archived: {}", repo);
+        Ok(())
+    }
+    
+    /// Deletes `repo` and marks its chain node Deleted once GitHub confirms
+    /// it is gone, recording the node in fork_archive.jsonl; a failed
+    /// deletion leaves the node as it was.
     pub fn delete_fork(
+        &self,
+        state: OrchestratorState,
+        repo: &str,
+        client: &dyn ForkApi,
+    ) -> Result<OrchestratorState> {
+        // Looked up by name: positions shift as the chain changes
+        let index = state.fork_chain
+            .iter()
+            .position(|n| n.repo.eq_ignore_ascii_case(repo))
+            .with_context(|| format!("{} is not in the fork chain", repo))?;
+        
+        let record = self.archive_record(&state.fork_chain[index], client);
+        self.delete_repo_verified(repo, client)?;
+        self.archive_deleted(record);
+        self.state_manager.update_fork_status(state, index, ForkStatus::Deleted)
+    }
+    
+    /// `node` for fork_archive.jsonl, with the run count and latest run
+    /// fresh from GitHub when it answers. A failed lookup is only logged:
+    /// it must not stop the deletion.
+    fn archive_record(&self, node: &ForkChainNode, client: &dyn ForkApi) -> ArchivedFork {
+        let mut record = ArchivedFork::from_node(node);
+        
+        match client.run_summary(&node.repo) {
+            Ok(runs) => {
+                record.run_count = Some(runs.total_count);
+                if let Some(run) = runs.latest() {
+                    record.last_run_id = Some(run.id);
+                    record.last_run_conclusion = run.conclusion.clone();
+                }
+            }
+            Err(e) => warn!("Failed to read runs of {} for the fork archive: {:#}", node.repo, e),
+        }
+        
+        record
+    }
+    
+    fn archive_deleted(&self, mut record: ArchivedFork) {
+        record.deleted_at = Utc::now();
+        if let Err(e) = ForkArchive::new(self.state_manager.paths()).append(&record) {
+            warn!("Failed to archive {}: {:#}", record.repo, e);
+        }
+    }
+    
+    /// Hands chain node `index` over to `target`'s account: starts the
+    /// transfer, waits until the repo shows up under the new owner, then
+    /// points the node at it. `client` must be able to see the new location.
+    pub fn transfer_fork(
         &self,
         mut state: OrchestratorState,
-        fork_index: usize,
-        client: &GitHubClient,
+        index: usize,
+        target: &AccountInfo,
+        client: &dyn ForkApi,
     ) -> Result<OrchestratorState> {
-        if let Some(node) = state.fork_chain.get(fork_index) {
-            let repo = &node.repo;
-            
-            info!("Deleting fork: {}", repo);
+        let node = state.fork_chain
+            .get(index)
+            .with_context(|| format!("No fork at chain index {}", index))?;
+        let old_repo = node.repo.clone();
+        let name = old_repo
+            .split('/')
+            .nth(1)
+            .with_context(|| format!("Invalid repo '{}', expected owner/name", old_repo))?;
+        let new_repo = format!("{}/{}", target.owner(), name);
+        
+        if new_repo == old_repo {
+            bail!("{} already belongs to {}", old_repo, target.owner());
+        }
+        
+        info!("Transferring fork {} to {}", old_repo, target.owner());
+        client.transfer_repo(&old_repo, target.owner())?;
+        
+        let mut arrived = false;
+        for attempt in 1..=TRANSFER_WAIT_ATTEMPTS {
+            self.poll_pause();
             
-            // First disable workflow
-            match client.get_workflow_id(repo, "nexus.yml") {
-                Ok(Some(workflow_id)) => {
-                    client.disable_workflow(repo, workflow_id).ok();
+            match client.check_repo_exists(&new_repo) {
+                Ok(true) => {
+                    arrived = true;
+                    break;
                 }
-                _ => {}
+                Ok(false) => debug!("Transfer not done yet, attempt {}/{}", attempt, TRANSFER_WAIT_ATTEMPTS),
+                Err(e) => warn!("Error checking transferred repo: {}", e),
+            }
+        }
+        
+        if !arrived {
+            bail!("Timeout waiting for {} to appear as {}", old_repo, new_repo);
+        }
+        
+        let node = &mut state.fork_chain[index];
+        node.repo = new_repo.clone();
+        node.username = target.owner().to_string();
+        node.owner_type = target.fork_owner_type();
+        node.pat_index = target.index;
+        node.last_updated = Utc::now();
+        self.state_manager.save_state(&state)?;
+        
+        info!("Fork transferred: {} -> {}", old_repo, new_repo);
+        EventLog::new(self.state_manager.paths()).record(
+            "transfer",
+            &format!("{} transferred to @{}", old_repo, target.owner()),
+            Some(&new_repo),
+        );
+        
+        Ok(state)
+    }
+    
+    /// Brings the fork's default branch up to date with its parent, so it
+    /// runs the parent's current workflow files. A merge conflict is
+    /// returned as `UpstreamSync::Conflict` rather than an error.
+    pub fn sync_with_upstream(&self, repo: &str, client: &dyn ForkApi) -> Result<UpstreamSync> {
+        let branch = client.get_repo_info(repo)?.default_branch;
+        info!("Syncing fork {} ({})", repo, branch);
+        
+        match client.merge_upstream(repo, &branch) {
+            Ok(merge) if merge.is_up_to_date() => Ok(UpstreamSync::UpToDate { branch }),
+            Ok(merge) => Ok(UpstreamSync::Merged {
+                branch,
+                merge_type: merge.merge_type.unwrap_or_else(|| "merge".to_string()),
+            }),
+            Err(e) => match GitHubError::of(&e) {
+                Some(GitHubError::Conflict(message)) => {
+                    warn!("Fork {} conflicts with upstream: {}", repo, message);
+                    Ok(UpstreamSync::Conflict { branch, message: message.clone() })
+                }
+                _ => Err(e.context(format!("Failed to sync {} with upstream", repo))),
+            },
+        }
+    }
+    
+    /// Existence, default branch and latest run of every chain repo that is
+    /// not deleted, in a single GraphQL query instead of several REST calls
+    /// per fork. Repos the token cannot see are reported as missing.
+    pub fn chain_snapshot(&self, state: &OrchestratorState, client: &dyn ForkApi) -> Result<Vec<ForkSnapshot>> {
+        let repos: Vec<&str> = state.fork_chain
+            .iter()
+            .filter(|n| n.status != ForkStatus::Deleted)
+            .map(|n| n.repo.as_str())
+            .collect();
+        
+        if repos.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let (query, variables) = chain_snapshot_query(&repos)?;
+        
+        // A missing repo is a NOT_FOUND error next to the others' data
+        let data = match client.api_graphql(&query, variables) {
+            Ok(data) => data,
+            Err(e) => match e.downcast_ref::<GraphQlError>() {
+                Some(error) if error.only_not_found() && error.data.is_some() => error.data.clone().unwrap_or_default(),
+                _ => return Err(e.context("Failed to fetch chain snapshot")),
+            },
+        };
+        
+        Ok(repos
+            .iter()
+            .enumerate()
+            .map(|(i, repo)| parse_fork_snapshot(repo, &data[format!("repo{}", i).as_str()]))
+            .collect())
+    }
+    
+    /// Compares the chain with GitHub: non-deleted nodes whose repo is gone,
+    /// and forks of `source_repo` or of a chain repo that belong to one of
+    /// `managed_owners` but are not tracked. Reads only; state is untouched.
+    pub fn reconcile(
+        &self,
+        state: &OrchestratorState,
+        source_repo: Option<&str>,
+        managed_owners: &[&str],
+        client: &dyn ForkApi,
+    ) -> Result<Vec<ChainDrift>> {
+        let tracked: Vec<&str> = state.fork_chain
+            .iter()
+            .filter(|n| n.status != ForkStatus::Deleted)
+            .map(|n| n.repo.as_str())
+            .collect();
+        let is_tracked = |repo: &str| tracked.iter().any(|t| t.eq_ignore_ascii_case(repo));
+        
+        let mut drift = Vec::new();
+        let mut parents: Vec<&str> = source_repo.into_iter().filter(|r| !is_tracked(r)).collect();
+        
+        for repo in &tracked {
+            if client.check_repo_exists(repo)? {
+                parents.push(repo);
+            } else {
+                drift.push(ChainDrift::MissingRepo { repo: repo.to_string() });
+            }
+        }
+        
+        for parent in parents {
+            for (owner, repo) in client.list_forks(parent)? {
+                let managed = managed_owners.iter().any(|m| m.eq_ignore_ascii_case(&owner));
+                if managed && !is_tracked(&repo) {
+                    drift.push(ChainDrift::UntrackedFork { repo, parent: parent.to_string() });
+                }
+            }
+        }
+        
+        info!("Reconciled chain with GitHub: {} discrepancies", drift.len());
+        Ok(drift)
+    }
+    
+    /// Forks of `parent_repo` owned by one of `managed_owners` that the
+    /// chain does not track. Only direct forks of the parent are listed.
+    pub fn orphan_forks(
+        &self,
+        state: &OrchestratorState,
+        parent_repo: &str,
+        managed_owners: &[&str],
+        client: &dyn ForkApi,
+    ) -> Result<Vec<OrphanFork>> {
+        let forks = client.list_forks(parent_repo)?;
+        let orphans = find_orphans(state, &forks, managed_owners);
+        info!("Found {} orphan forks of {}", orphans.len(), parent_repo);
+        Ok(orphans)
+    }
+    
+    /// Deletes an orphan after checking with GitHub that it still is a fork
+    /// of `parent_repo`; anything else is refused.
+    pub fn delete_orphan(&self, orphan: &OrphanFork, parent_repo: &str, client: &dyn ForkApi) -> Result<()> {
+        let info = client.get_repo_info(&orphan.repo)?;
+        match info.parent_name() {
+            Some(parent) if parent.eq_ignore_ascii_case(parent_repo) => {}
+            other => bail!(
+                "{} is not a fork of {} (parent: {}), left alone",
+                orphan.repo,
+                parent_repo,
+                other.unwrap_or("none")
+            ),
+        }
+        
+        self.delete_repo_verified(&orphan.repo, client)
+    }
+    
+    /// Rebuilds the chain from GitHub when the state file is lost: each
+    /// account's fork of `parent_repo`, ordered by the fork metadata from the
+    /// parent down, Exhausted when billing says so and Active otherwise. Any
+    /// existing state file is backed up before the fresh one is written.
+    /// Reads only on GitHub, so running it again gives the same chain.
+    pub fn rebuild_state(
+        &self,
+        accounts: &[AccountInfo],
+        parent_repo: &str,
+        proxy_mgr: &ProxyManager,
+        client_for: &ApiFactory,
+    ) -> Result<OrchestratorState> {
+        let billing_mon = BillingMonitor::from_config(&self.state_manager.paths().thresholds_file())?;
+        let mut found: Vec<ForkChainNode> = Vec::new();
+        
+        for account in accounts {
+            let repo = expected_fork(account, parent_repo)?;
+            // Tokens sharing an organization share its fork
+            if found.iter().any(|n| n.repo.eq_ignore_ascii_case(&repo)) {
+                continue;
             }
             
-            thread::sleep(Duration::from_secs(3));
-            
-            // Delete repository
-            client.delete_repo(repo)?;
-            
-            info!("Fork deleted: {}", repo);
+            let client = client_for(account, proxy_mgr.proxy_for(account)?);
+            let info = match client.get_repo_info(&repo) {
+                Ok(info) => info,
+                Err(e) if GitHubError::is_not_found(&e) => {
+                    info!("No fork for {}", account.owner());
+                    continue;
+                }
+                Err(e) => return Err(e.context(format!("Failed to look up {}", repo))),
+            };
+            let parent = match info.parent_name() {
+                Some(parent) => parent.to_string(),
+                None => {
+                    warn!("{} is not a fork, left out of the chain", repo);
+                    continue;
+                }
+            };
             
-            // Update state
-            state = self.state_manager.update_fork_status(state, fork_index, ForkStatus::Disabled)?;
+            let billing_owner = account.billing_owner().0;
+            let billing = billing_mon.check_billing(account, client.as_ref())?;
+            let mut node = fork_node(account, &info.full_name, &parent);
+            if let Some(created_at) = info.created_at {
+                node.created_at = created_at;
+            }
+            // An estimate only means billing could not be read; Exhausted
+            // would make the fork a target for the next cleanup
+            if billing.is_estimated {
+                warn!("Billing unknown for {}, {} assumed Active", billing_owner, repo);
+            } else {
+                node.billing_used = billing.total_core_hours_used;
+                if billing.is_exhausted {
+                    node.status = ForkStatus::Exhausted;
+                }
+            }
+            found.push(node);
         }
         
+        // Parents before their forks, accounts in order among equals
+        let mut chain: Vec<ForkChainNode> = Vec::new();
+        loop {
+            let next = found.iter().position(|n| match n.parent.as_deref() {
+                Some(parent) => {
+                    parent.eq_ignore_ascii_case(parent_repo)
+                        || chain.iter().any(|c| c.repo.eq_ignore_ascii_case(parent))
+                }
+                None => false,
+            });
+            match next {
+                Some(i) => chain.push(found.remove(i)),
+                None => break,
+            }
+        }
+        for node in &found {
+            warn!(
+                "{} is forked from {}, outside the chain of {}; left out",
+                node.repo,
+                node.parent.as_deref().unwrap_or("?"),
+                parent_repo
+            );
+        }
+        
+        let state = OrchestratorState {
+            current_active_index: chain
+                .iter()
+                .find(|n| n.status == ForkStatus::Active)
+                .map(|n| n.pat_index)
+                .unwrap_or(0),
+            total_accounts: accounts.len(),
+            fork_chain: chain,
+            ..OrchestratorState::default()
+        };
+        
+        self.state_manager.backup_state()?;
+        self.state_manager.save_state(&state)?;
+        
+        info!("Rebuilt fork chain of {} from GitHub: {} forks", parent_repo, state.fork_chain.len());
         Ok(state)
     }
     
+    /// Checks every node that is not Deleted on GitHub with its own token:
+    /// the token, the repo, the workflow file and whether it is enabled as
+    /// the node's status expects, and the Nexus secrets. Checks that need
+    /// an earlier one to pass are not run when it fails.
+    pub fn verify_chain(
+        &self,
+        state: &OrchestratorState,
+        account_mgr: &AccountManager,
+        proxy_mgr: &ProxyManager,
+        client_for: &ApiFactory,
+    ) -> ChainVerification {
+        let workflow_file = &settings::get().github.workflow_file;
+        
+        let nodes = state.fork_chain
+            .iter()
+            .filter(|n| n.status != ForkStatus::Deleted)
+            .map(|node| {
+                let mut report = NodeVerification::new(node);
+                let account = match account_mgr.get_account(node.pat_index) {
+                    Some(account) => account,
+                    None => {
+                        report.token = Check::Failed(format!("token #{} is not in tokens.txt", node.pat_index));
+                        return report;
+                    }
+                };
+                match proxy_mgr.proxy_for(account) {
+                    Ok(proxy) => verify_node(node, workflow_file, client_for(account, proxy).as_ref(), &mut report),
+                    Err(e) => report.token = Check::Failed(format!("{:#}", e)),
+                }
+                report
+            })
+            .collect();
+        
+        ChainVerification { nodes }
+    }
+    
     pub fn get_next_parent_repo(&self, state: &OrchestratorState) -> Option<String> {
         // Find the last active or exhausted fork to use as parent
         state.fork_chain
@@ -154,69 +971,858 @@ impl ForkManager {
             .find(|n| n.status == ForkStatus::Active || n.status == ForkStatus::Exhausted)
             .map(|n| n.repo.clone())
     }
+    
+    /// Deletes or archives every node matched by `options.filter`, updating
+    /// state after each one. `client_for` fails when there is no token or no
+    /// proxy for the node's account, which fails just that node. Dry runs
+    /// only read: each node is checked against its token's rights and state
+    /// is left as it was. After Ctrl-C the
+    /// fork in progress is finished and recorded; the rest are left alone.
+    pub fn cleanup(
+        &self,
+        mut state: OrchestratorState,
+        options: &CleanupOptions,
+        client_for: &dyn Fn(&ForkChainNode) -> Result<Box<dyn ForkApi>>,
+    ) -> Result<(OrchestratorState, CleanupSummary)> {
+        let selected = options.filter.select(&state, Utc::now());
+        info!("Cleanup matched {} forks", selected.len());
+        
+        let _interruptible = cancel::interruptible();
+        let mut summary = CleanupSummary::default();
+        
+        for index in selected {
+            let node = state.fork_chain[index].clone();
+            
+            if self.cancel.is_cancelled() {
+                summary.results.push(CleanupResult::new(node, CleanupOutcome::Cancelled));
+                continue;
+            }
+            
+            let outcome = if options.dry_run {
+                match client_for(&node) {
+                    Ok(client) => self.plan_node(&node, options.mode, client.as_ref())
+                        .unwrap_or_else(|e| CleanupOutcome::Blocked(format!("{:#}", e))),
+                    Err(e) => {
+                        warn!("Access to {} not checked: {:#}", node.repo, e);
+                        CleanupOutcome::Planned(options.mode)
+                    }
+                }
+            } else {
+                client_for(&node)
+                    .and_then(|client| self.cleanup_node(&node, options.mode, client.as_ref()))
+                    .unwrap_or_else(|e| CleanupOutcome::Failed(format!("{:#}", e)))
+            };
+            
+            let new_status = match &outcome {
+                CleanupOutcome::Done(mode) => Some(mode.resulting_status()),
+                CleanupOutcome::Skipped(_) => Some(ForkStatus::Deleted),
+                CleanupOutcome::Planned(_)
+                | CleanupOutcome::Blocked(_)
+                | CleanupOutcome::Failed(_)
+                | CleanupOutcome::Cancelled => None,
+            };
+            if let Some(status) = new_status.filter(|s| *s != node.status && !options.dry_run) {
+                state = self.state_manager.update_fork_status(state, index, status)?;
+            }
+            
+            match &outcome {
+                CleanupOutcome::Failed(e) => warn!("Failed to clean up {}: {}", node.repo, e),
+                CleanupOutcome::Blocked(e) => warn!("Cleanup of {} would fail: {}", node.repo, e),
+                _ => {}
+            }
+            summary.results.push(CleanupResult::new(node, outcome));
+        }
+        
+        if summary.cancelled() > 0 {
+            EventLog::new(self.state_manager.paths()).record(
+                "cancelled",
+                &format!("Cleanup cancelled, {} forks left untouched", summary.cancelled()),
+                None,
+            );
+        }
+        
+        info!(
+            "Cleanup complete: {} matched, {} deleted, {} failed, {} skipped",
+            summary.matched(),
+            summary.deleted(),
+            summary.failed(),
+            summary.skipped()
+        );
+        Ok((state, summary))
+    }
+    
+    fn cleanup_node(&self, node: &ForkChainNode, mode: CleanupMode, client: &dyn ForkApi) -> Result<CleanupOutcome> {
+        if !client.check_repo_exists(&node.repo)? {
+            return Ok(CleanupOutcome::Skipped("repository is already gone".to_string()));
+        }
+        
+        match mode {
+            CleanupMode::Delete => {
+                let record = self.archive_record(node, client);
+                self.delete_repo_verified(&node.repo, client)?;
+                self.archive_deleted(record);
+            }
+            CleanupMode::Archive => self.archive_fork_repo(&node.repo, client)?,
+        }
+        
+        Ok(CleanupOutcome::Done(mode))
+    }
+    
+    /// What `cleanup_node` would do, using only reads: Blocked when the
+    /// token may not delete or archive the repo.
+    fn plan_node(&self, node: &ForkChainNode, mode: CleanupMode, client: &dyn ForkApi) -> Result<CleanupOutcome> {
+        let info = match client.get_repo_info(&node.repo) {
+            Err(e) if GitHubError::is_not_found(&e) => {
+                return Ok(CleanupOutcome::Skipped("repository is already gone".to_string()));
+            }
+            result => result?,
+        };
+        
+        if info.permissions.is_some_and(|p| !p.admin) {
+            return Ok(CleanupOutcome::Blocked(format!("token has no admin rights on {}", node.repo)));
+        }
+        
+        // Fine-grained tokens list no scopes; their rights show in `permissions`
+        if mode == CleanupMode::Delete {
+            if let Some(scopes) = client.token_scopes()? {
+                if !scopes.iter().any(|s| s == "delete_repo") {
+                    return Ok(CleanupOutcome::Blocked("token lacks the delete_repo scope".to_string()));
+                }
+            }
+        }
+        
+        Ok(CleanupOutcome::Planned(mode))
+    }
 }
 
-pub fn cleanup_exhausted_forks() -> Result<()> {
-    info!("Starting cleanup of exhausted forks...");
+fn verify_node(node: &ForkChainNode, workflow_file: &str, client: &dyn GitHubApi, report: &mut NodeVerification) {
+    // GET user fails once the token is revoked or expired
+    report.token = Check::from_result(client.token_scopes().map(|_| ()));
+    if !report.token.passed() {
+        return;
+    }
     
-    let config_dir = std::path::PathBuf::from("config");
-    let state_mgr = StateManager::new(&config_dir)?;
-    let mut state = state_mgr.load_state()?;
+    report.repo_exists = match client.check_repo_exists(&node.repo) {
+        Ok(true) => Check::Passed,
+        Ok(false) => Check::Failed("not found".to_string()),
+        Err(e) => Check::Failed(format!("{:#}", e)),
+    };
+    if !report.repo_exists.passed() {
+        return;
+    }
     
-    let exhausted_forks: Vec<_> = state.fork_chain
-        .iter()
-        .enumerate()
-        .filter(|(_, n)| n.status == ForkStatus::Exhausted)
-        .map(|(i, n)| (i, n.clone()))
-        .collect();
+    match client.workflow_state(&node.repo, workflow_file) {
+        Ok(Some(state)) => {
+            report.workflow = Check::Passed;
+            let expected = match node.status {
+                ForkStatus::Active => Some(true),
+                ForkStatus::Exhausted | ForkStatus::Disabled => Some(false),
+                ForkStatus::Source | ForkStatus::Deleted => None,
+            };
+            if let Some(enabled) = expected {
+                report.enabled = if (state == "active") == enabled {
+                    Check::Passed
+                } else {
+                    Check::Failed(format!("workflow is {} but the fork is {:?}", state, node.status))
+                };
+            }
+        }
+        Ok(None) => report.workflow = Check::Failed(format!("{} not found", workflow_file)),
+        Err(e) => report.workflow = Check::Failed(format!("{:#}", e)),
+    }
     
-    if exhausted_forks.is_empty() {
-        info!("No exhausted forks to clean up");
-        return Ok(());
+    let names = match &settings::get().github.secrets_environment {
+        Some(environment) => client
+            .get_repo_info(&node.repo)
+            .and_then(|info| client.list_environment_secrets(info.id, environment)),
+        None => client.list_secrets(&node.repo),
+    };
+    report.secrets = match names {
+        Ok(names) => {
+            let missing = secrets::missing_nexus_secrets(&names);
+            if missing.is_empty() {
+                Check::Passed
+            } else {
+                Check::Failed(format!("missing {}", missing.join(", ")))
+            }
+        }
+        Err(e) => Check::Failed(format!("{:#}", e)),
+    };
+}
+
+/// The (owner, repo) `forks` that belong to one of `managed_owners` and
+/// are not a chain node. Deleted nodes do not count: their repo should be
+/// gone, so one that is still listed is an orphan too.
+pub fn find_orphans(state: &OrchestratorState, forks: &[(String, String)], managed_owners: &[&str]) -> Vec<OrphanFork> {
+    let is_tracked = |repo: &str| {
+        state.fork_chain
+            .iter()
+            .any(|n| n.status != ForkStatus::Deleted && n.repo.eq_ignore_ascii_case(repo))
+    };
+    
+    forks.iter()
+        .filter(|(owner, _)| managed_owners.iter().any(|m| m.eq_ignore_ascii_case(owner)))
+        .filter(|(_, repo)| !is_tracked(repo))
+        .map(|(owner, repo)| OrphanFork { owner: owner.clone(), repo: repo.clone() })
+        .collect()
+}
+
+/// Where `account`'s fork of `parent_repo` lands unless its name is taken:
+/// the account's owner, and `github.fork_name` or else the parent's name.
+fn expected_fork(account: &AccountInfo, parent_repo: &str) -> Result<String> {
+    let parent_name = parent_repo
+        .split('/')
+        .nth(1)
+        .with_context(|| ConfigError::new(format!("Invalid parent repo '{}', expected owner/name", parent_repo)))?;
+    let name = settings::get().github.fork_name_for(account.index);
+    
+    Ok(format!("{}/{}", account.owner(), name.as_deref().unwrap_or(parent_name)))
+}
+
+/// A new Active chain node for `account`'s fork of `parent_repo`.
+fn fork_node(account: &AccountInfo, repo: &str, parent_repo: &str) -> ForkChainNode {
+    ForkChainNode {
+        pat_index: account.index,
+        username: account.owner().to_string(),
+        owner_type: account.fork_owner_type(),
+        repo: repo.to_string(),
+        parent: Some(parent_repo.to_string()),
+        billing_used: 0.0,
+        status: ForkStatus::Active,
+        created_at: chrono::Utc::now(),
+        last_updated: chrono::Utc::now(),
+        last_run: None,
     }
+}
+
+/// The chain snapshot query: one `repoN` alias per repo, owner and name
+/// passed as variables.
+fn chain_snapshot_query(repos: &[&str]) -> Result<(String, serde_json::Value)> {
+    let mut params = Vec::new();
+    let mut fields = String::new();
+    let mut variables = serde_json::Map::new();
     
-    info!("Found {} exhausted forks to delete", exhausted_forks.len());
+    for (i, repo) in repos.iter().enumerate() {
+        let (owner, name) = repo
+            .split_once('/')
+            .with_context(|| format!("Invalid repo '{}', expected owner/name", repo))?;
+        
+        params.push(format!("$owner{i}: String!, $name{i}: String!"));
+        fields.push_str(&format!("  repo{i}: repository(owner: $owner{i}, name: $name{i}) {{ ...snapshot }}\n"));
+        variables.insert(format!("owner{}", i), owner.into());
+        variables.insert(format!("name{}", i), name.into());
+    }
     
-    // Load tokens to get credentials
-    let tokens_file = config_dir.join("tokens.txt");
-    let mut account_mgr = crate::core::account::AccountManager::new(&config_dir.join("cache"));
-    account_mgr.load_tokens(&tokens_file)?;
+    let query = format!("query({}) {{\n{}}}\n{}", params.join(", "), fields, SNAPSHOT_FRAGMENT);
     
-    let fork_mgr = ForkManager::new(state_mgr);
+    Ok((query, serde_json::Value::Object(variables)))
+}
+
+/// One `repoN` alias of the snapshot response; null means the repo is gone.
+fn parse_fork_snapshot(repo: &str, node: &serde_json::Value) -> ForkSnapshot {
+    let branch = &node["defaultBranchRef"];
+    let suite = &branch["target"]["checkSuites"]["nodes"][0];
     
-    for (index, node) in exhausted_forks {
-        if let Some(account) = account_mgr.get_account(node.pat_index) {
-            info!("Deleting fork: {} (index {})", node.repo, index);
-            
-            let client = GitHubClient::new(account.token.clone(), None);
-            
-            match fork_mgr.delete_fork(state.clone(), index, &client) {
-                Ok(new_state) => {
-                    state = new_state;
-                    info!("Successfully deleted: {}", node.repo);
-                }
-                Err(e) => {
-                    warn!("Failed to delete {}: {}", node.repo, e);
+    let latest_run = suite["workflowRun"]["databaseId"].as_u64().map(|run_id| LatestRun {
+        run_id,
+        status: suite["status"].as_str().unwrap_or("unknown").to_ascii_lowercase(),
+        conclusion: suite["conclusion"].as_str().map(|c| c.to_ascii_lowercase()),
+        created_at: suite["workflowRun"]["createdAt"].as_str().and_then(|t| t.parse().ok()),
+    });
+    
+    ForkSnapshot {
+        repo: repo.to_string(),
+        exists: !node.is_null(),
+        default_branch: branch["name"].as_str().map(|b| b.to_string()),
+        latest_run,
+    }
+}
+
+/// What cleanup does with a matched fork.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CleanupMode {
+    #[default]
+    Delete,
+    Archive,
+}
+
+impl CleanupMode {
+    pub fn parse(raw: &str) -> Result<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "delete" => Ok(CleanupMode::Delete),
+            "archive" => Ok(CleanupMode::Archive),
+            other => bail!(ConfigError::new(format!(
+                "Unknown cleanup mode '{}', use delete or archive",
+                other
+            ))),
+        }
+    }
+    
+    fn verb(self) -> &'static str {
+        match self {
+            CleanupMode::Delete => "delete",
+            CleanupMode::Archive => "archive",
+        }
+    }
+    
+    /// Archived repos still exist, so their node is only marked Disabled.
+    fn resulting_status(self) -> ForkStatus {
+        match self {
+            CleanupMode::Delete => ForkStatus::Deleted,
+            CleanupMode::Archive => ForkStatus::Disabled,
+        }
+    }
+}
+
+/// Which chain nodes a cleanup touches. All criteria must match; no
+/// statuses means exhausted forks only. The source repo is never matched.
+#[derive(Debug, Clone, Default)]
+pub struct CleanupFilter {
+    pub statuses: Vec<ForkStatus>,
+    /// Measured from the fork's creation
+    pub older_than: Option<chrono::Duration>,
+    pub account: Option<String>,
+    /// The most recently created exhausted forks left alone, since new
+    /// chains continue from them (see `get_next_parent_repo`)
+    pub keep_last: usize,
+}
+
+impl CleanupFilter {
+    pub fn matches(&self, node: &ForkChainNode, now: DateTime<Utc>) -> bool {
+        if node.status == ForkStatus::Source {
+            return false;
+        }
+        
+        let status_matches = if self.statuses.is_empty() {
+            node.status == ForkStatus::Exhausted
+        } else {
+            self.statuses.contains(&node.status)
+        };
+        let age_matches = self.older_than.is_none_or(|age| now - node.created_at >= age);
+        let account_matches = self.account
+            .as_deref()
+            .is_none_or(|username| node.username.eq_ignore_ascii_case(username));
+        
+        status_matches && age_matches && account_matches
+    }
+    
+    /// Chain indexes of the matching nodes, less the `keep_last` newest
+    /// exhausted forks of the whole chain.
+    pub fn select(&self, state: &OrchestratorState, now: DateTime<Utc>) -> Vec<usize> {
+        let mut exhausted: Vec<(usize, DateTime<Utc>)> = state.fork_chain
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.status == ForkStatus::Exhausted)
+            .map(|(i, n)| (i, n.created_at))
+            .collect();
+        // Newest first; later in the chain wins a tie
+        exhausted.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.cmp(&a.0)));
+        let kept: Vec<usize> = exhausted.into_iter().take(self.keep_last).map(|(i, _)| i).collect();
+        
+        state.fork_chain
+            .iter()
+            .enumerate()
+            .filter(|(i, n)| !kept.contains(i) && self.matches(n, now))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Parses `--status` values; only statuses that can be cleaned up are accepted.
+pub fn parse_cleanup_status(raw: &str) -> Result<ForkStatus> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "exhausted" => Ok(ForkStatus::Exhausted),
+        "disabled" => Ok(ForkStatus::Disabled),
+        "deleted" => Ok(ForkStatus::Deleted),
+        other => bail!(ConfigError::new(format!(
+            "Cannot clean up '{}' forks, use exhausted, disabled or deleted",
+            other
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CleanupOptions {
+    pub filter: CleanupFilter,
+    pub mode: CleanupMode,
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CleanupOutcome {
+    /// Deleted or archived, depending on the mode.
+    Done(CleanupMode),
+    /// Dry run: would have been deleted or archived.
+    Planned(CleanupMode),
+    /// Dry run: the real run would fail, and why.
+    Blocked(String),
+    Skipped(String),
+    Failed(String),
+    /// Not started because of Ctrl-C.
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CleanupResult {
+    pub repo: String,
+    /// Status and billing of the node when cleanup reached it
+    pub status: ForkStatus,
+    pub billing_used: f32,
+    pub outcome: CleanupOutcome,
+}
+
+impl CleanupResult {
+    fn new(node: ForkChainNode, outcome: CleanupOutcome) -> Self {
+        Self {
+            repo: node.repo,
+            status: node.status,
+            billing_used: node.billing_used,
+            outcome,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CleanupSummary {
+    pub results: Vec<CleanupResult>,
+}
+
+impl CleanupSummary {
+    pub fn matched(&self) -> usize {
+        self.results.len()
+    }
+    
+    /// Deleted or archived.
+    pub fn deleted(&self) -> usize {
+        self.count(|o| matches!(o, CleanupOutcome::Done(_)))
+    }
+    
+    /// Failed, or would fail on a dry run.
+    pub fn failed(&self) -> usize {
+        self.count(|o| matches!(o, CleanupOutcome::Failed(_) | CleanupOutcome::Blocked(_)))
+    }
+    
+    /// Dry-run entries and forks that were already gone.
+    pub fn skipped(&self) -> usize {
+        self.count(|o| matches!(o, CleanupOutcome::Planned(_) | CleanupOutcome::Skipped(_)))
+    }
+    
+    pub fn cancelled(&self) -> usize {
+        self.count(|o| matches!(o, CleanupOutcome::Cancelled))
+    }
+    
+    fn count(&self, predicate: impl Fn(&CleanupOutcome) -> bool) -> usize {
+        self.results.iter().filter(|r| predicate(&r.outcome)).count()
+    }
+    
+    /// Skipped forks count as neither success nor failure; on a dry run
+    /// planned forks count as successes.
+    pub fn exit_code(&self) -> u8 {
+        if self.cancelled() > 0 {
+            return exit_code::INTERRUPTED;
+        }
+        let succeeded = self.deleted() + self.count(|o| matches!(o, CleanupOutcome::Planned(_)));
+        exit_code::for_counts(succeeded, succeeded + self.failed())
+    }
+    
+    pub fn print(&self) {
+        for result in &self.results {
+            match &result.outcome {
+                CleanupOutcome::Done(mode) => println!("✅ {}: {}d", result.repo, mode.verb()),
+                CleanupOutcome::Planned(mode) => println!(
+                    "📝 {} ({:?}, billing {:.1}/120.0): would disable workflows and {}",
+                    result.repo, result.status, result.billing_used, mode.verb()
+                ),
+                CleanupOutcome::Blocked(reason) => println!(
+                    "🚫 {} ({:?}, billing {:.1}/120.0): would fail, {}",
+                    result.repo, result.status, result.billing_used, reason
+                ),
+                CleanupOutcome::Skipped(reason) => println!("⏭️  {}: skipped, {}", result.repo, reason),
+                CleanupOutcome::Failed(e) => println!("❌ {}: {}", result.repo, e),
+                CleanupOutcome::Cancelled => println!("⏹️  {}: cancelled", result.repo),
+            }
+        }
+        
+        println!(
+            "Matched {} · deleted {} · failed {} · skipped {}",
+            self.matched(),
+            self.deleted(),
+            self.failed(),
+            self.skipped()
+        );
+    }
+}
+
+/// Result of one `verify` check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Check {
+    Passed,
+    Failed(String),
+    /// Does not apply to the node, or a check it needs failed
+    NotRun,
+}
+
+impl Check {
+    fn from_result(result: Result<()>) -> Self {
+        match result {
+            Ok(()) => Check::Passed,
+            Err(e) => Check::Failed(format!("{:#}", e)),
+        }
+    }
+    
+    pub fn passed(&self) -> bool {
+        *self == Check::Passed
+    }
+    
+    fn icon(&self) -> &'static str {
+        match self {
+            Check::Passed => "✅",
+            Check::Failed(_) => "❌",
+            Check::NotRun => "➖",
+        }
+    }
+}
+
+/// `verify` checks of one chain node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeVerification {
+    pub repo: String,
+    pub status: ForkStatus,
+    /// The owning token still authenticates
+    pub token: Check,
+    pub repo_exists: Check,
+    /// The workflow file is in the repo
+    pub workflow: Check,
+    /// The workflow is enabled on Active forks and disabled on Exhausted or
+    /// Disabled ones
+    pub enabled: Check,
+    /// NEXUS_NODE_IDS and NEXUS_WALLETS are set
+    pub secrets: Check,
+}
+
+impl NodeVerification {
+    fn new(node: &ForkChainNode) -> Self {
+        Self {
+            repo: node.repo.clone(),
+            status: node.status.clone(),
+            token: Check::NotRun,
+            repo_exists: Check::NotRun,
+            workflow: Check::NotRun,
+            enabled: Check::NotRun,
+            secrets: Check::NotRun,
+        }
+    }
+    
+    /// (column, check) in table order.
+    pub fn checks(&self) -> [(&'static str, &Check); 5] {
+        [
+            ("token", &self.token),
+            ("repo", &self.repo_exists),
+            ("workflow", &self.workflow),
+            ("enabled", &self.enabled),
+            ("secrets", &self.secrets),
+        ]
+    }
+    
+    pub fn failed(&self) -> bool {
+        self.checks().iter().any(|(_, check)| matches!(check, Check::Failed(_)))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChainVerification {
+    pub nodes: Vec<NodeVerification>,
+}
+
+impl ChainVerification {
+    pub fn failed(&self) -> usize {
+        self.nodes.iter().filter(|n| n.failed()).count()
+    }
+    
+    /// Failures on other forks are reported but only the active fork's
+    /// fail the run.
+    pub fn exit_code(&self) -> u8 {
+        if self.nodes.iter().any(|n| n.status == ForkStatus::Active && n.failed()) {
+            exit_code::FAILURE
+        } else {
+            exit_code::SUCCESS
+        }
+    }
+    
+    pub fn print(&self) {
+        println!(
+            "{:<36} {:<10} {:<6} {:<6} {:<8} {:<7} {:<7}",
+            "Fork", "Status", "Token", "Repo", "Workflow", "Enabled", "Secrets"
+        );
+        println!("{}", "─".repeat(86));
+        
+        for node in &self.nodes {
+            let [token, repo, workflow, enabled, secrets] = node.checks().map(|(_, check)| check.icon());
+            println!(
+                "{:<36} {:<10} {:<5} {:<5} {:<7} {:<6} {:<6}",
+                node.repo,
+                format!("{:?}", node.status),
+                token, repo, workflow, enabled, secrets
+            );
+            for (name, check) in node.checks() {
+                if let Check::Failed(e) = check {
+                    println!("     ❌ {}: {}", name, e);
                 }
             }
-            
-            thread::sleep(Duration::from_secs(2));
         }
+        
+        println!("\nVerified {} forks, {} with failed checks", self.nodes.len(), self.failed());
     }
+}
+
+/// Deletes every fork `filter` matches, or on a dry run only lists what
+/// would happen to each one.
+pub fn cleanup_exhausted_forks(ctx: &AppContext, filter: &CleanupFilter, dry_run: bool) -> Result<CleanupSummary> {
+    info!("Starting cleanup of exhausted forks...");
+    
+    let state = ctx.state().load_state()?;
+    let options = CleanupOptions { filter: filter.clone(), dry_run, ..CleanupOptions::default() };
     
-    info!("Cleanup complete");
-    Ok(())
+    if options.filter.select(&state, Utc::now()).is_empty() {
+        info!("No exhausted forks to clean up");
+        return Ok(CleanupSummary::default());
+    }
+    
+    // Tokens for the credentials, proxies so each goes out over its own.
+    // A dry run without tokens still lists the forks, just unchecked.
+    let account_mgr = if dry_run { ctx.accounts().ok() } else { Some(ctx.accounts()?) };
+    let account_mgr = account_mgr.as_deref();
+    let proxy_mgr = &*ctx.proxies()?;
+    
+    // A token that cannot delete is refused before any fork is touched
+    if let (false, CleanupMode::Delete, Some(accounts)) = (dry_run, options.mode, account_mgr) {
+        for index in options.filter.select(&state, Utc::now()) {
+            if let Some(account) = accounts.get_account(state.fork_chain[index].pat_index) {
+                account.require(&[Capability::Delete])?;
+            }
+        }
+    }
+    
+    let fork_mgr = ForkManager::new(ctx.state());
+    let (_, summary) = fork_mgr.cleanup(state, &options, &|node| {
+        let (accounts, account) = account_mgr
+            .and_then(|accounts| Some((accounts, accounts.get_account(node.pat_index)?)))
+            .with_context(|| format!("no token for account index {}", node.pat_index))?;
+        let client = client_for_account(account, proxy_mgr)?;
+        if !dry_run {
+            accounts.revalidate(account, &client)?;
+        }
+        Ok(Box::new(client) as Box<dyn ForkApi>)
+    })?;
+    
+    Ok(summary)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::paths::Paths;
     
     #[test]
     fn test_fork_manager_creation() {
         let temp_dir = tempfile::tempdir().unwrap();
-        let state_mgr = StateManager::new(temp_dir.path()).unwrap();
-        let fork_mgr = ForkManager::new(state_mgr);
+        let state_mgr = StateManager::new(&Paths::new(temp_dir.path())).unwrap();
+        let fork_mgr = ForkManager::new(Arc::new(state_mgr));
         // Just test construction
     }
+    
+    #[test]
+    fn test_cleanup_uses_given_config_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        
+        // Empty state: nothing to clean, tokens.txt is never read
+        let summary = cleanup_exhausted_forks(&AppContext::new(temp_dir.path()).unwrap(), &CleanupFilter::default(), false).unwrap();
+        assert_eq!(summary, CleanupSummary::default());
+        assert_eq!(summary.exit_code(), exit_code::SUCCESS);
+        
+        assert!(temp_dir.path().join("cache").is_dir());
+    }
+    
+    fn node(pat_index: usize, status: ForkStatus, age_days: i64, now: DateTime<Utc>) -> ForkChainNode {
+        ForkChainNode {
+            pat_index,
+            username: format!("user_{}", pat_index),
+            owner_type: crate::core::account::OwnerType::User,
+            repo: format!("user_{}/nexus-runner", pat_index),
+            parent: None,
+            billing_used: 0.0,
+            status,
+            created_at: now - chrono::Duration::days(age_days),
+            last_updated: now - chrono::Duration::days(age_days),
+            last_run: None,
+        }
+    }
+    
+    fn chain(now: DateTime<Utc>) -> OrchestratorState {
+        OrchestratorState {
+            fork_chain: vec![
+                node(0, ForkStatus::Source, 30, now),
+                node(1, ForkStatus::Exhausted, 10, now),
+                node(2, ForkStatus::Exhausted, 1, now),
+                node(3, ForkStatus::Disabled, 10, now),
+                node(4, ForkStatus::Deleted, 10, now),
+                node(5, ForkStatus::Active, 10, now),
+            ],
+            ..OrchestratorState::default()
+        }
+    }
+    
+    #[test]
+    fn test_cleanup_filter_defaults_to_exhausted() {
+        let now = Utc::now();
+        assert_eq!(CleanupFilter::default().select(&chain(now), now), vec![1, 2]);
+    }
+    
+    #[test]
+    fn test_cleanup_filter_composition() {
+        let now = Utc::now();
+        let state = chain(now);
+        let week = Some(chrono::Duration::days(7));
+        
+        let filter = CleanupFilter {
+            statuses: vec![ForkStatus::Exhausted, ForkStatus::Disabled, ForkStatus::Deleted],
+            ..CleanupFilter::default()
+        };
+        assert_eq!(filter.select(&state, now), vec![1, 2, 3, 4]);
+        
+        let filter = CleanupFilter { older_than: week, ..filter };
+        assert_eq!(filter.select(&state, now), vec![1, 3, 4]);
+        
+        let filter = CleanupFilter { account: Some("USER_3".to_string()), ..filter };
+        assert_eq!(filter.select(&state, now), vec![3]);
+        
+        // Criteria are ANDed: user_2's fork is too young
+        let filter = CleanupFilter { account: Some("user_2".to_string()), ..filter };
+        assert!(filter.select(&state, now).is_empty());
+        
+        // The source repo never matches, whatever the filter says
+        let filter = CleanupFilter { statuses: vec![ForkStatus::Source], ..CleanupFilter::default() };
+        assert!(filter.select(&state, now).is_empty());
+    }
+    
+    #[test]
+    fn test_cleanup_filter_keeps_newest_exhausted_forks() {
+        let now = Utc::now();
+        let mut state = chain(now);
+        state.fork_chain.push(node(6, ForkStatus::Exhausted, 5, now));
+        
+        // user_2 (1 day) and user_6 (5 days) are the newest exhausted forks
+        let filter = CleanupFilter { keep_last: 2, ..CleanupFilter::default() };
+        assert_eq!(filter.select(&state, now), vec![1]);
+        
+        // Kept forks count even when another criterion already skips them
+        let filter = CleanupFilter { older_than: Some(chrono::Duration::days(3)), keep_last: 1, ..filter };
+        assert_eq!(filter.select(&state, now), vec![1, 6]);
+        
+        let filter = CleanupFilter { keep_last: 10, ..filter };
+        assert!(filter.select(&state, now).is_empty());
+        
+        // Age is taken from creation, not from the last status change
+        state.fork_chain[1].last_updated = now;
+        let filter = CleanupFilter { older_than: Some(chrono::Duration::days(7)), ..CleanupFilter::default() };
+        assert_eq!(filter.select(&state, now), vec![1]);
+    }
+    
+    #[test]
+    fn test_find_orphans() {
+        let now = Utc::now();
+        let fork = |owner: &str, name: &str| (owner.to_string(), format!("{}/{}", owner, name));
+        let forks = vec![
+            fork("user_1", "nexus-runner"),
+            fork("user_4", "nexus-runner"),
+            fork("User_6", "nexus-runner"),
+            fork("stranger", "nexus-runner"),
+        ];
+        let managed = ["user_1", "user_4", "user_6"];
+        
+        // user_1 is tracked, user_4's node is Deleted, user_6 is not in the
+        // chain at all, strangers are never managed
+        let orphans = find_orphans(&chain(now), &forks, &managed);
+        let repos: Vec<&str> = orphans.iter().map(|o| o.repo.as_str()).collect();
+        assert_eq!(repos, vec!["user_4/nexus-runner", "User_6/nexus-runner"]);
+        
+        assert!(find_orphans(&chain(now), &forks, &[]).is_empty());
+    }
+    
+    #[test]
+    fn test_chain_snapshot_query() {
+        let (query, variables) = chain_snapshot_query(&["alice/nexus-runner", "acme/nexus-runner"]).unwrap();
+        
+        assert!(query.starts_with("query($owner0: String!, $name0: String!, $owner1: String!, $name1: String!) {"));
+        assert!(query.contains("repo1: repository(owner: $owner1, name: $name1) { ...snapshot }"));
+        assert!(query.contains("fragment snapshot on Repository"));
+        assert_eq!(variables, serde_json::json!({
+            "owner0": "alice", "name0": "nexus-runner",
+            "owner1": "acme", "name1": "nexus-runner",
+        }));
+        
+        assert!(chain_snapshot_query(&["no-slash"]).is_err());
+    }
+    
+    #[test]
+    fn test_parse_fork_snapshot() {
+        let node = serde_json::json!({
+            "defaultBranchRef": {
+                "name": "main",
+                "target": { "checkSuites": { "nodes": [{
+                    "status": "COMPLETED",
+                    "conclusion": "SUCCESS",
+                    "workflowRun": { "databaseId": 42, "createdAt": "2026-10-16T10:00:00Z" }
+                }] } }
+            }
+        });
+        let snapshot = parse_fork_snapshot("alice/nexus-runner", &node);
+        assert!(snapshot.exists);
+        assert_eq!(snapshot.latest_run, Some(LatestRun {
+            run_id: 42,
+            status: "completed".to_string(),
+            conclusion: Some("success".to_string()),
+            created_at: Some("2026-10-16T10:00:00Z".parse().unwrap()),
+        }));
+        
+        // Exists but never ran
+        let idle = parse_fork_snapshot("alice/nexus-runner", &serde_json::json!({ "defaultBranchRef": { "name": "main", "target": {} } }));
+        assert_eq!((idle.exists, idle.latest_run), (true, None));
+        
+        let gone = parse_fork_snapshot("bob/nexus-runner", &serde_json::Value::Null);
+        assert_eq!((gone.exists, gone.default_branch), (false, None));
+    }
+    
+    #[test]
+    fn test_parse_cleanup_flags() {
+        assert_eq!(parse_cleanup_status("Deleted").unwrap(), ForkStatus::Deleted);
+        assert_eq!(CleanupMode::parse("archive").unwrap(), CleanupMode::Archive);
+        
+        for err in [parse_cleanup_status("active").unwrap_err(), CleanupMode::parse("purge").unwrap_err()] {
+            assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        }
+    }
+    
+    #[test]
+    fn test_cleanup_summary_counts() {
+        let result = |outcome| CleanupResult::new(node(1, ForkStatus::Exhausted, 0, Utc::now()), outcome);
+        let summary = CleanupSummary {
+            results: vec![
+                result(CleanupOutcome::Done(CleanupMode::Delete)),
+                result(CleanupOutcome::Skipped("repository is already gone".to_string())),
+                result(CleanupOutcome::Failed("HTTP 403".to_string())),
+            ],
+        };
+        
+        assert_eq!((summary.matched(), summary.deleted(), summary.failed(), summary.skipped()), (3, 1, 1, 1));
+        assert_eq!(summary.exit_code(), exit_code::PARTIAL);
+        
+        let mut dry_run = CleanupSummary { results: vec![result(CleanupOutcome::Planned(CleanupMode::Archive))] };
+        assert_eq!(dry_run.skipped(), 1);
+        assert_eq!(dry_run.exit_code(), exit_code::SUCCESS);
+        
+        dry_run.results.push(result(CleanupOutcome::Blocked("token lacks the delete_repo scope".to_string())));
+        assert_eq!((dry_run.deleted(), dry_run.failed()), (0, 1));
+        assert_eq!(dry_run.exit_code(), exit_code::PARTIAL);
+    }
 }
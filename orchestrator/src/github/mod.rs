@@ -2,10 +2,16 @@
 
 pub mod api;
 pub mod fork;
+pub mod secret_hashes;
+pub mod secret_vault;
 pub mod secrets;
+pub mod variables;
 pub mod workflow;
 
-pub use api::GitHubClient;
-pub use fork::ForkManager;
-pub use secrets::SecretsManager;
+pub use api::{ApiFactory, GitHubApi, GitHubClient};
+pub use fork::{ForkApi, ForkManager};
+pub use secret_hashes::SecretHashes;
+pub use secret_vault::SecretVault;
+pub use secrets::{SecretLayout, SecretStore, SecretWriteResult, SecretsManager};
+pub use variables::VariablesManager;
 pub use workflow::WorkflowController;
@@ -0,0 +1,251 @@
+// src/github/secret_hashes.rs - Local record of secret values already on GitHub
+
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use log::warn;
+use crate::utils::crypto;
+
+const KEY_LEN: usize = 32;
+const BLOCK_LEN: usize = 64;
+
+/// What `SecretsManager::sync_secrets` does for one repo.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SecretSyncPlan {
+    /// Changed, new, or missing on GitHub
+    pub set: Vec<String>,
+    /// Same value as last written and still listed
+    pub unchanged: Vec<String>,
+    /// Written by us earlier, no longer wanted, still listed
+    pub delete: Vec<String>,
+}
+
+/// HMAC-SHA256 of each secret value the orchestrator wrote, per repo and
+/// name (secrets_manifest.json). The HMAC key is random and kept next to it
+/// in secrets_manifest.key, so the manifest alone cannot confirm a guessed
+/// value.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SecretHashes {
+    /// repo -> secret name -> hex HMAC of the value
+    repos: BTreeMap<String, BTreeMap<String, String>>,
+    #[serde(skip)]
+    key: Vec<u8>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl SecretHashes {
+    /// The manifest at `path`, empty when missing or unreadable. The key is
+    /// created on first use; a new key makes every recorded hash stale.
+    pub fn load(path: &Path) -> Result<Self> {
+        let key_path = path.with_extension("key");
+        let key = match fs::read(&key_path) {
+            Ok(key) if key.len() == KEY_LEN => key,
+            _ => {
+                let key = crypto::random_bytes(KEY_LEN);
+                write_private(&key_path, &key)?;
+                key
+            }
+        };
+        
+        let mut hashes: Self = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        hashes.key = key;
+        hashes.path = path.to_path_buf();
+        Ok(hashes)
+    }
+    
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(&self.path, json).with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+    
+    /// Which of `desired` to write and which managed secrets to delete,
+    /// given the names GitHub lists as `present`. `force` writes them all.
+    pub fn plan(&self, repo: &str, desired: &BTreeMap<String, String>, present: &[String], force: bool) -> SecretSyncPlan {
+        let recorded = self.repos.get(repo);
+        let mut plan = SecretSyncPlan::default();
+        
+        for (name, value) in desired {
+            let listed = present.iter().any(|n| n == name);
+            let same = recorded
+                .and_then(|names| names.get(name))
+                .is_some_and(|hash| *hash == self.hash(value));
+            if listed && same && !force {
+                plan.unchanged.push(name.clone());
+            } else {
+                plan.set.push(name.clone());
+            }
+        }
+        
+        plan.delete = recorded
+            .into_iter()
+            .flat_map(|names| names.keys())
+            .filter(|name| !desired.contains_key(*name) && present.iter().any(|n| n == *name))
+            .cloned()
+            .collect();
+        plan
+    }
+    
+    pub fn record(&mut self, repo: &str, name: &str, value: &str) {
+        let hash = self.hash(value);
+        self.repos.entry(repo.to_string()).or_default().insert(name.to_string(), hash);
+    }
+    
+    pub fn forget(&mut self, repo: &str, name: &str) {
+        if let Some(names) = self.repos.get_mut(repo) {
+            names.remove(name);
+            if names.is_empty() {
+                self.repos.remove(repo);
+            }
+        }
+    }
+    
+    /// Names recorded for `repo`.
+    pub fn managed(&self, repo: &str) -> Vec<String> {
+        self.repos.get(repo).map(|names| names.keys().cloned().collect()).unwrap_or_default()
+    }
+    
+    fn hash(&self, value: &str) -> String {
+        hmac_sha256(&self.key, value.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// HMAC-SHA256 (RFC 2104) with a key no longer than one block.
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; BLOCK_LEN];
+    block[..key.len()].copy_from_slice(key);
+    
+    let mut inner = Sha256::new();
+    inner.update(block.map(|b| b ^ 0x36));
+    inner.update(message);
+    
+    let mut outer = Sha256::new();
+    outer.update(block.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+pub(crate) fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        file.write_all(content)?;
+    }
+    
+    #[cfg(not(unix))]
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    fn desired(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+    
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+    
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        // Test case 2 of RFC 4231
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = mac.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+    
+    #[test]
+    fn test_plan_follows_recorded_values() {
+        let temp_dir = tempdir().unwrap();
+        let mut hashes = SecretHashes::load(&temp_dir.path().join("secrets_manifest.json")).unwrap();
+        let present = names(&["API_KEY", "OLD"]);
+        
+        // Nothing recorded: everything is written
+        let plan = hashes.plan("a/b", &desired(&[("API_KEY", "one")]), &present, false);
+        assert_eq!(plan.set, names(&["API_KEY"]));
+        
+        hashes.record("a/b", "API_KEY", "one");
+        hashes.record("a/b", "OLD", "x");
+        let plan = hashes.plan("a/b", &desired(&[("API_KEY", "one")]), &present, false);
+        assert_eq!(plan, SecretSyncPlan {
+            set: vec![],
+            unchanged: names(&["API_KEY"]),
+            delete: names(&["OLD"]),
+        });
+        
+        // A changed value, and force, both write
+        assert_eq!(hashes.plan("a/b", &desired(&[("API_KEY", "two")]), &present, false).set, names(&["API_KEY"]));
+        assert_eq!(hashes.plan("a/b", &desired(&[("API_KEY", "one")]), &present, true).set, names(&["API_KEY"]));
+        
+        // Recorded per repo
+        assert_eq!(hashes.plan("a/c", &desired(&[("API_KEY", "one")]), &present, false).set, names(&["API_KEY"]));
+    }
+    
+    #[test]
+    fn test_recorded_but_missing_on_github_is_written() {
+        let temp_dir = tempdir().unwrap();
+        let mut hashes = SecretHashes::load(&temp_dir.path().join("secrets_manifest.json")).unwrap();
+        hashes.record("a/b", "API_KEY", "one");
+        hashes.record("a/b", "OLD", "x");
+        
+        // Someone deleted both on GitHub: write the wanted one, leave the other be
+        let plan = hashes.plan("a/b", &desired(&[("API_KEY", "one")]), &[], false);
+        assert_eq!(plan.set, names(&["API_KEY"]));
+        assert!(plan.delete.is_empty());
+    }
+    
+    #[test]
+    fn test_saved_hashes_need_the_same_key() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cache").join("secrets_manifest.json");
+        let present = names(&["API_KEY"]);
+        
+        let mut hashes = SecretHashes::load(&path).unwrap();
+        hashes.record("a/b", "API_KEY", "one");
+        hashes.save().unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("one"));
+        
+        let reloaded = SecretHashes::load(&path).unwrap();
+        assert_eq!(reloaded.managed("a/b"), names(&["API_KEY"]));
+        assert!(reloaded.plan("a/b", &desired(&[("API_KEY", "one")]), &present, false).set.is_empty());
+        
+        // A new key invalidates what was recorded
+        fs::remove_file(path.with_extension("key")).unwrap();
+        let rekeyed = SecretHashes::load(&path).unwrap();
+        assert_eq!(rekeyed.plan("a/b", &desired(&[("API_KEY", "one")]), &present, false).set, names(&["API_KEY"]));
+        
+        let mut hashes = rekeyed;
+        hashes.forget("a/b", "API_KEY");
+        assert!(hashes.managed("a/b").is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+// src/github/secret_vault.rs - Encrypted local copy of secrets set through the orchestrator
+
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::github::secret_hashes::write_private;
+use crate::utils::crypto;
+use crate::utils::exit_code::ConfigError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VaultEntry {
+    pub value: String,
+    /// Repo the value was last written to
+    pub repo: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Secrets GitHub will not hand back, kept so a replacement fork can get
+/// them again (vault.bin). The file is sealed with a random key kept next
+/// to it in vault.key; both are only readable by the owner.
+#[derive(Debug)]
+pub struct SecretVault {
+    /// secret name -> last value written under it
+    entries: BTreeMap<String, VaultEntry>,
+    key: Vec<u8>,
+    path: PathBuf,
+}
+
+impl SecretVault {
+    /// The vault at `path`, empty when missing. The key is created with the
+    /// vault; a vault whose key is gone or does not open it is an error
+    /// rather than being replaced, since its values exist nowhere else.
+    pub fn load(path: &Path) -> Result<Self> {
+        let key_path = path.with_extension("key");
+        
+        if !path.exists() {
+            let key = match fs::read(&key_path) {
+                Ok(key) if key.len() == crypto::SECRETBOX_KEY_LEN => key,
+                _ => {
+                    let key = crypto::random_bytes(crypto::SECRETBOX_KEY_LEN);
+                    write_private(&key_path, &key)?;
+                    key
+                }
+            };
+            return Ok(Self { entries: BTreeMap::new(), key, path: path.to_path_buf() });
+        }
+        
+        let key = fs::read(&key_path).with_context(|| ConfigError::new(format!(
+            "{} exists but its key {} cannot be read",
+            path.display(),
+            key_path.display()
+        )))?;
+        let sealed = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let json = crypto::secretbox_open(&key, &sealed).with_context(|| ConfigError::new(format!(
+            "Cannot open {} with {}",
+            path.display(),
+            key_path.display()
+        )))?;
+        let entries = serde_json::from_slice(&json)
+            .with_context(|| format!("Invalid vault contents in {}", path.display()))?;
+        
+        Ok(Self { entries, key, path: path.to_path_buf() })
+    }
+    
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_vec(&self.entries)?;
+        write_private(&self.path, &crypto::secretbox_seal(&self.key, &json)?)
+    }
+    
+    pub fn store(&mut self, repo: &str, name: &str, value: &str) {
+        self.entries.insert(name.to_string(), VaultEntry {
+            value: value.to_string(),
+            repo: repo.to_string(),
+            updated_at: Utc::now(),
+        });
+    }
+    
+    /// Drops `name`; false when it was not vaulted.
+    pub fn forget(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+    
+    /// Every vaulted secret, by name.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &VaultEntry)> {
+        self.entries.iter().map(|(name, entry)| (name.as_str(), entry))
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_vault_round_trip_is_sealed() {
+        crypto::init_crypto().unwrap();
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("cache").join("vault.bin");
+        
+        let mut vault = SecretVault::load(&path).unwrap();
+        assert!(vault.is_empty());
+        vault.store("a/b", "API_KEY", "one");
+        vault.store("a/c", "API_KEY", "two");
+        vault.store("a/b", "RPC_URL", "https://rpc");
+        vault.save().unwrap();
+        
+        let raw = fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("two"));
+        assert!(!String::from_utf8_lossy(&raw).contains("API_KEY"));
+        
+        let mut reloaded = SecretVault::load(&path).unwrap();
+        let names: Vec<&str> = reloaded.entries().map(|(name, _)| name).collect();
+        assert_eq!(names, ["API_KEY", "RPC_URL"]);
+        let entry = reloaded.entries().next().unwrap().1;
+        assert_eq!((entry.value.as_str(), entry.repo.as_str()), ("two", "a/c"));
+        
+        assert!(reloaded.forget("RPC_URL"));
+        assert!(!reloaded.forget("RPC_URL"));
+    }
+    
+    #[test]
+    fn test_vault_without_its_key_is_not_replaced() {
+        crypto::init_crypto().unwrap();
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("vault.bin");
+        
+        let mut vault = SecretVault::load(&path).unwrap();
+        vault.store("a/b", "API_KEY", "one");
+        vault.save().unwrap();
+        let sealed = fs::read(&path).unwrap();
+        
+        fs::remove_file(path.with_extension("key")).unwrap();
+        assert!(SecretVault::load(&path).is_err());
+        
+        fs::write(path.with_extension("key"), crypto::random_bytes(crypto::SECRETBOX_KEY_LEN)).unwrap();
+        assert!(SecretVault::load(&path).is_err());
+        assert_eq!(fs::read(&path).unwrap(), sealed);
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_vault_files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        crypto::init_crypto().unwrap();
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("vault.bin");
+        
+        let mut vault = SecretVault::load(&path).unwrap();
+        vault.store("a/b", "API_KEY", "one");
+        vault.save().unwrap();
+        
+        for file in [path.clone(), path.with_extension("key")] {
+            assert_eq!(fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+}
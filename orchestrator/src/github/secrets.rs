@@ -1,152 +1,651 @@
 // src/github/secrets.rs - Secrets management (ported from Datagram)
 
 use anyhow::{Result, Context, bail};
+use chrono::{DateTime, Utc};
 use log::{info, debug, warn};
-use std::thread;
-use std::time::Duration;
-use crate::github::api::GitHubClient;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::core::settings;
+use crate::github::api::{GitHubApi, GitHubError};
+use crate::github::secret_hashes::{SecretHashes, SecretSyncPlan};
+use crate::github::secret_vault::SecretVault;
 use crate::utils::crypto::encrypt_for_github;
+use crate::utils::delay::{self, Sleeper};
+use crate::utils::retry::retry_with_backoff;
+
+/// What happened to a secret write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretWriteResult {
+    Created,
+    Updated,
+    /// The PUT succeeded but the secret could not be read back
+    Unverified,
+}
+
+impl std::fmt::Display for SecretWriteResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretWriteResult::Created => write!(f, "created"),
+            SecretWriteResult::Updated => write!(f, "updated"),
+            SecretWriteResult::Unverified => write!(f, "unverified"),
+        }
+    }
+}
+
+/// Secrets the Nexus workflow reads, see `SecretsManager::set_nexus_secrets`.
+pub const NEXUS_SECRETS: [&str; 2] = ["NEXUS_NODE_IDS", "NEXUS_WALLETS"];
+
+/// Prefixes of the per-node secrets in the indexed layout.
+pub const INDEXED_SECRETS: [&str; 2] = ["NEXUS_NODE", "NEXUS_WALLET"];
+
+/// Most secrets GitHub keeps in one repository or environment.
+const MAX_SECRETS: usize = 100;
+
+/// How the node IDs and wallets are laid out over secrets; setup.json
+/// `secret_layout`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretLayout {
+    /// NEXUS_SECRETS, newline-separated, split when too large
+    #[default]
+    Joined,
+    /// `NEXUS_NODE_<n>` and `NEXUS_WALLET_<n>` for node n (from 1), so one
+    /// node can change without rewriting the others
+    Indexed,
+}
+
+impl SecretLayout {
+    /// The secrets holding node `index` (1-based) in the indexed layout.
+    pub fn indexed_names(index: usize) -> [String; 2] {
+        INDEXED_SECRETS.map(|prefix| format!("{}_{}", prefix, index))
+    }
+    
+    /// (name, value) of every secret carrying `node_ids` and `wallets`.
+    fn secrets(self, node_ids: &[String], wallets: &[String]) -> Result<Vec<(String, String)>> {
+        match self {
+            SecretLayout::Joined => {
+                let mut secrets = Vec::new();
+                for (name, values) in NEXUS_SECRETS.iter().zip([node_ids, wallets]) {
+                    let value = values.join("\n");
+                    let parts = chunk_secret(name, &value)?;
+                    if parts.len() > 1 {
+                        info!("{} is {} bytes, split into {} secrets", name, value.len(), parts.len() - 1);
+                    }
+                    secrets.extend(parts);
+                }
+                Ok(secrets)
+            }
+            SecretLayout::Indexed => {
+                if node_ids.len() * 2 > MAX_SECRETS {
+                    bail!(
+                        "{} nodes need {} secrets in the indexed layout, over GitHub's {}; use the joined layout",
+                        node_ids.len(),
+                        node_ids.len() * 2,
+                        MAX_SECRETS
+                    );
+                }
+                Ok(node_ids
+                    .iter()
+                    .zip(wallets)
+                    .enumerate()
+                    .flat_map(|(i, (node_id, wallet))| {
+                        let [node, wallet_name] = Self::indexed_names(i + 1);
+                        [(node, node_id.clone()), (wallet_name, wallet.clone())]
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+/// How far our clock may run ahead of GitHub's when checking `updated_at`.
+const CLOCK_SKEW_SECS: i64 = 120;
+
+/// Largest secret value GitHub accepts, in bytes.
+pub const SECRET_SIZE_LIMIT: usize = 48 * 1024;
+
+/// The secrets that carry `value` under `name`: the pair itself when it
+/// fits in one secret, otherwise `NAME_1`..`NAME_n` holding whole lines and
+/// then `NAME_COUNT` = n. Joining the parts with newlines gives the value
+/// back; the workflow does so whenever `NAME_COUNT` is set.
+pub fn chunk_secret(name: &str, value: &str) -> Result<Vec<(String, String)>> {
+    if value.len() <= SECRET_SIZE_LIMIT {
+        return Ok(vec![(name.to_string(), value.to_string())]);
+    }
+    
+    let mut chunks: Vec<String> = Vec::new();
+    for line in value.split('\n') {
+        if line.len() > SECRET_SIZE_LIMIT {
+            bail!("{} has a line longer than {} bytes, which no secret can hold", name, SECRET_SIZE_LIMIT);
+        }
+        match chunks.last_mut() {
+            Some(chunk) if chunk.len() + 1 + line.len() <= SECRET_SIZE_LIMIT => {
+                chunk.push('\n');
+                chunk.push_str(line);
+            }
+            _ => chunks.push(line.to_string()),
+        }
+    }
+    
+    let count = chunks.len();
+    // The count goes last, so a run never sees it before every part exists
+    Ok(chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| (format!("{}_{}", name, i + 1), chunk))
+        .chain(std::iter::once((format!("{}_COUNT", name), count.to_string())))
+        .collect())
+}
+
+/// Whether `secret` is `name` itself or one of its `chunk_secret` parts.
+fn is_part_of(secret: &str, name: &str) -> bool {
+    match secret.strip_prefix(name) {
+        Some("") => true,
+        Some(rest) => rest
+            .strip_prefix('_')
+            .is_some_and(|suffix| suffix == "COUNT" || (!suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_digit()))),
+        None => false,
+    }
+}
+
+/// Whether `secret` is one of NEXUS_SECRETS, whole or split, or a
+/// per-node secret of the indexed layout.
+fn is_nexus_secret(secret: &str) -> bool {
+    NEXUS_SECRETS.iter().chain(&INDEXED_SECRETS).any(|name| is_part_of(secret, name))
+}
+
+/// Names in `secrets` that `listed` lacks or shows as updated before `since`.
+fn not_updated_since<'a>(secrets: &[(&'a str, &str)], listed: &[(String, DateTime<Utc>)], since: DateTime<Utc>) -> Vec<&'a str> {
+    secrets
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| !listed.iter().any(|(n, updated_at)| n == name && *updated_at >= since))
+        .collect()
+}
+
+/// Whether `names` holds `name`, whole or split by `chunk_secret`.
+pub fn has_secret(names: &[String], name: &str) -> bool {
+    let count = format!("{}_COUNT", name);
+    names.iter().any(|n| *n == name || *n == count)
+}
+
+/// The Nexus secrets `names` lacks, in whichever layout it holds: the
+/// wallets of indexed nodes without one, else NEXUS_SECRETS not set.
+pub fn missing_nexus_secrets(names: &[String]) -> Vec<String> {
+    let nodes: Vec<&str> = names
+        .iter()
+        .filter_map(|n| n.strip_prefix("NEXUS_NODE_"))
+        .filter(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+        .collect();
+    
+    if nodes.is_empty() {
+        return NEXUS_SECRETS
+            .iter()
+            .filter(|s| !has_secret(names, s))
+            .map(|s| s.to_string())
+            .collect();
+    }
+    nodes
+        .into_iter()
+        .map(|index| format!("NEXUS_WALLET_{}", index))
+        .filter(|wallet| !names.contains(wallet))
+        .collect()
+}
+
+/// Repository secret operations, so callers can run against a test double.
+pub trait SecretStore {
+    fn set_secret(&self, repo: &str, secret_name: &str, secret_value: &str) -> Result<SecretWriteResult>;
+    fn delete_secret(&self, repo: &str, secret_name: &str) -> Result<()>;
+    fn list_secrets(&self, repo: &str) -> Result<Vec<String>>;
+}
 
 pub struct SecretsManager {
-    client: GitHubClient,
+    client: Box<dyn GitHubApi>,
+    /// Where `set_nexus_secrets` writes; None for repository secrets
+    environment: Option<String>,
+    /// Values already written, so `sync_secrets` can skip them
+    hashes: Option<Arc<Mutex<SecretHashes>>>,
+    /// Copies of what `set_secrets` wrote, for `replay_secrets`
+    vault: Option<Arc<Mutex<SecretVault>>>,
+    /// `sync_secrets` writes everything regardless of `hashes`
+    force: bool,
+    /// An unverified write is Unverified rather than an error
+    lenient_verification: bool,
+    sleeper: Arc<dyn Sleeper>,
 }
 
 impl SecretsManager {
-    pub fn new(client: GitHubClient) -> Self {
-        Self { client }
+    pub fn new(client: Box<dyn GitHubApi>) -> Self {
+        Self {
+            client,
+            environment: None,
+            hashes: None,
+            vault: None,
+            force: false,
+            lenient_verification: settings::get().github.lenient_secret_verification,
+            sleeper: delay::thread_sleeper(),
+        }
+    }
+    
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+    
+    /// Sends the Nexus secrets to `environment`, creating it when missing.
+    pub fn with_environment(mut self, environment: Option<String>) -> Self {
+        self.environment = environment;
+        self
+    }
+    
+    /// Lets `sync_secrets` skip values it wrote before; saved after each sync.
+    pub fn with_secret_hashes(mut self, hashes: SecretHashes) -> Self {
+        self.hashes = Some(Arc::new(Mutex::new(hashes)));
+        self
     }
     
-    fn get_repo_public_key(&self, repo: &str) -> Result<(String, String)> {
-        debug!("Getting public key for {}", repo);
+    /// As `with_secret_hashes`, for managers syncing different repos at
+    /// once; the manifest is only locked to plan, record and save.
+    pub fn with_shared_secret_hashes(mut self, hashes: Arc<Mutex<SecretHashes>>) -> Self {
+        self.hashes = Some(hashes);
+        self
+    }
+    
+    /// Keeps what `set_secrets` writes in `vault`, saved after each write,
+    /// so `replay_secrets` can put it on another fork.
+    pub fn with_vault(mut self, vault: Arc<Mutex<SecretVault>>) -> Self {
+        self.vault = Some(vault);
+        self
+    }
+    
+    /// Makes `sync_secrets` write every secret, as without a manifest.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+    
+    /// Reports secrets that could not be verified as Unverified instead of
+    /// failing; defaults to `github.lenient_secret_verification`.
+    pub fn with_lenient_verification(mut self, lenient: bool) -> Self {
+        self.lenient_verification = lenient;
+        self
+    }
+    
+    pub fn set_secret(&self, repo: &str, secret_name: &str, secret_value: &str) -> Result<SecretWriteResult> {
+        let mut results = self.set_secrets(repo, &[(secret_name, secret_value)])?;
+        Ok(results.remove(0).1)
+    }
+    
+    /// Writes several secrets with one public key fetch and one read-back:
+    /// every value is encrypted before the first PUT, the PUTs go out one
+    /// after another, and a read-back of `updated_at` afterwards verifies them all.
+    pub fn set_secrets(&self, repo: &str, secrets: &[(&str, &str)]) -> Result<Vec<(String, SecretWriteResult)>> {
+        let started = Instant::now();
+        info!("Setting {} secrets in {}", secrets.len(), repo);
         
-        let response = self.client.api_call(
-            &format!("repos/{}/actions/secrets/public-key", repo),
-            "GET"
-        )?;
+        let existing = self.client.list_secrets(repo).unwrap_or_default();
+        let (public_key, key_id) = self.client.get_repo_public_key(repo)?;
         
-        let json: serde_json::Value = serde_json::from_str(&response)
-            .context("Failed to parse public key response")?;
+        let results = self.write_all(
+            secrets,
+            &public_key,
+            &existing,
+            |name, value| self.client.put_secret(repo, name, value, &key_id),
+            || self.client.list_secret_times(repo),
+        )?;
         
-        let key = json["key"]
-            .as_str()
-            .context("Public key not found")?
-            .to_string();
+        info!("Set {} secrets in {} in {:.1?}", secrets.len(), repo, started.elapsed());
         
-        let key_id = json["key_id"]
-            .as_str()
-            .context("Key ID not found")?
-            .to_string();
+        // The Nexus secrets are rebuilt from nodes.txt and wallets.txt
+        if let Some(vault) = &self.vault {
+            let mut vault = vault.lock().unwrap();
+            for (name, value) in secrets.iter().filter(|(name, _)| !is_nexus_secret(name)) {
+                vault.store(repo, name, value);
+            }
+            vault.save()?;
+        }
+        Ok(results)
+    }
+    
+    /// Writes every vaulted secret to `target_repo`, as when a new fork
+    /// takes over from the one they were set on. Nothing without a vault.
+    pub fn replay_secrets(&self, target_repo: &str) -> Result<Vec<(String, SecretWriteResult)>> {
+        let Some(vault) = &self.vault else {
+            return Ok(Vec::new());
+        };
+        let vaulted: Vec<(String, String)> = vault
+            .lock()
+            .unwrap()
+            .entries()
+            .map(|(name, entry)| (name.to_string(), entry.value.clone()))
+            .collect();
+        if vaulted.is_empty() {
+            return Ok(Vec::new());
+        }
         
-        Ok((key, key_id))
+        info!("Replaying {} vaulted secrets to {}", vaulted.len(), target_repo);
+        let secrets: Vec<(&str, &str)> = vaulted.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect();
+        self.set_secrets(target_repo, &secrets)
     }
     
-    pub fn set_secret(&self, repo: &str, secret_name: &str, secret_value: &str) -> Result<()> {
-        info!("Setting secret {} in {}", secret_name, repo);
+    /// Brings `repo` to `desired`: writes the secrets whose value changed
+    /// since the manifest recorded it, or that GitHub no longer lists, and
+    /// deletes secrets written earlier that are no longer desired. Without
+    /// a manifest, or with `with_force`, every desired secret is written.
+    pub fn sync_secrets(&self, repo: &str, desired: &BTreeMap<String, String>) -> Result<SecretSyncPlan> {
+        let Some(hashes) = &self.hashes else {
+            let secrets: Vec<(&str, &str)> = desired.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect();
+            self.set_secrets(repo, &secrets)?;
+            return Ok(SecretSyncPlan { set: desired.keys().cloned().collect(), ..SecretSyncPlan::default() });
+        };
+        let present = self.client.list_secrets(repo)?;
+        let plan = hashes.lock().unwrap().plan(repo, desired, &present, self.force);
+        info!(
+            "Secrets in {}: {} to set, {} unchanged, {} to delete",
+            repo, plan.set.len(), plan.unchanged.len(), plan.delete.len()
+        );
         
-        // Get public key
-        let (public_key, key_id) = self.get_repo_public_key(repo)?;
+        if !plan.set.is_empty() {
+            let secrets: Vec<(&str, &str)> = plan.set.iter().map(|n| (n.as_str(), desired[n].as_str())).collect();
+            let results = self.set_secrets(repo, &secrets);
+            let mut hashes = hashes.lock().unwrap();
+            // Whatever was written before a failure is kept
+            for (name, value) in &secrets {
+                match &results {
+                    Ok(results) if results.iter().any(|(n, r)| n == name && *r == SecretWriteResult::Unverified) => {
+                        hashes.forget(repo, name)
+                    }
+                    Ok(_) => hashes.record(repo, name, value),
+                    Err(_) => hashes.forget(repo, name),
+                }
+            }
+            if let Err(e) = results {
+                hashes.save()?;
+                return Err(e);
+            }
+        }
         
-        // Encrypt value
-        let encrypted_value = encrypt_for_github(&public_key, secret_value)
-            .context("Failed to encrypt secret value")?;
+        for name in &plan.delete {
+            self.delete_secret(repo, name)?;
+            hashes.lock().unwrap().forget(repo, name);
+        }
         
-        // Prepare payload
-        let payload = serde_json::json!({
-            "encrypted_value": encrypted_value,
-            "key_id": key_id
-        });
+        let mut hashes = hashes.lock().unwrap();
+        // Managed names neither wanted nor listed need no call, only forgetting
+        for name in hashes.managed(repo) {
+            if !desired.contains_key(&name) {
+                hashes.forget(repo, &name);
+            }
+        }
+        
+        hashes.save()?;
+        Ok(plan)
+    }
+    
+    pub fn set_environment_secret(&self, repo: &str, environment: &str, secret_name: &str, secret_value: &str) -> Result<SecretWriteResult> {
+        let mut results = self.set_environment_secrets(repo, environment, &[(secret_name, secret_value)])?;
+        Ok(results.remove(0).1)
+    }
+    
+    /// `set_secrets` for the secrets of one GitHub Environment, which are
+    /// addressed by the repo's numeric id.
+    pub fn set_environment_secrets(
+        &self,
+        repo: &str,
+        environment: &str,
+        secrets: &[(&str, &str)],
+    ) -> Result<Vec<(String, SecretWriteResult)>> {
+        let started = Instant::now();
+        info!("Setting {} secrets in environment {} of {}", secrets.len(), environment, repo);
         
-        // Set secret
-        self.client.api_call_with_data(
-            &format!("repos/{}/actions/secrets/{}", repo, secret_name),
-            "PUT",
-            &payload.to_string()
+        let repo_id = self.client.get_repo_info(repo)?.id;
+        let (public_key, key_id) = match self.client.environment_public_key(repo_id, environment) {
+            Ok(key) => key,
+            Err(e) if GitHubError::is_not_found(&e) => {
+                return Err(e.context(format!(
+                    "Environment '{}' does not exist in {}; create it under the repo's Settings > Environments, \
+                     or set github.secrets_environment and run deploy to have it created",
+                    environment, repo
+                )));
+            }
+            Err(e) => return Err(e),
+        };
+        let existing = self.client.list_environment_secrets(repo_id, environment).unwrap_or_default();
+        
+        let results = self.write_all(
+            secrets,
+            &public_key,
+            &existing,
+            |name, value| self.client.put_environment_secret(repo_id, environment, name, value, &key_id),
+            || self.client.list_environment_secret_times(repo_id, environment),
         )?;
         
-        info!("Secret {} set successfully", secret_name);
+        info!("Set {} secrets in environment {} of {} in {:.1?}", secrets.len(), environment, repo, started.elapsed());
+        Ok(results)
+    }
+    
+    /// Creates `environment` in `repo` unless it exists; true when created.
+    pub fn create_environment(&self, repo: &str, environment: &str) -> Result<bool> {
+        let created = self.client
+            .create_environment(repo, environment)
+            .with_context(|| format!("Failed to create environment {} in {}", environment, repo))?;
+        if created {
+            info!("Environment {} created in {}", environment, repo);
+        }
+        Ok(created)
+    }
+    
+    /// Encrypts every value, PUTs them in order, then reads the list back
+    /// until each secret shows an `updated_at` no older than the first PUT.
+    /// A secret that never does fails the write, unless verification is
+    /// lenient. `existing` tells created secrets from updated ones.
+    fn write_all(
+        &self,
+        secrets: &[(&str, &str)],
+        public_key: &str,
+        existing: &[String],
+        put: impl Fn(&str, &str) -> Result<()>,
+        list_times: impl Fn() -> Result<Vec<(String, DateTime<Utc>)>>,
+    ) -> Result<Vec<(String, SecretWriteResult)>> {
+        let encrypted = secrets
+            .iter()
+            .map(|(name, value)| {
+                encrypt_for_github(public_key, value)
+                    .context("Failed to encrypt secret value")
+                    .with_context(|| format!("Failed to set {}", name))
+            })
+            .collect::<Result<Vec<_>>>()?;
         
-        // Verify secret was set
-        thread::sleep(Duration::from_secs(2));
+        let delays = &settings::get().delays;
+        let since = Utc::now() - chrono::Duration::seconds(CLOCK_SKEW_SECS);
+        for (i, ((name, _), value)) in secrets.iter().zip(&encrypted).enumerate() {
+            if i > 0 {
+                self.sleeper.sleep("between_secrets", Duration::from_millis(delays.between_secrets_ms));
+            }
+            put(name, value).with_context(|| format!("Failed to set {}", name))?;
+            debug!("Secret {} written", name);
+        }
         
-        match self.client.api_call(
-            &format!("repos/{}/actions/secrets/{}", repo, secret_name),
-            "GET"
-        ) {
-            Ok(_) => {
-                info!("Secret {} verified", secret_name);
+        self.sleeper.sleep("secret_verify", Duration::from_millis(delays.secret_verify_ms));
+        let mut stale: Vec<&str> = secrets.iter().map(|(name, _)| *name).collect();
+        let verified = retry_with_backoff(&settings::get().retry_profile("secret_verify"), "Secret verification", self.sleeper.as_ref(), || {
+            stale = not_updated_since(secrets, &list_times()?, since);
+            if stale.is_empty() {
                 Ok(())
+            } else {
+                bail!("GitHub does not show {} as updated", stale.join(", "))
             }
-            Err(e) => {
-                warn!("Secret verification failed: {}", e);
-                Ok(()) // Don't fail if verification fails
+        });
+        if let Err(e) = verified {
+            if !self.lenient_verification {
+                return Err(e.context("Secrets were written but could not be verified"));
             }
+            warn!("Secret verification failed: {:#}", e);
         }
+        
+        Ok(secrets
+            .iter()
+            .map(|(name, _)| {
+                let result = if stale.contains(name) {
+                    SecretWriteResult::Unverified
+                } else if existing.iter().any(|n| n == name) {
+                    SecretWriteResult::Updated
+                } else {
+                    SecretWriteResult::Created
+                };
+                (name.to_string(), result)
+            })
+            .collect())
     }
     
+    /// Writes the node IDs and wallets in `layout`, then deletes the Nexus
+    /// secrets it no longer uses: parts of an earlier split, indices past
+    /// the node count, and everything of the other layout.
     pub fn set_nexus_secrets(
         &self,
         repo: &str,
         node_ids: &[String],
         wallets: &[String],
+        layout: SecretLayout,
     ) -> Result<()> {
         if node_ids.len() != wallets.len() {
             bail!("Node IDs and wallets count mismatch: {} vs {}", node_ids.len(), wallets.len());
         }
         
-        info!("Setting Nexus secrets for {} nodes", node_ids.len());
-        
-        // Create newline-separated strings
-        let node_ids_str = node_ids.join("\n");
-        let wallets_str = wallets.join("\n");
-        
-        // Set NEXUS_NODE_IDS
-        self.set_secret(repo, "NEXUS_NODE_IDS", &node_ids_str)
-            .context("Failed to set NEXUS_NODE_IDS")?;
+        info!("Setting Nexus secrets for {} nodes ({:?} layout)", node_ids.len(), layout);
         
-        thread::sleep(Duration::from_secs(1));
+        let chunked = layout.secrets(node_ids, wallets)?;
+        let secrets: Vec<(&str, &str)> = chunked.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect();
         
-        // Set NEXUS_WALLETS
-        self.set_secret(repo, "NEXUS_WALLETS", &wallets_str)
-            .context("Failed to set NEXUS_WALLETS")?;
+        match &self.environment {
+            Some(environment) => {
+                self.create_environment(repo, environment)?;
+                self.set_environment_secrets(repo, environment, &secrets)?;
+                let repo_id = self.client.get_repo_info(repo)?.id;
+                self.remove_stale_parts(
+                    &secrets,
+                    self.client.list_environment_secrets(repo_id, environment)?,
+                    |name| self.client.delete_environment_secret(repo_id, environment, name),
+                )?;
+            }
+            None => {
+                self.sync_secrets(repo, &chunked.iter().cloned().collect())?;
+                self.remove_stale_parts(
+                    &secrets,
+                    self.client.list_secrets(repo)?,
+                    |name| self.client.delete_secret(repo, name),
+                )?;
+            }
+        }
         
         info!("All Nexus secrets set successfully");
         Ok(())
     }
     
-    pub fn delete_secret(&self, repo: &str, secret_name: &str) -> Result<()> {
-        debug!("Deleting secret {} from {}", secret_name, repo);
-        
-        self.client.api_call(
-            &format!("repos/{}/actions/secrets/{}", repo, secret_name),
-            "DELETE"
-        )?;
-        
-        info!("Secret {} deleted", secret_name);
+    /// Deletes Nexus secrets, parts or indices that `written` no longer
+    /// uses, so a stale `NAME_COUNT` or node cannot shadow what was just
+    /// written.
+    fn remove_stale_parts(
+        &self,
+        written: &[(&str, &str)],
+        present: Vec<String>,
+        delete: impl Fn(&str) -> Result<()>,
+    ) -> Result<()> {
+        for name in present {
+            let stale = is_nexus_secret(&name) && !written.iter().any(|(n, _)| *n == name);
+            if stale {
+                match delete(&name) {
+                    Ok(()) => info!("Deleted stale secret {}", name),
+                    Err(e) if GitHubError::is_not_found(&e) => {}
+                    Err(e) => return Err(e.context(format!("Failed to delete stale secret {}", name))),
+                }
+            }
+        }
         Ok(())
     }
     
-    pub fn list_secrets(&self, repo: &str) -> Result<Vec<String>> {
-        debug!("Listing secrets for {}", repo);
+    /// Deletes the Nexus secrets of either layout from the repo and from
+    /// the environment when one is set. Secrets already gone, and an
+    /// environment that does not exist, are not errors. Returns the names
+    /// deleted.
+    pub fn delete_nexus_secrets(&self, repo: &str) -> Result<Vec<String>> {
+        let mut deleted = Vec::new();
         
-        let response = self.client.api_call(
-            &format!("repos/{}/actions/secrets", repo),
-            "GET"
-        )?;
+        for name in self.client.list_secrets(repo)?.into_iter().filter(|n| is_nexus_secret(n)) {
+            match self.client.delete_secret(repo, &name) {
+                Ok(()) => deleted.push(name),
+                Err(e) if GitHubError::is_not_found(&e) => {}
+                Err(e) => return Err(e.context(format!("Failed to delete {}", name))),
+            }
+        }
         
-        let json: serde_json::Value = serde_json::from_str(&response)
-            .context("Failed to parse secrets list")?;
+        if let Some(environment) = &self.environment {
+            let repo_id = self.client.get_repo_info(repo)?.id;
+            let names = match self.client.list_environment_secrets(repo_id, environment) {
+                Ok(names) => names,
+                Err(e) if GitHubError::is_not_found(&e) => Vec::new(),
+                Err(e) => return Err(e),
+            };
+            for name in names.into_iter().filter(|n| is_nexus_secret(n)) {
+                match self.client.delete_environment_secret(repo_id, environment, &name) {
+                    Ok(()) => deleted.push(format!("{}/{}", environment, name)),
+                    Err(e) if GitHubError::is_not_found(&e) => {}
+                    Err(e) => return Err(e.context(format!("Failed to delete {} from environment {}", name, environment))),
+                }
+            }
+        }
         
-        let mut secret_names = Vec::new();
+        if let Some(hashes) = &self.hashes {
+            let mut hashes = hashes.lock().unwrap();
+            for name in hashes.managed(repo).into_iter().filter(|n| is_nexus_secret(n)) {
+                hashes.forget(repo, &name);
+            }
+            hashes.save()?;
+        }
         
-        if let Some(secrets) = json["secrets"].as_array() {
-            for secret in secrets {
-                if let Some(name) = secret["name"].as_str() {
-                    secret_names.push(name.to_string());
-                }
+        info!("Deleted {} Nexus secrets from {}", deleted.len(), repo);
+        Ok(deleted)
+    }
+    
+    pub fn delete_secret(&self, repo: &str, secret_name: &str) -> Result<()> {
+        debug!("Deleting secret {} from {}", secret_name, repo);
+        
+        match self.client.delete_secret(repo, secret_name) {
+            Ok(()) => info!("Secret {} deleted", secret_name),
+            Err(e) if GitHubError::is_not_found(&e) => info!("Secret {} was not set", secret_name),
+            Err(e) => return Err(e),
+        }
+        
+        if let Some(vault) = &self.vault {
+            let mut vault = vault.lock().unwrap();
+            if vault.forget(secret_name) {
+                vault.save()?;
             }
         }
         
-        Ok(secret_names)
+        Ok(())
+    }
+    
+    pub fn list_secrets(&self, repo: &str) -> Result<Vec<String>> {
+        debug!("Listing secrets for {}", repo);
+        
+        self.client.list_secrets(repo)
+    }
+}
+
+impl SecretStore for SecretsManager {
+    fn set_secret(&self, repo: &str, secret_name: &str, secret_value: &str) -> Result<SecretWriteResult> {
+        SecretsManager::set_secret(self, repo, secret_name, secret_value)
+    }
+    
+    fn delete_secret(&self, repo: &str, secret_name: &str) -> Result<()> {
+        SecretsManager::delete_secret(self, repo, secret_name)
+    }
+    
+    fn list_secrets(&self, repo: &str) -> Result<Vec<String>> {
+        SecretsManager::list_secrets(self, repo)
     }
 }
 
@@ -160,4 +659,96 @@ mod tests {
         let joined = nodes.join("\n");
         assert_eq!(joined, "node1\nnode2");
     }
+    
+    #[test]
+    fn test_not_updated_since() {
+        let since = Utc::now();
+        let listed = vec![
+            ("FRESH".to_string(), since),
+            ("STALE".to_string(), since - chrono::Duration::seconds(1)),
+        ];
+        let secrets = [("FRESH", "a"), ("STALE", "b"), ("MISSING", "c")];
+        assert_eq!(not_updated_since(&secrets, &listed, since), vec!["STALE", "MISSING"]);
+    }
+    
+    #[test]
+    fn test_missing_nexus_secrets_in_either_layout() {
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        
+        assert_eq!(missing_nexus_secrets(&names(&["NEXUS_NODE_IDS_COUNT", "NEXUS_WALLETS"])), Vec::<String>::new());
+        assert_eq!(missing_nexus_secrets(&names(&["OTHER"])), vec!["NEXUS_NODE_IDS", "NEXUS_WALLETS"]);
+        assert_eq!(
+            missing_nexus_secrets(&names(&["NEXUS_NODE_1", "NEXUS_NODE_2", "NEXUS_WALLET_1", "NEXUS_NODE_IDS"])),
+            vec!["NEXUS_WALLET_2"]
+        );
+        
+        assert!(is_nexus_secret("NEXUS_WALLET_12") && is_nexus_secret("NEXUS_NODE_IDS_2"));
+        assert!(!is_nexus_secret("NEXUS_NODE_X"));
+    }
+    
+    #[test]
+    fn test_indexed_layout_is_capped() {
+        let values: Vec<String> = (0..51).map(|i| i.to_string()).collect();
+        assert_eq!(SecretLayout::Indexed.secrets(&values[..50], &values[..50]).unwrap().len(), 100);
+        assert!(SecretLayout::Indexed.secrets(&values, &values).is_err());
+    }
+    
+    /// `lines` lines of `width` bytes each, joined with newlines.
+    fn lines(lines: usize, width: usize) -> String {
+        (0..lines).map(|i| format!("{:0>width$}", i, width = width)).collect::<Vec<_>>().join("\n")
+    }
+    
+    #[test]
+    fn test_chunk_secret_boundaries() {
+        // 1024 lines of 47 bytes, each with its newline, is exactly the limit
+        let exact = format!("{}\n", lines(1024, 47));
+        assert_eq!(exact.len(), SECRET_SIZE_LIMIT);
+        assert_eq!(chunk_secret("NEXUS_NODE_IDS", &exact).unwrap(), vec![("NEXUS_NODE_IDS".to_string(), exact.clone())]);
+        
+        // One byte more splits, and every part stays within the limit
+        let over = format!("{}x", exact);
+        let parts = chunk_secret("NEXUS_NODE_IDS", &over).unwrap();
+        assert_eq!(
+            parts.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(),
+            vec!["NEXUS_NODE_IDS_1", "NEXUS_NODE_IDS_2", "NEXUS_NODE_IDS_COUNT"]
+        );
+        assert_eq!(parts[2].1, "2");
+        assert!(parts.iter().all(|(_, value)| value.len() <= SECRET_SIZE_LIMIT));
+        assert_eq!(parts[1].1, "x");
+        
+        // Reassembly as the workflow does it
+        let joined = parts[..2].iter().map(|(_, value)| value.as_str()).collect::<Vec<_>>().join("\n");
+        assert_eq!(joined, over);
+    }
+    
+    #[test]
+    fn test_chunk_secret_splits_on_lines() {
+        let value = lines(3000, 40);
+        let parts = chunk_secret("NEXUS_WALLETS", &value).unwrap();
+        let count: usize = parts.last().unwrap().1.parse().unwrap();
+        
+        assert_eq!(count, 3);
+        assert_eq!(parts.len(), count + 1);
+        for (i, (name, chunk)) in parts[..count].iter().enumerate() {
+            assert_eq!(*name, format!("NEXUS_WALLETS_{}", i + 1));
+            assert!(chunk.len() <= SECRET_SIZE_LIMIT);
+            assert!(chunk.split('\n').all(|line| line.len() == 40), "{} cut a line", name);
+        }
+        
+        assert!(chunk_secret("NEXUS_WALLETS", &"x".repeat(SECRET_SIZE_LIMIT + 1)).is_err());
+    }
+    
+    #[test]
+    fn test_secret_parts() {
+        assert!(is_part_of("NEXUS_WALLETS", "NEXUS_WALLETS"));
+        assert!(is_part_of("NEXUS_WALLETS_12", "NEXUS_WALLETS"));
+        assert!(is_part_of("NEXUS_WALLETS_COUNT", "NEXUS_WALLETS"));
+        assert!(!is_part_of("NEXUS_WALLETS_", "NEXUS_WALLETS"));
+        assert!(!is_part_of("NEXUS_WALLETS_BACKUP", "NEXUS_WALLETS"));
+        assert!(!is_part_of("NEXUS_NODE_IDS_1", "NEXUS_WALLETS"));
+        
+        let names = vec!["NEXUS_NODE_IDS_1".to_string(), "NEXUS_NODE_IDS_COUNT".to_string()];
+        assert!(has_secret(&names, "NEXUS_NODE_IDS"));
+        assert!(!has_secret(&names, "NEXUS_WALLETS"));
+    }
 }
@@ -0,0 +1,56 @@
+// src/github/variables.rs - Actions variables (plain-text configuration)
+
+use anyhow::{Result, Context};
+use log::{info, debug};
+use crate::github::api::{GitHubApi, GitHubError};
+
+/// Repository Actions variables: values the workflow reads as `vars.NAME`,
+/// visible in logs and settings, so nothing secret belongs here.
+pub struct VariablesManager {
+    client: Box<dyn GitHubApi>,
+}
+
+impl VariablesManager {
+    pub fn new(client: Box<dyn GitHubApi>) -> Self {
+        Self { client }
+    }
+    
+    /// Creates the variable, or updates it when GitHub answers that it
+    /// exists. Returns true when it was created.
+    pub fn set_variable(&self, repo: &str, name: &str, value: &str) -> Result<bool> {
+        match self.client.create_variable(repo, name, value) {
+            Ok(()) => {
+                info!("Variable {} created in {}", name, repo);
+                Ok(true)
+            }
+            Err(e) if matches!(GitHubError::of(&e), Some(GitHubError::Conflict(_))) => {
+                self.client.update_variable(repo, name, value)
+                    .with_context(|| format!("Failed to update variable {}", name))?;
+                info!("Variable {} updated in {}", name, repo);
+                Ok(false)
+            }
+            Err(e) => Err(e.context(format!("Failed to create variable {}", name))),
+        }
+    }
+    
+    pub fn get_variable(&self, repo: &str, name: &str) -> Result<Option<String>> {
+        self.client.get_variable(repo, name)
+    }
+    
+    pub fn list_variables(&self, repo: &str) -> Result<Vec<(String, String)>> {
+        debug!("Listing variables for {}", repo);
+        
+        self.client.list_variables(repo)
+    }
+    
+    /// A variable that was not set counts as deleted.
+    pub fn delete_variable(&self, repo: &str, name: &str) -> Result<()> {
+        match self.client.delete_variable(repo, name) {
+            Ok(()) => info!("Variable {} deleted", name),
+            Err(e) if GitHubError::is_not_found(&e) => info!("Variable {} was not set", name),
+            Err(e) => return Err(e),
+        }
+        
+        Ok(())
+    }
+}
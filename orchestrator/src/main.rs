@@ -1,4 +1,6 @@
 // src/main.rs - Complete entry point
+mod app;
+mod cli;
 mod core;
 mod github;
 mod nexus;
@@ -8,83 +10,792 @@ mod utils;
 mod ui;
 
 use anyhow::Result;
+use clap::Parser;
+use clap::error::ErrorKind;
 use log::{info, error};
-use std::env;
 use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+use cli::{AlertAction, Cli, Command, ConfigAction, ForkAction, LogsAction, SecretsAction, TokenAction, VariablesAction, VaultAction};
+use monitor::health::BillingOutput;
+use utils::exit_code;
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            e.print().ok();
+            return match e.kind() {
+                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => ExitCode::SUCCESS,
+                _ => ExitCode::from(exit_code::CONFIG_ERROR),
+            };
+        }
+    };
+    
+    match start(cli) {
+        Ok(code) => ExitCode::from(code),
+        Err(e) if utils::cancel::is_cancelled(&e) => {
+            eprintln!("Cancelled: every completed step is saved, the rest was skipped");
+            ExitCode::from(exit_code::INTERRUPTED)
+        }
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(exit_code::for_error(&e))
+        }
+    }
+}
+
+fn start(cli: Cli) -> Result<u8> {
     // Initialize logger
     let log_dir = PathBuf::from("logs");
     std::fs::create_dir_all(&log_dir).ok();
     
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .format_timestamp_secs()
-        .init();
+    let mut logger = env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+    if let Some(level) = &cli.log_level {
+        logger.parse_filters(level);
+    }
+    // Tokens and proxy credentials never reach the terminal or log files
+    logger.format(|buf, record| {
+        use std::io::Write;
+        writeln!(
+            buf,
+            "[{} {:<5} {}] {}",
+            buf.timestamp_seconds(),
+            record.level(),
+            record.target(),
+            utils::redact::scrub(&utils::logger::with_scope(&record.args().to_string()))
+        )
+    });
+    logger.init();
+    utils::platform::init_console();
+    monitor::metrics::init(&log_dir);
     
-    // Initialize libsodium
-    if unsafe { utils::crypto::init_crypto().is_err() } {
+    if cli.version {
+        print_version();
+        return Ok(exit_code::SUCCESS);
+    }
+    
+    // Initialize libsodium (doctor reports a failure as one of its checks)
+    let diagnosing = matches!(cli.command, Some(Command::Doctor));
+    if unsafe { utils::crypto::init_crypto().is_err() } && !diagnosing {
         error!("Failed to initialize libsodium");
         return Err(anyhow::anyhow!("Crypto initialization failed"));
     }
     
     info!("Nexus GitHub Orchestrator v2.0 starting...");
     
-    // Parse command line arguments
-    let args: Vec<String> = env::args().collect();
+    let config_dir = cli.resolved_config_dir()?;
+    info!("Using config directory: {}", config_dir.display());
+    
+    let result = run(cli, config_dir);
+    monitor::metrics::flush();
+    result
+}
+
+fn run(cli: Cli, config_dir: PathBuf) -> Result<u8> {
+    // `config` and `doctor` report settings errors themselves instead of failing on them
+    let reports_settings_errors = matches!(cli.command, Some(Command::Config { .. } | Command::Doctor));
+    let settings = match core::Settings::load(&config_dir) {
+        Ok(settings) => core::settings::init(settings),
+        Err(_) if reports_settings_errors => core::settings::get(),
+        Err(e) => return Err(e),
+    };
+    
+    if cli.no_color || !settings.ui.color {
+        colored::control::set_override(false);
+    }
+    
+    let paths = core::Paths::new(&config_dir);
+    for moved in paths.migrate_legacy_cache()? {
+        info!("Migrated legacy cache file to {}", moved.display());
+    }
+    
+    // Built once; every command below shares its state, accounts and caches
+    let ctx = Arc::new(app::AppContext::new(&config_dir)?);
+    
+    // Commands that change the fork chain run one instance at a time
+    let _lock = changes_chain(cli.command.as_ref()).then(|| ctx.state().lock()).transpose()?;
+    
+    let command = match cli.command {
+        Some(command) => command,
+        // Start interactive UI
+        None => {
+            ui::menu::run_menu(&ctx, cli.yes)?;
+            return Ok(exit_code::SUCCESS);
+        }
+    };
+    
+    match command {
+        Command::Status { json, reconcile, rebuild, parent, history } => {
+            if rebuild {
+                orchestration::sync::StateSync::new(ctx.clone()).rebuild(parent.as_deref())?;
+            }
+            core::state::show_status(&ctx, json, history)?;
+            if reconcile {
+                let drift = orchestration::forks::ForkCommand::new(ctx.clone()).reconcile()?;
+                orchestration::forks::print_drift(&drift);
+            }
+            Ok(exit_code::SUCCESS)
+        }
+        Command::Billing { json, summary_json, accounts, force } => {
+            let output = if json {
+                BillingOutput::Json
+            } else if summary_json {
+                BillingOutput::SummaryJson
+            } else {
+                BillingOutput::Table
+            };
+            let summary = monitor::health::show_billing(&ctx, output, &accounts, force)?;
+            Ok(summary.exit_code())
+        }
+        Command::Cleanup { statuses, older_than, keep_last, account, dry_run, mode, orphans, parent } => {
+            use github::fork::{self, CleanupFilter, CleanupMode, CleanupOptions};
+            
+            if orphans {
+                return run_orphan_cleanup(&ctx, parent.as_deref(), account.as_deref(), dry_run, cli.yes);
+            }
+            
+            let options = CleanupOptions {
+                filter: CleanupFilter {
+                    statuses: statuses.iter().map(|s| fork::parse_cleanup_status(s)).collect::<Result<_>>()?,
+                    older_than: older_than.as_deref().map(utils::duration::parse_duration).transpose()?,
+                    account,
+                    keep_last: keep_last.unwrap_or(0),
+                },
+                mode: CleanupMode::parse(&mode)?,
+                dry_run,
+            };
+            
+            let summary = orchestration::forks::ForkCommand::new(ctx.clone()).cleanup(&options)?;
+            summary.print();
+            Ok(summary.exit_code())
+        }
+        Command::Rotate => {
+            let rotator = orchestration::Rotator::new(ctx.clone());
+            let rotated = rotator.check_and_rotate()?;
+            if rotated {
+                println!("✅ Account rotated successfully");
+            } else {
+                println!("ℹ️  No rotation needed");
+            }
+            Ok(exit_code::SUCCESS)
+        }
+        Command::Sync => {
+            let report = orchestration::sync::StateSync::new(ctx.clone()).run()?;
+            report.print();
+            Ok(report.exit_code())
+        }
+        Command::Verify { workflows: true } => {
+            let report = orchestration::Deployer::new(ctx.clone()).check_workflow_drift()?;
+            report.print();
+            Ok(report.exit_code())
+        }
+        Command::Verify { workflows: false } => {
+            let report = orchestration::sync::StateSync::new(ctx.clone()).verify()?;
+            report.print();
+            Ok(report.exit_code())
+        }
+        Command::Deploy { workflow_only, secrets_only, force, render_only, workflows, only_drifted } => {
+            let deployer = orchestration::Deployer::new(ctx.clone())
+                .with_force_secrets(force)
+                .with_workflows(workflows);
+            if render_only {
+                print!("{}", deployer.render_workflow()?);
+                return Ok(exit_code::SUCCESS);
+            }
+            if only_drifted {
+                let report = deployer.deploy_drifted()?;
+                report.print();
+                return Ok(report.exit_code());
+            }
+            if !secrets_only {
+                deployer.deploy_main_workflow()?;
+                println!("✅ Workflow deployed");
+            }
+            if workflow_only {
+                return Ok(exit_code::SUCCESS);
+            }
+            let report = deployer.set_all_secrets()?;
+            report.print();
+            Ok(report.exit_code())
+        }
+        Command::ScrubSecrets => {
+            if !ui::input::confirm("⚠️  Delete NEXUS_NODE_IDS and NEXUS_WALLETS from every fork in the chain?", cli.yes) {
+                println!("Cancelled");
+                return Ok(exit_code::FAILURE);
+            }
+            let report = orchestration::Deployer::new(ctx.clone()).scrub_secrets()?;
+            report.print();
+            Ok(report.exit_code())
+        }
+        Command::Trigger { repo, account, workflow, inputs, watch } => {
+            let trigger = orchestration::Trigger::new(ctx.clone());
+            let outcome = trigger.run(&orchestration::trigger::TriggerOptions {
+                repo,
+                workflow,
+                account,
+                inputs,
+                watch,
+            })?;
+            Ok(outcome.exit_code())
+        }
+        Command::Artifacts { repo, run } => {
+            let (repo, artifacts) = orchestration::Trigger::new(ctx.clone())
+                .collect_artifacts(repo.as_deref(), run, std::path::Path::new("logs"))?;
+            orchestration::trigger::print_artifacts(&repo, artifacts.as_ref());
+            Ok(exit_code::SUCCESS)
+        }
+        Command::Jobs { repo, run } => {
+            let (repo, report) = orchestration::Trigger::new(ctx.clone()).run_report(repo.as_deref(), run)?;
+            match report {
+                Some(report) => {
+                    orchestration::trigger::print_run_report(&repo, &report);
+                    Ok(if report.is_healthy() { exit_code::SUCCESS } else { exit_code::FAILURE })
+                }
+                None => {
+                    println!("ℹ️  No completed runs in {}", repo);
+                    Ok(exit_code::SUCCESS)
+                }
+            }
+        }
+        Command::Rerun { repo, run, max_attempts, all_jobs } => {
+            let (repo, report) = orchestration::Trigger::new(ctx.clone())
+                .rerun(repo.as_deref(), run, max_attempts, all_jobs)?;
+            orchestration::trigger::print_rerun(&repo, report.as_ref());
+            Ok(report.map_or(exit_code::SUCCESS, |report| report.exit_code()))
+        }
+        Command::Fork { action } => run_fork(&ctx, action, cli.yes),
+        Command::Logs { run: Some(repo), .. } => {
+            let (repo, logs) = orchestration::Trigger::new(ctx.clone())
+                .fetch_failure_logs(Some(&repo), std::path::Path::new("logs"))?;
+            orchestration::trigger::print_run_logs(&repo, logs.as_ref());
+            Ok(exit_code::SUCCESS)
+        }
+        Command::Logs { action: Some(action), .. } => run_logs(std::path::Path::new("logs"), action),
+        Command::Logs { action: None, run: None } => {
+            anyhow::bail!(utils::exit_code::ConfigError::new("Pass a logs subcommand or --run <owner/name>"))
+        }
+        Command::Metrics { since, prometheus } => run_metrics(std::path::Path::new("logs"), &since, prometheus),
+        Command::Secrets { action } => run_secrets(&ctx, action),
+        Command::Variables { action } => run_variables(&ctx, action),
+        Command::Token { action } => run_token(&ctx, action),
+        Command::Vault { action } => run_vault(&ctx, action),
+        Command::Validate { tokens, proxies, nodes, online, stale_only, json, output } => {
+            let options = orchestration::validate::ValidationOptions { tokens, proxies, nodes, online, stale_only };
+            if orchestration::validate::run_validate_command(&config_dir, options, json, output.as_deref())? {
+                Ok(exit_code::SUCCESS)
+            } else {
+                Ok(exit_code::CONFIG_ERROR)
+            }
+        }
+        Command::Doctor => Ok(orchestration::doctor::run_doctor(&config_dir)),
+        Command::Config { action: ConfigAction::Show { json } } => {
+            core::settings::show_settings(&config_dir, json)?;
+            Ok(exit_code::SUCCESS)
+        }
+        Command::Config { action: ConfigAction::Validate } => {
+            if core::settings::validate_settings(&config_dir)? {
+                Ok(exit_code::SUCCESS)
+            } else {
+                Ok(exit_code::CONFIG_ERROR)
+            }
+        }
+        Command::Watch { interval } => {
+            let interval = interval.unwrap_or(core::settings::get().monitor.watch_interval_minutes);
+            let rotator = orchestration::Rotator::new(ctx.clone());
+            rotator.watch(Duration::from_secs(interval.max(1) * 60))?;
+            Ok(exit_code::INTERRUPTED)
+        }
+        Command::Report => {
+            core::state::show_status(&ctx, false, false)?;
+            let summary = monitor::health::show_billing_all(&ctx, BillingOutput::Table)?;
+            Ok(summary.exit_code())
+        }
+        Command::Alert { action: AlertAction::Test { message } } => {
+            let alert_mgr = ctx.alerts()?;
+            if !alert_mgr.is_enabled() {
+                println!("ℹ️  Alerts are disabled in {}", ctx.alerts_file().display());
+                return Ok(exit_code::SUCCESS);
+            }
+            alert_mgr.send_alert(&message)?;
+            println!("✅ Test alert sent");
+            Ok(exit_code::SUCCESS)
+        }
+        Command::Dashboard { refresh } => {
+            ui::dashboard::run_dashboard(
+                &ctx,
+                refresh.unwrap_or(core::settings::get().monitor.dashboard_refresh_secs),
+            )?;
+            Ok(exit_code::SUCCESS)
+        }
+        Command::SelfUpdate { check, version } => {
+            use orchestration::self_update::{GitHubReleases, SelfUpdater};
+            
+            let updater = SelfUpdater::new(Box::new(GitHubReleases::from_context(&ctx)?))?;
+            if check {
+                let outcome = updater.check(version.as_deref())?;
+                outcome.print();
+                return Ok(outcome.check_exit_code());
+            }
+            
+            let outcome = updater.update(version.as_deref())?;
+            outcome.print();
+            Ok(exit_code::SUCCESS)
+        }
+        Command::Version => {
+            print_version();
+            Ok(exit_code::SUCCESS)
+        }
+    }
+}
+
+/// The interactive menu, the commands that create, rotate, sync or delete
+/// forks and the token edits, which renumber the chain; these take the
+/// state lock.
+fn changes_chain(command: Option<&Command>) -> bool {
+    match command {
+        None => true,
+        Some(Command::Status { rebuild, .. }) => *rebuild,
+        Some(Command::Cleanup { dry_run, .. }) => !dry_run,
+        Some(Command::Fork { action }) => !matches!(action, ForkAction::List { .. }),
+        Some(command) => matches!(command, Command::Rotate | Command::Sync | Command::Deploy { .. } | Command::Watch { .. } | Command::Token { .. }),
+    }
+}
+
+/// `cleanup --orphans`: lists the orphan forks, then deletes them once confirmed.
+fn run_orphan_cleanup(
+    ctx: &Arc<app::AppContext>,
+    parent: Option<&str>,
+    account: Option<&str>,
+    dry_run: bool,
+    assume_yes: bool,
+) -> Result<u8> {
+    use orchestration::forks::{self, ForkCommand};
+    
+    let command = ForkCommand::new(ctx.clone());
+    let (parent, orphans) = command.find_orphans(parent, account)?;
+    forks::print_orphans(&parent, &orphans);
+    
+    if orphans.is_empty() || dry_run {
+        return Ok(exit_code::SUCCESS);
+    }
+    if !ui::input::confirm(&format!("⚠️  Permanently delete these {} repos?", orphans.len()), assume_yes) {
+        println!("Cancelled");
+        return Ok(exit_code::FAILURE);
+    }
+    
+    let results = command.delete_orphans(&parent, &orphans)?;
+    for result in &results {
+        result.print();
+    }
+    Ok(forks::results_exit_code(&results))
+}
+
+fn run_fork(ctx: &Arc<app::AppContext>, action: ForkAction, assume_yes: bool) -> Result<u8> {
+    use orchestration::forks::{self, ForkCommand};
+    
+    let command = ForkCommand::new(ctx.clone());
+    
+    let results = match action {
+        ForkAction::List { status, json } => {
+            let statuses = status.as_deref().map(forks::parse_status_filter).transpose()?.unwrap_or_default();
+            let nodes = command.list(&statuses)?;
+            
+            if json {
+                println!("{}", serde_json::to_string_pretty(&nodes)?);
+            } else if nodes.is_empty() {
+                println!("ℹ️  No forks match");
+            } else {
+                for node in &nodes {
+                    println!(
+                        "{:<40} {:<21} {:<10} {}",
+                        node.repo,
+                        node.owner_label(),
+                        format!("{:?}", node.status),
+                        node.created_at.format("%Y-%m-%d %H:%M")
+                    );
+                }
+            }
+            return Ok(exit_code::SUCCESS);
+        }
+        ForkAction::Create { account, parent } => {
+            vec![command.create(account.as_deref(), parent.as_deref())?]
+        }
+        ForkAction::Delete { repo, keep_state, account } => {
+            if !ui::input::confirm_typed(&format!("⚠️  This permanently deletes {}.", repo), &repo, assume_yes) {
+                println!("Cancelled");
+                return Ok(exit_code::FAILURE);
+            }
+            vec![command.delete(&repo, keep_state, account.as_deref())?]
+        }
+        ForkAction::Sync { repo, all_active, account } => {
+            command.sync(repo.as_deref(), all_active, account.as_deref())?
+        }
+    };
+    
+    for result in &results {
+        result.print();
+    }
     
-    if args.len() > 1 {
-        match args[1].as_str() {
-            "status" => {
-                return core::state::show_status();
+    Ok(forks::results_exit_code(&results))
+}
+
+fn run_logs(log_dir: &std::path::Path, action: LogsAction) -> Result<u8> {
+    use monitor::logs::{self, LogViewer};
+    
+    let viewer = LogViewer::new(log_dir);
+    
+    match action {
+        LogsAction::List => {
+            let files = viewer.list_files()?;
+            if files.is_empty() {
+                println!("ℹ️  No log files in {} yet", log_dir.display());
             }
-            "billing" => {
-                return monitor::health::show_billing_all();
+            for file in files {
+                let age = file.modified
+                    .map(|m| ui::display::format_age(chrono::Local::now() - m))
+                    .unwrap_or_else(|| "?".to_string());
+                println!(
+                    "{:<40} {:>10} {:>10}",
+                    file.path.strip_prefix(log_dir).unwrap_or(&file.path).display(),
+                    format!("{:.1} KB", file.size as f64 / 1024.0),
+                    age
+                );
             }
-            "cleanup" => {
-                return github::fork::cleanup_exhausted_forks();
+        }
+        LogsAction::Tail { file, lines, follow } => {
+            let path = viewer.resolve_file(&file)?;
+            let mut handle = std::fs::File::open(&path)?;
+            for line in logs::tail_lines(&mut handle, lines)? {
+                println!("{}", logs::colorize_line(&line));
             }
-            "rotate" => {
-                let rotator = orchestration::Rotator::new(PathBuf::from("config"));
-                let rotated = rotator.check_and_rotate()?;
-                if rotated {
-                    println!("✅ Account rotated successfully");
-                } else {
-                    println!("ℹ️  No rotation needed");
+            if follow {
+                logs::follow(&path)?;
+            }
+        }
+        LogsAction::Show { run_id } => {
+            let files = viewer.run_files(run_id)?;
+            if files.is_empty() {
+                anyhow::bail!(utils::exit_code::ConfigError::new(format!(
+                    "No log files for run {} in {}",
+                    run_id,
+                    log_dir.display()
+                )));
+            }
+            for path in files {
+                println!("═══ {} ═══", path.display());
+                for line in std::fs::read_to_string(&path)?.lines() {
+                    println!("{}", logs::colorize_line(line));
                 }
-                return Ok(());
             }
-            "version" | "-v" | "--version" => {
-                println!("Nexus GitHub Orchestrator v2.0.0");
-                return Ok(());
+        }
+        LogsAction::Grep { pattern, since } => {
+            let since = since.as_deref().map(utils::duration::parse_duration).transpose()?;
+            for (path, line) in viewer.grep(&pattern, since)? {
+                println!(
+                    "{}: {}",
+                    path.strip_prefix(log_dir).unwrap_or(&path).display(),
+                    logs::colorize_line(&line)
+                );
             }
-            "help" | "-h" | "--help" => {
-                print_help();
-                return Ok(());
+        }
+    }
+    
+    Ok(exit_code::SUCCESS)
+}
+
+fn run_metrics(log_dir: &std::path::Path, since: &str, prometheus: bool) -> Result<u8> {
+    use monitor::metrics;
+    
+    // A window reaching back past the earliest representable time covers everything
+    let since = chrono::Utc::now()
+        .checked_sub_signed(utils::duration::parse_duration(since)?)
+        .unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC);
+    let samples = metrics::read_samples(&log_dir.join(metrics::METRICS_FILE), since)?;
+    
+    if prometheus {
+        print!("{}", metrics::ApiMetrics::from_samples(&samples).to_prometheus());
+        return Ok(exit_code::SUCCESS);
+    }
+    
+    if samples.is_empty() {
+        println!("ℹ️  No GitHub API calls recorded since {}", since.format("%Y-%m-%d %H:%M UTC"));
+        return Ok(exit_code::SUCCESS);
+    }
+    
+    println!("{:<14} {:>7} {:>7} {:>9} {:>9}", "ACCOUNT", "CALLS", "FAILED", "P50", "P95");
+    for latency in metrics::latency_by_account(&samples) {
+        println!(
+            "{:<14} {:>7} {:>7} {:>8}ms {:>8}ms",
+            latency.account, latency.calls, latency.failed, latency.p50_ms, latency.p95_ms
+        );
+    }
+    Ok(exit_code::SUCCESS)
+}
+
+fn run_secrets(ctx: &Arc<app::AppContext>, action: SecretsAction) -> Result<u8> {
+    use orchestration::secrets;
+    
+    let command = secrets::SecretsCommand::new(ctx.clone());
+    
+    match action {
+        SecretsAction::Set { repo, name, value, account, environment } => {
+            secrets::validate_secret_name(&name)?;
+            let secret_value = value.source().read()?;
+            let store = command.store_for(&repo, account.as_deref())?;
+            let result = match environment {
+                Some(environment) => store.set_environment_secret(&repo, &environment, &name, &secret_value)?,
+                None => secrets::set_secret(&store, &repo, &name, &secret_value)?,
+            };
+            println!("{} {} {}: {}", if result == github::SecretWriteResult::Unverified { "⚠️ " } else { "✅" }, repo, name, result);
+            Ok(secrets::write_exit_code(result))
+        }
+        SecretsAction::List { repo, account } => {
+            let store = command.store_for(&repo, account.as_deref())?;
+            for name in github::SecretStore::list_secrets(&store, &repo)? {
+                println!("{}", name);
             }
-            _ => {
-                eprintln!("Unknown command: {}", args[1]);
-                print_help();
-                return Ok(());
+            Ok(exit_code::SUCCESS)
+        }
+        SecretsAction::Delete { repo, name, account } => {
+            secrets::validate_secret_name(&name)?;
+            let store = command.store_for(&repo, account.as_deref())?;
+            github::SecretStore::delete_secret(&store, &repo, &name)?;
+            println!("🗑️  {} {}: deleted", repo, name);
+            Ok(exit_code::SUCCESS)
+        }
+        SecretsAction::Apply { manifest, all_active, account } => {
+            let manifest = manifest.unwrap_or_else(|| ctx.paths().secrets_manifest());
+            let pool = utils::WorkerPool::from_settings()
+                .with_progress(std::sync::Arc::new(ui::display::ProgressLine::new("Applying")));
+            let report = command.apply(&manifest, all_active, account.as_deref(), &pool)?;
+            report.print();
+            Ok(report.exit_code())
+        }
+        SecretsAction::ImportEnv { repo } => {
+            let repo = match repo {
+                Some(repo) => repo,
+                None => {
+                    let state_mgr = ctx.state();
+                    let state = state_mgr.load_state()?;
+                    state_mgr.get_active_fork(&state)
+                        .map(|n| n.repo.clone())
+                        .ok_or_else(|| exit_code::ConfigError::new("No active fork in state; pass the repo"))?
+                }
+            };
+            let deployment = orchestration::Deployer::new(ctx.clone()).deploy_env_file(&repo)?;
+            deployment.print(&repo);
+            Ok(deployment.exit_code())
+        }
+    }
+}
+
+fn run_variables(ctx: &Arc<app::AppContext>, action: VariablesAction) -> Result<u8> {
+    let command = orchestration::secrets::SecretsCommand::new(ctx.clone());
+    
+    match action {
+        VariablesAction::Set { repo, name, value, account } => {
+            let created = command.variables_for(&repo, account.as_deref())?.set_variable(&repo, &name, &value)?;
+            println!("✅ {} {}: {}", repo, name, if created { "created" } else { "updated" });
+            Ok(exit_code::SUCCESS)
+        }
+        VariablesAction::Get { repo, name, account } => {
+            match command.variables_for(&repo, account.as_deref())?.get_variable(&repo, &name)? {
+                Some(value) => {
+                    println!("{}", value);
+                    Ok(exit_code::SUCCESS)
+                }
+                None => {
+                    eprintln!("{} has no variable {}", repo, name);
+                    Ok(exit_code::FAILURE)
+                }
             }
         }
+        VariablesAction::List { repo, account } => {
+            for (name, value) in command.variables_for(&repo, account.as_deref())?.list_variables(&repo)? {
+                println!("{}={}", name, value);
+            }
+            Ok(exit_code::SUCCESS)
+        }
+        VariablesAction::Delete { repo, name, account } => {
+            command.variables_for(&repo, account.as_deref())?.delete_variable(&repo, &name)?;
+            println!("🗑️  {} {}: deleted", repo, name);
+            Ok(exit_code::SUCCESS)
+        }
     }
+}
+
+fn run_token(ctx: &Arc<app::AppContext>, action: TokenAction) -> Result<u8> {
+    let command = orchestration::tokens::TokenCommand::new(ctx.clone());
     
-    // Start interactive UI
-    ui::menu::run_menu()
+    match action {
+        TokenAction::Add { token, owner } => {
+            let line = match owner {
+                Some(owner) => format!("{} {}", token, owner),
+                None => token,
+            };
+            println!("✅ {}", command.add(&line)?);
+        }
+        TokenAction::Remove { account, force } => command.remove(&account, force)?.print(),
+        TokenAction::Disable { account } => {
+            let account = command.set_enabled(&account, false)?;
+            println!("⏸️  @{} disabled; rotation, billing checks and deployments skip it", account.username);
+        }
+        TokenAction::Enable { account } => {
+            let account = command.set_enabled(&account, true)?;
+            println!("✅ @{} enabled", account.username);
+        }
+        TokenAction::Priority { account, priority } => {
+            let account = command.set_priority(&account, priority)?;
+            println!("✅ @{} now has rotation priority {}", account.username, account.priority);
+        }
+        TokenAction::Label { account, label, notes } => {
+            let account = command.set_label(&account, &label, notes.as_deref())?;
+            println!("✅ {}", account.display_name());
+        }
+    }
+    Ok(exit_code::SUCCESS)
 }
 
-fn print_help() {
-    println!("Nexus GitHub Orchestrator v2.0.0");
-    println!();
-    println!("USAGE:");
-    println!("    nexus-orchestrator [COMMAND]");
-    println!();
-    println!("COMMANDS:");
-    println!("    (none)      Start interactive menu");
-    println!("    status      Show orchestrator status");
-    println!("    billing     Show billing for all accounts");
-    println!("    cleanup     Clean up exhausted forks");
-    println!("    rotate      Force account rotation");
-    println!("    version     Show version");
-    println!("    help        Show this help");
+fn run_vault(ctx: &Arc<app::AppContext>, action: VaultAction) -> Result<u8> {
+    let vault = ctx.vault()?;
+    let mut vault = vault.lock().unwrap();
+    
+    match action {
+        VaultAction::List => {
+            if vault.is_empty() {
+                println!("No secrets vaulted");
+            }
+            for (name, entry) in vault.entries() {
+                println!("{:<30} {:<40} {}", name, entry.repo, entry.updated_at.format("%Y-%m-%d %H:%M UTC"));
+            }
+            Ok(exit_code::SUCCESS)
+        }
+        VaultAction::Forget { name } => {
+            if !vault.forget(&name) {
+                eprintln!("{} is not vaulted", name);
+                return Ok(exit_code::FAILURE);
+            }
+            vault.save()?;
+            println!("🗑️  {}: forgotten", name);
+            Ok(exit_code::SUCCESS)
+        }
+    }
+}
+
+fn print_version() {
+    println!("Nexus GitHub Orchestrator v{}", env!("CARGO_PKG_VERSION"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    fn run_in(config_dir: &std::path::Path, args: &[&str]) -> u8 {
+        let cli = Cli::try_parse_from(std::iter::once("nexus-orchestrator").chain(args.iter().copied())).unwrap();
+        match run(cli, config_dir.to_path_buf()) {
+            Ok(code) => code,
+            Err(e) => exit_code::for_error(&e),
+        }
+    }
+    
+    #[test]
+    fn test_success_paths_exit_zero() {
+        let temp_dir = tempdir().unwrap();
+        
+        assert_eq!(run_in(temp_dir.path(), &["status", "--json"]), exit_code::SUCCESS);
+        assert_eq!(run_in(temp_dir.path(), &["cleanup"]), exit_code::SUCCESS);
+        assert_eq!(run_in(temp_dir.path(), &["version"]), exit_code::SUCCESS);
+    }
+    
+    #[test]
+    fn test_held_state_lock_exits_locked() {
+        let temp_dir = tempdir().unwrap();
+        let state_mgr = core::StateManager::new(&core::Paths::new(temp_dir.path())).unwrap();
+        let lock = state_mgr.lock().unwrap();
+        
+        assert_eq!(run_in(temp_dir.path(), &["rotate"]), exit_code::LOCKED);
+        assert_eq!(run_in(temp_dir.path(), &["cleanup"]), exit_code::LOCKED);
+        assert_eq!(run_in(temp_dir.path(), &["token", "disable", "0"]), exit_code::LOCKED);
+        // Read-only commands do not wait for the lock
+        assert_eq!(run_in(temp_dir.path(), &["status", "--json"]), exit_code::SUCCESS);
+        assert_eq!(run_in(temp_dir.path(), &["cleanup", "--dry-run"]), exit_code::SUCCESS);
+        
+        drop(lock);
+        assert_eq!(run_in(temp_dir.path(), &["cleanup"]), exit_code::SUCCESS);
+    }
+    
+    #[test]
+    fn test_missing_config_is_config_error() {
+        let temp_dir = tempdir().unwrap();
+        
+        assert_eq!(run_in(temp_dir.path(), &["billing", "--json"]), exit_code::CONFIG_ERROR);
+        assert_eq!(run_in(temp_dir.path(), &["validate", "--json"]), exit_code::CONFIG_ERROR);
+        assert_eq!(run_in(temp_dir.path(), &["trigger"]), exit_code::CONFIG_ERROR);
+    }
+    
+    #[test]
+    fn test_config_commands() {
+        let temp_dir = tempdir().unwrap();
+        
+        assert_eq!(run_in(temp_dir.path(), &["config", "show"]), exit_code::SUCCESS);
+        assert_eq!(run_in(temp_dir.path(), &["config", "validate"]), exit_code::SUCCESS);
+        
+        std::fs::write(temp_dir.path().join("orchestrator.toml"), "[github]\nbrnach = \"main\"\n").unwrap();
+        assert_eq!(run_in(temp_dir.path(), &["config", "validate"]), exit_code::CONFIG_ERROR);
+        assert_eq!(run_in(temp_dir.path(), &["config", "show"]), exit_code::CONFIG_ERROR);
+        assert_eq!(run_in(temp_dir.path(), &["status"]), exit_code::CONFIG_ERROR);
+    }
+    
+    #[test]
+    fn test_cache_files_land_in_one_directory() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        std::fs::write(dir.join("tokens.txt"), "ghp_aaaa\nghp_bbbb\n").unwrap();
+        std::fs::write(dir.join("proxies.txt"), "http://u:p@10.0.0.1:8080\nhttp://u:p@10.0.0.2:8080\n").unwrap();
+        // Left in the config dir root by an older build
+        std::fs::write(dir.join("ownermap.json"), "{}").unwrap();
+        
+        for args in [
+            &["status", "--json"][..],
+            &["cleanup"],
+            &["validate", "--json"],
+            &["billing", "--account", "mallory"],
+            &["trigger"],
+            &["fork", "list"],
+        ] {
+            run_in(dir, args);
+        }
+        
+        let paths = core::Paths::new(dir);
+        let mut found = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in std::fs::read_dir(&current).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if core::paths::CACHE_FILES.iter().any(|name| path.ends_with(name)) {
+                    found.push(path);
+                }
+            }
+        }
+        
+        assert!(found.contains(&paths.proxymap()), "{:?}", found);
+        assert!(found.contains(&paths.ownermap()), "{:?}", found);
+        assert!(found.iter().all(|path| path.parent() == Some(paths.cache_dir())), "{:?}", found);
+    }
+    
+    #[test]
+    fn test_unknown_account_is_config_error() {
+        let temp_dir = tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("tokens.txt"), "ghp_aaaa\n").unwrap();
+        
+        assert_eq!(
+            run_in(temp_dir.path(), &["billing", "--account", "mallory"]),
+            exit_code::CONFIG_ERROR
+        );
+    }
 }
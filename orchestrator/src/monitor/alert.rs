@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 use log::{info, warn};
+use crate::utils::{platform, redact};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AlertConfig {
@@ -38,9 +39,17 @@ impl AlertManager {
             AlertConfig::default()
         };
         
+        for secret in [&config.telegram_bot_token, &config.discord_webhook].into_iter().flatten() {
+            redact::register(secret);
+        }
+        
         Ok(Self { config })
     }
     
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
     pub fn send_alert(&self, message: &str) -> Result<()> {
         if !self.config.enabled {
             return Ok(());
@@ -58,8 +67,6 @@ impl AlertManager {
     }
     
     fn send_telegram(&self, bot_token: &str, chat_id: &str, message: &str) -> Result<()> {
-        use std::process::Command;
-        
         let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
         
         let payload = serde_json::json!({
@@ -68,7 +75,7 @@ impl AlertManager {
             "parse_mode": "Markdown"
         });
         
-        let output = Command::new("curl")
+        let output = platform::command("curl")
             .args(&[
                 "-X", "POST",
                 &url,
@@ -81,20 +88,18 @@ impl AlertManager {
         if output.status.success() {
             info!("Telegram alert sent");
         } else {
-            warn!("Failed to send Telegram alert: {}", String::from_utf8_lossy(&output.stderr));
+            warn!("Failed to send Telegram alert: {}", redact::output_text(&output.stderr));
         }
         
         Ok(())
     }
     
     fn send_discord(&self, webhook: &str, message: &str) -> Result<()> {
-        use std::process::Command;
-        
         let payload = serde_json::json!({
             "content": message
         });
         
-        let output = Command::new("curl")
+        let output = platform::command("curl")
             .args(&[
                 "-X", "POST",
                 webhook,
@@ -107,7 +112,7 @@ impl AlertManager {
         if output.status.success() {
             info!("Discord alert sent");
         } else {
-            warn!("Failed to send Discord alert: {}", String::from_utf8_lossy(&output.stderr));
+            warn!("Failed to send Discord alert: {}", redact::output_text(&output.stderr));
         }
         
         Ok(())
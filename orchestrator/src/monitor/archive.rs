@@ -0,0 +1,91 @@
+// src/monitor/archive.rs - Record of deleted forks (fork_archive.jsonl)
+
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use crate::core::paths::Paths;
+use crate::core::state::{ForkChainNode, ForkStatus};
+
+/// What a chain node looked like right before its repo was deleted.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchivedFork {
+    pub deleted_at: DateTime<Utc>,
+    pub repo: String,
+    pub username: String,
+    pub pat_index: usize,
+    pub parent: Option<String>,
+    /// Status before deletion
+    pub status: ForkStatus,
+    pub billing_used: f32,
+    pub created_at: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+    /// None when GitHub could not be asked
+    pub run_count: Option<u64>,
+    pub last_run_id: Option<u64>,
+    pub last_run_conclusion: Option<String>,
+}
+
+impl ArchivedFork {
+    /// The node as recorded, with the last run the orchestrator saw.
+    pub fn from_node(node: &ForkChainNode) -> Self {
+        Self {
+            deleted_at: Utc::now(),
+            repo: node.repo.clone(),
+            username: node.username.clone(),
+            pat_index: node.pat_index,
+            parent: node.parent.clone(),
+            status: node.status.clone(),
+            billing_used: node.billing_used,
+            created_at: node.created_at,
+            last_updated: node.last_updated,
+            run_count: None,
+            last_run_id: node.last_run.as_ref().map(|run| run.run_id),
+            last_run_conclusion: node.last_run.as_ref().and_then(|run| run.conclusion.clone()),
+        }
+    }
+}
+
+pub struct ForkArchive {
+    archive_file: PathBuf,
+}
+
+impl ForkArchive {
+    pub fn new(paths: &Paths) -> Self {
+        Self {
+            archive_file: paths.fork_archive(),
+        }
+    }
+    
+    pub fn append(&self, fork: &ArchivedFork) -> Result<()> {
+        let line = serde_json::to_string(fork)
+            .context("Failed to serialize archived fork")?;
+        
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.archive_file)
+            .context("Failed to open fork_archive.jsonl")?;
+        
+        writeln!(file, "{}", line).context("Failed to write archived fork")?;
+        Ok(())
+    }
+    
+    /// Every archived fork, oldest first. Malformed lines are skipped.
+    pub fn read_all(&self) -> Result<Vec<ArchivedFork>> {
+        if !self.archive_file.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let content = fs::read_to_string(&self.archive_file)
+            .context("Failed to read fork_archive.jsonl")?;
+        
+        Ok(content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect())
+    }
+}
@@ -0,0 +1,104 @@
+// src/monitor/events.rs - Append-only event log (events.jsonl)
+
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use log::warn;
+use crate::core::paths::Paths;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub timestamp: DateTime<Utc>,
+    pub kind: String,
+    pub message: String,
+    pub repo: Option<String>,
+}
+
+impl Event {
+    pub fn new(kind: &str, message: &str, repo: Option<&str>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            kind: kind.to_string(),
+            message: message.to_string(),
+            repo: repo.map(|r| r.to_string()),
+        }
+    }
+}
+
+pub struct EventLog {
+    events_file: PathBuf,
+}
+
+impl EventLog {
+    pub fn new(paths: &Paths) -> Self {
+        Self {
+            events_file: paths.events_file(),
+        }
+    }
+    
+    pub fn append(&self, event: &Event) -> Result<()> {
+        let line = serde_json::to_string(event)
+            .context("Failed to serialize event")?;
+        
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.events_file)
+            .context("Failed to open events.jsonl")?;
+        
+        writeln!(file, "{}", line).context("Failed to write event")?;
+        Ok(())
+    }
+    
+    /// Convenience wrapper for callers that must not fail because of the event log.
+    pub fn record(&self, kind: &str, message: &str, repo: Option<&str>) {
+        if let Err(e) = self.append(&Event::new(kind, message, repo)) {
+            warn!("Failed to record {} event: {}", kind, e);
+        }
+    }
+    
+    /// Returns the last `count` events, oldest first. Malformed lines are skipped.
+    pub fn read_recent(&self, count: usize) -> Result<Vec<Event>> {
+        if !self.events_file.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let content = fs::read_to_string(&self.events_file)
+            .context("Failed to read events.jsonl")?;
+        
+        let events: Vec<Event> = content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect();
+        
+        let skip = events.len().saturating_sub(count);
+        Ok(events.into_iter().skip(skip).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_read_recent_returns_tail() {
+        let temp_dir = tempdir().unwrap();
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
+        let log = EventLog::new(&paths);
+        
+        for i in 0..8 {
+            log.append(&Event::new("test", &format!("event {}", i), None)).unwrap();
+        }
+        
+        let recent = log.read_recent(5).unwrap();
+        assert_eq!(recent.len(), 5);
+        assert_eq!(recent[0].message, "event 3");
+        assert_eq!(recent[4].message, "event 7");
+    }
+}
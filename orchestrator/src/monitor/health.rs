@@ -1,109 +1,996 @@
 // src/monitor/health.rs - Health monitoring for workflows
 
-use anyhow::Result;
-use std::path::PathBuf;
-use log::info;
+use anyhow::{Result, Context, bail};
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use log::{info, warn};
+use crate::app::AppContext;
 use crate::core::{
-    state::StateManager,
-    account::AccountManager,
-    billing::BillingMonitor,
+    state::{ForkStatus, StateManager},
+    account::{AccountInfo, AccountManager},
+    billing::{BillingInfo, BillingMonitor},
+    paths::Paths,
     proxy::ProxyManager,
+    settings,
 };
+use crate::github::workflow::KeepAlive;
+use crate::github::{api, workflow, ApiFactory, GitHubClient, WorkflowController};
+use crate::github::api::RateLimit;
+use crate::monitor::events::EventLog;
+use crate::monitor::{metrics, AlertManager};
+use crate::utils::delay::{self, Sleeper};
+use crate::utils::exit_code::{self, ConfigError};
+use crate::utils::platform;
+use crate::utils::pool::{JobOutcome, WorkerPool};
+
+/// Tokens this close to expiring are alerted on, not just flagged.
+pub const TOKEN_ALERT_DAYS: i64 = 7;
+
+/// Events kept in view when looking for the last expiry alert of a token.
+const TOKEN_EVENT_WINDOW: usize = 1000;
 
 pub struct HealthMonitor {
     state_manager: StateManager,
     billing_monitor: BillingMonitor,
+    billing_cache_file: PathBuf,
+    rate_limit_cache_file: PathBuf,
+    sleeper: Arc<dyn Sleeper>,
+    pool: WorkerPool,
 }
 
 impl HealthMonitor {
-    pub fn new(config_dir: &PathBuf) -> Result<Self> {
-        let state_manager = StateManager::new(config_dir)?;
-        let billing_monitor = BillingMonitor::default();
+    pub fn new(paths: &Paths) -> Result<Self> {
+        let state_manager = StateManager::new(paths)?;
+        let billing_monitor = BillingMonitor::from_config(&paths.thresholds_file())?;
+        let billing_cache_file = paths.billing_cache();
         
         Ok(Self {
             state_manager,
             billing_monitor,
+            billing_cache_file,
+            rate_limit_cache_file: paths.rate_limit_cache(),
+            sleeper: delay::thread_sleeper(),
+            pool: WorkerPool::from_settings(),
         })
     }
     
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+    
+    /// Pool the billing sweep runs on, e.g. with a progress bar attached.
+    pub fn with_pool(mut self, pool: WorkerPool) -> Self {
+        self.pool = pool;
+        self
+    }
+    
+    /// Resolves `--account` selectors (username or token index) to accounts.
+    /// An empty selector list selects every account.
+    pub fn select_accounts(
+        &self,
+        account_mgr: &AccountManager,
+        selectors: &[String],
+    ) -> Result<Vec<AccountInfo>> {
+        if selectors.is_empty() {
+            return Ok(account_mgr.get_all_accounts().to_vec());
+        }
+        
+        let mut selected: Vec<AccountInfo> = Vec::new();
+        
+        for selector in selectors {
+            let account = match selector.parse::<usize>() {
+                Ok(index) => account_mgr.get_account(index).with_context(|| ConfigError::new(format!(
+                    "Account index {} out of range (0-{})",
+                    index,
+                    account_mgr.total_accounts().saturating_sub(1)
+                )))?,
+                Err(_) => match account_mgr.get_account_by_username(selector) {
+                    Some(account) => account,
+                    None => {
+                        let known: Vec<&str> = account_mgr.get_all_accounts()
+                            .iter()
+                            .map(|a| a.username.as_str())
+                            .collect();
+                        bail!(ConfigError::new(format!(
+                            "Unknown account '{}'. Known accounts: {}",
+                            selector,
+                            known.join(", ")
+                        )));
+                    }
+                },
+            };
+            
+            if !selected.iter().any(|a| a.index == account.index) {
+                selected.push(account.clone());
+            }
+        }
+        
+        Ok(selected)
+    }
+    
+    /// Billing of `accounts`, from the cache while it is fresh unless
+    /// `force`. Disabled accounts are skipped, and left out of the result.
     pub fn check_all_accounts(
         &self,
-        accounts: &[crate::core::account::AccountInfo],
+        accounts: &[AccountInfo],
         proxy_manager: &ProxyManager,
-    ) -> Result<Vec<crate::core::billing::BillingInfo>> {
-        let mut billing_infos = Vec::new();
+        display: bool,
+        force: bool,
+    ) -> Result<Vec<BillingInfo>> {
+        let mut cache = self.load_billing_cache();
+        let ttl = Duration::minutes(settings::get().monitor.billing_cache_ttl_minutes);
+        
+        // Organization accounts are billed, and cached, under the org
+        let cached: Vec<Option<BillingInfo>> = accounts
+            .iter()
+            .map(|account| cache.get(account.billing_owner().0)
+                .filter(|info| !force && Utc::now() - info.checked_at < ttl)
+                .cloned())
+            .collect();
+        let to_fetch: Vec<&AccountInfo> = accounts
+            .iter()
+            .zip(&cached)
+            .filter(|(account, info)| account.enabled && info.is_none())
+            .map(|(account, _)| account)
+            .collect();
+        let fetched = !to_fetch.is_empty();
+        
+        // Space out live API calls
+        let pause = std::time::Duration::from_millis(settings::get().delays.between_billing_checks_ms);
+        let pool = self.pool.clone().with_pacing(self.sleeper.clone(), "between_billing_checks", pause);
+        let budget = pool.retry_budget();
         
-        for account in accounts {
+        let sweep = pool.run(&to_fetch, |account| account.token.clone(), |account| {
             let proxy = proxy_manager.get_proxy(&account.token)
                 .map(|p| p.to_curl_format());
+            let client = GitHubClient::new(account.token.clone(), proxy)
+                .with_timeout(api::DEFAULT_TIMEOUT)
+                .with_cache(api::DEFAULT_CACHE_TTL)
+                .with_sleeper(self.sleeper.clone())
+                .with_retry_budget(budget.clone());
             
-            match self.billing_monitor.check_billing(
-                &account.username,
-                &account.token,
-                proxy.as_deref(),
-            ) {
-                Ok(info) => {
-                    self.billing_monitor.display_billing(&info);
-                    billing_infos.push(info);
-                }
+            let info = self.billing_monitor.check_billing(account, &client)?;
+            
+            // Best effort: a failed quota check does not fail the billing check
+            let rate = match client.get_rate_limit() {
+                Ok(rate) => Some(rate),
                 Err(e) => {
-                    eprintln!("Failed to check billing for {}: {}", account.username, e);
+                    warn!("Failed to read rate limit for {}: {}", account.username, e);
+                    None
+                }
+            };
+            Ok((info, rate))
+        });
+        
+        let mut fetched_outcomes = sweep.outcomes.into_iter();
+        let mut billing_infos = Vec::new();
+        let mut rate_limits = load_rate_limits(&self.rate_limit_cache_file);
+        let mut rate_limits_changed = false;
+        
+        for (account, cached) in accounts.iter().zip(cached) {
+            if !account.enabled {
+                if display {
+                    println!("⏸️  @{}: disabled, skipped", account.username);
                 }
+                continue;
             }
             
-            std::thread::sleep(std::time::Duration::from_secs(2));
+            let key = metrics::mask_token(&account.token);
+            let info = match cached {
+                Some(info) => info,
+                None => match fetched_outcomes.next() {
+                    Some(JobOutcome::Done((info, rate))) => {
+                        if !info.is_estimated {
+                            cache.insert(info.username.clone(), info.clone());
+                        }
+                        if let Some(rate) = rate {
+                            rate_limits.insert(key.clone(), RateLimitReading::new(&account.username, rate));
+                            rate_limits_changed = true;
+                        }
+                        info
+                    }
+                    Some(JobOutcome::Failed(e)) => {
+                        eprintln!("Failed to check billing for {}: {}", account.username, e);
+                        continue;
+                    }
+                    Some(JobOutcome::Cancelled) | None => continue,
+                },
+            };
+            
+            if display {
+                self.billing_monitor.display_billing(&info);
+                if let Some(label) = &account.label {
+                    println!("   🏷️  {}", label);
+                }
+                if let Some(reading) = rate_limits.get(&key) {
+                    reading.display(settings::get().monitor.rate_limit_floor);
+                }
+                if let Some(expiring) = ExpiringToken::flag(account, Utc::now()) {
+                    println!("   ⚠️  {}", expiring.message(Utc::now()));
+                }
+            }
+            billing_infos.push(info);
+        }
+        
+        if fetched {
+            if let Err(e) = self.save_billing_cache(&cache) {
+                warn!("Failed to save billing cache: {}", e);
+            }
+        }
+        if rate_limits_changed {
+            if let Err(e) = save_rate_limits(&self.rate_limit_cache_file, &rate_limits) {
+                warn!("Failed to save rate limit cache: {}", e);
+            }
         }
         
         Ok(billing_infos)
     }
+    
+    /// Cached billing for `owner`, if younger than the cache TTL.
+    pub fn cached_billing(&self, owner: &str) -> Option<BillingInfo> {
+        let ttl = Duration::minutes(settings::get().monitor.billing_cache_ttl_minutes);
+        self.load_billing_cache()
+            .remove(owner)
+            .filter(|info| Utc::now() - info.checked_at < ttl)
+    }
+    
+    /// Every cached billing result, however old, for read-only views.
+    pub fn cached_billing_all(&self) -> Vec<BillingInfo> {
+        self.load_billing_cache().into_values().collect()
+    }
+    
+    /// Adds live results to the billing cache; estimated ones are skipped.
+    pub fn cache_billing(&self, infos: &[BillingInfo]) -> Result<()> {
+        let mut cache = self.load_billing_cache();
+        for info in infos.iter().filter(|info| !info.is_estimated) {
+            cache.insert(info.username.clone(), info.clone());
+        }
+        self.save_billing_cache(&cache)
+    }
+    
+    /// Runs of the workflow queued for `monitor.stuck_queued_minutes` or
+    /// longer on any Active fork, each checked with its own account's
+    /// token. Every one found is alerted on, on each check while it stays
+    /// queued; a fork that cannot be checked is logged and skipped, as are
+    /// those of disabled accounts.
+    pub fn check_stuck_runs(
+        &self,
+        account_mgr: &AccountManager,
+        proxy_mgr: &ProxyManager,
+        client_for: &ApiFactory,
+        alerts: &AlertManager,
+    ) -> Result<Vec<StuckRun>> {
+        let settings = settings::get();
+        let stuck_after = Duration::minutes(settings.monitor.stuck_queued_minutes as i64);
+        let state = self.state_manager.load_state()?;
+        let now = Utc::now();
+        let mut stuck = Vec::new();
+        
+        for node in state.fork_chain.iter().filter(|n| n.status == ForkStatus::Active) {
+            if !account_mgr.is_enabled(node.pat_index) {
+                info!("Skipping {}: its account is disabled", node.repo);
+                continue;
+            }
+            
+            let runs = account_mgr
+                .get_account(node.pat_index)
+                .with_context(|| format!("No token for account index {}", node.pat_index))
+                .and_then(|account| Ok(client_for(account, proxy_mgr.proxy_for(account)?)))
+                .and_then(|client| {
+                    client.list_workflow_runs(&node.repo, &settings.github.workflow_file, Some("queued"), 100)
+                });
+            let runs = match runs {
+                Ok(runs) => runs,
+                Err(e) => {
+                    warn!("Could not check {} for stuck runs: {:#}", node.repo, e);
+                    continue;
+                }
+            };
+            
+            stuck.extend(runs.into_iter().filter(|run| now - run.created_at >= stuck_after).map(|run| StuckRun {
+                repo: node.repo.clone(),
+                run_id: run.id,
+                queued_minutes: (now - run.created_at).num_minutes(),
+            }));
+        }
+        
+        for run in &stuck {
+            warn!("{}", run.message());
+            if let Err(e) = alerts.send_alert(&run.message()) {
+                warn!("Could not send alert: {:#}", e);
+            }
+        }
+        
+        Ok(stuck)
+    }
+    
+    /// Runs `WorkflowController::keep_alive` on every fork in the chain
+    /// that is not deleted and whose account is enabled, each with that
+    /// account's token, and logs
+    /// what it did per repo. A fork that fails is logged and the others
+    /// still get theirs.
+    pub fn keep_alive(
+        &self,
+        account_mgr: &AccountManager,
+        proxy_mgr: &ProxyManager,
+        client_for: &ApiFactory,
+        controller: &WorkflowController,
+    ) -> Result<Vec<(String, Result<KeepAlive>)>> {
+        let state = self.state_manager.load_state()?;
+        let mut outcomes = Vec::new();
+        
+        for node in state.fork_chain.iter().filter(|n| n.status != ForkStatus::Deleted) {
+            if !account_mgr.is_enabled(node.pat_index) {
+                info!("Keep-alive {}: skipped, its account is disabled", node.repo);
+                continue;
+            }
+            
+            let outcome = account_mgr
+                .get_account(node.pat_index)
+                .with_context(|| format!("No token for account index {}", node.pat_index))
+                .and_then(|account| Ok(client_for(account, proxy_mgr.proxy_for(account)?)))
+                .and_then(|client| controller.keep_alive(&node.repo, client.as_ref()));
+            
+            match &outcome {
+                Ok(done) => info!("Keep-alive {}: {}", node.repo, done),
+                Err(e) => warn!("Keep-alive failed for {}: {:#}", node.repo, e),
+            }
+            outcomes.push((node.repo.clone(), outcome));
+        }
+        
+        Ok(outcomes)
+    }
+    
+    /// Tokens among `accounts` expiring within
+    /// `monitor.token_expiry_warning_days`, each logged as a warning. Those
+    /// within `TOKEN_ALERT_DAYS` are also alerted on, once a day per token.
+    pub fn check_token_expiry(&self, accounts: &[AccountInfo], alerts: &AlertManager) -> Vec<ExpiringToken> {
+        let now = Utc::now();
+        let expiring: Vec<ExpiringToken> = accounts.iter().filter_map(|account| ExpiringToken::flag(account, now)).collect();
+        
+        let events = EventLog::new(self.state_manager.paths());
+        let recent = events.read_recent(TOKEN_EVENT_WINDOW).unwrap_or_default();
+        
+        for token in &expiring {
+            let message = token.message(now);
+            warn!("{}", message);
+            
+            let alerted_today = recent.iter().any(|e| {
+                e.kind == "token_expiry" && now - e.timestamp < Duration::days(1) && e.message.starts_with(&token.prefix())
+            });
+            if token.expires_at - now > Duration::days(TOKEN_ALERT_DAYS) || alerted_today {
+                continue;
+            }
+            
+            events.record("token_expiry", &message, None);
+            if let Err(e) = alerts.send_alert(&message) {
+                warn!("Could not send alert: {:#}", e);
+            }
+        }
+        
+        expiring
+    }
+    
+    fn load_billing_cache(&self) -> HashMap<String, BillingInfo> {
+        fs::read_to_string(&self.billing_cache_file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+    
+    fn save_billing_cache(&self, cache: &HashMap<String, BillingInfo>) -> Result<()> {
+        if let Some(parent) = self.billing_cache_file.parent() {
+            fs::create_dir_all(parent).context("Failed to create cache directory")?;
+        }
+        
+        let json = serde_json::to_string_pretty(cache)
+            .context("Failed to serialize billing cache")?;
+        
+        fs::write(&self.billing_cache_file, json)
+            .context("Failed to write billing cache")?;
+        
+        info!("Saved {} billing entries to cache", cache.len());
+        Ok(())
+    }
+}
+
+/// A run that has been waiting for a runner too long, see
+/// `HealthMonitor::check_stuck_runs`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StuckRun {
+    pub repo: String,
+    pub run_id: u64,
+    pub queued_minutes: i64,
 }
 
-pub fn show_billing_all() -> Result<()> {
-    let config_dir = PathBuf::from("config");
+impl StuckRun {
+    /// Alert text, with a link to the run.
+    pub fn message(&self) -> String {
+        format!(
+            "Run #{} in {} has been queued for {} minutes; the account may be at its concurrency limit or Actions disabled on the fork: {}",
+            self.run_id,
+            self.repo,
+            self.queued_minutes,
+            workflow::run_url(&self.repo, self.run_id)
+        )
+    }
+}
+
+/// A token expiring within `monitor.token_expiry_warning_days`, see
+/// `HealthMonitor::check_token_expiry`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpiringToken {
+    pub username: String,
+    pub index: usize,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ExpiringToken {
+    /// `account`'s token when it expires within the warning window of `now`.
+    pub fn flag(account: &AccountInfo, now: DateTime<Utc>) -> Option<Self> {
+        let window = Duration::days(settings::get().monitor.token_expiry_warning_days as i64);
+        if !account.expires_within(window, now) {
+            return None;
+        }
+        
+        Some(Self {
+            username: account.username.clone(),
+            index: account.index,
+            expires_at: account.expires_at?,
+        })
+    }
     
-    println!("\n╔═══════════════════════════════════════════════════════╗");
-    println!("║          BILLING STATUS - ALL ACCOUNTS               ║");
-    println!("╚═══════════════════════════════════════════════════════╝\n");
+    fn prefix(&self) -> String {
+        format!("Token #{} (@{})", self.index, self.username)
+    }
     
-    let tokens_file = config_dir.join("tokens.txt");
-    let proxies_file = config_dir.join("proxies.txt");
-    let cache_dir = config_dir.join("cache");
+    pub fn message(&self, now: DateTime<Utc>) -> String {
+        let left = self.expires_at - now;
+        let when = if left <= Duration::zero() {
+            "has expired".to_string()
+        } else if left < Duration::days(1) {
+            format!("expires in {} hours", left.num_hours())
+        } else {
+            format!("expires in {} days", left.num_days())
+        };
+        format!(
+            "{} {} ({}); replace it in tokens.txt before it stops working",
+            self.prefix(),
+            when,
+            self.expires_at.format("%Y-%m-%d %H:%M UTC")
+        )
+    }
+}
+
+/// Last REST quota seen for a token. Kept in cache/rate_limits.json keyed by
+/// the masked token, so `status` can show it without calling the API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitReading {
+    pub username: String,
+    #[serde(flatten)]
+    pub rate: RateLimit,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl RateLimitReading {
+    pub fn new(username: &str, rate: RateLimit) -> Self {
+        Self {
+            username: username.to_string(),
+            rate,
+            checked_at: Utc::now(),
+        }
+    }
     
-    let mut account_mgr = AccountManager::new(&cache_dir);
-    account_mgr.load_tokens(&tokens_file)?;
+    /// Fewer than `floor` requests left, and the window has not reset since.
+    pub fn is_low(&self, floor: u32, now: DateTime<Utc>) -> bool {
+        self.rate.remaining < floor && self.rate.reset_at > now
+    }
     
-    let mut proxy_mgr = ProxyManager::new(&cache_dir);
+    pub fn display(&self, floor: u32) {
+        println!(
+            "   🔑 API quota: {}/{} requests left, resets {}",
+            self.rate.remaining,
+            self.rate.limit,
+            self.rate.reset_at.format("%H:%M UTC")
+        );
+        if self.is_low(floor, Utc::now()) {
+            println!("   ⚠️  API quota below {} requests", floor);
+        }
+    }
+}
+
+/// Rate limit readings from `file`, keyed by masked token. Missing or
+/// unreadable caches are empty.
+pub fn load_rate_limits(file: &Path) -> HashMap<String, RateLimitReading> {
+    fs::read_to_string(file)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_rate_limits(file: &Path, readings: &HashMap<String, RateLimitReading>) -> Result<()> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent).context("Failed to create cache directory")?;
+    }
     
-    if proxies_file.exists() {
-        let tokens: Vec<String> = std::fs::read_to_string(&tokens_file)?
-            .lines()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+    let json = serde_json::to_string_pretty(readings)
+        .context("Failed to serialize rate limit cache")?;
+    fs::write(file, json).context("Failed to write rate limit cache")
+}
+
+/// Month-to-date burn rate projected forward to the monthly reset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BillingForecast {
+    pub hours_per_day: f32,
+    /// None when the quota outlasts the current billing month
+    pub exhausted_at: Option<DateTime<Utc>>,
+}
+
+/// Start of the billing month `now` is in, and of the next one.
+fn billing_month(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let month_start = Utc
+        .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .unwrap();
+    let (next_year, next_month) = if now.month() == 12 {
+        (now.year() + 1, 1)
+    } else {
+        (now.year(), now.month() + 1)
+    };
+    let month_end = Utc
+        .with_ymd_and_hms(next_year, next_month, 1, 0, 0, 0)
+        .unwrap();
+    (month_start, month_end)
+}
+
+/// Days since the billing month started, at least an hour's worth.
+fn days_into_month(now: DateTime<Utc>) -> f32 {
+    ((now - billing_month(now).0).num_minutes() as f32 / 1440.0).max(1.0 / 24.0)
+}
+
+impl BillingForecast {
+    pub fn from_info(info: &BillingInfo, now: DateTime<Utc>) -> Self {
+        let month_end = billing_month(now).1;
+        let hours_per_day = info.total_core_hours_used / days_into_month(now);
+        
+        let exhausted_at = if info.is_exhausted {
+            Some(now)
+        } else if hours_per_day > 0.0 {
+            let minutes_left = (info.hours_remaining / hours_per_day * 1440.0) as i64;
+            Some(now + Duration::minutes(minutes_left)).filter(|at| *at < month_end)
+        } else {
+            None
+        };
         
-        proxy_mgr.load_from_file(&proxies_file, &tokens)?;
+        Self { hours_per_day, exhausted_at }
+    }
+    
+    pub fn display(&self) {
+        match self.exhausted_at {
+            Some(at) => println!(
+                "   📈 Forecast: {:.1}h/day, exhausted around {}",
+                self.hours_per_day,
+                at.format("%Y-%m-%d %H:%M UTC")
+            ),
+            None => println!(
+                "   📈 Forecast: {:.1}h/day, lasts until the monthly reset",
+                self.hours_per_day
+            ),
+        }
+    }
+}
+
+/// Core-hours a day one node costs running around the clock on a Linux
+/// runner, billed at 2x.
+pub const CORE_HOURS_PER_NODE_DAY: f32 = 48.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BurnSource {
+    /// Core-hours used this month across the accounts, per day
+    MonthToDate,
+    /// Nothing used yet this month: active nodes × `CORE_HOURS_PER_NODE_DAY`
+    ActiveNodes,
+}
+
+/// Quota left across the whole chain and how long it lasts at the
+/// current burn rate.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChainCapacity {
+    /// Over the accounts with quota left whose billing could be read
+    pub hours_remaining: f32,
+    pub accounts: usize,
+    /// Accounts left out because their billing could not be read
+    pub unknown: usize,
+    pub hours_per_day: f32,
+    pub burn_source: BurnSource,
+    /// None when nothing is burning
+    pub days_left: Option<f32>,
+    pub exhausted_at: Option<DateTime<Utc>>,
+}
+
+impl ChainCapacity {
+    /// `active_nodes` is how many nodes the active fork runs, for the burn
+    /// rate when this month's usage is still zero.
+    pub fn from_infos(infos: &[BillingInfo], active_nodes: usize, now: DateTime<Utc>) -> Self {
+        let (readable, unknown): (Vec<&BillingInfo>, Vec<&BillingInfo>) = infos
+            .iter()
+            .partition(|info| !info.is_estimated && !info.permission_missing);
+        let with_quota: Vec<&&BillingInfo> = readable.iter().filter(|info| !info.is_exhausted).collect();
+        let hours_remaining: f32 = with_quota.iter().map(|info| info.hours_remaining).sum();
+        
+        let used: f32 = readable.iter().map(|info| info.total_core_hours_used).sum();
+        let (hours_per_day, burn_source) = if used > 0.0 {
+            (used / days_into_month(now), BurnSource::MonthToDate)
+        } else {
+            (active_nodes as f32 * CORE_HOURS_PER_NODE_DAY, BurnSource::ActiveNodes)
+        };
+        
+        let days_left = Some(hours_remaining / hours_per_day).filter(|_| hours_per_day > 0.0);
+        Self {
+            hours_remaining,
+            accounts: with_quota.len(),
+            unknown: unknown.len(),
+            hours_per_day,
+            burn_source,
+            days_left,
+            exhausted_at: days_left.map(|days| now + Duration::minutes((days * 1440.0) as i64)),
+        }
+    }
+    
+    pub fn display(&self, now: DateTime<Utc>) {
+        println!(
+            "  ⏳ Chain capacity: ~{:.1} core-hours left across {} accounts",
+            self.hours_remaining, self.accounts
+        );
+        let basis = match self.burn_source {
+            BurnSource::MonthToDate => "this month's usage",
+            BurnSource::ActiveNodes => "active nodes",
+        };
+        match (self.days_left, self.exhausted_at) {
+            (Some(days), Some(at)) => println!(
+                "     enough for ~{:.1} days at {:.1}h/day ({}), until about {}{}",
+                days,
+                self.hours_per_day,
+                basis,
+                at.format("%Y-%m-%d %H:%M UTC"),
+                if at >= billing_month(now).1 { ", past the monthly reset" } else { "" }
+            ),
+            _ => println!("     nothing is burning quota: no usage this month and no active nodes"),
+        }
+        if self.unknown > 0 {
+            println!("     {} accounts left out: their billing could not be read", self.unknown);
+        }
+    }
+}
+
+/// Nodes in nodes.txt while the chain has an active fork, else 0.
+pub fn active_node_count(paths: &Paths, state: &crate::core::state::OrchestratorState) -> usize {
+    if !state.fork_chain.iter().any(|node| node.status == ForkStatus::Active) {
+        return 0;
+    }
+    fs::read_to_string(paths.nodes_file())
+        .map(|content| platform::config_lines(&content).len())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BillingOutput {
+    Table,
+    Json,
+    SummaryJson,
+}
+
+/// One element of `billing --json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BillingReportEntry {
+    #[serde(flatten)]
+    pub billing: BillingInfo,
+    pub data_age_secs: i64,
+}
+
+impl BillingReportEntry {
+    pub fn from_info(info: &BillingInfo) -> Self {
+        Self {
+            billing: info.clone(),
+            data_age_secs: (Utc::now() - info.checked_at).num_seconds().max(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct BillingSummary {
+    pub total: usize,
+    pub ok: usize,
+    pub warning: usize,
+    pub exhausted: usize,
+    /// Fine-grained tokens without billing permission
+    pub unknown: usize,
+}
+
+impl BillingSummary {
+    pub fn from_infos(infos: &[BillingInfo]) -> Self {
+        let total = infos.len();
+        let exhausted = infos.iter().filter(|b| b.is_exhausted).count();
+        let warning = infos.iter().filter(|b| b.is_warning && !b.is_exhausted).count();
+        let unknown = infos.iter().filter(|b| b.permission_missing).count();
+        
+        Self {
+            total,
+            ok: total - exhausted - warning - unknown,
+            warning,
+            exhausted,
+            unknown,
+        }
+    }
+    
+    /// `ALL_EXHAUSTED` when no account has quota left, `PARTIAL` when some do not.
+    pub fn exit_code(&self) -> u8 {
+        if self.total > 0 && self.exhausted == self.total {
+            exit_code::ALL_EXHAUSTED
+        } else if self.exhausted > 0 {
+            exit_code::PARTIAL
+        } else {
+            exit_code::SUCCESS
+        }
+    }
+}
+
+pub fn show_billing_all(ctx: &AppContext, output: BillingOutput) -> Result<BillingSummary> {
+    show_billing(ctx, output, &[], false)
+}
+
+/// Billing for the accounts matching `selectors` (all accounts when empty).
+/// `force` skips the billing cache.
+pub fn show_billing(
+    ctx: &AppContext,
+    output: BillingOutput,
+    selectors: &[String],
+    force: bool,
+) -> Result<BillingSummary> {
+    if output == BillingOutput::Table && selectors.is_empty() {
+        println!("\n╔═══════════════════════════════════════════════════════╗");
+        println!("║          BILLING STATUS - ALL ACCOUNTS               ║");
+        println!("╚═══════════════════════════════════════════════════════╝\n");
     }
     
-    let health_monitor = HealthMonitor::new(&config_dir)?;
+    let account_mgr = ctx.accounts()?;
+    let proxy_mgr = ctx.proxies()?;
+    let health_monitor = ctx.health()?;
+    let accounts = health_monitor.select_accounts(&account_mgr, selectors)?;
     
     let billing_infos = health_monitor.check_all_accounts(
-        account_mgr.get_all_accounts(),
+        &accounts,
         &proxy_mgr,
+        output == BillingOutput::Table && selectors.is_empty(),
+        force,
     )?;
     
-    println!("\n─────────────────────────────────────────────────────────");
-    println!("Summary:");
+    let summary = BillingSummary::from_infos(&billing_infos);
     
-    let total = billing_infos.len();
-    let exhausted = billing_infos.iter().filter(|b| b.is_exhausted).count();
-    let warning = billing_infos.iter().filter(|b| b.is_warning && !b.is_exhausted).count();
-    let ok = total - exhausted - warning;
+    match output {
+        BillingOutput::Json => {
+            let entries: Vec<BillingReportEntry> = billing_infos
+                .iter()
+                .map(BillingReportEntry::from_info)
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        }
+        BillingOutput::SummaryJson => {
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        }
+        BillingOutput::Table if !selectors.is_empty() => {
+            let now = Utc::now();
+            println!();
+            for info in &billing_infos {
+                health_monitor.billing_monitor.display_billing(info);
+                let labels: Vec<&str> = accounts
+                    .iter()
+                    .filter(|account| account.billing_owner().0 == info.username)
+                    .filter_map(|account| account.label.as_deref())
+                    .collect();
+                if !labels.is_empty() {
+                    println!("   🏷️  {}", labels.join(", "));
+                }
+                if !info.permission_missing {
+                    BillingForecast::from_info(info, now).display();
+                }
+                println!(
+                    "   🕒 Checked {}s ago{}",
+                    (now - info.checked_at).num_seconds().max(0),
+                    if info.is_estimated { " (estimated)" } else { "" }
+                );
+            }
+            println!();
+        }
+        BillingOutput::Table => {
+            println!("\n─────────────────────────────────────────────────────────");
+            println!("Summary:");
+            println!("  Total Accounts: {}", summary.total);
+            println!("  🟢 OK: {}", summary.ok);
+            println!("  🟡 Warning: {}", summary.warning);
+            println!("  🔴 Exhausted: {}", summary.exhausted);
+            if summary.unknown > 0 {
+                println!("  ⚪ Billing permission missing: {}", summary.unknown);
+            }
+            let state = ctx.state().load_state()?;
+            let now = Utc::now();
+            ChainCapacity::from_infos(&billing_infos, active_node_count(ctx.paths(), &state), now).display(now);
+            println!("─────────────────────────────────────────────────────────\n");
+        }
+    }
     
-    println!("  Total Accounts: {}", total);
-    println!("  🟢 OK: {}", ok);
-    println!("  🟡 Warning: {}", warning);
-    println!("  🔴 Exhausted: {}", exhausted);
-    println!("─────────────────────────────────────────────────────────\n");
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    fn billing(username: &str, used: f32, warning: bool, exhausted: bool) -> BillingInfo {
+        BillingInfo {
+            username: username.to_string(),
+            total_core_hours_used: used,
+            total_minutes_used: used * 30.0,
+            included_minutes: 2000.0,
+            hours_remaining: 120.0 - used,
+            is_exhausted: exhausted,
+            is_warning: warning,
+            is_estimated: false,
+            permission_missing: false,
+            plan_name: None,
+            included_core_hours: 120.0,
+            checked_at: Utc::now(),
+        }
+    }
+    
+    fn keys(value: &serde_json::Value) -> Vec<String> {
+        let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+    
+    #[test]
+    fn test_billing_json_field_names() {
+        let entry = BillingReportEntry::from_info(&billing("alice", 10.0, false, false));
+        let value = serde_json::to_value(&entry).unwrap();
+        
+        assert_eq!(keys(&value), vec![
+            "checked_at",
+            "data_age_secs",
+            "hours_remaining",
+            "included_core_hours",
+            "included_minutes",
+            "is_estimated",
+            "is_exhausted",
+            "is_warning",
+            "permission_missing",
+            "plan",
+            "total_core_hours_used",
+            "total_minutes_used",
+            "username",
+        ]);
+        
+        let summary = serde_json::to_value(BillingSummary::default()).unwrap();
+        assert_eq!(keys(&summary), vec!["exhausted", "ok", "total", "unknown", "warning"]);
+    }
     
-    Ok(())
+    #[test]
+    fn test_summary_exit_code_reflects_exhaustion() {
+        let ok = billing("a", 10.0, false, false);
+        let warn = billing("b", 118.5, true, false);
+        let exhausted = billing("c", 119.8, true, true);
+        
+        assert_eq!(BillingSummary::from_infos(std::slice::from_ref(&ok)).exit_code(), exit_code::SUCCESS);
+        assert_eq!(BillingSummary::from_infos(&[ok.clone(), warn.clone()]).exit_code(), exit_code::SUCCESS);
+        
+        let summary = BillingSummary::from_infos(&[ok, warn, exhausted.clone()]);
+        assert_eq!(summary, BillingSummary { total: 3, ok: 1, warning: 1, exhausted: 1, unknown: 0 });
+        assert_eq!(summary.exit_code(), exit_code::PARTIAL);
+        
+        let unreadable = BillingInfo { permission_missing: true, ..billing("d", 0.0, false, false) };
+        let summary = BillingSummary::from_infos(&[unreadable, billing("e", 10.0, false, false)]);
+        assert_eq!(summary, BillingSummary { total: 2, ok: 1, warning: 0, exhausted: 0, unknown: 1 });
+        assert_eq!(summary.exit_code(), exit_code::SUCCESS);
+        
+        assert_eq!(BillingSummary::from_infos(&[exhausted]).exit_code(), exit_code::ALL_EXHAUSTED);
+    }
+    
+    #[test]
+    fn test_select_accounts_by_username_or_index() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("tokens.txt"), "ghp_aaaa\nghp_bbbb\nghp_cccc\n").unwrap();
+        
+        let paths = Paths::new(dir);
+        let mut account_mgr = AccountManager::new(&paths);
+        account_mgr.load_tokens(&paths.tokens_file()).unwrap();
+        let monitor = HealthMonitor::new(&paths).unwrap();
+        
+        assert_eq!(monitor.select_accounts(&account_mgr, &[]).unwrap().len(), 3);
+        
+        let selected = monitor
+            .select_accounts(&account_mgr, &["2".to_string(), "USER_0".to_string(), "user_2".to_string()])
+            .unwrap();
+        let indices: Vec<usize> = selected.iter().map(|a| a.index).collect();
+        assert_eq!(indices, vec![2, 0]);
+        
+        let err = monitor.select_accounts(&account_mgr, &["mallory".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("user_0, user_1, user_2"));
+        assert!(monitor.select_accounts(&account_mgr, &["7".to_string()]).is_err());
+    }
+    
+    #[test]
+    fn test_rate_limit_cache_round_trip() {
+        let temp_dir = tempdir().unwrap();
+        let file = Paths::new(temp_dir.path()).rate_limit_cache();
+        let now = Utc::now();
+        let reading = RateLimitReading::new("alice", RateLimit {
+            limit: 5000,
+            remaining: 120,
+            reset_at: now + Duration::minutes(20),
+        });
+        
+        assert!(load_rate_limits(&file).is_empty());
+        
+        let mut readings = HashMap::new();
+        readings.insert(metrics::mask_token("ghp_abcdefghwxyz"), reading.clone());
+        save_rate_limits(&file, &readings).unwrap();
+        
+        let loaded = load_rate_limits(&file);
+        assert_eq!(loaded.get("ghp_…wxyz"), Some(&reading));
+        
+        assert!(reading.is_low(500, now));
+        assert!(!reading.is_low(100, now));
+        // A reading from before the reset says nothing about the new window
+        assert!(!reading.is_low(500, now + Duration::hours(1)));
+    }
+    
+    #[test]
+    fn test_forecast_projects_month_to_date_rate() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 11, 0, 0, 0).unwrap();
+        
+        // 100h in 10 days: 10h/day, 20h left runs out in two days
+        let forecast = BillingForecast::from_info(&billing("a", 100.0, false, false), now);
+        assert!((forecast.hours_per_day - 10.0).abs() < 0.01);
+        assert_eq!(forecast.exhausted_at, Some(Utc.with_ymd_and_hms(2026, 10, 13, 0, 0, 0).unwrap()));
+        
+        // 10h in 10 days: 110h left outlasts the month
+        let forecast = BillingForecast::from_info(&billing("b", 10.0, false, false), now);
+        assert_eq!(forecast.exhausted_at, None);
+    }
+    
+    #[test]
+    fn test_chain_capacity_sums_readable_accounts_with_quota() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 11, 0, 0, 0).unwrap();
+        let unreadable = BillingInfo { permission_missing: true, is_estimated: true, ..billing("d", 0.0, false, false) };
+        
+        // 20h + 100h left; 120h + 100h + 20h used in 10 days is 24h/day
+        let infos = [
+            billing("a", 120.0, true, true),
+            billing("b", 100.0, false, false),
+            billing("c", 20.0, false, false),
+            unreadable.clone(),
+        ];
+        let capacity = ChainCapacity::from_infos(&infos, 3, now);
+        assert_eq!((capacity.hours_remaining, capacity.accounts, capacity.unknown), (120.0, 2, 1));
+        assert_eq!(capacity.burn_source, BurnSource::MonthToDate);
+        assert!((capacity.hours_per_day - 24.0).abs() < 0.01);
+        assert_eq!(capacity.exhausted_at, Some(Utc.with_ymd_and_hms(2026, 10, 16, 0, 0, 0).unwrap()));
+        
+        // Nothing used yet: the active nodes set the rate
+        let fresh = [billing("a", 0.0, false, false), unreadable];
+        let capacity = ChainCapacity::from_infos(&fresh, 2, now);
+        assert_eq!(capacity.burn_source, BurnSource::ActiveNodes);
+        assert_eq!(capacity.hours_per_day, 2.0 * CORE_HOURS_PER_NODE_DAY);
+        assert!((capacity.days_left.unwrap() - 1.25).abs() < 0.01);
+        
+        let idle = ChainCapacity::from_infos(&fresh, 0, now);
+        assert_eq!((idle.days_left, idle.exhausted_at), (None, None));
+    }
 }
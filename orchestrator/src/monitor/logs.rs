@@ -0,0 +1,325 @@
+// src/monitor/logs.rs - Read-only access to the orchestrator log files
+
+use anyhow::{Result, Context, bail};
+use chrono::{DateTime, Duration, Local, NaiveDateTime};
+use colored::*;
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use crate::utils::exit_code::ConfigError;
+
+const TAIL_BLOCK_SIZE: u64 = 8192;
+const FOLLOW_POLL_MS: u64 = 500;
+
+#[derive(Debug, Clone)]
+pub struct LogFileInfo {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+}
+
+pub struct LogViewer {
+    log_dir: PathBuf,
+}
+
+impl LogViewer {
+    pub fn new(log_dir: &Path) -> Self {
+        Self {
+            log_dir: log_dir.to_path_buf(),
+        }
+    }
+    
+    /// All `.log` files under the log directory (one level of subdirectories), newest first.
+    pub fn list_files(&self) -> Result<Vec<LogFileInfo>> {
+        if !self.log_dir.is_dir() {
+            bail!(ConfigError::new(format!(
+                "No log directory at {} yet; logs appear after the first run",
+                self.log_dir.display()
+            )));
+        }
+        
+        let mut files = Vec::new();
+        collect_log_files(&self.log_dir, 1, &mut files)?;
+        files.sort_by_key(|f| Reverse(f.modified));
+        Ok(files)
+    }
+    
+    /// Resolves `orchestrator`, `orchestrator.log` or `runs/123.log` inside the log directory.
+    pub fn resolve_file(&self, name: &str) -> Result<PathBuf> {
+        let mut path = self.log_dir.join(name);
+        if path.extension().is_none() {
+            path.set_extension("log");
+        }
+        
+        if !path.is_file() {
+            let known: Vec<String> = self.list_files()?
+                .iter()
+                .filter_map(|f| f.path.strip_prefix(&self.log_dir).ok().map(|p| p.display().to_string()))
+                .collect();
+            bail!(ConfigError::new(format!(
+                "Log file {} not found. Available: {}",
+                path.display(),
+                if known.is_empty() { "none".to_string() } else { known.join(", ") }
+            )));
+        }
+        
+        Ok(path)
+    }
+    
+    /// Files belonging to one workflow run: any log whose name contains the run id.
+    pub fn run_files(&self, run_id: u64) -> Result<Vec<PathBuf>> {
+        let needle = run_id.to_string();
+        
+        Ok(self.list_files()?
+            .into_iter()
+            .map(|f| f.path)
+            .filter(|p| {
+                p.file_stem()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|s| s.split(|c: char| !c.is_ascii_digit()).any(|part| part == needle))
+            })
+            .collect())
+    }
+    
+    /// Lines containing `pattern`, optionally only those logged within `since`.
+    /// Lines without a timestamp inherit the previous line's.
+    pub fn grep(&self, pattern: &str, since: Option<Duration>) -> Result<Vec<(PathBuf, String)>> {
+        // A window reaching back past the earliest representable time keeps every line
+        let cutoff = since.and_then(|d| Local::now().naive_local().checked_sub_signed(d));
+        let mut matches = Vec::new();
+        
+        for file in self.list_files()? {
+            let content = match fs::read(&file.path) {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Err(_) => continue,
+            };
+            
+            let mut current: Option<NaiveDateTime> = None;
+            
+            for line in content.lines() {
+                if let Some(ts) = parse_line_timestamp(line) {
+                    current = Some(ts);
+                }
+                
+                let recent = match (cutoff, current) {
+                    (Some(cutoff), Some(ts)) => ts >= cutoff,
+                    (Some(_), None) => false,
+                    (None, _) => true,
+                };
+                
+                if recent && line.contains(pattern) {
+                    matches.push((file.path.clone(), line.to_string()));
+                }
+            }
+        }
+        
+        Ok(matches)
+    }
+}
+
+fn collect_log_files(dir: &Path, depth: usize, files: &mut Vec<LogFileInfo>) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?;
+    
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        
+        if metadata.is_dir() {
+            if depth > 0 {
+                collect_log_files(&path, depth - 1, files)?;
+            }
+        } else if path.extension().is_some_and(|e| e == "log") {
+            files.push(LogFileInfo {
+                path,
+                size: metadata.len(),
+                modified: metadata.modified().ok().map(DateTime::<Local>::from),
+            });
+        }
+    }
+    
+    Ok(())
+}
+
+/// Parses the `[YYYY-MM-DD HH:MM:SS]` prefix written by `FileLogger`.
+pub fn parse_line_timestamp(line: &str) -> Option<NaiveDateTime> {
+    let rest = line.strip_prefix('[')?;
+    let end = rest.find(']')?;
+    NaiveDateTime::parse_from_str(&rest[..end], "%Y-%m-%d %H:%M:%S").ok()
+}
+
+/// Last `count` lines of a seekable source, reading backwards in blocks so
+/// large logs are not loaded whole.
+pub fn tail_lines<R: Read + Seek>(reader: &mut R, count: usize) -> Result<Vec<String>> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    
+    let len = reader.seek(SeekFrom::End(0))?;
+    let mut pos = len;
+    let mut buf: Vec<u8> = Vec::new();
+    
+    while pos > 0 {
+        let read = TAIL_BLOCK_SIZE.min(pos);
+        pos -= read;
+        
+        let mut block = vec![0u8; read as usize];
+        reader.seek(SeekFrom::Start(pos))?;
+        reader.read_exact(&mut block)?;
+        block.extend_from_slice(&buf);
+        buf = block;
+        
+        // One extra newline: the trailing one, plus the one before the first wanted line
+        if buf.iter().filter(|&&b| b == b'\n').count() > count {
+            break;
+        }
+    }
+    
+    let text = String::from_utf8_lossy(&buf);
+    let lines: Vec<&str> = text.lines().collect();
+    let skip = lines.len().saturating_sub(count);
+    
+    Ok(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+pub fn colorize_line(line: &str) -> String {
+    if line.contains("[ERROR]") {
+        line.red().to_string()
+    } else if line.contains("[WARN]") {
+        line.yellow().to_string()
+    } else if let Some(end) = line.find(']').filter(|_| parse_line_timestamp(line).is_some()) {
+        format!("{}{}", line[..=end].bright_black(), &line[end + 1..])
+    } else {
+        line.to_string()
+    }
+}
+
+/// Prints new lines as they are appended. Reopens the file when it is
+/// rotated (different inode) or truncated (shorter than what was read).
+pub fn follow(path: &Path) -> Result<()> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut pos = file.seek(SeekFrom::End(0))?;
+    let mut identity = file_identity(&file);
+    let mut pending = String::new();
+    
+    loop {
+        thread::sleep(std::time::Duration::from_millis(FOLLOW_POLL_MS));
+        
+        if let Ok(metadata) = fs::metadata(path) {
+            let rotated = file_identity_from(&metadata) != identity;
+            
+            if rotated || metadata.len() < pos {
+                if let Ok(reopened) = File::open(path) {
+                    file = reopened;
+                    identity = file_identity(&file);
+                    pos = 0;
+                    pending.clear();
+                    println!("{}", format!("--- {} was rotated, reopening ---", path.display()).bright_black());
+                }
+            }
+        }
+        
+        file.seek(SeekFrom::Start(pos))?;
+        let mut chunk = Vec::new();
+        pos += file.read_to_end(&mut chunk)? as u64;
+        
+        if chunk.is_empty() {
+            continue;
+        }
+        
+        pending.push_str(&String::from_utf8_lossy(&chunk));
+        
+        // Only print complete lines; keep a partial trailing line for the next poll
+        while let Some(newline) = pending.find('\n') {
+            let line: String = pending.drain(..=newline).collect();
+            println!("{}", colorize_line(line.trim_end_matches(['\r', '\n'])));
+        }
+    }
+}
+
+#[cfg(unix)]
+fn file_identity_from(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_identity_from(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+fn file_identity(file: &File) -> Option<(u64, u64)> {
+    file.metadata().ok().and_then(|m| file_identity_from(&m))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_tail_lines() {
+        let content: String = (1..=5000).map(|i| format!("line {}\n", i)).collect();
+        let mut cursor = Cursor::new(content.into_bytes());
+        
+        let tail = tail_lines(&mut cursor, 3).unwrap();
+        assert_eq!(tail, vec!["line 4998", "line 4999", "line 5000"]);
+        
+        // Block boundaries and more lines than the file has
+        let mut cursor = Cursor::new(b"a\nb\nc".to_vec());
+        assert_eq!(tail_lines(&mut cursor, 2).unwrap(), vec!["b", "c"]);
+        assert_eq!(tail_lines(&mut cursor, 10).unwrap(), vec!["a", "b", "c"]);
+        assert!(tail_lines(&mut Cursor::new(Vec::new()), 5).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_parse_line_timestamp() {
+        let ts = parse_line_timestamp("[2026-10-16 13:05:09] [INFO] nexus - started").unwrap();
+        assert_eq!(ts.to_string(), "2026-10-16 13:05:09");
+        
+        assert!(parse_line_timestamp("[INFO] no timestamp").is_none());
+        assert!(parse_line_timestamp("plain line").is_none());
+    }
+    
+    #[test]
+    fn test_grep_since_and_missing_dir() {
+        let temp_dir = tempdir().unwrap();
+        let log_dir = temp_dir.path().join("logs");
+        
+        let err = LogViewer::new(&log_dir).list_files().unwrap_err();
+        assert!(err.to_string().contains("No log directory"));
+        
+        fs::create_dir_all(log_dir.join("runs")).unwrap();
+        let now = Local::now().naive_local();
+        let fmt = |ts: NaiveDateTime| ts.format("%Y-%m-%d %H:%M:%S").to_string();
+        fs::write(log_dir.join("orchestrator.log"), format!(
+            "[{}] [WARN] old rotation\n[{}] [WARN] new rotation\ncontinued rotation detail\n",
+            fmt(now - Duration::hours(5)),
+            fmt(now - Duration::minutes(5)),
+        )).unwrap();
+        fs::write(log_dir.join("runs").join("run-12345.log"), "step 1\n").unwrap();
+        
+        let viewer = LogViewer::new(&log_dir);
+        assert_eq!(viewer.grep("rotation", None).unwrap().len(), 3);
+        
+        let recent = viewer.grep("rotation", Some(Duration::hours(2))).unwrap();
+        let lines: Vec<&str> = recent.iter().map(|(_, l)| l.as_str()).collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("new rotation"));
+        assert_eq!(lines[1], "continued rotation detail");
+        // A window longer than the calendar reaches back keeps everything
+        assert_eq!(viewer.grep("rotation", Some(Duration::MAX)).unwrap().len(), 3);
+        
+        assert_eq!(viewer.run_files(12345).unwrap().len(), 1);
+        assert!(viewer.run_files(1234).unwrap().is_empty());
+        assert!(viewer.resolve_file("orchestrator").is_ok());
+        assert!(viewer.resolve_file("missing").is_err());
+    }
+}
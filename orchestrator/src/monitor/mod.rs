@@ -2,6 +2,11 @@
 
 pub mod health;
 pub mod alert;
+pub mod archive;
+pub mod events;
+pub mod logs;
+pub mod metrics;
 
 pub use health::HealthMonitor;
 pub use alert::AlertManager;
+pub use archive::{ArchivedFork, ForkArchive};
@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 use log::{info, warn};
+use crate::github::SecretLayout;
+use crate::utils::exit_code::ConfigError;
+use crate::utils::platform;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NexusConfig {
@@ -15,37 +18,35 @@ pub struct NexusConfig {
 impl NexusConfig {
     pub fn load_from_files(nodes_file: &Path, wallets_file: &Path) -> Result<Self> {
         let nodes_content = fs::read_to_string(nodes_file)
-            .context("Failed to read nodes.txt")?;
+            .context(ConfigError::new("Failed to read nodes.txt"))?;
         
-        let node_ids: Vec<String> = nodes_content
-            .lines()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
+        let node_ids: Vec<String> = platform::config_lines(&nodes_content)
+            .into_iter()
+            .map(|(_, s)| s.to_string())
             .collect();
         
         let wallets_content = fs::read_to_string(wallets_file)
-            .context("Failed to read wallets.txt")?;
+            .context(ConfigError::new("Failed to read wallets.txt"))?;
         
-        let wallets: Vec<String> = wallets_content
-            .lines()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
+        let wallets: Vec<String> = platform::config_lines(&wallets_content)
+            .into_iter()
+            .map(|(_, s)| s.to_string())
             .collect();
         
         if node_ids.is_empty() {
-            bail!("No node IDs found in nodes.txt");
+            bail!(ConfigError::new("No node IDs found in nodes.txt"));
         }
         
         if wallets.is_empty() {
-            bail!("No wallets found in wallets.txt");
+            bail!(ConfigError::new("No wallets found in wallets.txt"));
         }
         
         if node_ids.len() != wallets.len() {
-            bail!(
+            bail!(ConfigError::new(format!(
                 "Node IDs and wallets count mismatch: {} nodes vs {} wallets",
                 node_ids.len(),
                 wallets.len()
-            );
+            )));
         }
         
         info!("Loaded {} node configurations", node_ids.len());
@@ -74,15 +75,27 @@ impl NexusConfig {
         Ok(())
     }
     
-    pub fn generate_matrix_json(&self, max_parallel: usize) -> Result<String> {
+    /// The workflow matrix, one item per node. In the indexed layout items
+    /// name the node's secrets instead of carrying the values.
+    pub fn generate_matrix_json(&self, max_parallel: usize, layout: SecretLayout) -> Result<String> {
         let mut matrix_items = Vec::new();
         
         for (i, (node_id, wallet)) in self.node_ids.iter().zip(self.wallets.iter()).enumerate() {
-            matrix_items.push(serde_json::json!({
-                "index": i + 1,
-                "node_id": node_id,
-                "wallet": wallet
-            }));
+            matrix_items.push(match layout {
+                SecretLayout::Joined => serde_json::json!({
+                    "index": i + 1,
+                    "node_id": node_id,
+                    "wallet": wallet
+                }),
+                SecretLayout::Indexed => {
+                    let [node_secret, wallet_secret] = SecretLayout::indexed_names(i + 1);
+                    serde_json::json!({
+                        "index": i + 1,
+                        "node_secret": node_secret,
+                        "wallet_secret": wallet_secret
+                    })
+                }
+            });
         }
         
         let matrix = serde_json::json!({
@@ -138,6 +151,39 @@ mod tests {
         assert_eq!(config.wallets.len(), 2);
     }
     
+    #[test]
+    fn test_nexus_config_load_crlf() {
+        let mut nodes_file = NamedTempFile::new().unwrap();
+        let mut wallets_file = NamedTempFile::new().unwrap();
+        
+        write!(nodes_file, "node1\r\nnode2\r\n").unwrap();
+        write!(wallets_file, "0x8254a986319461bf29ae35940a96786e507ad9ac\r\n0x0000000000000000000000000000000000000001\r\n").unwrap();
+        
+        let config = NexusConfig::load_from_files(nodes_file.path(), wallets_file.path()).unwrap();
+        
+        assert_eq!(config.node_ids, vec!["node1", "node2"]);
+        assert_eq!(config.wallets[1], "0x0000000000000000000000000000000000000001");
+    }
+    
+    #[test]
+    fn test_matrix_names_indexed_secrets() {
+        let config = NexusConfig {
+            node_ids: vec!["node1".to_string(), "node2".to_string()],
+            wallets: vec!["0xa".to_string(), "0xb".to_string()],
+        };
+        
+        let joined: serde_json::Value = serde_json::from_str(&config.generate_matrix_json(20, SecretLayout::Joined).unwrap()).unwrap();
+        assert_eq!(joined["include"][1], serde_json::json!({ "index": 2, "node_id": "node2", "wallet": "0xb" }));
+        
+        let indexed = config.generate_matrix_json(20, SecretLayout::Indexed).unwrap();
+        assert!(!indexed.contains("node2") && !indexed.contains("0xb"));
+        let indexed: serde_json::Value = serde_json::from_str(&indexed).unwrap();
+        assert_eq!(
+            indexed["include"][1],
+            serde_json::json!({ "index": 2, "node_secret": "NEXUS_NODE_2", "wallet_secret": "NEXUS_WALLET_2" })
+        );
+    }
+    
     #[test]
     fn test_split_batches() {
         let mut node_ids = Vec::new();
@@ -1,83 +1,741 @@
 // Update imports at top of src/orchestration/deploy.rs
-use anyhow::{Result, Context};
-use std::path::{Path, PathBuf};
-use log::info;
-use crate::core::{account, state, StateManager};
-use crate::github::{GitHubClient, SecretsManager, WorkflowController};
+use anyhow::{Result, Context, bail};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use log::{info, warn};
+use crate::app::AppContext;
+use crate::core::account::{self, Capability};
+use crate::core::{settings, state};
+use crate::github::workflow;
+use crate::github::{api, ApiFactory, GitHubClient, SecretHashes, SecretLayout, SecretWriteResult, SecretsManager, VariablesManager, WorkflowController};
+use crate::monitor::events::EventLog;
 use crate::nexus::NexusConfig;
+use crate::orchestration::env_file::{self, EnvKind};
+use crate::orchestration::forks;
+use crate::utils::cancel::{self, CancelToken, Cancelled};
+use crate::utils::delay::{self, Sleeper};
+use crate::utils::exit_code::{self, ConfigError};
+use crate::utils::logger;
+use crate::utils::pool::{JobOutcome, WorkerPool};
 
 pub struct Deployer {
-    config_dir: PathBuf,
+    ctx: Arc<AppContext>,
+    client_factory: ApiFactory,
+    sleeper: Arc<dyn Sleeper>,
+    cancel: CancelToken,
+    /// Rewrite every secret instead of skipping unchanged ones
+    force_secrets: bool,
+    /// Forks `set_all_secrets` works on at once
+    concurrency: usize,
+    /// Managed workflows `deploy_main_workflow` pushes; empty for all
+    workflows: Vec<String>,
 }
 
 impl Deployer {
-    pub fn new(config_dir: PathBuf) -> Self {
-        Self { config_dir }
+    pub fn new(ctx: Arc<AppContext>) -> Self {
+        Self {
+            ctx,
+            client_factory: Box::new(|account, proxy| {
+                Box::new(GitHubClient::new(account.token.clone(), proxy).with_cache(api::DEFAULT_CACHE_TTL))
+            }),
+            sleeper: delay::thread_sleeper(),
+            cancel: cancel::ctrl_c(),
+            force_secrets: false,
+            concurrency: settings::get().pool.deploy_workers,
+            workflows: Vec::new(),
+        }
+    }
+    
+    pub fn with_client_factory(mut self, client_factory: ApiFactory) -> Self {
+        self.client_factory = client_factory;
+        self
+    }
+    
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+    
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+    
+    pub fn with_force_secrets(mut self, force: bool) -> Self {
+        self.force_secrets = force;
+        self
+    }
+    
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+    
+    /// Deploys only these managed workflows, by file name.
+    pub fn with_workflows(mut self, workflows: Vec<String>) -> Self {
+        self.workflows = workflows;
+        self
+    }
+    
+    /// The selected workflows with their placeholders filled in, as
+    /// `deploy_main_workflow` would push them; each under a comment naming
+    /// its file when there are several.
+    pub fn render_workflow(&self) -> Result<String> {
+        let controller = self.load_workflow()?;
+        let several = controller.names().count() > 1;
+        
+        let mut rendered = String::new();
+        for (name, content) in controller.workflows() {
+            if several {
+                rendered.push_str(&format!("# .github/workflows/{}\n", name));
+            }
+            rendered.push_str(content);
+            if several && !content.ends_with('\n') {
+                rendered.push('\n');
+            }
+        }
+        Ok(rendered)
+    }
+    
+    fn load_workflow(&self) -> Result<WorkflowController> {
+        Ok(WorkflowController::load_managed()?
+            .select(&self.workflows)?
+            .with_template_values(&self.template_values()?)?
+            .with_branch(forks::deploy_branch(self.ctx.paths())?))
     }
     
     pub fn deploy_main_workflow(&self) -> Result<()> {
         info!("Deploying main workflow");
-        let workflow_path = Path::new(".github/workflows/nexus.yml");
         let setup = self.load_setup()?;
         let main_repo = format!("{}/{}", setup.owner, setup.repo_name);
         
-        let tokens = self.load_tokens()?;
-        let main_token = tokens.first().unwrap();
+        let accounts = self.ctx.accounts()?;
+        let account = accounts
+            .get_all_accounts()
+            .iter()
+            .find(|account| account.enabled)
+            .context(ConfigError::new("No enabled accounts in tokens.txt"))?;
+        account.require(&[Capability::Workflows])?;
+        let proxy = self.ctx.proxies()?.proxy_for(account)?;
+        let client = (self.client_factory)(account, proxy.clone());
+        let controller = self.load_workflow()?
+            .with_sleeper(self.sleeper.clone())
+            .with_git_credentials(&account.token, proxy);
         
-        let client = GitHubClient::new(main_token.clone(), None);
-        let controller = WorkflowController::new(workflow_path)?;
-        
-        controller.deploy_to_repo(&main_repo, &client)?;
+        controller.deploy_to_repo(&main_repo, client.as_ref())?;
         info!("Main workflow deployed to {}", main_repo);
         Ok(())
     }
     
-    pub fn set_all_secrets(&self) -> Result<()> {
+    /// Sets the Nexus secrets, and the `variables` from setup.json, on every
+    /// active fork of an enabled account, `[pool] deploy_workers` forks at
+    /// once, each with its own account and proxy. One fork failing does not
+    /// stop the others; see the report. Repos are recorded in the deploy
+    /// checkpoint as they finish, so a run stopped by Ctrl-C or left with
+    /// failures resumes with the rest.
+    pub fn set_all_secrets(&self) -> Result<DeployReport> {
+        let _interruptible = cancel::interruptible();
         info!("Setting secrets for all repos");
+        let paths = self.ctx.paths();
+        let nexus_config = NexusConfig::load_from_files(
+            &paths.nodes_file(),
+            &paths.wallets_file()
+        )?;
+        let variables = self.load_variables()?;
+        let layout = self.load_secret_layout()?;
+        
+        let state = self.ctx.state().load_state()?;
+        let checkpoint_file = paths.deploy_checkpoint();
+        let checkpoint = DeployCheckpoint::load(&checkpoint_file, &nexus_config);
+        if !checkpoint.completed.is_empty() {
+            info!("Resuming: {} repos already have their secrets", checkpoint.completed.len());
+        }
+        let mut report = DeployReport { resumed: checkpoint.completed.clone(), ..DeployReport::default() };
+        
+        // Accounts and proxies are looked up here; the workers only call GitHub
+        let accounts = self.ctx.accounts()?;
+        let jobs: Vec<_> = state.fork_chain
+            .iter()
+            .filter(|node| node.status == state::ForkStatus::Active && !checkpoint.completed.contains(&node.repo))
+            .filter(|node| accounts.is_enabled(node.pat_index))
+            .map(|node| {
+                let client_args = self.get_account_by_index(node.pat_index).and_then(|account| {
+                    let proxy = self.ctx.proxies()?.proxy_for(&account)?;
+                    Ok((account, proxy))
+                });
+                (node, client_args)
+            })
+            .collect();
+        // Refused before any fork is touched rather than failing fork by fork
+        for (account, _) in jobs.iter().filter_map(|(_, client_args)| client_args.as_ref().ok()) {
+            account.require(&[Capability::Secrets])?;
+        }
+        
+        let hashes = Arc::new(Mutex::new(SecretHashes::load(&paths.secret_hashes())?));
+        let checkpoint = Mutex::new(checkpoint);
+        let environment = settings::get().github.secrets_environment.clone();
+        let (client_factory, sleeper, force) = (&self.client_factory, &self.sleeper, self.force_secrets);
+        
+        let sweep = WorkerPool::new(self.concurrency)
+            .with_cancel(self.cancel.clone())
+            .run(&jobs, |(node, _)| node.pat_index.to_string(), |(node, client_args)| {
+                let _scope = logger::scoped(&node.repo);
+                let (account, proxy) = client_args.as_ref().map_err(|e| anyhow::anyhow!("{:#}", e))?;
+                
+                SecretsManager::new(client_factory(account, proxy.clone()))
+                    .with_sleeper(sleeper.clone())
+                    .with_environment(environment.clone())
+                    .with_shared_secret_hashes(hashes.clone())
+                    .with_force(force)
+                    .set_nexus_secrets(&node.repo, &nexus_config.node_ids, &nexus_config.wallets, layout)?;
+                set_variables(&VariablesManager::new(client_factory(account, proxy.clone())), &node.repo, &variables)?;
+                info!("Secrets set for {}", node.repo);
+                
+                let mut checkpoint = checkpoint.lock().unwrap();
+                checkpoint.completed.push(node.repo.clone());
+                checkpoint.save(&checkpoint_file)
+            });
+        
+        let cancelled = sweep.cancelled() > 0;
+        for ((node, _), outcome) in jobs.iter().zip(sweep.outcomes) {
+            let result = match outcome {
+                JobOutcome::Done(()) => Ok(()),
+                JobOutcome::Failed(e) => {
+                    warn!("Failed to set secrets for {}: {:#}", node.repo, e);
+                    Err(format!("{:#}", e))
+                }
+                JobOutcome::Cancelled => continue,
+            };
+            report.outcomes.push(DeployOutcome { repo: node.repo.clone(), result });
+        }
+        
+        if cancelled {
+            EventLog::new(paths).record(
+                "cancelled",
+                &format!("Secrets deployment stopped after {} repos", checkpoint.into_inner().unwrap().completed.len()),
+                None,
+            );
+            return Err(Cancelled.into());
+        }
+        
+        if report.failed() == 0 && checkpoint_file.exists() {
+            fs::remove_file(&checkpoint_file).context("Failed to remove the deploy checkpoint")?;
+        }
+        Ok(report)
+    }
+    
+    /// Brings a re-created fork into service: enables its managed
+    /// workflows, which GitHub leaves disabled on new forks, and sets the Nexus secrets, the
+    /// vaulted secrets and the setup.json variables.
+    pub fn redeploy_fork(&self, repo: &str, pat_index: usize) -> Result<()> {
+        let account = self.get_account_by_index(pat_index)?;
+        let proxy = self.ctx.proxies()?.proxy_for(&account)?;
+        let client = (self.client_factory)(&account, proxy.clone());
+        
+        for workflow in workflow::managed_workflow_names() {
+            match client.get_workflow_id(repo, &workflow)? {
+                Some(workflow_id) => client.enable_workflow(repo, workflow_id)?,
+                None => warn!("Workflow {} not found in {}, not enabled", workflow, repo),
+            }
+        }
+        
+        let paths = self.ctx.paths();
         let nexus_config = NexusConfig::load_from_files(
-            &self.config_dir.join("nodes.txt"),
-            &self.config_dir.join("wallets.txt")
+            &paths.nodes_file(),
+            &paths.wallets_file()
         )?;
+        let secrets_mgr = SecretsManager::new(client)
+            .with_sleeper(self.sleeper.clone())
+            .with_environment(settings::get().github.secrets_environment.clone())
+            .with_secret_hashes(SecretHashes::load(&self.ctx.paths().secret_hashes())?)
+            .with_vault(self.ctx.vault()?);
+        secrets_mgr.set_nexus_secrets(repo, &nexus_config.node_ids, &nexus_config.wallets, self.load_secret_layout()?)?;
+        secrets_mgr.replay_secrets(repo)?;
+        set_variables(&VariablesManager::new((self.client_factory)(&account, proxy)), repo, &self.load_variables()?)?;
+        
+        info!("Workflow enabled and secrets set for {}", repo);
+        Ok(())
+    }
+    
+    /// Pushes config/secrets.env to `repo` with its owner's token: the
+    /// secrets in one bulk write, then the variables.
+    pub fn deploy_env_file(&self, repo: &str) -> Result<EnvDeployment> {
+        let entries = env_file::load(&self.ctx.paths().secrets_env())?;
+        let state = self.ctx.state().load_state()?;
+        let account = self.ctx.accounts()?.resolve_for_repo(&state, repo, None)?.clone();
+        ensure_enabled(&account)?;
+        let proxy = self.ctx.proxies()?.proxy_for(&account)?;
+        let mut deployment = EnvDeployment::default();
         
-        let state_mgr = StateManager::new(&self.config_dir)?;
-        let state = state_mgr.load_state()?;
+        let secrets: Vec<(&str, &str)> = entries
+            .iter()
+            .filter(|e| e.kind == EnvKind::Secret)
+            .map(|e| (e.name.as_str(), e.value.as_str()))
+            .collect();
+        if !secrets.is_empty() {
+            deployment.secrets = SecretsManager::new((self.client_factory)(&account, proxy.clone()))
+                .with_sleeper(self.sleeper.clone())
+                .with_vault(self.ctx.vault()?)
+                .set_secrets(repo, &secrets)?;
+        }
+        
+        let variables_mgr = VariablesManager::new((self.client_factory)(&account, proxy));
+        for entry in entries.iter().filter(|e| e.kind == EnvKind::Variable) {
+            let created = variables_mgr.set_variable(repo, &entry.name, &entry.value)?;
+            deployment.variables.push((entry.name.clone(), created));
+        }
+        
+        info!("secrets.env pushed to {}: {} secrets, {} variables", repo, deployment.secrets.len(), deployment.variables.len());
+        Ok(deployment)
+    }
+    
+    /// Deletes the Nexus secrets from every chain fork still on GitHub,
+    /// whatever its status, with each fork's own account and proxy; forks
+    /// of disabled accounts are left alone. One fork failing does not stop
+    /// the others; see the report.
+    pub fn scrub_secrets(&self) -> Result<ScrubReport> {
+        let _interruptible = cancel::interruptible();
+        let paths = self.ctx.paths();
+        let state = self.ctx.state().load_state()?;
+        let mut report = ScrubReport::default();
         
-        for node in &state.fork_chain {
-            if node.status != state::ForkStatus::Active {
-                continue;
+        let accounts = self.ctx.accounts()?;
+        for node in state.fork_chain.iter().filter(|node| accounts.is_enabled(node.pat_index)) {
+            if self.cancel.is_cancelled() {
+                return Err(Cancelled.into());
             }
             
-            let account = self.get_account_by_index(node.pat_index)?;
-            let client = GitHubClient::new(account.token.clone(), None);
-            let secrets_mgr = SecretsManager::new(client);
+            let result = self.get_account_by_index(node.pat_index).and_then(|account| {
+                let client = (self.client_factory)(&account, self.ctx.proxies()?.proxy_for(&account)?);
+                if !client.check_repo_exists(&node.repo)? {
+                    return Ok(None);
+                }
+                SecretsManager::new(client)
+                    .with_environment(settings::get().github.secrets_environment.clone())
+                    .with_secret_hashes(SecretHashes::load(&paths.secret_hashes())?)
+                    .delete_nexus_secrets(&node.repo)
+                    .map(Some)
+            });
             
-            secrets_mgr.set_nexus_secrets(
-                &node.repo,
-                &nexus_config.node_ids,
-                &nexus_config.wallets
-            )?;
+            match result {
+                Ok(None) => report.gone.push(node.repo.clone()),
+                Ok(Some(deleted)) => report.outcomes.push(ScrubOutcome { repo: node.repo.clone(), result: Ok(deleted) }),
+                Err(e) => {
+                    warn!("Failed to scrub secrets from {}: {:#}", node.repo, e);
+                    report.outcomes.push(ScrubOutcome { repo: node.repo.clone(), result: Err(format!("{:#}", e)) });
+                }
+            }
+        }
+        
+        EventLog::new(paths).record(
+            "scrub",
+            &format!("Nexus secrets deleted from {} forks, {} failed", report.outcomes.len() - report.failed(), report.failed()),
+            None,
+        );
+        Ok(report)
+    }
+    
+    /// `verify --workflows`: how the workflows on each fork in the chain
+    /// compare with the local ones, see `WorkflowController::check_drift`.
+    pub fn check_workflow_drift(&self) -> Result<DriftReport> {
+        self.sweep_drift(false)
+    }
+    
+    /// `deploy --only-drifted`: deploys the workflows to the chain forks
+    /// whose copy is missing or differs from the local one, leaving the
+    /// rest alone.
+    pub fn deploy_drifted(&self) -> Result<DriftReport> {
+        self.sweep_drift(true)
+    }
+    
+    fn sweep_drift(&self, redeploy: bool) -> Result<DriftReport> {
+        let _interruptible = cancel::interruptible();
+        let state = self.ctx.state().load_state()?;
+        let controller = self.load_workflow()?.with_sleeper(self.sleeper.clone());
+        let mut report = DriftReport::default();
+        
+        let accounts = self.ctx.accounts()?;
+        let nodes: Vec<_> = state.fork_chain
+            .iter()
+            .filter(|node| node.status != state::ForkStatus::Deleted && accounts.is_enabled(node.pat_index))
+            .collect();
+        
+        if redeploy {
+            for node in &nodes {
+                if let Some(account) = accounts.get_account(node.pat_index) {
+                    account.require(&[Capability::Workflows])?;
+                }
+            }
+        }
+        
+        for node in nodes {
+            if self.cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
             
-            info!("Secrets set for {}", node.repo);
+            let result = self.get_account_by_index(node.pat_index).and_then(|account| {
+                let proxy = self.ctx.proxies()?.proxy_for(&account)?;
+                let client = (self.client_factory)(&account, proxy.clone());
+                let drift = controller.check_drift(&node.repo, client.as_ref())?;
+                
+                let redeployed = redeploy && drift != workflow::Drift::Same;
+                if redeployed {
+                    controller
+                        .clone()
+                        .with_git_credentials(&account.token, proxy)
+                        .deploy_to_repo(&node.repo, client.as_ref())?;
+                }
+                Ok((drift, redeployed))
+            });
+            
+            let result = result.map_err(|e| {
+                warn!("Failed to check the workflows on {}: {:#}", node.repo, e);
+                format!("{:#}", e)
+            });
+            report.outcomes.push(DriftOutcome { repo: node.repo.clone(), result });
         }
         
-        Ok(())
+        Ok(report)
     }
     
-    fn load_setup(&self) -> Result<SetupConfig> {
-        let content = std::fs::read_to_string(self.config_dir.join("setup.json"))?;
-        Ok(serde_json::from_str(&content)?)
+    /// `variables` from setup.json; none without the file or the key.
+    /// Numbers and booleans are pushed as their text.
+    fn load_variables(&self) -> Result<BTreeMap<String, String>> {
+        self.load_setup_values("variables")
+    }
+    
+    /// Values for the `{{name}}` placeholders of nexus.yml: `node_count`
+    /// and `wallet_count` from nodes.txt and wallets.txt when present,
+    /// `main_repo` and `workflow_file`, then `workflow_values` from
+    /// setup.json, which may override them.
+    fn template_values(&self) -> Result<BTreeMap<String, String>> {
+        let paths = self.ctx.paths();
+        let mut values = BTreeMap::new();
+        
+        if let Ok(config) = NexusConfig::load_from_files(&paths.nodes_file(), &paths.wallets_file()) {
+            values.insert("node_count".to_string(), config.node_ids.len().to_string());
+            values.insert("wallet_count".to_string(), config.wallets.len().to_string());
+        }
+        if let Ok(setup) = self.load_setup() {
+            values.insert("main_repo".to_string(), format!("{}/{}", setup.owner(), setup.repo_name()));
+        }
+        values.insert("workflow_file".to_string(), settings::get().github.workflow_file.clone());
+        values.extend(self.load_setup_values("workflow_values")?);
+        
+        Ok(values)
+    }
+    
+    /// The object under `key` in setup.json as name/text pairs; none
+    /// without the file or the key. Numbers and booleans become their text.
+    fn load_setup_values(&self, key: &str) -> Result<BTreeMap<String, String>> {
+        let Some(setup) = self.read_setup()? else {
+            return Ok(BTreeMap::new());
+        };
+        
+        let values = match &setup[key] {
+            serde_json::Value::Null => return Ok(BTreeMap::new()),
+            serde_json::Value::Object(values) => values,
+            _ => bail!(ConfigError::new(format!("setup.json {} must be an object of NAME: value", key))),
+        };
+        
+        values
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Number(_) | serde_json::Value::Bool(_) => value.to_string(),
+                    _ => bail!(ConfigError::new(format!("setup.json {} {} must be a string, number or boolean", key, name))),
+                };
+                Ok((name.clone(), value))
+            })
+            .collect()
     }
     
-    fn load_tokens(&self) -> Result<Vec<String>> {
-        let content = std::fs::read_to_string(self.config_dir.join("tokens.txt"))?;
-        Ok(content.lines().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+    /// `secret_layout` from setup.json; joined without the file or the key.
+    fn load_secret_layout(&self) -> Result<SecretLayout> {
+        match self.read_setup()?.map(|setup| setup["secret_layout"].clone()) {
+            None | Some(serde_json::Value::Null) => Ok(SecretLayout::default()),
+            Some(layout) => serde_json::from_value(layout)
+                .context(ConfigError::new("setup.json secret_layout must be \"joined\" or \"indexed\"")),
+        }
     }
     
+    /// setup.json as JSON; None when the file is missing.
+    fn read_setup(&self) -> Result<Option<serde_json::Value>> {
+        let content = match fs::read_to_string(self.ctx.paths().setup_file()) {
+            Ok(content) => content,
+            Err(_) => return Ok(None),
+        };
+        serde_json::from_str(&content)
+            .map(Some)
+            .context(ConfigError::new("Invalid setup.json"))
+    }
+    
+    fn load_setup(&self) -> Result<SetupConfig> {
+        let content = std::fs::read_to_string(self.ctx.paths().setup_file())
+            .context(ConfigError::new("Failed to read setup.json"))?;
+        serde_json::from_str(&content).context(ConfigError::new("Invalid setup.json"))
+    }
+    
+    /// The account at `index`; refused when it is disabled, so its forks
+    /// are never deployed to.
     fn get_account_by_index(&self, index: usize) -> Result<account::AccountInfo> {
-        let mut mgr = account::AccountManager::new(&self.config_dir.join("cache"));
-        mgr.load_tokens(&self.config_dir.join("tokens.txt"))?;
-        mgr.get_account(index).cloned().ok_or_else(|| anyhow::anyhow!("Account not found"))
+        let account = self.ctx.accounts()?
+            .get_account(index)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Account not found"))?;
+        ensure_enabled(&account)?;
+        Ok(account)
+    }
+}
+
+fn ensure_enabled(account: &account::AccountInfo) -> Result<()> {
+    if !account.enabled {
+        bail!(ConfigError::new(format!(
+            "@{} is disabled; enable it with `token enable {}` to deploy to its forks",
+            account.username, account.username
+        )));
+    }
+    Ok(())
+}
+
+/// What `Deployer::deploy_env_file` wrote to one repo.
+#[derive(Debug, Default)]
+pub struct EnvDeployment {
+    pub secrets: Vec<(String, SecretWriteResult)>,
+    /// Variable names and whether each was created
+    pub variables: Vec<(String, bool)>,
+}
+
+impl EnvDeployment {
+    /// Partial when a secret could not be verified.
+    pub fn exit_code(&self) -> u8 {
+        if self.secrets.iter().any(|(_, r)| *r == SecretWriteResult::Unverified) {
+            exit_code::PARTIAL
+        } else {
+            exit_code::SUCCESS
+        }
+    }
+    
+    pub fn print(&self, repo: &str) {
+        for (name, result) in &self.secrets {
+            let icon = if *result == SecretWriteResult::Unverified { "⚠️ " } else { "✅" };
+            println!("{} {} secret {}: {}", icon, repo, name, result);
+        }
+        for (name, created) in &self.variables {
+            println!("✅ {} variable {}: {}", repo, name, if *created { "created" } else { "updated" });
+        }
+    }
+}
+
+/// One fork in a `DeployReport`: set, or why it failed.
+#[derive(Debug)]
+pub struct DeployOutcome {
+    pub repo: String,
+    pub result: std::result::Result<(), String>,
+}
+
+/// One fork in a `DriftReport`: how its workflows compared and whether
+/// they were deployed again, or why it could not be checked.
+#[derive(Debug)]
+pub struct DriftOutcome {
+    pub repo: String,
+    pub result: std::result::Result<(workflow::Drift, bool), String>,
+}
+
+/// What `Deployer::check_workflow_drift` or `deploy_drifted` found across
+/// the chain.
+#[derive(Debug, Default)]
+pub struct DriftReport {
+    pub outcomes: Vec<DriftOutcome>,
+}
+
+impl DriftReport {
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.result.is_err()).count()
+    }
+    
+    /// Forks whose workflows are missing or differ and were left so.
+    pub fn drifted(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|o| matches!(o.result, Ok((drift, false)) if drift != workflow::Drift::Same))
+            .count()
+    }
+    
+    /// `PARTIAL` when some forks could not be checked or deployed,
+    /// `FAILURE` when some are left drifted.
+    pub fn exit_code(&self) -> u8 {
+        if self.failed() > 0 {
+            exit_code::PARTIAL
+        } else if self.drifted() > 0 {
+            exit_code::FAILURE
+        } else {
+            exit_code::SUCCESS
+        }
+    }
+    
+    pub fn print(&self) {
+        println!("{:<40} Workflows", "Fork");
+        println!("{}", "─".repeat(60));
+        for outcome in &self.outcomes {
+            match &outcome.result {
+                Ok((workflow::Drift::Same, _)) => println!("{:<40} ✅ up to date", outcome.repo),
+                Ok((drift, true)) => println!("{:<40} 🚀 was {}, deployed", outcome.repo, drift),
+                Ok((drift, false)) => println!("{:<40} ⚠️  {}", outcome.repo, drift),
+                Err(e) => println!("{:<40} ❌ {}", outcome.repo, e),
+            }
+        }
+        
+        let redeployed = self.outcomes.iter().filter(|o| matches!(o.result, Ok((_, true)))).count();
+        println!(
+            "\n{} up to date, {} deployed, {} drifted, {} failed{}",
+            self.outcomes.len() - self.failed() - redeployed - self.drifted(),
+            redeployed,
+            self.drifted(),
+            self.failed(),
+            if self.drifted() > 0 { "; run deploy --only-drifted to update them" } else { "" }
+        );
+    }
+}
+
+/// What `Deployer::set_all_secrets` did across the active forks.
+#[derive(Debug, Default)]
+pub struct DeployReport {
+    pub outcomes: Vec<DeployOutcome>,
+    /// Set by an earlier run that did not finish
+    pub resumed: Vec<String>,
+}
+
+impl DeployReport {
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.result.is_err()).count()
     }
+    
+    pub fn exit_code(&self) -> u8 {
+        exit_code::for_counts(self.outcomes.len() - self.failed(), self.outcomes.len())
+    }
+    
+    pub fn print(&self) {
+        println!("{:<40} Secrets", "Fork");
+        println!("{}", "─".repeat(60));
+        for repo in &self.resumed {
+            println!("{:<40} ✅ set by the earlier run", repo);
+        }
+        for outcome in &self.outcomes {
+            match &outcome.result {
+                Ok(()) => println!("{:<40} ✅ set", outcome.repo),
+                Err(e) => println!("{:<40} ❌ {}", outcome.repo, e),
+            }
+        }
+        
+        println!(
+            "\n{} forks set, {} failed{}",
+            self.outcomes.len() - self.failed() + self.resumed.len(),
+            self.failed(),
+            if self.failed() > 0 { "; run deploy again to retry the failed ones" } else { "" }
+        );
+    }
+}
+
+/// One fork in a `ScrubReport`: the secrets deleted, or why it failed.
+#[derive(Debug)]
+pub struct ScrubOutcome {
+    pub repo: String,
+    pub result: std::result::Result<Vec<String>, String>,
+}
+
+/// What `Deployer::scrub_secrets` did across the chain.
+#[derive(Debug, Default)]
+pub struct ScrubReport {
+    pub outcomes: Vec<ScrubOutcome>,
+    /// Chain forks no longer on GitHub
+    pub gone: Vec<String>,
+}
+
+impl ScrubReport {
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.result.is_err()).count()
+    }
+    
+    pub fn exit_code(&self) -> u8 {
+        exit_code::for_counts(self.outcomes.len() - self.failed(), self.outcomes.len())
+    }
+    
+    pub fn print(&self) {
+        for outcome in &self.outcomes {
+            match &outcome.result {
+                Ok(deleted) if deleted.is_empty() => println!("✅ {}: no Nexus secrets", outcome.repo),
+                Ok(deleted) => println!("🗑️  {}: deleted {}", outcome.repo, deleted.join(", ")),
+                Err(e) => println!("❌ {}: {}", outcome.repo, e),
+            }
+        }
+        
+        println!(
+            "\n{} forks scrubbed, {} failed, {} no longer on GitHub",
+            self.outcomes.len() - self.failed(),
+            self.failed(),
+            self.gone.len()
+        );
+    }
+}
+
+/// Creates or updates each variable on `repo`.
+fn set_variables(variables_mgr: &VariablesManager, repo: &str, variables: &BTreeMap<String, String>) -> Result<()> {
+    for (name, value) in variables {
+        variables_mgr.set_variable(repo, name, value)?;
+    }
+    
+    if !variables.is_empty() {
+        info!("{} variables set for {}", variables.len(), repo);
+    }
+    Ok(())
+}
+
+/// Progress of an unfinished `set_all_secrets`, tied to the node ids and
+/// wallets it was deploying.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeployCheckpoint {
+    pub fingerprint: String,
+    pub completed: Vec<String>,
+}
+
+impl DeployCheckpoint {
+    /// The saved checkpoint, or an empty one when there is none or it was
+    /// written for different nodes.txt/wallets.txt contents.
+    pub fn load(path: &Path, config: &NexusConfig) -> Self {
+        let fingerprint = fingerprint(config);
+        
+        let saved: Option<Self> = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+        
+        match saved {
+            Some(saved) if saved.fingerprint == fingerprint => saved,
+            Some(_) => {
+                warn!("Ignoring {}: nodes or wallets changed since it was written", path.display());
+                Self { fingerprint, completed: Vec::new() }
+            }
+            None => Self { fingerprint, completed: Vec::new() },
+        }
+    }
+    
+    fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+fn fingerprint(config: &NexusConfig) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(config.node_ids.join("\n"));
+    hasher.update("\0");
+    hasher.update(config.wallets.join("\n"));
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 #[derive(serde::Deserialize)]
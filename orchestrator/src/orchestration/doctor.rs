@@ -0,0 +1,563 @@
+// src/orchestration/doctor.rs - Environment and configuration diagnostics
+
+use chrono::{DateTime, Utc};
+use colored::*;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use crate::core::paths::Paths;
+use crate::core::proxy::{ProxyConfig, ProxyManager};
+use crate::core::settings::{self, Settings};
+use crate::core::state::{OrchestratorState, STATE_SCHEMA_VERSION};
+use crate::monitor::alert::AlertConfig;
+use crate::orchestration::secrets::SecretsManifest;
+use crate::orchestration::validate::{ConfigValidator, ValidationOptions};
+use crate::utils::{exit_code, platform};
+
+const TEXT_FILES: [&str; 4] = ["tokens.txt", "proxies.txt", "nodes.txt", "wallets.txt"];
+const CLOCK_SKEW_WARN_SECS: i64 = 30;
+const CLOCK_SKEW_FAIL_SECS: i64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    pub fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Pass, detail: detail.into(), hint: None }
+    }
+    
+    pub fn warn(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Warn, detail: detail.into(), hint: Some(hint.into()) }
+    }
+    
+    pub fn fail(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Fail, detail: detail.into(), hint: Some(hint.into()) }
+    }
+    
+    pub fn print(&self) {
+        let label = match self.status {
+            CheckStatus::Pass => "PASS".green(),
+            CheckStatus::Warn => "WARN".yellow(),
+            CheckStatus::Fail => "FAIL".red(),
+        };
+        
+        println!("[{}] {:<16} {}", label, self.name, self.detail);
+        if let Some(hint) = &self.hint {
+            println!("       {} {}", "→".bright_black(), hint.bright_black());
+        }
+    }
+}
+
+/// First line of `<binary> --version`, or None if it cannot be run.
+fn binary_version(binary: &Path) -> Option<String> {
+    let output = std::process::Command::new(binary).arg("--version").output().ok()?;
+    
+    if !output.status.success() {
+        return None;
+    }
+    
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|l| l.trim().to_string())
+}
+
+/// `required` binaries fail when missing, optional ones only warn.
+pub fn check_binary(binary: &str, required: bool, purpose: &str) -> CheckResult {
+    let name = format!("binary:{}", binary);
+    
+    let found = platform::find_executable(binary)
+        .and_then(|path| binary_version(&path).map(|version| (path, version)));
+    
+    match found {
+        Some((path, version)) => CheckResult::pass(&name, format!("{} ({})", version, path.display())),
+        None if required => CheckResult::fail(
+            &name,
+            format!("{} not found on PATH (needed for {})", binary, purpose),
+            format!("Install {} and make sure it is on PATH", binary),
+        ),
+        None => CheckResult::warn(
+            &name,
+            format!("{} not found on PATH ({} will not work)", binary, purpose),
+            format!("Install {} if you need {}", binary, purpose),
+        ),
+    }
+}
+
+pub fn check_crypto() -> CheckResult {
+    match crate::utils::crypto::init_crypto() {
+        Ok(()) => CheckResult::pass("crypto", "libsodium initialized"),
+        Err(e) => CheckResult::fail(
+            "crypto",
+            e.to_string(),
+            "Install libsodium (apt install libsodium-dev / brew install libsodium) and rebuild",
+        ),
+    }
+}
+
+pub fn check_config_dir(config_dir: &Path) -> CheckResult {
+    if !config_dir.is_dir() {
+        return CheckResult::fail(
+            "config-dir",
+            format!("{} does not exist", config_dir.display()),
+            "Create it or pass --config-dir / set NEXUS_CONFIG_DIR",
+        );
+    }
+    
+    let missing: Vec<&str> = TEXT_FILES
+        .iter()
+        .copied()
+        .filter(|f| !config_dir.join(f).is_file())
+        .collect();
+    
+    if !missing.is_empty() {
+        return CheckResult::fail(
+            "config-dir",
+            format!("missing {}", missing.join(", ")),
+            format!("Create the missing files in {}", config_dir.display()),
+        );
+    }
+    
+    let paths = Paths::new(config_dir);
+    let cache_dir = paths.cache_dir();
+    let probe = cache_dir.join(".doctor-probe");
+    let writable = paths.ensure_cache_dir().is_ok() && fs::write(&probe, b"").is_ok();
+    fs::remove_file(&probe).ok();
+    
+    if !writable {
+        return CheckResult::fail(
+            "config-dir",
+            format!("{} is not writable", cache_dir.display()),
+            "Fix the directory owner or permissions",
+        );
+    }
+    
+    CheckResult::pass("config-dir", format!("{} complete", config_dir.display()))
+}
+
+/// tokens.txt holds PATs: it should not be readable by group or others.
+#[cfg(unix)]
+pub fn check_permissions(config_dir: &Path) -> CheckResult {
+    use std::os::unix::fs::PermissionsExt;
+    
+    let tokens = config_dir.join("tokens.txt");
+    let mode = match fs::metadata(&tokens) {
+        Ok(metadata) => metadata.permissions().mode() & 0o777,
+        Err(_) => return CheckResult::warn("permissions", "tokens.txt not found", "See config-dir"),
+    };
+    
+    if mode & 0o077 != 0 {
+        CheckResult::warn(
+            "permissions",
+            format!("tokens.txt is mode {:o}, readable by other users", mode),
+            format!("chmod 600 {}", tokens.display()),
+        )
+    } else {
+        CheckResult::pass("permissions", format!("tokens.txt is mode {:o}", mode))
+    }
+}
+
+#[cfg(not(unix))]
+pub fn check_permissions(_config_dir: &Path) -> CheckResult {
+    CheckResult::pass("permissions", "not checked on this platform")
+}
+
+/// Windows line endings and UTF-8 byte order marks in the line-based files.
+/// A BOM glues itself to the first token, which is then silently ignored.
+pub fn check_line_endings(config_dir: &Path) -> CheckResult {
+    let mut bom = Vec::new();
+    let mut crlf = Vec::new();
+    
+    for file in TEXT_FILES {
+        if let Ok(bytes) = fs::read(config_dir.join(file)) {
+            if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+                bom.push(file);
+            }
+            if bytes.windows(2).any(|w| w == b"\r\n") {
+                crlf.push(file);
+            }
+        }
+    }
+    
+    if !bom.is_empty() {
+        CheckResult::fail(
+            "line-endings",
+            format!("byte order mark in {}", bom.join(", ")),
+            "Re-save as UTF-8 without BOM",
+        )
+    } else if !crlf.is_empty() {
+        CheckResult::warn(
+            "line-endings",
+            format!("Windows (CRLF) line endings in {}", crlf.join(", ")),
+            "Convert with dos2unix or sed -i 's/\\r$//' <file>",
+        )
+    } else {
+        CheckResult::pass("line-endings", "LF only")
+    }
+}
+
+/// Offline validation of tokens/proxies/nodes plus every optional config file.
+pub fn check_config_files(config_dir: &Path) -> Vec<CheckResult> {
+    let report = ConfigValidator::new(config_dir).run(ValidationOptions {
+        tokens: true,
+        proxies: true,
+        nodes: true,
+        online: false,
+        stale_only: false,
+    });
+    
+    let mut results: Vec<CheckResult> = report
+        .sections
+        .iter()
+        .map(|section| {
+            let name = format!("parse:{}", section.name);
+            match section.errors.first() {
+                Some(error) => CheckResult::fail(
+                    &name,
+                    format!("{} ({} errors)", error, section.errors.len()),
+                    "Run `validate` for every error with line numbers",
+                ),
+                None => CheckResult::pass(&name, format!("{} entries", section.checked)),
+            }
+        })
+        .collect();
+    
+    results.push(check_optional_file(config_dir, "setup.json", |content| {
+        serde_json::from_str::<serde_json::Value>(content).map(|_| ()).map_err(|e| e.to_string())
+    }));
+    results.push(check_optional_file(config_dir, settings::SETTINGS_FILE, |_| {
+        Settings::load(config_dir).map(|_| ()).map_err(|e| e.to_string())
+    }));
+    results.push(check_optional_file(config_dir, "alerts.json", |content| {
+        serde_json::from_str::<AlertConfig>(content).map(|_| ()).map_err(|e| e.to_string())
+    }));
+    results.push(check_optional_file(config_dir, "secrets.toml", |_| {
+        SecretsManifest::load(&config_dir.join("secrets.toml")).map(|_| ()).map_err(|e| format!("{:#}", e))
+    }));
+    
+    results
+}
+
+fn check_optional_file<F>(config_dir: &Path, file: &str, parse: F) -> CheckResult
+where
+    F: FnOnce(&str) -> std::result::Result<(), String>,
+{
+    let name = format!("parse:{}", file);
+    
+    match fs::read_to_string(config_dir.join(file)) {
+        Err(_) => CheckResult::pass(&name, "not present (optional)"),
+        Ok(content) => match parse(&content) {
+            Ok(()) => CheckResult::pass(&name, "ok"),
+            Err(e) => CheckResult::fail(&name, e, format!("Fix or remove {}", file)),
+        },
+    }
+}
+
+pub fn check_state_schema(config_dir: &Path) -> CheckResult {
+    let state_file = Paths::new(config_dir).state_file();
+    
+    let content = match fs::read_to_string(&state_file) {
+        Ok(content) => content,
+        Err(_) => return CheckResult::pass("state", "no state yet"),
+    };
+    
+    let state: OrchestratorState = match serde_json::from_str(&content) {
+        Ok(state) => state,
+        Err(e) => {
+            return CheckResult::fail(
+                "state",
+                format!("{} is not valid state: {}", state_file.display(), e),
+                "Restore it from a backup or delete it to start a fresh chain",
+            )
+        }
+    };
+    
+    match state.schema_version {
+        v if v == STATE_SCHEMA_VERSION => CheckResult::pass("state", format!("schema v{}", v)),
+        0 => CheckResult::warn(
+            "state",
+            "written before schema versioning",
+            "It is upgraded on the next save; no action needed",
+        ),
+        v if v > STATE_SCHEMA_VERSION => CheckResult::fail(
+            "state",
+            format!("schema v{} is newer than this build (v{})", v, STATE_SCHEMA_VERSION),
+            "Run `self-update` or rebuild from the latest source",
+        ),
+        v => CheckResult::warn(
+            "state",
+            format!("schema v{} is older than v{}", v, STATE_SCHEMA_VERSION),
+            "It is upgraded on the next save",
+        ),
+    }
+}
+
+/// Compares GitHub's `Date` header with the local clock.
+pub fn evaluate_clock_skew(date_header: &str, now: DateTime<Utc>) -> CheckResult {
+    let server = match DateTime::parse_from_rfc2822(date_header) {
+        Ok(server) => server.with_timezone(&Utc),
+        Err(_) => {
+            return CheckResult::warn(
+                "clock",
+                format!("unparseable Date header '{}'", date_header),
+                "Retry later",
+            )
+        }
+    };
+    
+    let skew = (now - server).num_seconds();
+    
+    if skew.abs() >= CLOCK_SKEW_FAIL_SECS {
+        CheckResult::fail(
+            "clock",
+            format!("local clock is {}s off GitHub", skew),
+            "Enable NTP (timedatectl set-ntp true); large skew breaks token and billing checks",
+        )
+    } else if skew.abs() >= CLOCK_SKEW_WARN_SECS {
+        CheckResult::warn("clock", format!("local clock is {}s off GitHub", skew), "Enable NTP time sync")
+    } else {
+        CheckResult::pass("clock", format!("{}s off GitHub", skew))
+    }
+}
+
+fn api_url() -> String {
+    let host = &settings::get().github.host;
+    
+    if host == "github.com" {
+        "https://api.github.com/".to_string()
+    } else {
+        format!("https://{}/api/v3/", host)
+    }
+}
+
+pub fn check_clock_skew() -> CheckResult {
+    let url = api_url();
+    let response = reqwest::blocking::Client::new()
+        .head(&url)
+        .header("User-Agent", "nexus-orchestrator")
+        .send();
+    
+    match response {
+        Ok(response) => match response.headers().get("date").and_then(|d| d.to_str().ok()) {
+            Some(date) => evaluate_clock_skew(date, Utc::now()),
+            None => CheckResult::warn("clock", "no Date header from GitHub", "Retry later"),
+        },
+        Err(e) => CheckResult::warn(
+            "clock",
+            format!("cannot reach {}: {}", url, e),
+            "Check direct internet access; forks use proxies, but this check does not",
+        ),
+    }
+}
+
+pub fn check_first_proxy(config_dir: &Path) -> CheckResult {
+    let paths = Paths::new(config_dir);
+    let first = fs::read_to_string(paths.proxies_file())
+        .ok()
+        .and_then(|c| c.lines().map(str::trim).find(|l| !l.is_empty()).map(str::to_string));
+    
+    let proxy = match first.as_deref().map(ProxyConfig::from_url) {
+        None => return CheckResult::warn("proxy", "no proxies configured", "Add proxies to proxies.txt"),
+        Some(Err(e)) => return CheckResult::fail("proxy", format!("first proxy: {}", e), "Fix the first entry in proxies.txt"),
+        Some(Ok(proxy)) => proxy,
+    };
+    
+    match ProxyManager::new(&paths).test_proxy(&proxy) {
+        Ok(true) => CheckResult::pass("proxy", format!("{}:{} reachable", proxy.host, proxy.port)),
+        Ok(false) => CheckResult::fail(
+            "proxy",
+            format!("{}:{} did not return HTTP 200", proxy.host, proxy.port),
+            "Check the proxy credentials and that it allows HTTPS CONNECT",
+        ),
+        Err(e) => CheckResult::fail("proxy", e.to_string(), "Install curl; the proxy test shells out to it"),
+    }
+}
+
+pub fn run_checks(config_dir: &Path) -> Vec<CheckResult> {
+    let mut results = vec![
+        check_binary("gh", true, "all GitHub API calls"),
+        check_binary("git", settings::get().github.deploy_with_git, "github.deploy_with_git"),
+        check_binary("curl", false, "proxy tests and alerts"),
+        check_crypto(),
+        check_config_dir(config_dir),
+        check_permissions(config_dir),
+        check_line_endings(config_dir),
+    ];
+    
+    results.extend(check_config_files(config_dir));
+    results.push(check_state_schema(config_dir));
+    results.push(check_clock_skew());
+    results.push(check_first_proxy(config_dir));
+    
+    results
+}
+
+/// Runs every check, prints one line each and returns the exit code
+/// (1 if any check failed; warnings alone still exit 0).
+pub fn run_doctor(config_dir: &Path) -> u8 {
+    let results = run_checks(config_dir);
+    
+    for result in &results {
+        result.print();
+    }
+    
+    let failed = results.iter().filter(|r| r.status == CheckStatus::Fail).count();
+    let warned = results.iter().filter(|r| r.status == CheckStatus::Warn).count();
+    
+    println!("\n{} checks: {} failed, {} warnings", results.len(), failed, warned);
+    
+    if failed > 0 {
+        exit_code::FAILURE
+    } else {
+        exit_code::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use tempfile::tempdir;
+    
+    const WALLET: &str = "0x8254a986319461bf29ae35940a96786e507ad9ac";
+    
+    fn write_config(dir: &Path) {
+        fs::write(dir.join("tokens.txt"), "ghp_aaaaaaaaaaaa\n").unwrap();
+        fs::write(dir.join("proxies.txt"), "http://u:p@1.2.3.4:8080\n").unwrap();
+        fs::write(dir.join("nodes.txt"), "node_abc123\n").unwrap();
+        fs::write(dir.join("wallets.txt"), format!("{}\n", WALLET)).unwrap();
+    }
+    
+    #[test]
+    fn test_missing_binary() {
+        let required = check_binary("nexus-doctor-no-such-binary", true, "testing");
+        assert_eq!(required.status, CheckStatus::Fail);
+        assert!(required.hint.is_some());
+        
+        let optional = check_binary("nexus-doctor-no-such-binary", false, "testing");
+        assert_eq!(optional.status, CheckStatus::Warn);
+    }
+    
+    #[test]
+    fn test_config_dir() {
+        let temp_dir = tempdir().unwrap();
+        assert_eq!(check_config_dir(&temp_dir.path().join("nope")).status, CheckStatus::Fail);
+        
+        let result = check_config_dir(temp_dir.path());
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.detail.contains("tokens.txt"));
+        
+        write_config(temp_dir.path());
+        assert_eq!(check_config_dir(temp_dir.path()).status, CheckStatus::Pass);
+        assert!(!temp_dir.path().join("cache").join(".doctor-probe").exists());
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let temp_dir = tempdir().unwrap();
+        write_config(temp_dir.path());
+        let tokens = temp_dir.path().join("tokens.txt");
+        
+        fs::set_permissions(&tokens, fs::Permissions::from_mode(0o644)).unwrap();
+        let result = check_permissions(temp_dir.path());
+        assert_eq!(result.status, CheckStatus::Warn);
+        assert!(result.hint.unwrap().starts_with("chmod 600"));
+        
+        fs::set_permissions(&tokens, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(check_permissions(temp_dir.path()).status, CheckStatus::Pass);
+    }
+    
+    #[test]
+    fn test_line_endings() {
+        let temp_dir = tempdir().unwrap();
+        write_config(temp_dir.path());
+        assert_eq!(check_line_endings(temp_dir.path()).status, CheckStatus::Pass);
+        
+        fs::write(temp_dir.path().join("tokens.txt"), "ghp_aaaaaaaaaaaa\r\nghp_bbbbbbbbbbbb\r\n").unwrap();
+        let result = check_line_endings(temp_dir.path());
+        assert_eq!(result.status, CheckStatus::Warn);
+        assert!(result.detail.contains("tokens.txt"));
+        
+        fs::write(temp_dir.path().join("nodes.txt"), b"\xEF\xBB\xBFnode_abc123\n").unwrap();
+        assert_eq!(check_line_endings(temp_dir.path()).status, CheckStatus::Fail);
+    }
+    
+    #[test]
+    fn test_config_files() {
+        let temp_dir = tempdir().unwrap();
+        write_config(temp_dir.path());
+        
+        let results = check_config_files(temp_dir.path());
+        assert!(results.iter().all(|r| r.status == CheckStatus::Pass), "{:?}", results);
+        
+        fs::write(temp_dir.path().join("setup.json"), "{ not json").unwrap();
+        fs::write(temp_dir.path().join("proxies.txt"), "socks://nope\n").unwrap();
+        
+        let failed: Vec<String> = check_config_files(temp_dir.path())
+            .into_iter()
+            .filter(|r| r.status == CheckStatus::Fail)
+            .map(|r| r.name)
+            .collect();
+        assert_eq!(failed, vec!["parse:proxies", "parse:setup.json"]);
+    }
+    
+    #[test]
+    fn test_state_schema() {
+        let temp_dir = tempdir().unwrap();
+        let cache_dir = temp_dir.path().join("cache");
+        assert_eq!(check_state_schema(temp_dir.path()).status, CheckStatus::Pass);
+        
+        fs::create_dir_all(&cache_dir).unwrap();
+        let legacy = r#"{"fork_chain":[],"current_active_index":0,"total_accounts":2,"last_rotation":null}"#;
+        fs::write(cache_dir.join("active.json"), legacy).unwrap();
+        assert_eq!(check_state_schema(temp_dir.path()).status, CheckStatus::Warn);
+        
+        let future = legacy.replace("{\"fork_chain\"", "{\"schema_version\":99,\"fork_chain\"");
+        fs::write(cache_dir.join("active.json"), future).unwrap();
+        assert_eq!(check_state_schema(temp_dir.path()).status, CheckStatus::Fail);
+        
+        let current = serde_json::to_string(&OrchestratorState::default()).unwrap();
+        fs::write(cache_dir.join("active.json"), current).unwrap();
+        assert_eq!(check_state_schema(temp_dir.path()).status, CheckStatus::Pass);
+        
+        fs::write(cache_dir.join("active.json"), "garbage").unwrap();
+        assert_eq!(check_state_schema(temp_dir.path()).status, CheckStatus::Fail);
+    }
+    
+    #[test]
+    fn test_clock_skew() {
+        let server = Utc::now();
+        let header = server.to_rfc2822();
+        
+        assert_eq!(evaluate_clock_skew(&header, server + Duration::seconds(3)).status, CheckStatus::Pass);
+        assert_eq!(evaluate_clock_skew(&header, server - Duration::seconds(90)).status, CheckStatus::Warn);
+        assert_eq!(evaluate_clock_skew(&header, server + Duration::minutes(10)).status, CheckStatus::Fail);
+        assert_eq!(evaluate_clock_skew("yesterday", server).status, CheckStatus::Warn);
+    }
+    
+    #[test]
+    fn test_first_proxy_without_proxies() {
+        let temp_dir = tempdir().unwrap();
+        assert_eq!(check_first_proxy(temp_dir.path()).status, CheckStatus::Warn);
+        
+        fs::write(temp_dir.path().join("proxies.txt"), "\nnot-a-proxy\n").unwrap();
+        assert_eq!(check_first_proxy(temp_dir.path()).status, CheckStatus::Fail);
+    }
+}
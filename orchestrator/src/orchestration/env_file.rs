@@ -0,0 +1,193 @@
+// src/orchestration/env_file.rs - secrets.env: extra secrets and variables per deployment
+
+use anyhow::{Result, Context, bail};
+use std::fs;
+use std::path::Path;
+use crate::orchestration::secrets::validate_secret_name;
+use crate::utils::exit_code::ConfigError;
+use crate::utils::platform;
+
+/// Names with this prefix are variables, pushed without it.
+pub const VARIABLE_PREFIX: &str = "VAR_";
+
+/// A comment line that makes the next entry a variable.
+pub const VARIABLE_ANNOTATION: &str = "#@variable";
+
+/// Where an entry of secrets.env goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvKind {
+    Secret,
+    Variable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvEntry {
+    pub name: String,
+    pub value: String,
+    pub kind: EnvKind,
+    /// 1-based line in the file
+    pub line: usize,
+}
+
+pub fn load(path: &Path) -> Result<Vec<EnvEntry>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| ConfigError::new(format!("Failed to read {}", path.display())))?;
+    parse(&content).with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// Entries of a dotenv file: `NAME=value` lines, optionally `export`ed,
+/// with `#` comments. Values may be 'single quoted' (taken as is) or
+/// "double quoted" (with \n, \t, \r, \" and \\ escapes, so one line can
+/// hold a multiline value). Entries are secrets unless named `VAR_*` or
+/// preceded by `#@variable`. Every malformed line is reported, by number.
+pub fn parse(content: &str) -> Result<Vec<EnvEntry>> {
+    let mut entries: Vec<EnvEntry> = Vec::new();
+    let mut errors = Vec::new();
+    let mut variable_next = false;
+    
+    for (line_no, line) in platform::config_lines(content) {
+        if line == VARIABLE_ANNOTATION {
+            variable_next = true;
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        
+        let annotated = std::mem::take(&mut variable_next);
+        let (name, value) = match parse_line(line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.push(format!("line {}: {}", line_no, e));
+                continue;
+            }
+        };
+        let (name, kind) = match name.strip_prefix(VARIABLE_PREFIX) {
+            Some(name) => (name, EnvKind::Variable),
+            None if annotated => (name, EnvKind::Variable),
+            None => (name, EnvKind::Secret),
+        };
+        
+        if let Err(e) = validate_secret_name(name) {
+            errors.push(format!("line {}: {}", line_no, e));
+        } else if value.is_empty() {
+            errors.push(format!("line {}: {} has an empty value", line_no, name));
+        } else if let Some(first) = entries.iter().find(|e| e.name == name && e.kind == kind) {
+            errors.push(format!("line {}: {} is already set on line {}", line_no, name, first.line));
+        } else {
+            entries.push(EnvEntry { name: name.to_string(), value, kind, line: line_no });
+        }
+    }
+    
+    if !errors.is_empty() {
+        bail!(ConfigError::new(format!("Malformed lines:\n  {}", errors.join("\n  "))));
+    }
+    Ok(entries)
+}
+
+/// Name and unquoted value of one `NAME=value` line. Errors never quote
+/// the value, which may be a secret.
+fn parse_line(line: &str) -> std::result::Result<(&str, String), String> {
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let (name, raw) = line.split_once('=').ok_or("expected NAME=value")?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("missing name before '='".to_string());
+    }
+    let raw = raw.trim();
+    
+    let value = if let Some(quoted) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.chars();
+        loop {
+            match chars.next() {
+                None => return Err(format!("{}: unterminated double quote", name)),
+                Some('"') => break,
+                Some('\\') => value.push(match chars.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some(c @ ('"' | '\\')) => c,
+                    Some(c) => return Err(format!("{}: unknown escape \\{}", name, c)),
+                    None => return Err(format!("{}: unterminated double quote", name)),
+                }),
+                Some(c) => value.push(c),
+            }
+        }
+        only_comment_after(name, chars.as_str())?;
+        value
+    } else if let Some(quoted) = raw.strip_prefix('\'') {
+        let end = quoted.find('\'').ok_or_else(|| format!("{}: unterminated single quote", name))?;
+        only_comment_after(name, &quoted[end + 1..])?;
+        quoted[..end].to_string()
+    } else {
+        // Unquoted, a comment needs a space before the '#'
+        raw.split_once(" #").map_or(raw, |(value, _)| value).trim_end().to_string()
+    };
+    
+    Ok((name, value))
+}
+
+fn only_comment_after(name: &str, rest: &str) -> std::result::Result<(), String> {
+    let rest = rest.trim();
+    if rest.is_empty() || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(format!("{}: text after the closing quote", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn entry(name: &str, value: &str, kind: EnvKind, line: usize) -> EnvEntry {
+        EnvEntry { name: name.to_string(), value: value.to_string(), kind, line }
+    }
+    
+    #[test]
+    fn test_parse_quoting_and_classification() {
+        let content = "\
+# Extra settings
+API_KEY=abc123  # inline comment
+export RPC_URL='https://rpc.example/#frag'
+CERT=\"line one\\nline \\\"two\\\"\"
+
+#@variable
+MAX_PARALLEL=20
+VAR_REGION=eu-west
+";
+        assert_eq!(parse(content).unwrap(), vec![
+            entry("API_KEY", "abc123", EnvKind::Secret, 2),
+            entry("RPC_URL", "https://rpc.example/#frag", EnvKind::Secret, 3),
+            entry("CERT", "line one\nline \"two\"", EnvKind::Secret, 4),
+            entry("MAX_PARALLEL", "20", EnvKind::Variable, 7),
+            entry("REGION", "eu-west", EnvKind::Variable, 8),
+        ]);
+    }
+    
+    #[test]
+    fn test_every_malformed_line_is_reported() {
+        let content = "\
+GOOD=1
+no equals sign
+BAD=\"unterminated
+QUOTE='x' trailing
+GITHUB_TOKEN=x
+EMPTY=
+GOOD=2
+";
+        let err = parse(content).unwrap_err().to_string();
+        for expected in [
+            "line 2: expected NAME=value",
+            "line 3: BAD: unterminated double quote",
+            "line 4: QUOTE: text after the closing quote",
+            "line 5: Invalid secret name 'GITHUB_TOKEN'",
+            "line 6: EMPTY has an empty value",
+            "line 7: GOOD is already set on line 1",
+        ] {
+            assert!(err.contains(expected), "{} missing from:\n{}", expected, err);
+        }
+        assert!(!err.contains("trailing"));
+    }
+}
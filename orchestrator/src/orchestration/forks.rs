@@ -0,0 +1,448 @@
+// src/orchestration/forks.rs - Fork chain operations for the CLI
+
+use anyhow::{Result, Context, bail};
+use std::fs;
+use std::sync::Arc;
+use crate::app::AppContext;
+use crate::core::{
+    account::{AccountInfo, AccountManager},
+    state::{ForkChainNode, ForkStatus},
+    Paths, StateManager,
+};
+use crate::github::fork::{ChainDrift, CleanupOptions, CleanupSummary, OrphanFork};
+use crate::github::{ForkApi, ForkManager, GitHubClient};
+use crate::utils::cancel::{self, CancelToken};
+use crate::utils::delay::{self, Sleeper};
+use crate::utils::exit_code::{self, ConfigError};
+
+/// Prints what `ForkCommand::reconcile` found.
+pub fn print_drift(drift: &[ChainDrift]) {
+    if drift.is_empty() {
+        println!("✅ Fork chain matches GitHub");
+    }
+    for item in drift {
+        println!("⚠️  {}", item);
+    }
+}
+
+/// Prints what `ForkCommand::find_orphans` found.
+pub fn print_orphans(parent: &str, orphans: &[OrphanFork]) {
+    if orphans.is_empty() {
+        println!("✅ No orphan forks of {}", parent);
+        return;
+    }
+    println!("{} orphan forks of {}:", orphans.len(), parent);
+    for orphan in orphans {
+        println!("  🗑️  {}", orphan);
+    }
+}
+
+/// `owner/name` of the main repo from setup.json, the default parent of
+/// the fork chain.
+pub fn main_repo(paths: &Paths) -> Result<String> {
+    let content = fs::read_to_string(paths.setup_file())
+        .context(ConfigError::new("setup.json is missing; pass --parent <owner/name>"))?;
+    let setup: serde_json::Value = serde_json::from_str(&content)
+        .context(ConfigError::new("Invalid setup.json"))?;
+    
+    match (setup["main_repo_owner"].as_str(), setup["main_repo_name"].as_str()) {
+        (Some(owner), Some(name)) => Ok(format!("{}/{}", owner, name)),
+        _ => bail!(ConfigError::new(
+            "setup.json has no main_repo_owner/main_repo_name; pass --parent <owner/name>"
+        )),
+    }
+}
+
+/// `fork_organization` from setup.json: the organization every account
+/// forks into. None without setup.json or the key.
+pub fn fork_organization(paths: &Paths) -> Result<Option<String>> {
+    let content = match fs::read_to_string(paths.setup_file()) {
+        Ok(content) => content,
+        Err(_) => return Ok(None),
+    };
+    let setup: serde_json::Value = serde_json::from_str(&content)
+        .context(ConfigError::new("Invalid setup.json"))?;
+    
+    Ok(setup["fork_organization"]
+        .as_str()
+        .map(str::trim)
+        .filter(|org| !org.is_empty())
+        .map(|org| org.to_string()))
+}
+
+/// `deploy_branch` from setup.json: the branch workflows are deployed to
+/// and dispatched on. None without setup.json or the key, for the repo's
+/// default branch.
+pub fn deploy_branch(paths: &Paths) -> Result<Option<String>> {
+    let content = match fs::read_to_string(paths.setup_file()) {
+        Ok(content) => content,
+        Err(_) => return Ok(None),
+    };
+    let setup: serde_json::Value = serde_json::from_str(&content)
+        .context(ConfigError::new("Invalid setup.json"))?;
+    
+    let branch = match setup["deploy_branch"].as_str().map(str::trim) {
+        None | Some("") => return Ok(None),
+        Some(branch) => branch,
+    };
+    if branch.starts_with('-') || branch.contains("..") || branch.chars().any(|c| c.is_whitespace() || "~^:?*[\\".contains(c)) {
+        bail!(ConfigError::new(format!("setup.json deploy_branch '{}' is not a valid branch name", branch)));
+    }
+    Ok(Some(branch.to_string()))
+}
+
+/// Builds the GitHub client for an account and its proxy URL.
+pub type ClientFactory = Box<dyn Fn(&AccountInfo, Option<String>) -> Box<dyn ForkApi>>;
+
+/// One printed result line per fork.
+#[derive(Debug)]
+pub struct ForkResult {
+    pub repo: String,
+    pub outcome: std::result::Result<String, String>,
+}
+
+impl ForkResult {
+    fn from_result(repo: &str, result: Result<String>) -> Self {
+        Self {
+            repo: repo.to_string(),
+            outcome: result.map_err(|e| format!("{:#}", e)),
+        }
+    }
+    
+    pub fn print(&self) {
+        match &self.outcome {
+            Ok(message) => println!("✅ {}: {}", self.repo, message),
+            Err(e) => println!("❌ {}: {}", self.repo, e),
+        }
+    }
+}
+
+pub fn results_exit_code(results: &[ForkResult]) -> u8 {
+    let succeeded = results.iter().filter(|r| r.outcome.is_ok()).count();
+    exit_code::for_counts(succeeded, results.len())
+}
+
+/// Parses `active,exhausted` into statuses.
+pub fn parse_status_filter(raw: &str) -> Result<Vec<ForkStatus>> {
+    raw.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| match s.to_ascii_lowercase().as_str() {
+            "active" => Ok(ForkStatus::Active),
+            "exhausted" => Ok(ForkStatus::Exhausted),
+            "disabled" => Ok(ForkStatus::Disabled),
+            "source" => Ok(ForkStatus::Source),
+            "deleted" => Ok(ForkStatus::Deleted),
+            other => bail!(ConfigError::new(format!(
+                "Unknown fork status '{}', use active, exhausted, disabled, source or deleted",
+                other
+            ))),
+        })
+        .collect()
+}
+
+pub struct ForkCommand {
+    ctx: Arc<AppContext>,
+    client_factory: ClientFactory,
+    sleeper: Arc<dyn Sleeper>,
+    cancel: CancelToken,
+}
+
+impl ForkCommand {
+    pub fn new(ctx: Arc<AppContext>) -> Self {
+        Self {
+            ctx,
+            client_factory: Box::new(|account, proxy| {
+                Box::new(GitHubClient::new(account.token.clone(), proxy))
+            }),
+            sleeper: delay::thread_sleeper(),
+            cancel: cancel::ctrl_c(),
+        }
+    }
+    
+    pub fn with_client_factory(mut self, client_factory: ClientFactory) -> Self {
+        self.client_factory = client_factory;
+        self
+    }
+    
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+    
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+    
+    fn state_manager(&self) -> Arc<StateManager> {
+        self.ctx.state()
+    }
+    
+    fn fork_manager(&self) -> ForkManager {
+        ForkManager::new(self.state_manager())
+            .with_sleeper(self.sleeper.clone())
+            .with_cancel(self.cancel.clone())
+    }
+    
+    fn accounts(&self) -> Result<Arc<AccountManager>> {
+        self.ctx.accounts()
+    }
+    
+    fn client_for(&self, account: &AccountInfo) -> Result<Box<dyn ForkApi>> {
+        let proxy = self.ctx.proxies()?.proxy_for(account)?;
+        Ok((self.client_factory)(account, proxy))
+    }
+    
+    /// Chain nodes, optionally only those with one of `statuses`.
+    pub fn list(&self, statuses: &[ForkStatus]) -> Result<Vec<ForkChainNode>> {
+        let state = self.state_manager().load_state()?;
+        
+        Ok(state.fork_chain
+            .into_iter()
+            .filter(|n| statuses.is_empty() || statuses.contains(&n.status))
+            .collect())
+    }
+    
+    /// Forks the parent (default: the newest chain fork, else the main repo
+    /// from setup.json) into the account (default: first one without a fork).
+    pub fn create(&self, account: Option<&str>, parent: Option<&str>) -> Result<ForkResult> {
+        let state_mgr = self.state_manager();
+        let state = state_mgr.load_state()?;
+        let account_mgr = self.accounts()?;
+        let fork_mgr = self.fork_manager();
+        
+        let account = match account {
+            Some(username) => account_mgr
+                .get_account_by_username(username)
+                .with_context(|| ConfigError::new(format!("Unknown account '{}'", username)))?,
+            None => account_mgr
+                .iter()
+                .find(|(index, _)| !state.fork_chain.iter().any(|n| {
+                    n.pat_index == *index
+                        && !matches!(n.status, ForkStatus::Disabled | ForkStatus::Deleted)
+                }))
+                .map(|(_, account)| account)
+                .context(ConfigError::new(
+                    "Every account already has a fork in the chain; pass --account <username>"
+                ))?,
+        };
+        
+        let parent = match parent {
+            Some(parent) => parent.to_string(),
+            None => match fork_mgr.get_next_parent_repo(&state) {
+                Some(parent) => parent,
+                None => self.main_repo()?,
+            },
+        };
+        
+        let client = self.client_for(account)?;
+        let result = fork_mgr
+            .create_fork_chain(state, account, &parent, client.as_ref())
+            .map(|(_, repo)| repo);
+        
+        Ok(match result {
+            Ok(repo) => ForkResult::from_result(&repo, Ok(format!("forked from {} as @{}", parent, account.username))),
+            Err(e) => ForkResult::from_result(&format!("{}/?", account.username), Err(e)),
+        })
+    }
+    
+    fn main_repo(&self) -> Result<String> {
+        main_repo(self.ctx.paths())
+    }
+    
+    /// Runs the disable-cancel-delete-verify sequence. Unless `keep_state`,
+    /// the chain node is marked Deleted.
+    pub fn delete(&self, repo: &str, keep_state: bool, account: Option<&str>) -> Result<ForkResult> {
+        let state_mgr = self.state_manager();
+        let state = state_mgr.load_state()?;
+        let account_mgr = self.accounts()?;
+        let fork_mgr = self.fork_manager();
+        
+        let account = account_mgr.resolve_for_repo(&state, repo, account)?;
+        let client = self.client_for(account)?;
+        let in_chain = state.fork_chain.iter().any(|n| n.repo.eq_ignore_ascii_case(repo));
+        
+        let result = if in_chain && !keep_state {
+            fork_mgr
+                .delete_fork(state, repo, client.as_ref())
+                .map(|_| "deleted, marked Deleted in state".to_string())
+        } else {
+            fork_mgr
+                .delete_repo_verified(repo, client.as_ref())
+                .map(|_| "deleted, state unchanged".to_string())
+        };
+        
+        Ok(ForkResult::from_result(repo, result))
+    }
+    
+    /// Chain against GitHub's fork lists, see `ForkManager::reconcile`. Asks
+    /// with the first account's token; every account's owner counts as managed.
+    pub fn reconcile(&self) -> Result<Vec<ChainDrift>> {
+        let state = self.state_manager().load_state()?;
+        let account_mgr = self.accounts()?;
+        let accounts = account_mgr.get_all_accounts();
+        let account = accounts
+            .first()
+            .context(ConfigError::new("No accounts in tokens.txt to query GitHub with"))?;
+        let owners: Vec<&str> = accounts.iter().map(|a| a.owner()).collect();
+        let client = self.client_for(account)?;
+        
+        self.fork_manager().reconcile(&state, self.main_repo().ok().as_deref(), &owners, client.as_ref())
+    }
+    
+    /// Forks of `parent` (default: the main repo from setup.json) on the
+    /// managed accounts, or only on `account`, that the chain does not track.
+    /// GitHub is asked with the first account's token.
+    pub fn find_orphans(&self, parent: Option<&str>, account: Option<&str>) -> Result<(String, Vec<OrphanFork>)> {
+        let parent = match parent {
+            Some(parent) => parent.to_string(),
+            None => self.main_repo()?,
+        };
+        let state = self.state_manager().load_state()?;
+        let account_mgr = self.accounts()?;
+        let accounts = account_mgr.get_all_accounts();
+        let lister = accounts
+            .first()
+            .context(ConfigError::new("No accounts in tokens.txt to query GitHub with"))?;
+        let owners: Vec<&str> = accounts
+            .iter()
+            .filter(|a| match account {
+                Some(name) => a.username.eq_ignore_ascii_case(name) || a.owner().eq_ignore_ascii_case(name),
+                None => true,
+            })
+            .map(|a| a.owner())
+            .collect();
+        let client = self.client_for(lister)?;
+        
+        let orphans = self.fork_manager().orphan_forks(&state, &parent, &owners, client.as_ref())?;
+        Ok((parent, orphans))
+    }
+    
+    /// Deletes `orphans` of `parent`, each with its owner's token.
+    pub fn delete_orphans(&self, parent: &str, orphans: &[OrphanFork]) -> Result<Vec<ForkResult>> {
+        let account_mgr = self.accounts()?;
+        let accounts = account_mgr.get_all_accounts();
+        let fork_mgr = self.fork_manager();
+        
+        Ok(orphans
+            .iter()
+            .map(|orphan| {
+                let result = accounts
+                    .iter()
+                    .find(|a| a.owner().eq_ignore_ascii_case(&orphan.owner))
+                    .with_context(|| format!("No token for @{}", orphan.owner))
+                    .and_then(|account| self.client_for(account))
+                    .and_then(|client| fork_mgr.delete_orphan(orphan, parent, client.as_ref()))
+                    .map(|_| "orphan fork deleted".to_string());
+                ForkResult::from_result(&orphan.repo, result)
+            })
+            .collect())
+    }
+    
+    /// Deletes or archives the forks matched by `options`. Empty selections
+    /// never read tokens.txt; dry runs use it to check each token's rights
+    /// when it is there and list the forks unchecked when it is not.
+    pub fn cleanup(&self, options: &CleanupOptions) -> Result<CleanupSummary> {
+        let state = self.state_manager().load_state()?;
+        let fork_mgr = self.fork_manager();
+        
+        let account_mgr = if options.filter.select(&state, chrono::Utc::now()).is_empty() {
+            None
+        } else if options.dry_run {
+            self.accounts().ok()
+        } else {
+            // Loaded up front so a proxy config error stops the run instead of each deletion
+            self.ctx.proxies()?;
+            Some(self.accounts()?)
+        };
+        
+        let (_, summary) = fork_mgr.cleanup(state, options, &|node| {
+            let account = account_mgr
+                .as_deref()
+                .and_then(|accounts| accounts.get_account(node.pat_index))
+                .with_context(|| format!("no token for account index {}", node.pat_index))?;
+            self.client_for(account)
+        })?;
+        
+        Ok(summary)
+    }
+    
+    /// Syncs `repo`, every active fork, or the current active fork with
+    /// upstream. A fork that conflicts fails on its own; the others still sync.
+    pub fn sync(&self, repo: Option<&str>, all_active: bool, account: Option<&str>) -> Result<Vec<ForkResult>> {
+        let state_mgr = self.state_manager();
+        let state = state_mgr.load_state()?;
+        let account_mgr = self.accounts()?;
+        let fork_mgr = self.fork_manager();
+        
+        let repos: Vec<String> = match repo {
+            Some(repo) => vec![repo.to_string()],
+            None if all_active => state.fork_chain
+                .iter()
+                .filter(|n| n.status == ForkStatus::Active)
+                .map(|n| n.repo.clone())
+                .collect(),
+            None => state_mgr.get_active_fork(&state).map(|n| n.repo.clone()).into_iter().collect(),
+        };
+        
+        if repos.is_empty() {
+            bail!(ConfigError::new("No active fork to sync; pass a repo or create the fork chain first"));
+        }
+        
+        Ok(repos
+            .iter()
+            .map(|repo| {
+                let result = account_mgr
+                    .resolve_for_repo(&state, repo, account)
+                    .and_then(|account| {
+                        let client = self.client_for(account)?;
+                        fork_mgr.sync_with_upstream(repo, client.as_ref())
+                    })
+                    .and_then(|sync| {
+                        if sync.is_conflict() {
+                            bail!(sync.to_string());
+                        }
+                        Ok(sync.to_string())
+                    });
+                ForkResult::from_result(repo, result)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_status_filter() {
+        assert_eq!(
+            parse_status_filter("active, Exhausted").unwrap(),
+            vec![ForkStatus::Active, ForkStatus::Exhausted]
+        );
+        assert!(parse_status_filter("").unwrap().is_empty());
+        
+        let err = parse_status_filter("active,gone").unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+    }
+    
+    #[test]
+    fn test_deploy_branch_from_setup() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let paths = Paths::new(temp_dir.path());
+        assert_eq!(deploy_branch(&paths).unwrap(), None);
+        
+        let write = |branch: &str| {
+            fs::write(paths.setup_file(), serde_json::json!({ "deploy_branch": branch }).to_string()).unwrap();
+        };
+        write(" nexus-deploy ");
+        assert_eq!(deploy_branch(&paths).unwrap().as_deref(), Some("nexus-deploy"));
+        write("");
+        assert_eq!(deploy_branch(&paths).unwrap(), None);
+        
+        write("bad branch");
+        let err = deploy_branch(&paths).unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+    }
+}
@@ -1,6 +1,16 @@
 // src/orchestration/mod.rs
 pub mod deploy;
+pub mod doctor;
+pub mod env_file;
+pub mod forks;
 pub mod rotate;
+pub mod secrets;
+pub mod self_update;
+pub mod sync;
+pub mod tokens;
+pub mod trigger;
+pub mod validate;
 
 pub use deploy::Deployer;
 pub use rotate::Rotator;
+pub use trigger::Trigger;
@@ -1,21 +1,73 @@
 // Update imports at top of src/orchestration/rotate.rs
-use anyhow::{Result, Context};
-use std::path::PathBuf;
-use log::{info, warn};
-use crate::core::{account, billing, proxy, state, StateManager};
-use crate::github::{fork, GitHubClient};
+use anyhow::{Result, Context, bail};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use log::{info, warn, error};
+use crate::app::AppContext;
+use crate::core::{billing, settings, state};
+use crate::core::account::{self, AccountInfo, AccountManager};
+use crate::core::settings::RotationStrategy;
+use crate::github::api::GitHubError;
+use crate::github::{api, fork, ApiFactory, GitHubClient, SecretsManager, WorkflowController};
+use crate::monitor::events::EventLog;
+use crate::orchestration::{sync, Deployer};
+use crate::utils::cancel::{self, CancelToken, Cancelled};
+use crate::utils::delay::{self, Sleeper};
+use crate::utils::exit_code::ConfigError;
+
+/// How far back the event log is searched for the last healing attempt
+const HEAL_EVENT_WINDOW: usize = 1000;
 
 pub struct Rotator {
-    config_dir: PathBuf,
+    ctx: Arc<AppContext>,
+    client_factory: ApiFactory,
+    /// Redeploys a fork that had to be re-created
+    deployer: Deployer,
+    sleeper: Arc<dyn Sleeper>,
+    cancel: CancelToken,
 }
 
 impl Rotator {
-    pub fn new(config_dir: PathBuf) -> Self {
-        Self { config_dir }
+    pub fn new(ctx: Arc<AppContext>) -> Self {
+        Self {
+            deployer: Deployer::new(ctx.clone()),
+            ctx,
+            client_factory: Box::new(|account, proxy| {
+                Box::new(GitHubClient::new(account.token.clone(), proxy).with_timeout(api::DEFAULT_TIMEOUT))
+            }),
+            sleeper: delay::thread_sleeper(),
+            cancel: cancel::ctrl_c(),
+        }
+    }
+    
+    pub fn with_client_factory(mut self, client_factory: ApiFactory) -> Self {
+        self.client_factory = client_factory;
+        self
+    }
+    
+    pub fn with_deployer(mut self, deployer: Deployer) -> Self {
+        self.deployer = deployer;
+        self
     }
     
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+    
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+    
+    /// Rotates away from the active fork once its account is exhausted, or
+    /// its token is rejected with 401, which quarantines the account.
+    /// Disabling the old fork and recording the rotation form one step: after
+    /// Ctrl-C it is either done completely or not started.
     pub fn check_and_rotate(&self) -> Result<bool> {
-        let state_mgr = StateManager::new(&self.config_dir)?;
+        let _interruptible = cancel::interruptible();
+        let state_mgr = self.ctx.state();
         let mut state = state_mgr.load_state()?;
         
         let active_fork = match state_mgr.get_active_fork(&state) {
@@ -23,44 +75,446 @@ impl Rotator {
             None => return Ok(false),
         };
         
-        let account = self.load_account(active_fork.pat_index)?;
-        let proxy = self.load_proxy(&account.token)?;
+        let accounts = self.ctx.accounts()?;
+        let account = accounts
+            .get_account(active_fork.pat_index)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Account not found"))?;
+        let proxy = self.ctx.proxy_url(&account.token)?;
         
-        let billing_mon = billing::BillingMonitor::default();
-        let billing = billing_mon.check_billing(&account.username, &account.token, proxy.as_deref())?;
+        let client = (self.client_factory)(&account, proxy);
         
-        if billing.is_exhausted {
-            info!("Account {} exhausted, rotating", account.username);
-            
-            let client = GitHubClient::new(account.token.clone(), proxy);
-            let fork_mgr = fork::ForkManager::new(state_mgr.clone());
-            
-            fork_mgr.disable_fork_workflow(&active_fork.repo, "nexus.yml", &client)?;
+        // A fork deleted behind our back is re-created before anything else;
+        // a revoked token fails every call from here on
+        let mut rejected = None;
+        match client.check_repo_exists(&active_fork.repo) {
+            Ok(false) => {
+                let active_fork = active_fork.clone();
+                self.heal_active_fork(state, &active_fork, &account, client.as_ref())?;
+                return Ok(false);
+            }
+            Ok(true) => {}
+            Err(e) if GitHubError::is_unauthorized(&e) => rejected = Some(e),
+            Err(e) => warn!("Could not check that {} still exists: {:#}", active_fork.repo, e),
+        }
+        
+        let settings = settings::get();
+        if rejected.is_none() {
+            let billing_mon = billing::BillingMonitor::from_config(&self.ctx.paths().thresholds_file())?;
+            let billing = billing_mon.check_billing(&account, client.as_ref())?;
+            if billing.permission_missing {
+                warn!(
+                    "Cannot tell whether {} is exhausted: its fine-grained token lacks billing (Plan: read) permission",
+                    account.display_name()
+                );
+            }
+            if !billing.is_exhausted {
+                return Ok(false);
+            }
+        }
+        
+        let events = EventLog::new(state_mgr.paths());
+        let why = if rejected.is_some() { "revoked" } else { "exhausted" };
+        
+        if self.cancel.is_cancelled() {
+            events.record(
+                "cancelled",
+                &format!("Rotation away from {} @{} not started", why, account.username),
+                Some(&active_fork.repo),
+            );
+            return Err(Cancelled.into());
+        }
+        
+        if rejected.is_none() {
+            match accounts.revalidate(&account, client.as_ref()) {
+                Err(e) if GitHubError::is_unauthorized(&e) => rejected = Some(e),
+                result => result?,
+            }
+        }
+        if let Some(e) = &rejected {
+            self.quarantine(&account, e, &events, &active_fork.repo)?;
+        }
+        let rotates_to = |index: usize| accounts.is_enabled(index) && !(rejected.is_some() && index == account.index);
+        
+        // Checked before the old fork is touched, so it keeps running
+        if !accounts.iter().any(|(index, _)| rotates_to(index)) {
+            bail!(ConfigError::new("Every account is disabled; enable one with `token enable <username>`"));
+        }
+        
+        info!("Account {} {}, rotating", account.username, why);
+        
+        let fork_mgr = fork::ForkManager::new(state_mgr.clone()).with_sleeper(self.sleeper.clone());
+        
+        // A rejected token can no longer stop its own fork
+        if rejected.is_none() {
+            fork_mgr.disable_fork_workflows(&active_fork.repo, client.as_ref())?;
             
-            std::thread::sleep(std::time::Duration::from_secs(5));
+            // A disabled workflow does not stop the runs already going
+            match WorkflowController::cancel_active_runs(&active_fork.repo, client.as_ref(), self.sleeper.as_ref()) {
+                Ok(running) if running.is_empty() => {}
+                Ok(running) => warn!("Runs {:?} in {} still going after cancelling them", running, active_fork.repo),
+                Err(e) => warn!("Could not cancel the runs in {}: {:#}", active_fork.repo, e),
+            }
+        }
+        
+        state = state_mgr.update_fork_status(state, active_fork.pat_index, state::ForkStatus::Exhausted)?;
+        state.total_accounts = accounts.total_accounts();
+        
+        let next_index = next_account_index(
+            &state,
+            active_fork.pat_index,
+            settings.rotation.strategy,
+            rotates_to,
+            |index| accounts.priority(index),
+        )?;
+        state.current_active_index = next_index;
+        state_mgr.save_state(&state)?;
+        
+        info!("Rotated to account index {}", next_index);
+        events.record(
+            "rotation",
+            &format!("@{} {}, rotated to account index {}", account.username, why, next_index),
+            Some(&active_fork.repo),
+        );
             
-            state = state_mgr.update_fork_status(state, active_fork.pat_index, state::ForkStatus::Exhausted)?;
+        if !self.cancel.is_cancelled() {
+            self.prepare_next_fork(&state, next_index, &fork_mgr, &events);
+        }
+        
+        if !self.cancel.is_cancelled() {
+            self.sleeper.sleep("rotation_cooldown", Duration::from_secs(settings.rotation.cooldown_secs));
+        }
+        Ok(true)
+    }
+    
+    /// Sets aside the account whose token GitHub rejected with 401, see
+    /// `AccountManager::quarantine`, and alerts.
+    fn quarantine(&self, account: &AccountInfo, error: &anyhow::Error, events: &EventLog, repo: &str) -> Result<()> {
+        let paths = self.ctx.paths();
+        let mut accounts = AccountManager::new(paths);
+        accounts.load_tokens(&paths.tokens_file())?;
+        paths.ensure_cache_dir()?;
+        accounts.quarantine(account.index)?;
+        self.ctx.reload_accounts();
+        
+        let message = format!(
+            "Token #{} ({}) rejected by GitHub: {:#}; account quarantined until `token enable {}`",
+            account.index,
+            account.display_name(),
+            error,
+            account.index
+        );
+        error!("{}", message);
+        events.record("quarantine", &message, Some(repo));
+        self.alert(&message);
+        Ok(())
+    }
+    
+    /// Re-forks the active fork after it disappeared from GitHub and
+    /// redeploys it. Not tried again for `rotation.heal_cooldown_minutes`
+    /// after an attempt on the same repo, so a blocked account does not loop.
+    /// Every attempt is alerted.
+    fn heal_active_fork(
+        &self,
+        state: state::OrchestratorState,
+        node: &state::ForkChainNode,
+        account: &AccountInfo,
+        client: &dyn api::GitHubApi,
+    ) -> Result<()> {
+        let state_mgr = self.ctx.state();
+        let events = EventLog::new(state_mgr.paths());
+        
+        let cooldown = chrono::Duration::minutes(settings::get().rotation.heal_cooldown_minutes);
+        if let Some(last) = last_heal(&events, &node.repo) {
+            if Utc::now() - last < cooldown {
+                bail!(
+                    "{} is missing again; it was re-forked at {}, next attempt after {}",
+                    node.repo,
+                    last.format("%Y-%m-%d %H:%M UTC"),
+                    (last + cooldown).format("%Y-%m-%d %H:%M UTC"),
+                );
+            }
+        }
+        
+        events.record(
+            "heal",
+            &format!("{} missing on GitHub, re-forking it for @{}", node.repo, account.username),
+            Some(&node.repo),
+        );
+        
+        let fork_mgr = fork::ForkManager::new(state_mgr.clone()).with_sleeper(self.sleeper.clone());
+        let fork = match fork_mgr.heal(state, &node.repo, account, client) {
+            Ok((_, fork)) => fork,
+            Err(e) => {
+                let message = format!("Re-forking missing {} failed: {:#}", node.repo, e);
+                events.record("heal", &message, Some(&node.repo));
+                self.alert(&message);
+                return Err(e);
+            }
+        };
+        
+        // The new fork is in the chain either way; secrets can be set again
+        // with `deploy secrets`
+        let message = match self.deployer.redeploy_fork(&fork, node.pat_index) {
+            Ok(()) => format!("{} was missing on GitHub; re-forked as {} and redeployed", node.repo, fork),
+            Err(e) => format!(
+                "{} was missing on GitHub; re-forked as {} but redeploying failed: {:#}",
+                node.repo, fork, e
+            ),
+        };
+        warn!("{}", message);
+        events.record("heal", &message, Some(&fork));
+        self.alert(&message);
+        Ok(())
+    }
+    
+    /// Alerts on runs stuck in the queue of any Active fork; a failed check
+    /// is logged.
+    fn check_stuck_runs(&self) {
+        let checked = self.ctx.health().and_then(|health| {
+            health.check_stuck_runs(
+                &*self.ctx.accounts()?,
+                &*self.ctx.proxies()?,
+                &self.client_factory,
+                self.ctx.alerts()?,
+            )
+        });
+        if let Err(e) = checked {
+            warn!("Stuck run check failed: {:#}", e);
+        }
+    }
+    
+    /// Keep-alive pass over the chain, see `HealthMonitor::keep_alive`;
+    /// failures are logged.
+    fn keep_alive(&self) {
+        let done = WorkflowController::load_managed().and_then(|controller| {
+            self.ctx.health()?.keep_alive(&*self.ctx.accounts()?, &*self.ctx.proxies()?, &self.client_factory, &controller)
+        });
+        if let Err(e) = done {
+            warn!("Keep-alive pass failed: {:#}", e);
+        }
+    }
+    
+    /// Flags tokens about to expire, see `HealthMonitor::check_token_expiry`.
+    fn check_token_expiry(&self) {
+        let checked = self.ctx.health().and_then(|health| {
+            health.check_token_expiry(self.ctx.accounts()?.get_all_accounts(), self.ctx.alerts()?);
+            Ok(())
+        });
+        if let Err(e) = checked {
+            warn!("Token expiry check failed: {:#}", e);
+        }
+    }
+    
+    /// Best effort: a failed alert is logged, never raised.
+    fn alert(&self, message: &str) {
+        if let Err(e) = self.ctx.alerts().and_then(|alerts| alerts.send_alert(message)) {
+            warn!("Could not send alert: {:#}", e);
+        }
+    }
+    
+    /// Readies the fork that takes over: merges upstream into it, so it
+    /// starts on the parent's current workflow, and writes the vaulted
+    /// secrets to it. Failures and conflicts are logged; they do not undo
+    /// the rotation.
+    fn prepare_next_fork(
+        &self,
+        state: &state::OrchestratorState,
+        next_index: usize,
+        fork_mgr: &fork::ForkManager,
+        events: &EventLog,
+    ) {
+        let node = match state.fork_chain
+            .iter()
+            .find(|n| n.pat_index == next_index && n.status == state::ForkStatus::Active)
+        {
+            Some(n) => n,
+            None => return,
+        };
+        
+        let client = self.ctx.accounts().and_then(|accounts| {
+            let account = accounts
+                .get_account(next_index)
+                .with_context(|| format!("No token for account index {}", next_index))?;
+            Ok((self.client_factory)(account, self.ctx.proxy_url(&account.token)?))
+        });
+        let client = match client {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Could not prepare {}: {:#}", node.repo, e);
+                return;
+            }
+        };
+        
+        match fork_mgr.sync_with_upstream(&node.repo, client.as_ref()) {
+            Ok(sync) if sync.is_conflict() => {
+                warn!("{}: {}", node.repo, sync);
+                events.record("sync", &sync.to_string(), Some(&node.repo));
+            }
+            Ok(sync) => info!("{}: {}", node.repo, sync),
+            Err(e) => warn!("Could not sync {} with upstream: {:#}", node.repo, e),
+        }
+        
+        let replayed = self.ctx.vault().and_then(|vault| {
+            SecretsManager::new(client)
+                .with_sleeper(self.sleeper.clone())
+                .with_vault(vault)
+                .replay_secrets(&node.repo)
+        });
+        match replayed {
+            Ok(secrets) if secrets.is_empty() => {}
+            Ok(secrets) => info!("{} vaulted secrets written to {}", secrets.len(), node.repo),
+            Err(e) => {
+                let message = format!("Could not write the vaulted secrets to {}: {:#}", node.repo, e);
+                warn!("{}", message);
+                events.record("vault", &message, Some(&node.repo));
+            }
+        }
+    }
+    
+    /// Syncs the chain and checks every `interval` until Ctrl-C; failures are
+    /// logged and retried. Returns once cancelled.
+    pub fn watch(&self, interval: Duration) -> Result<()> {
+        let _interruptible = cancel::interruptible();
+        
+        loop {
+            if !self.cancel.is_cancelled() {
+                if let Err(e) = sync::sync_chain(&self.ctx, &self.client_factory) {
+                    warn!("State sync failed: {}", e);
+                }
+                self.check_stuck_runs();
+                self.keep_alive();
+                self.check_token_expiry();
+            }
             
-            let next_index = (active_fork.pat_index + 1) % state.total_accounts;
-            state.current_active_index = next_index;
-            state_mgr.save_state(&state)?;
+            match self.check_and_rotate() {
+                Ok(true) => info!("Account rotated"),
+                Ok(false) => info!("No rotation needed"),
+                Err(e) if cancel::is_cancelled(&e) => break,
+                Err(e) => error!("Rotation check failed: {}", e),
+            }
             
-            info!("Rotated to account index {}", next_index);
-            return Ok(true);
+            if !self.cancel.sleep(self.sleeper.as_ref(), "watch_interval", interval) {
+                break;
+            }
         }
         
-        Ok(false)
+        EventLog::new(self.ctx.state().paths()).record("cancelled", "Watch stopped by Ctrl-C", None);
+        Ok(())
     }
+}
+
+/// When the last healing attempt on `repo` started, from the event log.
+fn last_heal(events: &EventLog, repo: &str) -> Option<DateTime<Utc>> {
+    events
+        .read_recent(HEAL_EVENT_WINDOW)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|e| e.kind == "heal" && e.repo.as_deref().is_some_and(|r| r.eq_ignore_ascii_case(repo)))
+        .map(|e| e.timestamp)
+        .max()
+}
+
+/// Returns the next enabled account after `current`, per the configured
+/// strategy. Once accounts differ in `priority`, the highest-priority one
+/// not exhausted wins whatever the strategy; see `account::rotation_order`.
+/// Fails when every account is disabled.
+pub fn next_account_index(
+    state: &state::OrchestratorState,
+    current: usize,
+    strategy: RotationStrategy,
+    enabled: impl Fn(usize) -> bool,
+    priority: impl Fn(usize) -> u8,
+) -> Result<usize> {
+    let order = account::rotation_order(state.total_accounts, current, enabled, &priority);
+    let first = *order.first().context(ConfigError::new(
+        "Every account is disabled; enable one with `token enable <username>`"
+    ))?;
+    let prioritized = order.iter().any(|&index| priority(index) != priority(first));
     
-    fn load_account(&self, index: usize) -> Result<account::AccountInfo> {
-        let mut mgr = account::AccountManager::new(&self.config_dir.join("cache"));
-        mgr.load_tokens(&self.config_dir.join("tokens.txt"))?;
-        mgr.get_account(index).cloned().ok_or_else(|| anyhow::anyhow!("Account not found"))
+    Ok(match strategy {
+        RotationStrategy::Sequential if !prioritized => first,
+        _ => order
+            .into_iter()
+            .find(|&index| !state.is_exhausted(index))
+            .unwrap_or(first),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn node(pat_index: usize, status: state::ForkStatus) -> state::ForkChainNode {
+        state::ForkChainNode {
+            pat_index,
+            username: format!("user_{}", pat_index),
+            owner_type: crate::core::account::OwnerType::User,
+            repo: format!("user_{}/nexus-runner", pat_index),
+            parent: None,
+            billing_used: 0.0,
+            status,
+            created_at: chrono::Utc::now(),
+            last_updated: chrono::Utc::now(),
+            last_run: None,
+        }
     }
     
-    fn load_proxy(&self, token: &str) -> Result<Option<String>> {
-        let mut proxy_mgr = proxy::ProxyManager::new(&self.config_dir.join("cache"));
-        proxy_mgr.load_cache().ok();
-        Ok(proxy_mgr.get_proxy(token).map(|p| p.to_curl_format()))
+    #[test]
+    fn test_next_account_index_strategies() {
+        let state = state::OrchestratorState {
+            fork_chain: vec![
+                node(0, state::ForkStatus::Active),
+                node(1, state::ForkStatus::Exhausted),
+                node(2, state::ForkStatus::Active),
+            ],
+            total_accounts: 4,
+            ..Default::default()
+        };
+        
+        let all = |_| true;
+        let none = |_| 0;
+        assert_eq!(next_account_index(&state, 0, RotationStrategy::Sequential, all, none).unwrap(), 1);
+        assert_eq!(next_account_index(&state, 0, RotationStrategy::SkipExhausted, all, none).unwrap(), 2);
+        assert_eq!(next_account_index(&state, 3, RotationStrategy::SkipExhausted, all, none).unwrap(), 0);
+        
+        // Disabled accounts are passed by, whatever the strategy
+        assert_eq!(next_account_index(&state, 0, RotationStrategy::Sequential, |index| index != 1, none).unwrap(), 2);
+        assert_eq!(next_account_index(&state, 0, RotationStrategy::SkipExhausted, |index| index != 2, none).unwrap(), 3);
+        
+        let err = next_account_index(&state, 0, RotationStrategy::Sequential, |_| false, none).unwrap_err();
+        assert_eq!(crate::utils::exit_code::for_error(&err), crate::utils::exit_code::CONFIG_ERROR);
+    }
+    
+    #[test]
+    fn test_next_account_index_priorities() {
+        let state = state::OrchestratorState {
+            fork_chain: vec![
+                node(0, state::ForkStatus::Exhausted),
+                node(1, state::ForkStatus::Active),
+                node(3, state::ForkStatus::Exhausted),
+            ],
+            total_accounts: 5,
+            ..Default::default()
+        };
+        let priorities = [0, 0, 1, 9, 1];
+        let priority = |index: usize| priorities[index];
+        
+        // 3 ranks highest but is exhausted; 2 and 4 tie, 2 comes first after 0
+        assert_eq!(account::rotation_order(5, 0, |_| true, priority), vec![3, 2, 4, 1, 0]);
+        for strategy in [RotationStrategy::Sequential, RotationStrategy::SkipExhausted] {
+            assert_eq!(next_account_index(&state, 0, strategy, |_| true, priority).unwrap(), 2);
+            // Ties go in index order from the account after the current one
+            assert_eq!(next_account_index(&state, 2, strategy, |_| true, priority).unwrap(), 4);
+            assert_eq!(next_account_index(&state, 0, strategy, |index| index != 2, priority).unwrap(), 4);
+            // Below every prioritized account, index order again
+            assert_eq!(next_account_index(&state, 0, strategy, |index| index < 2, priority).unwrap(), 1);
+        }
+        
+        // Every enabled account exhausted: the highest-priority one
+        assert_eq!(
+            next_account_index(&state, 1, RotationStrategy::SkipExhausted, |index| index != 1 && index != 2 && index != 4, priority).unwrap(),
+            3
+        );
     }
 }
@@ -0,0 +1,430 @@
+// src/orchestration/secrets.rs - CLI-driven secret management
+
+use anyhow::{Result, Context, bail};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use log::info;
+use crate::app::AppContext;
+use crate::core::{account::AccountInfo, state};
+use crate::github::{api, GitHubClient, SecretStore, SecretWriteResult, SecretsManager, VariablesManager};
+use crate::utils::exit_code::{self, ConfigError};
+use crate::utils::pool::WorkerPool;
+
+/// Where a secret value comes from. Values are never taken from a bare
+/// command line argument so they stay out of shell history.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecretSource {
+    File(PathBuf),
+    Stdin,
+    Env(String),
+}
+
+impl SecretSource {
+    pub fn read(&self) -> Result<String> {
+        self.read_with(&|var| std::env::var(var).ok())
+    }
+    
+    /// `read`, looking environment variables up through `env`.
+    fn read_with(&self, env: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+        let value = match self {
+            SecretSource::File(path) => fs::read_to_string(path)
+                .with_context(|| ConfigError::new(format!("Failed to read secret value from {}", path.display())))?,
+            SecretSource::Stdin => {
+                let mut value = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut value)
+                    .context("Failed to read secret value from stdin")?;
+                value
+            }
+            SecretSource::Env(var) => env(var)
+                .with_context(|| ConfigError::new(format!("Environment variable {} is not set", var)))?,
+        };
+        
+        // Files and heredocs usually end with a newline that is not part of the value
+        let value = value.strip_suffix('\n').unwrap_or(&value);
+        let value = value.strip_suffix('\r').unwrap_or(value);
+        
+        if value.is_empty() {
+            bail!(ConfigError::new("Secret value is empty"));
+        }
+        
+        Ok(value.to_string())
+    }
+}
+
+/// GitHub secret names: letters, digits and underscores, not starting with a
+/// digit or the reserved GITHUB_ prefix.
+pub fn validate_secret_name(name: &str) -> Result<()> {
+    let valid_chars = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let starts_with_digit = name.chars().next().is_none_or(|c| c.is_ascii_digit());
+    
+    if !valid_chars || starts_with_digit {
+        bail!(ConfigError::new(format!(
+            "Invalid secret name '{}': use letters, digits and underscores, not starting with a digit",
+            name
+        )));
+    }
+    
+    if name.to_ascii_uppercase().starts_with("GITHUB_") {
+        bail!(ConfigError::new(format!("Invalid secret name '{}': the GITHUB_ prefix is reserved", name)));
+    }
+    
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestEntry {
+    file: Option<PathBuf>,
+    env: Option<String>,
+}
+
+/// `secrets.toml`:
+///
+/// ```toml
+/// repos = ["alice/nexus-runner"]   # optional, defaults to the active fork
+///
+/// [secrets.NEXUS_NODE_IDS]
+/// file = "nodes.txt"               # relative to the manifest
+///
+/// [secrets.EXTRA_TOKEN]
+/// env = "EXTRA_TOKEN"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecretsManifest {
+    #[serde(default)]
+    pub repos: Vec<String>,
+    #[serde(default)]
+    secrets: BTreeMap<String, ManifestEntry>,
+    #[serde(skip)]
+    base_dir: PathBuf,
+}
+
+impl SecretsManifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| ConfigError::new(format!("Failed to read manifest {}", path.display())))?;
+        
+        let mut manifest: SecretsManifest = toml::from_str(&content)
+            .with_context(|| ConfigError::new(format!("Invalid manifest {}", path.display())))?;
+        
+        manifest.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(manifest)
+    }
+    
+    /// Resolves every entry to a `(name, value)` pair. Fails before anything is written.
+    pub fn resolve(&self) -> Result<Vec<(String, String)>> {
+        self.resolve_with(&|var| std::env::var(var).ok())
+    }
+    
+    fn resolve_with(&self, env: &dyn Fn(&str) -> Option<String>) -> Result<Vec<(String, String)>> {
+        if self.secrets.is_empty() {
+            bail!(ConfigError::new("Manifest defines no secrets"));
+        }
+        
+        let mut values = Vec::new();
+        
+        for (name, entry) in &self.secrets {
+            validate_secret_name(name)?;
+            
+            let source = match (&entry.file, &entry.env) {
+                (Some(file), None) => SecretSource::File(self.base_dir.join(file)),
+                (None, Some(var)) => SecretSource::Env(var.clone()),
+                _ => bail!(ConfigError::new(format!(
+                    "Secret {}: set exactly one of `file` or `env`",
+                    name
+                ))),
+            };
+            
+            let value = source.read_with(env).with_context(|| format!("Secret {}", name))?;
+            values.push((name.clone(), value));
+        }
+        
+        Ok(values)
+    }
+}
+
+#[derive(Debug)]
+pub struct SecretWriteOutcome {
+    pub repo: String,
+    pub name: String,
+    pub result: std::result::Result<SecretWriteResult, String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ApplyReport {
+    pub outcomes: Vec<SecretWriteOutcome>,
+}
+
+impl ApplyReport {
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.result.is_err()).count()
+    }
+    
+    pub fn unverified(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|o| matches!(o.result, Ok(SecretWriteResult::Unverified)))
+            .count()
+    }
+    
+    pub fn exit_code(&self) -> u8 {
+        let total = self.outcomes.len();
+        let failed = self.failed();
+        
+        if failed > 0 {
+            exit_code::for_counts(total - failed, total)
+        } else if self.unverified() > 0 {
+            exit_code::PARTIAL
+        } else {
+            exit_code::SUCCESS
+        }
+    }
+    
+    pub fn print(&self) {
+        for outcome in &self.outcomes {
+            match &outcome.result {
+                Ok(SecretWriteResult::Unverified) => {
+                    println!("⚠️  {} {}: unverified", outcome.repo, outcome.name)
+                }
+                Ok(result) => println!("✅ {} {}: {}", outcome.repo, outcome.name, result),
+                Err(e) => println!("❌ {} {}: {}", outcome.repo, outcome.name, e),
+            }
+        }
+        
+        println!(
+            "\n{} written, {} unverified, {} failed",
+            self.outcomes.len() - self.failed() - self.unverified(),
+            self.unverified(),
+            self.failed()
+        );
+    }
+}
+
+/// Exit code for a single `secrets set`.
+pub fn write_exit_code(result: SecretWriteResult) -> u8 {
+    match result {
+        SecretWriteResult::Created | SecretWriteResult::Updated => exit_code::SUCCESS,
+        SecretWriteResult::Unverified => exit_code::PARTIAL,
+    }
+}
+
+pub fn set_secret(store: &dyn SecretStore, repo: &str, name: &str, value: &str) -> Result<SecretWriteResult> {
+    validate_secret_name(name)?;
+    store.set_secret(repo, name, value)
+}
+
+/// Writes every manifest value to every repo, continuing past failures.
+/// Repos run on the pool, one owner at a time; outcomes keep repo order.
+pub fn apply_values(
+    values: &[(String, String)],
+    repos: &[String],
+    pool: &WorkerPool,
+    store_for: &(dyn Fn(&str) -> Result<Box<dyn SecretStore>> + Sync),
+) -> ApplyReport {
+    let sweep = pool.run(
+        repos,
+        |repo| repo.split('/').next().unwrap_or(repo).to_string(),
+        |repo| Ok(apply_to_repo(values, repo, store_for)),
+    );
+    
+    let mut report = ApplyReport::default();
+    
+    for (repo, outcome) in repos.iter().zip(sweep.outcomes) {
+        match outcome.into_result() {
+            Ok(outcomes) => report.outcomes.extend(outcomes),
+            Err(e) => report.outcomes.extend(failed_outcomes(values, repo, &e)),
+        }
+    }
+    
+    report
+}
+
+fn apply_to_repo(
+    values: &[(String, String)],
+    repo: &str,
+    store_for: &(dyn Fn(&str) -> Result<Box<dyn SecretStore>> + Sync),
+) -> Vec<SecretWriteOutcome> {
+    let store = match store_for(repo) {
+        Ok(store) => store,
+        Err(e) => return failed_outcomes(values, repo, &e),
+    };
+    
+    values
+        .iter()
+        .map(|(name, value)| {
+            info!("Applying {} to {}", name, repo);
+            
+            SecretWriteOutcome {
+                repo: repo.to_string(),
+                name: name.clone(),
+                result: store.set_secret(repo, name, value).map_err(|e| format!("{:#}", e)),
+            }
+        })
+        .collect()
+}
+
+fn failed_outcomes(values: &[(String, String)], repo: &str, e: &anyhow::Error) -> Vec<SecretWriteOutcome> {
+    values
+        .iter()
+        .map(|(name, _)| SecretWriteOutcome {
+            repo: repo.to_string(),
+            name: name.clone(),
+            result: Err(format!("{:#}", e)),
+        })
+        .collect()
+}
+
+pub struct SecretsCommand {
+    ctx: Arc<AppContext>,
+}
+
+impl SecretsCommand {
+    pub fn new(ctx: Arc<AppContext>) -> Self {
+        Self { ctx }
+    }
+    
+    /// A secrets client for `repo` using the owning account's token and
+    /// proxy. What it sets is kept in the vault.
+    pub fn store_for(&self, repo: &str, account: Option<&str>) -> Result<SecretsManager> {
+        Ok(SecretsManager::new(Box::new(self.client_for(repo, account)?)).with_vault(self.ctx.vault()?))
+    }
+    
+    /// An Actions variables client for `repo`, chosen the same way as `store_for`.
+    pub fn variables_for(&self, repo: &str, account: Option<&str>) -> Result<VariablesManager> {
+        Ok(VariablesManager::new(Box::new(self.client_for(repo, account)?)))
+    }
+    
+    fn client_for(&self, repo: &str, account: Option<&str>) -> Result<GitHubClient> {
+        if repo.split('/').count() != 2 {
+            bail!(ConfigError::new(format!("Invalid repo '{}', expected owner/name", repo)));
+        }
+        
+        let account = self.resolve_account(repo, account)?;
+        let proxy = self.ctx.proxy_url(&account.token)?;
+        
+        info!("Using @{} for {}", account.username, repo);
+        Ok(GitHubClient::new(account.token, proxy).with_cache(api::DEFAULT_CACHE_TTL))
+    }
+    
+    fn resolve_account(&self, repo: &str, username: Option<&str>) -> Result<AccountInfo> {
+        let state = self.ctx.state().load_state()?;
+        
+        self.ctx.accounts()?.resolve_for_repo(&state, repo, username).cloned()
+    }
+    
+    /// Manifest repos, every active fork with `all_active`, or the current active fork.
+    pub fn target_repos(&self, manifest: &SecretsManifest, all_active: bool) -> Result<Vec<String>> {
+        if !manifest.repos.is_empty() && !all_active {
+            return Ok(manifest.repos.clone());
+        }
+        
+        let state_mgr = self.ctx.state();
+        let state = state_mgr.load_state()?;
+        
+        let repos: Vec<String> = if all_active {
+            state.fork_chain
+                .iter()
+                .filter(|n| n.status == state::ForkStatus::Active)
+                .map(|n| n.repo.clone())
+                .collect()
+        } else {
+            state_mgr.get_active_fork(&state).map(|n| n.repo.clone()).into_iter().collect()
+        };
+        
+        if repos.is_empty() {
+            bail!(ConfigError::new(
+                "No active fork in state; list `repos` in the manifest or create the fork chain first"
+            ));
+        }
+        
+        Ok(repos)
+    }
+    
+    pub fn apply(
+        &self,
+        manifest_path: &Path,
+        all_active: bool,
+        account: Option<&str>,
+        pool: &WorkerPool,
+    ) -> Result<ApplyReport> {
+        let manifest = SecretsManifest::load(manifest_path)?;
+        let values = manifest.resolve()?;
+        let repos = self.target_repos(&manifest, all_active)?;
+        
+        Ok(apply_values(&values, &repos, pool, &|repo| {
+            Ok(Box::new(self.store_for(repo, account)?) as Box<dyn SecretStore>)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_secret_names() {
+        assert!(validate_secret_name("NEXUS_NODE_IDS").is_ok());
+        assert!(validate_secret_name("_private2").is_ok());
+        
+        assert!(validate_secret_name("").is_err());
+        assert!(validate_secret_name("2FAST").is_err());
+        assert!(validate_secret_name("HAS-DASH").is_err());
+        assert!(validate_secret_name("github_token").is_err());
+    }
+    
+    #[test]
+    fn test_file_source_strips_one_trailing_newline() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("value");
+        
+        fs::write(&path, "line1\nline2\n").unwrap();
+        assert_eq!(SecretSource::File(path.clone()).read().unwrap(), "line1\nline2");
+        
+        fs::write(&path, "\n").unwrap();
+        assert!(SecretSource::File(path).read().is_err());
+    }
+    
+    #[test]
+    fn test_manifest_resolves_relative_files_and_env() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        
+        fs::write(dir.join("nodes.txt"), "node_1\nnode_2\n").unwrap();
+        fs::write(dir.join("secrets.toml"), r#"
+repos = ["alice/nexus-runner"]
+
+[secrets.NEXUS_NODE_IDS]
+file = "nodes.txt"
+
+[secrets.EXTRA]
+env = "NEXUS_TEST_MANIFEST_EXTRA"
+"#).unwrap();
+
+        let manifest = SecretsManifest::load(&dir.join("secrets.toml")).unwrap();
+        let env = |var: &str| (var == "NEXUS_TEST_MANIFEST_EXTRA").then(|| "from-env".to_string());
+        let values = manifest.resolve_with(&env).unwrap();
+        
+        assert_eq!(manifest.repos, vec!["alice/nexus-runner".to_string()]);
+        assert_eq!(values, vec![
+            ("EXTRA".to_string(), "from-env".to_string()),
+            ("NEXUS_NODE_IDS".to_string(), "node_1\nnode_2".to_string()),
+        ]);
+    }
+    
+    #[test]
+    fn test_manifest_rejects_inline_values() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("secrets.toml");
+        fs::write(&path, "[secrets.TOKEN]\nvalue = \"hunter2\"\n").unwrap();
+        
+        let err = SecretsManifest::load(&path).unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+    }
+}
@@ -0,0 +1,381 @@
+// src/orchestration/self_update.rs - Update the binary from GitHub releases
+
+use anyhow::{Result, Context, bail};
+use semver::Version;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use log::info;
+use tempfile::NamedTempFile;
+use crate::app::AppContext;
+use crate::utils::exit_code::{self, ConfigError};
+
+pub const RELEASE_REPO: &str = "Kyugito666/Nexus-Actions-Orchestrator";
+const API_BASE: &str = "https://api.github.com";
+const CHECKSUM_ASSETS: [&str; 2] = ["checksums.txt", "SHA256SUMS"];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|a| a.name == name)
+    }
+}
+
+/// Where releases come from, so the update flow can run against a test double.
+pub trait ReleaseSource {
+    fn latest_release(&self) -> Result<Release>;
+    fn release_by_tag(&self, tag: &str) -> Result<Release>;
+    /// Streams the asset at `url` into `out`, returning the bytes written.
+    fn download(&self, url: &str, out: &mut dyn Write) -> Result<u64>;
+}
+
+/// The GitHub releases API, unauthenticated or with the first token from tokens.txt.
+pub struct GitHubReleases {
+    client: reqwest::blocking::Client,
+    token: Option<String>,
+    api_base: String,
+}
+
+impl GitHubReleases {
+    pub fn new(token: Option<String>) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(concat!("nexus-orchestrator/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .context("Failed to build HTTP client")?;
+        
+        Ok(Self { client, token, api_base: API_BASE.to_string() })
+    }
+    
+    /// Talks to another API root, e.g. a local fixture server.
+    pub fn with_api_base(mut self, api_base: &str) -> Self {
+        self.api_base = api_base.trim_end_matches('/').to_string();
+        self
+    }
+    
+    /// Uses the first token when tokens.txt is readable; the API also works without one.
+    pub fn from_context(ctx: &AppContext) -> Result<Self> {
+        let token = ctx.accounts()
+            .ok()
+            .and_then(|accounts| accounts.get_account(0).map(|a| a.token.clone()));
+        
+        Self::new(token)
+    }
+    
+    fn get(&self, url: &str, accept: &str) -> Result<reqwest::blocking::Response> {
+        let mut request = self.client.get(url).header("Accept", accept);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        
+        let response = request.send()
+            .with_context(|| format!("Request to {} failed", url))?;
+        
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            bail!(ConfigError::new(format!("Not found: {}", url)));
+        }
+        if !response.status().is_success() {
+            bail!("HTTP {} from {}", response.status(), url);
+        }
+        
+        Ok(response)
+    }
+    
+    fn release(&self, path: &str) -> Result<Release> {
+        let url = format!("{}/repos/{}/releases/{}", self.api_base, RELEASE_REPO, path);
+        self.get(&url, "application/vnd.github+json")?
+            .json()
+            .context("Failed to parse release response")
+    }
+}
+
+impl ReleaseSource for GitHubReleases {
+    fn latest_release(&self) -> Result<Release> {
+        self.release("latest")
+    }
+    
+    fn release_by_tag(&self, tag: &str) -> Result<Release> {
+        self.release(&format!("tags/{}", tag))
+    }
+    
+    fn download(&self, url: &str, out: &mut dyn Write) -> Result<u64> {
+        let mut response = self.get(url, "application/octet-stream")?;
+        response.copy_to(out).with_context(|| format!("Download of {} failed", url))
+    }
+}
+
+/// Parses `v2.1.0` or `2.1.0`.
+pub fn parse_version(tag: &str) -> Result<Version> {
+    let trimmed = tag.trim();
+    Version::parse(trimmed.strip_prefix('v').unwrap_or(trimmed))
+        .with_context(|| ConfigError::new(format!("Invalid version '{}', expected vX.Y.Z", tag)))
+}
+
+/// Release asset for this platform, e.g. `nexus-orchestrator-linux-x86_64`.
+pub fn platform_asset_name() -> String {
+    format!(
+        "nexus-orchestrator-{}-{}{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// Finds `asset` in a `sha256sum`-style file (`<hex>  <name>`, optional `*` before the name).
+pub fn parse_checksums(content: &str, asset: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        let hash = parts.next()?;
+        let name = parts.next()?.trim_start_matches('*');
+        
+        (name == asset && hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| hash.to_ascii_lowercase())
+    })
+}
+
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+pub fn verify_checksum(path: &Path, expected: &str) -> Result<()> {
+    let actual = sha256_file(path)?;
+    
+    if !actual.eq_ignore_ascii_case(expected) {
+        bail!("Checksum mismatch: expected {}, got {}", expected, actual);
+    }
+    
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateOutcome {
+    UpToDate { current: Version },
+    Available { current: Version, release: Version },
+    Updated { from: Version, to: Version },
+}
+
+impl UpdateOutcome {
+    /// `--check`: 0 when an update is available, 1 when already up to date,
+    /// so `self-update --check && self-update` works in scripts.
+    pub fn check_exit_code(&self) -> u8 {
+        match self {
+            UpdateOutcome::Available { .. } => exit_code::SUCCESS,
+            _ => exit_code::FAILURE,
+        }
+    }
+    
+    pub fn print(&self) {
+        match self {
+            UpdateOutcome::UpToDate { current } => println!("✅ v{} is the latest release", current),
+            UpdateOutcome::Available { current, release } => {
+                println!("⬆️  v{} is available (installed: v{})", release, current)
+            }
+            UpdateOutcome::Updated { from, to } => println!("✅ Updated v{} → v{}", from, to),
+        }
+    }
+}
+
+pub struct SelfUpdater {
+    source: Box<dyn ReleaseSource>,
+    current: Version,
+    exe_path: PathBuf,
+    asset_name: String,
+}
+
+impl SelfUpdater {
+    pub fn new(source: Box<dyn ReleaseSource>) -> Result<Self> {
+        let exe_path = std::env::current_exe()
+            .and_then(|p| p.canonicalize())
+            .context("Failed to locate the running executable")?;
+        
+        Ok(Self {
+            source,
+            current: parse_version(env!("CARGO_PKG_VERSION"))?,
+            exe_path,
+            asset_name: platform_asset_name(),
+        })
+    }
+    
+    /// Tests point the updater at a stand-in binary and version.
+    pub fn with_target(mut self, exe_path: &Path, current: Version, asset_name: &str) -> Self {
+        self.exe_path = exe_path.to_path_buf();
+        self.current = current;
+        self.asset_name = asset_name.to_string();
+        self
+    }
+    
+    fn fetch(&self, version: Option<&str>) -> Result<(Release, Version)> {
+        let release = match version {
+            Some(tag) => {
+                let tag = format!("v{}", parse_version(tag)?);
+                self.source.release_by_tag(&tag)
+                    .with_context(|| format!("Failed to fetch release {}", tag))?
+            }
+            None => self.source.latest_release().context("Failed to fetch the latest release")?,
+        };
+        
+        let release_version = parse_version(&release.tag_name)?;
+        Ok((release, release_version))
+    }
+    
+    /// Compares against the latest release, or the requested one.
+    pub fn check(&self, version: Option<&str>) -> Result<UpdateOutcome> {
+        let (_, release_version) = self.fetch(version)?;
+        Ok(self.compare(release_version, version.is_some()))
+    }
+    
+    /// An explicitly requested version is installed even if it is older.
+    fn compare(&self, release: Version, explicit: bool) -> UpdateOutcome {
+        if release == self.current || (!explicit && release < self.current) {
+            UpdateOutcome::UpToDate { current: self.current.clone() }
+        } else {
+            UpdateOutcome::Available { current: self.current.clone(), release }
+        }
+    }
+    
+    pub fn update(&self, version: Option<&str>) -> Result<UpdateOutcome> {
+        let (release, release_version) = self.fetch(version)?;
+        
+        if let UpdateOutcome::UpToDate { current } = self.compare(release_version.clone(), version.is_some()) {
+            return Ok(UpdateOutcome::UpToDate { current });
+        }
+        
+        let asset = release.asset(&self.asset_name).with_context(|| format!(
+            "Release {} has no asset {} for this platform",
+            release.tag_name, self.asset_name
+        ))?;
+        let expected = self.expected_checksum(&release)?;
+        
+        info!("Downloading {} from {}", asset.name, release.tag_name);
+        
+        // Download next to the executable so the final rename stays on one filesystem.
+        // The temp file is removed on every early return.
+        let exe_dir = self.exe_path.parent().context("Executable has no parent directory")?;
+        let mut download = NamedTempFile::new_in(exe_dir)
+            .with_context(|| format!("Failed to create a temp file in {}", exe_dir.display()))?;
+        
+        self.source.download(&asset.browser_download_url, download.as_file_mut())?;
+        download.as_file_mut().sync_all()?;
+        verify_checksum(download.path(), &expected)?;
+        
+        let permissions = fs::metadata(&self.exe_path)?.permissions();
+        download.as_file().set_permissions(permissions)?;
+        
+        replace_executable(download, &self.exe_path)?;
+        
+        Ok(UpdateOutcome::Updated {
+            from: self.current.clone(),
+            to: release_version,
+        })
+    }
+    
+    fn expected_checksum(&self, release: &Release) -> Result<String> {
+        let checksums = CHECKSUM_ASSETS
+            .iter()
+            .find_map(|name| release.asset(name))
+            .with_context(|| format!(
+                "Release {} has no {} file; refusing to install an unverified binary",
+                release.tag_name,
+                CHECKSUM_ASSETS.join(" or ")
+            ))?;
+        
+        let mut content = Vec::new();
+        self.source.download(&checksums.browser_download_url, &mut content)?;
+        
+        parse_checksums(&String::from_utf8_lossy(&content), &self.asset_name).with_context(|| format!(
+            "{} has no checksum for {}",
+            checksums.name, self.asset_name
+        ))
+    }
+}
+
+/// Atomically renames the verified download over the executable.
+#[cfg(not(windows))]
+fn replace_executable(download: NamedTempFile, target: &Path) -> Result<()> {
+    download.persist(target)
+        .map_err(|e| e.error)
+        .with_context(|| format!("Failed to replace {}", target.display()))?;
+    Ok(())
+}
+
+/// Windows cannot overwrite a running executable, but it can rename it:
+/// move the old binary aside, move the new one in, and move the old one
+/// back if that fails. The `.old` file is cleaned up by the next update.
+#[cfg(windows)]
+fn replace_executable(download: NamedTempFile, target: &Path) -> Result<()> {
+    let old = target.with_extension("old.exe");
+    fs::remove_file(&old).ok();
+    
+    fs::rename(target, &old)
+        .with_context(|| format!("Failed to move {} aside", target.display()))?;
+    
+    if let Err(e) = download.persist(target) {
+        fs::rename(&old, target).ok();
+        return Err(e.error).with_context(|| format!("Failed to replace {}", target.display()));
+    }
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("v2.1.0").unwrap(), Version::new(2, 1, 0));
+        assert_eq!(parse_version("2.0.10").unwrap(), Version::new(2, 0, 10));
+        assert!(parse_version("v2.10.0").unwrap() > parse_version("v2.9.3").unwrap());
+        assert!(parse_version("v2.1.0-rc.1").unwrap() < parse_version("v2.1.0").unwrap());
+        assert!(parse_version("latest").is_err());
+    }
+    
+    #[test]
+    fn test_parse_checksums() {
+        let hash = "a".repeat(64);
+        let content = format!(
+            "{}  nexus-orchestrator-linux-x86_64\n{} *nexus-orchestrator-windows-x86_64.exe\nnot a line\n",
+            hash,
+            "B".repeat(64)
+        );
+        
+        assert_eq!(parse_checksums(&content, "nexus-orchestrator-linux-x86_64"), Some(hash));
+        assert_eq!(
+            parse_checksums(&content, "nexus-orchestrator-windows-x86_64.exe"),
+            Some("b".repeat(64))
+        );
+        assert_eq!(parse_checksums(&content, "nexus-orchestrator-macos-aarch64"), None);
+        assert_eq!(parse_checksums("abc  short-hash\n", "short-hash"), None);
+    }
+    
+    #[test]
+    fn test_verify_checksum() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("binary");
+        fs::write(&path, b"hello").unwrap();
+        
+        let hello = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert!(verify_checksum(&path, hello).is_ok());
+        assert!(verify_checksum(&path, &hello.to_uppercase()).is_ok());
+        assert!(verify_checksum(&path, &"0".repeat(64)).is_err());
+    }
+}
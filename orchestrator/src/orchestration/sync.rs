@@ -0,0 +1,283 @@
+// src/orchestration/sync.rs - Refreshes every chain node from GitHub
+
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use log::{info, warn};
+use crate::app::AppContext;
+use crate::core::billing::{BillingInfo, BillingMonitor};
+use crate::core::settings;
+use crate::core::state::{ForkChainNode, ForkStatus, OrchestratorState, RunSnapshot};
+use crate::github::fork::ChainVerification;
+use crate::github::{ApiFactory, ForkManager, GitHubApi, GitHubClient};
+use crate::monitor::events::EventLog;
+use crate::orchestration::forks;
+use crate::utils::exit_code;
+
+/// Something in state that GitHub disagrees with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Discrepancy {
+    MissingRepo { repo: String },
+    /// A run is still going on a fork rotation already gave up on
+    RunOnExhausted { repo: String, run_id: u64, status: String },
+    MissingAccount { repo: String, pat_index: usize },
+}
+
+impl Discrepancy {
+    pub fn repo(&self) -> &str {
+        match self {
+            Discrepancy::MissingRepo { repo }
+            | Discrepancy::RunOnExhausted { repo, .. }
+            | Discrepancy::MissingAccount { repo, .. } => repo,
+        }
+    }
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discrepancy::MissingRepo { repo } => write!(f, "{} no longer exists on GitHub", repo),
+            Discrepancy::RunOnExhausted { repo, run_id, status } => {
+                write!(f, "{} is exhausted but run #{} is {}", repo, run_id, status)
+            }
+            Discrepancy::MissingAccount { repo, pat_index } => {
+                write!(f, "{} belongs to token #{}, which is not in tokens.txt", repo, pat_index)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub refreshed: Vec<String>,
+    pub discrepancies: Vec<Discrepancy>,
+    /// (repo, error) for nodes that could not be refreshed
+    pub failed: Vec<(String, String)>,
+}
+
+impl SyncReport {
+    /// `PARTIAL` when some nodes could not be refreshed.
+    pub fn exit_code(&self) -> u8 {
+        if self.failed.is_empty() {
+            exit_code::SUCCESS
+        } else {
+            exit_code::PARTIAL
+        }
+    }
+    
+    pub fn print(&self) {
+        println!("🔄 Refreshed {} fork(s)", self.refreshed.len());
+        for discrepancy in &self.discrepancies {
+            println!("⚠️  {}", discrepancy);
+        }
+        for (repo, error) in &self.failed {
+            println!("❌ {}: {}", repo, error);
+        }
+    }
+}
+
+/// `sync`: billing, latest run and existence of every non-deleted node.
+pub struct StateSync {
+    ctx: Arc<AppContext>,
+    client_factory: ApiFactory,
+}
+
+impl StateSync {
+    pub fn new(ctx: Arc<AppContext>) -> Self {
+        Self {
+            ctx,
+            client_factory: Box::new(|account, proxy| {
+                Box::new(GitHubClient::new(account.token.clone(), proxy))
+            }),
+        }
+    }
+    
+    pub fn with_client_factory(mut self, client_factory: ApiFactory) -> Self {
+        self.client_factory = client_factory;
+        self
+    }
+    
+    pub fn run(&self) -> Result<SyncReport> {
+        sync_chain(&self.ctx, &self.client_factory)
+    }
+    
+    /// `status --rebuild`: a fresh chain of `parent` (default: the main repo
+    /// from setup.json) from GitHub, see `ForkManager::rebuild_state`.
+    pub fn rebuild(&self, parent: Option<&str>) -> Result<OrchestratorState> {
+        let parent = match parent {
+            Some(parent) => parent.to_string(),
+            None => forks::main_repo(self.ctx.paths())?,
+        };
+        let accounts = self.ctx.accounts()?;
+        
+        ForkManager::new(self.ctx.state()).rebuild_state(
+            accounts.get_all_accounts(),
+            &parent,
+            &*self.ctx.proxies()?,
+            &self.client_factory,
+        )
+    }
+    
+    /// `verify`: see `ForkManager::verify_chain`.
+    pub fn verify(&self) -> Result<ChainVerification> {
+        let state = self.ctx.state().load_state()?;
+        
+        Ok(ForkManager::new(self.ctx.state()).verify_chain(
+            &state,
+            &*self.ctx.accounts()?,
+            &*self.ctx.proxies()?,
+            &self.client_factory,
+        ))
+    }
+}
+
+/// Refreshes the chain in memory and writes the state once at the end.
+/// Discrepancies are also recorded as events.
+pub fn sync_chain(ctx: &AppContext, client_factory: &ApiFactory) -> Result<SyncReport> {
+    let state_mgr = ctx.state();
+    let mut state = state_mgr.load_state()?;
+    let accounts = ctx.accounts()?;
+    let health = ctx.health()?;
+    let billing_mon = BillingMonitor::from_config(&ctx.paths().thresholds_file())?;
+    
+    let mut report = SyncReport::default();
+    let mut billing: HashMap<String, BillingInfo> = HashMap::new();
+    
+    for node in state.fork_chain.iter_mut().filter(|n| n.status != ForkStatus::Deleted) {
+        let account = match accounts.get_account(node.pat_index) {
+            Some(account) => account,
+            None => {
+                report.discrepancies.push(Discrepancy::MissingAccount {
+                    repo: node.repo.clone(),
+                    pat_index: node.pat_index,
+                });
+                continue;
+            }
+        };
+        let client = (client_factory)(account, ctx.proxy_url(&account.token)?);
+        
+        match client.check_repo_exists(&node.repo) {
+            Ok(true) => {}
+            Ok(false) => {
+                report.discrepancies.push(Discrepancy::MissingRepo { repo: node.repo.clone() });
+                continue;
+            }
+            Err(e) => {
+                warn!("Failed to sync {}: {}", node.repo, e);
+                report.failed.push((node.repo.clone(), format!("{:#}", e)));
+                continue;
+            }
+        }
+        
+        // One billing lookup per billing owner, from the cache when it is
+        // fresh; forks in a shared organization are billed to the token's user
+        let billing_owner = account.billing_owner().0;
+        let info = match billing.get(billing_owner) {
+            Some(info) => info.clone(),
+            None => {
+                let info = match health.cached_billing(billing_owner) {
+                    Some(info) => info,
+                    None => billing_mon.check_billing(account, client.as_ref())?,
+                };
+                billing.insert(billing_owner.to_string(), info.clone());
+                info
+            }
+        };
+        
+        match sync_node(node, client.as_ref(), &info) {
+            Ok(found) => {
+                report.refreshed.push(node.repo.clone());
+                report.discrepancies.extend(found);
+            }
+            Err(e) => {
+                warn!("Failed to sync {}: {}", node.repo, e);
+                report.failed.push((node.repo.clone(), format!("{:#}", e)));
+            }
+        }
+    }
+    
+    state_mgr.save_state(&state)?;
+    
+    let fetched: Vec<BillingInfo> = billing.into_values().collect();
+    if let Err(e) = health.cache_billing(&fetched) {
+        warn!("Failed to save billing cache: {}", e);
+    }
+    
+    let events = EventLog::new(state_mgr.paths());
+    for discrepancy in &report.discrepancies {
+        warn!("{}", discrepancy);
+        events.record("discrepancy", &discrepancy.to_string(), Some(discrepancy.repo()));
+    }
+    
+    info!(
+        "Synced {} fork(s), {} discrepancies, {} failed",
+        report.refreshed.len(),
+        report.discrepancies.len(),
+        report.failed.len()
+    );
+    Ok(report)
+}
+
+/// Billing and latest run of a node whose repo exists.
+fn sync_node(node: &mut ForkChainNode, client: &dyn GitHubApi, billing: &BillingInfo) -> Result<Vec<Discrepancy>> {
+    let mut found = Vec::new();
+    
+    // A failed billing call yields an estimate; keep the last real figure
+    if !billing.is_estimated {
+        node.billing_used = billing.total_core_hours_used;
+    }
+    
+    if let Some(run_id) = client.get_latest_workflow_run(&node.repo, &settings::get().github.workflow_file, None)? {
+        let (status, conclusion) = client.get_workflow_status(&node.repo, run_id)?;
+        let started_at = match &node.last_run {
+            Some(run) if run.run_id == run_id => run.started_at,
+            _ => Utc::now(),
+        };
+        
+        if node.status == ForkStatus::Exhausted && status != "completed" {
+            found.push(Discrepancy::RunOnExhausted {
+                repo: node.repo.clone(),
+                run_id,
+                status: status.clone(),
+            });
+        }
+        
+        node.last_run = Some(RunSnapshot {
+            run_id,
+            status,
+            conclusion,
+            started_at,
+            checked_at: Utc::now(),
+        });
+    }
+    
+    node.last_updated = Utc::now();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_report_exit_code_and_messages() {
+        let mut report = SyncReport {
+            refreshed: vec!["a/nexus-runner".to_string()],
+            discrepancies: vec![Discrepancy::RunOnExhausted {
+                repo: "a/nexus-runner".to_string(),
+                run_id: 7,
+                status: "in_progress".to_string(),
+            }],
+            failed: Vec::new(),
+        };
+        
+        // Discrepancies are reported, not failures
+        assert_eq!(report.exit_code(), exit_code::SUCCESS);
+        assert_eq!(report.discrepancies[0].to_string(), "a/nexus-runner is exhausted but run #7 is in_progress");
+        
+        report.failed.push(("b/nexus-runner".to_string(), "HTTP 502".to_string()));
+        assert_eq!(report.exit_code(), exit_code::PARTIAL);
+    }
+}
@@ -0,0 +1,475 @@
+// src/orchestration/tokens.rs - Adding and removing tokens for the CLI
+
+use anyhow::{Result, Context, bail};
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::Arc;
+use log::info;
+use crate::app::AppContext;
+use crate::core::account::{self, AccountInfo, AccountManager, TokenLookup};
+use crate::core::proxy::ProxyConfig;
+use crate::core::settings::{self, ProxyMappingMode};
+use crate::core::state::ForkStatus;
+use crate::utils::exit_code::ConfigError;
+use crate::utils::{platform, redact};
+
+/// How a new token is checked; `account::lookup_token` outside tests.
+pub type TokenLookupFn = Box<dyn Fn(&str, Option<&ProxyConfig>) -> Result<TokenLookup>>;
+
+/// What `TokenCommand::add` appended to tokens.txt.
+#[derive(Debug, Clone)]
+pub struct TokenAdded {
+    pub index: usize,
+    pub username: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether the check went out through the proxy the token is paired with
+    pub proxied: bool,
+    /// Capabilities the check found missing; empty when all are there or
+    /// GitHub did not tell
+    pub missing: Vec<&'static str>,
+}
+
+impl fmt::Display for TokenAdded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Token #{} added for @{}", self.index, self.username)?;
+        if let Some(expires_at) = self.expires_at {
+            write!(f, ", expires {}", expires_at.format("%Y-%m-%d"))?;
+        }
+        if self.proxied {
+            write!(f, " (checked through its proxy)")?;
+        }
+        if !self.missing.is_empty() {
+            write!(f, "; cannot {}", self.missing.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// What `TokenCommand::remove` took out of tokens.txt and the state.
+#[derive(Debug)]
+pub struct TokenRemoved {
+    pub index: usize,
+    pub username: String,
+    /// Chain nodes of the removed account, dropped from the state
+    pub dropped_forks: Vec<String>,
+    /// Chain nodes of later accounts whose token index moved down by one
+    pub renumbered: usize,
+    /// proxies.txt line paired with the token, when proxies map by line
+    pub proxy_line: Option<usize>,
+}
+
+impl TokenRemoved {
+    pub fn print(&self) {
+        println!("🗑️  Token #{} (@{}) removed", self.index, self.username);
+        for repo in &self.dropped_forks {
+            println!("   {} dropped from the fork chain; the repo itself is left on GitHub", repo);
+        }
+        if self.renumbered > 0 {
+            println!("   {} forks of later accounts renumbered", self.renumbered);
+        }
+        if let Some(line) = self.proxy_line {
+            println!(
+                "⚠️  proxies.txt pairs tokens with proxies by line: remove line {} there too so later tokens keep their proxies",
+                line
+            );
+        }
+    }
+}
+
+/// `token add`, `token remove` and `token enable/disable`: edits tokens.txt
+/// and the tokenmap cache while keeping the fork chain's token indexes and
+/// the account count in step.
+pub struct TokenCommand {
+    ctx: Arc<AppContext>,
+    lookup: TokenLookupFn,
+}
+
+impl TokenCommand {
+    pub fn new(ctx: Arc<AppContext>) -> Self {
+        Self {
+            ctx,
+            lookup: Box::new(account::lookup_token),
+        }
+    }
+    
+    pub fn with_lookup(mut self, lookup: TokenLookupFn) -> Self {
+        self.lookup = lookup;
+        self
+    }
+    
+    /// Checks `line` (a token, optionally followed by its owner as in
+    /// tokens.txt) through the proxy it will be paired with, then appends it
+    /// as the next account. Nothing is written when the check fails.
+    pub fn add(&self, line: &str) -> Result<TokenAdded> {
+        let mut import = self.import(true)?;
+        import.check(line)?;
+        let mut added = import.finish()?;
+        Ok(added.remove(0))
+    }
+    
+    /// Starts an import of several tokens, see `TokenImport`. With
+    /// `use_proxies`, each is checked through the proxy it will be paired
+    /// with, as `add` does; otherwise directly.
+    pub fn import(&self, use_proxies: bool) -> Result<TokenImport<'_>> {
+        let paths = self.ctx.paths();
+        let content = read_tokens(&paths.tokens_file())?;
+        let mut accounts = AccountManager::new(paths);
+        if !account::token_lines(&content).is_empty() {
+            accounts.load_tokens(&paths.tokens_file())?;
+        }
+        
+        Ok(TokenImport {
+            command: self,
+            existing: account::token_lines(&content).len(),
+            content,
+            accounts,
+            use_proxies,
+            accepted: Vec::new(),
+        })
+    }
+    
+    /// Takes the account `which` (token index or username) out of
+    /// tokens.txt and the cache. Its chain nodes are dropped and those of
+    /// later accounts move down one index, as the tokens do. Refused while
+    /// the account owns an Active fork unless `force`.
+    pub fn remove(&self, which: &str, force: bool) -> Result<TokenRemoved> {
+        let paths = self.ctx.paths();
+        let content = read_tokens(&paths.tokens_file())?;
+        
+        let mut accounts = AccountManager::new(paths);
+        accounts.load_tokens(&paths.tokens_file())?;
+        let account = find_account(&accounts, which)?.clone();
+        let index = account.index;
+        
+        let state_mgr = self.ctx.state();
+        let mut state = state_mgr.load_state()?;
+        let active: Vec<&str> = state.fork_chain
+            .iter()
+            .filter(|node| node.pat_index == index && node.status == ForkStatus::Active)
+            .map(|node| node.repo.as_str())
+            .collect();
+        if !active.is_empty() && !force {
+            bail!(ConfigError::new(format!(
+                "@{} owns the active fork {}; rotate away from it first or pass --force",
+                account.username,
+                active.join(", ")
+            )));
+        }
+        
+        // A token pasted twice goes with every copy
+        let lines: Vec<usize> = platform::config_lines(&content)
+            .into_iter()
+            .filter(|(_, line)| account::parse_token_line(line).is_some_and(|(token, _)| token == account.token))
+            .map(|(line_no, _)| line_no)
+            .collect();
+        write_atomic(&paths.tokens_file(), &without_lines(&content, &lines))?;
+        self.ctx.reload_accounts();
+        accounts.forget_token(&account.token)?;
+        
+        let dropped_forks: Vec<String> = state.fork_chain
+            .iter()
+            .filter(|node| node.pat_index == index)
+            .map(|node| node.repo.clone())
+            .collect();
+        state.fork_chain.retain(|node| node.pat_index != index);
+        let mut renumbered = 0;
+        for node in state.fork_chain.iter_mut().filter(|node| node.pat_index > index) {
+            node.pat_index -= 1;
+            renumbered += 1;
+        }
+        if state.current_active_index > index {
+            state.current_active_index -= 1;
+        }
+        state.total_accounts = accounts.total_accounts() - 1;
+        state_mgr.save_state(&state)?;
+        
+        let proxy_line = match settings::get().proxy.mapping_mode {
+            ProxyMappingMode::Index => fs::read_to_string(paths.proxies_file())
+                .ok()
+                .and_then(|proxies| platform::config_lines(&proxies).get(index).map(|(line, _)| *line)),
+            ProxyMappingMode::RoundRobin => None,
+        };
+        
+        info!("Removed token #{} (@{})", index, account.username);
+        Ok(TokenRemoved { index, username: account.username, dropped_forks, renumbered, proxy_line })
+    }
+    
+    /// `token disable` / `token enable`: sets the account `which` (token
+    /// index or username) aside or back into use, keeping its token and
+    /// index. Returns the updated account.
+    pub fn set_enabled(&self, which: &str, enabled: bool) -> Result<AccountInfo> {
+        self.update_enabled(which, |_| enabled)
+    }
+    
+    /// Disables the account `which` if it is enabled, and the other way round.
+    pub fn toggle(&self, which: &str) -> Result<AccountInfo> {
+        self.update_enabled(which, |enabled| !enabled)
+    }
+    
+    /// `token priority`: sets how early rotation takes the account `which`.
+    /// Returns the updated account.
+    pub fn set_priority(&self, which: &str, priority: u8) -> Result<AccountInfo> {
+        let mut accounts = self.load_accounts()?;
+        let index = find_account(&accounts, which)?.index;
+        
+        let account = accounts.set_priority(index, priority)?.clone();
+        self.ctx.reload_accounts();
+        info!("@{} rotation priority set to {}", account.username, priority);
+        Ok(account)
+    }
+    
+    /// `token label`: names the account `which`, an empty label clearing
+    /// it. Notes are replaced when given, cleared when empty, kept otherwise.
+    pub fn set_label(&self, which: &str, label: &str, notes: Option<&str>) -> Result<AccountInfo> {
+        let mut accounts = self.load_accounts()?;
+        let account = find_account(&accounts, which)?;
+        let index = account.index;
+        let notes = match notes {
+            Some(notes) => non_empty(notes),
+            None => account.notes.clone(),
+        };
+        
+        let account = accounts.set_annotations(index, non_empty(label), notes)?.clone();
+        self.ctx.reload_accounts();
+        info!("Token #{} labelled {}", index, account.display_name());
+        Ok(account)
+    }
+    
+    /// Every account in tokens.txt as it is on disk now.
+    pub fn list(&self) -> Result<Vec<AccountInfo>> {
+        Ok(self.load_accounts()?.get_all_accounts().to_vec())
+    }
+    
+    fn update_enabled(&self, which: &str, enabled: impl Fn(bool) -> bool) -> Result<AccountInfo> {
+        let mut accounts = self.load_accounts()?;
+        let account = find_account(&accounts, which)?;
+        let (index, enabled) = (account.index, enabled(account.enabled));
+        
+        let account = accounts.set_enabled(index, enabled)?.clone();
+        self.ctx.reload_accounts();
+        info!("@{} {}", account.username, if enabled { "enabled" } else { "disabled" });
+        Ok(account)
+    }
+    
+    /// Read fresh rather than through the context, which may hold a list
+    /// from before an edit in the same session.
+    fn load_accounts(&self) -> Result<AccountManager> {
+        let mut accounts = AccountManager::new(self.ctx.paths());
+        accounts.load_tokens(&self.ctx.paths().tokens_file())?;
+        Ok(accounts)
+    }
+}
+
+/// The account `which` names: a token index, or a username with or without `@`.
+fn find_account<'a>(accounts: &'a AccountManager, which: &str) -> Result<&'a AccountInfo> {
+    match which.parse::<usize>() {
+        Ok(index) => accounts.get_account(index),
+        Err(_) => accounts.get_account_by_username(which.trim_start_matches('@')),
+    }
+    .with_context(|| ConfigError::new(format!(
+        "Unknown account '{}'; pass a token index below {} or a username",
+        which,
+        accounts.total_accounts()
+    )))
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    Some(text.trim()).filter(|text| !text.is_empty()).map(str::to_string)
+}
+
+/// tokens.txt as text; empty when there is none yet.
+fn read_tokens(tokens_file: &Path) -> Result<String> {
+    match fs::read_to_string(tokens_file) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e).context(ConfigError::new("Failed to read tokens.txt")),
+    }
+}
+
+/// The proxy the token at `index` is paired with, the way `ProxyManager`
+/// maps them; None without proxies.txt.
+fn paired_proxy(proxies_file: &Path, index: usize) -> Result<Option<ProxyConfig>> {
+    let Ok(content) = fs::read_to_string(proxies_file) else {
+        return Ok(None);
+    };
+    let lines = platform::config_lines(&content);
+    if lines.is_empty() {
+        return Ok(None);
+    }
+    
+    let (line_no, url) = match settings::get().proxy.mapping_mode {
+        ProxyMappingMode::Index => *lines.get(index).with_context(|| ConfigError::new(format!(
+            "proxies.txt has no proxy for token #{}; add one at the end of it first",
+            index
+        )))?,
+        ProxyMappingMode::RoundRobin => lines[index % lines.len()],
+    };
+    ProxyConfig::from_url(url)
+        .map(Some)
+        .with_context(|| ConfigError::new(format!("Invalid proxy URL at line {}: {}", line_no, url)))
+}
+
+/// A token that passed its check during an import, not yet written.
+struct AcceptedToken {
+    token: String,
+    owner: Option<String>,
+    lookup: TokenLookup,
+    added: TokenAdded,
+}
+
+/// Tokens checked one at a time, each as it is entered, and appended to
+/// tokens.txt together by `finish`. Tokens already in tokens.txt or earlier
+/// in the import, and logins already taken, are refused.
+pub struct TokenImport<'a> {
+    command: &'a TokenCommand,
+    /// tokens.txt as it was when the import started
+    content: String,
+    existing: usize,
+    accounts: AccountManager,
+    use_proxies: bool,
+    accepted: Vec<AcceptedToken>,
+}
+
+impl TokenImport<'_> {
+    /// Checks `line` as the next account; on success it is kept for `finish`.
+    pub fn check(&mut self, line: &str) -> Result<TokenAdded> {
+        let (token, owner) = account::parse_token_line(line)
+            .context(ConfigError::new("Not a GitHub token; expected ghp_... or github_pat_..."))?;
+        redact::register(&token);
+        
+        if let Some(existing) = self.accounts.get_account_by_token(&token) {
+            bail!(ConfigError::new(format!("That token is already in tokens.txt, as token #{}", existing.index)));
+        }
+        if self.accepted.iter().any(|accepted| accepted.token == token) {
+            bail!(ConfigError::new("That token was already given above"));
+        }
+        
+        let index = self.existing + self.accepted.len();
+        let proxy = match self.use_proxies {
+            true => paired_proxy(&self.command.ctx.paths().proxies_file(), index)?,
+            false => None,
+        };
+        let lookup = (self.command.lookup)(&token, proxy.as_ref())
+            .context(ConfigError::new("Token check failed; tokens.txt left unchanged"))?;
+        
+        if let Some(existing) = self.accounts.get_account_by_username(&lookup.username) {
+            bail!(ConfigError::new(format!("@{} is already token #{}", lookup.username, existing.index)));
+        }
+        if self.accepted.iter().any(|accepted| accepted.lookup.username.eq_ignore_ascii_case(&lookup.username)) {
+            bail!(ConfigError::new(format!("@{} was already given above", lookup.username)));
+        }
+        
+        let added = TokenAdded {
+            index,
+            username: lookup.username.clone(),
+            expires_at: lookup.expires_at,
+            proxied: proxy.is_some(),
+            missing: lookup.capabilities.map_or_else(Vec::new, |capabilities| {
+                account::Capability::ALL
+                    .iter()
+                    .filter(|capability| !capabilities.has(**capability))
+                    .map(|capability| capability.name())
+                    .collect()
+            }),
+        };
+        self.accepted.push(AcceptedToken { token, owner, lookup, added: added.clone() });
+        Ok(added)
+    }
+    
+    /// Tokens accepted so far.
+    pub fn accepted(&self) -> usize {
+        self.accepted.len()
+    }
+    
+    /// Appends the accepted tokens to tokens.txt, records them in the
+    /// tokenmap cache and updates the account count. Refused, with nothing
+    /// written, when tokens.txt changed since the import started.
+    pub fn finish(self) -> Result<Vec<TokenAdded>> {
+        let paths = self.command.ctx.paths();
+        if read_tokens(&paths.tokens_file())? != self.content {
+            bail!(ConfigError::new("tokens.txt changed during the import; nothing was written, import again"));
+        }
+        if self.accepted.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let mut updated = self.content;
+        if !updated.is_empty() && !updated.ends_with(['\n', '\r']) {
+            updated.push('\n');
+        }
+        for accepted in &self.accepted {
+            match &accepted.owner {
+                Some(owner) => updated.push_str(&format!("{} {}\n", accepted.token, owner)),
+                None => updated.push_str(&format!("{}\n", accepted.token)),
+            }
+        }
+        write_atomic(&paths.tokens_file(), &updated)?;
+        self.command.ctx.reload_accounts();
+        
+        let mut added = Vec::new();
+        for accepted in self.accepted {
+            self.accounts.remember_token(&accepted.token, accepted.added.index, &accepted.lookup)?;
+            info!("Added token #{} for @{}", accepted.added.index, accepted.added.username);
+            added.push(accepted.added);
+        }
+        
+        let state_mgr = self.command.ctx.state();
+        let mut state = state_mgr.load_state()?;
+        state.total_accounts = self.existing + added.len();
+        state_mgr.save_state(&state)?;
+        
+        Ok(added)
+    }
+}
+
+/// `content` without the lines numbered `line_nos`, counted as
+/// `platform::config_lines` does.
+fn without_lines(content: &str, line_nos: &[usize]) -> String {
+    let mut kept = String::with_capacity(content.len());
+    let mut rest = content;
+    let mut current = 0;
+    
+    while !rest.is_empty() {
+        current += 1;
+        let end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+        let ending = if rest[end..].starts_with("\r\n") {
+            2
+        } else {
+            (end < rest.len()) as usize
+        };
+        
+        if !line_nos.contains(&current) {
+            kept.push_str(&rest[..end + ending]);
+        }
+        rest = &rest[end + ending..];
+    }
+    
+    kept
+}
+
+/// Writes next to `path`, then renames over it, so readers never see half a file.
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let temp_file = path.with_extension("tmp");
+    fs::write(&temp_file, content)
+        .with_context(|| format!("Failed to write {}", temp_file.display()))?;
+    fs::rename(&temp_file, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_without_lines_keeps_other_lines_and_endings() {
+        let content = "# main accounts\r\nghp_aaaa\r\n\r\nghp_bbbb my-org\nghp_cccc";
+        let tokens = account::token_lines(content);
+        assert_eq!(tokens.iter().map(|(line, _, _)| *line).collect::<Vec<_>>(), [2, 4, 5]);
+        
+        assert_eq!(without_lines(content, &[tokens[1].0]), "# main accounts\r\nghp_aaaa\r\n\r\nghp_cccc");
+        assert_eq!(without_lines(content, &[2, 5]), "# main accounts\r\n\r\nghp_bbbb my-org\n");
+    }
+}
@@ -0,0 +1,598 @@
+// src/orchestration/trigger.rs - Ad-hoc workflow dispatch
+
+use anyhow::{Result, Context, bail};
+use chrono::{DateTime, Utc};
+use std::cell::RefCell;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use log::{info, warn};
+use crate::app::AppContext;
+use crate::core::{account, settings, state::{ForkStatus, RunSnapshot}, StateManager};
+use crate::github::api::WorkflowRun;
+use crate::github::workflow::{self, RerunReport, RunArtifacts, RunLogs, RunProgress, RunReport, RunWatch};
+use crate::github::{ApiFactory, GitHubApi, GitHubClient, WorkflowController};
+use crate::monitor::health::StuckRun;
+use crate::orchestration::forks;
+use crate::utils::cancel::{self, CancelToken};
+use crate::utils::delay::{self, Sleeper};
+use crate::utils::exit_code::{self, ConfigError};
+
+const CORRELATE_ATTEMPTS: u32 = 12;
+/// How far this machine's clock may run ahead of GitHub's when matching a
+/// dispatched run by its creation time.
+const CLOCK_SLACK_SECS: i64 = 5;
+
+#[derive(Debug, Clone, Default)]
+pub struct TriggerOptions {
+    pub repo: Option<String>,
+    /// Workflow file to dispatch; `github.workflow_file` when None
+    pub workflow: Option<String>,
+    pub account: Option<String>,
+    pub inputs: Vec<String>,
+    pub watch: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerOutcome {
+    Dispatched { run_id: Option<u64> },
+    /// `healthy`: see `RunReport::is_healthy`
+    Completed { run_id: u64, conclusion: String, healthy: bool },
+    TimedOut { run_id: u64 },
+    /// Still waiting for a runner after `monitor.stuck_queued_minutes`
+    Queued { run_id: u64 },
+}
+
+impl TriggerOutcome {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            TriggerOutcome::Dispatched { .. } => exit_code::SUCCESS,
+            TriggerOutcome::Completed { healthy: true, .. } => exit_code::SUCCESS,
+            TriggerOutcome::Completed { .. } => exit_code::FAILURE,
+            TriggerOutcome::TimedOut { .. } | TriggerOutcome::Queued { .. } => exit_code::TIMED_OUT,
+        }
+    }
+}
+
+/// Parses repeated `key=value` arguments into the workflow_dispatch inputs object.
+pub fn parse_inputs(raw: &[String]) -> Result<Option<serde_json::Value>> {
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    
+    let mut inputs = serde_json::Map::new();
+    
+    for item in raw {
+        let (key, value) = item
+            .split_once('=')
+            .with_context(|| ConfigError::new(format!("Invalid --input '{}', expected key=value", item)))?;
+        
+        let key = key.trim();
+        if key.is_empty() {
+            bail!(ConfigError::new(format!("Invalid --input '{}': key is empty", item)));
+        }
+        
+        inputs.insert(key.to_string(), serde_json::Value::String(value.to_string()));
+    }
+    
+    Ok(Some(serde_json::Value::Object(inputs)))
+}
+
+pub struct Trigger {
+    ctx: Arc<AppContext>,
+    client_factory: ApiFactory,
+    sleeper: Arc<dyn Sleeper>,
+    cancel: CancelToken,
+}
+
+impl Trigger {
+    pub fn new(ctx: Arc<AppContext>) -> Self {
+        Self {
+            ctx,
+            client_factory: Box::new(|account, proxy| Box::new(GitHubClient::new(account.token.clone(), proxy))),
+            sleeper: delay::thread_sleeper(),
+            cancel: cancel::ctrl_c(),
+        }
+    }
+    
+    pub fn with_client_factory(mut self, client_factory: ApiFactory) -> Self {
+        self.client_factory = client_factory;
+        self
+    }
+    
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+    
+    pub fn run(&self, options: &TriggerOptions) -> Result<TriggerOutcome> {
+        let inputs = parse_inputs(&options.inputs)?;
+        let state_mgr = self.ctx.state();
+        let state = state_mgr.load_state()?;
+        
+        let repo = resolve_repo(&state_mgr, &state, options.repo.as_deref(), "--repo")?;
+        
+        let account = self.resolve_account(&state, &repo, options.account.as_deref())?;
+        let proxy = self.ctx.proxy_url(&account.token)?;
+        
+        if proxy.is_none() {
+            warn!("No proxy mapped for @{}, dispatching without proxy", account.username);
+        }
+        
+        let client = (self.client_factory)(&account, proxy);
+        
+        let workflow = options.workflow.as_deref().unwrap_or(&settings::get().github.workflow_file).to_string();
+        
+        // Remember the newest run so the dispatched one can be told apart
+        let previous_run = client.get_latest_workflow_run(&repo, &workflow, None).unwrap_or(None);
+        
+        let branch = match forks::deploy_branch(self.ctx.paths())? {
+            Some(branch) => branch,
+            None => client.get_repo_info(&repo)?.default_branch,
+        };
+        info!("Dispatching {} on {}@{} as @{}", workflow, repo, branch, account.username);
+        let dispatched_at = Utc::now();
+        client.trigger_workflow(&repo, &workflow, &branch, inputs.as_ref())?;
+        println!("✅ Workflow dispatched on {}", repo);
+        
+        let run_id = match self.correlate_run(client.as_ref(), &repo, &workflow, previous_run, dispatched_at) {
+            Some(id) => id,
+            None => {
+                println!("⚠️  Dispatched, but the new run did not show up yet");
+                return Ok(TriggerOutcome::Dispatched { run_id: None });
+            }
+        };
+        
+        println!("Run ID: {}", run_id);
+        self.record_run(&state_mgr, &repo, run_id, "queued", None);
+        
+        if !options.watch {
+            return Ok(TriggerOutcome::Dispatched { run_id: Some(run_id) });
+        }
+        
+        self.watch_run(client.as_ref(), &state_mgr, &repo, run_id)
+    }
+    
+    /// The newest `per_page` runs of the workflow on every active fork,
+    /// each fork's listing failing on its own.
+    pub fn recent_runs(&self, per_page: u32) -> Result<Vec<(String, Result<Vec<WorkflowRun>>)>> {
+        let state = self.ctx.state().load_state()?;
+        let workflow_file = &settings::get().github.workflow_file;
+        
+        Ok(state
+            .fork_chain
+            .iter()
+            .filter(|node| node.status == ForkStatus::Active)
+            .map(|node| {
+                let runs = self
+                    .client_for(&state, &node.repo)
+                    .and_then(|client| client.list_workflow_runs(&node.repo, workflow_file, None, per_page));
+                (node.repo.clone(), runs)
+            })
+            .collect())
+    }
+    
+    /// `logs --run`: saves and unpacks the logs of the newest failed run of
+    /// `repo` (default: the active fork) under `log_dir/runs`.
+    pub fn fetch_failure_logs(&self, repo: Option<&str>, log_dir: &Path) -> Result<(String, Option<RunLogs>)> {
+        let state_mgr = self.ctx.state();
+        let state = state_mgr.load_state()?;
+        let repo = resolve_repo(&state_mgr, &state, repo, "--run")?;
+        let client = self.client_for(&state, &repo)?;
+        
+        let logs = WorkflowController::fetch_failure_logs(&repo, client.as_ref(), &log_dir.join("runs"), true)?;
+        Ok((repo, logs))
+    }
+    
+    /// `artifacts`: downloads and unpacks the artifacts of run `run_id` of
+    /// `repo` (default: the newest completed run of the active fork) under
+    /// `log_dir/artifacts`. None when no run has completed yet.
+    pub fn collect_artifacts(
+        &self,
+        repo: Option<&str>,
+        run_id: Option<u64>,
+        log_dir: &Path,
+    ) -> Result<(String, Option<RunArtifacts>)> {
+        let state_mgr = self.ctx.state();
+        let state = state_mgr.load_state()?;
+        let repo = resolve_repo(&state_mgr, &state, repo, "repo")?;
+        let client = self.client_for(&state, &repo)?;
+        
+        let run_id = match run_id {
+            Some(run_id) => run_id,
+            None => {
+                let workflow_file = &settings::get().github.workflow_file;
+                match client.list_workflow_runs(&repo, workflow_file, Some("completed"), 1)?.first() {
+                    Some(run) => run.id,
+                    None => return Ok((repo, None)),
+                }
+            }
+        };
+        
+        let artifacts = WorkflowController::collect_artifacts(&repo, run_id, client.as_ref(), &log_dir.join("artifacts"))?;
+        Ok((repo, Some(artifacts)))
+    }
+    
+    /// `jobs`: the per-job breakdown of run `run_id` of `repo` (default:
+    /// the newest completed run of the active fork). None when there is no
+    /// completed run.
+    pub fn run_report(&self, repo: Option<&str>, run_id: Option<u64>) -> Result<(String, Option<RunReport>)> {
+        let state_mgr = self.ctx.state();
+        let state = state_mgr.load_state()?;
+        let repo = resolve_repo(&state_mgr, &state, repo, "repo")?;
+        let client = self.client_for(&state, &repo)?;
+        
+        let run_id = match run_id {
+            Some(run_id) => run_id,
+            None => {
+                let workflow_file = &settings::get().github.workflow_file;
+                match client.list_workflow_runs(&repo, workflow_file, Some("completed"), 1)?.first() {
+                    Some(run) => run.id,
+                    None => return Ok((repo, None)),
+                }
+            }
+        };
+        
+        let report = WorkflowController::run_report(&repo, run_id, client.as_ref())?;
+        Ok((repo, Some(report)))
+    }
+    
+    /// `rerun`: re-runs run `run_id` of `repo` (default: the newest failed
+    /// run of the active fork) and keeps re-running it while it fails, up
+    /// to `max_attempts` times. None when there is no failed run to take.
+    pub fn rerun(
+        &self,
+        repo: Option<&str>,
+        run_id: Option<u64>,
+        max_attempts: u32,
+        all_jobs: bool,
+    ) -> Result<(String, Option<RerunReport>)> {
+        let state_mgr = self.ctx.state();
+        let state = state_mgr.load_state()?;
+        let repo = resolve_repo(&state_mgr, &state, repo, "repo")?;
+        let client = self.client_for(&state, &repo)?;
+        
+        let run_id = match run_id {
+            Some(run_id) => run_id,
+            None => match client.latest_failed_run(&repo)? {
+                Some(run_id) => run_id,
+                None => return Ok((repo, None)),
+            },
+        };
+        
+        let on_progress = |progress: &RunProgress| println!("[{}] {}", Utc::now().format("%H:%M:%S"), progress.summary());
+        let watch = RunWatch { sleeper: self.sleeper.as_ref(), cancel: &self.cancel, on_progress: &on_progress };
+        let _interruptible = cancel::interruptible();
+        let report = WorkflowController::auto_rerun(&repo, run_id, client.as_ref(), max_attempts, all_jobs, &watch)?;
+        let status = if report.conclusion == "timeout" { "in_progress" } else { "completed" };
+        let conclusion = Some(report.conclusion.clone()).filter(|_| status == "completed");
+        self.record_run(&state_mgr, &repo, run_id, status, conclusion);
+        Ok((repo, Some(report)))
+    }
+    
+    fn client_for(&self, state: &crate::core::OrchestratorState, repo: &str) -> Result<Box<dyn GitHubApi>> {
+        let account = self.resolve_account(state, repo, None)?;
+        let proxy = self.ctx.proxy_url(&account.token)?;
+        Ok((self.client_factory)(&account, proxy))
+    }
+    
+    fn resolve_account(
+        &self,
+        state: &crate::core::OrchestratorState,
+        repo: &str,
+        username: Option<&str>,
+    ) -> Result<account::AccountInfo> {
+        self.ctx.accounts()?.resolve_for_repo(state, repo, username).cloned()
+    }
+    
+    /// The run GitHub created for a dispatch issued at `dispatched_at`:
+    /// a dispatched run of `workflow` no older than that (less
+    /// CLOCK_SLACK_SECS) and other than `previous`.
+    fn correlate_run(
+        &self,
+        client: &dyn GitHubApi,
+        repo: &str,
+        workflow: &str,
+        previous: Option<u64>,
+        dispatched_at: DateTime<Utc>,
+    ) -> Option<u64> {
+        let created_after = dispatched_at - chrono::Duration::seconds(CLOCK_SLACK_SECS);
+        
+        for _ in 0..CORRELATE_ATTEMPTS {
+            self.sleeper.sleep("dispatch_poll", Duration::from_millis(settings::get().delays.dispatch_poll_ms));
+            
+            match client.get_latest_workflow_run(repo, workflow, Some(created_after)) {
+                Ok(Some(id)) if Some(id) != previous => return Some(id),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to look up dispatched run: {}", e),
+            }
+        }
+        
+        None
+    }
+    
+    /// Prints a progress line per poll until run `run_id` completes,
+    /// keeping the chain node's run up to date as its status changes.
+    fn watch_run(
+        &self,
+        client: &dyn GitHubApi,
+        state_mgr: &StateManager,
+        repo: &str,
+        run_id: u64,
+    ) -> Result<TriggerOutcome> {
+        let monitor = &settings::get().monitor;
+        let last_status = RefCell::new(String::new());
+        let on_progress = |progress: &RunProgress| {
+            println!("[{}] {}", Utc::now().format("%H:%M:%S"), progress.summary());
+            if *last_status.borrow() != progress.status {
+                self.record_run(state_mgr, repo, run_id, &progress.status, progress.conclusion.clone());
+                last_status.replace(progress.status.clone());
+            }
+        };
+        let watch = RunWatch { sleeper: self.sleeper.as_ref(), cancel: &self.cancel, on_progress: &on_progress };
+        
+        let _interruptible = cancel::interruptible();
+        match WorkflowController::watch_run(repo, run_id, client, monitor.stuck_run_minutes, &watch)?.as_str() {
+            "timeout" => {
+                println!("⏱️  Gave up watching run #{} after {} minutes", run_id, monitor.stuck_run_minutes);
+                Ok(TriggerOutcome::TimedOut { run_id })
+            }
+            "queued" => {
+                let stuck = StuckRun {
+                    repo: repo.to_string(),
+                    run_id,
+                    queued_minutes: monitor.stuck_queued_minutes as i64,
+                };
+                println!("⚠️  {}", stuck.message());
+                if let Err(e) = self.ctx.alerts().and_then(|alerts| alerts.send_alert(&stuck.message())) {
+                    warn!("Could not send alert: {:#}", e);
+                }
+                Ok(TriggerOutcome::Queued { run_id })
+            }
+            conclusion => {
+                println!("Run #{} finished: {}", run_id, conclusion);
+                let healthy = match conclusion {
+                    "success" => true,
+                    // A matrix run fails when one node does; the jobs tell how bad it is
+                    _ => match WorkflowController::run_report(repo, run_id, client) {
+                        Ok(report) => {
+                            print_run_report(repo, &report);
+                            report.is_healthy()
+                        }
+                        Err(e) => {
+                            warn!("Could not list the jobs of run #{}: {:#}", run_id, e);
+                            false
+                        }
+                    },
+                };
+                Ok(TriggerOutcome::Completed { run_id, conclusion: conclusion.to_string(), healthy })
+            }
+        }
+    }
+    
+    /// Stores the run on the chain node so status views can show it without API calls.
+    fn record_run(
+        &self,
+        state_mgr: &StateManager,
+        repo: &str,
+        run_id: u64,
+        status: &str,
+        conclusion: Option<String>,
+    ) {
+        let result = (|| -> Result<()> {
+            let mut state = state_mgr.load_state()?;
+            
+            if let Some(node) = state.fork_chain.iter_mut().find(|n| n.repo == repo) {
+                let started_at = match &node.last_run {
+                    Some(run) if run.run_id == run_id => run.started_at,
+                    _ => Utc::now(),
+                };
+                
+                node.last_run = Some(RunSnapshot {
+                    run_id,
+                    status: status.to_string(),
+                    conclusion,
+                    started_at,
+                    checked_at: Utc::now(),
+                });
+                state_mgr.save_state(&state)?;
+            }
+            
+            Ok(())
+        })();
+        
+        if let Err(e) = result {
+            warn!("Failed to record run #{} in state: {}", run_id, e);
+        }
+    }
+}
+
+/// `repo` when given (checked to be owner/name), else the active fork.
+/// `flag` names the option in error messages.
+fn resolve_repo(
+    state_mgr: &StateManager,
+    state: &crate::core::OrchestratorState,
+    repo: Option<&str>,
+    flag: &str,
+) -> Result<String> {
+    match repo {
+        Some(repo) => {
+            if repo.split('/').count() != 2 {
+                bail!(ConfigError::new(format!("Invalid {} '{}', expected owner/name", flag, repo)));
+            }
+            Ok(repo.to_string())
+        }
+        None => state_mgr
+            .get_active_fork(state)
+            .map(|node| node.repo.clone())
+            .with_context(|| ConfigError::new(format!(
+                "No active fork in state; pass {} <owner/name> or create the fork chain first",
+                flag
+            ))),
+    }
+}
+
+/// Prints what `Trigger::collect_artifacts` unpacked.
+pub fn print_artifacts(repo: &str, artifacts: Option<&RunArtifacts>) {
+    let artifacts = match artifacts {
+        Some(artifacts) => artifacts,
+        None => {
+            println!("ℹ️  No completed runs in {}", repo);
+            return;
+        }
+    };
+    
+    if artifacts.artifacts.is_empty() && artifacts.expired.is_empty() {
+        println!("ℹ️  Run #{} in {} uploaded no artifacts", artifacts.run_id, repo);
+        return;
+    }
+    println!("📦 Artifacts of run #{} in {}: {}", artifacts.run_id, repo, artifacts.dir.display());
+    for (name, files) in &artifacts.artifacts {
+        println!("   {} ({} files)", name, files.len());
+    }
+    for name in &artifacts.expired {
+        println!("   {} (expired)", name);
+    }
+}
+
+/// Prints how `Trigger::rerun` went.
+pub fn print_rerun(repo: &str, report: Option<&RerunReport>) {
+    match report {
+        Some(report) if report.conclusion == "timeout" => println!(
+            "⏱️  Run #{} in {} still not done after {} re-runs",
+            report.run_id, repo, report.attempts
+        ),
+        Some(report) if report.conclusion == "queued" => println!(
+            "⚠️  Run #{} in {} is stuck in the queue after {} re-runs: {}",
+            report.run_id, repo, report.attempts, workflow::run_url(repo, report.run_id)
+        ),
+        Some(report) => println!(
+            "{} Run #{} in {} finished {} after {} re-runs",
+            if report.conclusion == "success" { "✅" } else { "❌" },
+            report.run_id, repo, report.conclusion, report.attempts
+        ),
+        None => println!("ℹ️  No failed runs in {}", repo),
+    }
+}
+
+/// Prints a run's jobs with their durations, then how many succeeded.
+pub fn print_run_report(repo: &str, report: &RunReport) {
+    println!(
+        "Run #{} in {}: {} {}",
+        report.run_id,
+        repo,
+        report.status,
+        report.conclusion.as_deref().unwrap_or("")
+    );
+    println!("   {:<30} {:<12} {:<11} DURATION", "JOB", "STATUS", "CONCLUSION");
+    for job in &report.jobs {
+        let duration = match (job.started_at, job.completed_at) {
+            (Some(started), Some(completed)) => {
+                let secs = (completed - started).num_seconds().max(0);
+                format!("{}m {:02}s", secs / 60, secs % 60)
+            }
+            _ => "-".to_string(),
+        };
+        println!(
+            "   {:<30} {:<12} {:<11} {}",
+            job.name,
+            job.status,
+            job.conclusion.as_deref().unwrap_or("-"),
+            duration
+        );
+    }
+    
+    match (report.succeeded(), report.success_percent()) {
+        ((succeeded, finished), Some(percent)) => println!(
+            "{} {}/{} jobs succeeded ({:.0}%)",
+            if report.is_healthy() { "✅" } else { "❌" },
+            succeeded,
+            finished,
+            percent
+        ),
+        _ => println!("ℹ️  No job has finished yet"),
+    }
+}
+
+/// Prints the runs `Trigger::recent_runs` listed for `repo`, newest first.
+pub fn print_runs(repo: &str, runs: &[WorkflowRun]) {
+    println!("{}", repo);
+    if runs.is_empty() {
+        println!("   no runs yet");
+        return;
+    }
+    
+    println!("   {:<12} {:<12} {:<11} {:<18} STARTED", "RUN", "STATUS", "CONCLUSION", "EVENT");
+    for run in runs {
+        println!(
+            "   {:<12} {:<12} {:<11} {:<18} {}",
+            run.id,
+            run.status,
+            run.conclusion.as_deref().unwrap_or("-"),
+            run.event,
+            run.created_at.format("%Y-%m-%d %H:%M UTC")
+        );
+    }
+}
+
+/// Prints what `Trigger::fetch_failure_logs` saved.
+pub fn print_run_logs(repo: &str, logs: Option<&RunLogs>) {
+    match logs {
+        Some(logs) => {
+            println!("📥 Logs of failed run #{} in {}: {}", logs.run_id, repo, logs.archive.display());
+            for file in &logs.files {
+                println!("   {}", file.display());
+            }
+        }
+        None => println!("ℹ️  No failed runs in {}", repo),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    #[test]
+    fn test_parse_inputs() {
+        assert!(parse_inputs(&[]).unwrap().is_none());
+        
+        let inputs = parse_inputs(&["batch=2".to_string(), "mode=fast=yes".to_string()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(inputs["batch"], "2");
+        assert_eq!(inputs["mode"], "fast=yes");
+        
+        assert!(parse_inputs(&["novalue".to_string()]).is_err());
+        assert!(parse_inputs(&["=x".to_string()]).is_err());
+    }
+    
+    #[test]
+    fn test_missing_active_fork_is_actionable() {
+        let temp_dir = tempdir().unwrap();
+        let trigger = Trigger::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()));
+        
+        let err = trigger.run(&TriggerOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("--repo"));
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+    }
+    
+    #[test]
+    fn test_outcome_exit_codes() {
+        assert_eq!(TriggerOutcome::Dispatched { run_id: None }.exit_code(), exit_code::SUCCESS);
+        assert_eq!(
+            TriggerOutcome::Completed { run_id: 1, conclusion: "success".to_string(), healthy: true }.exit_code(),
+            exit_code::SUCCESS
+        );
+        assert_eq!(
+            TriggerOutcome::Completed { run_id: 1, conclusion: "failure".to_string(), healthy: false }.exit_code(),
+            exit_code::FAILURE
+        );
+        // Most of its jobs succeeded
+        assert_eq!(
+            TriggerOutcome::Completed { run_id: 1, conclusion: "failure".to_string(), healthy: true }.exit_code(),
+            exit_code::SUCCESS
+        );
+        assert_eq!(TriggerOutcome::TimedOut { run_id: 1 }.exit_code(), exit_code::TIMED_OUT);
+    }
+}
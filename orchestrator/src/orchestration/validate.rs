@@ -0,0 +1,446 @@
+// src/orchestration/validate.rs - Non-interactive configuration validation
+
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use crate::core::account::{self, AccountManager};
+use crate::core::paths::Paths;
+use crate::core::proxy::{ProxyConfig, ProxyManager};
+use crate::core::settings::{self, ProxyMappingMode};
+use crate::core::token_report::{self, TokenReportEntry};
+use crate::nexus::NexusValidator;
+use crate::utils::platform;
+use crate::utils::pool::{JobOutcome, WorkerPool};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ValidationOptions {
+    pub tokens: bool,
+    pub proxies: bool,
+    pub nodes: bool,
+    pub online: bool,
+    /// Only the tokens due for revalidation; implies `tokens` and `online`
+    pub stale_only: bool,
+}
+
+impl ValidationOptions {
+    /// No category selected means "validate everything".
+    pub fn normalized(self) -> Self {
+        if self.stale_only {
+            Self { tokens: true, online: true, ..self }
+        } else if self.tokens || self.proxies || self.nodes {
+            self
+        } else {
+            Self { tokens: true, proxies: true, nodes: true, ..self }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationSection {
+    pub name: String,
+    pub checked: usize,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl ValidationSection {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            checked: 0,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    pub online: bool,
+    pub sections: Vec<ValidationSection>,
+    /// One per token checked online, for `--output`
+    #[serde(skip)]
+    pub tokens: Vec<TokenReportEntry>,
+}
+
+impl ValidationReport {
+    pub fn has_errors(&self) -> bool {
+        self.sections.iter().any(|s| !s.errors.is_empty())
+    }
+    
+    pub fn print(&self) {
+        for section in &self.sections {
+            let icon = if !section.errors.is_empty() {
+                "❌"
+            } else if !section.warnings.is_empty() {
+                "⚠️ "
+            } else {
+                "✅"
+            };
+            
+            println!(
+                "{} {:<8} {} checked, {} errors, {} warnings",
+                icon,
+                section.name,
+                section.checked,
+                section.errors.len(),
+                section.warnings.len()
+            );
+            
+            for error in &section.errors {
+                println!("     ❌ {}", error);
+            }
+            for warning in &section.warnings {
+                println!("     ⚠️  {}", warning);
+            }
+        }
+        
+        if !self.online {
+            println!("\nℹ️  Offline checks only; pass --online to contact GitHub and test proxies");
+        }
+    }
+}
+
+pub struct ConfigValidator {
+    paths: Paths,
+}
+
+impl ConfigValidator {
+    pub fn new(config_dir: &Path) -> Self {
+        Self {
+            paths: Paths::new(config_dir),
+        }
+    }
+    
+    pub fn run(&self, options: ValidationOptions) -> ValidationReport {
+        let options = options.normalized();
+        let mut sections = Vec::new();
+        let mut tokens = Vec::new();
+        
+        if options.tokens {
+            sections.push(self.validate_tokens(options.online, options.stale_only, &mut tokens));
+        }
+        if options.proxies {
+            sections.push(self.validate_proxies(options.online));
+        }
+        if options.nodes {
+            sections.push(self.validate_nodes());
+        }
+        
+        ValidationReport {
+            online: options.online,
+            sections,
+            tokens,
+        }
+    }
+    
+    fn read_lines(&self, filename: &str, section: &mut ValidationSection) -> Option<Vec<(usize, String)>> {
+        match fs::read_to_string(self.paths.config_dir().join(filename)) {
+            Ok(content) => Some(
+                platform::config_lines(&content)
+                    .into_iter()
+                    .map(|(i, l)| (i, l.to_string()))
+                    .collect(),
+            ),
+            Err(e) => {
+                section.errors.push(format!("Cannot read {}: {}", filename, e));
+                None
+            }
+        }
+    }
+    
+    /// Online, each token that checks out is recorded as freshly validated,
+    /// and every token checked gets an entry in `report`.
+    fn validate_tokens(&self, online: bool, stale_only: bool, report: &mut Vec<TokenReportEntry>) -> ValidationSection {
+        let mut section = ValidationSection::new("tokens");
+        
+        let lines = match self.read_lines("tokens.txt", &mut section) {
+            Some(lines) => lines,
+            None => return section,
+        };
+        
+        for (i, (line_no, token)) in lines.iter().enumerate() {
+            if !token.starts_with("ghp_") && !token.starts_with("github_pat_") {
+                section.warnings.push(format!(
+                    "tokens.txt:{}: not a ghp_/github_pat_ token, it will be ignored",
+                    line_no
+                ));
+            } else if let Some((first, _)) = lines[..i]
+                .iter()
+                .find(|(_, earlier)| earlier.split_whitespace().next() == token.split_whitespace().next())
+            {
+                section.warnings.push(format!(
+                    "tokens.txt:{}: repeats the token on line {}, it will be ignored",
+                    line_no, first
+                ));
+            }
+        }
+        
+        let mut account_mgr = AccountManager::new(&self.paths);
+        if let Err(e) = account_mgr.load_tokens(&self.paths.tokens_file()) {
+            section.errors.push(e.to_string());
+            return section;
+        }
+        
+        let accounts: Vec<_> = account_mgr
+            .get_all_accounts()
+            .iter()
+            .filter(|account| !stale_only || account.stale)
+            .cloned()
+            .collect();
+        section.checked = accounts.len();
+        
+        if online {
+            let proxies = self.load_proxies();
+            
+            let sweep = WorkerPool::from_settings().run(&accounts, |account| account.token.clone(), |account| {
+                let proxy = proxies.get(account.index).and_then(|p| p.as_ref());
+                account::lookup_token(&account.token, proxy)
+            });
+            
+            for (i, e) in sweep.errors() {
+                section.errors.push(format!("Token #{} ({}): {}", accounts[i].index, accounts[i].username, e));
+            }
+            for (account, outcome) in accounts.iter().zip(&sweep.outcomes) {
+                let proxy = proxies.get(account.index).and_then(|p| p.as_ref());
+                match outcome {
+                    JobOutcome::Done(lookup) => report.push(TokenReportEntry::new(account, Ok(lookup), proxy)),
+                    JobOutcome::Failed(e) => report.push(TokenReportEntry::new(account, Err(format!("{:#}", e)), proxy)),
+                    JobOutcome::Cancelled => {}
+                }
+            }
+            
+            // Accounts left out by --stale-only still count, under their cached names
+            let mut found: Vec<(usize, String)> = account_mgr
+                .iter()
+                .filter(|(index, _)| !accounts.iter().any(|account| account.index == *index))
+                .map(|(index, account)| (index, account.username.clone()))
+                .collect();
+            for (account, outcome) in accounts.iter().zip(&sweep.outcomes) {
+                let JobOutcome::Done(lookup) = outcome else { continue };
+                found.push((account.index, lookup.username.clone()));
+                if let Err(e) = self.paths.ensure_cache_dir().and_then(|_| account_mgr.record_lookup(account.index, lookup)) {
+                    section.warnings.push(format!("Token #{} ({}): validation not recorded: {}", account.index, lookup.username, e));
+                }
+            }
+            found.sort();
+            if let Err(e) = account_mgr.check_unique_usernames(&found) {
+                section.errors.push(e.to_string());
+            }
+            
+            let window = chrono::Duration::days(settings::get().monitor.token_expiry_warning_days as i64);
+            for (account, outcome) in accounts.iter().zip(&sweep.outcomes) {
+                let i = account.index;
+                let JobOutcome::Done(lookup) = outcome else { continue };
+                if let Some(expires_at) = lookup.expires_at {
+                    if expires_at - chrono::Utc::now() <= window {
+                        section.warnings.push(format!(
+                            "Token #{} ({}): expires {}",
+                            i,
+                            lookup.username,
+                            expires_at.format("%Y-%m-%d %H:%M UTC")
+                        ));
+                    }
+                }
+                if let Some(capabilities) = lookup.capabilities {
+                    let missing: Vec<&str> = account::Capability::ALL
+                        .iter()
+                        .filter(|capability| !capabilities.has(**capability))
+                        .map(|capability| capability.name())
+                        .collect();
+                    if !missing.is_empty() {
+                        section.warnings.push(format!("Token #{} ({}): cannot {}", i, lookup.username, missing.join(", ")));
+                    }
+                }
+            }
+        }
+        
+        section
+    }
+    
+    fn load_proxies(&self) -> Vec<Option<ProxyConfig>> {
+        fs::read_to_string(self.paths.proxies_file())
+            .map(|content| {
+                platform::config_lines(&content)
+                    .into_iter()
+                    .map(|(_, l)| ProxyConfig::from_url(l).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    
+    fn validate_proxies(&self, online: bool) -> ValidationSection {
+        let mut section = ValidationSection::new("proxies");
+        
+        let lines = match self.read_lines("proxies.txt", &mut section) {
+            Some(lines) => lines,
+            None => return section,
+        };
+        
+        let mut parsed = Vec::new();
+        
+        for (line_no, line) in &lines {
+            section.checked += 1;
+            
+            match ProxyConfig::from_url(line) {
+                Ok(proxy) => parsed.push(proxy),
+                Err(e) => section.errors.push(format!("proxies.txt:{}: {}", line_no, e)),
+            }
+        }
+        
+        let token_count = fs::read_to_string(self.paths.tokens_file())
+            .map(|c| platform::config_lines(&c).into_iter().filter_map(|(_, l)| account::parse_token_line(l)).count())
+            .unwrap_or(0);
+        
+        let one_per_token = settings::get().proxy.mapping_mode == ProxyMappingMode::Index;
+        
+        if one_per_token && lines.len() < token_count {
+            section.errors.push(format!(
+                "Not enough proxies: {} proxies for {} tokens (1 PAT = 1 proxy)",
+                lines.len(),
+                token_count
+            ));
+        }
+        
+        if online {
+            let proxy_mgr = ProxyManager::new(&self.paths);
+            
+            let sweep = WorkerPool::from_settings().run(
+                &parsed,
+                |proxy| format!("{}:{}", proxy.host, proxy.port),
+                |proxy| proxy_mgr.test_proxy(proxy),
+            );
+            
+            for (proxy, outcome) in parsed.iter().zip(sweep.outcomes) {
+                match outcome {
+                    JobOutcome::Done(true) | JobOutcome::Cancelled => {}
+                    JobOutcome::Done(false) => section.errors.push(format!("{}:{} failed the connectivity test", proxy.host, proxy.port)),
+                    JobOutcome::Failed(e) => section.errors.push(format!("{}:{}: {}", proxy.host, proxy.port, e)),
+                }
+            }
+        }
+        
+        section
+    }
+    
+    fn validate_nodes(&self) -> ValidationSection {
+        let mut section = ValidationSection::new("nodes");
+        
+        let nodes = self.read_lines("nodes.txt", &mut section);
+        let wallets = self.read_lines("wallets.txt", &mut section);
+        
+        if let Some(nodes) = &nodes {
+            section.checked = nodes.len();
+            
+            for (line_no, node_id) in nodes {
+                if let Err(e) = NexusValidator::validate_node_id(node_id) {
+                    section.errors.push(format!("nodes.txt:{}: {}", line_no, e));
+                }
+            }
+        }
+        
+        if let Some(wallets) = &wallets {
+            for (line_no, wallet) in wallets {
+                if let Err(e) = NexusValidator::validate_wallet(wallet) {
+                    section.errors.push(format!("wallets.txt:{}: {}", line_no, e));
+                }
+            }
+        }
+        
+        if let (Some(nodes), Some(wallets)) = (&nodes, &wallets) {
+            if nodes.is_empty() {
+                section.errors.push("No node IDs found in nodes.txt".to_string());
+            } else if nodes.len() != wallets.len() {
+                section.errors.push(format!(
+                    "Node IDs and wallets count mismatch: {} nodes vs {} wallets",
+                    nodes.len(),
+                    wallets.len()
+                ));
+            }
+        }
+        
+        section
+    }
+}
+
+/// `output`, when given, takes the per-token report (which needs `tokens`
+/// and `online`): a file, or `json`/`csv` to print it instead of the summary.
+pub fn run_validate_command(config_dir: &Path, options: ValidationOptions, json: bool, output: Option<&str>) -> Result<bool> {
+    let options = match output {
+        Some(_) => ValidationOptions { tokens: true, online: true, ..options },
+        None => options,
+    };
+    let report = ConfigValidator::new(config_dir).run(options);
+    
+    let exported = match output {
+        Some(output) => Some(token_report::export_token_report(&report.tokens, output)?),
+        None => None,
+    };
+    
+    match exported {
+        // The report went to stdout
+        Some(None) => {}
+        _ if json => println!("{}", serde_json::to_string_pretty(&report)?),
+        _ => {
+            report.print();
+            if let Some(Some(path)) = exported {
+                println!("\n📄 Token report written to {}", path.display());
+            }
+        }
+    }
+    
+    Ok(!report.has_errors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+    
+    const WALLET: &str = "0x8254a986319461bf29ae35940a96786e507ad9ac";
+    
+    #[test]
+    fn test_clean_config_dir_validates_offline() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        
+        fs::write(dir.join("tokens.txt"), "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb\n").unwrap();
+        fs::write(dir.join("proxies.txt"), "http://u:p@1.2.3.4:8080\nhttp://u:p@1.2.3.5:8080\n").unwrap();
+        fs::write(dir.join("nodes.txt"), "node_abc123\n").unwrap();
+        fs::write(dir.join("wallets.txt"), format!("{}\n", WALLET)).unwrap();
+        
+        let report = ConfigValidator::new(dir).run(ValidationOptions {
+            tokens: false,
+            proxies: false,
+            nodes: false,
+            online: false,
+            stale_only: false,
+        });
+        
+        assert_eq!(report.sections.len(), 3);
+        assert!(!report.has_errors(), "{:?}", report);
+        assert!(!dir.join("cache").exists());
+    }
+    
+    #[test]
+    fn test_errors_carry_line_numbers() {
+        let temp_dir = tempdir().unwrap();
+        let dir = temp_dir.path();
+        
+        fs::write(dir.join("tokens.txt"), "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb\n").unwrap();
+        fs::write(dir.join("proxies.txt"), "http://u:p@1.2.3.4:8080\n\nnot-a-proxy\n").unwrap();
+        
+        let report = ConfigValidator::new(dir).run(ValidationOptions {
+            tokens: false,
+            proxies: true,
+            nodes: false,
+            online: false,
+            stale_only: false,
+        });
+        
+        assert_eq!(report.sections.len(), 1);
+        assert!(report.has_errors());
+        assert!(report.sections[0].errors[0].starts_with("proxies.txt:3:"));
+    }
+}
@@ -0,0 +1,142 @@
+// src/utils/cancel.rs - Ctrl-C handling and cooperative cancellation
+
+use log::warn;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use crate::utils::delay::Sleeper;
+use crate::utils::exit_code;
+
+/// Longest uninterrupted sleep in `CancelToken::sleep`.
+const SLEEP_SLICE: Duration = Duration::from_secs(1);
+
+/// Cooperative cancellation: once set, steps that have not started are skipped.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+    
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+    
+    /// Sleeps `duration` in short slices, returning early (false) when cancelled.
+    pub fn sleep(&self, sleeper: &dyn Sleeper, name: &str, duration: Duration) -> bool {
+        let mut left = duration;
+        
+        while !left.is_zero() {
+            if self.is_cancelled() {
+                return false;
+            }
+            let slice = left.min(SLEEP_SLICE);
+            sleeper.sleep(name, slice);
+            left -= slice;
+        }
+        
+        !self.is_cancelled()
+    }
+    
+    fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// The operation stopped at a step boundary after Ctrl-C.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+static CTRL_C: OnceLock<CancelToken> = OnceLock::new();
+static INTERRUPTIBLE: AtomicUsize = AtomicUsize::new(0);
+
+/// The token Ctrl-C cancels. Inside an `interruptible()` section the first
+/// Ctrl-C lets the current step finish and skips the rest; a second one, or
+/// one outside such a section, exits.
+pub fn ctrl_c() -> CancelToken {
+    CTRL_C
+        .get_or_init(|| {
+            let token = CancelToken::new();
+            let handler_token = token.clone();
+            
+            let installed = ctrlc::set_handler(move || {
+                if INTERRUPTIBLE.load(Ordering::SeqCst) > 0 && !handler_token.is_cancelled() {
+                    eprintln!("\nCancelling: the current step will finish, press Ctrl-C again to quit");
+                    handler_token.cancel();
+                } else {
+                    std::process::exit(exit_code::INTERRUPTED as i32);
+                }
+            });
+            
+            if let Err(e) = installed {
+                warn!("Could not install the Ctrl-C handler: {}", e);
+            }
+            
+            token
+        })
+        .clone()
+}
+
+/// While held, Ctrl-C cancels instead of exiting. Sections nest; when the
+/// outermost one ends the Ctrl-C token is cleared for the next operation.
+pub struct Interruptible(());
+
+pub fn interruptible() -> Interruptible {
+    INTERRUPTIBLE.fetch_add(1, Ordering::SeqCst);
+    Interruptible(())
+}
+
+impl Drop for Interruptible {
+    fn drop(&mut self) {
+        if INTERRUPTIBLE.fetch_sub(1, Ordering::SeqCst) == 1 {
+            if let Some(token) = CTRL_C.get() {
+                token.reset();
+            }
+        }
+    }
+}
+
+/// Whether `err` is, or wraps, a cancellation.
+pub fn is_cancelled(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<Cancelled>().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::delay::RecordingSleeper;
+    
+    #[test]
+    fn test_check_and_sleep_stop_once_cancelled() {
+        let token = CancelToken::new();
+        let sleeper = RecordingSleeper::default();
+        
+        assert!(token.sleep(&sleeper, "watch_interval", Duration::from_millis(2500)));
+        assert_eq!(sleeper.count("watch_interval"), 3);
+        
+        token.clone().cancel();
+        assert!(!token.sleep(&sleeper, "watch_interval", Duration::from_secs(60)));
+        assert_eq!(sleeper.count("watch_interval"), 3);
+        
+        let err = anyhow::Error::from(Cancelled).context("Rotation stopped");
+        assert!(is_cancelled(&err));
+        assert_eq!(exit_code::for_error(&err), exit_code::INTERRUPTED);
+        
+        let other = anyhow::anyhow!("boom").context("Rotation stopped");
+        assert!(!is_cancelled(&other));
+    }
+}
@@ -2,7 +2,7 @@
 
 use anyhow::{Result, Context};
 use std::ffi::{CString, CStr};
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_void};
 
 extern "C" {
     fn crypto_init() -> i32;
@@ -13,8 +13,32 @@ extern "C" {
         output_len: *mut usize,
     ) -> i32;
     fn crypto_free(ptr: *mut c_char);
+    // libsodium itself
+    fn randombytes_buf(buf: *mut c_void, size: usize);
+    fn crypto_secretbox_easy(c: *mut u8, m: *const u8, mlen: u64, n: *const u8, k: *const u8) -> i32;
+    fn crypto_secretbox_open_easy(m: *mut u8, c: *const u8, clen: u64, n: *const u8, k: *const u8) -> i32;
+    fn sodium_base64_encoded_len(bin_len: usize, variant: i32) -> usize;
+    fn sodium_bin2base64(b64: *mut c_char, b64_maxlen: usize, bin: *const u8, bin_len: usize, variant: i32) -> *mut c_char;
+    fn sodium_base642bin(
+        bin: *mut u8,
+        bin_maxlen: usize,
+        b64: *const c_char,
+        b64_len: usize,
+        ignore: *const c_char,
+        bin_len: *mut usize,
+        b64_end: *const *const c_char,
+        variant: i32,
+    ) -> i32;
 }
 
+/// sodium_base64_VARIANT_ORIGINAL: the standard alphabet, padded
+const BASE64_ORIGINAL: i32 = 1;
+
+/// Key length of `secretbox_seal`
+pub const SECRETBOX_KEY_LEN: usize = 32;
+const SECRETBOX_NONCE_LEN: usize = 24;
+const SECRETBOX_MAC_LEN: usize = 16;
+
 pub fn init_crypto() -> Result<()> {
     unsafe {
         let result = crypto_init();
@@ -31,9 +55,10 @@ pub fn encrypt_for_github(public_key_b64: &str, secret_value: &str) -> Result<St
     let plaintext_c = CString::new(secret_value)
         .context("Invalid secret value string")?;
     
-    const MAX_OUTPUT: usize = 8192; // 8KB should be enough
-    let mut output: Vec<u8> = vec![0u8; MAX_OUTPUT];
-    let mut output_len = MAX_OUTPUT;
+    // Base64 of the sealed box (48 bytes of overhead) plus the terminating nul
+    let max_output = (secret_value.len() + 48).div_ceil(3) * 4 + 1;
+    let mut output: Vec<u8> = vec![0u8; max_output];
+    let mut output_len = max_output;
     
     unsafe {
         let result = encrypt_secret(
@@ -47,8 +72,9 @@ pub fn encrypt_for_github(public_key_b64: &str, secret_value: &str) -> Result<St
             anyhow::bail!("Encryption failed with code: {}", result);
         }
         
-        // Convert output to Rust String
-        let encrypted_str = CStr::from_bytes_until_nul(&output[..output_len])
+        // Convert output to Rust String; output_len is now the length
+        // without the nul, so look for it in the whole buffer
+        let encrypted_str = CStr::from_bytes_until_nul(&output)
             .context("Invalid C string from encryption")?
             .to_str()
             .context("Invalid UTF-8 in encrypted output")?
@@ -58,6 +84,104 @@ pub fn encrypt_for_github(public_key_b64: &str, secret_value: &str) -> Result<St
     }
 }
 
+/// `len` bytes from libsodium's CSPRNG.
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    unsafe {
+        randombytes_buf(bytes.as_mut_ptr() as *mut c_void, len);
+    }
+    bytes
+}
+
+/// Encrypts and authenticates `plaintext` under `key` (XSalsa20-Poly1305).
+/// The result is a random nonce followed by the ciphertext.
+pub fn secretbox_seal(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    if key.len() != SECRETBOX_KEY_LEN {
+        anyhow::bail!("Secretbox key must be {} bytes, got {}", SECRETBOX_KEY_LEN, key.len());
+    }
+    
+    let nonce = random_bytes(SECRETBOX_NONCE_LEN);
+    let mut sealed = vec![0u8; SECRETBOX_NONCE_LEN + SECRETBOX_MAC_LEN + plaintext.len()];
+    sealed[..SECRETBOX_NONCE_LEN].copy_from_slice(&nonce);
+    
+    let result = unsafe {
+        crypto_secretbox_easy(
+            sealed[SECRETBOX_NONCE_LEN..].as_mut_ptr(),
+            plaintext.as_ptr(),
+            plaintext.len() as u64,
+            nonce.as_ptr(),
+            key.as_ptr(),
+        )
+    };
+    if result != 0 {
+        anyhow::bail!("Secretbox encryption failed with code: {}", result);
+    }
+    Ok(sealed)
+}
+
+/// Reverses `secretbox_seal`; fails on a wrong key or altered data.
+pub fn secretbox_open(key: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    if key.len() != SECRETBOX_KEY_LEN {
+        anyhow::bail!("Secretbox key must be {} bytes, got {}", SECRETBOX_KEY_LEN, key.len());
+    }
+    if sealed.len() < SECRETBOX_NONCE_LEN + SECRETBOX_MAC_LEN {
+        anyhow::bail!("Sealed data is too short");
+    }
+    
+    let (nonce, ciphertext) = sealed.split_at(SECRETBOX_NONCE_LEN);
+    let mut plaintext = vec![0u8; ciphertext.len() - SECRETBOX_MAC_LEN];
+    let result = unsafe {
+        crypto_secretbox_open_easy(
+            plaintext.as_mut_ptr(),
+            ciphertext.as_ptr(),
+            ciphertext.len() as u64,
+            nonce.as_ptr(),
+            key.as_ptr(),
+        )
+    };
+    if result != 0 {
+        anyhow::bail!("Decryption failed: wrong key or corrupted data");
+    }
+    Ok(plaintext)
+}
+
+/// Standard, padded base64 of `bin`.
+pub fn base64_encode(bin: &[u8]) -> String {
+    unsafe {
+        let mut b64 = vec![0u8; sodium_base64_encoded_len(bin.len(), BASE64_ORIGINAL)];
+        sodium_bin2base64(b64.as_mut_ptr() as *mut c_char, b64.len(), bin.as_ptr(), bin.len(), BASE64_ORIGINAL);
+        // Drop the terminating nul; the alphabet is ASCII
+        b64.pop();
+        String::from_utf8_unchecked(b64)
+    }
+}
+
+/// Decodes standard, padded base64, skipping line breaks as GitHub puts
+/// them in file contents.
+pub fn base64_decode(b64: &str) -> Result<Vec<u8>> {
+    let mut bin = vec![0u8; b64.len() / 4 * 3 + 3];
+    let mut bin_len = 0;
+    let ignore = c"\r\n";
+    
+    let result = unsafe {
+        sodium_base642bin(
+            bin.as_mut_ptr(),
+            bin.len(),
+            b64.as_ptr() as *const c_char,
+            b64.len(),
+            ignore.as_ptr(),
+            &mut bin_len,
+            std::ptr::null(),
+            BASE64_ORIGINAL,
+        )
+    };
+    if result != 0 {
+        anyhow::bail!("Invalid base64");
+    }
+    bin.truncate(bin_len);
+    Ok(bin)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,4 +206,42 @@ mod tests {
         assert!(!encrypted.is_empty());
         assert!(encrypted.len() > secret.len()); // Encrypted data is larger
     }
+    
+    #[test]
+    fn test_encrypt_largest_secret() {
+        init_crypto().unwrap();
+        
+        let public_key = "hBSZF+rsRNIWNzMC2DUc6lE1R0CKT8pFqPxQH+2F6zk=";
+        let secret = "x".repeat(crate::github::secrets::SECRET_SIZE_LIMIT);
+        
+        let encrypted = encrypt_for_github(public_key, &secret).unwrap();
+        assert_eq!(encrypted.len(), (secret.len() + 48).div_ceil(3) * 4);
+    }
+    
+    #[test]
+    fn test_secretbox_round_trip() {
+        init_crypto().unwrap();
+        let key = random_bytes(SECRETBOX_KEY_LEN);
+        
+        let sealed = secretbox_seal(&key, b"hunter2").unwrap();
+        assert_eq!(sealed.len(), 24 + 16 + 7);
+        assert_ne!(secretbox_seal(&key, b"hunter2").unwrap(), sealed);
+        assert_eq!(secretbox_open(&key, &sealed).unwrap(), b"hunter2");
+        
+        assert!(secretbox_open(&random_bytes(SECRETBOX_KEY_LEN), &sealed).is_err());
+        let mut altered = sealed.clone();
+        altered[30] ^= 1;
+        assert!(secretbox_open(&key, &altered).is_err());
+        assert!(secretbox_open(&key, &sealed[..20]).is_err());
+    }
+    
+    #[test]
+    fn test_base64_round_trip() {
+        init_crypto().unwrap();
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"name: Nexus\n"), "bmFtZTogTmV4dXMK");
+        assert_eq!(base64_decode("bmFtZTog\nTmV4dXMK\n").unwrap(), b"name: Nexus\n");
+        assert_eq!(base64_decode(&base64_encode(b"ab")).unwrap(), b"ab");
+        assert!(base64_decode("not base64!").is_err());
+    }
 }
@@ -0,0 +1,122 @@
+// src/utils/delay.rs - Named pauses between GitHub operations
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Fixed pauses, in milliseconds. Loaded from the `[delays]` section of
+/// orchestrator.toml.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Delays {
+    /// Between fork readiness/deletion checks, and before deleting a fork
+    /// whose runs were just cancelled
+    pub fork_poll_ms: u64,
+    /// After pushing the workflow, so GitHub registers it before it is used
+    pub after_deploy_ms: u64,
+    /// Before reading back a secret that was just written
+    pub secret_verify_ms: u64,
+    /// Between writing consecutive secrets to one repo
+    pub between_secrets_ms: u64,
+    /// Between live billing requests for different accounts
+    pub between_billing_checks_ms: u64,
+    /// Between token validation requests
+    pub between_validations_ms: u64,
+    /// Between polls for a just-dispatched workflow run
+    pub dispatch_poll_ms: u64,
+    /// Between checks that cancelled runs have stopped
+    pub run_cancel_poll_ms: u64,
+    /// Before re-running the failed jobs of a run
+    pub rerun_ms: u64,
+    /// Before retrying a call that hit the GitHub rate limit, when the
+    /// response carried no reset headers
+    pub rate_limit_ms: u64,
+}
+
+impl Default for Delays {
+    fn default() -> Self {
+        Self {
+            fork_poll_ms: 5000,
+            after_deploy_ms: 3000,
+            secret_verify_ms: 2000,
+            between_secrets_ms: 1000,
+            between_billing_checks_ms: 2000,
+            between_validations_ms: 1000,
+            dispatch_poll_ms: 5000,
+            run_cancel_poll_ms: 5000,
+            rerun_ms: 30000,
+            rate_limit_ms: 60000,
+        }
+    }
+}
+
+/// Performs pauses, so tests can skip them and check which ones happened.
+pub trait Sleeper: Send + Sync {
+    /// `name` identifies the pause, e.g. the `Delays` field without `_ms`.
+    fn sleep(&self, name: &str, duration: Duration);
+}
+
+/// Really sleeps; used everywhere outside tests.
+pub struct ThreadSleeper;
+
+impl Sleeper for ThreadSleeper {
+    fn sleep(&self, _name: &str, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+pub fn thread_sleeper() -> Arc<dyn Sleeper> {
+    Arc::new(ThreadSleeper)
+}
+
+/// Returns immediately and remembers every pause it was asked for.
+#[derive(Default)]
+pub struct RecordingSleeper {
+    pauses: Mutex<Vec<(String, Duration)>>,
+}
+
+impl RecordingSleeper {
+    pub fn pauses(&self) -> Vec<(String, Duration)> {
+        self.pauses.lock().unwrap().clone()
+    }
+    
+    /// How often the pause called `name` was requested.
+    pub fn count(&self, name: &str) -> usize {
+        self.pauses.lock().unwrap().iter().filter(|(n, _)| n == name).count()
+    }
+}
+
+impl Sleeper for RecordingSleeper {
+    fn sleep(&self, name: &str, duration: Duration) {
+        self.pauses.lock().unwrap().push((name.to_string(), duration));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_recording_sleeper_returns_immediately() {
+        let sleeper = RecordingSleeper::default();
+        let start = std::time::Instant::now();
+        
+        sleeper.sleep("rate_limit", Duration::from_millis(Delays::default().rate_limit_ms));
+        sleeper.sleep("fork_poll", Duration::from_secs(5));
+        sleeper.sleep("fork_poll", Duration::from_secs(5));
+        
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(sleeper.count("fork_poll"), 2);
+        assert_eq!(sleeper.pauses()[0], ("rate_limit".to_string(), Duration::from_secs(60)));
+    }
+    
+    #[test]
+    fn test_delays_partial_toml() {
+        let delays: Delays = toml::from_str("fork_poll_ms = 250").unwrap();
+        assert_eq!(delays.fork_poll_ms, 250);
+        assert_eq!(delays.after_deploy_ms, Delays::default().after_deploy_ms);
+        
+        assert!(toml::from_str::<Delays>("fork_poll_secs = 1").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+// src/utils/duration.rs - Human-friendly duration arguments
+
+use anyhow::{Result, bail};
+use chrono::Duration;
+use crate::utils::exit_code::ConfigError;
+
+/// Parses durations like `90s`, `15m`, `2h`, `7d` or `1w`.
+pub fn parse_duration(raw: &str) -> Result<Duration> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split);
+    
+    let value: i64 = number
+        .parse()
+        .map_err(|_| ConfigError::new(format!("Invalid duration '{}', expected e.g. 30m, 2h, 7d", raw)))?;
+    
+    let duration = match unit {
+        "s" => Duration::try_seconds(value),
+        "m" => Duration::try_minutes(value),
+        "h" => Duration::try_hours(value),
+        "d" => Duration::try_days(value),
+        "w" => Duration::try_weeks(value),
+        _ => bail!(ConfigError::new(format!(
+            "Invalid duration unit in '{}', use s, m, h, d or w",
+            raw
+        ))),
+    };
+    
+    duration.ok_or_else(|| ConfigError::new(format!("Duration '{}' is out of range", raw)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::exit_code;
+    
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s").unwrap(), Duration::seconds(90));
+        assert_eq!(parse_duration("15m").unwrap(), Duration::minutes(15));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::hours(2));
+        assert_eq!(parse_duration(" 7d ").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration("1w").unwrap(), Duration::weeks(1));
+        assert_eq!(parse_duration("0d").unwrap(), Duration::zero());
+    }
+    
+    #[test]
+    fn test_parse_duration_rejects_bad_input() {
+        for raw in ["", "2", "h", "2y", "-3d", "1.5h", "3 d"] {
+            let err = parse_duration(raw).unwrap_err();
+            assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR, "{}", raw);
+        }
+    }
+    
+    #[test]
+    fn test_parse_duration_rejects_out_of_range_values() {
+        for raw in ["300000000000d", "99999999999999w", "9223372036854775807s"] {
+            let err = parse_duration(raw).unwrap_err();
+            assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR, "{}", raw);
+            assert!(err.to_string().contains("out of range"), "{}", err);
+        }
+        assert_eq!(parse_duration("106751991d").unwrap(), Duration::days(106_751_991));
+    }
+}
@@ -0,0 +1,103 @@
+// src/utils/exit_code.rs - Process exit codes shared by all CLI commands
+//
+//   0  success
+//   1  generic failure
+//   2  partial failure (some items succeeded, some failed)
+//   3  configuration or usage error (missing/invalid config files, bad arguments)
+//   4  every account is out of Actions quota
+//   5  another orchestrator instance holds the lock
+//   6  timed out waiting for a workflow run
+// 130  stopped by Ctrl-C (after finishing the step in progress)
+
+use std::fmt;
+use crate::utils::cancel;
+
+pub const SUCCESS: u8 = 0;
+pub const FAILURE: u8 = 1;
+pub const PARTIAL: u8 = 2;
+pub const CONFIG_ERROR: u8 = 3;
+pub const ALL_EXHAUSTED: u8 = 4;
+pub const LOCKED: u8 = 5;
+pub const TIMED_OUT: u8 = 6;
+pub const INTERRUPTED: u8 = 130;
+
+/// Marks an error as caused by configuration or user input rather than a runtime failure.
+/// Use as `.context(ConfigError::new(...))` or `bail!(ConfigError::new(...))`.
+#[derive(Debug)]
+pub struct ConfigError(String);
+
+impl ConfigError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Another instance is already running against the same config directory.
+#[derive(Debug)]
+pub struct LockError(pub String);
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for LockError {}
+
+/// Exit code for a command that failed with `err`.
+pub fn for_error(err: &anyhow::Error) -> u8 {
+    if err.downcast_ref::<ConfigError>().is_some() {
+        CONFIG_ERROR
+    } else if err.downcast_ref::<LockError>().is_some() {
+        LOCKED
+    } else if cancel::is_cancelled(err) {
+        INTERRUPTED
+    } else {
+        FAILURE
+    }
+}
+
+/// Exit code for a batch operation where `succeeded` of `total` items went through.
+pub fn for_counts(succeeded: usize, total: usize) -> u8 {
+    if succeeded >= total {
+        SUCCESS
+    } else if succeeded == 0 {
+        FAILURE
+    } else {
+        PARTIAL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+    
+    #[test]
+    fn test_for_error_finds_marker_under_context() {
+        let err: anyhow::Error = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .context(ConfigError::new("Failed to read tokens.txt"))
+            .context("Failed to load accounts")
+            .unwrap_err();
+        assert_eq!(for_error(&err), CONFIG_ERROR);
+        
+        assert_eq!(for_error(&anyhow!(LockError("locked".to_string()))), LOCKED);
+        assert_eq!(for_error(&anyhow!("gh api failed")), FAILURE);
+    }
+    
+    #[test]
+    fn test_for_counts() {
+        assert_eq!(for_counts(0, 0), SUCCESS);
+        assert_eq!(for_counts(5, 5), SUCCESS);
+        assert_eq!(for_counts(3, 5), PARTIAL);
+        assert_eq!(for_counts(0, 5), FAILURE);
+    }
+}
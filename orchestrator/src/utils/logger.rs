@@ -2,10 +2,42 @@
 
 use chrono::Local;
 use log::{Record, Level, Metadata};
+use std::cell::RefCell;
 use std::fs::{OpenOptions, create_dir_all};
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Mutex;
+use crate::utils::redact;
+
+thread_local! {
+    static SCOPE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Clears the thread's log scope when dropped, see `scoped`.
+pub struct LogScope {
+    previous: Option<String>,
+}
+
+impl Drop for LogScope {
+    fn drop(&mut self) {
+        SCOPE.with(|scope| *scope.borrow_mut() = self.previous.take());
+    }
+}
+
+/// Tags this thread's log lines with `name` (e.g. the repo a worker is on)
+/// until the returned guard drops, so concurrent jobs stay readable.
+pub fn scoped(name: &str) -> LogScope {
+    let previous = SCOPE.with(|scope| scope.borrow_mut().replace(name.to_string()));
+    LogScope { previous }
+}
+
+/// `message` with the thread's log scope in front, if it has one.
+pub fn with_scope(message: &str) -> String {
+    SCOPE.with(|scope| match &*scope.borrow() {
+        Some(name) => format!("[{}] {}", name, message),
+        None => message.to_string(),
+    })
+}
 
 pub struct FileLogger {
     log_dir: PathBuf,
@@ -57,7 +89,7 @@ impl log::Log for FileLogger {
                 "[{}] {} - {}",
                 record.level(),
                 record.target(),
-                record.args()
+                redact::scrub(&with_scope(&record.args().to_string()))
             );
             
             self.log_to_file(category, &message);
@@ -73,3 +105,23 @@ pub fn setup_logging(log_dir: PathBuf) -> Result<(), Box<dyn std::error::Error>>
     log::set_max_level(log::LevelFilter::Info);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_scope_nests_and_ends_with_its_guard() {
+        assert_eq!(with_scope("start"), "start");
+        {
+            let _outer = scoped("alice/nexus-runner");
+            assert_eq!(with_scope("put"), "[alice/nexus-runner] put");
+            {
+                let _inner = scoped("bob/nexus-runner");
+                assert_eq!(with_scope("put"), "[bob/nexus-runner] put");
+            }
+            assert_eq!(with_scope("put"), "[alice/nexus-runner] put");
+        }
+        assert_eq!(with_scope("done"), "done");
+    }
+}
@@ -1,9 +1,18 @@
 // src/utils/mod.rs
 
+pub mod cancel;
 pub mod crypto;
+pub mod delay;
+pub mod duration;
+pub mod exit_code;
 pub mod logger;
+pub mod platform;
+pub mod pool;
+pub mod redact;
 pub mod retry;
 
+pub use cancel::CancelToken;
 pub use crypto::encrypt_for_github;
 pub use logger::setup_logging;
-pub use retry::{retry_with_backoff, RetryConfig};
+pub use pool::WorkerPool;
+pub use retry::{retry_with_backoff, RetryBudget, RetryConfig};
@@ -0,0 +1,190 @@
+// src/utils/platform.rs - Windows/Unix differences: executables, null device, console
+
+use std::env;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+static ANSI_CONSOLE: AtomicBool = AtomicBool::new(true);
+
+/// Where discarded subprocess output goes: `NUL` on Windows, `/dev/null` elsewhere.
+pub fn null_device() -> &'static str {
+    null_device_for(cfg!(windows))
+}
+
+fn null_device_for(windows: bool) -> &'static str {
+    if windows {
+        "NUL"
+    } else {
+        "/dev/null"
+    }
+}
+
+/// File name of an executable: `gh` is `gh.exe` on Windows.
+pub fn executable_name(binary: &str) -> String {
+    executable_name_for(binary, cfg!(windows))
+}
+
+fn executable_name_for(binary: &str, windows: bool) -> String {
+    if windows && Path::new(binary).extension().is_none() {
+        format!("{}.exe", binary)
+    } else {
+        binary.to_string()
+    }
+}
+
+/// Full path of `binary` in one of the PATH directories.
+pub fn find_executable(binary: &str) -> Option<PathBuf> {
+    let name = executable_name(binary);
+    let paths = env::var_os("PATH")?;
+    
+    env::split_paths(&paths)
+        .map(|dir| dir.join(&name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Command for an external tool (gh, git, curl), started by its full path
+/// when it is on PATH so the `.exe` lookup does not depend on the platform.
+pub fn command(binary: &str) -> Command {
+    Command::new(find_executable(binary).unwrap_or_else(|| PathBuf::from(executable_name(binary))))
+}
+
+/// `Child::wait_with_output` with a deadline: past `timeout` the child is
+/// killed and None returned. The output pipes are drained on their own
+/// threads, which are left behind if a grandchild still holds them open.
+pub fn wait_with_timeout(mut child: Child, timeout: Option<Duration>) -> io::Result<Option<Output>> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return child.wait_with_output().map(Some),
+    };
+    
+    let stdout = child.stdout.take().map(|pipe| thread::spawn(move || read_all(pipe)));
+    let stderr = child.stderr.take().map(|pipe| thread::spawn(move || read_all(pipe)));
+    let deadline = Instant::now() + timeout;
+    
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    
+    let collect = |reader: Option<thread::JoinHandle<Vec<u8>>>| {
+        reader.map(|r| r.join().unwrap_or_default()).unwrap_or_default()
+    };
+    
+    Ok(Some(Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    }))
+}
+
+fn read_all(mut pipe: impl Read) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let _ = pipe.read_to_end(&mut buf);
+    buf
+}
+
+/// Turns on ANSI escape handling in Windows consoles. Legacy consoles that
+/// cannot do it get plain output: no colors, and `cls` to clear the screen.
+pub fn init_console() {
+    #[cfg(windows)]
+    {
+        if colored::control::set_virtual_terminal(true).is_err() {
+            colored::control::set_override(false);
+            ANSI_CONSOLE.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// False only on Windows consoles without virtual terminal support.
+pub fn ansi_console() -> bool {
+    ANSI_CONSOLE.load(Ordering::Relaxed)
+}
+
+/// Lines of a one-entry-per-line config file (tokens, proxies, nodes,
+/// wallets) with 1-based line numbers. Tolerates a UTF-8 BOM and LF, CRLF
+/// or CR line endings; entries are trimmed and blank lines skipped.
+pub fn config_lines(content: &str) -> Vec<(usize, &str)> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut lines = Vec::new();
+    let mut rest = content;
+    let mut line_no = 0;
+    
+    while !rest.is_empty() {
+        line_no += 1;
+        let end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+        let line = rest[..end].trim();
+        
+        if !line.is_empty() {
+            lines.push((line_no, line));
+        }
+        
+        rest = &rest[end..];
+        rest = rest.strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix('\n'))
+            .or_else(|| rest.strip_prefix('\r'))
+            .unwrap_or(rest);
+    }
+    
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_null_device_selection() {
+        assert_eq!(null_device_for(true), "NUL");
+        assert_eq!(null_device_for(false), "/dev/null");
+        assert_eq!(null_device(), if cfg!(windows) { "NUL" } else { "/dev/null" });
+    }
+    
+    #[test]
+    fn test_executable_name() {
+        assert_eq!(executable_name_for("gh", true), "gh.exe");
+        assert_eq!(executable_name_for("gh.exe", true), "gh.exe");
+        assert_eq!(executable_name_for("gh", false), "gh");
+    }
+    
+    #[test]
+    fn test_config_lines_line_endings() {
+        let expected = vec![(1, "ghp_aaa"), (3, "ghp_bbb acme")];
+        
+        assert_eq!(config_lines("ghp_aaa\n\nghp_bbb acme\n"), expected);
+        assert_eq!(config_lines("ghp_aaa\r\n\r\nghp_bbb acme\r\n"), expected);
+        assert_eq!(config_lines("ghp_aaa\r\rghp_bbb acme"), expected);
+        assert_eq!(config_lines("\u{feff}ghp_aaa\r\n  \r\n  ghp_bbb acme  "), expected);
+        assert!(config_lines("").is_empty());
+        assert!(config_lines("\r\n\n").is_empty());
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_with_timeout_kills_slow_child() {
+        use std::process::Stdio;
+        
+        let spawn = |script: &str| {
+            Command::new("sh").args(["-c", script]).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap()
+        };
+        
+        let started = Instant::now();
+        let output = wait_with_timeout(spawn("sleep 5"), Some(Duration::from_millis(100))).unwrap();
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(3));
+        
+        let output = wait_with_timeout(spawn("echo done"), Some(Duration::from_secs(5))).unwrap().unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"done\n");
+    }
+}
@@ -0,0 +1,366 @@
+// src/utils/pool.rs - Bounded worker pool for multi-account sweeps
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use crate::core::settings;
+use crate::utils::cancel::{self, CancelToken};
+use crate::utils::delay::Sleeper;
+use crate::utils::exit_code;
+use crate::utils::retry::RetryBudget;
+
+/// Sweep progress, told about every finished job from the worker threads.
+/// `ui::display::ProgressLine` draws it as a terminal progress bar.
+pub trait Progress: Send + Sync {
+    fn start(&self, total: usize);
+    /// One job finished; `ok` is false when it failed.
+    fn advance(&self, ok: bool);
+    fn finish(&self);
+}
+
+/// Reports nothing; the default for sweeps that print their own summary.
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn start(&self, _total: usize) {}
+    fn advance(&self, _ok: bool) {}
+    fn finish(&self) {}
+}
+
+/// What happened to one item of a sweep.
+#[derive(Debug)]
+pub enum JobOutcome<R> {
+    Done(R),
+    Failed(anyhow::Error),
+    /// Skipped because the sweep was cancelled before it started
+    Cancelled,
+}
+
+impl<R> JobOutcome<R> {
+    pub fn is_done(&self) -> bool {
+        matches!(self, JobOutcome::Done(_))
+    }
+    
+    pub fn into_result(self) -> Result<R> {
+        match self {
+            JobOutcome::Done(value) => Ok(value),
+            JobOutcome::Failed(e) => Err(e),
+            JobOutcome::Cancelled => Err(anyhow::anyhow!("Cancelled")),
+        }
+    }
+}
+
+/// Outcomes of a sweep, in the order the items were given.
+#[derive(Debug)]
+pub struct Sweep<R> {
+    pub outcomes: Vec<JobOutcome<R>>,
+}
+
+impl<R> Sweep<R> {
+    pub fn len(&self) -> usize {
+        self.outcomes.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+    
+    pub fn succeeded(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.is_done()).count()
+    }
+    
+    pub fn failed(&self) -> usize {
+        self.outcomes.iter().filter(|o| matches!(o, JobOutcome::Failed(_))).count()
+    }
+    
+    pub fn cancelled(&self) -> usize {
+        self.outcomes.iter().filter(|o| matches!(o, JobOutcome::Cancelled)).count()
+    }
+    
+    /// Item index and error of every failed job.
+    pub fn errors(&self) -> Vec<(usize, &anyhow::Error)> {
+        self.outcomes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, o)| match o {
+                JobOutcome::Failed(e) => Some((i, e)),
+                _ => None,
+            })
+            .collect()
+    }
+    
+    /// Cancelled items count as not done.
+    pub fn exit_code(&self) -> u8 {
+        exit_code::for_counts(self.succeeded(), self.len())
+    }
+}
+
+/// Runs one job per item on a bounded number of threads. Items sharing a
+/// key (e.g. a token) run one after another, never at the same time.
+#[derive(Clone)]
+pub struct WorkerPool {
+    workers: usize,
+    cancel: CancelToken,
+    progress: Arc<dyn Progress>,
+    retry_budget: RetryBudget,
+    pacing: Option<(Arc<dyn Sleeper>, &'static str, Duration)>,
+}
+
+impl WorkerPool {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+            cancel: CancelToken::new(),
+            progress: Arc::new(NoProgress),
+            retry_budget: RetryBudget::unlimited(),
+            pacing: None,
+        }
+    }
+    
+    /// Size and retry budget from the `[pool]` settings, cancelled by Ctrl-C.
+    pub fn from_settings() -> Self {
+        let pool = &settings::get().pool;
+        
+        Self::new(pool.workers)
+            .with_retry_budget(RetryBudget::new(pool.retry_budget))
+            .with_cancel(cancel::ctrl_c())
+    }
+    
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+    
+    pub fn with_progress(mut self, progress: Arc<dyn Progress>) -> Self {
+        self.progress = progress;
+        self
+    }
+    
+    pub fn with_retry_budget(mut self, retry_budget: RetryBudget) -> Self {
+        self.retry_budget = retry_budget;
+        self
+    }
+    
+    /// Makes each worker pause between the jobs it runs, as the sweeps did
+    /// when they ran one account at a time.
+    pub fn with_pacing(mut self, sleeper: Arc<dyn Sleeper>, name: &'static str, pause: Duration) -> Self {
+        self.pacing = Some((sleeper, name, pause));
+        self
+    }
+    
+    /// Shared by the calls of one sweep; hand it to the clients the jobs build.
+    pub fn retry_budget(&self) -> RetryBudget {
+        self.retry_budget.clone()
+    }
+    
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+    
+    /// Runs `job` for every item and returns the outcomes in input order.
+    pub fn run<T, R, K, F>(&self, items: &[T], key: K, job: F) -> Sweep<R>
+    where
+        T: Sync,
+        R: Send,
+        K: Fn(&T) -> String,
+        F: Fn(&T) -> Result<R> + Sync,
+    {
+        let lanes = lanes(items, key);
+        let next_lane = AtomicUsize::new(0);
+        let slots: Mutex<Vec<Option<JobOutcome<R>>>> = Mutex::new(items.iter().map(|_| None).collect());
+        
+        let _interruptible = cancel::interruptible();
+        self.progress.start(items.len());
+        
+        thread::scope(|scope| {
+            for _ in 0..self.workers.min(lanes.len()) {
+                scope.spawn(|| {
+                    let mut ran_before = false;
+                    
+                    while let Some(lane) = lanes.get(next_lane.fetch_add(1, Ordering::SeqCst)) {
+                        for &index in lane {
+                            let outcome = if self.cancel.is_cancelled() {
+                                JobOutcome::Cancelled
+                            } else {
+                                if ran_before {
+                                    self.pause();
+                                }
+                                ran_before = true;
+                                
+                                let outcome = match job(&items[index]) {
+                                    Ok(value) => JobOutcome::Done(value),
+                                    Err(e) => JobOutcome::Failed(e),
+                                };
+                                self.progress.advance(outcome.is_done());
+                                outcome
+                            };
+                            
+                            slots.lock().unwrap()[index] = Some(outcome);
+                        }
+                    }
+                });
+            }
+        });
+        
+        self.progress.finish();
+        
+        Sweep {
+            outcomes: slots
+                .into_inner()
+                .unwrap()
+                .into_iter()
+                .map(|o| o.unwrap_or(JobOutcome::Cancelled))
+                .collect(),
+        }
+    }
+    
+    fn pause(&self) {
+        if let Some((sleeper, name, pause)) = &self.pacing {
+            self.cancel.sleep(sleeper.as_ref(), name, *pause);
+        }
+    }
+}
+
+/// Item indexes grouped by key, lanes in order of each key's first item.
+fn lanes<T>(items: &[T], key: impl Fn(&T) -> String) -> Vec<Vec<usize>> {
+    let mut lanes: Vec<Vec<usize>> = Vec::new();
+    let mut lane_of: HashMap<String, usize> = HashMap::new();
+    
+    for (index, item) in items.iter().enumerate() {
+        let lane = *lane_of.entry(key(item)).or_insert_with(|| {
+            lanes.push(Vec::new());
+            lanes.len() - 1
+        });
+        lanes[lane].push(index);
+    }
+    
+    lanes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::delay::RecordingSleeper;
+    use std::sync::atomic::AtomicU32;
+    
+    #[test]
+    fn test_results_keep_input_order() {
+        let items: Vec<u64> = (0..16).collect();
+        
+        // Early items finish last
+        let sweep = WorkerPool::new(4).run(&items, |i| i.to_string(), |&i| {
+            thread::sleep(Duration::from_millis(16 - i));
+            Ok(i * 10)
+        });
+        
+        let values: Vec<u64> = sweep.outcomes.into_iter().map(|o| o.into_result().unwrap()).collect();
+        assert_eq!(values, items.iter().map(|i| i * 10).collect::<Vec<_>>());
+    }
+    
+    #[test]
+    fn test_distinct_keys_run_up_to_worker_count() {
+        let items: Vec<u32> = (0..16).collect();
+        let max_parallel = AtomicU32::new(0);
+        let running = AtomicU32::new(0);
+        
+        let sweep = WorkerPool::new(4).run(&items, |i| i.to_string(), |&i| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_parallel.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            running.fetch_sub(1, Ordering::SeqCst);
+            
+            if i % 5 == 0 {
+                anyhow::bail!("item {} failed", i);
+            }
+            Ok(i)
+        });
+        
+        let max_parallel = max_parallel.load(Ordering::SeqCst);
+        assert!((2..=4).contains(&max_parallel), "{} jobs at once", max_parallel);
+        // Failed items do not stop the others
+        assert_eq!((sweep.succeeded(), sweep.failed()), (12, 4));
+    }
+    
+    #[test]
+    fn test_same_key_runs_serially_in_order() {
+        // Three tokens, four calls each
+        let items: Vec<(String, u32)> = (0..12).map(|i| (format!("ghp_{}", i % 3), i)).collect();
+        let in_flight: Mutex<HashMap<String, u32>> = Mutex::new(HashMap::new());
+        let started: Mutex<Vec<(String, u32)>> = Mutex::new(Vec::new());
+        let max_parallel = AtomicU32::new(0);
+        let running = AtomicU32::new(0);
+        
+        let sweep = WorkerPool::new(3).run(&items, |(token, _)| token.clone(), |(token, i)| {
+            {
+                let mut in_flight = in_flight.lock().unwrap();
+                let count = in_flight.entry(token.clone()).or_insert(0);
+                *count += 1;
+                assert_eq!(*count, 1, "two calls for {} at once", token);
+            }
+            started.lock().unwrap().push((token.clone(), *i));
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_parallel.fetch_max(now, Ordering::SeqCst);
+            
+            thread::sleep(Duration::from_millis(5));
+            
+            running.fetch_sub(1, Ordering::SeqCst);
+            *in_flight.lock().unwrap().get_mut(token).unwrap() -= 1;
+            Ok(())
+        });
+        
+        assert_eq!(sweep.succeeded(), 12);
+        assert!(max_parallel.load(Ordering::SeqCst) <= 3);
+        
+        // Each token's calls start in input order
+        let started = started.into_inner().unwrap();
+        for token in ["ghp_0", "ghp_1", "ghp_2"] {
+            let order: Vec<u32> = started.iter().filter(|(t, _)| t == token).map(|(_, i)| *i).collect();
+            assert!(order.windows(2).all(|w| w[0] < w[1]), "{}: {:?}", token, order);
+        }
+    }
+    
+    #[test]
+    fn test_cancel_skips_jobs_not_started() {
+        let cancel = CancelToken::new();
+        let items: Vec<u32> = (0..6).collect();
+        
+        let sweep = WorkerPool::new(1).with_cancel(cancel.clone()).run(&items, |i| i.to_string(), |&i| {
+            if i == 2 {
+                cancel.cancel();
+            }
+            Ok(i)
+        });
+        
+        assert_eq!((sweep.succeeded(), sweep.cancelled()), (3, 3));
+        assert!(matches!(sweep.outcomes[2], JobOutcome::Done(2)));
+        assert!(matches!(sweep.outcomes[3], JobOutcome::Cancelled));
+        assert_eq!(sweep.exit_code(), exit_code::PARTIAL);
+    }
+    
+    #[test]
+    fn test_failures_are_collected_and_paced() {
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let items = vec!["a", "b", "c", "d"];
+        
+        let sweep = WorkerPool::new(1)
+            .with_pacing(sleeper.clone(), "between_validations", Duration::from_secs(1))
+            .run(&items, |s| s.to_string(), |&s| {
+                if s == "b" || s == "d" {
+                    anyhow::bail!("{} is invalid", s);
+                }
+                Ok(s)
+            });
+        
+        let errors: Vec<(usize, String)> = sweep.errors().into_iter().map(|(i, e)| (i, e.to_string())).collect();
+        assert_eq!(errors, vec![(1, "b is invalid".to_string()), (3, "d is invalid".to_string())]);
+        assert_eq!(sweep.exit_code(), exit_code::PARTIAL);
+        // One worker, four jobs: a pause between each pair
+        assert_eq!(sleeper.count("between_validations"), 3);
+        
+        let none: Sweep<()> = WorkerPool::new(4).run(&Vec::<u32>::new(), |i| i.to_string(), |_| Ok(()));
+        assert!(none.is_empty());
+    }
+}
@@ -1,16 +1,22 @@
 // src/utils/retry.rs - Retry logic with exponential backoff
 
-use std::thread;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use anyhow::Result;
 use log::{warn, debug};
+use serde::{Deserialize, Serialize};
+use crate::utils::delay::Sleeper;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct RetryConfig {
     pub max_attempts: u32,
     pub initial_delay_ms: u64,
     pub max_delay_ms: u64,
     pub multiplier: f64,
+    /// Longest wait for a rate limit to reset before retrying
+    pub max_rate_limit_wait_ms: u64,
 }
 
 impl Default for RetryConfig {
@@ -20,17 +26,98 @@ impl Default for RetryConfig {
             initial_delay_ms: 1000,
             max_delay_ms: 30000,
             multiplier: 2.0,
+            max_rate_limit_wait_ms: 3_600_000,
         }
     }
 }
 
+/// Retries shared by every call of a sweep, so one dead proxy or an outage
+/// cannot multiply into a retry storm across all accounts.
+#[derive(Clone, Debug, Default)]
+pub struct RetryBudget {
+    /// None means unlimited
+    remaining: Option<Arc<AtomicU32>>,
+}
+
+impl RetryBudget {
+    pub fn new(retries: u32) -> Self {
+        Self {
+            remaining: Some(Arc::new(AtomicU32::new(retries))),
+        }
+    }
+    
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+    
+    /// Takes one retry; false once the budget is used up.
+    pub fn try_spend(&self) -> bool {
+        match &self.remaining {
+            None => true,
+            Some(remaining) => remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok(),
+        }
+    }
+    
+    pub fn remaining(&self) -> Option<u32> {
+        self.remaining.as_ref().map(|r| r.load(Ordering::SeqCst))
+    }
+}
+
+/// What `retry_when` does after a failed attempt.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Retry {
+    /// Give up and return the error
+    Stop,
+    /// Try again after the exponential backoff delay
+    Backoff,
+    /// Try again after `wait`, slept as the `pause` pause, in place of the backoff delay
+    After { pause: &'static str, wait: Duration },
+}
+
+/// Runs `operation` until it succeeds or `config.max_attempts` are used,
+/// waiting between attempts through `sleeper` as the `retry` pause.
 pub fn retry_with_backoff<F, T>(
     config: &RetryConfig,
     operation_name: &str,
+    sleeper: &dyn Sleeper,
+    operation: F,
+) -> Result<T>
+where
+    F: FnMut() -> Result<T>,
+{
+    retry_with_budget(config, &RetryBudget::unlimited(), operation_name, sleeper, operation)
+}
+
+/// Like `retry_with_backoff`, but every retry is also taken from `budget`.
+pub fn retry_with_budget<F, T>(
+    config: &RetryConfig,
+    budget: &RetryBudget,
+    operation_name: &str,
+    sleeper: &dyn Sleeper,
+    operation: F,
+) -> Result<T>
+where
+    F: FnMut() -> Result<T>,
+{
+    retry_when(config, budget, operation_name, sleeper, |_| Retry::Backoff, operation)
+}
+
+/// Like `retry_with_budget`, but `retry` decides for each error whether to
+/// give up at once, back off as usual or wait a time of its own. Nothing is
+/// slept after the last attempt.
+pub fn retry_when<F, T, R>(
+    config: &RetryConfig,
+    budget: &RetryBudget,
+    operation_name: &str,
+    sleeper: &dyn Sleeper,
+    retry: R,
     mut operation: F,
 ) -> Result<T>
 where
     F: FnMut() -> Result<T>,
+    R: Fn(&anyhow::Error) -> Retry,
 {
     let mut attempt = 0;
     let mut delay = config.initial_delay_ms;
@@ -51,6 +138,10 @@ where
                 return Ok(result);
             }
             Err(e) => {
+                let retry = retry(&e);
+                if retry == Retry::Stop {
+                    return Err(e);
+                }
                 if attempt >= config.max_attempts {
                     warn!(
                         "{} failed after {} attempts: {}",
@@ -59,18 +150,30 @@ where
                     return Err(e);
                 }
                 
+                if !budget.try_spend() {
+                    warn!("{} failed, retry budget used up: {}", operation_name, e);
+                    return Err(e);
+                }
+                
+                let (pause, wait) = match retry {
+                    Retry::After { pause, wait } => (pause, wait),
+                    _ => {
+                        let wait = Duration::from_millis(delay);
+                        // Exponential backoff
+                        delay = ((delay as f64) * config.multiplier) as u64;
+                        if delay > config.max_delay_ms {
+                            delay = config.max_delay_ms;
+                        }
+                        ("retry", wait)
+                    }
+                };
+                
                 warn!(
                     "{} failed (attempt {}): {}. Retrying in {}ms...",
-                    operation_name, attempt, e, delay
+                    operation_name, attempt, e, wait.as_millis()
                 );
                 
-                thread::sleep(Duration::from_millis(delay));
-                
-                // Exponential backoff
-                delay = ((delay as f64) * config.multiplier) as u64;
-                if delay > config.max_delay_ms {
-                    delay = config.max_delay_ms;
-                }
+                sleeper.sleep(pause, wait);
             }
         }
     }
@@ -80,6 +183,7 @@ where
 mod tests {
     use super::*;
     use std::sync::atomic::{AtomicU32, Ordering};
+    use crate::utils::delay::RecordingSleeper;
     
     #[test]
     fn test_retry_success() {
@@ -89,9 +193,12 @@ mod tests {
             initial_delay_ms: 10,
             max_delay_ms: 100,
             multiplier: 2.0,
+            ..RetryConfig::default()
         };
         
-        let result = retry_with_backoff(&config, "test", || {
+        let sleeper = RecordingSleeper::default();
+        
+        let result = retry_with_backoff(&config, "test", &sleeper, || {
             let count = counter.fetch_add(1, Ordering::SeqCst);
             if count < 2 {
                 anyhow::bail!("Simulated failure {}", count);
@@ -102,6 +209,10 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), 42);
         assert_eq!(counter.load(Ordering::SeqCst), 3);
+        assert_eq!(sleeper.pauses(), vec![
+            ("retry".to_string(), Duration::from_millis(10)),
+            ("retry".to_string(), Duration::from_millis(20)),
+        ]);
     }
     
     #[test]
@@ -111,12 +222,87 @@ mod tests {
             initial_delay_ms: 10,
             max_delay_ms: 100,
             multiplier: 2.0,
+            ..RetryConfig::default()
         };
         
-        let result = retry_with_backoff(&config, "test", || {
+        let result: Result<()> = retry_with_backoff(&config, "test", &RecordingSleeper::default(), || {
             anyhow::bail!("Always fails");
         });
         
         assert!(result.is_err());
     }
+    
+    #[test]
+    fn test_retry_when_returns_other_errors_at_once() {
+        let config = RetryConfig {
+            initial_delay_ms: 1,
+            max_delay_ms: 1,
+            ..RetryConfig::default()
+        };
+        let calls = AtomicU32::new(0);
+        
+        let result: Result<()> = retry_when(&config, &RetryBudget::unlimited(), "test", &RecordingSleeper::default(), |e| {
+            if e.to_string() == "busy" { Retry::Backoff } else { Retry::Stop }
+        }, || {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => anyhow::bail!("busy"),
+                _ => anyhow::bail!("gone"),
+            }
+        });
+        
+        assert_eq!(result.unwrap_err().to_string(), "gone");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+    
+    #[test]
+    fn test_retry_when_waits_replace_the_backoff_and_stop_at_the_last_attempt() {
+        let config = RetryConfig {
+            initial_delay_ms: 10,
+            ..RetryConfig::default()
+        };
+        let sleeper = RecordingSleeper::default();
+        let calls = AtomicU32::new(0);
+        
+        let result: Result<()> = retry_when(&config, &RetryBudget::unlimited(), "test", &sleeper, |_| {
+            Retry::After { pause: "rate_limit", wait: Duration::from_secs(60) }
+        }, || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            anyhow::bail!("limited")
+        });
+        
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        // One wait before each of the two retries, none after the last attempt
+        assert_eq!(sleeper.pauses(), vec![("rate_limit".to_string(), Duration::from_secs(60)); 2]);
+    }
+    
+    #[test]
+    fn test_retry_budget_is_shared() {
+        let config = RetryConfig {
+            max_attempts: 5,
+            initial_delay_ms: 1,
+            max_delay_ms: 1,
+            multiplier: 1.0,
+            ..RetryConfig::default()
+        };
+        let budget = RetryBudget::new(3);
+        let calls = AtomicU32::new(0);
+        let sleeper = RecordingSleeper::default();
+        
+        let failing = || -> Result<()> {
+            calls.fetch_add(1, Ordering::SeqCst);
+            anyhow::bail!("Always fails")
+        };
+        
+        // The first operation takes all three retries, the second gets none
+        assert!(retry_with_budget(&config, &budget, "first", &sleeper, failing).is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+        assert!(retry_with_budget(&config, &budget.clone(), "second", &sleeper, failing).is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+        assert_eq!(budget.remaining(), Some(0));
+        assert_eq!(sleeper.count("retry"), 3);
+        
+        assert!(RetryBudget::unlimited().try_spend());
+        assert_eq!(RetryBudget::unlimited().remaining(), None);
+    }
 }
@@ -17,3 +17,4462 @@ fn test_crypto_init() {
     };
     assert!(result.is_ok());
 }
+
+mod secrets_cli {
+    use anyhow::{anyhow, Result};
+    use nexus_orchestrator::github::{SecretStore, SecretWriteResult};
+    use nexus_orchestrator::orchestration::secrets::{apply_values, set_secret};
+    use nexus_orchestrator::utils::exit_code;
+    use nexus_orchestrator::utils::WorkerPool;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+    
+    /// In-memory secret store keyed by (repo, name).
+    #[derive(Clone, Default)]
+    struct MockSecretStore {
+        secrets: Arc<Mutex<BTreeMap<(String, String), String>>>,
+        unverified: bool,
+    }
+    
+    impl SecretStore for MockSecretStore {
+        fn set_secret(&self, repo: &str, secret_name: &str, secret_value: &str) -> Result<SecretWriteResult> {
+            let previous = self.secrets.lock().unwrap().insert(
+                (repo.to_string(), secret_name.to_string()),
+                secret_value.to_string(),
+            );
+            
+            Ok(match previous {
+                _ if self.unverified => SecretWriteResult::Unverified,
+                Some(_) => SecretWriteResult::Updated,
+                None => SecretWriteResult::Created,
+            })
+        }
+        
+        fn delete_secret(&self, repo: &str, secret_name: &str) -> Result<()> {
+            self.secrets
+                .lock()
+                .unwrap()
+                .remove(&(repo.to_string(), secret_name.to_string()))
+                .map(|_| ())
+                .ok_or_else(|| anyhow!("HTTP 404: Not Found"))
+        }
+        
+        fn list_secrets(&self, repo: &str) -> Result<Vec<String>> {
+            Ok(self.secrets
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|(r, _)| r == repo)
+                .map(|(_, name)| name.clone())
+                .collect())
+        }
+    }
+    
+    const REPO: &str = "alice/nexus-runner";
+    
+    #[test]
+    fn test_set_reports_created_then_updated() {
+        let store = MockSecretStore::default();
+        
+        assert_eq!(set_secret(&store, REPO, "API_KEY", "one").unwrap(), SecretWriteResult::Created);
+        assert_eq!(set_secret(&store, REPO, "API_KEY", "two").unwrap(), SecretWriteResult::Updated);
+        assert_eq!(store.secrets.lock().unwrap()[&(REPO.to_string(), "API_KEY".to_string())], "two");
+        
+        // Invalid names never reach the API
+        assert!(set_secret(&store, REPO, "GITHUB_TOKEN", "x").is_err());
+        assert_eq!(store.secrets.lock().unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_apply_continues_past_failing_repo() {
+        let store = MockSecretStore::default();
+        let values = vec![
+            ("NEXUS_NODE_IDS".to_string(), "node_1".to_string()),
+            ("NEXUS_WALLETS".to_string(), "0xabc".to_string()),
+        ];
+        let repos = vec![REPO.to_string(), "mallory/gone".to_string(), "bob/runner".to_string()];
+        
+        let report = apply_values(&values, &repos, &WorkerPool::new(4), &|repo| {
+            if repo == "mallory/gone" {
+                Err(anyhow!("mallory/gone is not in the fork chain"))
+            } else {
+                Ok(Box::new(store.clone()) as Box<dyn SecretStore>)
+            }
+        });
+        
+        assert_eq!(report.outcomes.len(), 6);
+        assert_eq!(report.failed(), 2);
+        // Repos run concurrently but are reported in manifest order
+        let reported: Vec<&str> = report.outcomes.iter().map(|o| o.repo.as_str()).collect();
+        assert_eq!(reported, vec![REPO, REPO, "mallory/gone", "mallory/gone", "bob/runner", "bob/runner"]);
+        assert_eq!(report.exit_code(), exit_code::PARTIAL);
+        assert_eq!(store.list_secrets("bob/runner").unwrap(), vec!["NEXUS_NODE_IDS", "NEXUS_WALLETS"]);
+    }
+    
+    #[test]
+    fn test_apply_unverified_is_partial() {
+        let store = MockSecretStore { unverified: true, ..Default::default() };
+        let values = vec![("API_KEY".to_string(), "v".to_string())];
+        
+        let report = apply_values(&values, &[REPO.to_string()], &WorkerPool::new(1), &|_| {
+            Ok(Box::new(store.clone()) as Box<dyn SecretStore>)
+        });
+        
+        assert_eq!(report.failed(), 0);
+        assert_eq!(report.unverified(), 1);
+        assert_eq!(report.exit_code(), exit_code::PARTIAL);
+    }
+}
+
+mod fork_cli {
+    use anyhow::{anyhow, Result};
+    use nexus_orchestrator::core::account::{AccountInfo, Capabilities, OwnerType, TokenLookup};
+    use nexus_orchestrator::core::proxy::{ProxyConfig, ProxyManager};
+    use nexus_orchestrator::core::state::ForkStatus;
+    use nexus_orchestrator::core::{AccountManager, Paths, StateManager};
+    use nexus_orchestrator::github::api::{GitHubError, MergeUpstream, RepoInfo, RepoPermissions, RepoRef, RunEntry, RunSummary};
+    use nexus_orchestrator::github::fork::{ChainDrift, CleanupFilter, CleanupMode, CleanupOptions, CleanupOutcome, OrphanFork};
+    use nexus_orchestrator::github::{ForkApi, ForkManager};
+    use nexus_orchestrator::orchestration::forks::{results_exit_code, ForkCommand};
+    use nexus_orchestrator::orchestration::tokens::TokenCommand;
+    use nexus_orchestrator::monitor::events::EventLog;
+    use nexus_orchestrator::monitor::ForkArchive;
+    use nexus_orchestrator::utils::delay::RecordingSleeper;
+    use nexus_orchestrator::utils::{exit_code, CancelToken};
+    use nexus_orchestrator::AppContext;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use tempfile::{tempdir, TempDir};
+    
+    /// GitHub as seen by the fork commands: repos, workflow ids, active runs
+    /// and the calls made, shared by every client the factory hands out.
+    #[derive(Default)]
+    struct FakeGitHub {
+        repos: BTreeSet<String>,
+        workflows: BTreeMap<String, u64>,
+        active_runs: BTreeMap<String, Vec<u64>>,
+        /// Repos whose deletion is refused; the token has no admin rights on them
+        protected: BTreeSet<String>,
+        /// Repos the token gets a 403 for
+        hidden: BTreeSet<String>,
+        calls: Vec<String>,
+        /// Cancelled (as by Ctrl-C) right after the first call starting with the prefix
+        cancel_after: Option<(String, CancelToken)>,
+        /// Existence checks a transferred repo stays missing for under its new owner
+        transfer_delay: u32,
+        /// Transferred repos still in flight, with the checks left
+        transfers: BTreeMap<String, u32>,
+        /// (parent, fork) of every fork created
+        forks: Vec<(String, String)>,
+        /// Lookups a new fork stays empty for before its contents are copied
+        populate_delay: u32,
+        /// New forks still empty, with the lookups left
+        unpopulated: BTreeMap<String, u32>,
+        /// Fork requests are accepted but the repo never shows up
+        forks_never_appear: bool,
+        /// `x-oauth-scopes` of the token; None as for fine-grained tokens
+        scopes: Option<Vec<String>>,
+        /// Workflow run count per repo; the runs of other repos fail to load
+        run_counts: BTreeMap<String, u64>,
+    }
+    
+    /// Client for one account; forks land under its username.
+    #[derive(Clone, Default)]
+    struct MockForkApi {
+        github: Arc<Mutex<FakeGitHub>>,
+        username: String,
+    }
+    
+    impl MockForkApi {
+        fn log(&self, call: String) {
+            let mut github = self.github.lock().unwrap();
+            if let Some((prefix, cancel)) = &github.cancel_after {
+                if call.starts_with(prefix.as_str()) {
+                    cancel.cancel();
+                }
+            }
+            github.calls.push(call);
+        }
+    }
+    
+    impl ForkApi for MockForkApi {
+        fn check_repo_exists(&self, repo: &str) -> Result<bool> {
+            self.log(format!("exists {}", repo));
+            let mut github = self.github.lock().unwrap();
+            if github.hidden.contains(repo) {
+                return Err(GitHubError::Forbidden("Resource not accessible by personal access token (HTTP 403)".to_string()).into());
+            }
+            if let Some(left) = github.transfers.get_mut(repo) {
+                *left = left.saturating_sub(1);
+                if *left == 0 {
+                    github.transfers.remove(repo);
+                    github.repos.insert(repo.to_string());
+                }
+            }
+            Ok(github.repos.contains(repo))
+        }
+        
+        fn get_repo_info(&self, repo: &str) -> Result<RepoInfo> {
+            self.log(format!("info {}", repo));
+            let github = self.github.lock().unwrap();
+            if !github.repos.contains(repo) {
+                return Err(GitHubError::NotFound("Not Found (HTTP 404)".to_string()).into());
+            }
+            let parent = github.forks.iter().rev().find(|(_, fork)| fork == repo);
+            Ok(RepoInfo {
+                full_name: repo.to_string(),
+                default_branch: "main".to_string(),
+                size: 64,
+                parent: parent.map(|(parent, _)| RepoRef { full_name: parent.clone() }),
+                permissions: Some(RepoPermissions { admin: !github.protected.contains(repo), push: true, pull: true }),
+                ..RepoInfo::default()
+            })
+        }
+        
+        fn branch_head(&self, repo: &str, branch: &str) -> Result<Option<String>> {
+            self.log(format!("branch {} {}", repo, branch));
+            let mut github = self.github.lock().unwrap();
+            match github.unpopulated.get_mut(repo) {
+                Some(left) if *left > 0 => {
+                    *left -= 1;
+                    Ok(None)
+                }
+                _ => Ok(Some("0123abcd".to_string())),
+            }
+        }
+        
+        fn create_fork(&self, source_repo: &str, organization: Option<&str>, name: Option<&str>) -> Result<String> {
+            match organization {
+                Some(org) => self.log(format!("fork {} into {}", source_repo, org)),
+                None => self.log(format!("fork {}", source_repo)),
+            }
+            let name = name.unwrap_or_else(|| source_repo.split('/').nth(1).unwrap());
+            let mut fork = format!("{}/{}", organization.unwrap_or(&self.username), name);
+            let mut github = self.github.lock().unwrap();
+            // Like GitHub, a name an unrelated repo holds gets a suffix
+            if github.repos.contains(&fork) && !github.forks.iter().any(|(parent, f)| parent == source_repo && *f == fork) {
+                fork = format!("{}-1", fork);
+            }
+            if !github.forks_never_appear {
+                github.repos.insert(fork.clone());
+            }
+            github.forks.push((source_repo.to_string(), fork.clone()));
+            let delay = github.populate_delay;
+            github.unpopulated.insert(fork.clone(), delay);
+            Ok(fork)
+        }
+        
+        fn delete_repo(&self, repo: &str) -> Result<()> {
+            self.log(format!("delete {}", repo));
+            if self.github.lock().unwrap().protected.contains(repo) {
+                return Err(anyhow!("HTTP 403: Must have admin rights to Repository"));
+            }
+            self.github.lock().unwrap().repos.remove(repo);
+            Ok(())
+        }
+        
+        fn archive_repo(&self, repo: &str) -> Result<()> {
+            self.log(format!("archive {}", repo));
+            Ok(())
+        }
+        
+        fn transfer_repo(&self, repo: &str, new_owner: &str) -> Result<()> {
+            self.log(format!("transfer {} to {}", repo, new_owner));
+            let mut github = self.github.lock().unwrap();
+            github.repos.remove(repo);
+            let moved = format!("{}/{}", new_owner, repo.split('/').nth(1).unwrap());
+            let delay = github.transfer_delay;
+            github.transfers.insert(moved, delay);
+            Ok(())
+        }
+        
+        fn list_forks(&self, repo: &str) -> Result<Vec<(String, String)>> {
+            self.log(format!("forks of {}", repo));
+            let github = self.github.lock().unwrap();
+            Ok(github.forks
+                .iter()
+                .filter(|(parent, fork)| parent == repo && github.repos.contains(fork))
+                .map(|(_, fork)| (fork.split('/').next().unwrap().to_string(), fork.clone()))
+                .collect())
+        }
+        
+        fn get_workflow_id(&self, repo: &str, _workflow_file: &str) -> Result<Option<u64>> {
+            Ok(self.github.lock().unwrap().workflows.get(repo).copied())
+        }
+        
+        fn workflow_state(&self, repo: &str, _workflow_file: &str) -> Result<Option<String>> {
+            Ok(self.github.lock().unwrap().workflows.get(repo).map(|_| "active".to_string()))
+        }
+        
+        fn disable_workflow(&self, repo: &str, workflow_id: u64) -> Result<()> {
+            self.log(format!("disable {} {}", repo, workflow_id));
+            Ok(())
+        }
+        
+        fn enable_actions(&self, repo: &str) -> Result<()> {
+            self.log(format!("enable-actions {}", repo));
+            Ok(())
+        }
+        
+        fn list_active_runs(&self, repo: &str, _workflow_file: Option<&str>) -> Result<Vec<u64>> {
+            Ok(self.github.lock().unwrap().active_runs.get(repo).cloned().unwrap_or_default())
+        }
+        
+        fn run_summary(&self, repo: &str) -> Result<RunSummary> {
+            match self.github.lock().unwrap().run_counts.get(repo) {
+                Some(&total_count) => Ok(RunSummary {
+                    total_count,
+                    runs: vec![RunEntry {
+                        id: 9000 + total_count,
+                        status: Some("completed".to_string()),
+                        conclusion: Some("success".to_string()),
+                    }],
+                }),
+                None => Err(GitHubError::Other("Bad Gateway (HTTP 502)".to_string()).into()),
+            }
+        }
+        
+        fn cancel_workflow_run(&self, repo: &str, run_id: u64) -> Result<()> {
+            self.log(format!("cancel {} {}", repo, run_id));
+            Ok(())
+        }
+        
+        fn merge_upstream(&self, repo: &str, branch: &str) -> Result<MergeUpstream> {
+            self.log(format!("sync {} {}", repo, branch));
+            if self.github.lock().unwrap().repos.contains(repo) {
+                Ok(MergeUpstream { merge_type: Some("fast-forward".to_string()), ..MergeUpstream::default() })
+            } else {
+                Err(anyhow!("HTTP 404: Not Found"))
+            }
+        }
+        
+        fn api_graphql(&self, _query: &str, _variables: serde_json::Value) -> Result<serde_json::Value> {
+            Err(anyhow!("GraphQL is not mocked"))
+        }
+        
+        fn token_scopes(&self) -> Result<Option<Vec<String>>> {
+            self.log("scopes".to_string());
+            Ok(self.github.lock().unwrap().scopes.clone())
+        }
+        
+        fn username(&self) -> Result<String> {
+            Ok(self.username.clone())
+        }
+    }
+    
+    const PARENT: &str = "origin/nexus-runner";
+    const FORK: &str = "user_0/nexus-runner";
+    
+    fn setup() -> (TempDir, MockForkApi) {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join("tokens.txt"), "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb\n").unwrap();
+        
+        let api = MockForkApi::default();
+        api.github.lock().unwrap().repos.insert(PARENT.to_string());
+        (temp_dir, api)
+    }
+    
+    fn command_with(temp_dir: &TempDir, api: &MockForkApi, sleeper: Arc<RecordingSleeper>) -> ForkCommand {
+        let api = api.clone();
+        
+        ForkCommand::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(Box::new(move |account, _| {
+                Box::new(MockForkApi { username: account.username.clone(), ..api.clone() })
+            }))
+            .with_sleeper(sleeper)
+    }
+    
+    fn command(temp_dir: &TempDir, api: &MockForkApi) -> ForkCommand {
+        command_with(temp_dir, api, Arc::new(RecordingSleeper::default()))
+    }
+    
+    fn fork_status(temp_dir: &TempDir, repo: &str) -> Option<ForkStatus> {
+        let state = StateManager::new(&Paths::new(temp_dir.path())).unwrap().load_state().unwrap();
+        state.fork_chain.iter().find(|n| n.repo == repo).map(|n| n.status.clone())
+    }
+    
+    #[test]
+    fn test_create_adds_chain_node() {
+        let (temp_dir, api) = setup();
+        
+        let result = command(&temp_dir, &api).create(None, Some(PARENT)).unwrap();
+        
+        assert_eq!(result.repo, FORK);
+        assert!(result.outcome.is_ok(), "{:?}", result);
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Active));
+        {
+            let calls = &api.github.lock().unwrap().calls;
+            let forked = calls.iter().position(|c| *c == format!("fork {}", PARENT)).unwrap();
+            let enabled = calls.iter().position(|c| *c == format!("enable-actions {}", FORK));
+            // Actions is turned on once the new fork is ready
+            assert!(enabled.is_some_and(|enabled| enabled > forked), "{:?}", calls);
+        }
+        
+        // user_0 now has a fork, so the next default account is user_1
+        let next = command(&temp_dir, &api).create(None, None).unwrap();
+        assert_eq!(next.repo, "user_1/nexus-runner");
+        assert!(api.github.lock().unwrap().calls.contains(&format!("fork {}", FORK)));
+    }
+    
+    #[test]
+    fn test_create_tracks_the_name_github_gave_the_fork() {
+        let (temp_dir, api) = setup();
+        // user_0 already owns an unrelated repo under the parent's name
+        api.github.lock().unwrap().repos.insert(FORK.to_string());
+        
+        let result = command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        
+        assert!(result.outcome.is_ok(), "{:?}", result);
+        assert_eq!(result.repo, "user_0/nexus-runner-1");
+        assert_eq!(fork_status(&temp_dir, "user_0/nexus-runner-1"), Some(ForkStatus::Active));
+        assert_eq!(fork_status(&temp_dir, FORK), None);
+        assert!(api.github.lock().unwrap().calls.contains(&"branch user_0/nexus-runner-1 main".to_string()));
+    }
+    
+    #[test]
+    fn test_create_forks_into_organization() {
+        let (temp_dir, api) = setup();
+        fs::write(temp_dir.path().join("tokens.txt"), "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb acme-labs\n").unwrap();
+        fs::create_dir_all(temp_dir.path().join("cache")).unwrap();
+        fs::write(temp_dir.path().join("cache/ownermap.json"), r#"{"acme-labs": "organization"}"#).unwrap();
+        
+        let result = command(&temp_dir, &api).create(Some("user_1"), Some(PARENT)).unwrap();
+        
+        assert_eq!(result.repo, "acme-labs/nexus-runner");
+        assert!(api.github.lock().unwrap().calls.contains(&format!("fork {} into acme-labs", PARENT)));
+        
+        let state = StateManager::new(&Paths::new(temp_dir.path())).unwrap().load_state().unwrap();
+        let node = &state.fork_chain[0];
+        assert_eq!((node.pat_index, node.owner_type), (1, OwnerType::Organization));
+        assert_eq!(node.owner_label(), "acme-labs [org]");
+        
+        // User accounts keep forking into their own namespace, without the parameter
+        command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        assert!(api.github.lock().unwrap().calls.contains(&format!("fork {}", PARENT)));
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Active));
+    }
+    
+    fn account(index: usize) -> AccountInfo {
+        AccountInfo {
+            username: format!("user_{}", index),
+            token: "ghp_bbbbbbbbbbbb".to_string(),
+            index,
+            owner: None,
+            owner_type: OwnerType::User,
+            fork_org: None,
+            expires_at: None,
+            capabilities: None,
+            enabled: true,
+            validated_at: None,
+            stale: false,
+            priority: 0,
+            label: None,
+            notes: None,
+            quarantined_at: None,
+        }
+    }
+    
+    #[test]
+    fn test_transfer_moves_chain_node_once_repo_arrives() {
+        let (temp_dir, api) = setup();
+        command(&temp_dir, &api).create(None, Some(PARENT)).unwrap();
+        api.github.lock().unwrap().transfer_delay = 3;
+        
+        let state_mgr = Arc::new(StateManager::new(&Paths::new(temp_dir.path())).unwrap());
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let manager = ForkManager::new(state_mgr.clone()).with_sleeper(sleeper.clone());
+        let state = manager.transfer_fork(state_mgr.load_state().unwrap(), 0, &account(1), &api).unwrap();
+        
+        let node = &state.fork_chain[0];
+        assert_eq!((node.repo.as_str(), node.username.as_str(), node.pat_index), ("user_1/nexus-runner", "user_1", 1));
+        assert_eq!(node.status, ForkStatus::Active);
+        assert_eq!(state_mgr.load_state().unwrap().fork_chain[0].repo, "user_1/nexus-runner");
+        assert!(api.github.lock().unwrap().calls.contains(&format!("transfer {} to user_1", FORK)));
+        assert_eq!(sleeper.count("fork_poll"), 3);
+    }
+    
+    #[test]
+    fn test_transfer_times_out_and_leaves_state() {
+        let (temp_dir, api) = setup();
+        command(&temp_dir, &api).create(None, Some(PARENT)).unwrap();
+        api.github.lock().unwrap().transfer_delay = u32::MAX;
+        
+        let state_mgr = Arc::new(StateManager::new(&Paths::new(temp_dir.path())).unwrap());
+        let manager = ForkManager::new(state_mgr.clone()).with_sleeper(Arc::new(RecordingSleeper::default()));
+        let err = manager.transfer_fork(state_mgr.load_state().unwrap(), 0, &account(1), &api).unwrap_err();
+        
+        assert!(err.to_string().contains("Timeout waiting"), "{}", err);
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Active));
+    }
+        
+    #[test]
+    fn test_bulk_create_keeps_account_order_and_reports_failures() {
+        let (temp_dir, api) = setup();
+        {
+            let mut github = api.github.lock().unwrap();
+            github.populate_delay = 1;
+            github.hidden.insert("user_2/nexus-runner".to_string());
+        }
+        
+        let paths = Paths::new(temp_dir.path());
+        let state_mgr = Arc::new(StateManager::new(&paths).unwrap());
+        let manager = ForkManager::new(state_mgr.clone()).with_sleeper(Arc::new(RecordingSleeper::default()));
+        let accounts: Vec<AccountInfo> = (0..4).map(account).collect();
+        let client_for = |account: &AccountInfo, _proxy: Option<String>| -> Box<dyn ForkApi> {
+            Box::new(MockForkApi { username: account.username.clone(), ..api.clone() })
+        };
+        
+        let (state, report) = manager
+            .create_chain_bulk(state_mgr.load_state().unwrap(), &accounts, PARENT, &ProxyManager::new(&paths), 3, &client_for)
+            .unwrap();
+        
+        let repos: Vec<&str> = state.fork_chain.iter().map(|n| n.repo.as_str()).collect();
+        assert_eq!(repos, vec!["user_0/nexus-runner", "user_1/nexus-runner", "user_3/nexus-runner"]);
+        assert!(state.fork_chain.iter().all(|n| n.parent.as_deref() == Some(PARENT)));
+        assert_eq!(state_mgr.load_state().unwrap().fork_chain.len(), 3);
+        assert_eq!(report.created.len(), 3);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, "user_2");
+        assert!(report.failed[0].1.contains("cannot see repo"), "{:?}", report);
+    }
+    
+    #[test]
+    fn test_reconcile_flags_missing_and_untracked_forks() {
+        let (temp_dir, api) = setup();
+        fs::write(
+            temp_dir.path().join("setup.json"),
+            r#"{"main_repo_owner": "origin", "main_repo_name": "nexus-runner"}"#,
+        ).unwrap();
+        command(&temp_dir, &api).create(None, Some(PARENT)).unwrap();
+        
+        // Forked by hand: a managed account off the chain fork, and a stranger off the source
+        MockForkApi { username: "user_1".to_string(), ..api.clone() }.create_fork(FORK, None, None).unwrap();
+        MockForkApi { username: "stranger".to_string(), ..api.clone() }.create_fork(PARENT, None, None).unwrap();
+        
+        let drift = command(&temp_dir, &api).reconcile().unwrap();
+        assert_eq!(drift, vec![ChainDrift::UntrackedFork {
+            repo: "user_1/nexus-runner".to_string(),
+            parent: FORK.to_string(),
+        }]);
+        
+        api.github.lock().unwrap().repos.remove(FORK);
+        let drift = command(&temp_dir, &api).reconcile().unwrap();
+        assert_eq!(drift, vec![ChainDrift::MissingRepo { repo: FORK.to_string() }]);
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Active));
+    }
+    
+    #[test]
+    fn test_orphan_cleanup_deletes_only_untracked_forks_of_the_parent() {
+        let (temp_dir, api) = setup();
+        fs::write(
+            temp_dir.path().join("setup.json"),
+            r#"{"main_repo_owner": "origin", "main_repo_name": "nexus-runner"}"#,
+        ).unwrap();
+        command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        
+        // Left over from experiments: user_1 forked the parent by hand; a
+        // stranger's fork and user_1's unrelated repo are none of our business
+        MockForkApi { username: "user_1".to_string(), ..api.clone() }.create_fork(PARENT, None, None).unwrap();
+        MockForkApi { username: "stranger".to_string(), ..api.clone() }.create_fork(PARENT, None, None).unwrap();
+        api.github.lock().unwrap().repos.insert("user_1/notes".to_string());
+        
+        let (parent, orphans) = command(&temp_dir, &api).find_orphans(None, None).unwrap();
+        assert_eq!(parent, PARENT);
+        assert_eq!(orphans, vec![OrphanFork { owner: "user_1".to_string(), repo: OTHER_FORK.to_string() }]);
+        
+        // Narrowed to an account without orphans
+        let (_, orphans_of_user_0) = command(&temp_dir, &api).find_orphans(None, Some("user_0")).unwrap();
+        assert!(orphans_of_user_0.is_empty());
+        
+        // A repo that is not a fork of the parent is refused even when asked for
+        let not_a_fork = OrphanFork { owner: "user_1".to_string(), repo: "user_1/notes".to_string() };
+        let results = command(&temp_dir, &api).delete_orphans(PARENT, &[orphans[0].clone(), not_a_fork]).unwrap();
+        assert!(results[0].outcome.is_ok(), "{:?}", results[0]);
+        assert!(results[1].outcome.as_ref().unwrap_err().contains("is not a fork of origin/nexus-runner"));
+        
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Active));
+        let github = api.github.lock().unwrap();
+        assert!(!github.repos.contains(OTHER_FORK));
+        assert!(github.repos.contains("user_1/notes") && github.repos.contains(FORK) && github.repos.contains("stranger/nexus-runner"));
+    }
+    
+    #[test]
+    fn test_delete_runs_full_sequence_and_updates_state() {
+        let (temp_dir, api) = setup();
+        command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        {
+            let mut github = api.github.lock().unwrap();
+            github.workflows.insert(FORK.to_string(), 77);
+            github.active_runs.insert(FORK.to_string(), vec![1001, 1002]);
+            github.calls.clear();
+        }
+        
+        let result = command(&temp_dir, &api).delete(FORK, false, None).unwrap();
+        assert!(result.outcome.is_ok(), "{:?}", result);
+        
+        assert_eq!(api.github.lock().unwrap().calls, vec![
+            format!("disable {} 77", FORK),
+            format!("cancel {} 1001", FORK),
+            format!("cancel {} 1002", FORK),
+            format!("delete {}", FORK),
+            format!("exists {}", FORK),
+        ]);
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Deleted));
+    }
+    
+    #[test]
+    fn test_create_stops_when_token_cannot_see_fork() {
+        let (temp_dir, api) = setup();
+        api.github.lock().unwrap().hidden.insert(FORK.to_string());
+        
+        let result = command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        
+        let err = result.outcome.unwrap_err();
+        assert!(err.contains(&format!("cannot see repo {}; check the token's scopes", FORK)), "{}", err);
+        assert!(!api.github.lock().unwrap().calls.iter().any(|c| c.starts_with("fork ")));
+        assert_eq!(fork_status(&temp_dir, FORK), None);
+    }
+    
+    #[test]
+    fn test_create_waits_until_fork_is_populated() {
+        let (temp_dir, api) = setup();
+        api.github.lock().unwrap().populate_delay = 2;
+        let sleeper = Arc::new(RecordingSleeper::default());
+        
+        let result = command_with(&temp_dir, &api, sleeper.clone()).create(Some("user_0"), Some(PARENT)).unwrap();
+        
+        assert!(result.outcome.is_ok(), "{:?}", result);
+        assert_eq!(sleeper.count("fork_poll"), 3);
+        assert_eq!(api.github.lock().unwrap().calls.iter().filter(|c| *c == &format!("branch {} main", FORK)).count(), 3);
+    }
+    
+    #[test]
+    fn test_create_timeout_tells_empty_fork_from_missing_one() {
+        let (temp_dir, api) = setup();
+        api.github.lock().unwrap().populate_delay = u32::MAX;
+        
+        let result = command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        let err = result.outcome.unwrap_err();
+        assert!(err.contains("exists but its main branch still has no commits after 120s"), "{}", err);
+        assert_eq!(fork_status(&temp_dir, FORK), None);
+        
+        let (temp_dir, api) = setup();
+        api.github.lock().unwrap().forks_never_appear = true;
+        
+        let result = command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        let err = result.outcome.unwrap_err();
+        assert!(err.contains("did not appear within 120s"), "{}", err);
+    }
+    
+    #[test]
+    fn test_fork_waits_are_recorded_not_slept() {
+        let (temp_dir, api) = setup();
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let start = Instant::now();
+        
+        command_with(&temp_dir, &api, sleeper.clone()).create(Some("user_0"), Some(PARENT)).unwrap();
+        // The fork is ready at the first check
+        assert_eq!(sleeper.count("fork_poll"), 1);
+        
+        command_with(&temp_dir, &api, sleeper.clone()).delete(FORK, false, None).unwrap();
+        // One pause for cancelled runs to settle; the repo is gone at the first check
+        assert_eq!(sleeper.count("fork_poll"), 2);
+        
+        assert!(sleeper.pauses().iter().all(|(_, d)| *d == Duration::from_secs(5)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+    
+    #[test]
+    fn test_delete_keep_state_leaves_node() {
+        let (temp_dir, api) = setup();
+        command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        
+        let result = command(&temp_dir, &api).delete(FORK, true, None).unwrap();
+        
+        assert!(result.outcome.is_ok(), "{:?}", result);
+        assert!(!api.github.lock().unwrap().repos.contains(FORK));
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Active));
+    }
+    
+    #[test]
+    fn test_delete_fork_addresses_nodes_by_name() {
+        let (temp_dir, api) = exhausted_chain();
+        api.github.lock().unwrap().protected.insert(FORK.to_string());
+        let state_mgr = Arc::new(StateManager::new(&Paths::new(temp_dir.path())).unwrap());
+        let manager = ForkManager::new(state_mgr.clone()).with_sleeper(Arc::new(RecordingSleeper::default()));
+        
+        // Refused: the node keeps its status
+        let state = state_mgr.load_state().unwrap();
+        assert!(manager.delete_fork(state.clone(), FORK, &api).is_err());
+        
+        let state = manager.delete_fork(state, OTHER_FORK, &api).unwrap();
+        assert_eq!(state.fork_chain[0].status, ForkStatus::Exhausted);
+        assert_eq!(state.fork_chain[1].status, ForkStatus::Deleted);
+        assert_eq!(fork_status(&temp_dir, OTHER_FORK), Some(ForkStatus::Deleted));
+        
+        let err = manager.delete_fork(state, "stranger/repo", &api).unwrap_err();
+        assert!(err.to_string().contains("not in the fork chain"), "{}", err);
+        assert_eq!(deletes(&api), 2);
+    }
+    
+    #[test]
+    fn test_deleted_forks_are_archived_even_without_run_history() {
+        let (temp_dir, api) = exhausted_chain();
+        api.github.lock().unwrap().run_counts.insert(OTHER_FORK.to_string(), 42);
+        let state_mgr = Arc::new(StateManager::new(&Paths::new(temp_dir.path())).unwrap());
+        let manager = ForkManager::new(state_mgr.clone()).with_sleeper(Arc::new(RecordingSleeper::default()));
+        
+        let archive = ForkArchive::new(&Paths::new(temp_dir.path()));
+        
+        // A refused deletion is not archived
+        api.github.lock().unwrap().protected.insert(OTHER_FORK.to_string());
+        let state = state_mgr.load_state().unwrap();
+        assert!(manager.delete_fork(state.clone(), OTHER_FORK, &api).is_err());
+        assert!(archive.read_all().unwrap().is_empty());
+        api.github.lock().unwrap().protected.clear();
+        
+        let state = manager.delete_fork(state, OTHER_FORK, &api).unwrap();
+        // FORK's runs fail to load: it is deleted and archived anyway
+        manager.delete_fork(state, FORK, &api).unwrap();
+        
+        let archived = archive.read_all().unwrap();
+        let summary: Vec<_> = archived
+            .iter()
+            .map(|a| (a.repo.as_str(), a.status.clone(), a.run_count, a.last_run_conclusion.as_deref()))
+            .collect();
+        assert_eq!(summary, vec![
+            (OTHER_FORK, ForkStatus::Exhausted, Some(42), Some("success")),
+            (FORK, ForkStatus::Exhausted, None, None),
+        ]);
+        assert_eq!(archived[0].last_run_id, Some(9042));
+        assert_eq!(deletes(&api), 3);
+    }
+    
+    #[test]
+    fn test_delete_unknown_repo_needs_account() {
+        let (temp_dir, api) = setup();
+        
+        let err = command(&temp_dir, &api).delete("stranger/repo", false, None).unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        assert!(api.github.lock().unwrap().calls.is_empty());
+    }
+    
+    #[test]
+    fn test_sync_and_list_filter() {
+        let (temp_dir, api) = setup();
+        command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        
+        let results = command(&temp_dir, &api).sync(None, true, None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results_exit_code(&results), exit_code::SUCCESS);
+        assert!(api.github.lock().unwrap().calls.contains(&format!("sync {} main", FORK)));
+        
+        // The fork vanished upstream: sync fails for it
+        api.github.lock().unwrap().repos.remove(FORK);
+        let results = command(&temp_dir, &api).sync(Some(FORK), false, None).unwrap();
+        assert_eq!(results_exit_code(&results), exit_code::FAILURE);
+        
+        let cmd = command(&temp_dir, &api);
+        assert_eq!(cmd.list(&[]).unwrap().len(), 1);
+        assert_eq!(cmd.list(&[ForkStatus::Active]).unwrap().len(), 1);
+        assert!(cmd.list(&[ForkStatus::Exhausted, ForkStatus::Disabled]).unwrap().is_empty());
+    }
+    
+    const OTHER_FORK: &str = "user_1/nexus-runner";
+    
+    /// Two exhausted forks, one per account.
+    fn exhausted_chain() -> (TempDir, MockForkApi) {
+        let (temp_dir, api) = setup();
+        command(&temp_dir, &api).create(Some("user_0"), Some(PARENT)).unwrap();
+        command(&temp_dir, &api).create(Some("user_1"), Some(FORK)).unwrap();
+        
+        let state_mgr = StateManager::new(&Paths::new(temp_dir.path())).unwrap();
+        let state = state_mgr.load_state().unwrap();
+        let state = state_mgr.update_fork_status(state, 0, ForkStatus::Exhausted).unwrap();
+        state_mgr.update_fork_status(state, 1, ForkStatus::Exhausted).unwrap();
+        
+        (temp_dir, api)
+    }
+    
+    fn deletes(api: &MockForkApi) -> usize {
+        api.github.lock().unwrap().calls.iter().filter(|c| c.starts_with("delete ")).count()
+    }
+    
+    #[test]
+    fn test_cleanup_dry_run_touches_nothing() {
+        let (temp_dir, api) = exhausted_chain();
+        // Dry runs must not need tokens either
+        fs::remove_file(temp_dir.path().join("tokens.txt")).unwrap();
+        
+        let options = CleanupOptions { dry_run: true, ..CleanupOptions::default() };
+        let summary = command(&temp_dir, &api).cleanup(&options).unwrap();
+        
+        assert_eq!((summary.matched(), summary.deleted(), summary.skipped()), (2, 0, 2));
+        assert_eq!(summary.exit_code(), exit_code::SUCCESS);
+        assert_eq!(deletes(&api), 0);
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Exhausted));
+    }
+    
+    #[test]
+    fn test_cleanup_dry_run_reports_deletes_that_would_fail() {
+        let (temp_dir, api) = exhausted_chain();
+        {
+            let mut github = api.github.lock().unwrap();
+            github.protected.insert(OTHER_FORK.to_string());
+            github.calls.clear();
+        }
+        
+        let options = CleanupOptions { dry_run: true, ..CleanupOptions::default() };
+        let summary = command(&temp_dir, &api).cleanup(&options).unwrap();
+        assert_eq!(summary.results[0].outcome, CleanupOutcome::Planned(CleanupMode::Delete));
+        assert_eq!(summary.results[0].status, ForkStatus::Exhausted);
+        assert!(matches!(&summary.results[1].outcome, CleanupOutcome::Blocked(e) if e.contains("no admin rights")));
+        assert_eq!(summary.exit_code(), exit_code::PARTIAL);
+        
+        // A classic token without delete_repo cannot delete anything
+        api.github.lock().unwrap().scopes = Some(vec!["repo".to_string(), "workflow".to_string()]);
+        let summary = command(&temp_dir, &api).cleanup(&options).unwrap();
+        assert_eq!(summary.results[0].outcome, CleanupOutcome::Blocked("token lacks the delete_repo scope".to_string()));
+        assert_eq!(summary.exit_code(), exit_code::FAILURE);
+        
+        // Only reads went out, and state is as it was
+        let github = api.github.lock().unwrap();
+        assert!(github.calls.iter().all(|c| c.starts_with("info ") || c == "scopes"), "{:?}", github.calls);
+        drop(github);
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Exhausted));
+        assert_eq!(fork_status(&temp_dir, OTHER_FORK), Some(ForkStatus::Exhausted));
+    }
+    
+    #[test]
+    fn test_cleanup_partial_failure_then_deleted_filter() {
+        let (temp_dir, api) = exhausted_chain();
+        api.github.lock().unwrap().protected.insert(OTHER_FORK.to_string());
+        
+        let summary = command(&temp_dir, &api).cleanup(&CleanupOptions::default()).unwrap();
+        assert_eq!((summary.matched(), summary.deleted(), summary.failed()), (2, 1, 1));
+        assert_eq!(summary.exit_code(), exit_code::PARTIAL);
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Deleted));
+        assert_eq!(fork_status(&temp_dir, OTHER_FORK), Some(ForkStatus::Exhausted));
+        
+        // The deleted fork is already gone on GitHub: skipped, not retried
+        let options = CleanupOptions {
+            filter: CleanupFilter { statuses: vec![ForkStatus::Deleted], ..CleanupFilter::default() },
+            ..CleanupOptions::default()
+        };
+        let before = deletes(&api);
+        let summary = command(&temp_dir, &api).cleanup(&options).unwrap();
+        assert_eq!((summary.matched(), summary.skipped()), (1, 1));
+        assert_eq!(summary.exit_code(), exit_code::SUCCESS);
+        assert_eq!(deletes(&api), before);
+    }
+    
+    #[test]
+    fn test_cleanup_goes_through_proxies_and_skips_unmapped_tokens() {
+        let (temp_dir, api) = exhausted_chain();
+        // Only user_0's token has a proxy
+        let mappings: BTreeMap<&str, ProxyConfig> =
+            [("ghp_aaaaaaaaaaaa", ProxyConfig::from_url("http://u:p@1.2.3.4:8080").unwrap())].into();
+        fs::write(Paths::new(temp_dir.path()).proxymap(), serde_json::to_string(&mappings).unwrap()).unwrap();
+        
+        let proxies = Arc::new(Mutex::new(Vec::new()));
+        let (seen, api_for) = (proxies.clone(), api.clone());
+        let summary = ForkCommand::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(Box::new(move |account, proxy| {
+                seen.lock().unwrap().push((account.username.clone(), proxy));
+                Box::new(MockForkApi { username: account.username.clone(), ..api_for.clone() })
+            }))
+            .with_sleeper(Arc::new(RecordingSleeper::default()))
+            .cleanup(&CleanupOptions::default())
+            .unwrap();
+        
+        assert_eq!((summary.matched(), summary.deleted(), summary.failed()), (2, 1, 1));
+        assert_eq!(*proxies.lock().unwrap(), vec![("user_0".to_string(), Some("http://u:p@1.2.3.4:8080".to_string()))]);
+        assert!(matches!(&summary.results[1].outcome, CleanupOutcome::Failed(e) if e.contains("No proxy mapped for @user_1")));
+        assert_eq!(deletes(&api), 1);
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Deleted));
+        assert_eq!(fork_status(&temp_dir, OTHER_FORK), Some(ForkStatus::Exhausted));
+    }
+    
+    #[test]
+    fn test_cleanup_cancel_finishes_current_fork_only() {
+        let (temp_dir, api) = exhausted_chain();
+        let cancel = CancelToken::new();
+        api.github.lock().unwrap().cancel_after = Some(("delete ".to_string(), cancel.clone()));
+        
+        let summary = command(&temp_dir, &api).with_cancel(cancel).cleanup(&CleanupOptions::default()).unwrap();
+        
+        // Ctrl-C arrived while FORK was being deleted: its deletion is verified
+        // and saved, OTHER_FORK is not touched
+        assert_eq!((summary.matched(), summary.deleted(), summary.cancelled()), (2, 1, 1));
+        assert_eq!(summary.exit_code(), exit_code::INTERRUPTED);
+        assert_eq!(deletes(&api), 1);
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Deleted));
+        assert_eq!(fork_status(&temp_dir, OTHER_FORK), Some(ForkStatus::Exhausted));
+        
+        let events = EventLog::new(&Paths::new(temp_dir.path())).read_recent(1).unwrap();
+        assert_eq!(events[0].kind, "cancelled");
+    }
+    
+    #[test]
+    fn test_cleanup_archive_for_one_account() {
+        let (temp_dir, api) = exhausted_chain();
+        
+        let options = CleanupOptions {
+            filter: CleanupFilter { account: Some("user_1".to_string()), ..CleanupFilter::default() },
+            mode: CleanupMode::Archive,
+            dry_run: false,
+        };
+        let summary = command(&temp_dir, &api).cleanup(&options).unwrap();
+        
+        assert_eq!((summary.matched(), summary.deleted()), (1, 1));
+        assert!(api.github.lock().unwrap().calls.contains(&format!("archive {}", OTHER_FORK)));
+        assert_eq!(deletes(&api), 0);
+        assert_eq!(fork_status(&temp_dir, OTHER_FORK), Some(ForkStatus::Disabled));
+        assert_eq!(fork_status(&temp_dir, FORK), Some(ForkStatus::Exhausted));
+    }
+    
+    fn tokens(temp_dir: &TempDir) -> TokenCommand {
+        tokens_for(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+    }
+    
+    fn tokens_for(ctx: Arc<AppContext>) -> TokenCommand {
+        TokenCommand::new(ctx)
+            .with_lookup(Box::new(|token, _| match token {
+                "ghp_cccccccccccc" => Ok(TokenLookup { username: "carol".to_string(), expires_at: None, capabilities: None }),
+                "ghp_aaaaaaaaaaaa" => Ok(TokenLookup { username: "user_0".to_string(), expires_at: None, capabilities: None }),
+                _ => Err(anyhow!("HTTP 401: Bad credentials")),
+            }))
+    }
+    
+    #[test]
+    fn test_token_import_checks_each_token_and_writes_the_accepted_ones_together() {
+        let (temp_dir, _api) = setup();
+        let tokens_file = temp_dir.path().join("tokens.txt");
+        let command = TokenCommand::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_lookup(Box::new(|token, _| match token {
+                "ghp_cccccccccccc" => Ok(TokenLookup {
+                    username: "carol".to_string(),
+                    expires_at: None,
+                    capabilities: Some(Capabilities { fork: true, workflows: true, secrets: true, delete: false, billing: true }),
+                }),
+                "ghp_dddddddddddd" => Ok(TokenLookup { username: "dave".to_string(), expires_at: None, capabilities: None }),
+                "ghp_eeeeeeeeeeee" => Ok(TokenLookup { username: "Carol".to_string(), expires_at: None, capabilities: None }),
+                _ => Err(anyhow!("HTTP 401: Bad credentials")),
+            }));
+        
+        let mut import = command.import(false).unwrap();
+        let carol = import.check("ghp_cccccccccccc my-org").unwrap();
+        assert_eq!((carol.index, carol.missing.as_slice()), (2, ["delete"].as_slice()));
+        // Already in tokens.txt, given twice, a taken login, a bad token
+        assert!(import.check("ghp_aaaaaaaaaaaa").is_err());
+        assert!(import.check("ghp_cccccccccccc").is_err());
+        assert!(import.check("ghp_eeeeeeeeeeee").is_err());
+        assert!(import.check("ghp_ffffffffffff").is_err());
+        assert!(import.check("not a token").is_err());
+        assert_eq!(import.check("ghp_dddddddddddd").unwrap().index, 3);
+        
+        // Nothing is written before the end
+        assert_eq!(fs::read_to_string(&tokens_file).unwrap(), "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb\n");
+        let added = import.finish().unwrap();
+        assert_eq!(added.iter().map(|a| a.username.as_str()).collect::<Vec<_>>(), ["carol", "dave"]);
+        assert_eq!(
+            fs::read_to_string(&tokens_file).unwrap(),
+            "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb\nghp_cccccccccccc my-org\nghp_dddddddddddd\n"
+        );
+        
+        let paths = Paths::new(temp_dir.path());
+        assert_eq!(StateManager::new(&paths).unwrap().load_state().unwrap().total_accounts, 4);
+        let mut accounts = AccountManager::new(&paths);
+        accounts.load_tokens(&tokens_file).unwrap();
+        assert_eq!(accounts.get_account(3).unwrap().username, "dave");
+        
+        // An import started before tokens.txt changed writes nothing
+        fs::write(&tokens_file, "ghp_aaaaaaaaaaaa\n").unwrap();
+        let mut import = command.import(false).unwrap();
+        import.check("ghp_ffffffffffff").unwrap_err();
+        import.check("ghp_bbbbbbbbbbbb").unwrap_err();
+        import.check("ghp_dddddddddddd").unwrap();
+        fs::write(&tokens_file, "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb\n").unwrap();
+        let err = import.finish().unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        assert_eq!(fs::read_to_string(&tokens_file).unwrap(), "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb\n");
+    }
+    
+    #[test]
+    fn test_token_edits_reach_the_context_they_were_made_through() {
+        let (temp_dir, _api) = setup();
+        let ctx = Arc::new(AppContext::new(temp_dir.path()).unwrap());
+        assert_eq!(ctx.accounts().unwrap().total_accounts(), 2);
+        
+        tokens_for(ctx.clone()).add("ghp_cccccccccccc").unwrap();
+        assert_eq!(ctx.accounts().unwrap().get_account(2).unwrap().token, "ghp_cccccccccccc");
+        
+        // Later indexes move down, so index 1 is carol's token now
+        tokens_for(ctx.clone()).remove("0", true).unwrap();
+        let accounts = ctx.accounts().unwrap();
+        assert_eq!(accounts.total_accounts(), 2);
+        assert_eq!(accounts.get_account(1).unwrap().token, "ghp_cccccccccccc");
+    }
+    
+    #[test]
+    fn test_token_add_and_remove_keep_chain_indexes_in_step() {
+        let (temp_dir, api) = setup();
+        let tokens_file = temp_dir.path().join("tokens.txt");
+        command(&temp_dir, &api).create(None, Some(PARENT)).unwrap();
+        
+        // Bad and duplicate tokens leave tokens.txt alone
+        let err = tokens(&temp_dir).add("ghp_dddddddddddd").unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        let err = tokens(&temp_dir).add("ghp_bbbbbbbbbbbb").unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        assert_eq!(fs::read_to_string(&tokens_file).unwrap(), "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb\n");
+        
+        let added = tokens(&temp_dir).add("ghp_cccccccccccc my-org").unwrap();
+        assert_eq!((added.index, added.username.as_str()), (2, "carol"));
+        assert_eq!(
+            fs::read_to_string(&tokens_file).unwrap(),
+            "ghp_aaaaaaaaaaaa\nghp_bbbbbbbbbbbb\nghp_cccccccccccc my-org\n"
+        );
+        let paths = Paths::new(temp_dir.path());
+        let state_mgr = StateManager::new(&paths).unwrap();
+        assert_eq!(state_mgr.load_state().unwrap().total_accounts, 3);
+        let mut accounts = AccountManager::new(&paths);
+        accounts.load_tokens(&tokens_file).unwrap();
+        assert_eq!(accounts.get_account(2).unwrap().username, "carol");
+        
+        // carol's fork sits after user_0's in the chain
+        let mut state = state_mgr.load_state().unwrap();
+        let mut node = state.fork_chain[0].clone();
+        node.pat_index = 2;
+        node.repo = "carol/nexus-runner".to_string();
+        state.fork_chain.push(node);
+        state_mgr.save_state(&state).unwrap();
+        
+        // user_0 owns the active fork
+        let err = tokens(&temp_dir).remove("0", false).unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        assert!(err.to_string().contains(FORK), "{}", err);
+        
+        let removed = tokens(&temp_dir).remove("user_0", true).unwrap();
+        assert_eq!(removed.dropped_forks, [FORK]);
+        assert_eq!(removed.renumbered, 1);
+        assert_eq!(fs::read_to_string(&tokens_file).unwrap(), "ghp_bbbbbbbbbbbb\nghp_cccccccccccc my-org\n");
+        
+        let state = state_mgr.load_state().unwrap();
+        assert_eq!(state.total_accounts, 2);
+        assert_eq!(state.fork_chain.len(), 1);
+        assert_eq!((state.fork_chain[0].repo.as_str(), state.fork_chain[0].pat_index), ("carol/nexus-runner", 1));
+        let mut accounts = AccountManager::new(&paths);
+        accounts.load_tokens(&tokens_file).unwrap();
+        assert_eq!(accounts.get_account(1).unwrap().username, "carol");
+        assert_eq!(accounts.get_account(0).unwrap().username, "user_0");
+    }
+}
+
+mod self_update_cli {
+    use anyhow::{anyhow, Result};
+    use nexus_orchestrator::orchestration::self_update::{
+        parse_version, sha256_file, Release, ReleaseAsset, ReleaseSource, SelfUpdater, UpdateOutcome,
+    };
+    use nexus_orchestrator::utils::exit_code;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::io::Write;
+    use std::path::Path;
+    use tempfile::{tempdir, TempDir};
+    
+    const ASSET: &str = "nexus-orchestrator-testos-testarch";
+    const NEW_BINARY: &[u8] = b"new binary v2.1.0";
+    
+    /// Serves releases and asset bodies from memory. A body of `None`
+    /// fails the download after writing half of it.
+    struct MockReleases {
+        releases: Vec<Release>,
+        bodies: BTreeMap<String, Option<Vec<u8>>>,
+    }
+    
+    impl ReleaseSource for MockReleases {
+        fn latest_release(&self) -> Result<Release> {
+            self.releases.first().cloned().ok_or_else(|| anyhow!("HTTP 404"))
+        }
+        
+        fn release_by_tag(&self, tag: &str) -> Result<Release> {
+            self.releases.iter().find(|r| r.tag_name == tag).cloned().ok_or_else(|| anyhow!("HTTP 404"))
+        }
+        
+        fn download(&self, url: &str, out: &mut dyn Write) -> Result<u64> {
+            match self.bodies.get(url) {
+                Some(Some(body)) => {
+                    out.write_all(body)?;
+                    Ok(body.len() as u64)
+                }
+                Some(None) => {
+                    out.write_all(&NEW_BINARY[..NEW_BINARY.len() / 2])?;
+                    Err(anyhow!("connection reset"))
+                }
+                None => Err(anyhow!("HTTP 404")),
+            }
+        }
+    }
+    
+    fn release(tag: &str) -> Release {
+        let asset = |name: &str| ReleaseAsset {
+            name: name.to_string(),
+            browser_download_url: format!("https://example.test/{}/{}", tag, name),
+        };
+        
+        Release {
+            tag_name: tag.to_string(),
+            assets: vec![asset(ASSET), asset("checksums.txt")],
+        }
+    }
+    
+    /// One release v2.1.0 whose checksums file lists `checksum` for the asset.
+    fn source(checksum: &str, binary: Option<&[u8]>) -> MockReleases {
+        let mut bodies = BTreeMap::new();
+        bodies.insert(
+            format!("https://example.test/v2.1.0/{}", ASSET),
+            binary.map(|b| b.to_vec()),
+        );
+        bodies.insert(
+            "https://example.test/v2.1.0/checksums.txt".to_string(),
+            Some(format!("{}  {}\n", checksum, ASSET).into_bytes()),
+        );
+        
+        MockReleases {
+            releases: vec![release("v2.1.0"), release("v1.9.0")],
+            bodies,
+        }
+    }
+    
+    fn new_binary_checksum() -> String {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("new");
+        fs::write(&path, NEW_BINARY).unwrap();
+        sha256_file(&path).unwrap()
+    }
+    
+    fn installed(current: &str) -> (TempDir, std::path::PathBuf) {
+        let temp_dir = tempdir().unwrap();
+        let exe = temp_dir.path().join("nexus-orchestrator");
+        fs::write(&exe, format!("old binary {}", current)).unwrap();
+        (temp_dir, exe)
+    }
+    
+    fn updater(source: MockReleases, exe: &Path, current: &str) -> SelfUpdater {
+        SelfUpdater::new(Box::new(source))
+            .unwrap()
+            .with_target(exe, parse_version(current).unwrap(), ASSET)
+    }
+    
+    fn dir_entries(dir: &Path) -> usize {
+        fs::read_dir(dir).unwrap().count()
+    }
+    
+    #[test]
+    fn test_check_compares_versions() {
+        let (_dir, exe) = installed("2.0.0");
+        
+        let outcome = updater(source("", None), &exe, "2.0.0").check(None).unwrap();
+        assert_eq!(outcome, UpdateOutcome::Available {
+            current: parse_version("2.0.0").unwrap(),
+            release: parse_version("2.1.0").unwrap(),
+        });
+        assert_eq!(outcome.check_exit_code(), exit_code::SUCCESS);
+        
+        let outcome = updater(source("", None), &exe, "2.1.0").check(None).unwrap();
+        assert_eq!(outcome.check_exit_code(), exit_code::FAILURE);
+        
+        // Newer local builds are not "updated" to the older latest release
+        let outcome = updater(source("", None), &exe, "2.2.0").check(None).unwrap();
+        assert!(matches!(outcome, UpdateOutcome::UpToDate { .. }));
+        
+        // ...but an explicit --version may downgrade
+        let outcome = updater(source("", None), &exe, "2.2.0").check(Some("1.9.0")).unwrap();
+        assert!(matches!(outcome, UpdateOutcome::Available { .. }));
+        
+        assert!(updater(source("", None), &exe, "2.0.0").check(Some("v3.0.0")).is_err());
+    }
+    
+    #[test]
+    fn test_update_replaces_verified_binary() {
+        let (dir, exe) = installed("2.0.0");
+        let checksum = new_binary_checksum();
+        
+        let outcome = updater(source(&checksum, Some(NEW_BINARY)), &exe, "2.0.0").update(None).unwrap();
+        
+        assert!(matches!(outcome, UpdateOutcome::Updated { .. }));
+        assert_eq!(fs::read(&exe).unwrap(), NEW_BINARY);
+        assert_eq!(dir_entries(dir.path()), 1);
+    }
+    
+    #[test]
+    fn test_checksum_mismatch_keeps_old_binary() {
+        let (dir, exe) = installed("2.0.0");
+        
+        let err = updater(source(&"0".repeat(64), Some(NEW_BINARY)), &exe, "2.0.0")
+            .update(None)
+            .unwrap_err();
+        
+        assert!(err.to_string().contains("Checksum mismatch"), "{}", err);
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "old binary 2.0.0");
+        assert_eq!(dir_entries(dir.path()), 1);
+    }
+    
+    #[test]
+    fn test_failed_download_leaves_nothing_behind() {
+        let (dir, exe) = installed("2.0.0");
+        
+        assert!(updater(source(&new_binary_checksum(), None), &exe, "2.0.0").update(None).is_err());
+        
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "old binary 2.0.0");
+        assert_eq!(dir_entries(dir.path()), 1);
+    }
+    
+    #[test]
+    fn test_missing_checksum_refuses_install() {
+        let (dir, exe) = installed("2.0.0");
+        let mut source = source(&new_binary_checksum(), Some(NEW_BINARY));
+        source.releases[0].assets.retain(|a| a.name != "checksums.txt");
+        
+        let err = updater(source, &exe, "2.0.0").update(None).unwrap_err();
+        
+        assert!(err.to_string().contains("unverified"), "{}", err);
+        assert_eq!(dir_entries(dir.path()), 1);
+    }
+}
+
+mod github_flows {
+    use anyhow::{anyhow, Result};
+    use chrono::{DateTime, Utc};
+    use nexus_orchestrator::core::account::OwnerType;
+    use nexus_orchestrator::core::billing::BillingMonitor;
+    use nexus_orchestrator::core::state::{ForkStatus, OrchestratorState};
+    use nexus_orchestrator::core::{AccountManager, Paths, StateManager};
+    use nexus_orchestrator::github::api::{Artifact, GitHubError, RunJob, MergeUpstream, RepoFile, RepoInfo, RepoRef, RunSummary, WorkflowRun};
+    use nexus_orchestrator::github::fork::Check;
+    use nexus_orchestrator::github::{ApiFactory, ForkApi, ForkManager, GitHubApi, SecretHashes, SecretLayout, SecretWriteResult, SecretsManager, VariablesManager, WorkflowController};
+    use nexus_orchestrator::github::workflow::{Drift, KeepAlive};
+    use nexus_orchestrator::orchestration::secrets::set_secret;
+    use nexus_orchestrator::orchestration::sync::{Discrepancy, StateSync};
+    use nexus_orchestrator::orchestration::tokens::TokenCommand;
+    use nexus_orchestrator::orchestration::trigger::{TriggerOptions, TriggerOutcome};
+    use nexus_orchestrator::orchestration::{Deployer, Rotator, Trigger};
+    use nexus_orchestrator::monitor::events::{Event, EventLog};
+    use nexus_orchestrator::utils::crypto::init_crypto;
+    use nexus_orchestrator::utils::delay::RecordingSleeper;
+    use nexus_orchestrator::utils::{cancel, exit_code, CancelToken};
+    use nexus_orchestrator::AppContext;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex, MutexGuard};
+    use std::time::Duration;
+    use tempfile::{tempdir, TempDir};
+    
+    const SOURCE: &str = "origin/nexus-runner";
+    const PUBLIC_KEY: &str = "hBSZF+rsRNIWNzMC2DUc6lE1R0CKT8pFqPxQH+2F6zk=";
+    const KEY_ID: &str = "568250167242549743";
+    
+    /// GitHub's side of the flows: repos, workflows, secrets and billed
+    /// minutes, shared by every account's client.
+    #[derive(Default)]
+    struct Backend {
+        repos: BTreeSet<String>,
+        workflows: BTreeMap<String, u64>,
+        /// (repo, name) -> (encrypted value, key id)
+        secrets: BTreeMap<(String, String), (String, String)>,
+        /// (repo, name) -> updated_at; secrets missing here date from the epoch
+        secret_times: BTreeMap<(String, String), DateTime<Utc>>,
+        /// Secret names whose PUTs are accepted but never land
+        dropped_puts: BTreeSet<String>,
+        /// Actions minutes per owner; owners missing here fail the billing call
+        minutes: BTreeMap<String, f32>,
+        /// GitHub plan per owner; owners missing here have no visible plan
+        plans: BTreeMap<String, String>,
+        /// Latest workflow run per repo: (run id, status)
+        runs: BTreeMap<String, (u64, String)>,
+        /// Runs per repo waiting for a runner
+        queued_runs: BTreeMap<String, Vec<WorkflowRun>>,
+        /// Fork -> the repo it was forked from
+        parents: BTreeMap<String, String>,
+        /// Repos whose workflow is disabled
+        disabled: BTreeSet<String>,
+        /// Repos whose workflow GitHub disabled for inactivity
+        inactive: BTreeSet<String>,
+        /// Last push per repo; unknown for repos missing here
+        pushed: BTreeMap<String, DateTime<Utc>>,
+        /// Owners whose token no longer authenticates
+        revoked: BTreeSet<String>,
+        /// (repo id, name) of the GitHub Environments
+        environments: BTreeSet<(u64, String)>,
+        /// (repo, name) -> Actions variable value
+        variables: BTreeMap<(String, String), String>,
+        /// (repo, path) -> file on the default branch
+        files: BTreeMap<(String, String), RepoFile>,
+        /// (repo, branch) of the branches created besides `main`
+        branches: BTreeSet<(String, String)>,
+        calls: Vec<String>,
+        /// Cancelled (as by Ctrl-C) right after the first call starting with the prefix
+        cancel_after: Option<(String, CancelToken)>,
+    }
+    
+    /// The backend, borrowed like a RefCell but shared with the threads of
+    /// the pooled flows.
+    #[derive(Default)]
+    struct Shared(Mutex<Backend>);
+    
+    impl Shared {
+        fn borrow(&self) -> MutexGuard<'_, Backend> {
+            self.0.lock().unwrap()
+        }
+        
+        fn borrow_mut(&self) -> MutexGuard<'_, Backend> {
+            self.0.lock().unwrap()
+        }
+    }
+    
+    /// Client for one account; forks land under its owner.
+    #[derive(Clone, Default)]
+    struct MockGitHub {
+        backend: Arc<Shared>,
+        owner: String,
+    }
+    
+    impl MockGitHub {
+        fn log(&self, call: String) {
+            let mut backend = self.backend.borrow_mut();
+            if let Some((prefix, cancel)) = &backend.cancel_after {
+                if call.starts_with(prefix.as_str()) {
+                    cancel.cancel();
+                }
+            }
+            backend.calls.push(call);
+        }
+        
+        /// A PUT as GitHub applies it, unless the name's PUTs are dropped.
+        fn store_secret(&self, repo: &str, name: &str, encrypted_value: &str, key_id: &str) {
+            let mut backend = self.backend.borrow_mut();
+            if backend.dropped_puts.contains(name) {
+                return;
+            }
+            let key = (repo.to_string(), name.to_string());
+            backend.secret_times.insert(key.clone(), Utc::now());
+            backend.secrets.insert(key, (encrypted_value.to_string(), key_id.to_string()));
+        }
+        
+        fn cancel_after(&self, prefix: &str) -> CancelToken {
+            let cancel = CancelToken::new();
+            self.backend.borrow_mut().cancel_after = Some((prefix.to_string(), cancel.clone()));
+            cancel
+        }
+        
+        fn as_owner(&self, owner: &str) -> Self {
+            Self { owner: owner.to_string(), ..self.clone() }
+        }
+        
+        fn factory(&self) -> ApiFactory {
+            let api = self.clone();
+            Box::new(move |account, _| Box::new(api.as_owner(account.owner())))
+        }
+        
+        fn calls_starting(&self, prefix: &str) -> Vec<String> {
+            self.backend.borrow().calls.iter().filter(|c| c.starts_with(prefix)).cloned().collect()
+        }
+    }
+    
+    impl ForkApi for MockGitHub {
+        fn check_repo_exists(&self, repo: &str) -> Result<bool> {
+            Ok(self.backend.borrow().repos.contains(repo))
+        }
+        
+        fn get_repo_info(&self, repo: &str) -> Result<RepoInfo> {
+            let backend = self.backend.borrow();
+            if !backend.repos.contains(repo) {
+                return Err(GitHubError::NotFound("Not Found (HTTP 404)".to_string()).into());
+            }
+            Ok(RepoInfo {
+                full_name: repo.to_string(),
+                default_branch: "main".to_string(),
+                id: repo_id(&backend, repo),
+                size: 64,
+                parent: backend.parents.get(repo).map(|parent| RepoRef { full_name: parent.clone() }),
+                pushed_at: backend.pushed.get(repo).copied(),
+                ..RepoInfo::default()
+            })
+        }
+        
+        fn branch_head(&self, repo: &str, branch: &str) -> Result<Option<String>> {
+            let backend = self.backend.borrow();
+            let exists = branch == "main" || backend.branches.contains(&(repo.to_string(), branch.to_string()));
+            Ok((backend.repos.contains(repo) && exists).then(|| "0123abcd".to_string()))
+        }
+        
+        fn create_fork(&self, source_repo: &str, organization: Option<&str>, name: Option<&str>) -> Result<String> {
+            self.log(format!("fork {}", source_repo));
+            let name = name.unwrap_or_else(|| source_repo.split('/').nth(1).unwrap());
+            let fork = format!("{}/{}", organization.unwrap_or(&self.owner), name);
+            
+            let mut backend = self.backend.borrow_mut();
+            let workflow_id = backend.workflows.len() as u64 + 1;
+            backend.repos.insert(fork.clone());
+            backend.workflows.insert(fork.clone(), workflow_id);
+            backend.parents.insert(fork.clone(), source_repo.to_string());
+            Ok(fork)
+        }
+        
+        fn delete_repo(&self, repo: &str) -> Result<()> {
+            self.log(format!("delete {}", repo));
+            self.backend.borrow_mut().repos.remove(repo);
+            Ok(())
+        }
+        
+        fn archive_repo(&self, repo: &str) -> Result<()> {
+            self.log(format!("archive {}", repo));
+            Ok(())
+        }
+        
+        fn transfer_repo(&self, repo: &str, new_owner: &str) -> Result<()> {
+            self.log(format!("transfer {} to {}", repo, new_owner));
+            let mut backend = self.backend.borrow_mut();
+            backend.repos.remove(repo);
+            backend.repos.insert(format!("{}/{}", new_owner, repo.split('/').nth(1).unwrap()));
+            Ok(())
+        }
+        
+        fn list_forks(&self, _repo: &str) -> Result<Vec<(String, String)>> {
+            Ok(Vec::new())
+        }
+        
+        fn get_workflow_id(&self, repo: &str, _workflow_file: &str) -> Result<Option<u64>> {
+            Ok(self.backend.borrow().workflows.get(repo).copied())
+        }
+        
+        fn workflow_state(&self, repo: &str, _workflow_file: &str) -> Result<Option<String>> {
+            let backend = self.backend.borrow();
+            let state = if backend.inactive.contains(repo) {
+                "disabled_inactivity"
+            } else if backend.disabled.contains(repo) {
+                "disabled_manually"
+            } else {
+                "active"
+            };
+            Ok(backend.workflows.contains_key(repo).then(|| state.to_string()))
+        }
+        
+        fn disable_workflow(&self, repo: &str, workflow_id: u64) -> Result<()> {
+            self.log(format!("disable {} {}", repo, workflow_id));
+            self.backend.borrow_mut().disabled.insert(repo.to_string());
+            Ok(())
+        }
+        
+        fn enable_actions(&self, repo: &str) -> Result<()> {
+            self.log(format!("enable-actions {}", repo));
+            Ok(())
+        }
+        
+        fn list_active_runs(&self, repo: &str, _workflow_file: Option<&str>) -> Result<Vec<u64>> {
+            match self.backend.borrow().runs.get(repo) {
+                Some((run_id, status)) if status != "completed" => Ok(vec![*run_id]),
+                _ => Ok(Vec::new()),
+            }
+        }
+        
+        fn run_summary(&self, _repo: &str) -> Result<RunSummary> {
+            Ok(RunSummary::default())
+        }
+        
+        fn cancel_workflow_run(&self, repo: &str, run_id: u64) -> Result<()> {
+            self.log(format!("cancel {} {}", repo, run_id));
+            if let Some((_, status)) = self.backend.borrow_mut().runs.get_mut(repo) {
+                *status = "completed".to_string();
+            }
+            Ok(())
+        }
+        
+        fn merge_upstream(&self, repo: &str, branch: &str) -> Result<MergeUpstream> {
+            self.log(format!("sync {} {}", repo, branch));
+            Ok(MergeUpstream { merge_type: Some("none".to_string()), ..MergeUpstream::default() })
+        }
+        
+        fn api_graphql(&self, _query: &str, _variables: serde_json::Value) -> Result<serde_json::Value> {
+            Err(anyhow!("GraphQL is not mocked"))
+        }
+        
+        fn token_scopes(&self) -> Result<Option<Vec<String>>> {
+            if self.backend.borrow().revoked.contains(&self.owner) {
+                return Err(GitHubError::Unauthorized("Bad credentials (HTTP 401)".to_string()).into());
+            }
+            Ok(None)
+        }
+        
+        fn username(&self) -> Result<String> {
+            if self.backend.borrow().revoked.contains(&self.owner) {
+                return Err(GitHubError::Unauthorized("Bad credentials (HTTP 401)".to_string()).into());
+            }
+            Ok(self.owner.clone())
+        }
+    }
+    
+    impl GitHubApi for MockGitHub {
+        fn get_repo_public_key(&self, repo: &str) -> Result<(String, String)> {
+            self.log(format!("key {}", repo));
+            if !self.backend.borrow().repos.contains(repo) {
+                return Err(anyhow!("HTTP 404: Not Found"));
+            }
+            Ok((PUBLIC_KEY.to_string(), KEY_ID.to_string()))
+        }
+        
+        fn secret_exists(&self, repo: &str, secret_name: &str) -> Result<bool> {
+            Ok(self.backend.borrow().secrets.contains_key(&(repo.to_string(), secret_name.to_string())))
+        }
+        
+        fn put_secret(&self, repo: &str, secret_name: &str, encrypted_value: &str, key_id: &str) -> Result<()> {
+            self.log(format!("put {} {}", repo, secret_name));
+            self.store_secret(repo, secret_name, encrypted_value, key_id);
+            Ok(())
+        }
+        
+        fn delete_secret(&self, repo: &str, secret_name: &str) -> Result<()> {
+            self.log(format!("unset {} {}", repo, secret_name));
+            self.backend
+                .borrow_mut()
+                .secrets
+                .remove(&(repo.to_string(), secret_name.to_string()))
+                .map(|_| ())
+                .ok_or_else(|| GitHubError::NotFound("Not Found (HTTP 404)".to_string()).into())
+        }
+        
+        fn list_secrets(&self, repo: &str) -> Result<Vec<String>> {
+            Ok(self.backend
+                .borrow()
+                .secrets
+                .keys()
+                .filter(|(r, _)| r == repo)
+                .map(|(_, name)| name.clone())
+                .collect())
+        }
+        
+        fn environment_public_key(&self, repo_id: u64, environment: &str) -> Result<(String, String)> {
+            self.log(format!("key #{} {}", repo_id, environment));
+            if !self.backend.borrow().environments.contains(&(repo_id, environment.to_string())) {
+                return Err(GitHubError::NotFound("Not Found (HTTP 404)".to_string()).into());
+            }
+            Ok((PUBLIC_KEY.to_string(), KEY_ID.to_string()))
+        }
+        
+        fn put_environment_secret(
+            &self,
+            repo_id: u64,
+            environment: &str,
+            secret_name: &str,
+            encrypted_value: &str,
+            key_id: &str,
+        ) -> Result<()> {
+            self.log(format!("put #{} {} {}", repo_id, environment, secret_name));
+            self.store_secret(&format!("#{}/{}", repo_id, environment), secret_name, encrypted_value, key_id);
+            Ok(())
+        }
+        
+        fn list_secret_times(&self, repo: &str) -> Result<Vec<(String, DateTime<Utc>)>> {
+            let backend = self.backend.borrow();
+            Ok(backend
+                .secrets
+                .keys()
+                .filter(|(r, _)| r == repo)
+                .map(|key| (key.1.clone(), backend.secret_times.get(key).copied().unwrap_or_default()))
+                .collect())
+        }
+        
+        fn list_environment_secrets(&self, repo_id: u64, environment: &str) -> Result<Vec<String>> {
+            self.list_secrets(&format!("#{}/{}", repo_id, environment))
+        }
+        
+        fn list_environment_secret_times(&self, repo_id: u64, environment: &str) -> Result<Vec<(String, DateTime<Utc>)>> {
+            self.list_secret_times(&format!("#{}/{}", repo_id, environment))
+        }
+        
+        fn delete_environment_secret(&self, repo_id: u64, environment: &str, secret_name: &str) -> Result<()> {
+            self.log(format!("unset #{} {} {}", repo_id, environment, secret_name));
+            self.backend
+                .borrow_mut()
+                .secrets
+                .remove(&(format!("#{}/{}", repo_id, environment), secret_name.to_string()))
+                .map(|_| ())
+                .ok_or_else(|| anyhow!("HTTP 404: Not Found"))
+        }
+        
+        fn create_environment(&self, repo: &str, environment: &str) -> Result<bool> {
+            let mut backend = self.backend.borrow_mut();
+            let id = repo_id(&backend, repo);
+            Ok(backend.environments.insert((id, environment.to_string())))
+        }
+        
+        fn create_variable(&self, repo: &str, name: &str, value: &str) -> Result<()> {
+            self.log(format!("var create {} {}", repo, name));
+            let mut backend = self.backend.borrow_mut();
+            let key = (repo.to_string(), name.to_string());
+            if backend.variables.contains_key(&key) {
+                return Err(GitHubError::Conflict("Already exists - Variable already exists (HTTP 409)".to_string()).into());
+            }
+            backend.variables.insert(key, value.to_string());
+            Ok(())
+        }
+        
+        fn update_variable(&self, repo: &str, name: &str, value: &str) -> Result<()> {
+            self.log(format!("var update {} {}", repo, name));
+            self.backend.borrow_mut().variables.insert((repo.to_string(), name.to_string()), value.to_string());
+            Ok(())
+        }
+        
+        fn get_variable(&self, repo: &str, name: &str) -> Result<Option<String>> {
+            Ok(self.backend.borrow().variables.get(&(repo.to_string(), name.to_string())).cloned())
+        }
+        
+        fn list_variables(&self, repo: &str) -> Result<Vec<(String, String)>> {
+            Ok(self.backend
+                .borrow()
+                .variables
+                .iter()
+                .filter(|((r, _), _)| r == repo)
+                .map(|((_, name), value)| (name.clone(), value.clone()))
+                .collect())
+        }
+        
+        fn delete_variable(&self, repo: &str, name: &str) -> Result<()> {
+            self.log(format!("var delete {} {}", repo, name));
+            match self.backend.borrow_mut().variables.remove(&(repo.to_string(), name.to_string())) {
+                Some(_) => Ok(()),
+                None => Err(GitHubError::NotFound("Not Found (HTTP 404)".to_string()).into()),
+            }
+        }
+        
+        fn get_file(&self, repo: &str, path: &str, branch: &str) -> Result<Option<RepoFile>> {
+            self.log(format!("get-file {} {} {}", repo, path, branch));
+            Ok(self.backend.borrow().files.get(&(repo.to_string(), path.to_string())).cloned())
+        }
+        
+        fn put_file(&self, repo: &str, path: &str, branch: &str, content: &str, _message: &str, sha: Option<&str>) -> Result<()> {
+            self.log(format!("put-file {} {} {} {}", repo, path, branch, sha.unwrap_or("new")));
+            let mut backend = self.backend.borrow_mut();
+            let key = (repo.to_string(), path.to_string());
+            // GitHub refuses to replace a file without its current SHA
+            if backend.files.get(&key).map(|file| file.sha.as_str()) != sha {
+                return Err(GitHubError::Conflict("sha does not match (HTTP 409)".to_string()).into());
+            }
+            let sha = format!("sha{}", backend.calls.len());
+            backend.files.insert(key, RepoFile { sha, content: content.to_string() });
+            backend.pushed.insert(repo.to_string(), Utc::now());
+            Ok(())
+        }
+        
+        fn create_branch(&self, repo: &str, branch: &str, sha: &str) -> Result<()> {
+            self.log(format!("create-branch {} {} {}", repo, branch, sha));
+            self.backend.borrow_mut().branches.insert((repo.to_string(), branch.to_string()));
+            Ok(())
+        }
+        
+        fn enable_workflow(&self, repo: &str, workflow_id: u64) -> Result<()> {
+            self.log(format!("enable {} {}", repo, workflow_id));
+            let mut backend = self.backend.borrow_mut();
+            backend.disabled.remove(repo);
+            backend.inactive.remove(repo);
+            Ok(())
+        }
+        
+        fn trigger_workflow(
+            &self,
+            repo: &str,
+            workflow_file: &str,
+            ref_name: &str,
+            _inputs: Option<&serde_json::Value>,
+        ) -> Result<()> {
+            self.log(format!("dispatch {} {} {}", repo, workflow_file, ref_name));
+            Ok(())
+        }
+        
+        fn get_latest_workflow_run(
+            &self,
+            repo: &str,
+            _workflow_file: &str,
+            _created_after: Option<DateTime<Utc>>,
+        ) -> Result<Option<u64>> {
+            Ok(self.backend.borrow().runs.get(repo).map(|(run_id, _)| *run_id))
+        }
+        
+        fn list_workflow_runs(
+            &self,
+            repo: &str,
+            workflow_file: &str,
+            status: Option<&str>,
+            _per_page: u32,
+        ) -> Result<Vec<WorkflowRun>> {
+            self.log(format!("list-runs {} {} {}", repo, workflow_file, status.unwrap_or("any")));
+            let backend = self.backend.borrow();
+            match status {
+                Some("queued") | None => Ok(backend.queued_runs.get(repo).cloned().unwrap_or_default()),
+                Some(_) => Ok(Vec::new()),
+            }
+        }
+        
+        fn get_run_jobs(&self, _repo: &str, _run_id: u64) -> Result<Vec<RunJob>> {
+            Ok(Vec::new())
+        }
+        
+        fn get_workflow_status(&self, repo: &str, _run_id: u64) -> Result<(String, Option<String>)> {
+            match self.backend.borrow().runs.get(repo) {
+                Some((_, status)) if status != "completed" => Ok((status.clone(), None)),
+                _ => Ok(("completed".to_string(), Some("success".to_string()))),
+            }
+        }
+        
+        fn latest_failed_run(&self, _repo: &str) -> Result<Option<u64>> {
+            Ok(None)
+        }
+        
+        fn rerun_run(&self, repo: &str, run_id: u64) -> Result<()> {
+            self.log(format!("rerun {} {}", repo, run_id));
+            Ok(())
+        }
+        
+        fn rerun_failed_jobs(&self, repo: &str, run_id: u64) -> Result<()> {
+            self.log(format!("rerun-failed {} {}", repo, run_id));
+            Ok(())
+        }
+        
+        fn download_run_logs(&self, repo: &str, run_id: u64, _dest: &Path) -> Result<()> {
+            Err(anyhow!("no logs for run {} in {}", run_id, repo))
+        }
+        
+        fn list_artifacts(&self, _repo: &str, _run_id: u64) -> Result<Vec<Artifact>> {
+            Ok(Vec::new())
+        }
+        
+        fn download_artifact(&self, repo: &str, artifact_id: u64, _dest: &Path) -> Result<()> {
+            Err(anyhow!("no artifact {} in {}", artifact_id, repo))
+        }
+        
+        fn actions_minutes_used(&self, owner: &str, _owner_type: OwnerType) -> Result<f32> {
+            self.log(format!("billing {}", owner));
+            self.backend
+                .borrow()
+                .minutes
+                .get(owner)
+                .copied()
+                .ok_or_else(|| GitHubError::Forbidden("Resource not accessible by personal access token (HTTP 403)".to_string()).into())
+        }
+        
+        fn plan_name(&self, owner: &str, _owner_type: OwnerType) -> Result<Option<String>> {
+            Ok(self.backend.borrow().plans.get(owner).cloned())
+        }
+    }
+    
+    /// Forks are numbered after their workflow id, the source is #1000.
+    fn repo_id(backend: &Backend, repo: &str) -> u64 {
+        1000 + backend.workflows.get(repo).copied().unwrap_or(0)
+    }
+    
+    fn setup(accounts: usize) -> (TempDir, MockGitHub) {
+        let temp_dir = tempdir().unwrap();
+        let tokens: String = (0..accounts)
+            .map(|i| format!("ghp_{}\n", i.to_string().repeat(12)))
+            .collect();
+        fs::write(temp_dir.path().join("tokens.txt"), tokens).unwrap();
+        
+        let api = MockGitHub::default();
+        api.backend.borrow_mut().repos.insert(SOURCE.to_string());
+        (temp_dir, api)
+    }
+    
+    fn load_state(temp_dir: &TempDir) -> OrchestratorState {
+        StateManager::new(&Paths::new(temp_dir.path())).unwrap().load_state().unwrap()
+    }
+    
+    /// Forks SOURCE -> user_0 -> user_1 -> ..., one fork per account.
+    fn build_chain(temp_dir: &TempDir, api: &MockGitHub, sleeper: Arc<RecordingSleeper>) -> Vec<String> {
+        let fork_mgr = ForkManager::new(Arc::new(StateManager::new(&Paths::new(temp_dir.path())).unwrap())).with_sleeper(sleeper);
+        let mut accounts = AccountManager::new(&Paths::new(temp_dir.path()));
+        accounts.load_tokens(&temp_dir.path().join("tokens.txt")).unwrap();
+        
+        let mut state = load_state(temp_dir);
+        let mut parent = SOURCE.to_string();
+        let mut forks = Vec::new();
+        
+        for account in accounts.get_all_accounts() {
+            let client = api.as_owner(account.owner());
+            let (next_state, fork) = fork_mgr.create_fork_chain(state, account, &parent, &client).unwrap();
+            state = next_state;
+            parent = fork.clone();
+            forks.push(fork);
+        }
+        
+        forks
+    }
+    
+    #[test]
+    fn test_fork_chain_creation() {
+        let (temp_dir, api) = setup(3);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        
+        assert_eq!(forks, vec!["user_0/nexus-runner", "user_1/nexus-runner", "user_2/nexus-runner"]);
+        assert_eq!(api.calls_starting("fork "), vec![
+            format!("fork {}", SOURCE),
+            "fork user_0/nexus-runner".to_string(),
+            "fork user_1/nexus-runner".to_string(),
+        ]);
+        assert_eq!(sleeper.count("fork_poll"), 3);
+        
+        let state = load_state(&temp_dir);
+        let parents: Vec<_> = state.fork_chain.iter().map(|n| n.parent.clone().unwrap()).collect();
+        assert_eq!(parents, vec![SOURCE, "user_0/nexus-runner", "user_1/nexus-runner"]);
+        assert!(state.fork_chain.iter().all(|n| n.status == ForkStatus::Active));
+        
+        // Forks already in the chain are reused, not forked again
+        let mut accounts = AccountManager::new(&Paths::new(temp_dir.path()));
+        accounts.load_tokens(&temp_dir.path().join("tokens.txt")).unwrap();
+        let fork_mgr = ForkManager::new(Arc::new(StateManager::new(&Paths::new(temp_dir.path())).unwrap())).with_sleeper(sleeper);
+        let account = accounts.get_account(0).unwrap();
+        let (state, fork) = fork_mgr
+            .create_fork_chain(state, account, SOURCE, &api.as_owner("user_0"))
+            .unwrap();
+        
+        assert_eq!(fork, "user_0/nexus-runner");
+        assert_eq!(state.fork_chain.len(), 3);
+        assert_eq!(api.calls_starting("fork ").len(), 3);
+    }
+    
+    #[test]
+    fn test_full_rotation() {
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        build_chain(&temp_dir, &api, sleeper.clone());
+        {
+            let mut backend = api.backend.borrow_mut();
+            // 3600 minutes is 120 core-hours, past the 119.5 critical threshold
+            backend.minutes.insert("user_0".to_string(), 3600.0);
+            backend.minutes.insert("user_1".to_string(), 600.0);
+        }
+        
+        let rotator = || {
+            Rotator::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+                .with_client_factory(api.factory())
+                .with_sleeper(sleeper.clone())
+        };
+        
+        api.backend.borrow_mut().runs.insert("user_0/nexus-runner".to_string(), (77, "in_progress".to_string()));
+        
+        assert!(rotator().check_and_rotate().unwrap());
+        assert_eq!(api.calls_starting("disable "), vec!["disable user_0/nexus-runner 1"]);
+        // The run still burning the exhausted account's minutes is cancelled
+        assert_eq!(api.calls_starting("cancel "), vec!["cancel user_0/nexus-runner 77"]);
+        assert_eq!(sleeper.count("run_cancel_poll"), 0);
+        assert_eq!(sleeper.count("rotation_cooldown"), 1);
+        // The fork taking over is synced with upstream first
+        assert_eq!(api.calls_starting("sync "), vec!["sync user_1/nexus-runner main"]);
+        
+        let state = load_state(&temp_dir);
+        assert_eq!(state.fork_chain[0].status, ForkStatus::Exhausted);
+        assert_eq!(state.fork_chain[1].status, ForkStatus::Active);
+        assert_eq!((state.current_active_index, state.total_accounts), (1, 2));
+        
+        // user_1 is the active fork now and still has quota
+        assert!(!rotator().check_and_rotate().unwrap());
+        assert_eq!(api.calls_starting("billing "), vec!["billing user_0", "billing user_1"]);
+        assert_eq!(api.calls_starting("disable ").len(), 1);
+        
+        // A failing billing call counts as exhausted, and the index wraps around
+        api.backend.borrow_mut().minutes.remove("user_1");
+        assert!(rotator().check_and_rotate().unwrap());
+        assert_eq!(api.calls_starting("disable ").len(), 2);
+        
+        let state = load_state(&temp_dir);
+        assert!(state.fork_chain.iter().all(|n| n.status == ForkStatus::Exhausted));
+        assert_eq!(state.current_active_index, 0);
+    }
+    
+    #[test]
+    fn test_rotation_revalidates_the_exhausted_account_first() {
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        build_chain(&temp_dir, &api, sleeper.clone());
+        {
+            let mut backend = api.backend.borrow_mut();
+            backend.minutes.insert("user_0".to_string(), 3600.0);
+            backend.minutes.insert("user_1".to_string(), 600.0);
+            backend.revoked.insert("user_0".to_string());
+        }
+        let ctx = || Arc::new(AppContext::new(temp_dir.path()).unwrap());
+        
+        // The billing reading is no proof the token still works; its 401 on
+        // revalidation sets the account aside instead of retrying it
+        let rotator = Rotator::new(ctx()).with_client_factory(api.factory()).with_sleeper(sleeper);
+        assert!(rotator.check_and_rotate().unwrap());
+        assert!(api.calls_starting("disable ").is_empty());
+        
+        let state = load_state(&temp_dir);
+        assert_eq!(state.fork_chain[0].status, ForkStatus::Exhausted);
+        assert_eq!(state.current_active_index, 1);
+        
+        let quarantined = ctx().accounts().unwrap().get_account(0).unwrap().clone();
+        assert!(!quarantined.enabled && quarantined.quarantined_at.is_some());
+        let events = EventLog::new(&Paths::new(temp_dir.path())).read_recent(10).unwrap();
+        assert!(events.iter().any(|e| e.kind == "quarantine" && e.message.contains("Token #0 (@user_0)")), "{:?}", events);
+        
+        // Once the token is fixed, enabling the account lifts the quarantine
+        let enabled = TokenCommand::new(ctx()).set_enabled("0", true).unwrap();
+        assert!(enabled.enabled && enabled.quarantined_at.is_none());
+    }
+    
+    #[test]
+    fn test_fine_grained_token_without_billing_permission_is_not_exhausted() {
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        build_chain(&temp_dir, &api, sleeper.clone());
+        fs::write(temp_dir.path().join("tokens.txt"), "github_pat_000000000000
+ghp_111111111111
+").unwrap();
+
+        let ctx = Arc::new(AppContext::new(temp_dir.path()).unwrap());
+        let account = ctx.accounts().unwrap().get_account(0).unwrap().clone();
+        let info = BillingMonitor::default().check_billing(&account, &api.as_owner("user_0")).unwrap();
+        assert!(info.permission_missing);
+        assert!(!info.is_exhausted && !info.is_warning);
+        
+        // Unknown usage is no reason to rotate away
+        let rotator = Rotator::new(ctx).with_client_factory(api.factory()).with_sleeper(sleeper);
+        assert!(!rotator.check_and_rotate().unwrap());
+        assert!(api.calls_starting("disable ").is_empty());
+        assert_eq!(load_state(&temp_dir).fork_chain[0].status, ForkStatus::Active);
+    }
+    
+    #[test]
+    fn test_pro_plan_quota_scales_the_thresholds() {
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        build_chain(&temp_dir, &api, sleeper.clone());
+        {
+            let mut backend = api.backend.borrow_mut();
+            // 120 core-hours would exhaust a free account; Pro includes 3000 minutes, 180 core-hours
+            backend.minutes.insert("user_0".to_string(), 3600.0);
+            backend.minutes.insert("user_1".to_string(), 3600.0);
+            backend.plans.insert("user_0".to_string(), "pro".to_string());
+        }
+        
+        let ctx = Arc::new(AppContext::new(temp_dir.path()).unwrap());
+        let accounts = ctx.accounts().unwrap();
+        let monitor = BillingMonitor::default();
+        
+        let pro = monitor.check_billing(accounts.get_account(0).unwrap(), &api.as_owner("user_0")).unwrap();
+        assert_eq!(pro.plan_name.as_deref(), Some("pro"));
+        assert_eq!((pro.included_minutes, pro.included_core_hours, pro.hours_remaining), (3000.0, 180.0, 60.0));
+        assert!(!pro.is_warning && !pro.is_exhausted);
+        assert_eq!(pro.usage_percent().round(), 67.0);
+        
+        let free = monitor.check_billing(accounts.get_account(1).unwrap(), &api.as_owner("user_1")).unwrap();
+        assert_eq!((free.plan_name, free.included_core_hours), (None, 120.0));
+        assert!(free.is_exhausted);
+        
+        let rotator = Rotator::new(ctx.clone()).with_client_factory(api.factory()).with_sleeper(sleeper);
+        assert!(!rotator.check_and_rotate().unwrap());
+        assert_eq!(load_state(&temp_dir).fork_chain[0].status, ForkStatus::Active);
+    }
+    
+    #[test]
+    fn test_sync_enriches_state() {
+        let (temp_dir, api) = setup(3);
+        build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        
+        let state_mgr = StateManager::new(&Paths::new(temp_dir.path())).unwrap();
+        let mut state = state_mgr.load_state().unwrap();
+        state.fork_chain[0].status = ForkStatus::Exhausted;
+        state_mgr.save_state(&state).unwrap();
+        {
+            let mut backend = api.backend.borrow_mut();
+            backend.minutes.insert("user_0".to_string(), 3600.0);
+            backend.minutes.insert("user_1".to_string(), 600.0);
+            backend.runs.insert("user_0/nexus-runner".to_string(), (77, "in_progress".to_string()));
+            backend.runs.insert("user_1/nexus-runner".to_string(), (12, "completed".to_string()));
+            backend.repos.remove("user_2/nexus-runner");
+        }
+        
+        let report = StateSync::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(api.factory())
+            .run()
+            .unwrap();
+        
+        assert_eq!(report.refreshed, vec!["user_0/nexus-runner", "user_1/nexus-runner"]);
+        assert_eq!(report.discrepancies, vec![
+            Discrepancy::RunOnExhausted { repo: "user_0/nexus-runner".to_string(), run_id: 77, status: "in_progress".to_string() },
+            Discrepancy::MissingRepo { repo: "user_2/nexus-runner".to_string() },
+        ]);
+        assert_eq!(report.exit_code(), exit_code::SUCCESS);
+        
+        let state = load_state(&temp_dir);
+        let run = state.fork_chain[0].last_run.as_ref().unwrap();
+        assert_eq!((state.fork_chain[0].billing_used, run.run_id, run.status.as_str()), (120.0, 77, "in_progress"));
+        let run = state.fork_chain[1].last_run.as_ref().unwrap();
+        assert_eq!((state.fork_chain[1].billing_used, run.run_id, run.conclusion.as_deref()), (20.0, 12, Some("success")));
+        assert!(state.fork_chain[1].last_updated > state.fork_chain[1].created_at);
+        assert!(state.fork_chain[2].last_run.is_none());
+        
+        let events = EventLog::new(&Paths::new(temp_dir.path())).read_recent(10).unwrap();
+        let discrepancies: Vec<_> = events.iter().filter(|e| e.kind == "discrepancy").collect();
+        assert_eq!(discrepancies.len(), 2);
+        assert_eq!(discrepancies[1].repo.as_deref(), Some("user_2/nexus-runner"));
+        
+        // Billing is looked up once per owner and cached for the next sync;
+        // the missing repo's owner is not looked up at all
+        StateSync::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(api.factory())
+            .run()
+            .unwrap();
+        assert_eq!(api.calls_starting("billing "), vec!["billing user_0", "billing user_1"]);
+    }
+    
+    #[test]
+    fn test_fork_organization_from_setup_keeps_billing_on_user() {
+        let (temp_dir, api) = setup(1);
+        fs::write(temp_dir.path().join("setup.json"), r#"{"fork_organization": "nexus-farm"}"#).unwrap();
+        api.backend.borrow_mut().minutes.insert("user_0".to_string(), 600.0);
+        
+        let ctx = Arc::new(AppContext::new(temp_dir.path()).unwrap());
+        let account = ctx.accounts().unwrap().get_account(0).unwrap().clone();
+        let (state, fork) = ForkManager::new(ctx.state())
+            .create_fork_chain(load_state(&temp_dir), &account, SOURCE, &api.as_owner(account.owner()))
+            .unwrap();
+        
+        assert_eq!(fork, "nexus-farm/nexus-runner");
+        let node = &state.fork_chain[0];
+        assert_eq!((node.pat_index, node.username.as_str(), node.owner_type), (0, "nexus-farm", OwnerType::Organization));
+        
+        // Minutes are still read from the token's user, not the organization
+        let report = StateSync::new(ctx).with_client_factory(api.factory()).run().unwrap();
+        assert_eq!(report.refreshed, vec!["nexus-farm/nexus-runner"]);
+        assert_eq!(api.calls_starting("billing "), vec!["billing user_0"]);
+        assert_eq!(load_state(&temp_dir).fork_chain[0].billing_used, 20.0);
+    }
+    
+    #[test]
+    fn test_rebuild_state_from_github() {
+        let (temp_dir, api) = setup(4);
+        build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        {
+            let mut backend = api.backend.borrow_mut();
+            backend.minutes.insert("user_0".to_string(), 3600.0);
+            backend.minutes.insert("user_1".to_string(), 600.0);
+            // user_2's billing call fails; user_3's repo is not a fork
+            backend.parents.remove("user_3/nexus-runner");
+        }
+        let paths = Paths::new(temp_dir.path());
+        fs::write(paths.state_file(), "{\"fork_chain\": [{\"pat_in").unwrap();
+        
+        let rebuild = || {
+            StateSync::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+                .with_client_factory(api.factory())
+                .rebuild(Some(SOURCE))
+                .unwrap()
+        };
+        let state = rebuild();
+        
+        let chain: Vec<_> = state.fork_chain
+            .iter()
+            .map(|n| (n.repo.clone(), n.parent.clone().unwrap(), n.status.clone()))
+            .collect();
+        assert_eq!(chain, vec![
+            ("user_0/nexus-runner".to_string(), SOURCE.to_string(), ForkStatus::Exhausted),
+            ("user_1/nexus-runner".to_string(), "user_0/nexus-runner".to_string(), ForkStatus::Active),
+            // Unknown billing is not taken for exhaustion
+            ("user_2/nexus-runner".to_string(), "user_1/nexus-runner".to_string(), ForkStatus::Active),
+        ]);
+        assert_eq!((state.fork_chain[0].billing_used, state.fork_chain[2].billing_used), (120.0, 0.0));
+        assert_eq!((state.current_active_index, state.total_accounts), (1, 4));
+        
+        // The unreadable file was backed up as it was
+        let backups: Vec<_> = fs::read_dir(paths.backup_dir()).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "{\"fork_chain\": [{\"pat_in");
+        
+        // Same chain again, and nothing created or deleted on GitHub
+        let again = rebuild();
+        let repos = |state: &OrchestratorState| state.fork_chain.iter().map(|n| n.repo.clone()).collect::<Vec<_>>();
+        assert_eq!(repos(&again), repos(&state));
+        assert_eq!(repos(&load_state(&temp_dir)), repos(&state));
+        assert_eq!(api.calls_starting("fork ").len(), 4);
+        assert!(api.calls_starting("delete ").is_empty());
+    }
+    
+    #[test]
+    fn test_verify_chain_reports_each_check() {
+        let (temp_dir, api) = setup(4);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        {
+            let mut backend = api.backend.borrow_mut();
+            for (i, fork) in forks.iter().enumerate() {
+                backend.workflows.insert(fork.clone(), 100 + i as u64);
+                for name in ["NEXUS_NODE_IDS", "NEXUS_WALLETS"] {
+                    backend.secrets.insert((fork.clone(), name.to_string()), ("c2VjcmV0".to_string(), KEY_ID.to_string()));
+                }
+            }
+            // user_0 is exhausted but its workflow still runs; user_2 lost a
+            // secret; user_3's token was revoked
+            backend.secrets.remove(&(forks[2].clone(), "NEXUS_WALLETS".to_string()));
+            backend.revoked.insert("user_3".to_string());
+        }
+        let state_mgr = StateManager::new(&Paths::new(temp_dir.path())).unwrap();
+        let state = state_mgr.update_fork_status(load_state(&temp_dir), 0, ForkStatus::Exhausted).unwrap();
+        state_mgr.update_fork_status(state, 3, ForkStatus::Exhausted).unwrap();
+        
+        let verify = || {
+            StateSync::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+                .with_client_factory(api.factory())
+                .verify()
+                .unwrap()
+        };
+        let report = verify();
+        
+        let failures: Vec<Vec<&str>> = report.nodes
+            .iter()
+            .map(|n| n.checks().iter().filter(|(_, c)| matches!(c, Check::Failed(_))).map(|(name, _)| *name).collect())
+            .collect();
+        assert_eq!(failures, vec![vec!["enabled"], vec![], vec!["secrets"], vec!["token"]]);
+        assert_eq!(report.nodes[2].secrets, Check::Failed("missing NEXUS_WALLETS".to_string()));
+        assert_eq!(report.nodes[3].repo_exists, Check::NotRun);
+        // user_2's fork is active
+        assert_eq!(report.exit_code(), exit_code::FAILURE);
+        
+        // Failures on forks that are not active do not fail the run
+        api.backend.borrow_mut().secrets.insert(
+            (forks[2].clone(), "NEXUS_WALLETS".to_string()),
+            ("c2VjcmV0".to_string(), KEY_ID.to_string()),
+        );
+        let report = verify();
+        assert_eq!(report.failed(), 2);
+        assert_eq!(report.exit_code(), exit_code::SUCCESS);
+        assert!(api.calls_starting("put ").is_empty() && api.calls_starting("disable ").is_empty());
+    }
+    
+    #[test]
+    fn test_stuck_runs_are_found_on_every_active_fork() {
+        let (temp_dir, api) = setup(3);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let queued = |id: u64, minutes: i64| WorkflowRun {
+            id,
+            status: "queued".to_string(),
+            conclusion: None,
+            event: "workflow_dispatch".to_string(),
+            created_at: Utc::now() - chrono::Duration::minutes(minutes),
+            html_url: String::new(),
+        };
+        {
+            let mut backend = api.backend.borrow_mut();
+            // Default threshold is 30 minutes; user_2's fork is exhausted
+            backend.queued_runs.insert(forks[0].clone(), vec![queued(11, 5)]);
+            backend.queued_runs.insert(forks[1].clone(), vec![queued(21, 90), queued(22, 45)]);
+            backend.queued_runs.insert(forks[2].clone(), vec![queued(31, 300)]);
+        }
+        StateManager::new(&Paths::new(temp_dir.path()))
+            .unwrap()
+            .update_fork_status(load_state(&temp_dir), 2, ForkStatus::Exhausted)
+            .unwrap();
+        
+        let ctx = AppContext::new(temp_dir.path()).unwrap();
+        let stuck = ctx.health().unwrap().check_stuck_runs(
+            &ctx.accounts().unwrap(),
+            &ctx.proxies().unwrap(),
+            &api.factory(),
+            ctx.alerts().unwrap(),
+        ).unwrap();
+        
+        let found: Vec<(&str, u64)> = stuck.iter().map(|run| (run.repo.as_str(), run.run_id)).collect();
+        assert_eq!(found, vec![(forks[1].as_str(), 21), (forks[1].as_str(), 22)]);
+        assert_eq!(stuck[0].queued_minutes, 90);
+        assert!(stuck[0].message().contains(&format!("https://github.com/{}/actions/runs/21", forks[1])));
+        assert_eq!(api.calls_starting("list-runs ").len(), 2);
+    }
+    
+    #[test]
+    fn test_expiring_tokens_are_flagged_and_alerted_once_a_day() {
+        let (temp_dir, _api) = setup(3);
+        let ctx = AppContext::new(temp_dir.path()).unwrap();
+        let mut accounts = ctx.accounts().unwrap().get_all_accounts().to_vec();
+        // Flagged within 14 days, alerted within 7; account 2 never expires
+        accounts[0].expires_at = Some(Utc::now() + chrono::Duration::days(10) + chrono::Duration::hours(1));
+        accounts[1].expires_at = Some(Utc::now() + chrono::Duration::days(3) + chrono::Duration::hours(1));
+        assert_eq!(accounts[2].expiry_label(), "never");
+        
+        let health = ctx.health().unwrap();
+        let expiring = health.check_token_expiry(&accounts, ctx.alerts().unwrap());
+        let flagged: Vec<usize> = expiring.iter().map(|token| token.index).collect();
+        assert_eq!(flagged, [0, 1]);
+        assert!(expiring[1].message(Utc::now()).starts_with("Token #1 (@user_1) expires in 3 days"), "{:?}", expiring[1]);
+        
+        let alerted = || -> Vec<Event> {
+            EventLog::new(&Paths::new(temp_dir.path()))
+                .read_recent(10)
+                .unwrap()
+                .into_iter()
+                .filter(|e| e.kind == "token_expiry")
+                .collect()
+        };
+        assert_eq!(alerted().len(), 1);
+        assert!(alerted()[0].message.starts_with("Token #1 (@user_1)"));
+        
+        health.check_token_expiry(&accounts, ctx.alerts().unwrap());
+        assert_eq!(alerted().len(), 1);
+    }
+    
+    #[test]
+    fn test_keep_alive_revives_quiet_forks_only() {
+        let (temp_dir, api) = setup(3);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let workflow = temp_dir.path().join("nexus.yml");
+        fs::write(&workflow, "name: Nexus\n").unwrap();
+        {
+            let mut backend = api.backend.borrow_mut();
+            // Default threshold is 50 days
+            backend.inactive.insert(forks[0].clone());
+            backend.pushed.insert(forks[0].clone(), Utc::now() - chrono::Duration::days(3));
+            // Disabled by rotation, not by GitHub: stays disabled
+            backend.disabled.insert(forks[1].clone());
+            backend.pushed.insert(forks[1].clone(), Utc::now() - chrono::Duration::days(55));
+            backend.pushed.insert(forks[2].clone(), Utc::now() - chrono::Duration::days(90));
+        }
+        StateManager::new(&Paths::new(temp_dir.path()))
+            .unwrap()
+            .update_fork_status(load_state(&temp_dir), 2, ForkStatus::Deleted)
+            .unwrap();
+        
+        let ctx = AppContext::new(temp_dir.path()).unwrap();
+        let outcomes = ctx.health().unwrap().keep_alive(
+            &ctx.accounts().unwrap(),
+            &ctx.proxies().unwrap(),
+            &api.factory(),
+            &WorkflowController::new(&workflow).unwrap(),
+        ).unwrap();
+        
+        let done: Vec<(String, KeepAlive)> = outcomes.into_iter().map(|(repo, outcome)| (repo, outcome.unwrap())).collect();
+        assert_eq!(done, vec![
+            (forks[0].clone(), KeepAlive { reenabled: vec!["nexus.yml".to_string()], committed: false }),
+            (forks[1].clone(), KeepAlive { reenabled: vec![], committed: true }),
+        ]);
+        assert_eq!(done[0].1.to_string(), "re-enabled nexus.yml");
+        assert_eq!(api.calls_starting("enable "), vec![format!("enable {} 1", forks[0])]);
+        assert_eq!(api.calls_starting("put-file "), vec![format!("put-file {} .github/keepalive main new", forks[1])]);
+        assert!(api.backend.borrow().disabled.contains(&forks[1]));
+        
+        // The commit counts as activity
+        let again = KeepAlive::default();
+        let controller = WorkflowController::new(&workflow).unwrap();
+        assert_eq!(controller.keep_alive(&forks[1], &api).unwrap(), again);
+        assert_eq!(again.to_string(), "active, nothing to do");
+    }
+    
+    #[test]
+    fn test_rotation_reforks_a_deleted_active_fork_once() {
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        api.backend.borrow_mut().minutes.insert("user_0".to_string(), 600.0);
+        api.backend.borrow_mut().repos.remove(&forks[0]);
+        
+        let rotator = || {
+            let ctx = Arc::new(AppContext::new(temp_dir.path()).unwrap());
+            Rotator::new(ctx.clone())
+                .with_client_factory(api.factory())
+                .with_deployer(Deployer::new(ctx).with_client_factory(api.factory()).with_sleeper(sleeper.clone()))
+                .with_sleeper(sleeper.clone())
+        };
+        
+        assert!(!rotator().check_and_rotate().unwrap());
+        assert_eq!(api.calls_starting("fork ").last(), Some(&format!("fork {}", SOURCE)));
+        // The new fork's workflow is enabled; secrets fail here without nodes.txt
+        assert_eq!(api.calls_starting("enable "), vec![format!("enable {} 3", forks[0])]);
+        
+        let state = load_state(&temp_dir);
+        let statuses: Vec<_> = state.fork_chain.iter().map(|n| (n.repo.as_str(), n.status.clone())).collect();
+        assert_eq!(statuses, vec![
+            (forks[0].as_str(), ForkStatus::Disabled),
+            (forks[0].as_str(), ForkStatus::Active),
+            (forks[1].as_str(), ForkStatus::Active),
+        ]);
+        let events = EventLog::new(&Paths::new(temp_dir.path())).read_recent(10).unwrap();
+        let heals: Vec<_> = events.iter().filter(|e| e.kind == "heal").collect();
+        assert_eq!(heals.len(), 2);
+        assert!(heals[1].message.contains("re-forked as user_0/nexus-runner but redeploying failed"), "{}", heals[1].message);
+        
+        // Gone again within the cooldown: reported, not re-forked
+        api.backend.borrow_mut().repos.remove(&forks[0]);
+        let err = rotator().check_and_rotate().unwrap_err();
+        assert!(format!("{:#}", err).contains("is missing again"), "{:#}", err);
+        assert_eq!(api.calls_starting("fork ").len(), 3);
+        assert_eq!(api.calls_starting("billing ").len(), 0);
+    }
+    
+    #[test]
+    fn test_rotation_stops_at_step_boundaries() {
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        build_chain(&temp_dir, &api, sleeper.clone());
+        api.backend.borrow_mut().minutes.insert("user_0".to_string(), 3600.0);
+        
+        let rotator = |cancel: &CancelToken| {
+            Rotator::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+                .with_client_factory(api.factory())
+                .with_sleeper(sleeper.clone())
+                .with_cancel(cancel.clone())
+        };
+        let last_event = || EventLog::new(&Paths::new(temp_dir.path())).read_recent(1).unwrap().remove(0);
+        
+        // Cancelled before the step: nothing changes on GitHub or in state
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let err = rotator(&cancel).check_and_rotate().unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::INTERRUPTED);
+        assert!(api.calls_starting("disable ").is_empty());
+        assert_eq!(load_state(&temp_dir).fork_chain[0].status, ForkStatus::Active);
+        assert_eq!(last_event().kind, "cancelled");
+        
+        // Cancelled while the old fork is being disabled: the rotation still
+        // lands in state, only the cooldown is skipped
+        let cancel = api.cancel_after("disable ");
+        assert!(rotator(&cancel).check_and_rotate().unwrap());
+        let state = load_state(&temp_dir);
+        assert_eq!(state.fork_chain[0].status, ForkStatus::Exhausted);
+        assert_eq!(state.current_active_index, 1);
+        assert_eq!(sleeper.count("rotation_cooldown"), 0);
+        assert_eq!(last_event().kind, "rotation");
+        
+        // The watch loop stops instead of sleeping until the next check
+        rotator(&cancel).watch(Duration::from_secs(60)).unwrap();
+        assert_eq!(sleeper.count("watch_interval"), 0);
+        assert_eq!(api.calls_starting("disable ").len(), 1);
+        assert!(cancel::is_cancelled(&rotator(&cancel).check_and_rotate().unwrap_err()));
+        assert_eq!(last_event().kind, "cancelled");
+    }
+    
+    #[test]
+    fn test_secrets_deployment_resumes_after_cancel() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(3);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        fs::write(temp_dir.path().join("nodes.txt"), "node_a\n").unwrap();
+        fs::write(temp_dir.path().join("wallets.txt"), "0x8254a986319461bf29ae35940a96786e507ad9ac\n").unwrap();
+        let checkpoint = Paths::new(temp_dir.path()).deploy_checkpoint();
+        
+        // One fork at a time, so the cancel lands between known repos
+        let deployer = |cancel: CancelToken| {
+            Deployer::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+                .with_client_factory(api.factory())
+                .with_sleeper(sleeper.clone())
+                .with_cancel(cancel)
+                .with_concurrency(1)
+        };
+        
+        // Ctrl-C during the second repo: it still gets both secrets, the third none
+        let cancel = api.cancel_after(&format!("put {}", forks[1]));
+        let err = deployer(cancel).set_all_secrets().unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::INTERRUPTED);
+        assert_eq!(api.calls_starting("put ").len(), 4);
+        assert!(api.calls_starting(&format!("put {}", forks[2])).is_empty());
+        
+        let saved: serde_json::Value = serde_json::from_str(&fs::read_to_string(&checkpoint).unwrap()).unwrap();
+        assert_eq!(saved["completed"], serde_json::json!([forks[0], forks[1]]));
+        
+        // The next run only deploys what is left, then drops the checkpoint
+        api.backend.borrow_mut().cancel_after = None;
+        deployer(CancelToken::new()).set_all_secrets().unwrap();
+        assert_eq!(api.calls_starting("put ").len(), 6);
+        assert_eq!(api.calls_starting(&format!("put {}", forks[2])).len(), 2);
+        assert!(!checkpoint.exists());
+    }
+    
+    #[test]
+    fn test_deployment_refused_up_front_for_a_token_without_secrets_access() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        build_chain(&temp_dir, &api, sleeper.clone());
+        fs::write(temp_dir.path().join("nodes.txt"), "node_a\n").unwrap();
+        fs::write(temp_dir.path().join("wallets.txt"), "0x8254a986319461bf29ae35940a96786e507ad9ac\n").unwrap();
+        // As a validation of a token with workflow but not repo scope left it
+        let paths = Paths::new(temp_dir.path());
+        paths.ensure_cache_dir().unwrap();
+        fs::write(
+            paths.tokenmap(),
+            r#"{"ghp_111111111111": {"username": "user_1", "capabilities": {"fork": true, "workflows": true, "secrets": false, "delete": false}}}"#,
+        ).unwrap();
+        
+        let err = Deployer::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(api.factory())
+            .with_sleeper(sleeper)
+            .set_all_secrets()
+            .unwrap_err();
+        
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        assert!(err.to_string().starts_with("Token #1 (@user_1) lacks the repo scope"), "{}", err);
+        assert!(api.calls_starting("put ").is_empty());
+    }
+    
+    #[test]
+    fn test_disabled_account_keeps_its_index_and_is_never_deployed_to() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(3);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        fs::write(temp_dir.path().join("nodes.txt"), "node_a\n").unwrap();
+        fs::write(temp_dir.path().join("wallets.txt"), "0x8254a986319461bf29ae35940a96786e507ad9ac\n").unwrap();
+        
+        let ctx = || Arc::new(AppContext::new(temp_dir.path()).unwrap());
+        let disabled = TokenCommand::new(ctx()).set_enabled("user_1", false).unwrap();
+        assert_eq!((disabled.index, disabled.enabled), (1, false));
+        
+        // Kept in tokenmap.json, without moving anyone's index
+        let mut accounts = AccountManager::new(&Paths::new(temp_dir.path()));
+        accounts.load_tokens(&temp_dir.path().join("tokens.txt")).unwrap();
+        assert_eq!(accounts.get_all_accounts().iter().map(|a| a.enabled).collect::<Vec<_>>(), [true, false, true]);
+        
+        let deployer = || Deployer::new(ctx()).with_client_factory(api.factory()).with_sleeper(sleeper.clone());
+        let report = deployer().set_all_secrets().unwrap();
+        assert_eq!(report.outcomes.iter().map(|o| o.repo.as_str()).collect::<Vec<_>>(), [&forks[0], &forks[2]]);
+        assert!(api.calls_starting(&format!("put {}", forks[1])).is_empty());
+        
+        let err = deployer().redeploy_fork(&forks[1], 1).unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        
+        TokenCommand::new(ctx()).set_enabled("1", true).unwrap();
+        deployer().set_all_secrets().unwrap();
+        assert_eq!(api.calls_starting(&format!("put {}", forks[1])).len(), 2);
+    }
+    
+    #[test]
+    fn test_token_disabled_mid_session_is_skipped_by_the_same_context() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(3);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        fs::write(temp_dir.path().join("nodes.txt"), "node_a\n").unwrap();
+        fs::write(temp_dir.path().join("wallets.txt"), "0x8254a986319461bf29ae35940a96786e507ad9ac\n").unwrap();
+        
+        // As in the menu: one context for the whole session, accounts already loaded
+        let ctx = Arc::new(AppContext::new(temp_dir.path()).unwrap());
+        assert!(ctx.accounts().unwrap().is_enabled(1));
+        let deployer = Deployer::new(ctx.clone()).with_client_factory(api.factory()).with_sleeper(sleeper);
+        
+        TokenCommand::new(ctx.clone()).toggle("user_1").unwrap();
+        let report = deployer.set_all_secrets().unwrap();
+        assert_eq!(report.outcomes.iter().map(|o| o.repo.as_str()).collect::<Vec<_>>(), [&forks[0], &forks[2]]);
+        assert!(api.calls_starting(&format!("put {}", forks[1])).is_empty());
+        let err = deployer.redeploy_fork(&forks[1], 1).unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        
+        TokenCommand::new(ctx.clone()).toggle("user_1").unwrap();
+        deployer.set_all_secrets().unwrap();
+        assert_eq!(api.calls_starting(&format!("put {}", forks[1])).len(), 2);
+    }
+    
+    #[test]
+    fn test_parallel_deployment_reports_each_fork() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(3);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        fs::write(temp_dir.path().join("nodes.txt"), "node_a\n").unwrap();
+        fs::write(temp_dir.path().join("wallets.txt"), "0x8254a986319461bf29ae35940a96786e507ad9ac\n").unwrap();
+        let checkpoint = Paths::new(temp_dir.path()).deploy_checkpoint();
+        api.backend.borrow_mut().repos.remove(&forks[1]);
+        
+        let deployer = || {
+            Deployer::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+                .with_client_factory(api.factory())
+                .with_sleeper(sleeper.clone())
+                .with_concurrency(3)
+        };
+        
+        // The missing fork fails alone; the others still get their secrets
+        let report = deployer().set_all_secrets().unwrap();
+        assert_eq!(report.outcomes.iter().map(|o| o.repo.as_str()).collect::<Vec<_>>(), [&forks[0], &forks[1], &forks[2]]);
+        assert_eq!(report.failed(), 1);
+        assert!(report.outcomes[1].result.is_err());
+        assert_eq!(report.exit_code(), exit_code::PARTIAL);
+        assert_eq!(api.calls_starting("put ").len(), 4);
+        
+        // The checkpoint stays, so the next run only retries the failed fork
+        api.backend.borrow_mut().repos.insert(forks[1].clone());
+        let report = deployer().set_all_secrets().unwrap();
+        assert_eq!(report.resumed.len(), 2);
+        assert_eq!(report.outcomes.len(), 1);
+        assert_eq!(report.exit_code(), exit_code::SUCCESS);
+        assert_eq!(api.calls_starting(&format!("put {}", forks[1])).len(), 2);
+        assert!(!checkpoint.exists());
+    }
+    
+    #[test]
+    fn test_env_file_pushes_secrets_and_variables() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(1);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        let deployer = Deployer::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(api.factory())
+            .with_sleeper(sleeper);
+        
+        // A malformed file pushes nothing and names the line
+        fs::write(temp_dir.path().join("secrets.env"), "API_KEY=one\nbroken\n").unwrap();
+        let err = deployer.deploy_env_file(&forks[0]).unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        assert!(format!("{:#}", err).contains("line 2: expected NAME=value"), "{:#}", err);
+        assert!(api.calls_starting("put ").is_empty());
+        
+        fs::write(
+            temp_dir.path().join("secrets.env"),
+            "API_KEY=one\nCERT=\"a\\nb\"\n#@variable\nMAX_PARALLEL=20\nVAR_REGION=eu\n",
+        ).unwrap();
+        let deployment = deployer.deploy_env_file(&forks[0]).unwrap();
+        assert_eq!(deployment.secrets, vec![
+            ("API_KEY".to_string(), SecretWriteResult::Created),
+            ("CERT".to_string(), SecretWriteResult::Created),
+        ]);
+        assert_eq!(deployment.variables, vec![("MAX_PARALLEL".to_string(), true), ("REGION".to_string(), true)]);
+        // One public key fetch for both secrets
+        assert_eq!(api.calls_starting(&format!("key {}", forks[0])).len(), 1);
+        assert_eq!(api.calls_starting("var create ").len(), 2);
+    }
+    
+    #[test]
+    fn test_workflow_deploys_through_contents_api() {
+        let (temp_dir, api) = setup(1);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let path = ".github/workflows/nexus.yml";
+        let workflow = temp_dir.path().join("nexus.yml");
+        fs::write(&workflow, "name: Nexus v1\n").unwrap();
+        let controller = || {
+            WorkflowController::new(&workflow).unwrap().with_sleeper(Arc::new(RecordingSleeper::default()))
+        };
+        
+        // Created without a SHA, then left alone while unchanged
+        controller().deploy_to_repo(&forks[0], &api).unwrap();
+        controller().deploy_to_repo(&forks[0], &api).unwrap();
+        assert_eq!(api.calls_starting("put-file "), vec![format!("put-file {} {} main new", forks[0], path)]);
+        
+        // A changed workflow replaces the file by its SHA
+        let sha = api.backend.borrow().files[&(forks[0].clone(), path.to_string())].sha.clone();
+        fs::write(&workflow, "name: Nexus v2\n").unwrap();
+        controller().deploy_to_repo(&forks[0], &api).unwrap();
+        assert_eq!(api.calls_starting("put-file ")[1], format!("put-file {} {} main {}", forks[0], path, sha));
+        assert_eq!(api.backend.borrow().files[&(forks[0].clone(), path.to_string())].content, "name: Nexus v2\n");
+    }
+    
+    #[test]
+    fn test_managed_workflows_deploy_together() {
+        let (temp_dir, api) = setup(1);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let managed = temp_dir.path().join("managed");
+        fs::create_dir_all(&managed).unwrap();
+        fs::write(managed.join("nexus.yml"), "name: Nexus\n").unwrap();
+        fs::write(managed.join("health-check.yml"), "name: Health\n").unwrap();
+        let controller = || {
+            WorkflowController::from_dir(&managed).unwrap().with_sleeper(Arc::new(RecordingSleeper::default()))
+        };
+        
+        controller().deploy_to_repo(&forks[0], &api).unwrap();
+        assert_eq!(api.calls_starting("put-file "), vec![
+            format!("put-file {} .github/workflows/health-check.yml main new", forks[0]),
+            format!("put-file {} .github/workflows/nexus.yml main new", forks[0]),
+        ]);
+        
+        // Only the changed file is written again
+        fs::write(managed.join("health-check.yml"), "name: Health v2\n").unwrap();
+        controller().deploy_to_repo(&forks[0], &api).unwrap();
+        let puts = api.calls_starting("put-file ");
+        assert_eq!(puts.len(), 3);
+        assert!(puts[2].contains("health-check.yml"), "{:?}", puts);
+        
+        // Enabling without a name covers every managed workflow
+        controller().enable_workflow(&forks[0], None, &api).unwrap();
+        assert_eq!(api.calls_starting(&format!("enable {}", forks[0])).len(), 2);
+    }
+    
+    #[test]
+    fn test_drift_ignores_line_endings_and_reports_the_worst_file() {
+        let (temp_dir, api) = setup(2);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let managed = temp_dir.path().join("managed");
+        fs::create_dir_all(&managed).unwrap();
+        fs::write(managed.join("nexus.yml"), "name: Nexus\non: push\n").unwrap();
+        fs::write(managed.join("health-check.yml"), "name: Health\n").unwrap();
+        let controller = WorkflowController::from_dir(&managed).unwrap().with_sleeper(Arc::new(RecordingSleeper::default()));
+        
+        let put = |repo: &str, name: &str, content: &str| {
+            api.backend.borrow_mut().files.insert(
+                (repo.to_string(), format!(".github/workflows/{}", name)),
+                RepoFile { sha: "abc".to_string(), content: content.to_string() },
+            );
+        };
+        assert_eq!(controller.check_drift(&forks[0], &api).unwrap(), Drift::Missing);
+        
+        // Committed from Windows: same workflows, CRLF line endings
+        put(&forks[0], "nexus.yml", "name: Nexus\r\non: push\r\n");
+        put(&forks[0], "health-check.yml", "name: Health\r\n");
+        assert_eq!(controller.check_drift(&forks[0], &api).unwrap(), Drift::Same);
+        assert_eq!(api.calls_starting(&format!("get-file {} .github/workflows/nexus.yml main", forks[0])).len(), 2);
+        
+        // Nothing to write when only line endings differ
+        controller.deploy_to_repo(&forks[0], &api).unwrap();
+        assert!(api.calls_starting("put-file ").is_empty());
+        
+        put(&forks[1], "nexus.yml", "name: Nexus\non: workflow_dispatch\n");
+        assert_eq!(controller.check_drift(&forks[1], &api).unwrap(), Drift::Missing);
+        put(&forks[1], "health-check.yml", "name: Health\n");
+        assert_eq!(controller.check_drift(&forks[1], &api).unwrap(), Drift::Different);
+        
+        controller.deploy_to_repo(&forks[1], &api).unwrap();
+        assert_eq!(api.calls_starting("put-file "), vec![format!("put-file {} .github/workflows/nexus.yml main abc", forks[1])]);
+        assert_eq!(controller.check_drift(&forks[1], &api).unwrap(), Drift::Same);
+    }
+    
+    #[test]
+    fn test_deploy_and_dispatch_on_a_configured_branch() {
+        let (temp_dir, api) = setup(1);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let workflow = temp_dir.path().join("nexus.yml");
+        fs::write(&workflow, "name: Nexus\n").unwrap();
+        let controller = || {
+            WorkflowController::new(&workflow)
+                .unwrap()
+                .with_sleeper(Arc::new(RecordingSleeper::default()))
+                .with_branch(Some("nexus-deploy".to_string()))
+        };
+        
+        controller().deploy_to_repo(&forks[0], &api).unwrap();
+        assert_eq!(
+            api.calls_starting("create-branch "),
+            vec![format!("create-branch {} nexus-deploy 0123abcd", forks[0])]
+        );
+        assert_eq!(
+            api.calls_starting("put-file "),
+            vec![format!("put-file {} .github/workflows/nexus.yml nexus-deploy new", forks[0])]
+        );
+        
+        // The branch is only created once
+        fs::write(&workflow, "name: Nexus v2\n").unwrap();
+        controller().deploy_to_repo(&forks[0], &api).unwrap();
+        assert_eq!(api.calls_starting("create-branch ").len(), 1);
+        assert_eq!(api.calls_starting("put-file ").len(), 2);
+        
+        controller().trigger_workflow(&forks[0], None, &api).unwrap();
+        assert_eq!(api.calls_starting("dispatch "), vec![format!("dispatch {} nexus.yml nexus-deploy", forks[0])]);
+        
+        // Without a branch the default one is used, as before
+        WorkflowController::new(&workflow).unwrap().trigger_workflow(&forks[0], None, &api).unwrap();
+        assert_eq!(api.calls_starting("dispatch ")[1], format!("dispatch {} nexus.yml main", forks[0]));
+    }
+    
+    #[test]
+    fn test_trigger_dispatches_through_the_injected_client() {
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        api.backend.borrow_mut().runs.insert(forks[0].clone(), (77, "completed".to_string()));
+        
+        let trigger = Trigger::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(api.factory())
+            .with_sleeper(sleeper.clone());
+        let options = TriggerOptions { repo: Some(forks[0].clone()), ..TriggerOptions::default() };
+        
+        // The mock never starts a new run, so only the dispatch itself is reported
+        assert_eq!(trigger.run(&options).unwrap(), TriggerOutcome::Dispatched { run_id: None });
+        assert_eq!(api.calls_starting(&format!("dispatch {} ", forks[0])).len(), 1);
+        assert_eq!(sleeper.count("dispatch_poll"), 12);
+        
+        let (repo, report) = trigger.run_report(Some(&forks[0]), Some(77)).unwrap();
+        let report = report.unwrap();
+        assert_eq!((repo.as_str(), report.run_id), (forks[0].as_str(), 77));
+        assert_eq!(report.conclusion.as_deref(), Some("success"));
+    }
+    
+    #[test]
+    fn test_rotation_replays_vaulted_secrets() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        let ctx = Arc::new(AppContext::new(temp_dir.path()).unwrap());
+        
+        fs::write(temp_dir.path().join("secrets.env"), "API_KEY=one
+VAR_REGION=eu
+").unwrap();
+        Deployer::new(ctx.clone())
+            .with_client_factory(api.factory())
+            .with_sleeper(sleeper.clone())
+            .deploy_env_file(&forks[0])
+            .unwrap();
+        {
+            let vault = ctx.vault().unwrap();
+            let vault = vault.lock().unwrap();
+            let names: Vec<&str> = vault.entries().map(|(name, _)| name).collect();
+            // Variables and the Nexus secrets set by build_chain are not vaulted
+            assert_eq!(names, ["API_KEY"]);
+        }
+        assert!(api.calls_starting(&format!("put {} API_KEY", forks[1])).is_empty());
+        
+        api.backend.borrow_mut().minutes.insert("user_0".to_string(), 3600.0);
+        assert!(Rotator::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(api.factory())
+            .with_sleeper(sleeper)
+            .check_and_rotate()
+            .unwrap());
+        
+        assert_eq!(api.calls_starting(&format!("put {} API_KEY", forks[1])).len(), 1);
+        assert!(api.backend.borrow().secrets.contains_key(&(forks[1].clone(), "API_KEY".to_string())));
+    }
+    
+    #[test]
+    fn test_secrets_deployment() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        
+        let state_mgr = StateManager::new(&Paths::new(temp_dir.path())).unwrap();
+        state_mgr.update_fork_status(state_mgr.load_state().unwrap(), 1, ForkStatus::Exhausted).unwrap();
+        fs::write(temp_dir.path().join("nodes.txt"), "node_a\nnode_b\n").unwrap();
+        fs::write(
+            temp_dir.path().join("wallets.txt"),
+            "0x8254a986319461bf29ae35940a96786e507ad9ac\n0x0000000000000000000000000000000000000001\n",
+        ).unwrap();
+        fs::write(
+            temp_dir.path().join("setup.json"),
+            r#"{"variables": {"MAX_PARALLEL": 20, "NEXUS_RPC_URL": "https://rpc.example"}}"#,
+        ).unwrap();
+        
+        Deployer::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(api.factory())
+            .with_sleeper(sleeper.clone())
+            .set_all_secrets()
+            .unwrap();
+        
+        // Only the active fork gets secrets, encrypted with the repo key
+        assert_eq!(api.calls_starting("put "), vec![
+            format!("put {} NEXUS_NODE_IDS", forks[0]),
+            format!("put {} NEXUS_WALLETS", forks[0]),
+        ]);
+        {
+            let backend = api.backend.borrow();
+            let (encrypted, key_id) = &backend.secrets[&(forks[0].clone(), "NEXUS_NODE_IDS".to_string())];
+            assert_eq!(key_id, KEY_ID);
+            assert!(!encrypted.is_empty() && !encrypted.contains("node_a"));
+        }
+        // One key fetch and one read-back for both secrets
+        assert_eq!(api.calls_starting("key "), vec![format!("key {}", forks[0])]);
+        // setup.json variables go to the same repos, as plain text
+        assert_eq!(api.calls_starting("var "), vec![
+            format!("var create {} MAX_PARALLEL", forks[0]),
+            format!("var create {} NEXUS_RPC_URL", forks[0]),
+        ]);
+        assert_eq!(api.backend.borrow().variables[&(forks[0].clone(), "MAX_PARALLEL".to_string())], "20");
+        assert_eq!(sleeper.count("secret_verify"), 1);
+        assert_eq!(sleeper.count("between_secrets"), 1);
+        
+        // A second write to the same secret is an update
+        let secrets = SecretsManager::new(Box::new(api.as_owner("user_0"))).with_sleeper(sleeper.clone());
+        assert_eq!(secrets.set_secret(&forks[0], "NEXUS_WALLETS", "0xabc").unwrap(), SecretWriteResult::Updated);
+        assert_eq!(secrets.list_secrets(&forks[0]).unwrap(), vec!["NEXUS_NODE_IDS", "NEXUS_WALLETS"]);
+        
+        // Missing repos fail before anything is written
+        assert!(secrets.set_secret("user_0/gone", "API_KEY", "x").is_err());
+        assert_eq!(api.calls_starting("put ").len(), 3);
+    }
+    
+    #[test]
+    fn test_secrets_list_and_delete_go_through_the_api() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(2);
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let forks = build_chain(&temp_dir, &api, sleeper.clone());
+        
+        let secrets = SecretsManager::new(Box::new(api.as_owner("user_0"))).with_sleeper(sleeper);
+        set_secret(&secrets, &forks[0], "B_KEY", "b").unwrap();
+        set_secret(&secrets, &forks[0], "A_KEY", "a").unwrap();
+        set_secret(&secrets, &forks[1], "C_KEY", "c").unwrap();
+        assert_eq!(secrets.list_secrets(&forks[0]).unwrap(), vec!["A_KEY", "B_KEY"]);
+        
+        secrets.delete_secret(&forks[0], "A_KEY").unwrap();
+        assert_eq!(secrets.list_secrets(&forks[0]).unwrap(), vec!["B_KEY"]);
+        assert_eq!(secrets.list_secrets(&forks[1]).unwrap(), vec!["C_KEY"]);
+        
+        // Deleting a secret that is already gone is not an error
+        secrets.delete_secret(&forks[0], "A_KEY").unwrap();
+        assert_eq!(api.calls_starting("unset "), vec![format!("unset {} A_KEY", forks[0]); 2]);
+        
+        // Invalid names never reach the API
+        assert!(set_secret(&secrets, &forks[0], "GITHUB_TOKEN", "x").is_err());
+        assert_eq!(api.calls_starting("put ").len(), 3);
+    }
+    
+    #[test]
+    fn test_scrub_secrets_across_the_chain() {
+        let (temp_dir, api) = setup(4);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        {
+            let mut backend = api.backend.borrow_mut();
+            for fork in &forks {
+                for name in ["NEXUS_NODE_IDS", "NEXUS_WALLETS_1", "NEXUS_WALLETS_COUNT", "API_KEY"] {
+                    backend.secrets.insert((fork.clone(), name.to_string()), ("c2VjcmV0".to_string(), KEY_ID.to_string()));
+                }
+            }
+            // user_1's fork was deleted on GitHub
+            backend.repos.remove(&forks[1]);
+        }
+        let state_mgr = StateManager::new(&Paths::new(temp_dir.path())).unwrap();
+        let state = state_mgr.update_fork_status(load_state(&temp_dir), 0, ForkStatus::Exhausted).unwrap();
+        state_mgr.update_fork_status(state, 2, ForkStatus::Disabled).unwrap();
+        // user_3's token is no longer configured
+        let tokens = fs::read_to_string(temp_dir.path().join("tokens.txt")).unwrap();
+        fs::write(temp_dir.path().join("tokens.txt"), tokens.lines().take(3).collect::<Vec<_>>().join("\n")).unwrap();
+        
+        let report = Deployer::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(api.factory())
+            .scrub_secrets()
+            .unwrap();
+        
+        assert_eq!(report.gone, vec![forks[1].clone()]);
+        assert_eq!(
+            report.outcomes.iter().map(|o| o.repo.as_str()).collect::<Vec<_>>(),
+            vec![forks[0].as_str(), forks[2].as_str(), forks[3].as_str()]
+        );
+        // Exhausted and disabled forks too, split parts included, nothing else
+        for outcome in &report.outcomes[..2] {
+            assert_eq!(outcome.result.as_ref().unwrap(), &vec!["NEXUS_NODE_IDS", "NEXUS_WALLETS_1", "NEXUS_WALLETS_COUNT"]);
+            let backend = api.backend.borrow();
+            let left: Vec<&str> = backend.secrets.keys().filter(|(r, _)| *r == outcome.repo).map(|(_, n)| n.as_str()).collect();
+            assert_eq!(left, vec!["API_KEY"]);
+        }
+        assert!(report.outcomes[2].result.is_err());
+        assert_eq!(report.failed(), 1);
+        assert_eq!(report.exit_code(), exit_code::PARTIAL);
+        
+        // A second pass finds nothing left to delete
+        let report = Deployer::new(Arc::new(AppContext::new(temp_dir.path()).unwrap()))
+            .with_client_factory(api.factory())
+            .scrub_secrets()
+            .unwrap();
+        assert!(report.outcomes[..2].iter().all(|o| o.result.as_ref().unwrap().is_empty()));
+    }
+    
+    #[test]
+    fn test_variables_are_created_then_updated() {
+        let (temp_dir, api) = setup(1);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let variables = VariablesManager::new(Box::new(api.as_owner("user_0")));
+        
+        assert!(variables.set_variable(&forks[0], "MAX_PARALLEL", "10").unwrap());
+        // The create conflicts, so the existing variable is patched
+        assert!(!variables.set_variable(&forks[0], "MAX_PARALLEL", "20").unwrap());
+        assert_eq!(api.calls_starting("var "), vec![
+            format!("var create {} MAX_PARALLEL", forks[0]),
+            format!("var create {} MAX_PARALLEL", forks[0]),
+            format!("var update {} MAX_PARALLEL", forks[0]),
+        ]);
+        assert_eq!(variables.get_variable(&forks[0], "MAX_PARALLEL").unwrap().as_deref(), Some("20"));
+        assert_eq!(variables.list_variables(&forks[0]).unwrap(), vec![("MAX_PARALLEL".to_string(), "20".to_string())]);
+        
+        variables.delete_variable(&forks[0], "MAX_PARALLEL").unwrap();
+        // Deleting what is not there is not an error
+        variables.delete_variable(&forks[0], "MAX_PARALLEL").unwrap();
+        assert_eq!(variables.get_variable(&forks[0], "MAX_PARALLEL").unwrap(), None);
+    }
+    
+    #[test]
+    fn test_sync_secrets_writes_only_changes() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(1);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let manifest = temp_dir.path().join("cache").join("secrets_manifest.json");
+        let secrets = |force: bool| {
+            SecretsManager::new(Box::new(api.as_owner("user_0")))
+                .with_sleeper(Arc::new(RecordingSleeper::default()))
+                .with_secret_hashes(SecretHashes::load(&manifest).unwrap())
+                .with_force(force)
+        };
+        let desired = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+            pairs.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+        };
+        let puts = || api.calls_starting("put ").len();
+        
+        secrets(false).sync_secrets(&forks[0], &desired(&[("API_KEY", "one"), ("RPC", "x")])).unwrap();
+        assert_eq!(puts(), 2);
+        
+        // Nothing changed: no writes, and a fresh manager reads the manifest back
+        let plan = secrets(false).sync_secrets(&forks[0], &desired(&[("API_KEY", "one"), ("RPC", "x")])).unwrap();
+        assert_eq!(plan.unchanged, vec!["API_KEY", "RPC"]);
+        assert_eq!(puts(), 2);
+        
+        // One changed value is the only write
+        let plan = secrets(false).sync_secrets(&forks[0], &desired(&[("API_KEY", "two"), ("RPC", "x")])).unwrap();
+        assert_eq!(plan.set, vec!["API_KEY"]);
+        assert_eq!(api.calls_starting("put ").last().unwrap(), &format!("put {} API_KEY", forks[0]));
+        
+        // The manifest says RPC is there, GitHub says it is not
+        api.backend.borrow_mut().secrets.remove(&(forks[0].clone(), "RPC".to_string()));
+        let plan = secrets(false).sync_secrets(&forks[0], &desired(&[("API_KEY", "two"), ("RPC", "x")])).unwrap();
+        assert_eq!(plan.set, vec!["RPC"]);
+        assert_eq!(puts(), 4);
+        
+        // Managed secrets no longer wanted are deleted; force rewrites the rest
+        let plan = secrets(true).sync_secrets(&forks[0], &desired(&[("API_KEY", "two")])).unwrap();
+        assert_eq!(plan.set, vec!["API_KEY"]);
+        assert_eq!(plan.delete, vec!["RPC"]);
+        assert_eq!(api.calls_starting("unset "), vec![format!("unset {} RPC", forks[0])]);
+        assert_eq!(puts(), 5);
+        assert_eq!(api.backend.borrow().secrets.keys().filter(|(r, _)| *r == forks[0]).count(), 1);
+    }
+    
+    #[test]
+    fn test_oversized_nexus_secrets_are_split() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(1);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let secrets = SecretsManager::new(Box::new(api.as_owner("user_0")))
+            .with_sleeper(Arc::new(RecordingSleeper::default()));
+        
+        // 1500 nodes are over 48 KB for both secrets
+        let node_ids: Vec<String> = (0..1500).map(|i| format!("{:036}", i)).collect();
+        let wallets: Vec<String> = (0..1500).map(|i| format!("0x{:040x}", i)).collect();
+        secrets.set_nexus_secrets(&forks[0], &node_ids, &wallets, SecretLayout::Joined).unwrap();
+        assert_eq!(secrets.list_secrets(&forks[0]).unwrap(), vec![
+            "NEXUS_NODE_IDS_1", "NEXUS_NODE_IDS_2", "NEXUS_NODE_IDS_COUNT",
+            "NEXUS_WALLETS_1", "NEXUS_WALLETS_2", "NEXUS_WALLETS_COUNT",
+        ]);
+        assert!(api.calls_starting("unset ").is_empty());
+        
+        // Back to a small list: single secrets again, and no part left behind
+        secrets.set_nexus_secrets(&forks[0], &node_ids[..2], &wallets[..2], SecretLayout::Joined).unwrap();
+        assert_eq!(secrets.list_secrets(&forks[0]).unwrap(), vec!["NEXUS_NODE_IDS", "NEXUS_WALLETS"]);
+        assert_eq!(api.calls_starting("unset ").len(), 6);
+    }
+    
+    #[test]
+    fn test_indexed_layout_rewrites_only_changed_nodes() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(1);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let manifest = temp_dir.path().join("cache").join("secrets_manifest.json");
+        let secrets = || {
+            SecretsManager::new(Box::new(api.as_owner("user_0")))
+                .with_sleeper(Arc::new(RecordingSleeper::default()))
+                .with_secret_hashes(SecretHashes::load(&manifest).unwrap())
+        };
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        
+        // Joined first, so switching layouts has something to clean up
+        secrets().set_nexus_secrets(&forks[0], &strings(&["n1", "n2"]), &strings(&["0x1", "0x2"]), SecretLayout::Joined).unwrap();
+        secrets().set_nexus_secrets(&forks[0], &strings(&["n1", "n2", "n3"]), &strings(&["0x1", "0x2", "0x3"]), SecretLayout::Indexed).unwrap();
+        assert_eq!(secrets().list_secrets(&forks[0]).unwrap(), vec![
+            "NEXUS_NODE_1", "NEXUS_NODE_2", "NEXUS_NODE_3",
+            "NEXUS_WALLET_1", "NEXUS_WALLET_2", "NEXUS_WALLET_3",
+        ]);
+        
+        // Rotating node 2 writes only node 2; dropping node 3 deletes its pair
+        let puts = api.calls_starting("put ").len();
+        secrets().set_nexus_secrets(&forks[0], &strings(&["n1", "n2b"]), &strings(&["0x1", "0x2"]), SecretLayout::Indexed).unwrap();
+        assert_eq!(api.calls_starting("put ")[puts..], [format!("put {} NEXUS_NODE_2", forks[0])]);
+        assert_eq!(secrets().list_secrets(&forks[0]).unwrap(), vec![
+            "NEXUS_NODE_1", "NEXUS_NODE_2", "NEXUS_WALLET_1", "NEXUS_WALLET_2",
+        ]);
+        
+        // Scrubbing covers the indexed secrets too
+        assert_eq!(secrets().delete_nexus_secrets(&forks[0]).unwrap().len(), 4);
+    }
+    
+    #[test]
+    fn test_environment_secrets() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(1);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let secrets = SecretsManager::new(Box::new(api.as_owner("user_0")))
+            .with_sleeper(Arc::new(RecordingSleeper::default()));
+        
+        // A missing environment names itself and how to get it created
+        let err = secrets.set_environment_secret(&forks[0], "nexus-prod", "API_KEY", "x").unwrap_err();
+        assert!(format!("{:#}", err).contains("Environment 'nexus-prod' does not exist in user_0/nexus-runner"), "{:#}", err);
+        assert!(api.calls_starting("put ").is_empty());
+        
+        assert!(secrets.create_environment(&forks[0], "nexus-prod").unwrap());
+        assert!(!secrets.create_environment(&forks[0], "nexus-prod").unwrap());
+        
+        // Addressed by the repo id, and kept apart from the repo's own secrets
+        assert_eq!(
+            secrets.set_environment_secret(&forks[0], "nexus-prod", "API_KEY", "x").unwrap(),
+            SecretWriteResult::Created
+        );
+        assert_eq!(api.calls_starting("put "), vec!["put #1001 nexus-prod API_KEY"]);
+        assert!(secrets.list_secrets(&forks[0]).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_secret_that_did_not_land_fails_the_write() {
+        init_crypto().unwrap();
+        let (temp_dir, api) = setup(1);
+        let forks = build_chain(&temp_dir, &api, Arc::new(RecordingSleeper::default()));
+        let secrets = || {
+            SecretsManager::new(Box::new(api.as_owner("user_0")))
+                .with_sleeper(Arc::new(RecordingSleeper::default()))
+        };
+        
+        secrets().set_secret(&forks[0], "API_KEY", "one").unwrap();
+        // Written an hour ago; GitHub accepts the next PUT but keeps the old value and time
+        let mut backend = api.backend.borrow_mut();
+        backend.secret_times.insert((forks[0].clone(), "API_KEY".to_string()), Utc::now() - chrono::Duration::hours(1));
+        backend.dropped_puts.insert("API_KEY".to_string());
+        drop(backend);
+        
+        let err = secrets().set_secrets(&forks[0], &[("API_KEY", "two"), ("RPC", "x")]).unwrap_err();
+        assert!(format!("{:#}", err).contains("GitHub does not show API_KEY as updated"), "{:#}", err);
+        
+        // Lenient: reported, not raised, and only for the secret that did not land
+        let results = secrets()
+            .with_lenient_verification(true)
+            .set_secrets(&forks[0], &[("API_KEY", "two"), ("RPC", "y")])
+            .unwrap();
+        assert_eq!(results, vec![
+            ("API_KEY".to_string(), SecretWriteResult::Unverified),
+            ("RPC".to_string(), SecretWriteResult::Updated),
+        ]);
+    }
+}
+
+mod release_fixture {
+    use nexus_orchestrator::orchestration::self_update::{GitHubReleases, ReleaseSource, RELEASE_REPO};
+    use nexus_orchestrator::utils::exit_code;
+    use serde_json::json;
+    use tokio::runtime::Runtime;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    
+    /// A local stand-in for the GitHub REST API, for clients that speak HTTP
+    /// themselves. The server gets its own runtime so blocking clients can
+    /// call it from the test thread.
+    pub struct FixtureServer {
+        runtime: Runtime,
+        server: MockServer,
+    }
+    
+    impl FixtureServer {
+        pub fn start() -> Self {
+            let runtime = Runtime::new().unwrap();
+            let server = runtime.block_on(MockServer::start());
+            Self { runtime, server }
+        }
+        
+        pub fn uri(&self) -> String {
+            self.server.uri()
+        }
+        
+        pub fn mount(&self, mock: Mock) {
+            self.runtime.block_on(mock.mount(&self.server));
+        }
+        
+        /// How many `method` requests reached `path`.
+        pub fn received(&self, method: &str, path: &str) -> usize {
+            self.runtime
+                .block_on(self.server.received_requests())
+                .unwrap_or_default()
+                .iter()
+                .filter(|r| r.method.as_str() == method && r.url.path() == path)
+                .count()
+        }
+    }
+    
+    fn release_path(tail: &str) -> String {
+        format!("/repos/{}/releases/{}", RELEASE_REPO, tail)
+    }
+    
+    #[test]
+    fn test_release_and_download_over_http() {
+        let fixture = FixtureServer::start();
+        let asset_url = format!("{}/downloads/nexus-orchestrator-testos", fixture.uri());
+        
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(release_path("latest")))
+                .and(header("authorization", "Bearer ghp_fixture"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "tag_name": "v2.1.0",
+                    "assets": [{ "name": "nexus-orchestrator-testos", "browser_download_url": asset_url }]
+                }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/downloads/nexus-orchestrator-testos"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(b"binary v2.1.0".to_vec())),
+        );
+        
+        let releases = GitHubReleases::new(Some("ghp_fixture".to_string()))
+            .unwrap()
+            .with_api_base(&fixture.uri());
+        
+        let release = releases.latest_release().unwrap();
+        assert_eq!(release.tag_name, "v2.1.0");
+        assert_eq!(release.assets[0].browser_download_url, asset_url);
+        
+        let mut body = Vec::new();
+        assert_eq!(releases.download(&asset_url, &mut body).unwrap(), 13);
+        assert_eq!(body, b"binary v2.1.0");
+    }
+    
+    #[test]
+    fn test_http_errors_map_to_exit_codes() {
+        let fixture = FixtureServer::start();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(release_path("latest")))
+                .respond_with(ResponseTemplate::new(502)),
+        );
+        
+        let releases = GitHubReleases::new(None).unwrap().with_api_base(&fixture.uri());
+        
+        // Unknown tags are a usage error, server failures are not
+        let err = releases.release_by_tag("v9.9.9").unwrap_err();
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        
+        let err = releases.latest_release().unwrap_err();
+        assert!(err.to_string().contains("502"), "{}", err);
+        assert_eq!(exit_code::for_error(&err), exit_code::FAILURE);
+    }
+}
+
+mod github_client_http {
+    use super::release_fixture::FixtureServer;
+    use chrono::{TimeZone, Utc};
+    use nexus_orchestrator::core::account::OwnerType;
+    use nexus_orchestrator::core::proxy::ProxyConfig;
+    use nexus_orchestrator::core::state::{ForkChainNode, ForkStatus, OrchestratorState};
+    use nexus_orchestrator::core::{Paths, StateManager};
+    use nexus_orchestrator::github::api::{GitHubError, GraphQlError, RepoPermissions};
+    use nexus_orchestrator::github::fork::{ForkSnapshot, UpstreamSync};
+    use nexus_orchestrator::github::workflow::{RunProgress, RunWatch};
+    use nexus_orchestrator::github::{ForkManager, GitHubClient, VariablesManager, WorkflowController};
+    use nexus_orchestrator::utils::cancel::{self, CancelToken};
+    use nexus_orchestrator::utils::delay::RecordingSleeper;
+    use nexus_orchestrator::utils::{exit_code, RetryConfig};
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use wiremock::matchers::{body_json, body_partial_json, header, method, path, path_regex, query_param};
+    use wiremock::{Mock, ResponseTemplate};
+    
+    const TOKEN: &str = "ghp_fixture0token";
+    
+    fn fixture() -> FixtureServer {
+        let fixture = FixtureServer::start();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner"))
+                .and(header("authorization", format!("Bearer {}", TOKEN).as_str()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "full_name": "alice/nexus-runner" }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path_regex("^/repos/alice/gone(/|$)"))
+                .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "message": "Not Found" }))),
+        );
+        fixture.mount(
+            Mock::given(method("PUT"))
+                .and(path("/repos/alice/nexus-runner/actions/secrets/API_KEY"))
+                .and(body_json(json!({ "encrypted_value": "c2VjcmV0", "key_id": "42" })))
+                .respond_with(ResponseTemplate::new(201)),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/secrets"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "total_count": 1,
+                    "secrets": [{ "name": "API_KEY", "updated_at": "2026-10-16T10:00:00Z" }]
+                }))),
+        );
+        fixture
+    }
+    
+    /// What SecretsManager and ForkManager rely on, identical for both transports.
+    fn exercise(client: GitHubClient) {
+        assert!(client.check_repo_exists("alice/nexus-runner").unwrap());
+        assert!(!client.check_repo_exists("alice/gone").unwrap());
+        
+        client.put_secret("alice/nexus-runner", "API_KEY", "c2VjcmV0", "42").unwrap();
+        assert_eq!(client.list_secrets("alice/nexus-runner").unwrap(), vec!["API_KEY"]);
+        let (name, updated_at) = client.list_secret_times("alice/nexus-runner").unwrap().remove(0);
+        assert_eq!((name.as_str(), updated_at.to_rfc3339()), ("API_KEY", "2026-10-16T10:00:00+00:00".to_string()));
+        
+        let err = client.get_repo_public_key("alice/gone").unwrap_err();
+        assert!(GitHubError::is_not_found(&err), "{:?}", err);
+        let err = err.to_string();
+        assert!(err.starts_with("API call failed: ") && err.ends_with("Not Found (HTTP 404)"), "{}", err);
+    }
+    
+    #[test]
+    fn test_native_transport() {
+        let fixture = fixture();
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri());
+        
+        assert!(client.is_native());
+        exercise(client);
+    }
+    
+    #[test]
+    fn test_client_errors_are_not_retried() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/revoked"))
+                .respond_with(ResponseTemplate::new(401).set_body_json(json!({ "message": "Bad credentials" }))),
+        );
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/repos/alice/nexus-runner/actions/variables"))
+                .respond_with(ResponseTemplate::new(422).set_body_json(json!({ "message": "Validation Failed" }))),
+        );
+        // The default [retries.github] profile: 3 attempts
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri());
+        
+        assert!(!client.check_repo_exists("alice/gone").unwrap());
+        assert_eq!(fixture.received("GET", "/repos/alice/gone"), 1);
+        
+        let err = client.check_repo_exists("alice/revoked").unwrap_err();
+        assert!(GitHubError::is_unauthorized(&err), "{:?}", err);
+        assert_eq!(fixture.received("GET", "/repos/alice/revoked"), 1);
+        
+        assert!(client.create_variable("alice/nexus-runner", "MAX_PARALLEL", "20").is_err());
+        assert_eq!(fixture.received("POST", "/repos/alice/nexus-runner/actions/variables"), 1);
+    }
+    
+    #[test]
+    fn test_server_errors_are_retried_through_the_sleeper() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/flaky"))
+                .respond_with(ResponseTemplate::new(502).set_body_json(json!({ "message": "Server Error" }))),
+        );
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_sleeper(sleeper.clone());
+        
+        assert!(client.check_repo_exists("alice/flaky").is_err());
+        assert_eq!(fixture.received("GET", "/repos/alice/flaky"), 3);
+        assert_eq!(sleeper.pauses(), vec![
+            ("retry".to_string(), Duration::from_millis(1000)),
+            ("retry".to_string(), Duration::from_millis(2000)),
+        ]);
+    }
+    
+    #[test]
+    fn test_cancel_active_runs_tolerates_runs_that_just_completed() {
+        let fixture = FixtureServer::start();
+        let runs = "/repos/alice/nexus-runner/actions/workflows/nexus.yml/runs";
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(runs))
+                .and(query_param("status", "queued"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "workflow_runs": [{ "id": 7 }] }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(runs))
+                .and(query_param("status", "in_progress"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "workflow_runs": [{ "id": 8 }] }))),
+        );
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/repos/alice/nexus-runner/actions/runs/7/cancel"))
+                .respond_with(ResponseTemplate::new(202).set_body_json(json!({}))),
+        );
+        // Run 8 finished between the listing and the cancel
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/repos/alice/nexus-runner/actions/runs/8/cancel"))
+                .respond_with(ResponseTemplate::new(409)
+                    .set_body_json(json!({ "message": "Cannot cancel a workflow run that is completed." }))),
+        );
+        // Run 7 takes one poll to report cancelled
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/runs/7"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "in_progress" })))
+                .up_to_n_times(1),
+        );
+        for run_id in [7, 8] {
+            fixture.mount(
+                Mock::given(method("GET"))
+                    .and(path(format!("/repos/alice/nexus-runner/actions/runs/{}", run_id).as_str()))
+                    .respond_with(ResponseTemplate::new(200)
+                        .set_body_json(json!({ "status": "completed", "conclusion": "cancelled" }))),
+            );
+        }
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        let sleeper = RecordingSleeper::default();
+        
+        let running = WorkflowController::cancel_active_runs("alice/nexus-runner", &client, &sleeper).unwrap();
+        assert!(running.is_empty(), "{:?}", running);
+        assert_eq!(sleeper.count("run_cancel_poll"), 1);
+    }
+    
+    #[test]
+    fn test_list_workflow_runs_uses_the_workflow_endpoint() {
+        let fixture = FixtureServer::start();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/workflows/nexus.yml/runs"))
+                .and(query_param("status", "failure"))
+                .and(query_param("per_page", "100"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "total_count": 1,
+                    "workflow_runs": [{
+                        "id": 12, "status": "completed", "conclusion": "failure", "event": "workflow_dispatch",
+                        "created_at": "2026-03-01T10:00:00Z",
+                        "html_url": "https://github.com/alice/nexus-runner/actions/runs/12"
+                    }]
+                }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/bob/nexus-runner/actions/workflows/nexus.yml/runs"))
+                .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "message": "Not Found" }))),
+        );
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        let runs = client.list_workflow_runs("alice/nexus-runner", "nexus.yml", Some("failure"), 500).unwrap();
+        let ids: Vec<u64> = runs.iter().map(|run| run.id).collect();
+        assert_eq!(ids, [12]);
+        assert_eq!(runs[0].html_url, "https://github.com/alice/nexus-runner/actions/runs/12");
+        
+        assert!(client.list_workflow_runs("bob/nexus-runner", "nexus.yml", None, 10).unwrap().is_empty());
+    }
+    
+    #[test]
+    fn test_latest_run_ignores_other_workflows_and_older_dispatches() {
+        let fixture = FixtureServer::start();
+        // Repo-wide listing: a CodeQL run interleaved with ours, and newest
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/runs"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "total_count": 3,
+                    "workflow_runs": [
+                        { "id": 31, "event": "push", "path": ".github/workflows/codeql.yml", "created_at": "2026-03-01T10:05:00Z" },
+                        { "id": 30, "event": "workflow_dispatch", "path": ".github/workflows/nexus.yml", "created_at": "2026-03-01T10:04:00Z" },
+                        { "id": 29, "event": "push", "path": ".github/workflows/codeql.yml", "created_at": "2026-03-01T10:00:00Z" }
+                    ]
+                }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/workflows/nexus.yml/runs"))
+                .and(query_param("event", "workflow_dispatch"))
+                .and(query_param("created", ">=2026-03-01T10:05:00Z"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "total_count": 0, "workflow_runs": [] }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/workflows/nexus.yml/runs"))
+                .and(query_param("event", "workflow_dispatch"))
+                .and(query_param("per_page", "1"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "total_count": 1,
+                    "workflow_runs": [
+                        { "id": 30, "event": "workflow_dispatch", "path": ".github/workflows/nexus.yml", "created_at": "2026-03-01T10:04:00Z" }
+                    ]
+                }))),
+        );
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        assert_eq!(client.get_latest_workflow_run("alice/nexus-runner", "nexus.yml", None).unwrap(), Some(30));
+        // Run 30 predates a dispatch issued at 10:05
+        let dispatched_at = Utc.with_ymd_and_hms(2026, 3, 1, 10, 5, 0).unwrap();
+        assert_eq!(client.get_latest_workflow_run("alice/nexus-runner", "nexus.yml", Some(dispatched_at)).unwrap(), None);
+        assert_eq!(fixture.received("GET", "/repos/alice/nexus-runner/actions/runs"), 0);
+    }
+    
+    #[test]
+    fn test_watch_run_reports_jobs_and_leaves_the_run_on_cancel() {
+        let fixture = FixtureServer::start();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/runs/9"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "in_progress" }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/runs/9/jobs"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "total_count": 3,
+                    "jobs": [
+                        { "id": 1, "name": "node-0", "status": "completed", "conclusion": "success" },
+                        { "id": 2, "name": "node-1", "status": "in_progress", "conclusion": null },
+                        { "id": 3, "name": "node-2", "status": "queued", "conclusion": null }
+                    ]
+                }))),
+        );
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        let sleeper = RecordingSleeper::default();
+        let cancel = CancelToken::new();
+        let seen = std::cell::RefCell::new(Vec::new());
+        // Ctrl-C arrives while the first progress line is shown
+        let on_progress = |progress: &RunProgress| {
+            seen.borrow_mut().push(progress.summary());
+            cancel.cancel();
+        };
+        let watch = RunWatch { sleeper: &sleeper, cancel: &cancel, on_progress: &on_progress };
+        
+        let err = WorkflowController::watch_run("alice/nexus-runner", 9, &client, 60, &watch).unwrap_err();
+        
+        assert!(cancel::is_cancelled(&err), "{:?}", err);
+        assert_eq!(seen.borrow().len(), 1);
+        assert!(seen.borrow()[0].starts_with("run #9 in_progress: 1 queued, 1 running, 1/3 done"), "{:?}", seen.borrow());
+        assert_eq!(fixture.received("POST", "/repos/alice/nexus-runner/actions/runs/9/cancel"), 0);
+    }
+    
+    #[test]
+    fn test_run_report_reads_every_page_of_jobs() {
+        let fixture = FixtureServer::start();
+        let run = "/repos/alice/nexus-runner/actions/runs/9";
+        let job = |id: u64, conclusion: &str| json!({
+            "id": id, "name": format!("node-{}", id), "status": "completed", "conclusion": conclusion,
+            "started_at": "2026-03-01T10:00:00Z", "completed_at": "2026-03-01T10:42:30Z"
+        });
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(run))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_json(json!({ "status": "completed", "conclusion": "failure" }))),
+        );
+        let first: Vec<_> = (0..100).map(|id| job(id, "success")).collect();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(format!("{}/jobs", run).as_str()))
+                .and(query_param("page", "1"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "total_count": 102, "jobs": first }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(format!("{}/jobs", run).as_str()))
+                .and(query_param("page", "2"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_json(json!({ "total_count": 102, "jobs": [job(100, "success"), job(101, "failure")] }))),
+        );
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        let report = WorkflowController::run_report("alice/nexus-runner", 9, &client).unwrap();
+        assert_eq!(report.jobs.len(), 102);
+        assert_eq!(report.succeeded(), (101, 102));
+        let job = &report.jobs[101];
+        assert_eq!((job.name.as_str(), job.conclusion.as_deref()), ("node-101", Some("failure")));
+        assert_eq!(job.completed_at.unwrap() - job.started_at.unwrap(), chrono::Duration::seconds(2550));
+        // One node of a large matrix failing leaves the run healthy
+        assert!(report.is_healthy());
+    }
+    
+    #[test]
+    fn test_watch_run_gives_up_on_a_run_that_stays_queued() {
+        let fixture = FixtureServer::start();
+        let run = "/repos/alice/nexus-runner/actions/runs/9";
+        // Picked up once, then queued again for good after a re-run
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(run))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "queued" })))
+                .up_to_n_times(3),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(run))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "in_progress" })))
+                .up_to_n_times(1),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(run))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "queued" }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(format!("{}/jobs", run).as_str()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "total_count": 0, "jobs": [] }))),
+        );
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        let sleeper = RecordingSleeper::default();
+        let watch = RunWatch { sleeper: &sleeper, cancel: &CancelToken::new(), on_progress: &|_| {} };
+        
+        let result = WorkflowController::watch_run("alice/nexus-runner", 9, &client, 600, &watch).unwrap();
+        assert_eq!(result, "queued");
+        // monitor.stuck_queued_minutes of polling, counted from when the run went back to the queue
+        let polled: Duration = sleeper.pauses().iter().map(|(_, d)| *d).sum();
+        assert_eq!(polled, Duration::from_secs(30 * 60 + 4 * 10));
+    }
+    
+    #[test]
+    fn test_auto_rerun_counts_attempts_and_waits_out_running_runs() {
+        let fixture = FixtureServer::start();
+        let run = "/repos/alice/nexus-runner/actions/runs/9";
+        // Failed before the first re-run and again after it
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(run))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_json(json!({ "status": "completed", "conclusion": "failure" })))
+                .up_to_n_times(2),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path(run))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_json(json!({ "status": "completed", "conclusion": "success" }))),
+        );
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path(format!("{}/rerun-failed-jobs", run).as_str()))
+                .respond_with(ResponseTemplate::new(201).set_body_json(json!({})))
+                .up_to_n_times(1),
+        );
+        // The second request races a re-run someone else started
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path(format!("{}/rerun-failed-jobs", run).as_str()))
+                .respond_with(ResponseTemplate::new(403)
+                    .insert_header("x-ratelimit-remaining", "4999")
+                    .set_body_json(json!({ "message": "This workflow is already running" }))),
+        );
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        let sleeper = RecordingSleeper::default();
+        
+        let watch = RunWatch { sleeper: &sleeper, cancel: &CancelToken::new(), on_progress: &|_| {} };
+        
+        let report = WorkflowController::auto_rerun("alice/nexus-runner", 9, &client, 3, false, &watch).unwrap();
+        assert_eq!((report.attempts, report.conclusion.as_str()), (1, "success"));
+        assert_eq!(report.exit_code(), exit_code::SUCCESS);
+        // Two waits of delays.rerun_ms, slept in one-second slices so Ctrl-C is noticed
+        let rerun_wait: Duration = sleeper.pauses().iter().filter(|(name, _)| name == "rerun").map(|(_, d)| *d).sum();
+        assert_eq!(rerun_wait, Duration::from_secs(60));
+    }
+    
+    #[test]
+    fn test_rate_limit_waits_for_reset_headers() {
+        let fixture = FixtureServer::start();
+        let reset = chrono::Utc::now().timestamp() + 120;
+        
+        // Secondary limit: retry-after
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/user"))
+                .respond_with(ResponseTemplate::new(403)
+                    .insert_header("retry-after", "7")
+                    .set_body_json(json!({ "message": "You have exceeded a secondary rate limit" })))
+                .up_to_n_times(1),
+        );
+        // Primary limit: quota used up until the reset
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/user"))
+                .respond_with(ResponseTemplate::new(403)
+                    .insert_header("x-ratelimit-remaining", "0")
+                    .insert_header("x-ratelimit-reset", reset.to_string().as_str())
+                    .set_body_json(json!({ "message": "API rate limit exceeded" })))
+                .up_to_n_times(1),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/user"))
+                .respond_with(ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-remaining", "4999")
+                    .set_body_json(json!({ "login": "alice" }))),
+        );
+        
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_sleeper(sleeper.clone())
+            .with_retry_config(RetryConfig {
+                initial_delay_ms: 1,
+                max_delay_ms: 1,
+                max_rate_limit_wait_ms: 60_000,
+                ..RetryConfig::default()
+            });
+        
+        assert_eq!(client.get_username().unwrap(), "alice");
+        assert_eq!(client.rate_limit_remaining(), Some(4999));
+        
+        // Two minutes to the reset, capped at one; each in place of the usual backoff
+        assert_eq!(sleeper.pauses(), vec![
+            ("rate_limit".to_string(), Duration::from_secs(7)),
+            ("rate_limit".to_string(), Duration::from_secs(60)),
+        ]);
+    }
+    
+    #[test]
+    fn test_rate_limit_on_the_last_attempt_fails_without_waiting() {
+        let fixture = FixtureServer::start();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/user"))
+                .respond_with(ResponseTemplate::new(403)
+                    .insert_header("retry-after", "30")
+                    .set_body_json(json!({ "message": "You have exceeded a secondary rate limit" }))),
+        );
+        
+        let sleeper = Arc::new(RecordingSleeper::default());
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_sleeper(sleeper.clone())
+            .with_retry_config(RetryConfig { max_attempts: 2, ..RetryConfig::default() });
+        
+        let err = client.get_username().unwrap_err();
+        assert!(matches!(GitHubError::of(&err), Some(GitHubError::RateLimited { .. })), "{:#}", err);
+        assert_eq!(fixture.received("GET", "/user"), 2);
+        // Only the wait before the second attempt
+        assert_eq!(sleeper.pauses(), vec![("rate_limit".to_string(), Duration::from_secs(30))]);
+    }
+    
+    #[test]
+    fn test_graphql_errors_are_told_apart_from_transport_errors() {
+        const QUERY: &str = "query($login: String!) { user(login: $login) { login } }";
+        
+        let fixture = FixtureServer::start();
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/graphql"))
+                .and(body_partial_json(json!({ "variables": { "login": "alice" } })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "data": { "user": { "login": "alice" } } }))),
+        );
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/graphql"))
+                .and(body_partial_json(json!({ "variables": { "login": "ghost" } })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "data": { "user": null },
+                    "errors": [{ "type": "NOT_FOUND", "message": "Could not resolve to a User with the login of 'ghost'." }]
+                }))),
+        );
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/graphql"))
+                .and(body_partial_json(json!({ "variables": { "login": "outage" } })))
+                .respond_with(ResponseTemplate::new(502).set_body_json(json!({ "message": "Server Error" }))),
+        );
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 2, initial_delay_ms: 1, max_delay_ms: 1, ..RetryConfig::default() });
+        
+        let data = client.api_graphql(QUERY, json!({ "login": "alice" })).unwrap();
+        assert_eq!(data["user"]["login"], "alice");
+        
+        let err = client.api_graphql(QUERY, json!({ "login": "ghost" })).unwrap_err();
+        let graphql = err.downcast_ref::<GraphQlError>().expect("GraphQL error");
+        assert!(graphql.only_not_found());
+        assert_eq!(graphql.data, Some(json!({ "user": null })));
+        assert!(err.to_string().contains("Could not resolve to a User"), "{}", err);
+        
+        let err = client.api_graphql(QUERY, json!({ "login": "outage" })).unwrap_err();
+        assert!(err.downcast_ref::<GraphQlError>().is_none());
+        assert!(err.to_string().contains("Server Error (HTTP 502)"), "{}", err);
+    }
+    
+    #[test]
+    fn test_chain_snapshot_is_one_query() {
+        let fixture = FixtureServer::start();
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/graphql"))
+                .and(body_partial_json(json!({
+                    "variables": { "owner0": "alice", "name0": "nexus-runner", "owner1": "bob", "name1": "nexus-runner" }
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "data": {
+                        "repo0": {
+                            "defaultBranchRef": {
+                                "name": "main",
+                                "target": { "checkSuites": { "nodes": [{
+                                    "status": "IN_PROGRESS",
+                                    "conclusion": null,
+                                    "workflowRun": { "databaseId": 901, "createdAt": "2026-10-16T10:00:00Z" }
+                                }] } }
+                            }
+                        },
+                        "repo1": null
+                    },
+                    "errors": [{
+                        "type": "NOT_FOUND",
+                        "path": ["repo1"],
+                        "message": "Could not resolve to a Repository with the name 'bob/nexus-runner'."
+                    }]
+                })))
+                .expect(1),
+        );
+        
+        let temp_dir = tempfile::tempdir().unwrap();
+        let state_mgr = StateManager::new(&Paths::new(temp_dir.path())).unwrap();
+        let now = Utc::now();
+        let node = |owner: &str, status: ForkStatus| ForkChainNode {
+            pat_index: 0,
+            username: owner.to_string(),
+            owner_type: OwnerType::User,
+            repo: format!("{}/nexus-runner", owner),
+            parent: None,
+            billing_used: 0.0,
+            status,
+            created_at: now,
+            last_updated: now,
+            last_run: None,
+        };
+        let state = OrchestratorState {
+            fork_chain: vec![
+                node("alice", ForkStatus::Active),
+                node("carol", ForkStatus::Deleted),
+                node("bob", ForkStatus::Exhausted),
+            ],
+            ..OrchestratorState::default()
+        };
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri());
+        let snapshot = ForkManager::new(Arc::new(state_mgr)).chain_snapshot(&state, &client).unwrap();
+        
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].repo, "alice/nexus-runner");
+        assert!(snapshot[0].exists);
+        assert_eq!(snapshot[0].default_branch.as_deref(), Some("main"));
+        let run = snapshot[0].latest_run.as_ref().unwrap();
+        assert_eq!((run.run_id, run.status.as_str(), run.conclusion.as_deref()), (901, "in_progress", None));
+        assert_eq!(snapshot[1], ForkSnapshot {
+            repo: "bob/nexus-runner".to_string(),
+            exists: false,
+            default_branch: None,
+            latest_run: None,
+        });
+    }
+    
+    #[test]
+    fn test_native_timeout_is_a_network_error() {
+        let fixture = FixtureServer::start();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/user"))
+                .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5))),
+        );
+        
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_timeout(Duration::from_millis(200))
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        let err = client.api_call("user", "GET").unwrap_err().to_string();
+        assert!(err.starts_with("Network error: timeout"), "{}", err);
+    }
+    
+    /// A gh that never answers, as behind a dead proxy: killed at the
+    /// deadline and retried.
+    #[cfg(unix)]
+    #[test]
+    fn test_gh_timeout_kills_and_retries() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let gh = dir.path().join("gh");
+        let calls = dir.path().join("calls");
+        std::fs::write(&gh, format!("#!/bin/sh\necho call >> '{}'\nexec sleep 30\n", calls.display())).unwrap();
+        std::fs::set_permissions(&gh, std::fs::Permissions::from_mode(0o755)).unwrap();
+        
+        let client = GitHubClient::new_gh(TOKEN.to_string(), None)
+            .with_gh_binary(gh.to_str().unwrap())
+            .with_timeout(Duration::from_millis(200))
+            .with_retry_config(RetryConfig { max_attempts: 2, initial_delay_ms: 1, max_delay_ms: 1, ..RetryConfig::default() });
+        
+        let started = std::time::Instant::now();
+        let err = client.api_call("user", "GET").unwrap_err().to_string();
+        
+        assert!(err.starts_with("Network error: timeout"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(std::fs::read_to_string(&calls).unwrap().lines().count(), 2);
+    }
+    
+    /// A gh that drops the first connection: the secret write is retried
+    /// with its body sent again.
+    #[cfg(unix)]
+    #[test]
+    fn test_write_with_body_is_retried() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let gh = dir.path().join("gh");
+        let calls = dir.path().join("calls");
+        let bodies = dir.path().join("bodies");
+        std::fs::write(&gh, format!(
+            "#!/bin/sh\ncat >> '{bodies}'\necho >> '{bodies}'\necho call >> '{calls}'\n\
+             if [ $(wc -l < '{calls}') -lt 2 ]; then\n\
+             echo 'error connecting to api.github.com' >&2\nexit 1\nfi\n",
+            bodies = bodies.display(),
+            calls = calls.display(),
+        )).unwrap();
+        std::fs::set_permissions(&gh, std::fs::Permissions::from_mode(0o755)).unwrap();
+        
+        let client = GitHubClient::new_gh(TOKEN.to_string(), None)
+            .with_gh_binary(gh.to_str().unwrap())
+            .with_retry_config(RetryConfig { max_attempts: 3, initial_delay_ms: 1, max_delay_ms: 1, ..RetryConfig::default() });
+        
+        client.put_secret("alice/nexus-runner", "API_KEY", "c2VjcmV0", "42").unwrap();
+        
+        let body = json!({ "encrypted_value": "c2VjcmV0", "key_id": "42" });
+        let sent: Vec<serde_json::Value> = std::fs::read_to_string(&bodies)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(sent, vec![body.clone(), body]);
+    }
+    
+    #[test]
+    fn test_repo_exists_tells_missing_from_hidden() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/private-runner"))
+                .respond_with(
+                    ResponseTemplate::new(403)
+                        .insert_header("x-ratelimit-remaining", "4999")
+                        .set_body_json(json!({ "message": "Resource not accessible by personal access token" })),
+                ),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/revoked-runner"))
+                .respond_with(ResponseTemplate::new(401).set_body_json(json!({ "message": "Bad credentials" }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        assert!(client.check_repo_exists("alice/nexus-runner").unwrap());
+        assert!(!client.check_repo_exists("alice/gone").unwrap());
+        
+        let err = client.check_repo_exists("alice/private-runner").unwrap_err();
+        assert!(matches!(GitHubError::of(&err), Some(GitHubError::Forbidden(_))), "{:?}", err);
+        let err = client.check_repo_exists("alice/revoked-runner").unwrap_err();
+        assert!(matches!(GitHubError::of(&err), Some(GitHubError::Unauthorized(_))), "{:?}", err);
+        assert!(GitHubError::is_no_access(&err));
+    }
+    
+    fn log_archive() -> Vec<u8> {
+        use std::io::Write;
+        
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file("0_nexus.txt", zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"Error: prover exited with code 1").unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+    
+    #[test]
+    fn test_failure_logs_follow_redirect_and_unpack() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/runs"))
+                .and(query_param("status", "failure"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "workflow_runs": [{ "id": 77 }] }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/runs/77/logs"))
+                .and(header("authorization", format!("Bearer {}", TOKEN).as_str()))
+                .respond_with(
+                    ResponseTemplate::new(302).insert_header("location", format!("{}/blobs/run-77.zip?sig=abc", fixture.uri()).as_str()),
+                ),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/blobs/run-77.zip"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(log_archive())),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/runs/78/logs"))
+                .respond_with(ResponseTemplate::new(410).set_body_json(json!({ "message": "Logs have expired" }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None).unwrap().with_api_base(&fixture.uri());
+        let temp_dir = tempfile::tempdir().unwrap();
+        
+        let logs = WorkflowController::fetch_failure_logs("alice/nexus-runner", &client, temp_dir.path(), true)
+            .unwrap()
+            .unwrap();
+        
+        assert_eq!(logs.run_id, 77);
+        assert_eq!(logs.archive, temp_dir.path().join("alice-nexus-runner-77.zip"));
+        assert_eq!(logs.files, vec![temp_dir.path().join("alice-nexus-runner-77/0_nexus.txt")]);
+        assert!(std::fs::read_to_string(&logs.files[0]).unwrap().contains("prover exited"));
+        assert_eq!(fixture.received("GET", "/blobs/run-77.zip"), 1);
+        
+        let err = client.download_run_logs("alice/nexus-runner", 78, &temp_dir.path().join("78.zip")).unwrap_err();
+        assert_eq!(err.to_string(), "API call failed: Logs have expired (HTTP 410)");
+    }
+    
+    #[test]
+    fn test_artifacts_stream_through_redirect_and_unpack() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/runs/77/artifacts"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "total_count": 2,
+                    "artifacts": [
+                        { "id": 5, "name": "node-logs-1", "size_in_bytes": 180, "expired": false },
+                        { "id": 6, "name": "node-logs-0", "size_in_bytes": 160, "expired": true }
+                    ]
+                }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/artifacts/5/zip"))
+                .and(header("authorization", format!("Bearer {}", TOKEN).as_str()))
+                .respond_with(
+                    ResponseTemplate::new(302).insert_header("location", format!("{}/blobs/artifact-5.zip?sig=abc", fixture.uri()).as_str()),
+                ),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/blobs/artifact-5.zip"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(log_archive())),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None).unwrap().with_api_base(&fixture.uri());
+        let temp_dir = tempfile::tempdir().unwrap();
+        
+        let collected = WorkflowController::collect_artifacts("alice/nexus-runner", 77, &client, temp_dir.path()).unwrap();
+        
+        let dir = temp_dir.path().join("alice/nexus-runner/77");
+        assert_eq!(collected.dir, dir);
+        assert_eq!(collected.artifacts, vec![("node-logs-1".to_string(), vec![dir.join("node-logs-1/0_nexus.txt")])]);
+        assert_eq!(collected.expired, ["node-logs-0"]);
+        assert!(std::fs::read_to_string(dir.join("node-logs-1/0_nexus.txt")).unwrap().contains("prover exited"));
+        assert!(!dir.join("node-logs-1.zip").exists());
+        assert_eq!(fixture.received("GET", "/repos/alice/nexus-runner/actions/artifacts/6/zip"), 0);
+    }
+    
+    #[test]
+    fn test_create_fork_requests_a_name_and_returns_the_one_given() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/repos/origin/nexus-runner/forks"))
+                .and(body_json(json!({ "organization": "acme-labs", "name": "nexus-chain-1" })))
+                .respond_with(ResponseTemplate::new(202).set_body_json(json!({ "full_name": "acme-labs/nexus-chain-1-1" }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        let fork = client.create_fork("origin/nexus-runner", Some("acme-labs"), Some("nexus-chain-1")).unwrap();
+        assert_eq!(fork, "acme-labs/nexus-chain-1-1");
+    }
+    
+    #[test]
+    fn test_get_repo_info_parses_metadata() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/legacy-runner"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "full_name": "alice/legacy-runner",
+                    "default_branch": "master",
+                    "fork": true,
+                    "private": false,
+                    "archived": false,
+                    "size": 120,
+                    "created_at": "2024-03-01T08:30:00Z",
+                    "parent": { "full_name": "upstream/legacy-runner", "fork": false },
+                    "permissions": { "admin": false, "push": true, "pull": true },
+                }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        let info = client.get_repo_info("alice/legacy-runner").unwrap();
+        assert_eq!(info.default_branch, "master");
+        assert!(info.fork && !info.private && !info.archived);
+        assert_eq!(info.parent_name(), Some("upstream/legacy-runner"));
+        assert_eq!(info.created_at.unwrap().to_rfc3339(), "2024-03-01T08:30:00+00:00");
+        assert_eq!(info.permissions, Some(RepoPermissions { admin: false, push: true, pull: true }));
+        
+        // The fixture's plain repo has no parent and reports no contents yet
+        let info = client.get_repo_info("alice/nexus-runner").unwrap();
+        assert_eq!((info.parent_name(), info.size, info.fork), (None, 0, false));
+        assert!(GitHubError::is_not_found(&client.get_repo_info("alice/gone").unwrap_err()));
+    }
+    
+    #[test]
+    fn test_workflow_state_and_id_come_from_the_workflow_list() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/workflows"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "total_count": 2,
+                    "workflows": [
+                        { "id": 7, "path": ".github/workflows/ci.yml", "state": "active" },
+                        { "id": 9, "path": ".github/workflows/nexus.yml", "state": "disabled_manually" },
+                    ]
+                }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        assert_eq!(client.get_workflow_id("alice/nexus-runner", "nexus.yml").unwrap(), Some(9));
+        assert_eq!(client.workflow_state("alice/nexus-runner", "nexus.yml").unwrap().as_deref(), Some("disabled_manually"));
+        assert_eq!(client.workflow_state("alice/nexus-runner", "deploy.yml").unwrap(), None);
+    }
+    
+    #[test]
+    fn test_environments_are_created_once_and_addressed_by_repo_id() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/environments/nexus-prod"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "nexus-prod" }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/environments/staging"))
+                .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "message": "Not Found" }))),
+        );
+        fixture.mount(
+            Mock::given(method("PUT"))
+                .and(path("/repos/alice/nexus-runner/environments/staging"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "name": "staging" })))
+                .expect(1),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repositories/77/environments/nexus-prod/secrets/public-key"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "key": "cHVia2V5", "key_id": "99" }))),
+        );
+        fixture.mount(
+            Mock::given(method("PUT"))
+                .and(path("/repositories/77/environments/nexus-prod/secrets/API_KEY"))
+                .and(body_json(json!({ "encrypted_value": "c2VjcmV0", "key_id": "99" })))
+                .respond_with(ResponseTemplate::new(201)),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        // An existing environment is left alone: a PUT would reset its reviewers
+        assert!(!client.create_environment("alice/nexus-runner", "nexus-prod").unwrap());
+        assert!(client.create_environment("alice/nexus-runner", "staging").unwrap());
+        
+        assert_eq!(
+            client.environment_public_key(77, "nexus-prod").unwrap(),
+            ("cHVia2V5".to_string(), "99".to_string())
+        );
+        client.put_environment_secret(77, "nexus-prod", "API_KEY", "c2VjcmV0", "99").unwrap();
+    }
+    
+    #[test]
+    fn test_set_variable_patches_after_a_conflict() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/repos/alice/nexus-runner/actions/variables"))
+                .and(body_json(json!({ "name": "MAX_PARALLEL", "value": "20" })))
+                .respond_with(ResponseTemplate::new(409).set_body_json(json!({ "message": "Already exists - Variable already exists" }))),
+        );
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path("/repos/alice/nexus-runner/actions/variables"))
+                .and(body_json(json!({ "name": "NEXUS_RPC_URL", "value": "https://rpc.example" })))
+                .respond_with(ResponseTemplate::new(201)),
+        );
+        fixture.mount(
+            Mock::given(method("PATCH"))
+                .and(path("/repos/alice/nexus-runner/actions/variables/MAX_PARALLEL"))
+                .and(body_json(json!({ "name": "MAX_PARALLEL", "value": "20" })))
+                .respond_with(ResponseTemplate::new(204))
+                .expect(1),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/variables"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "total_count": 1,
+                    "variables": [{ "name": "MAX_PARALLEL", "value": "20" }]
+                }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri());
+        let variables = VariablesManager::new(Box::new(client));
+        
+        assert!(variables.set_variable("alice/nexus-runner", "NEXUS_RPC_URL", "https://rpc.example").unwrap());
+        assert!(!variables.set_variable("alice/nexus-runner", "MAX_PARALLEL", "20").unwrap());
+        assert_eq!(
+            variables.list_variables("alice/nexus-runner").unwrap(),
+            vec![("MAX_PARALLEL".to_string(), "20".to_string())]
+        );
+        assert_eq!(variables.get_variable("alice/gone", "MAX_PARALLEL").unwrap(), None);
+        // Neither the 409 nor the 404 is retried
+        assert_eq!(fixture.received("POST", "/repos/alice/nexus-runner/actions/variables"), 2);
+        assert_eq!(fixture.received("GET", "/repos/alice/gone/actions/variables/MAX_PARALLEL"), 1);
+    }
+    
+    #[test]
+    fn test_dispatch_sends_inputs_and_passes_on_rejected_ones() {
+        let fixture = fixture();
+        let dispatches = "/repos/alice/nexus-runner/actions/workflows/nexus.yml/dispatches";
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path(dispatches))
+                .and(body_json(json!({ "ref": "main", "inputs": { "batch": "2", "mode": "full" } })))
+                .respond_with(ResponseTemplate::new(204)),
+        );
+        fixture.mount(
+            Mock::given(method("POST"))
+                .and(path(dispatches))
+                .respond_with(ResponseTemplate::new(422)
+                    .set_body_json(json!({ "message": "Unexpected inputs provided: [\"nodes\"]" }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        let inputs = json!({ "batch": "2", "mode": "full" });
+        client.trigger_workflow("alice/nexus-runner", "nexus.yml", "main", Some(&inputs)).unwrap();
+        
+        let err = client
+            .trigger_workflow("alice/nexus-runner", "nexus.yml", "main", Some(&json!({ "nodes": "4" })))
+            .unwrap_err();
+        assert!(err.to_string().contains(r#"Unexpected inputs provided: ["nodes"]"#), "{}", err);
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+        
+        // Never sent: GitHub only takes a flat map of strings
+        let err = client
+            .trigger_workflow("alice/nexus-runner", "nexus.yml", "main", Some(&json!({ "max_nodes": 4 })))
+            .unwrap_err();
+        assert!(err.to_string().contains("max_nodes"), "{}", err);
+        assert_eq!(fixture.received("POST", dispatches), 2);
+    }
+    
+    #[test]
+    fn test_enable_actions_explains_an_organization_policy() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("PUT"))
+                .and(path("/repos/alice/nexus-runner/actions/permissions"))
+                .and(body_json(json!({ "enabled": true, "allowed_actions": "all" })))
+                .respond_with(ResponseTemplate::new(204)),
+        );
+        fixture.mount(
+            Mock::given(method("PUT"))
+                .and(path("/repos/acme-labs/nexus-runner/actions/permissions"))
+                .respond_with(ResponseTemplate::new(409)
+                    .set_body_json(json!({ "message": "Actions is disabled by the organization" }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        client.enable_actions("alice/nexus-runner").unwrap();
+        
+        let err = client.enable_actions("acme-labs/nexus-runner").unwrap_err();
+        assert!(err.to_string().contains("acme-labs's Actions policy does not allow it"), "{}", err);
+        assert_eq!(exit_code::for_error(&err), exit_code::CONFIG_ERROR);
+    }
+    
+    #[test]
+    fn test_token_scopes_come_from_the_oauth_header() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/user"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("x-oauth-scopes", "repo, workflow, delete_repo")
+                        .set_body_json(json!({ "login": "alice" })),
+                ),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        assert_eq!(
+            client.token_scopes().unwrap(),
+            Some(vec!["repo".to_string(), "workflow".to_string(), "delete_repo".to_string()])
+        );
+    }
+    
+    #[test]
+    fn test_branch_head_is_none_until_the_branch_exists() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/branches/main"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "name": "main",
+                    "commit": { "sha": "6dcb09b5b57875f334f61aebed695e2e4193db5e" },
+                }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/branches/dev"))
+                .respond_with(ResponseTemplate::new(404).set_body_json(json!({ "message": "Branch not found" }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        assert_eq!(
+            client.branch_head("alice/nexus-runner", "main").unwrap().as_deref(),
+            Some("6dcb09b5b57875f334f61aebed695e2e4193db5e")
+        );
+        assert_eq!(client.branch_head("alice/nexus-runner", "dev").unwrap(), None);
+    }
+    
+    #[test]
+    fn test_list_forks_follows_pages() {
+        let fixture = fixture();
+        let fork = |i: usize| json!({ "full_name": format!("user_{}/nexus-runner", i), "owner": { "login": format!("user_{}", i) } });
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/forks"))
+                .and(query_param("page", "1"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!((0..100).map(fork).collect::<Vec<_>>()))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/forks"))
+                .and(query_param("page", "2"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!([fork(100)]))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None).unwrap().with_api_base(&fixture.uri());
+        
+        let forks = client.list_forks("alice/nexus-runner").unwrap();
+        
+        assert_eq!(forks.len(), 101);
+        assert_eq!(forks[100], ("user_100".to_string(), "user_100/nexus-runner".to_string()));
+        assert_eq!(fixture.received("GET", "/repos/alice/nexus-runner/forks"), 2);
+    }
+    
+    #[test]
+    fn test_sync_with_upstream_reports_merge_outcomes() {
+        let fixture = fixture();
+        let outcomes = [
+            ("ahead", 200, json!({ "message": "Successfully fetched and fast-forwarded from upstream", "merge_type": "fast-forward", "base_branch": "upstream:main" })),
+            ("current", 200, json!({ "message": "This branch is not behind the upstream", "merge_type": "none", "base_branch": "upstream:main" })),
+            ("diverged", 409, json!({ "message": "There are merge conflicts" })),
+        ];
+        for (name, status, body) in outcomes {
+            fixture.mount(
+                Mock::given(method("GET"))
+                    .and(path(format!("/repos/alice/{}", name)))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "full_name": format!("alice/{}", name), "default_branch": "main" }))),
+            );
+            fixture.mount(
+                Mock::given(method("POST"))
+                    .and(path(format!("/repos/alice/{}/merge-upstream", name)))
+                    .and(body_json(json!({ "branch": "main" })))
+                    .respond_with(ResponseTemplate::new(status).set_body_json(body)),
+            );
+        }
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ForkManager::new(Arc::new(StateManager::new(&Paths::new(temp_dir.path())).unwrap()));
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        assert_eq!(
+            manager.sync_with_upstream("alice/ahead", &client).unwrap(),
+            UpstreamSync::Merged { branch: "main".to_string(), merge_type: "fast-forward".to_string() }
+        );
+        assert_eq!(
+            manager.sync_with_upstream("alice/current", &client).unwrap(),
+            UpstreamSync::UpToDate { branch: "main".to_string() }
+        );
+        
+        // A conflict is an outcome to report, not an error
+        let sync = manager.sync_with_upstream("alice/diverged", &client).unwrap();
+        assert!(sync.is_conflict());
+        assert!(sync.to_string().contains("There are merge conflicts"), "{}", sync);
+        
+        assert!(GitHubError::is_not_found(&manager.sync_with_upstream("alice/gone", &client).unwrap_err()));
+    }
+    
+    #[test]
+    fn test_response_cache_serves_repeated_gets() {
+        let fixture = fixture();
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/secrets/public-key"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "key": "a2V5", "key_id": "42" }))),
+        );
+        fixture.mount(
+            Mock::given(method("GET"))
+                .and(path("/repos/alice/nexus-runner/actions/runs/7"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "in_progress" }))),
+        );
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_cache(Duration::from_secs(60));
+        
+        for _ in 0..3 {
+            assert_eq!(client.get_repo_public_key("alice/nexus-runner").unwrap(), ("a2V5".to_string(), "42".to_string()));
+            client.put_secret("alice/nexus-runner", "API_KEY", "c2VjcmV0", "42").unwrap();
+        }
+        assert_eq!(fixture.received("GET", "/repos/alice/nexus-runner/actions/secrets/public-key"), 1);
+        assert_eq!(fixture.received("PUT", "/repos/alice/nexus-runner/actions/secrets/API_KEY"), 3);
+        
+        // The secret list sits above the written secret: refetched after each write
+        client.list_secrets("alice/nexus-runner").unwrap();
+        client.list_secrets("alice/nexus-runner").unwrap();
+        client.put_secret("alice/nexus-runner", "API_KEY", "c2VjcmV0", "42").unwrap();
+        client.list_secrets("alice/nexus-runner").unwrap();
+        assert_eq!(fixture.received("GET", "/repos/alice/nexus-runner/actions/secrets"), 2);
+        
+        // Verification reads bypass the cache
+        client.list_secret_times("alice/nexus-runner").unwrap();
+        client.list_secret_times("alice/nexus-runner").unwrap();
+        assert_eq!(fixture.received("GET", "/repos/alice/nexus-runner/actions/secrets"), 4);
+        
+        // Run status polling always goes to GitHub
+        client.get_workflow_status("alice/nexus-runner", 7).unwrap();
+        client.get_workflow_status("alice/nexus-runner", 7).unwrap();
+        assert_eq!(fixture.received("GET", "/repos/alice/nexus-runner/actions/runs/7"), 2);
+    }
+    
+    #[test]
+    fn test_trace_log_is_redacted() {
+        let fixture = fixture();
+        let logs = tempfile::tempdir().unwrap();
+        let client = GitHubClient::new_native(TOKEN.to_string(), None)
+            .unwrap()
+            .with_api_base(&fixture.uri())
+            .with_trace(logs.path())
+            .with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+        
+        client.put_secret("alice/nexus-runner", "API_KEY", "c2VjcmV0", "42").unwrap();
+        client.list_secrets("alice/nexus-runner").unwrap();
+        assert!(client.get_repo_public_key("alice/gone").is_err());
+        
+        let trace = std::fs::read_to_string(logs.path().join("api-trace.log")).unwrap();
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", trace);
+        assert!(lines[0].ends_with("PUT repos/alice/nexus-runner/actions/secrets/API_KEY [44 bytes redacted] -> ok: "), "{}", lines[0]);
+        assert!(lines[1].contains("GET repos/alice/nexus-runner/actions/secrets -> ok: {"), "{}", lines[1]);
+        assert!(lines[2].contains("-> HTTP 404: Not Found (HTTP 404)"), "{}", lines[2]);
+        assert!(!trace.contains("c2VjcmV0") && !trace.contains(TOKEN), "{}", trace);
+    }
+    
+    #[test]
+    fn test_native_transport_rejects_bad_proxy() {
+        assert!(GitHubClient::new_native(TOKEN.to_string(), Some("not a url".to_string())).is_err());
+    }
+    
+    #[test]
+    fn test_native_transport_accepts_socks5_proxy() {
+        let proxy = ProxyConfig::from_url("socks5://u:p@127.0.0.1:1080").unwrap();
+        let client = GitHubClient::new_native(TOKEN.to_string(), Some(proxy.to_curl_format())).unwrap();
+        assert!(client.is_native());
+    }
+    
+    /// The gh path, with a stand-in gh that forwards to the fixture server
+    /// the way `gh api` would and words failures like it.
+    #[cfg(unix)]
+    #[test]
+    fn test_gh_transport() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        if nexus_orchestrator::utils::platform::find_executable("curl").is_none() {
+            eprintln!("curl not found, skipping");
+            return;
+        }
+        
+        let fixture = fixture();
+        let dir = tempfile::tempdir().unwrap();
+        let gh = dir.path().join("gh");
+        std::fs::write(&gh, format!(r#"#!/bin/sh
+# gh api -i [-X METHOD] ENDPOINT [--input -]
+shift 2
+method=GET
+if [ "$1" = "-X" ]; then method=$2; shift 2; fi
+endpoint=$1
+hdr=$(mktemp)
+out=$(mktemp)
+if [ "$2" = "--input" ]; then
+    code=$(curl -s -D "$hdr" -o "$out" -w '%{{http_code}}' -X "$method" -H "Authorization: Bearer $GH_TOKEN" -H 'Content-Type: application/json' --data-binary @- "{base}/$endpoint")
+else
+    code=$(curl -s -D "$hdr" -o "$out" -w '%{{http_code}}' -X "$method" -H "Authorization: Bearer $GH_TOKEN" "{base}/$endpoint")
+fi
+cat "$hdr" "$out"
+if [ "$code" -ge 400 ]; then
+    echo "gh: $(sed -n 's/.*"message":"\([^"]*\)".*/\1/p' "$out") (HTTP $code)" >&2
+    exit 1
+fi
+"#, base = fixture.uri())).unwrap();
+        std::fs::set_permissions(&gh, std::fs::Permissions::from_mode(0o755)).unwrap();
+        
+        let client = GitHubClient::new_gh(TOKEN.to_string(), None).with_gh_binary(gh.to_str().unwrap());
+        assert!(!client.is_native());
+        exercise(client);
+    }
+}
+
@@ -0,0 +1,333 @@
+// src/ui/dashboard.rs - Auto-refreshing status dashboard
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use crossterm::event::{self, Event as TermEvent, KeyCode, KeyEventKind};
+use crossterm::terminal;
+use std::io::{self, Write};
+use std::time::Duration;
+use crate::app::AppContext;
+use crate::core::billing::BillingMonitor;
+use crate::core::settings;
+use crate::core::state::{ForkChainNode, ForkStatus, StateManager};
+use crate::monitor::events::{Event, EventLog};
+use crate::monitor::metrics::{self, AccountLatency};
+use crate::ui::{display, input};
+
+const RECENT_EVENTS: usize = 5;
+
+/// Everything a single dashboard frame needs, read from the cache files only.
+#[derive(Debug, Clone)]
+pub struct DashboardSnapshot {
+    pub generated_at: DateTime<Utc>,
+    pub active_fork: Option<ForkChainNode>,
+    pub healthy: usize,
+    pub warning: usize,
+    pub exhausted: usize,
+    pub recent_events: Vec<Event>,
+    /// GitHub API latency per account over the last hour
+    pub api_latency: Vec<AccountLatency>,
+    pub next_rotation: Option<DateTime<Utc>>,
+    pub critical_threshold: f32,
+    pub max_core_hours: f32,
+}
+
+pub fn collect_snapshot(state_mgr: &StateManager) -> Result<DashboardSnapshot> {
+    let state = state_mgr.load_state()?;
+    let billing_monitor = BillingMonitor::from_config(&state_mgr.paths().thresholds_file())?;
+    
+    let mut healthy = 0;
+    let mut warning = 0;
+    let mut exhausted = 0;
+    
+    for node in &state.fork_chain {
+        match node.status {
+            ForkStatus::Source | ForkStatus::Disabled | ForkStatus::Deleted => continue,
+            ForkStatus::Exhausted => exhausted += 1,
+            ForkStatus::Active => {
+                if node.billing_used >= billing_monitor.critical_threshold() {
+                    exhausted += 1;
+                } else if node.billing_used >= billing_monitor.warning_threshold() {
+                    warning += 1;
+                } else {
+                    healthy += 1;
+                }
+            }
+        }
+    }
+    
+    let active_fork = state_mgr.get_active_fork(&state).cloned();
+    let now = Utc::now();
+    let next_rotation = active_fork
+        .as_ref()
+        .and_then(|node| estimate_rotation(node, billing_monitor.critical_threshold(), now));
+    
+    let recent_events = EventLog::new(state_mgr.paths())
+        .read_recent(RECENT_EVENTS)
+        .unwrap_or_default();
+    
+    let api_latency = recent_latency(state_mgr, now);
+    
+    Ok(DashboardSnapshot {
+        generated_at: now,
+        active_fork,
+        healthy,
+        warning,
+        exhausted,
+        recent_events,
+        api_latency,
+        next_rotation,
+        critical_threshold: billing_monitor.critical_threshold(),
+        max_core_hours: billing_monitor.max_core_hours(),
+    })
+}
+
+/// Latency from logs/api_metrics.jsonl, labelled with usernames where the
+/// token cache knows them.
+fn recent_latency(state_mgr: &StateManager, now: DateTime<Utc>) -> Vec<AccountLatency> {
+    let metrics_file = Path::new("logs").join(metrics::METRICS_FILE);
+    let samples = metrics::read_samples(&metrics_file, now - ChronoDuration::hours(1)).unwrap_or_default();
+    
+    let usernames: HashMap<String, String> = fs::read_to_string(state_mgr.paths().tokenmap())
+        .ok()
+        .and_then(|content| serde_json::from_str::<HashMap<String, String>>(&content).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(token, username)| (metrics::mask_token(&token), username))
+        .collect();
+    
+    let mut latency = metrics::latency_by_account(&samples);
+    for account in &mut latency {
+        if let Some(username) = usernames.get(&account.account) {
+            account.account = username.clone();
+        }
+    }
+    latency
+}
+
+/// Projects when the active fork crosses the critical threshold from its average burn rate.
+fn estimate_rotation(node: &ForkChainNode, critical: f32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let elapsed_hours = (node.last_updated - node.created_at).num_seconds() as f32 / 3600.0;
+    
+    if node.billing_used <= 0.0 || elapsed_hours <= 0.0 {
+        return None;
+    }
+    
+    let rate = node.billing_used / elapsed_hours;
+    let hours_left = ((critical - node.billing_used) / rate).max(0.0);
+    let eta = node.last_updated + ChronoDuration::seconds((hours_left * 3600.0) as i64);
+    
+    Some(eta.max(now))
+}
+
+fn age_label(updated: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let age = now - updated;
+    
+    if age > ChronoDuration::minutes(settings::get().monitor.stale_after_minutes) {
+        format!("(stale, {} old)", display::format_age(age))
+    } else {
+        format!("({} ago)", display::format_age(age))
+    }
+}
+
+pub fn render_frame(snapshot: &DashboardSnapshot, refresh_secs: u64) -> String {
+    let now = snapshot.generated_at;
+    let mut out = String::new();
+    
+    out.push_str("╔═══════════════════════════════════════════════════════╗\n");
+    out.push_str("║          NEXUS LIVE DASHBOARD                         ║\n");
+    out.push_str("╚═══════════════════════════════════════════════════════╝\n");
+    out.push_str(&format!(
+        "Updated {} | refresh {}s | [i] interval  [q/Esc] quit\n\n",
+        now.format("%Y-%m-%d %H:%M:%S UTC"),
+        refresh_secs
+    ));
+    
+    out.push_str("Active Fork\n");
+    match &snapshot.active_fork {
+        Some(node) => {
+            out.push_str(&format!("  🟢 {} | {}\n", node.owner_label(), node.repo));
+            
+            match &node.last_run {
+                Some(run) => {
+                    let state = match &run.conclusion {
+                        Some(conclusion) => format!("{} ({})", run.status, conclusion),
+                        None => run.status.clone(),
+                    };
+                    let elapsed = if run.status == "completed" {
+                        String::new()
+                    } else {
+                        format!(", elapsed {}", display::format_age(now - run.started_at))
+                    };
+                    out.push_str(&format!(
+                        "  Run: #{} {}{} {}\n",
+                        run.run_id,
+                        state,
+                        elapsed,
+                        age_label(run.checked_at, now)
+                    ));
+                }
+                None => out.push_str("  Run: no run recorded\n"),
+            }
+            
+            out.push_str(&format!(
+                "  Billing: {} {:.1}/{:.1} core-hours {}\n",
+                display::progress_bar(node.billing_used, snapshot.max_core_hours, 20),
+                node.billing_used,
+                snapshot.max_core_hours,
+                age_label(node.last_updated, now)
+            ));
+        }
+        None => out.push_str("  No active fork\n"),
+    }
+    
+    out.push_str("\nAccounts\n");
+    out.push_str(&format!(
+        "  🟢 Healthy: {}   🟡 Warning: {}   🔴 Exhausted: {}\n",
+        snapshot.healthy, snapshot.warning, snapshot.exhausted
+    ));
+    
+    out.push_str("\nNext Rotation\n");
+    match snapshot.next_rotation {
+        Some(eta) => out.push_str(&format!(
+            "  ~{} (estimated, at {:.1} core-hours)\n",
+            eta.format("%Y-%m-%d %H:%M UTC"),
+            snapshot.critical_threshold
+        )),
+        None => out.push_str("  Not enough data to estimate\n"),
+    }
+    
+    out.push_str("\nAPI Latency (last hour)\n");
+    if snapshot.api_latency.is_empty() {
+        out.push_str("  No API calls recorded\n");
+    }
+    for latency in &snapshot.api_latency {
+        out.push_str(&format!(
+            "  {:<20} p50 {:>6}ms  p95 {:>6}ms  {} calls, {} failed\n",
+            latency.account, latency.p50_ms, latency.p95_ms, latency.calls, latency.failed
+        ));
+    }
+    
+    out.push_str("\nRecent Events\n");
+    if snapshot.recent_events.is_empty() {
+        out.push_str("  No events recorded\n");
+    }
+    for event in snapshot.recent_events.iter().rev() {
+        out.push_str(&format!(
+            "  [{}] {:<10} {}\n",
+            event.timestamp.format("%m-%d %H:%M"),
+            event.kind,
+            event.message
+        ));
+    }
+    
+    out
+}
+
+pub fn run_dashboard(ctx: &AppContext, refresh_secs: u64) -> Result<()> {
+    terminal::enable_raw_mode()?;
+    let result = dashboard_loop(&ctx.state(), refresh_secs.max(1));
+    terminal::disable_raw_mode().ok();
+    println!();
+    result
+}
+
+fn dashboard_loop(state_mgr: &StateManager, mut refresh_secs: u64) -> Result<()> {
+    loop {
+        let snapshot = collect_snapshot(state_mgr)?;
+        
+        display::clear_screen();
+        // Raw mode does not translate \n into \r\n
+        print!("{}", render_frame(&snapshot, refresh_secs).replace('\n', "\r\n"));
+        io::stdout().flush()?;
+        
+        if !event::poll(Duration::from_secs(refresh_secs))? {
+            continue;
+        }
+        
+        if let TermEvent::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('i') => {
+                    // Refresh is paused while the prompt is open
+                    terminal::disable_raw_mode()?;
+                    let value = input::read_input("\nRefresh interval (seconds): ");
+                    terminal::enable_raw_mode()?;
+                    
+                    match value.parse::<u64>() {
+                        Ok(secs) if secs > 0 => refresh_secs = secs,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::account::OwnerType;
+    use crate::core::state::RunSnapshot;
+    
+    fn synthetic_snapshot() -> DashboardSnapshot {
+        let now = Utc::now();
+        
+        DashboardSnapshot {
+            generated_at: now,
+            active_fork: Some(ForkChainNode {
+                pat_index: 2,
+                username: "runner-three".to_string(),
+                owner_type: OwnerType::User,
+                repo: "runner-three/nexus-runner".to_string(),
+                parent: Some("runner-two/nexus-runner".to_string()),
+                billing_used: 60.0,
+                status: ForkStatus::Active,
+                created_at: now - ChronoDuration::hours(30),
+                last_updated: now - ChronoDuration::minutes(45),
+                last_run: Some(RunSnapshot {
+                    run_id: 4242,
+                    status: "in_progress".to_string(),
+                    conclusion: None,
+                    started_at: now - ChronoDuration::minutes(90),
+                    checked_at: now - ChronoDuration::minutes(2),
+                }),
+            }),
+            healthy: 3,
+            warning: 1,
+            exhausted: 2,
+            recent_events: vec![Event::new("rotation", "Rotated to account index 2", None)],
+            api_latency: vec![AccountLatency {
+                account: "runner-three".to_string(),
+                calls: 40,
+                failed: 1,
+                p50_ms: 420,
+                p95_ms: 3100,
+            }],
+            next_rotation: None,
+            critical_threshold: 119.5,
+            max_core_hours: 120.0,
+        }
+    }
+    
+    #[test]
+    fn test_render_frame_from_snapshot() {
+        let frame = render_frame(&synthetic_snapshot(), 5);
+        
+        assert!(frame.contains("runner-three/nexus-runner"));
+        assert!(frame.contains("#4242 in_progress, elapsed 1h 30m (2m ago)"));
+        assert!(frame.contains("60.0/120.0 core-hours (stale, 45m old)"));
+        assert!(frame.contains("Healthy: 3   🟡 Warning: 1   🔴 Exhausted: 2"));
+        assert!(frame.contains("Rotated to account index 2"));
+        assert!(frame.contains("Not enough data to estimate"));
+        assert!(frame.contains("runner-three         p50    420ms  p95   3100ms  40 calls, 1 failed"));
+    }
+}
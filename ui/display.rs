@@ -1,6 +1,14 @@
 // src/ui/display.rs - Display utilities
 
 use colored::*;
+use crate::core::settings;
+use crate::utils::pool::Progress;
+use std::sync::Mutex;
+
+/// Emoji marker, or its ASCII stand-in when `[ui] ascii = true`.
+pub fn marker(emoji: &'static str, ascii: &'static str) -> &'static str {
+    if settings::get().ui.ascii { ascii } else { emoji }
+}
 
 pub fn print_banner() {
     println!("\n{}", "╔═══════════════════════════════════════════════════════════╗".cyan());
@@ -15,19 +23,19 @@ pub fn print_section(title: &str) {
 }
 
 pub fn print_success(message: &str) {
-    println!("{} {}", "✅".green(), message);
+    println!("{} {}", marker("✅", "[OK]").green(), message);
 }
 
 pub fn print_error(message: &str) {
-    eprintln!("{} {}", "❌".red(), message.red());
+    eprintln!("{} {}", marker("❌", "[ERROR]").red(), message.red());
 }
 
 pub fn print_warning(message: &str) {
-    println!("{} {}", "⚠️ ".yellow(), message.yellow());
+    println!("{} {}", marker("⚠️ ", "[WARN]").yellow(), message.yellow());
 }
 
 pub fn print_info(message: &str) {
-    println!("{} {}", "ℹ️ ".cyan(), message);
+    println!("{} {}", marker("ℹ️ ", "[INFO]").cyan(), message);
 }
 
 pub fn print_menu_item(number: usize, label: &str) {
@@ -42,8 +50,100 @@ pub fn print_separator() {
     println!("{}", "─────────────────────────────────────────────────────────".bright_black());
 }
 
+pub fn progress_bar(value: f32, max: f32, width: usize) -> String {
+    let ratio = if max > 0.0 { (value / max).clamp(0.0, 1.0) } else { 0.0 };
+    let filled = (ratio * width as f32).round() as usize;
+    
+    format!(
+        "[{}{}] {:>5.1}%",
+        marker("█", "#").repeat(filled),
+        marker("░", "-").repeat(width - filled),
+        ratio * 100.0
+    )
+}
+
+/// A sweep's progress, redrawn in place on one terminal line.
+pub struct ProgressLine {
+    label: String,
+    /// total, finished, failed
+    counts: Mutex<(usize, usize, usize)>,
+}
+
+impl ProgressLine {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            counts: Mutex::new((0, 0, 0)),
+        }
+    }
+    
+    fn draw(&self, (total, finished, failed): (usize, usize, usize)) {
+        use std::io::{self, Write};
+        
+        let failures = if failed > 0 {
+            format!(", {} failed", failed).red().to_string()
+        } else {
+            String::new()
+        };
+        
+        print!(
+            "\r  {} {} {}/{}{}",
+            self.label,
+            progress_bar(finished as f32, total as f32, 20),
+            finished,
+            total,
+            failures
+        );
+        io::stdout().flush().ok();
+    }
+}
+
+impl Progress for ProgressLine {
+    fn start(&self, total: usize) {
+        let mut counts = self.counts.lock().unwrap();
+        *counts = (total, 0, 0);
+        self.draw(*counts);
+    }
+    
+    fn advance(&self, ok: bool) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.1 += 1;
+        if !ok {
+            counts.2 += 1;
+        }
+        self.draw(*counts);
+    }
+    
+    fn finish(&self) {
+        println!();
+    }
+}
+
+pub fn format_age(age: chrono::Duration) -> String {
+    let secs = age.num_seconds().max(0);
+    
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    } else {
+        format!("{}d {}h", secs / 86400, (secs % 86400) / 3600)
+    }
+}
+
 pub fn clear_screen() {
+    use std::io::{self, Write};
+    
+    // Legacy Windows consoles print the escape codes literally
+    if cfg!(windows) && !crate::utils::platform::ansi_console() {
+        let _ = std::process::Command::new("cmd").args(["/C", "cls"]).status();
+        return;
+    }
+    
     print!("\x1B[2J\x1B[1;1H");
+    io::stdout().flush().ok();
 }
 
 pub fn pause() {
@@ -43,6 +43,20 @@ pub fn read_yes_no(prompt: &str) -> bool {
     }
 }
 
+pub fn confirm(prompt: &str, assume_yes: bool) -> bool {
+    assume_yes || read_yes_no(prompt)
+}
+
+/// Destructive actions: the user must type `expected` (e.g. the repo name) exactly.
+pub fn confirm_typed(prompt: &str, expected: &str, assume_yes: bool) -> bool {
+    if assume_yes {
+        return true;
+    }
+    
+    let input = read_input(&format!("{}\nType '{}' to confirm: ", prompt, expected));
+    input == expected
+}
+
 pub fn read_selection(prompt: &str, options: &[&str]) -> Result<usize> {
     println!("\n{}", prompt);
     
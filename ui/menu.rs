@@ -1,11 +1,21 @@
 // src/ui/menu.rs - Main menu system (mirip Nexus lama)
 
 use anyhow::Result;
+use crate::app::AppContext;
+use crate::core::billing::BillingThresholds;
+use crate::core::token_report;
 use crate::ui::{display, input};
-use std::path::PathBuf;
+use crate::github::fork::CleanupFilter;
 use crate::orchestration::{Deployer, Rotator};
+use crate::orchestration::forks::ForkCommand;
+use crate::orchestration::sync::StateSync;
+use crate::orchestration::tokens::TokenCommand;
+use crate::orchestration::trigger::{self, TriggerOptions, TriggerOutcome};
+use crate::utils::pool::WorkerPool;
+use crate::utils::platform;
+use std::sync::Arc;
 
-pub fn run_menu() -> Result<()> {
+pub fn run_menu(ctx: &Arc<AppContext>, assume_yes: bool) -> Result<()> {
     loop {
         display::clear_screen();
         display::print_banner();
@@ -27,11 +37,11 @@ pub fn run_menu() -> Result<()> {
                 display::print_info("Goodbye!");
                 return Ok(());
             }
-            1 => menu_setup(),
-            2 => menu_deployment(),
-            3 => menu_monitoring(),
-            4 => menu_operations(),
-            5 => menu_advanced(),
+            1 => menu_setup(ctx),
+            2 => menu_deployment(ctx),
+            3 => menu_monitoring(ctx),
+            4 => menu_operations(ctx, assume_yes),
+            5 => menu_advanced(ctx, assume_yes),
             _ => Ok(()),
         };
         
@@ -42,7 +52,7 @@ pub fn run_menu() -> Result<()> {
     }
 }
 
-fn menu_setup() -> Result<()> {
+fn menu_setup(ctx: &Arc<AppContext>) -> Result<()> {
     loop {
         display::clear_screen();
         display::print_section("SETUP & CONFIGURATION");
@@ -67,8 +77,7 @@ fn menu_setup() -> Result<()> {
                 display::pause();
             }
             2 => {
-                // Import tokens
-                display::print_info("Feature: Import Tokens");
+                import_tokens(ctx);
                 display::pause();
             }
             3 => {
@@ -83,14 +92,92 @@ fn menu_setup() -> Result<()> {
             }
             5 => {
                 // Validate all
-                validate_all_command()?;
+                validate_all_command(ctx)?;
+                display::pause();
             }
             _ => {}
         }
     }
 }
 
-fn menu_deployment() -> Result<()> {
+/// Adds or removes one token; see `TokenCommand`.
+fn import_tokens(ctx: &Arc<AppContext>) {
+    display::print_submenu_item(1, "Add Token");
+    display::print_submenu_item(2, "Remove Token");
+    display::print_submenu_item(3, "Enable/Disable Token");
+    display::print_submenu_item(4, "Set Rotation Priority");
+    display::print_submenu_item(5, "Label Token");
+    display::print_submenu_item(6, "Import Several Tokens (paste or file)");
+    println!();
+    display::print_submenu_item(0, "← Back");
+    
+    let command = TokenCommand::new(ctx.clone());
+    let result = match input::read_number("\nSelect: ", 0, 6) {
+        Ok(1) => {
+            let line = input::read_input("Token (optionally followed by its organization): ");
+            command.add(&line).map(|added| display::print_success(&added.to_string()))
+        }
+        Ok(2) => {
+            let account = input::read_input("Token index or username: ");
+            let force = input::read_yes_no("Remove it even if it owns the active fork?");
+            command.remove(&account, force).map(|removed| removed.print())
+        }
+        Ok(3) => command.list().and_then(|accounts| {
+            for account in &accounts {
+                println!(
+                    "  #{} @{}{}{}",
+                    account.index,
+                    account.username,
+                    match (account.enabled, account.quarantined_at) {
+                        (_, Some(_)) => " (quarantined: token rejected)",
+                        (true, None) => "",
+                        (false, None) => " (disabled)",
+                    },
+                    if account.stale { " (needs revalidation)" } else { "" }
+                );
+            }
+            let account = command.toggle(&input::read_input("Token index or username to toggle: "))?;
+            display::print_success(&format!("@{} {}", account.username, if account.enabled { "enabled" } else { "disabled" }));
+            Ok(())
+        }),
+        Ok(4) => command.list().and_then(|accounts| {
+            for account in &accounts {
+                println!("  #{} @{} priority {}", account.index, account.username, account.priority);
+            }
+            let which = input::read_input("Token index or username: ");
+            let priority = input::read_number("Priority (higher rotates in first): ", 0, u8::MAX as usize)?;
+            let account = command.set_priority(&which, priority as u8)?;
+            display::print_success(&format!("@{} now has rotation priority {}", account.username, account.priority));
+            Ok(())
+        }),
+        Ok(5) => command.list().and_then(|accounts| {
+            for account in &accounts {
+                println!("  #{} {}", account.index, account.display_name());
+            }
+            let which = input::read_input("Token index or username: ");
+            let label = input::read_input("Label (empty to clear): ");
+            let notes = input::read_input("Notes (empty to keep, '-' to clear): ");
+            let notes = match notes.trim() {
+                "" => None,
+                "-" => Some(""),
+                notes => Some(notes),
+            };
+            let account = command.set_label(&which, &label, notes)?;
+            display::print_success(&format!("Token #{} is {}", account.index, account.display_name()));
+            Ok(())
+        }),
+        Ok(6) => import_tokens_wizard(&command),
+        _ => return,
+    };
+    
+    match result {
+        // Accounts already loaded in this session keep the old list
+        Ok(()) => display::print_info("Restart the menu for other actions to use the new token list"),
+        Err(e) => display::print_error(&format!("{:#}", e)),
+    }
+}
+
+fn menu_deployment(ctx: &Arc<AppContext>) -> Result<()> {
     loop {
         display::clear_screen();
         display::print_section("DEPLOYMENT");
@@ -109,15 +196,23 @@ fn menu_deployment() -> Result<()> {
         match choice {
             0 => return Ok(()),
             1 => {
-                let deployer = Deployer::new(PathBuf::from("config"));
+                let deployer = Deployer::new(ctx.clone()).with_workflows(select_workflows()?);
                 deployer.deploy_main_workflow()?;
                 display::print_success("Workflow deployed");
                 display::pause();
             }
             3 => {
-                let deployer = Deployer::new(PathBuf::from("config"));
-                deployer.set_all_secrets()?;
-                display::print_success("Secrets set");
+                let report = Deployer::new(ctx.clone()).set_all_secrets()?;
+                report.print();
+                if report.failed() == 0 {
+                    display::print_success("Secrets set");
+                } else {
+                    display::print_warning(&format!("{} forks failed; deploy again to retry them", report.failed()));
+                }
+                display::pause();
+            }
+            4 => {
+                trigger_workflow(ctx)?;
                 display::pause();
             }
             _ => {
@@ -127,7 +222,61 @@ fn menu_deployment() -> Result<()> {
         }
     }
 }
-fn menu_monitoring() -> Result<()> {
+
+/// Dispatches the workflow on the active fork with workflow_dispatch
+/// inputs read one `key=value` per line.
+fn trigger_workflow(ctx: &Arc<AppContext>) -> Result<()> {
+    let mut inputs = Vec::new();
+    loop {
+        let line = input::read_input("Input as key=value (empty to dispatch): ");
+        if line.is_empty() {
+            break;
+        }
+        inputs.push(line);
+    }
+    // Catch a typo before anything is sent
+    trigger::parse_inputs(&inputs)?;
+    
+    let options = TriggerOptions {
+        inputs,
+        watch: input::read_yes_no("Wait for the run to finish?"),
+        ..Default::default()
+    };
+    let outcome = crate::orchestration::Trigger::new(ctx.clone()).run(&options)?;
+    
+    match outcome {
+        TriggerOutcome::Completed { conclusion, healthy: false, .. } => {
+            display::print_warning(&format!("Run finished: {}", conclusion));
+        }
+        TriggerOutcome::TimedOut { run_id } => {
+            display::print_warning(&format!("Stopped waiting for run {}", run_id));
+        }
+        TriggerOutcome::Queued { run_id } => {
+            display::print_warning(&format!("Run {} never left the queue", run_id));
+        }
+        _ => display::print_success("Workflow triggered"),
+    }
+    
+    Ok(())
+}
+
+/// Asks which managed workflow to deploy when there are several; empty
+/// for all of them.
+fn select_workflows() -> Result<Vec<String>> {
+    let names = crate::github::workflow::managed_workflow_names();
+    if names.len() < 2 {
+        return Ok(Vec::new());
+    }
+    
+    let mut options = vec!["All managed workflows"];
+    options.extend(names.iter().map(|name| name.as_str()));
+    match input::read_selection("Which workflow?", &options)? {
+        0 => Ok(Vec::new()),
+        choice => Ok(vec![names[choice - 1].clone()]),
+    }
+}
+
+fn menu_monitoring(ctx: &Arc<AppContext>) -> Result<()> {
     loop {
         display::clear_screen();
         display::print_section("MONITORING");
@@ -136,21 +285,41 @@ fn menu_monitoring() -> Result<()> {
         display::print_submenu_item(2, "Show Workflow Status");
         display::print_submenu_item(3, "Show Fork Chain");
         display::print_submenu_item(4, "View Logs");
+        display::print_submenu_item(5, "Live Dashboard");
+        display::print_submenu_item(6, "Verify Fork Chain");
         println!();
         display::print_submenu_item(0, "← Back");
         
         display::print_separator();
         
-        let choice = input::read_number("\nSelect: ", 0, 4)?;
+        let choice = input::read_number("\nSelect: ", 0, 6)?;
         
         match choice {
             0 => return Ok(()),
+            6 => {
+                display::print_info("Checking every fork on GitHub...");
+                crate::orchestration::sync::StateSync::new(ctx.clone()).verify()?.print();
+                display::pause();
+            }
+            5 => {
+                crate::ui::dashboard::run_dashboard(
+                    ctx,
+                    crate::core::settings::get().monitor.dashboard_refresh_secs,
+                )?;
+            }
             1 => {
-                crate::monitor::health::show_billing_all()?;
+                crate::monitor::health::show_billing_all(
+                    ctx,
+                    crate::monitor::health::BillingOutput::Table,
+                )?;
+                display::pause();
+            }
+            2 => {
+                show_workflow_status(ctx)?;
                 display::pause();
             }
             3 => {
-                crate::core::state::show_status()?;
+                crate::core::state::show_status(ctx, false, false)?;
                 display::pause();
             }
             _ => {
@@ -161,7 +330,45 @@ fn menu_monitoring() -> Result<()> {
     }
 }
 
-fn menu_operations() -> Result<()> {
+/// Runs listed per active fork in "Show Workflow Status".
+const RECENT_RUNS: u32 = 10;
+
+/// Recent runs of every active fork; offers to pull the logs of a fork
+/// whose newest run failed.
+fn show_workflow_status(ctx: &Arc<AppContext>) -> Result<()> {
+    let trigger = crate::orchestration::Trigger::new(ctx.clone());
+    let forks = trigger.recent_runs(RECENT_RUNS)?;
+    
+    if forks.is_empty() {
+        display::print_info("No active forks");
+        return Ok(());
+    }
+    
+    let mut failed = Vec::new();
+    for (repo, runs) in &forks {
+        match runs {
+            Ok(runs) => {
+                crate::orchestration::trigger::print_runs(repo, runs);
+                if runs.first().and_then(|run| run.conclusion.as_deref()) == Some("failure") {
+                    failed.push(repo.as_str());
+                }
+            }
+            Err(e) => display::print_warning(&format!("{}: cannot list runs: {}", repo, e)),
+        }
+        println!();
+    }
+    
+    for repo in failed {
+        if input::read_yes_no(&format!("Download the logs of the failed run in {}?", repo)) {
+            let (repo, logs) = trigger.fetch_failure_logs(Some(repo), std::path::Path::new("logs"))?;
+            crate::orchestration::trigger::print_run_logs(&repo, logs.as_ref());
+        }
+    }
+    
+    Ok(())
+}
+
+fn menu_operations(ctx: &Arc<AppContext>, assume_yes: bool) -> Result<()> {
     loop {
         display::clear_screen();
         display::print_section("OPERATIONS");
@@ -170,19 +377,55 @@ fn menu_operations() -> Result<()> {
         display::print_submenu_item(2, "Manual Fork Creation");
         display::print_submenu_item(3, "Disable All Workflows");
         display::print_submenu_item(4, "Cleanup Exhausted Forks");
+        display::print_submenu_item(5, "Sync All Active Forks");
+        display::print_submenu_item(6, "Scrub Nexus Secrets From All Forks");
         println!();
         display::print_submenu_item(0, "← Back");
         
         display::print_separator();
         
-        let choice = input::read_number("\nSelect: ", 0, 4)?;
+        let choice = input::read_number("\nSelect: ", 0, 6)?;
         
         match choice {
             0 => return Ok(()),
             4 => {
-                if input::read_yes_no("Delete all exhausted forks?") {
-                    crate::github::fork::cleanup_exhausted_forks()?;
-                    display::print_success("Cleanup complete");
+                display::print_info("Checking which exhausted forks would be deleted...");
+                let plan = crate::github::fork::cleanup_exhausted_forks(ctx, &CleanupFilter::default(), true)?;
+                
+                if plan.matched() == 0 {
+                    display::print_success("No exhausted forks to clean up");
+                } else {
+                    plan.print();
+                    if input::confirm("Delete all exhausted forks?", assume_yes) {
+                        let summary = crate::github::fork::cleanup_exhausted_forks(ctx, &CleanupFilter::default(), false)?;
+                        if summary.failed() == 0 {
+                            display::print_success("Cleanup complete");
+                        } else {
+                            display::print_warning(&format!(
+                                "Deleted {} of {} exhausted forks",
+                                summary.deleted(), summary.matched()
+                            ));
+                        }
+                    }
+                }
+                display::pause();
+            }
+            5 => {
+                display::print_info("Merging upstream into every active fork...");
+                for result in ForkCommand::new(ctx.clone()).sync(None, true, None)? {
+                    result.print();
+                }
+                display::pause();
+            }
+            6 => {
+                if input::confirm("Delete the Nexus secrets from every fork in the chain, exhausted and disabled ones included?", assume_yes) {
+                    let report = Deployer::new(ctx.clone()).scrub_secrets()?;
+                    report.print();
+                    if report.failed() == 0 {
+                        display::print_success("Secrets scrubbed");
+                    } else {
+                        display::print_warning(&format!("{} forks could not be scrubbed", report.failed()));
+                    }
                 }
                 display::pause();
             }
@@ -194,7 +437,7 @@ fn menu_operations() -> Result<()> {
     }
 }
 
-fn menu_advanced() -> Result<()> {
+fn menu_advanced(ctx: &Arc<AppContext>, assume_yes: bool) -> Result<()> {
     loop {
         display::clear_screen();
         display::print_section("ADVANCED");
@@ -203,17 +446,29 @@ fn menu_advanced() -> Result<()> {
         display::print_submenu_item(2, "Test Proxy Connections");
         display::print_submenu_item(3, "Export Reports");
         display::print_submenu_item(4, "Reset All Cache");
+        display::print_submenu_item(5, "Rebuild State From GitHub");
         println!();
         display::print_submenu_item(0, "← Back");
         
         display::print_separator();
         
-        let choice = input::read_number("\nSelect: ", 0, 4)?;
+        let choice = input::read_number("\nSelect: ", 0, 5)?;
         
         match choice {
             0 => return Ok(()),
+            1 => {
+                edit_thresholds(ctx)?;
+                display::pause();
+            }
             2 => {
-                test_proxies_command()?;
+                test_proxies_command(ctx)?;
+            }
+            5 => {
+                if input::confirm("Replace the fork chain with what GitHub shows? The current state is backed up first.", assume_yes) {
+                    let state = StateSync::new(ctx.clone()).rebuild(None)?;
+                    display::print_success(&format!("Rebuilt fork chain: {} forks", state.fork_chain.len()));
+                }
+                display::pause();
             }
             _ => {
                 display::print_info("Feature under development");
@@ -223,36 +478,112 @@ fn menu_advanced() -> Result<()> {
     }
 }
 
-fn validate_all_command() -> Result<()> {
-    use crate::core::{account::AccountManager, proxy::ProxyManager};
-    use crate::nexus::config::NexusConfig;
+/// Prompts for each billing threshold, blank keeping the current value,
+/// and writes thresholds.json when the new set is valid.
+fn edit_thresholds(ctx: &Arc<AppContext>) -> Result<()> {
+    let path = ctx.paths().thresholds_file();
+    let current = BillingThresholds::load(&path)?;
     
-    let config_dir = PathBuf::from("config");
-    let cache_dir = config_dir.join("cache");
+    let read = |prompt: &str, value: f32| -> f32 {
+        loop {
+            let line = input::read_input(&format!("{} [{}]: ", prompt, value));
+            if line.is_empty() {
+                return value;
+            }
+            match line.parse::<f32>() {
+                Ok(value) => return value,
+                Err(_) => display::print_error(&format!("'{}' is not a number", line)),
+            }
+        }
+    };
+    let thresholds = BillingThresholds {
+        warning: read("Warning at core-hours used", current.warning),
+        critical: read("Critical (rotate) at core-hours used", current.critical),
+        max_core_hours: read("Core-hours in the monthly quota", current.max_core_hours),
+        included_minutes: read("Actions minutes included per month", current.included_minutes),
+    };
     
-    display::print_info("Validating tokens...");
+    let errors = thresholds.validate();
+    if !errors.is_empty() {
+        for error in &errors {
+            display::print_error(error);
+        }
+        display::print_info(&format!("{} left unchanged", path.display()));
+    } else if thresholds == current {
+        display::print_info("No change");
+    } else {
+        thresholds.save(&path)?;
+        display::print_success(&format!("Saved {}", path.display()));
+        display::print_info("Rotation uses them from its next check; billing views after a restart");
+    }
+    Ok(())
+}
+
+/// Checks tokens as they are pasted, or read from a file, and writes the
+/// accepted ones to tokens.txt once the user confirms.
+fn import_tokens_wizard(command: &TokenCommand) -> Result<()> {
+    let source = input::read_input("Path to a file with one token per line (blank to paste them): ");
+    let use_proxies = input::read_yes_no("Check each token through the next free proxy in proxies.txt?");
+    let mut import = command.import(use_proxies)?;
     
-    let mut account_mgr = AccountManager::new(&cache_dir);
-    account_mgr.load_tokens(&config_dir.join("tokens.txt"))?;
+    let mut check = |line: &str| match import.check(line) {
+        Ok(added) => println!("  ✅ {}", added),
+        Err(e) => println!(
+            "  ❌ {}…: {:#}",
+            line.chars().take(token_report::TOKEN_PREFIX_LEN).collect::<String>(),
+            e
+        ),
+    };
+    if source.is_empty() {
+        println!("Paste tokens one per line, optionally followed by their organization; an empty line ends the list:");
+        loop {
+            let line = input::read_input("");
+            if line.is_empty() {
+                break;
+            }
+            check(&line);
+        }
+    } else {
+        let content = std::fs::read_to_string(&source)?;
+        for (_, line) in platform::config_lines(&content) {
+            check(line);
+        }
+    }
     
-    let mut proxy_mgr = ProxyManager::new(&cache_dir);
+    match import.accepted() {
+        0 => display::print_info("No token accepted; tokens.txt left unchanged"),
+        accepted if input::read_yes_no(&format!("Add {} tokens to tokens.txt?", accepted)) => {
+            let added = import.finish()?;
+            display::print_success(&format!("{} tokens added", added.len()));
+        }
+        _ => display::print_info("tokens.txt left unchanged"),
+    }
+    Ok(())
+}
+
+pub fn validate_all_command(ctx: &Arc<AppContext>) -> Result<()> {
+    use crate::nexus::config::NexusConfig;
     
-    let tokens: Vec<String> = std::fs::read_to_string(config_dir.join("tokens.txt"))?
-        .lines()
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect();
+    display::print_info("Validating tokens...");
     
-    proxy_mgr.load_from_file(&config_dir.join("proxies.txt"), &tokens)?;
-    account_mgr.validate_all(&proxy_mgr)?;
+    // Validation drops invalid tokens from its own copy only
+    let mut account_mgr = (*ctx.accounts()?).clone();
+    let report = account_mgr.validate_all(&*ctx.proxies()?, &sweep_pool("Validating"))?;
     
     display::print_success("Tokens validated");
     
+    let output = input::read_input("Save a token report (file ending in .json or .csv, blank to skip): ");
+    if !output.is_empty() {
+        if let Some(path) = token_report::export_token_report(&report, &output)? {
+            display::print_success(&format!("Token report written to {}", path.display()));
+        }
+    }
+    
     display::print_info("Validating nodes and wallets...");
     
     let nexus_config = NexusConfig::load_from_files(
-        &config_dir.join("nodes.txt"),
-        &config_dir.join("wallets.txt")
+        &ctx.paths().nodes_file(),
+        &ctx.paths().wallets_file()
     )?;
     
     nexus_config.validate()?;
@@ -260,22 +591,13 @@ fn validate_all_command() -> Result<()> {
     display::print_success("Nodes and wallets validated");
     display::print_success(&format!("Total: {} nodes", nexus_config.total_nodes()));
     
-    display::pause();
     Ok(())
 }
 
-fn test_proxies_command() -> Result<()> {
-    use crate::core::proxy::ProxyManager;
-    
-    let config_dir = PathBuf::from("config");
-    let cache_dir = config_dir.join("cache");
-    
-    let mut proxy_mgr = ProxyManager::new(&cache_dir);
-proxy_mgr.load_cache()?;
-    
+fn test_proxies_command(ctx: &Arc<AppContext>) -> Result<()> {
     display::print_info("Testing all proxy connections...");
     
-    let failed = proxy_mgr.validate_all()?;
+    let failed = ctx.proxies()?.validate_all(&sweep_pool("Testing"))?;
     
     if failed.is_empty() {
         display::print_success("All proxies are working!");
@@ -286,3 +608,8 @@ proxy_mgr.load_cache()?;
     display::pause();
     Ok(())
 }
+
+/// The settings-sized pool with a progress line, for sweeps started from the menu.
+fn sweep_pool(label: &str) -> WorkerPool {
+    WorkerPool::from_settings().with_progress(Arc::new(display::ProgressLine::new(label)))
+}
@@ -3,5 +3,6 @@
 pub mod menu;
 pub mod display;
 pub mod input;
+pub mod dashboard;
 
 pub use menu::run_menu;